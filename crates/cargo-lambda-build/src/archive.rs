@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
     fmt::Debug,
     fs::{read, File, Metadata},
     io::{Read, Seek, Write},
@@ -7,6 +7,7 @@ use std::{
     time::SystemTime,
 };
 
+use base64::prelude::*;
 use cargo_lambda_metadata::cargo::{target_dir_from_metadata, CargoMetadata};
 use cargo_lambda_remote::aws_sdk_lambda::types::Architecture as CpuArchitecture;
 use chrono::{DateTime, Utc};
@@ -114,11 +115,21 @@ impl BinaryArchive {
 
     /// Calculate the SHA256 hash of the zip binary file
     pub fn sha256(&self) -> Result<String> {
+        let sha256 = format!("{:X}", self.digest()?);
+        Ok(sha256)
+    }
+
+    /// Calculate the SHA256 hash of the zip binary file encoded in base64,
+    /// like the `CodeSha256` that AWS Lambda reports for deployed functions
+    pub fn code_sha256(&self) -> Result<String> {
+        Ok(BASE64_STANDARD.encode(self.digest()?))
+    }
+
+    fn digest(&self) -> Result<sha2::digest::Output<Sha256>> {
         let data = self.read()?;
         let mut hasher = Sha256::new();
         hasher.update(data);
-        let sha256 = format!("{:X}", hasher.finalize());
-        Ok(sha256)
+        Ok(hasher.finalize())
     }
 
     /// List the files inside the zip archive
@@ -152,15 +163,7 @@ pub fn create_binary_archive<P>(
 where
     P: AsRef<Path>,
 {
-    let bootstrap_dir = if let Some(dir) = base_dir {
-        dir.as_ref().join(data.binary_location())
-    } else {
-        let target_dir = metadata
-            .and_then(|m| target_dir_from_metadata(m).ok())
-            .unwrap_or_else(|| PathBuf::from("target"));
-
-        target_dir.join("lambda").join(data.binary_location())
-    };
+    let bootstrap_dir = bootstrap_dir(metadata, base_dir, data);
 
     let binary_path = bootstrap_dir.join(data.binary_name());
     if !binary_path.exists() {
@@ -172,6 +175,77 @@ where
     zip_binary(binary_path, bootstrap_dir, data, include)
 }
 
+/// Search for the zip archive that `cargo lambda build --output-format zip` created for a function
+/// inside the target directory. The archive is not created again.
+pub fn find_binary_archive<P>(
+    metadata: Option<&CargoMetadata>,
+    base_dir: &Option<P>,
+    data: &BinaryData,
+) -> Result<BinaryArchive>
+where
+    P: AsRef<Path>,
+{
+    let bootstrap_dir = bootstrap_dir(metadata, base_dir, data);
+    open_binary_archive(bootstrap_dir.join(data.zip_name()), data)
+}
+
+/// Open a zip archive created by a previous build, and extract its architecture by reading the binary inside it.
+pub fn open_binary_archive(path: PathBuf, data: &BinaryData) -> Result<BinaryArchive> {
+    let file = File::open(&path)
+        .map_err(|_| BuildError::ArchiveMissing(path.clone(), data.build_help().into()))?;
+
+    let binary_modified_at = file.metadata().ok().and_then(|meta| meta.modified().ok());
+
+    let file_name = match data.parent_dir() {
+        Some(parent) => Path::new(parent).join(data.binary_name()),
+        None => PathBuf::from(data.binary_name()),
+    };
+    let zip_file_name = convert_to_unix_path(&file_name)
+        .ok_or_else(|| BuildError::InvalidUnixFileName(file_name.clone()))?;
+
+    let mut archive = ZipArchive::new(file)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to open zip file `{path:?}`"))?;
+    let mut binary = archive
+        .by_name(&zip_file_name)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("zip file `{path:?}` doesn't include `{zip_file_name}`"))?;
+
+    let mut binary_data = Vec::new();
+    binary
+        .read_to_end(&mut binary_data)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to read `{zip_file_name}` from zip file `{path:?}`"))?;
+
+    let arch = binary_architecture(&binary_data)?;
+
+    Ok(BinaryArchive::new(
+        path,
+        arch.to_string(),
+        BinaryModifiedAt(binary_modified_at),
+    ))
+}
+
+/// Directory where the build puts the binary, and the zip archive, of a function.
+fn bootstrap_dir<P>(
+    metadata: Option<&CargoMetadata>,
+    base_dir: &Option<P>,
+    data: &BinaryData,
+) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    if let Some(dir) = base_dir {
+        dir.as_ref().join(data.binary_location())
+    } else {
+        let target_dir = metadata
+            .and_then(|m| target_dir_from_metadata(m).ok())
+            .unwrap_or_else(|| PathBuf::from("target"));
+
+        target_dir.join("lambda").join(data.binary_location())
+    }
+}
+
 /// Create a zip file from a function binary.
 /// The binary inside the zip file is called `bootstrap` for function binaries.
 /// The binary inside the zip file is called by its name, and put inside the `extensions`
@@ -212,15 +286,7 @@ pub fn zip_binary<BP: AsRef<Path>, DD: AsRef<Path>>(
         .wrap_err_with(|| format!("failed to read binary file `{path:?}`"))?;
 
     let binary_data = &*binary_data;
-    let object = ObjectFile::parse(binary_data)
-        .into_diagnostic()
-        .wrap_err("the provided function file is not a valid Linux binary")?;
-
-    let arch = match object.architecture() {
-        Architecture::Aarch64 => "arm64",
-        Architecture::X86_64 => "x86_64",
-        other => return Err(BuildError::InvalidBinaryArchitecture(other).into()),
-    };
+    let arch = binary_architecture(binary_data)?;
 
     let mut zip = ZipWriter::new(zipped_binary);
     if let Some(files) = include {
@@ -261,6 +327,19 @@ pub fn zip_binary<BP: AsRef<Path>, DD: AsRef<Path>>(
     ))
 }
 
+/// Architecture of a Linux binary, as Lambda names it.
+fn binary_architecture(binary_data: &[u8]) -> Result<&'static str> {
+    let object = ObjectFile::parse(binary_data)
+        .into_diagnostic()
+        .wrap_err("the provided function file is not a valid Linux binary")?;
+
+    match object.architecture() {
+        Architecture::Aarch64 => Ok("arm64"),
+        Architecture::X86_64 => Ok("x86_64"),
+        other => Err(BuildError::InvalidBinaryArchitecture(other).into()),
+    }
+}
+
 fn zip_file_options(file: &File, path: &Path) -> Result<SimpleFileOptions> {
     let meta = file
         .metadata()
@@ -279,7 +358,8 @@ fn include_files_in_zip<W>(zip: &mut ZipWriter<W>, files: &Vec<String>) -> Resul
 where
     W: Write + Seek,
 {
-    let mut file_map = HashMap::with_capacity(files.len());
+    // Files are added in the same order every time, so the same inputs produce the same zip file.
+    let mut file_map = BTreeMap::new();
     for file in files {
         match file.split_once(':') {
             None => file_map.insert(file.clone(), file.clone()),
@@ -288,7 +368,11 @@ where
     }
 
     for (base, file) in file_map {
-        for entry in WalkDir::new(&file).into_iter().filter_map(|e| e.ok()) {
+        for entry in WalkDir::new(&file)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
             let path = entry.path();
             let base = base.clone();
             let file = file.clone();
@@ -475,6 +559,31 @@ mod test {
             .expect("failed to find bootstrap in zip archive");
     }
 
+    #[rstest]
+    #[case("binary-x86-64", "x86_64", false)]
+    #[case("binary-arm64", "arm64", true)]
+    fn test_open_binary_archive(#[case] name: &str, #[case] arch: &str, #[case] extension: bool) {
+        let data = BinaryData::new(name, extension, false);
+        let bp = &format!("../../tests/binaries/{name}");
+        let dd = TempDir::with_prefix("cargo-lambda-").expect("failed to create temp dir");
+        let zipped =
+            zip_binary(bp, dd.path(), &data, None).expect("failed to create binary archive");
+        let content = zipped.read().expect("failed to read zip file");
+
+        let archive =
+            open_binary_archive(zipped.path.clone(), &data).expect("failed to open binary archive");
+        assert_eq!(arch, archive.architecture);
+        assert_eq!(zipped.path, archive.path);
+        assert_eq!(content, archive.read().expect("failed to read zip file"));
+
+        let missing = dd.path().join("missing.zip");
+        let err = open_binary_archive(missing, &data).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BuildError>(),
+            Some(BuildError::ArchiveMissing(..))
+        ));
+    }
+
     #[rstest]
     #[case("binary-x86-64", "x86_64")]
     #[case("binary-arm64", "arm64")]
@@ -567,6 +676,26 @@ mod test {
         assert_eq!(archive1.sha256().unwrap(), archive2.sha256().unwrap());
     }
 
+    #[test]
+    fn test_code_sha256() {
+        let data = BinaryData::new("binary-x86-64", false, false);
+
+        let bp = "../../tests/binaries/binary-x86-64";
+        let dd = TempDir::with_prefix("cargo-lambda-").expect("failed to create temp dir");
+
+        let archive =
+            zip_binary(bp, dd.path(), &data, None).expect("failed to create binary archive");
+
+        let code_sha256 = BASE64_STANDARD
+            .decode(archive.code_sha256().unwrap())
+            .expect("invalid base64 hash");
+        let hex = code_sha256
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect::<String>();
+        assert_eq!(archive.sha256().unwrap(), hex);
+    }
+
     #[test]
     fn test_create_binary_archive_with_base_path() {
         let data = BinaryData::new("binary-x86-64", false, false);
@@ -10,33 +10,69 @@ pub(crate) enum BuildError {
     #[error(
         "invalid options: --arm64, --x86-64, and --target cannot be specified at the same time"
     )]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::build::invalid_target_options))]
     InvalidTargetOptions,
     #[error("invalid options: --compiler=cargo is only allowed on Linux")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::build::invalid_compiler_option))]
     InvalidCompilerOption,
     #[error("install Zig and run cargo-lambda again")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::build::zig_missing))]
     ZigMissing,
     #[error("binary target is missing from this project: {0}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::build::function_binary_missing))]
     FunctionBinaryMissing(String),
     #[error("binary file for {0} not found, use `cargo lambda {1}` to create it")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::build::binary_missing))]
     BinaryMissing(String, String),
+    #[error("zip file {0:?} not found, use `cargo lambda {1} --output-format zip` to create it")]
+    #[diagnostic(code(cargo_lambda::build::archive_missing))]
+    ArchiveMissing(PathBuf, String),
     #[error("invalid binary architecture: {0:?}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::build::invalid_binary_architecture))]
     InvalidBinaryArchitecture(Architecture),
     #[error("invalid or unsupported target for AWS Lambda: {0}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::build::unsupported_target))]
     UnsupportedTarget(String),
     #[error("invalid unix file name: {0}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::build::invalid_unix_file_name))]
     InvalidUnixFileName(PathBuf),
+    #[error("the toolchain doesn't match the lock file {0:?}: {1}")]
+    #[diagnostic(
+        code(cargo_lambda::build::toolchain_mismatch),
+        help("install the locked toolchain, or build without `--frozen-toolchain` to update the lock file")
+    )]
+    ToolchainMismatch(PathBuf, String),
+    #[error("the toolchain lock file {0:?} doesn't exist")]
+    #[diagnostic(
+        code(cargo_lambda::build::missing_toolchain_lock),
+        help("build without `--frozen-toolchain` to create the lock file")
+    )]
+    MissingToolchainLock(PathBuf),
+    #[error("invalid options: --sign only works with --output-format=zip")]
+    #[diagnostic(code(cargo_lambda::build::invalid_sign_option))]
+    InvalidSignOption,
+    #[error("failed to run `cosign`: {0}")]
+    #[diagnostic(
+        code(cargo_lambda::build::cosign_command),
+        help("install cosign to sign and verify zip files: https://docs.sigstore.dev/cosign/system_config/installation/")
+    )]
+    CosignCommand(std::io::Error),
+    #[error("failed to sign the zip file {0:?}")]
+    #[diagnostic(code(cargo_lambda::build::signing_failed))]
+    SigningFailed(PathBuf),
+    #[error("the zip file {0:?} is not signed, the attestation {1:?} doesn't exist")]
+    #[diagnostic(
+        code(cargo_lambda::build::missing_attestation),
+        help("build the function with `cargo lambda build --output-format zip --sign` to sign it")
+    )]
+    MissingAttestation(PathBuf, PathBuf),
+    #[error("the signature of the zip file {0:?} is not valid: {1}")]
+    #[diagnostic(code(cargo_lambda::build::invalid_signature))]
+    InvalidSignature(PathBuf, String),
     #[error(transparent)]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::build::failed_build_command))]
     FailedBuildCommand(#[from] std::io::Error),
     #[error(transparent)]
-    #[diagnostic()]
+    #[diagnostic(transparent)]
     MetadataError(#[from] MetadataError),
 }
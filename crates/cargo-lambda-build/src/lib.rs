@@ -20,7 +20,10 @@ use tracing::{debug, warn};
 pub use cargo_zigbuild::Zig;
 
 mod archive;
-pub use archive::{create_binary_archive, zip_binary, BinaryArchive, BinaryData, BinaryModifiedAt};
+pub use archive::{
+    create_binary_archive, find_binary_archive, open_binary_archive, zip_binary, BinaryArchive,
+    BinaryData, BinaryModifiedAt,
+};
 
 mod compiler;
 use compiler::{build_command, build_profile};
@@ -28,11 +31,17 @@ use compiler::{build_command, build_profile};
 mod error;
 use error::BuildError;
 
+mod lockfile;
+use lockfile::{read_lock, write_lock, zig_version, ToolchainLock, TOOLCHAIN_LOCK_FILE};
+
+mod signing;
+pub use signing::{attestation_path, sign_archive, verify_archive};
+
 mod target_arch;
 use target_arch::validate_linux_target;
 
 mod toolchain;
-use toolchain::rustup_cmd;
+pub use toolchain::rustup_cmd;
 
 mod zig;
 pub use zig::{
@@ -49,6 +58,10 @@ pub async fn run(build: &mut Build, metadata: &CargoMetadata) -> Result<()> {
         Err(BuildError::InvalidTargetOptions)?;
     }
 
+    if build.sign && *build.output_format() != OutputFormat::Zip {
+        Err(BuildError::InvalidSignOption)?;
+    }
+
     let target_arch = if build.arm64 {
         TargetArch::arm64()
     } else if build.x86_64 {
@@ -131,12 +144,35 @@ pub async fn run(build: &mut Build, metadata: &CargoMetadata) -> Result<()> {
         Err(err) => return Err(err),
     };
 
+    // Zig is installed by `build_command`, so its version is checked after that.
+    let zig = if compiler_option.is_cargo_zigbuild() {
+        zig_version().await
+    } else {
+        None
+    };
+    let toolchain = ToolchainLock::current(zig, target_arch.rustc_target_without_glibc_version());
+    let lock_path = metadata
+        .workspace_root
+        .join(TOOLCHAIN_LOCK_FILE)
+        .into_std_path_buf();
+    let locked = check_toolchain(&lock_path, &toolchain, build.frozen_toolchain)?;
+
     let mut child = cmd.spawn().map_err(BuildError::FailedBuildCommand)?;
     let status = child.wait().map_err(BuildError::FailedBuildCommand)?;
     if !status.success() {
         std::process::exit(status.code().unwrap_or(1));
     }
 
+    if !build.frozen_toolchain {
+        let updated = match &locked {
+            Some(locked) => locked.update(toolchain),
+            None => toolchain,
+        };
+        if locked.as_ref() != Some(&updated) {
+            write_lock(&lock_path, &updated)?;
+        }
+    }
+
     // extract resolved target dir from cargo metadata
     let target_dir = target_dir_from_metadata(metadata).unwrap_or_else(|_| PathBuf::from("target"));
     let target_dir = Path::new(&target_dir);
@@ -185,7 +221,11 @@ pub async fn run(build: &mut Build, metadata: &CargoMetadata) -> Result<()> {
                         })?;
                 }
                 OutputFormat::Zip => {
-                    zip_binary(binary, bootstrap_dir, &data, build.include.clone())?;
+                    let archive = zip_binary(binary, bootstrap_dir, &data, build.include.clone())?;
+                    if build.sign {
+                        let attestation = sign_archive(&archive.path).await?;
+                        debug!(?attestation, "zip file signed");
+                    }
                 }
             }
         }
@@ -197,6 +237,37 @@ pub async fn run(build: &mut Build, metadata: &CargoMetadata) -> Result<()> {
     Ok(())
 }
 
+/// Compare the current toolchain with the lock file, and return the locked toolchain.
+/// Differences are warnings, unless the toolchain is frozen.
+fn check_toolchain(
+    lock_path: &Path,
+    toolchain: &ToolchainLock,
+    frozen: bool,
+) -> Result<Option<ToolchainLock>> {
+    let Some(locked) = read_lock(lock_path)? else {
+        if frozen {
+            return Err(BuildError::MissingToolchainLock(lock_path.to_path_buf()).into());
+        }
+        return Ok(None);
+    };
+
+    let differences = locked.differences(toolchain);
+    if !differences.is_empty() {
+        if frozen {
+            let err =
+                BuildError::ToolchainMismatch(lock_path.to_path_buf(), differences.join(", "));
+            return Err(err.into());
+        }
+        warn!(
+            lock_file = ?lock_path,
+            ?differences,
+            "the toolchain doesn't match the lock file, the lock file will be updated after the build"
+        );
+    }
+
+    Ok(Some(locked))
+}
+
 fn downcasted_user_cancellation(err: &Report) -> bool {
     match err.root_cause().downcast_ref::<InquireError>() {
         Some(err) => is_user_cancellation_error(err),
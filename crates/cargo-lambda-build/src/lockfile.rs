@@ -0,0 +1,179 @@
+use cargo_lambda_interactive::command::new_command;
+use cargo_zigbuild::Zig;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// File in the workspace root that records the toolchain of the last successful build.
+pub(crate) const TOOLCHAIN_LOCK_FILE: &str = "cargo-lambda.lock";
+
+const LOCK_FILE_HEADER: &str = "# This file is generated by Cargo Lambda.\n# Commit it, so everyone builds the functions with the same toolchain.\n";
+
+/// Toolchain used to build the functions in a project.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub(crate) struct ToolchainLock {
+    /// Version of Cargo Lambda
+    cargo_lambda: String,
+    /// Version of Zig, only when the project is built with cargo-zigbuild
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    zig: Option<String>,
+    /// Target triples that the project is built for
+    #[serde(default)]
+    targets: Vec<String>,
+}
+
+impl ToolchainLock {
+    /// Toolchain in the current environment.
+    pub(crate) fn current(zig: Option<String>, target: &str) -> Self {
+        ToolchainLock {
+            cargo_lambda: env!("CARGO_PKG_VERSION").to_string(),
+            zig,
+            targets: vec![target.to_string()],
+        }
+    }
+
+    /// Differences between this locked toolchain, and the current one.
+    /// Zig is only compared when both toolchains use it.
+    pub(crate) fn differences(&self, current: &ToolchainLock) -> Vec<String> {
+        let mut differences = Vec::new();
+
+        if self.cargo_lambda != current.cargo_lambda {
+            differences.push(format!(
+                "Cargo Lambda {} is locked, but the current version is {}",
+                self.cargo_lambda, current.cargo_lambda
+            ));
+        }
+
+        if let (Some(locked), Some(zig)) = (&self.zig, &current.zig) {
+            if locked != zig {
+                differences.push(format!(
+                    "Zig {locked} is locked, but the installed version is {zig}"
+                ));
+            }
+        }
+
+        for target in &current.targets {
+            if !self.targets.contains(target) {
+                differences.push(format!("the target {target} is not locked"));
+            }
+        }
+
+        differences
+    }
+
+    /// Lock the current toolchain, and keep the targets that were locked before.
+    pub(crate) fn update(&self, current: ToolchainLock) -> ToolchainLock {
+        let mut targets = self.targets.clone();
+        for target in current.targets {
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        targets.sort();
+
+        ToolchainLock {
+            cargo_lambda: current.cargo_lambda,
+            zig: current.zig.or_else(|| self.zig.clone()),
+            targets,
+        }
+    }
+}
+
+/// Read the lock file, or return `None` if it doesn't exist.
+pub(crate) fn read_lock(path: &Path) -> Result<Option<ToolchainLock>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    let lock = toml::from_str(&content)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("invalid toolchain lock file {}", path.display()))?;
+    Ok(Some(lock))
+}
+
+pub(crate) fn write_lock(path: &Path, lock: &ToolchainLock) -> Result<()> {
+    let content = toml::to_string(lock).into_diagnostic()?;
+    std::fs::write(path, format!("{LOCK_FILE_HEADER}{content}"))
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to write {}", path.display()))
+}
+
+/// Version of the Zig installation that cargo-zigbuild uses.
+pub(crate) async fn zig_version() -> Option<String> {
+    let (path, args) = Zig::find_zig().ok()?;
+    let output = new_command(&path.to_string_lossy())
+        .args(args)
+        .arg("version")
+        .output()
+        .await
+        .ok()?;
+
+    let version = String::from_utf8(output.stdout).ok()?;
+    let version = version.trim();
+    (output.status.success() && !version.is_empty()).then(|| version.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock(cargo_lambda: &str, zig: Option<&str>, targets: &[&str]) -> ToolchainLock {
+        ToolchainLock {
+            cargo_lambda: cargo_lambda.to_string(),
+            zig: zig.map(String::from),
+            targets: targets.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_differences() {
+        let locked = lock("1.6.2", Some("0.13.0"), &["aarch64-unknown-linux-gnu"]);
+
+        assert!(locked.differences(&locked.clone()).is_empty());
+        assert!(locked
+            .differences(&lock("1.6.2", None, &["aarch64-unknown-linux-gnu"]))
+            .is_empty());
+
+        let current = lock("1.7.0", Some("0.12.0"), &["x86_64-unknown-linux-gnu"]);
+        assert_eq!(
+            locked.differences(&current),
+            vec![
+                "Cargo Lambda 1.6.2 is locked, but the current version is 1.7.0",
+                "Zig 0.13.0 is locked, but the installed version is 0.12.0",
+                "the target x86_64-unknown-linux-gnu is not locked",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_update() {
+        let locked = lock("1.6.0", Some("0.13.0"), &["x86_64-unknown-linux-gnu"]);
+
+        let updated = locked.update(lock("1.6.2", None, &["aarch64-unknown-linux-gnu"]));
+        assert_eq!(
+            updated,
+            lock(
+                "1.6.2",
+                Some("0.13.0"),
+                &["aarch64-unknown-linux-gnu", "x86_64-unknown-linux-gnu"]
+            )
+        );
+    }
+
+    #[test]
+    fn test_lock_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(TOOLCHAIN_LOCK_FILE);
+        assert_eq!(read_lock(&path).unwrap(), None);
+
+        let locked = lock("1.6.2", Some("0.13.0"), &["aarch64-unknown-linux-gnu"]);
+        write_lock(&path, &locked).unwrap();
+        assert_eq!(read_lock(&path).unwrap(), Some(locked));
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# This file is generated by Cargo Lambda."));
+    }
+}
@@ -0,0 +1,98 @@
+use cargo_lambda_interactive::command::new_command;
+use miette::Result;
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use crate::error::BuildError;
+
+/// CLI that signs and verifies the zip files with Sigstore.
+const COSIGN: &str = "cosign";
+
+/// Suffix of the Sigstore bundle written next to each signed zip file.
+const ATTESTATION_SUFFIX: &str = ".sigstore.json";
+
+/// Path of the attestation for a zip file, like `bootstrap.zip.sigstore.json`.
+pub fn attestation_path(archive: &Path) -> PathBuf {
+    let mut path = archive.as_os_str().to_owned();
+    path.push(ATTESTATION_SUFFIX);
+    PathBuf::from(path)
+}
+
+/// Sign a zip file with Sigstore keyless signing, and write the attestation next to it.
+/// Cosign asks for an OIDC identity in the browser, unless the environment provides one, like GitHub Actions.
+pub async fn sign_archive(archive: &Path) -> Result<PathBuf> {
+    let attestation = attestation_path(archive);
+
+    let status = new_command(COSIGN)
+        .args(["sign-blob", "--yes", "--bundle"])
+        .arg(&attestation)
+        .arg(archive)
+        .stdout(Stdio::null())
+        .status()
+        .await
+        .map_err(BuildError::CosignCommand)?;
+
+    if !status.success() {
+        return Err(BuildError::SigningFailed(archive.to_path_buf()).into());
+    }
+
+    Ok(attestation)
+}
+
+/// Verify the signature of a zip file with the attestation next to it.
+/// The zip file must have been signed by `identity`, authenticated by the OIDC `issuer`.
+pub async fn verify_archive(archive: &Path, identity: &str, issuer: &str) -> Result<()> {
+    let attestation = attestation_path(archive);
+    if !attestation.exists() {
+        return Err(BuildError::MissingAttestation(archive.to_path_buf(), attestation).into());
+    }
+
+    let output = new_command(COSIGN)
+        .arg("verify-blob")
+        .arg("--bundle")
+        .arg(&attestation)
+        .args(["--certificate-identity", identity])
+        .args(["--certificate-oidc-issuer", issuer])
+        .arg(archive)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(BuildError::CosignCommand)?;
+
+    if !output.status.success() {
+        let reason = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(BuildError::InvalidSignature(archive.to_path_buf(), reason).into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attestation_path() {
+        assert_eq!(
+            PathBuf::from("target/lambda/basic/bootstrap.zip.sigstore.json"),
+            attestation_path(Path::new("target/lambda/basic/bootstrap.zip"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_archive_without_attestation() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("bootstrap.zip");
+        std::fs::write(&archive, b"zip").unwrap();
+
+        let err = verify_archive(&archive, "alice@example.com", "https://accounts.google.com")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BuildError>(),
+            Some(BuildError::MissingAttestation(..))
+        ));
+    }
+}
@@ -75,7 +75,8 @@ async fn install_target_component(component: &str, toolchain: &str) -> Result<()
     silent_command(&cmd, &args).await
 }
 
-pub(crate) fn rustup_cmd() -> String {
+/// Command to run rustup, it can be replaced with the `RUSTUP` environment variable.
+pub fn rustup_cmd() -> String {
     env::var("RUSTUP").unwrap_or_else(|_| "rustup".to_string())
 }
 
@@ -0,0 +1,122 @@
+use clap::{Args, Command, CommandFactory};
+use clap_complete::Shell;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::path::{Path, PathBuf};
+
+use crate::App;
+
+/// Name of the binary that cargo runs for `cargo lambda`.
+const BIN_NAME: &str = "cargo-lambda";
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "completions",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/completions.html"
+)]
+pub struct Completions {
+    /// Shell to generate the completions for
+    #[arg(value_enum)]
+    shell: Shell,
+}
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "manpages",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/manpages.html"
+)]
+pub struct Manpages {
+    /// Directory to write the man pages into
+    #[arg(value_name = "DIR")]
+    dir: PathBuf,
+}
+
+impl Completions {
+    pub fn run(&self) -> Result<()> {
+        let mut cmd = lambda_command()?;
+        clap_complete::generate(self.shell, &mut cmd, BIN_NAME, &mut std::io::stdout());
+        Ok(())
+    }
+}
+
+impl Manpages {
+    pub fn run(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to create {}", self.dir.display()))?;
+
+        let cmd = lambda_command()?;
+        let pages = write_manpages(&cmd, &self.dir)?;
+        println!("✅ {pages} man pages written to {}", self.dir.display());
+        Ok(())
+    }
+}
+
+/// Command tree of `cargo lambda`, without the `cargo` wrapper.
+fn lambda_command() -> Result<Command> {
+    let mut app = App::command();
+    let lambda = app
+        .find_subcommand_mut("lambda")
+        .cloned()
+        .ok_or_else(|| miette::miette!("missing lambda command"))?;
+    Ok(lambda.name(BIN_NAME).bin_name(BIN_NAME))
+}
+
+/// Write a man page for the command, and one for each of its subcommands,
+/// like `cargo-lambda.1`, and `cargo-lambda-build.1`.
+fn write_manpages(cmd: &Command, dir: &Path) -> Result<usize> {
+    let mut cmd = cmd.clone();
+    cmd.build();
+    write_page(&cmd, cmd.get_name(), dir)
+}
+
+fn write_page(cmd: &Command, name: &str, dir: &Path) -> Result<usize> {
+    let page = cmd.clone().name(name.to_string());
+    let path = dir.join(format!("{name}.1"));
+
+    let mut buffer = Vec::new();
+    clap_mangen::Man::new(page)
+        .render(&mut buffer)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to render the man page for {name}"))?;
+    std::fs::write(&path, buffer)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+
+    let mut pages = 1;
+    for sub in cmd.get_subcommands().filter(|s| !s.is_hide_set()) {
+        if sub.get_name() == "help" {
+            continue;
+        }
+        pages += write_page(sub, &format!("{name}-{}", sub.get_name()), dir)?;
+    }
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completions_include_subcommands() {
+        let mut cmd = lambda_command().unwrap();
+        let mut output = Vec::new();
+        clap_complete::generate(Shell::Bash, &mut cmd, BIN_NAME, &mut output);
+
+        let script = String::from_utf8(output).unwrap();
+        assert!(script.contains("deploy"));
+        assert!(script.contains("promote"));
+        assert!(script.contains("--manifest-path"));
+    }
+
+    #[test]
+    fn test_write_manpages() {
+        let dir = tempfile::tempdir().unwrap();
+        let cmd = lambda_command().unwrap();
+
+        let pages = write_manpages(&cmd, dir.path()).unwrap();
+        assert!(pages > 10);
+        assert!(dir.path().join("cargo-lambda.1").is_file());
+        assert!(dir.path().join("cargo-lambda-build.1").is_file());
+        assert!(dir.path().join("cargo-lambda-template-test.1").is_file());
+    }
+}
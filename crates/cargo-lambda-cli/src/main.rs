@@ -1,21 +1,38 @@
 #![warn(rust_2018_idioms, unused_lifetimes)]
 #![allow(clippy::multiple_crate_versions)]
 use cargo_lambda_build::Zig;
+use cargo_lambda_deploy::{Bench, Delete, Env, Info, Layers, List, Promote, Role, Rollback};
 use cargo_lambda_invoke::Invoke;
+use cargo_lambda_logs::Logs;
 use cargo_lambda_metadata::{
-    cargo::{build::Build, deploy::Deploy, load_metadata, watch::Watch},
-    config::{load_config, Config, ConfigOptions},
+    cargo::{
+        build::Build,
+        deploy::{Deploy, OutputFormat},
+        load_metadata,
+        watch::Watch,
+    },
+    config::{
+        load_config, load_examples_config, load_remote_config, load_templates_config,
+        load_updates_config, Config, ConfigOptions,
+    },
 };
-use cargo_lambda_new::{Init, New};
-use cargo_lambda_system::System;
-use cargo_lambda_watch::xray_layer;
-use clap::{CommandFactory, Parser, Subcommand};
+use cargo_lambda_new::{ConfigCommand, Init, New, Template};
+use cargo_lambda_remote::network;
+use cargo_lambda_system::{Doctor, System};
+use cargo_lambda_watch::{xray_layer, Test};
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use clap_cargo::style::CLAP_STYLING;
 use miette::{miette, ErrorHook, IntoDiagnostic, Result};
-use std::{boxed::Box, env, io::IsTerminal, path::PathBuf, str::FromStr};
+use std::{boxed::Box, env, io::IsTerminal, path::PathBuf, str::FromStr, time::Duration};
 use strum_macros::EnumString;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod completions;
+use completions::{Completions, Manpages};
+mod self_update;
+use self_update::SelfCommand;
+mod update_check;
+
 #[derive(Parser)]
 #[command(name = "cargo", bin_name = "cargo", disable_version_flag = true)]
 #[command(styles = CLAP_STYLING)]
@@ -45,11 +62,22 @@ struct Lambda {
     )]
     color: String,
 
+    /// Format of the logs. Use `json` to print one JSON object per line, with timestamps and targets
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = LogFormat::Text,
+        global = true,
+        env = "CARGO_LAMBDA_LOG_FORMAT"
+    )]
+    log_format: LogFormat,
+
     /// Path to the global configuration file
     #[arg(long, global = true, env = "CARGO_LAMBDA_GLOBAL")]
     global: Option<PathBuf>,
 
-    /// Context to use for the configuration file
+    /// Context to use for the configuration file. Separate several contexts with commas,
+    /// like `base,staging`, the contexts at the end take precedence
     #[arg(short = 'x', long, global = true, env = "CARGO_LAMBDA_CONTEXT")]
     context: Option<String>,
 
@@ -57,6 +85,10 @@ struct Lambda {
     #[arg(long, global = true, env = "CARGO_LAMBDA_ADMERGE")]
     admerge: bool,
 
+    /// Path to a PEM file with extra CA certificates to trust, like the certificate of a corporate proxy
+    #[arg(long, global = true, env = "CARGO_LAMBDA_CA_BUNDLE")]
+    ca_bundle: Option<PathBuf>,
+
     /// Print version information
     #[arg(short = 'V', long)]
     version: bool,
@@ -70,6 +102,14 @@ enum Color {
     Never,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum LogFormat {
+    /// Human readable logs
+    Text,
+    /// JSON lines, for CI systems and log processors
+    Json,
+}
+
 impl Color {
     fn is_ansi(&self) -> bool {
         match self {
@@ -91,23 +131,72 @@ impl Color {
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Subcommand)]
 enum LambdaSubcommand {
+    /// `cargo lambda bench` sends a controlled load of requests to a function deployed on AWS Lambda.
+    /// It reports latency percentiles, cold starts, and billed duration, to compare memory settings and architectures.
+    Bench(Bench),
     /// `cargo lambda build` compiles AWS Lambda functions and extension natively.
     /// It produces artifacts which you can then upload to AWS Lambda with `cargo lambda deploy`,
     /// or use with other ecosystem tools, SAM Cli or the AWS CDK.
     Build(Build),
+    /// `cargo lambda completions` prints the shell completions for all the subcommands and their flags.
+    /// It supports Bash, Zsh, Fish, Elvish, and PowerShell.
+    Completions(Completions),
+    /// `cargo lambda config init` asks about the build, deploy, and watch settings of a project.
+    /// It writes the configuration into the package's metadata, or into a global configuration file.
+    Config(ConfigCommand),
+    /// `cargo lambda delete` removes functions from AWS Lambda.
+    /// It can also remove the function's log group, and the execution role that `cargo lambda deploy` created.
+    Delete(Delete),
     /// `cargo lambda deploy` uploads functions and extensions to AWS Lambda.
     /// You can use the same command to create new functions as well as update existent functions code.
     Deploy(Deploy),
+    /// `cargo lambda doctor` checks the toolchain, the AWS credentials, and the network access that Cargo Lambda needs.
+    /// It prints how to fix the problems it finds.
+    Doctor(Doctor),
+    /// `cargo lambda env` manages the environment variables of functions deployed on AWS Lambda.
+    /// It can also sync the variables between a function and a local `.env` file.
+    Env(Env),
+    /// `cargo lambda info` describes a function deployed on AWS Lambda, with its URL, aliases, event sources, and recent errors.
+    /// It also shows the differences between the function and the deploy configuration in the project.
+    Info(Info),
     /// `cargo lambda init` creates Rust Lambda packages in an existent directory.
     /// Files present in that directory will be preserved as they were before running this command.
     Init(Init),
     /// `cargo lambda invoke` sends requests to the control plane emulator to test and debug interactions with your Lambda functions.
     /// This command can also be used to send requests to remote functions once deployed on AWS Lambda.
     Invoke(Invoke),
+    /// `cargo lambda layers` manages layers on AWS Lambda, like the layers that `cargo lambda deploy --extension` publishes.
+    /// It publishes, lists, and deletes layer versions, and adds layers to deployed functions.
+    Layers(Layers),
+    /// `cargo lambda list` shows the functions deployed on AWS Lambda,
+    /// and whether their code matches the binaries built in the workspace.
+    List(List),
+    /// `cargo lambda logs` prints the logs of functions deployed on AWS Lambda from CloudWatch Logs.
+    /// It can also follow the logs, and print new events as they arrive.
+    Logs(Logs),
+    /// `cargo lambda manpages` writes the man pages for Cargo Lambda and all its subcommands into a directory.
+    Manpages(Manpages),
     /// `cargo lambda new` creates Rust Lambda packages from a well defined template to help you start writing AWS Lambda functions in Rust.
     New(New),
+    /// `cargo lambda promote` moves a function's alias to a new version.
+    /// It can shift the traffic to the new version gradually before moving the alias completely.
+    Promote(Promote),
+    /// `cargo lambda role` manages the execution roles of functions deployed on AWS Lambda.
+    /// It creates roles, attaches and detaches policies, and cleans up the roles that Cargo Lambda created.
+    Role(Role),
+    /// `cargo lambda rollback` moves a function's alias back to the version it pointed to before the last promotion or deploy.
+    Rollback(Rollback),
+    /// `cargo lambda self update` replaces Cargo Lambda with the latest release from GitHub.
+    /// It verifies the release checksum before replacing the current executable.
+    #[command(name = "self")]
+    SelfCommand(SelfCommand),
     /// `cargo lambda system` shows the status of the system Zig installation.
     System(System),
+    /// `cargo lambda template` helps authors of project templates validate them before publishing them.
+    Template(Template),
+    /// `cargo lambda test` starts the emulator on a free port, and runs integration tests against it.
+    /// It runs `cargo test` with the emulator's URL in `CARGO_LAMBDA_INVOKE_URL`, or the test cases in a TOML file.
+    Test(Test),
     /// `cargo lambda watch` boots a development server that emulates interactions with the AWS Lambda control plane.
     /// This subcommand also reloads your Rust code as you work on it.
     Watch(Watch),
@@ -122,16 +211,41 @@ impl LambdaSubcommand {
         admerge: bool,
     ) -> Result<()> {
         match self {
+            Self::Bench(b) => b.run().await,
             Self::Build(b) => Self::run_build(b, global, context, admerge).await,
+            Self::Completions(c) => c.run(),
+            Self::Config(c) => c.run(global).await,
+            Self::Delete(d) => d.run().await,
             Self::Deploy(d) => Self::run_deploy(d, global, context, admerge).await,
-            Self::Init(mut i) => i.run().await,
-            Self::Invoke(i) => i.run().await,
-            Self::New(mut n) => n.run().await,
-            Self::System(s) => s.run().await,
+            Self::Doctor(d) => d.run().await,
+            Self::Env(e) => e.run().await,
+            Self::Info(i) => Self::run_info(i, global, context, admerge).await,
+            Self::Init(i) => Self::run_init(i, global, context).await,
+            Self::Invoke(i) => Self::run_invoke(i, global, context).await,
+            Self::Layers(l) => l.run().await,
+            Self::List(l) => Self::run_list(l).await,
+            Self::Logs(l) => Self::run_logs(l, global, context, admerge).await,
+            Self::Manpages(m) => m.run(),
+            Self::New(n) => Self::run_new(n, global, context).await,
+            Self::Promote(p) => p.run().await,
+            Self::Role(r) => r.run().await,
+            Self::Rollback(r) => r.run().await,
+            Self::SelfCommand(s) => s.run().await,
+            Self::System(s) => s.run(global, context, admerge).await,
+            Self::Template(t) => t.run().await,
+            Self::Test(t) => Self::run_test(t, color, global, context, admerge).await,
             Self::Watch(w) => Self::run_watch(w, color, global, context, admerge).await,
         }
     }
 
+    /// Commands that print completions, or update Cargo Lambda, don't print update notifications.
+    fn checks_for_updates(&self) -> bool {
+        !matches!(
+            self,
+            Self::Completions(_) | Self::Manpages(_) | Self::SelfCommand(_)
+        )
+    }
+
     async fn run_build(
         build: Build,
         global: Option<PathBuf>,
@@ -178,6 +292,134 @@ impl LambdaSubcommand {
         cargo_lambda_watch::run(&config.watch, &config.env, &metadata, color).await
     }
 
+    async fn run_test(
+        test: Test,
+        color: &str,
+        global: Option<PathBuf>,
+        context: Option<String>,
+        admerge: bool,
+    ) -> Result<()> {
+        let metadata = load_metadata(test.manifest_path())?;
+
+        let mut watch = Watch::default();
+        watch.cargo_opts.manifest_path = Some(test.manifest_path().to_path_buf());
+        let args_config = Config {
+            watch,
+            ..Default::default()
+        };
+
+        let options = ConfigOptions {
+            context,
+            global,
+            admerge,
+            ..Default::default()
+        };
+        let config = load_config(&args_config, &metadata, &options)?;
+        cargo_lambda_watch::run_tests(&test, &config.watch, &config.env, &metadata, color).await
+    }
+
+    async fn run_invoke(
+        mut invoke: Invoke,
+        global: Option<PathBuf>,
+        context: Option<String>,
+    ) -> Result<()> {
+        let options = ConfigOptions {
+            context,
+            global,
+            ..Default::default()
+        };
+
+        invoke.examples = load_examples_config(&options)?;
+        if invoke.is_remote() {
+            invoke.inherit_role(&load_remote_config(&options)?);
+        }
+        invoke.run().await
+    }
+
+    async fn run_list(list: List) -> Result<()> {
+        // Functions can be listed outside a Rust project, without comparing their code.
+        if !list.manifest_path().is_file() {
+            return list.run(None).await;
+        }
+
+        let metadata = load_metadata(list.manifest_path())?;
+        list.run(Some(&metadata)).await
+    }
+
+    async fn run_logs(
+        mut logs: Logs,
+        global: Option<PathBuf>,
+        context: Option<String>,
+        admerge: bool,
+    ) -> Result<()> {
+        // Logs can be read outside a Rust project, when the function name is provided.
+        if !logs.manifest_path().is_file() {
+            return logs.run(None).await;
+        }
+
+        let metadata = load_metadata(logs.manifest_path())?;
+        let options = ConfigOptions {
+            name: logs.name(),
+            context,
+            global,
+            admerge,
+        };
+
+        let config = load_config(&Config::default(), &metadata, &options)?;
+        logs.deploy = Some(config.deploy);
+        logs.run(Some(&metadata)).await
+    }
+
+    async fn run_info(
+        mut info: Info,
+        global: Option<PathBuf>,
+        context: Option<String>,
+        admerge: bool,
+    ) -> Result<()> {
+        // Functions can be described outside a Rust project, without comparing their configuration.
+        if !info.manifest_path().is_file() {
+            return info.run().await;
+        }
+
+        let metadata = load_metadata(info.manifest_path())?;
+        let options = ConfigOptions {
+            name: Some(info.name().to_string()),
+            context,
+            global,
+            admerge,
+        };
+
+        let config = load_config(&Config::default(), &metadata, &options)?;
+        info.deploy = Some(config.deploy);
+        info.run().await
+    }
+
+    async fn run_init(
+        mut init: Init,
+        global: Option<PathBuf>,
+        context: Option<String>,
+    ) -> Result<()> {
+        let options = ConfigOptions {
+            context,
+            global,
+            ..Default::default()
+        };
+
+        init.templates = load_templates_config(&options)?;
+        init.run().await
+    }
+
+    async fn run_new(mut new: New, global: Option<PathBuf>, context: Option<String>) -> Result<()> {
+        let options = ConfigOptions {
+            context,
+            global,
+            ..Default::default()
+        };
+
+        new.templates = load_templates_config(&options)?;
+        new.run().await
+    }
+
     async fn run_deploy(
         deploy: Deploy,
         global: Option<PathBuf>,
@@ -249,7 +491,8 @@ async fn main() -> Result<()> {
         };
         zig.execute().map_err(|e| miette!(e))
     } else {
-        let app = App::parse();
+        let matches = App::command().get_matches();
+        let app = App::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
         match app {
             App::Zig(zig) => {
@@ -263,7 +506,13 @@ async fn main() -> Result<()> {
                 color.write_env_var();
                 miette::set_hook(error_hook(Some(&color)))?;
 
-                run_subcommand(lambda, color).await
+                match run_subcommand(lambda, color).await {
+                    Err(err) if json_output(&matches) => {
+                        println!("{}", error_envelope(&err));
+                        std::process::exit(1);
+                    }
+                    result => result,
+                }
             }
         }
     }
@@ -279,6 +528,12 @@ async fn run_subcommand(lambda: Lambda, color: Color) -> Result<()> {
         Some(subcommand) => subcommand,
     };
 
+    // Subcommands read the CA bundle from the environment when they create HTTP clients.
+    if let Some(ca_bundle) = &lambda.ca_bundle {
+        std::env::set_var(network::CA_BUNDLE_ENV, ca_bundle);
+        network::load_ca_certificates()?;
+    }
+
     let log_directive = if lambda.verbose == 0 {
         std::env::var("RUST_LOG").unwrap_or_else(|_| "cargo_lambda=info".into())
     } else if lambda.verbose == 1 {
@@ -287,14 +542,28 @@ async fn run_subcommand(lambda: Lambda, color: Color) -> Result<()> {
         "cargo_lambda=trace".into()
     };
 
-    let fmt = tracing_subscriber::fmt::layer()
-        .with_target(false)
-        .without_time()
-        .with_ansi(color.is_ansi());
+    // Only one of these layers is enabled, the other one is `None`.
+    let (text, json) = match lambda.log_format {
+        LogFormat::Text => {
+            let fmt = tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .without_time()
+                .with_ansi(color.is_ansi());
+            (Some(fmt), None)
+        }
+        LogFormat::Json => {
+            let fmt = tracing_subscriber::fmt::layer()
+                .json()
+                .with_target(true)
+                .with_ansi(false);
+            (None, Some(fmt))
+        }
+    };
 
     let subscriber = tracing_subscriber::registry()
         .with(tracing_subscriber::EnvFilter::new(log_directive))
-        .with(fmt);
+        .with(text)
+        .with(json);
 
     if let LambdaSubcommand::Watch(w) = &*subcommand {
         subscriber.with(xray_layer(w)).init();
@@ -302,14 +571,61 @@ async fn run_subcommand(lambda: Lambda, color: Color) -> Result<()> {
         subscriber.init();
     }
 
-    subcommand
+    let update_check = subcommand
+        .checks_for_updates()
+        .then(|| {
+            let options = ConfigOptions {
+                context: lambda.context.clone(),
+                global: lambda.global.clone(),
+                ..Default::default()
+            };
+            load_updates_config(&options).ok()
+        })
+        .flatten()
+        .filter(|config| config.check)
+        .and_then(|_| update_check::notify_updates());
+
+    let result = subcommand
         .run(
             &color.to_lowercase(),
             lambda.global,
             lambda.context,
             lambda.admerge,
         )
-        .await
+        .await;
+
+    // The command doesn't wait for the update check, its result is only printed if it's already available.
+    if let Some(update_check) = update_check {
+        if let Ok(Ok(Some(latest))) = tokio::time::timeout(Duration::ZERO, update_check).await {
+            update_check::print_notification(&latest);
+        }
+    }
+    result
+}
+
+/// Whether the subcommand renders its output as JSON, with the flag `--output-format json`.
+fn json_output(matches: &ArgMatches) -> bool {
+    let mut matches = Some(matches);
+    while let Some(m) = matches {
+        if let Ok(Some(OutputFormat::Json)) = m.try_get_one::<OutputFormat>("output_format") {
+            return true;
+        }
+        matches = m.subcommand().map(|(_, m)| m);
+    }
+    false
+}
+
+/// Render the error as a JSON object, with its code, message, help, and causes,
+/// so wrappers can branch on the type of failure.
+fn error_envelope(err: &miette::Report) -> String {
+    let mut report = String::new();
+    if miette::JSONReportHandler::new()
+        .render_report(&mut report, &**err)
+        .is_err()
+    {
+        report = serde_json::json!({ "message": err.to_string() }).to_string();
+    }
+    format!(r#"{{"error": {report}}}"#)
 }
 
 fn error_hook(color: Option<&Color>) -> ErrorHook {
@@ -330,3 +646,43 @@ fn error_hook(color: Option<&Color>) -> ErrorHook {
             .build())
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_output() {
+        let matches = App::command()
+            .try_get_matches_from(["cargo", "lambda", "list", "--output-format", "json"])
+            .unwrap();
+        assert!(json_output(&matches));
+
+        let matches = App::command()
+            .try_get_matches_from(["cargo", "lambda", "list"])
+            .unwrap();
+        assert!(!json_output(&matches));
+
+        let matches = App::command()
+            .try_get_matches_from(["cargo", "lambda", "build", "--output-format", "zip"])
+            .unwrap();
+        assert!(!json_output(&matches));
+    }
+
+    #[test]
+    fn test_error_envelope() {
+        let err = miette!(
+            code = "cargo_lambda::test::missing_binary",
+            help = "add a binary",
+            "there are no binaries in this project"
+        );
+        let envelope = error_envelope(&err);
+        let value: serde_json::Value = serde_json::from_str(&envelope).unwrap();
+        assert_eq!(value["error"]["code"], "cargo_lambda::test::missing_binary");
+        assert_eq!(
+            value["error"]["message"],
+            "there are no binaries in this project"
+        );
+        assert_eq!(value["error"]["help"], "add a binary");
+    }
+}
@@ -0,0 +1,364 @@
+use cargo_lambda_remote::network;
+use clap::{Args, Subcommand};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use semver::Version;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+};
+
+const RELEASES_URL: &str = "https://api.github.com/repos/cargo-lambda/cargo-lambda/releases";
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "self",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/self.html"
+)]
+pub struct SelfCommand {
+    #[command(subcommand)]
+    command: SelfSubcommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum SelfSubcommand {
+    /// Replace the current executable with the latest release from GitHub
+    Update(Update),
+}
+
+#[derive(Args, Clone, Debug)]
+struct Update {
+    /// Only check whether there is a newer version, without installing it
+    #[arg(long)]
+    check: bool,
+
+    /// Version to install, the latest release by default
+    #[arg(long, value_name = "VERSION")]
+    version: Option<Version>,
+
+    /// Install the release even if it's not newer than the current version
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Format of the archives in the GitHub releases.
+#[derive(Debug, PartialEq)]
+enum ArchiveFormat {
+    TarGz,
+    Zip,
+}
+
+impl SelfCommand {
+    pub async fn run(&self) -> Result<()> {
+        match &self.command {
+            SelfSubcommand::Update(update) => update.run().await,
+        }
+    }
+}
+
+impl Update {
+    async fn run(&self) -> Result<()> {
+        let current = Version::parse(env!("CARGO_PKG_VERSION")).into_diagnostic()?;
+        let client = network::http_client_builder()?
+            .user_agent(format!("cargo-lambda/{current}"))
+            .build()
+            .into_diagnostic()?;
+
+        let release = fetch_release(&client, self.version.as_ref()).await?;
+        let version = release_version(&release.tag_name)?;
+
+        if version <= current && !self.force {
+            println!("✅ Cargo Lambda {current} is up to date");
+            return Ok(());
+        }
+
+        if self.check {
+            println!("🆕 Cargo Lambda {version} is available, you're using {current}");
+            println!("   run `cargo lambda self update` to install it");
+            return Ok(());
+        }
+
+        let (asset_name, format) = asset_name(&release.tag_name)?;
+        let archive = find_asset(&release, &asset_name)?;
+        let checksum = find_asset(&release, &format!("{asset_name}.sha256"))?;
+
+        println!("🔍 downloading {asset_name}");
+        let data = download(&client, &archive.browser_download_url).await?;
+        let checksum = download(&client, &checksum.browser_download_url).await?;
+        verify_checksum(&data, &String::from_utf8_lossy(&checksum))?;
+
+        let binary = extract_binary(&data, &format)?;
+
+        let current_exe = std::env::current_exe()
+            .and_then(dunce::canonicalize)
+            .into_diagnostic()
+            .wrap_err("failed to find the current executable")?;
+        replace_executable(&current_exe, &binary)?;
+
+        println!("✅ Cargo Lambda updated from {current} to {version}");
+        Ok(())
+    }
+}
+
+/// Version of the latest release in GitHub.
+pub(crate) async fn latest_version(client: &reqwest::Client) -> Result<Version> {
+    let release = fetch_release(client, None).await?;
+    release_version(&release.tag_name)
+}
+
+async fn fetch_release(client: &reqwest::Client, version: Option<&Version>) -> Result<Release> {
+    let url = match version {
+        Some(version) => format!("{RELEASES_URL}/tags/v{version}"),
+        None => format!("{RELEASES_URL}/latest"),
+    };
+
+    let mut request = client
+        .get(&url)
+        .header("Accept", "application/vnd.github+json");
+    // Authenticated requests have higher rate limits, which helps in CI.
+    if let Ok(token) = std::env::var("GITHUB_TOKEN") {
+        request = request.bearer_auth(token);
+    }
+
+    let response = request
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .into_diagnostic()
+        .wrap_err("failed to fetch the release information from GitHub")?;
+    let body = response.bytes().await.into_diagnostic()?;
+
+    serde_json::from_slice(&body)
+        .into_diagnostic()
+        .wrap_err("invalid release information from GitHub")
+}
+
+async fn download(client: &reqwest::Client, url: &str) -> Result<Vec<u8>> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to download {url}"))?;
+    let bytes = response
+        .bytes()
+        .await
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to download {url}"))?;
+    Ok(bytes.to_vec())
+}
+
+fn release_version(tag: &str) -> Result<Version> {
+    Version::parse(tag.trim_start_matches('v'))
+        .into_diagnostic()
+        .wrap_err_with(|| format!("invalid release version `{tag}`"))
+}
+
+/// Name of the release archive for the platform that this binary was built for.
+fn asset_name(tag: &str) -> Result<(String, ArchiveFormat)> {
+    let target = match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("macos", "aarch64") => "aarch64-apple-darwin",
+        ("macos", "x86_64") => "x86_64-apple-darwin",
+        ("linux", "aarch64") => "aarch64-unknown-linux-musl",
+        ("linux", "x86_64") => "x86_64-unknown-linux-musl",
+        ("windows", "x86_64") => "windows-x64",
+        (os, arch) => {
+            return Err(miette::miette!(
+                "there are no prebuilt binaries for {os} on {arch}, update Cargo Lambda with the tool that you used to install it"
+            ))
+        }
+    };
+    Ok(format_asset_name(tag, target))
+}
+
+fn format_asset_name(tag: &str, target: &str) -> (String, ArchiveFormat) {
+    if target.starts_with("windows") {
+        (
+            format!("cargo-lambda-{tag}.{target}.zip"),
+            ArchiveFormat::Zip,
+        )
+    } else {
+        (
+            format!("cargo-lambda-{tag}.{target}.tar.gz"),
+            ArchiveFormat::TarGz,
+        )
+    }
+}
+
+fn find_asset<'a>(release: &'a Release, name: &str) -> Result<&'a Asset> {
+    release
+        .assets
+        .iter()
+        .find(|asset| asset.name == name)
+        .ok_or_else(|| {
+            miette::miette!(
+                "the release {} doesn't include the file {name}",
+                release.tag_name
+            )
+        })
+}
+
+/// Compare the archive with the checksum file, in the format that `shasum` writes.
+fn verify_checksum(data: &[u8], checksum_file: &str) -> Result<()> {
+    let expected = checksum_file
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| miette::miette!("the checksum file is empty"))?;
+
+    let actual = format!("{:x}", Sha256::digest(data));
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(miette::miette!(
+            "the checksum of the downloaded file doesn't match the release checksum, expected {expected}, got {actual}"
+        ));
+    }
+    Ok(())
+}
+
+fn extract_binary(data: &[u8], format: &ArchiveFormat) -> Result<Vec<u8>> {
+    let mut binary = Vec::new();
+    match format {
+        ArchiveFormat::TarGz => {
+            let decoder = flate2::read::GzDecoder::new(Cursor::new(data));
+            let mut archive = tar::Archive::new(decoder);
+            let entries = archive.entries().into_diagnostic()?;
+            for entry in entries {
+                let mut entry = entry.into_diagnostic()?;
+                if entry.path().into_diagnostic()?.as_ref() == Path::new("cargo-lambda") {
+                    entry.read_to_end(&mut binary).into_diagnostic()?;
+                    return Ok(binary);
+                }
+            }
+        }
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(Cursor::new(data)).into_diagnostic()?;
+            if let Ok(mut file) = archive.by_name("cargo-lambda.exe") {
+                file.read_to_end(&mut binary).into_diagnostic()?;
+                return Ok(binary);
+            }
+        }
+    }
+
+    Err(miette::miette!(
+        "the release archive doesn't include the cargo-lambda executable"
+    ))
+}
+
+/// Write the new executable next to the current one, and move it into place.
+/// Windows doesn't allow replacing a running executable, but it allows renaming it.
+fn replace_executable(current_exe: &Path, binary: &[u8]) -> Result<()> {
+    let new_exe = sibling_path(current_exe, "new");
+    std::fs::write(&new_exe, binary)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to write {}", new_exe.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&new_exe, std::fs::Permissions::from_mode(0o755))
+            .into_diagnostic()?;
+    }
+
+    if cfg!(windows) {
+        let old_exe = sibling_path(current_exe, "old");
+        let _ = std::fs::remove_file(&old_exe);
+        std::fs::rename(current_exe, &old_exe)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to move {}", current_exe.display()))?;
+    }
+
+    std::fs::rename(&new_exe, current_exe)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to replace {}", current_exe.display()))
+}
+
+fn sibling_path(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_format_asset_name() {
+        assert_eq!(
+            (
+                "cargo-lambda-v1.6.2.aarch64-apple-darwin.tar.gz".to_string(),
+                ArchiveFormat::TarGz
+            ),
+            format_asset_name("v1.6.2", "aarch64-apple-darwin")
+        );
+        assert_eq!(
+            (
+                "cargo-lambda-v1.6.2.windows-x64.zip".to_string(),
+                ArchiveFormat::Zip
+            ),
+            format_asset_name("v1.6.2", "windows-x64")
+        );
+    }
+
+    #[test]
+    fn test_release_version() {
+        assert_eq!(Version::new(1, 6, 2), release_version("v1.6.2").unwrap());
+        assert!(release_version("latest").is_err());
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let data = b"cargo-lambda";
+        let checksum = format!(
+            "{:x}  cargo-lambda-v1.6.2.x86_64-unknown-linux-musl.tar.gz\n",
+            Sha256::digest(data)
+        );
+        assert!(verify_checksum(data, &checksum).is_ok());
+        assert!(verify_checksum(b"tampered", &checksum).is_err());
+        assert!(verify_checksum(data, "").is_err());
+    }
+
+    #[test]
+    fn test_extract_binary_from_zip() {
+        let mut data = Vec::new();
+        {
+            let mut writer = zip::ZipWriter::new(Cursor::new(&mut data));
+            writer
+                .start_file("cargo-lambda.exe", zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(b"binary").unwrap();
+            writer.finish().unwrap();
+        }
+
+        assert_eq!(
+            b"binary".to_vec(),
+            extract_binary(&data, &ArchiveFormat::Zip).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_replace_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("cargo-lambda");
+        std::fs::write(&exe, "old").unwrap();
+
+        replace_executable(&exe, b"new").unwrap();
+        assert_eq!("new", std::fs::read_to_string(&exe).unwrap());
+        assert!(!sibling_path(&exe, "new").exists());
+    }
+}
@@ -0,0 +1,175 @@
+use cargo_lambda_remote::network;
+use miette::IntoDiagnostic;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::task::JoinHandle;
+
+use crate::self_update::latest_version;
+
+/// How often to ask GitHub for the latest release.
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The check runs in the background, but it must not delay commands that finish quickly.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Result of the last check, cached between runs.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+struct UpdateCache {
+    /// Seconds since the Unix epoch when the last check happened
+    checked_at: u64,
+    /// Latest version in the last check, if the check succeeded
+    latest: Option<Version>,
+}
+
+impl UpdateCache {
+    fn is_stale(&self, now: u64) -> bool {
+        now.saturating_sub(self.checked_at) >= CHECK_INTERVAL.as_secs()
+    }
+
+    /// Latest version, if it's newer than the current version.
+    fn newer_version(&self, current: &Version) -> Option<&Version> {
+        self.latest.as_ref().filter(|latest| *latest > current)
+    }
+}
+
+/// Print a notification when the last check found a newer release, and
+/// start a new check in the background when the cached result is stale.
+/// The new check returns the newer release that it finds, if the cache didn't have it already.
+/// The command doesn't wait for it, so its result can be reported the next time that Cargo Lambda runs.
+pub(crate) fn notify_updates() -> Option<JoinHandle<Option<Version>>> {
+    let path = cache_path()?;
+    let cache = read_cache(&path);
+
+    let current = Version::parse(env!("CARGO_PKG_VERSION")).ok()?;
+    if let Some(latest) = cache.newer_version(&current) {
+        print_notification(latest);
+    }
+
+    let now = now();
+    if !cache.is_stale(now) {
+        return None;
+    }
+
+    Some(tokio::spawn(async move {
+        let latest = match tokio::time::timeout(CHECK_TIMEOUT, check(&current)).await {
+            Ok(Ok(latest)) => Some(latest),
+            Ok(Err(err)) => {
+                tracing::debug!(?err, "failed to check for new releases");
+                None
+            }
+            Err(_) => {
+                tracing::debug!("timed out checking for new releases");
+                None
+            }
+        };
+
+        let notified = cache.newer_version(&current).cloned();
+        let newer = latest.as_ref().filter(|latest| **latest > current).cloned();
+
+        // Failed checks are cached too, so they are not retried on every run.
+        let cache = UpdateCache {
+            checked_at: now,
+            latest: latest.or(cache.latest),
+        };
+        write_cache(&path, &cache);
+
+        newer.filter(|newer| Some(newer) != notified.as_ref())
+    }))
+}
+
+/// Tell the user that there is a newer release, and how to install it.
+pub(crate) fn print_notification(latest: &Version) {
+    eprintln!(
+        "🆕 Cargo Lambda {latest} is available, you're using {}",
+        env!("CARGO_PKG_VERSION")
+    );
+    eprintln!("   run `cargo lambda self update` to install it\n");
+}
+
+async fn check(current: &Version) -> miette::Result<Version> {
+    let client = network::http_client_builder()?
+        .user_agent(format!("cargo-lambda/{current}"))
+        .build()
+        .into_diagnostic()?;
+    latest_version(&client).await
+}
+
+fn cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|p| p.join("cargo-lambda").join("update-check.json"))
+}
+
+fn read_cache(path: &Path) -> UpdateCache {
+    std::fs::read(path)
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+fn write_cache(path: &Path, cache: &UpdateCache) {
+    let result = path
+        .parent()
+        .map(std::fs::create_dir_all)
+        .transpose()
+        .and_then(|_| std::fs::write(path, serde_json::to_vec(cache).unwrap_or_default()));
+    if let Err(err) = result {
+        tracing::debug!(?err, ?path, "failed to write the update check cache");
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_stale() {
+        let cache = UpdateCache {
+            checked_at: 1_000_000,
+            latest: None,
+        };
+        assert!(!cache.is_stale(1_000_000 + 60));
+        assert!(cache.is_stale(1_000_000 + CHECK_INTERVAL.as_secs()));
+        assert!(UpdateCache::default().is_stale(now()));
+    }
+
+    #[test]
+    fn test_newer_version() {
+        let cache = UpdateCache {
+            checked_at: 0,
+            latest: Some(Version::new(1, 7, 0)),
+        };
+        assert_eq!(
+            Some(&Version::new(1, 7, 0)),
+            cache.newer_version(&Version::new(1, 6, 2))
+        );
+        assert_eq!(None, cache.newer_version(&Version::new(1, 7, 0)));
+        assert_eq!(
+            None,
+            UpdateCache::default().newer_version(&Version::new(1, 6, 2))
+        );
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cargo-lambda").join("update-check.json");
+        assert_eq!(UpdateCache::default(), read_cache(&path));
+
+        let cache = UpdateCache {
+            checked_at: 1_700_000_000,
+            latest: Some(Version::new(1, 7, 0)),
+        };
+        write_cache(&path, &cache);
+        assert_eq!(cache, read_cache(&path));
+    }
+}
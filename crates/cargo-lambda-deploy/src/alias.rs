@@ -0,0 +1,356 @@
+use cargo_lambda_interactive::progress::Progress;
+use cargo_lambda_remote::{
+    aws_sdk_lambda::{
+        operation::get_alias::GetAliasOutput, types::AliasRoutingConfiguration,
+        Client as LambdaClient,
+    },
+    RemoteConfig,
+};
+use clap::Args;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::{collections::HashMap, time::Duration};
+
+use crate::functions;
+
+/// Marker that records the version an alias pointed to before Cargo Lambda moved it.
+/// It's stored in the alias' description, so it doesn't need any other resource.
+const PREVIOUS_VERSION_MARKER: &str = "[cargo-lambda previous-version=";
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "promote",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/promote.html"
+)]
+pub struct Promote {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Version to move the alias to, defaults to the latest published version
+    #[arg(long)]
+    version: Option<String>,
+
+    /// Percentages of traffic to send to the new version before moving the alias completely, like `10,50`
+    #[arg(long, value_delimiter = ',', value_parser = clap::value_parser!(u8).range(1..100))]
+    steps: Vec<u8>,
+
+    /// Seconds to wait between traffic shifting steps
+    #[arg(long, default_value = "60")]
+    step_interval: u64,
+
+    /// Name of the function to promote
+    #[arg(value_name = "NAME")]
+    name: String,
+}
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "rollback",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/rollback.html"
+)]
+pub struct Rollback {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Name of the function to roll back
+    #[arg(value_name = "NAME")]
+    name: String,
+}
+
+impl Promote {
+    #[tracing::instrument(skip(self), target = "cargo_lambda")]
+    pub async fn run(&self) -> Result<()> {
+        let alias = required_alias(&self.remote_config)?;
+        validate_steps(&self.steps)?;
+
+        let sdk_config = self.remote_config.sdk_config(None).await?;
+        let client = LambdaClient::new(&sdk_config);
+
+        let version = match &self.version {
+            Some(version) => version.clone(),
+            None => latest_version(&client, &self.name).await?,
+        };
+
+        let current = match get_alias(&client, &self.name, alias).await? {
+            Some(current) => current,
+            None => {
+                functions::upsert_alias(&self.name, alias, &version, &client).await?;
+                println!("✅ alias `{alias}` created with version {version}");
+                return Ok(());
+            }
+        };
+
+        let current_version = current.function_version().unwrap_or_default().to_string();
+        if current_version == version && !is_shifting(&current) {
+            println!("✅ alias `{alias}` already points to version {version}");
+            return Ok(());
+        }
+
+        let progress = Progress::start("shifting traffic");
+        for step in &self.steps {
+            progress.set_message(&format!(
+                "sending {step}% of the traffic to version {version}"
+            ));
+            update_alias(
+                &client,
+                &self.name,
+                alias,
+                &current_version,
+                Some((&version, *step)),
+                current.description(),
+            )
+            .await?;
+
+            tokio::time::sleep(Duration::from_secs(self.step_interval)).await;
+        }
+
+        progress.set_message(&format!("moving alias to version {version}"));
+        let description = if current_version == version {
+            current.description().map(String::from)
+        } else {
+            Some(record_previous_version(
+                current.description(),
+                &current_version,
+            ))
+        };
+        let result = update_alias(
+            &client,
+            &self.name,
+            alias,
+            &version,
+            None,
+            description.as_deref(),
+        )
+        .await;
+        progress.finish_and_clear();
+        result?;
+
+        println!("✅ alias `{alias}` moved from version {current_version} to version {version}");
+        Ok(())
+    }
+}
+
+impl Rollback {
+    #[tracing::instrument(skip(self), target = "cargo_lambda")]
+    pub async fn run(&self) -> Result<()> {
+        let alias = required_alias(&self.remote_config)?;
+
+        let sdk_config = self.remote_config.sdk_config(None).await?;
+        let client = LambdaClient::new(&sdk_config);
+
+        let current = get_alias(&client, &self.name, alias)
+            .await?
+            .ok_or_else(|| miette::miette!("the alias `{alias}` doesn't exist"))?;
+        let current_version = current.function_version().unwrap_or_default().to_string();
+
+        // An interrupted promotion leaves part of the traffic in the new version,
+        // rolling back sends all the traffic to the alias' version again.
+        if is_shifting(&current) {
+            update_alias(
+                &client,
+                &self.name,
+                alias,
+                &current_version,
+                None,
+                current.description(),
+            )
+            .await?;
+            println!(
+                "✅ traffic shifting stopped, alias `{alias}` points to version {current_version}"
+            );
+            return Ok(());
+        }
+
+        let previous = current
+            .description()
+            .and_then(previous_version)
+            .ok_or_else(|| {
+                miette::miette!(
+                    "the alias `{alias}` doesn't have a previous version recorded by Cargo Lambda"
+                )
+            })?;
+
+        let description = record_previous_version(current.description(), &current_version);
+        update_alias(
+            &client,
+            &self.name,
+            alias,
+            &previous,
+            None,
+            Some(&description),
+        )
+        .await?;
+
+        println!(
+            "✅ alias `{alias}` rolled back from version {current_version} to version {previous}"
+        );
+        Ok(())
+    }
+}
+
+fn required_alias(remote_config: &RemoteConfig) -> Result<&str> {
+    remote_config
+        .alias
+        .as_deref()
+        .ok_or_else(|| miette::miette!("missing alias, use the flag `--alias` to select it"))
+}
+
+fn validate_steps(steps: &[u8]) -> Result<()> {
+    if steps.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(miette::miette!(
+            "the traffic shifting steps must be in increasing order, like `10,50`"
+        ));
+    }
+    Ok(())
+}
+
+fn is_shifting(alias: &GetAliasOutput) -> bool {
+    alias
+        .routing_config()
+        .and_then(|c| c.additional_version_weights())
+        .is_some_and(|w| !w.is_empty())
+}
+
+async fn get_alias(
+    client: &LambdaClient,
+    name: &str,
+    alias: &str,
+) -> Result<Option<GetAliasOutput>> {
+    match client
+        .get_alias()
+        .function_name(name)
+        .name(alias)
+        .send()
+        .await
+    {
+        Ok(output) => Ok(Some(output)),
+        Err(err) if functions::alias_doesnt_exist_error(&err) => Ok(None),
+        Err(err) => Err(err).into_diagnostic().wrap_err("failed to fetch alias"),
+    }
+}
+
+/// Latest version published for the function. `$LATEST` is not a published version.
+async fn latest_version(client: &LambdaClient, name: &str) -> Result<String> {
+    let mut versions = client
+        .list_versions_by_function()
+        .function_name(name)
+        .into_paginator()
+        .items()
+        .send();
+
+    let mut latest: Option<u64> = None;
+    while let Some(function) = versions.next().await {
+        let function = function
+            .into_diagnostic()
+            .wrap_err("failed to list function versions")?;
+        if let Some(version) = function.version().and_then(|v| v.parse::<u64>().ok()) {
+            latest = latest.max(Some(version));
+        }
+    }
+
+    latest.map(|v| v.to_string()).ok_or_else(|| {
+        miette::miette!(
+            "the function `{name}` doesn't have published versions, deploy it with `cargo lambda deploy` first"
+        )
+    })
+}
+
+/// Point the alias to a version, and optionally send a percentage of the traffic to another version.
+async fn update_alias(
+    client: &LambdaClient,
+    name: &str,
+    alias: &str,
+    version: &str,
+    shift: Option<(&str, u8)>,
+    description: Option<&str>,
+) -> Result<()> {
+    let weights = shift
+        .map(|(version, percentage)| {
+            HashMap::from([(version.to_string(), percentage as f64 / 100.0)])
+        })
+        .unwrap_or_default();
+    let routing_config = AliasRoutingConfiguration::builder()
+        .set_additional_version_weights(Some(weights))
+        .build();
+
+    client
+        .update_alias()
+        .function_name(name)
+        .name(alias)
+        .function_version(version)
+        .routing_config(routing_config)
+        .set_description(description.map(String::from))
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to update alias")?;
+
+    Ok(())
+}
+
+/// Version recorded in the alias' description before Cargo Lambda moved it.
+pub(crate) fn previous_version(description: &str) -> Option<String> {
+    let start = description.find(PREVIOUS_VERSION_MARKER)? + PREVIOUS_VERSION_MARKER.len();
+    let end = description[start..].find(']')?;
+    let version = &description[start..start + end];
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+/// Record the previous version in the alias' description, keeping the rest of the description.
+pub(crate) fn record_previous_version(description: Option<&str>, version: &str) -> String {
+    let description = description.unwrap_or_default();
+    let rest = match description.find(PREVIOUS_VERSION_MARKER) {
+        Some(start) => {
+            let end = description[start..]
+                .find(']')
+                .map(|end| start + end + 1)
+                .unwrap_or(description.len());
+            format!("{}{}", &description[..start], &description[end..])
+        }
+        None => description.to_string(),
+    };
+
+    let rest = rest.trim();
+    if rest.is_empty() {
+        format!("{PREVIOUS_VERSION_MARKER}{version}]")
+    } else {
+        format!("{rest} {PREVIOUS_VERSION_MARKER}{version}]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_previous_version() {
+        assert_eq!(
+            "[cargo-lambda previous-version=3]",
+            record_previous_version(None, "3")
+        );
+        assert_eq!(
+            "production traffic [cargo-lambda previous-version=4]",
+            record_previous_version(
+                Some("production traffic [cargo-lambda previous-version=3]"),
+                "4"
+            )
+        );
+    }
+
+    #[test]
+    fn test_previous_version() {
+        assert_eq!(
+            Some("3".to_string()),
+            previous_version("production traffic [cargo-lambda previous-version=3]")
+        );
+        assert_eq!(None, previous_version("production traffic"));
+        assert_eq!(None, previous_version("[cargo-lambda previous-version=]"));
+    }
+
+    #[test]
+    fn test_validate_steps() {
+        assert!(validate_steps(&[]).is_ok());
+        assert!(validate_steps(&[10, 50]).is_ok());
+        assert!(validate_steps(&[50, 10]).is_err());
+        assert!(validate_steps(&[10, 10]).is_err());
+    }
+}
@@ -0,0 +1,469 @@
+use base64::{engine::general_purpose as b64, Engine as _};
+use cargo_lambda_interactive::progress::Progress;
+use cargo_lambda_metadata::{cargo::deploy::OutputFormat, lambda::Memory};
+use cargo_lambda_remote::{
+    aws_sdk_lambda::{primitives::Blob, types::LogType, Client as LambdaClient},
+    RemoteConfig,
+};
+use clap::{Args, ValueHint};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use serde::Serialize;
+use serde_json::ser::to_string_pretty;
+use std::{
+    fmt::Display,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+use tokio::task::JoinSet;
+
+use crate::functions;
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "bench",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/bench.html"
+)]
+pub struct Bench {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Number of requests to measure
+    #[arg(short = 'n', long, default_value = "100")]
+    requests: usize,
+
+    /// Number of requests to send at the same time
+    #[arg(short, long, default_value = "10")]
+    concurrency: usize,
+
+    /// Number of requests to send before measuring, to warm up the function
+    #[arg(short, long, default_value = "5")]
+    warmup: usize,
+
+    /// JSON payload to send in each request
+    #[arg(long, default_value = "{}", conflicts_with = "data_file")]
+    data_ascii: String,
+
+    /// File with the JSON payload to send in each request
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    data_file: Option<PathBuf>,
+
+    /// Memory sizes to benchmark, separated by commas, like `128,512,1024`.
+    /// The function is updated with each size, and restored to its original size at the end
+    #[arg(long, value_delimiter = ',')]
+    memory: Vec<Memory>,
+
+    /// Format to render the output (text, or json)
+    #[arg(short, long)]
+    output_format: Option<OutputFormat>,
+
+    /// Name of the function to benchmark
+    #[arg(value_name = "NAME")]
+    name: String,
+}
+
+/// Measurements of one invocation. Durations are in milliseconds.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Sample {
+    latency: f64,
+    report: Option<Report>,
+    error: bool,
+}
+
+/// Values in the `REPORT` line that Lambda writes at the end of each invocation.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Report {
+    duration: f64,
+    billed_duration: f64,
+    max_memory_used: Option<u64>,
+    init_duration: Option<f64>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct Percentiles {
+    p50: f64,
+    p95: f64,
+    p99: f64,
+}
+
+/// Results of the benchmark for one configuration of the function.
+#[derive(Debug, Default, Serialize)]
+struct BenchResult {
+    memory: i32,
+    architecture: String,
+    requests: usize,
+    errors: usize,
+    cold_starts: usize,
+    latency: Percentiles,
+    duration: Percentiles,
+    avg_billed_duration: f64,
+    max_memory_used: Option<u64>,
+}
+
+impl Bench {
+    #[tracing::instrument(skip(self), target = "cargo_lambda")]
+    pub async fn run(&self) -> Result<()> {
+        if self.concurrency == 0 || self.requests == 0 {
+            return Err(miette::miette!(
+                "the number of requests and the concurrency must be greater than zero"
+            ));
+        }
+
+        // Published versions are immutable, only `$LATEST` can be benchmarked with other memory sizes.
+        if !self.memory.is_empty() && self.remote_config.alias.is_some() {
+            return Err(miette::miette!(
+                "the flag `--memory` cannot be used with `--alias`, published versions cannot be updated"
+            ));
+        }
+
+        let payload = match &self.data_file {
+            Some(path) => std::fs::read_to_string(path)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to read {}", path.display()))?,
+            None => self.data_ascii.clone(),
+        };
+
+        let sdk_config = self.remote_config.sdk_config(None).await?;
+        let client = LambdaClient::new(&sdk_config);
+
+        let conf = client
+            .get_function_configuration()
+            .function_name(&self.name)
+            .set_qualifier(self.remote_config.alias.clone())
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err("failed to fetch the function configuration")?;
+        let original_memory = conf.memory_size().unwrap_or_default();
+        let architecture = conf
+            .architectures()
+            .first()
+            .map(|a| a.as_str().to_string())
+            .unwrap_or_else(|| "x86_64".to_string());
+
+        let mut results = Vec::new();
+        let progress = Progress::start("benchmarking function");
+
+        if self.memory.is_empty() {
+            let result = self.bench(&client, &payload, &progress).await;
+            results.push(result.map(|r| BenchResult {
+                memory: original_memory,
+                architecture: architecture.clone(),
+                ..r
+            }));
+        } else {
+            for memory in &self.memory {
+                let memory: i32 = memory.into();
+                let result = match self.update_memory(&client, memory, &progress).await {
+                    Ok(()) => self.bench(&client, &payload, &progress).await,
+                    Err(err) => Err(err),
+                };
+                let failed = result.is_err();
+                results.push(result.map(|r| BenchResult {
+                    memory,
+                    architecture: architecture.clone(),
+                    ..r
+                }));
+                if failed {
+                    break;
+                }
+            }
+
+            progress.set_message("restoring the function's memory");
+            self.update_memory(&client, original_memory, &progress)
+                .await?;
+        }
+        progress.finish_and_clear();
+
+        let results = results.into_iter().collect::<Result<Vec<_>>>()?;
+
+        match self.output_format.clone().unwrap_or_default() {
+            OutputFormat::Text => {
+                for result in &results {
+                    print!("{result}");
+                }
+            }
+            OutputFormat::Json => {
+                let text = to_string_pretty(&results)
+                    .into_diagnostic()
+                    .wrap_err("failed to serialize output into json")?;
+                println!("{text}")
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn update_memory(
+        &self,
+        client: &LambdaClient,
+        memory: i32,
+        progress: &Progress,
+    ) -> Result<()> {
+        progress.set_message(&format!("updating the function's memory to {memory} MB"));
+        client
+            .update_function_configuration()
+            .function_name(&self.name)
+            .memory_size(memory)
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err("failed to update the function's memory")?;
+
+        // Updating the configuration makes the next invocations start new instances of the function.
+        functions::wait_for_ready_state(client, &self.name, &None, progress).await
+    }
+
+    async fn bench(
+        &self,
+        client: &LambdaClient,
+        payload: &str,
+        progress: &Progress,
+    ) -> Result<BenchResult> {
+        if self.warmup > 0 {
+            progress.set_message(&format!("sending {} warmup requests", self.warmup));
+            self.load(client, payload, self.warmup).await?;
+        }
+
+        progress.set_message(&format!(
+            "sending {} requests, {} at a time",
+            self.requests, self.concurrency
+        ));
+        let samples = self.load(client, payload, self.requests).await?;
+        Ok(BenchResult::from_samples(&samples))
+    }
+
+    /// Send a number of requests, with as many requests in flight as the concurrency allows.
+    async fn load(
+        &self,
+        client: &LambdaClient,
+        payload: &str,
+        requests: usize,
+    ) -> Result<Vec<Sample>> {
+        let sent = Arc::new(AtomicUsize::new(0));
+        let mut workers = JoinSet::new();
+
+        for _ in 0..self.concurrency.min(requests) {
+            let client = client.clone();
+            let sent = sent.clone();
+            let name = self.name.clone();
+            let alias = self.remote_config.alias.clone();
+            let payload = payload.to_string();
+
+            workers.spawn(async move {
+                let mut samples = Vec::new();
+                while sent.fetch_add(1, Ordering::SeqCst) < requests {
+                    samples.push(invoke(&client, &name, &alias, &payload).await?);
+                }
+                Ok::<_, miette::Report>(samples)
+            });
+        }
+
+        let mut samples = Vec::with_capacity(requests);
+        while let Some(result) = workers.join_next().await {
+            samples.extend(result.into_diagnostic()??);
+        }
+        Ok(samples)
+    }
+}
+
+async fn invoke(
+    client: &LambdaClient,
+    name: &str,
+    alias: &Option<String>,
+    payload: &str,
+) -> Result<Sample> {
+    let start = Instant::now();
+    let output = client
+        .invoke()
+        .function_name(name)
+        .set_qualifier(alias.clone())
+        .payload(Blob::new(payload.as_bytes()))
+        .log_type(LogType::Tail)
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to invoke the function")?;
+    let latency = start.elapsed().as_secs_f64() * 1000.0;
+
+    let report = output
+        .log_result()
+        .and_then(|logs| b64::STANDARD.decode(logs).ok())
+        .and_then(|logs| String::from_utf8(logs).ok())
+        .and_then(|logs| parse_report(&logs));
+
+    Ok(Sample {
+        latency,
+        report,
+        error: output.function_error().is_some(),
+    })
+}
+
+/// Parse the `REPORT` line in the invocation logs, like:
+/// `REPORT RequestId: 1 Duration: 1.61 ms Billed Duration: 2 ms Memory Size: 128 MB Max Memory Used: 20 MB Init Duration: 30.12 ms`
+fn parse_report(logs: &str) -> Option<Report> {
+    let line = logs.lines().rev().find(|l| l.starts_with("REPORT "))?;
+
+    let field = |name: &str| -> Option<&str> {
+        line.split('\t')
+            .flat_map(|f| f.split("  "))
+            .find_map(|f| f.trim().strip_prefix(name))
+            .and_then(|v| v.split_whitespace().next())
+    };
+
+    Some(Report {
+        duration: field("Duration: ")?.parse().ok()?,
+        billed_duration: field("Billed Duration: ")?.parse().ok()?,
+        max_memory_used: field("Max Memory Used: ").and_then(|v| v.parse().ok()),
+        init_duration: field("Init Duration: ").and_then(|v| v.parse().ok()),
+    })
+}
+
+/// Nearest-rank percentile of values sorted in ascending order.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+impl Percentiles {
+    fn new(mut values: Vec<f64>) -> Percentiles {
+        values.sort_by(f64::total_cmp);
+        Percentiles {
+            p50: percentile(&values, 50.0),
+            p95: percentile(&values, 95.0),
+            p99: percentile(&values, 99.0),
+        }
+    }
+}
+
+impl BenchResult {
+    fn from_samples(samples: &[Sample]) -> BenchResult {
+        let reports = samples
+            .iter()
+            .filter_map(|s| s.report.as_ref())
+            .collect::<Vec<_>>();
+
+        let avg_billed_duration = if reports.is_empty() {
+            0.0
+        } else {
+            reports.iter().map(|r| r.billed_duration).sum::<f64>() / reports.len() as f64
+        };
+
+        BenchResult {
+            requests: samples.len(),
+            errors: samples.iter().filter(|s| s.error).count(),
+            cold_starts: reports.iter().filter(|r| r.init_duration.is_some()).count(),
+            latency: Percentiles::new(samples.iter().map(|s| s.latency).collect()),
+            duration: Percentiles::new(reports.iter().map(|r| r.duration).collect()),
+            avg_billed_duration,
+            max_memory_used: reports.iter().filter_map(|r| r.max_memory_used).max(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Display for BenchResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "📊 {} MB, {}", self.memory, self.architecture)?;
+        writeln!(
+            f,
+            "  requests: {} ({} errors, {} cold starts)",
+            self.requests, self.errors, self.cold_starts
+        )?;
+        writeln!(
+            f,
+            "  latency: p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms",
+            self.latency.p50, self.latency.p95, self.latency.p99
+        )?;
+        writeln!(
+            f,
+            "  duration: p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms",
+            self.duration.p50, self.duration.p95, self.duration.p99
+        )?;
+        writeln!(
+            f,
+            "  average billed duration: {:.2} ms",
+            self.avg_billed_duration
+        )?;
+        if let Some(memory) = self.max_memory_used {
+            writeln!(f, "  max memory used: {memory} MB")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(latency: f64, duration: f64, init_duration: Option<f64>) -> Sample {
+        Sample {
+            latency,
+            report: Some(Report {
+                duration,
+                billed_duration: duration.ceil(),
+                max_memory_used: Some(20),
+                init_duration,
+            }),
+            error: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_report() {
+        let logs = "START RequestId: 1 Version: $LATEST\nhello\nEND RequestId: 1\nREPORT RequestId: 1\tDuration: 1.61 ms\tBilled Duration: 2 ms\tMemory Size: 128 MB\tMax Memory Used: 20 MB\tInit Duration: 30.12 ms\t\n";
+        assert_eq!(
+            Some(Report {
+                duration: 1.61,
+                billed_duration: 2.0,
+                max_memory_used: Some(20),
+                init_duration: Some(30.12),
+            }),
+            parse_report(logs)
+        );
+
+        let warm = "REPORT RequestId: 2\tDuration: 0.98 ms\tBilled Duration: 1 ms\tMemory Size: 128 MB\tMax Memory Used: 20 MB\t";
+        assert_eq!(None, parse_report(warm).unwrap().init_duration);
+
+        assert_eq!(None, parse_report("START RequestId: 1"));
+    }
+
+    #[test]
+    fn test_percentile() {
+        let values = (1..=100).map(f64::from).collect::<Vec<_>>();
+        assert_eq!(50.0, percentile(&values, 50.0));
+        assert_eq!(95.0, percentile(&values, 95.0));
+        assert_eq!(99.0, percentile(&values, 99.0));
+        assert_eq!(7.0, percentile(&[7.0], 99.0));
+        assert_eq!(0.0, percentile(&[], 50.0));
+    }
+
+    #[test]
+    fn test_result_from_samples() {
+        let samples = vec![
+            sample(40.0, 30.5, Some(100.0)),
+            sample(12.0, 1.2, None),
+            sample(10.0, 0.8, None),
+            Sample {
+                latency: 11.0,
+                error: true,
+                ..Default::default()
+            },
+        ];
+
+        let result = BenchResult::from_samples(&samples);
+        assert_eq!(4, result.requests);
+        assert_eq!(1, result.errors);
+        assert_eq!(1, result.cold_starts);
+        assert_eq!(11.0, result.latency.p50);
+        assert_eq!(1.2, result.duration.p50);
+        assert_eq!((31.0 + 2.0 + 1.0) / 3.0, result.avg_billed_duration);
+        assert_eq!(Some(20), result.max_memory_used);
+    }
+}
@@ -0,0 +1,151 @@
+use cargo_lambda_metadata::cargo::deploy::CostProfile;
+use cargo_lambda_remote::aws_sdk_lambda::types::Architecture;
+use serde::Serialize;
+use std::fmt::Display;
+
+/// Price of one million requests, in USD.
+const REQUESTS_PRICE: f64 = 0.20;
+
+/// Price of one GB-second of compute time on x86_64, in USD.
+const X86_64_GB_SECOND_PRICE: f64 = 0.0000166667;
+
+/// Price of one GB-second of compute time on arm64, in USD.
+const ARM64_GB_SECOND_PRICE: f64 = 0.0000133334;
+
+/// Memory size of functions deployed without a memory setting, in MB.
+const DEFAULT_MEMORY_SIZE: i32 = 128;
+
+/// Monthly cost of a function in one architecture.
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct ArchitectureCost {
+    architecture: String,
+    requests_cost: f64,
+    compute_cost: f64,
+    monthly_cost: f64,
+}
+
+impl ArchitectureCost {
+    fn new(profile: &CostProfile, architecture: &Architecture, memory_size: i32) -> Self {
+        let gb_second_price = match architecture {
+            Architecture::Arm64 => ARM64_GB_SECOND_PRICE,
+            _ => X86_64_GB_SECOND_PRICE,
+        };
+
+        // Lambda bills the duration of each invocation rounded up to the nearest millisecond.
+        let gb_seconds =
+            profile.requests * (profile.avg_ms.ceil() / 1000.0) * (memory_size as f64 / 1024.0);
+
+        let requests_cost = profile.requests / 1_000_000.0 * REQUESTS_PRICE;
+        let compute_cost = gb_seconds * gb_second_price;
+
+        ArchitectureCost {
+            architecture: architecture.as_str().to_string(),
+            requests_cost,
+            compute_cost,
+            monthly_cost: requests_cost + compute_cost,
+        }
+    }
+}
+
+/// Estimate of the monthly cost of a function, with on-demand prices in us-east-1,
+/// and without the free tier. It also includes the cost in the other architecture,
+/// to compare them.
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct CostEstimate {
+    memory_size: i32,
+    requests: f64,
+    avg_ms: f64,
+    #[serde(flatten)]
+    cost: ArchitectureCost,
+    alternative: ArchitectureCost,
+}
+
+impl CostEstimate {
+    pub(crate) fn new(
+        profile: &CostProfile,
+        architecture: &Architecture,
+        memory_size: Option<i32>,
+    ) -> Self {
+        let memory_size = memory_size.unwrap_or(DEFAULT_MEMORY_SIZE);
+        let alternative = match architecture {
+            Architecture::Arm64 => Architecture::X8664,
+            _ => Architecture::Arm64,
+        };
+
+        CostEstimate {
+            memory_size,
+            requests: profile.requests,
+            avg_ms: profile.avg_ms,
+            cost: ArchitectureCost::new(profile, architecture, memory_size),
+            alternative: ArchitectureCost::new(profile, &alternative, memory_size),
+        }
+    }
+}
+
+impl Display for CostEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "💰 estimated monthly cost: ${:.2} ({}, {}MB, {} requests of {}ms)",
+            self.cost.monthly_cost,
+            self.cost.architecture,
+            self.memory_size,
+            self.requests,
+            self.avg_ms
+        )?;
+        writeln!(
+            f,
+            "  - requests: ${:.2}, compute: ${:.2}",
+            self.cost.requests_cost, self.cost.compute_cost
+        )?;
+        writeln!(
+            f,
+            "  - with {}: ${:.2}",
+            self.alternative.architecture, self.alternative.monthly_cost
+        )?;
+        write!(
+            f,
+            "ℹ️  estimated with on-demand prices in us-east-1, without the free tier"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_price(expected: f64, actual: f64) {
+        assert!(
+            (expected - actual).abs() < 0.005,
+            "expected ${expected:.2}, got ${actual:.2}"
+        );
+    }
+
+    #[test]
+    fn test_cost_estimate() {
+        let profile: CostProfile = "requests=1e6,avg-ms=120".parse().unwrap();
+
+        let estimate = CostEstimate::new(&profile, &Architecture::X8664, Some(1024));
+        assert_eq!("x86_64", estimate.cost.architecture);
+        assert_price(0.20, estimate.cost.requests_cost);
+        assert_price(2.00, estimate.cost.compute_cost);
+        assert_price(2.20, estimate.cost.monthly_cost);
+        assert_eq!("arm64", estimate.alternative.architecture);
+        assert_price(1.80, estimate.alternative.monthly_cost);
+
+        let estimate = CostEstimate::new(&profile, &Architecture::Arm64, None);
+        assert_eq!(DEFAULT_MEMORY_SIZE, estimate.memory_size);
+        assert_price(0.40, estimate.cost.monthly_cost);
+        assert_eq!("x86_64", estimate.alternative.architecture);
+    }
+
+    #[test]
+    fn test_billed_duration_is_rounded_up() {
+        let rounded: CostProfile = "requests=1e6,avg-ms=1.2".parse().unwrap();
+        let exact: CostProfile = "requests=1e6,avg-ms=2".parse().unwrap();
+        assert_eq!(
+            CostEstimate::new(&rounded, &Architecture::Arm64, Some(512)).cost,
+            CostEstimate::new(&exact, &Architecture::Arm64, Some(512)).cost
+        );
+    }
+}
@@ -0,0 +1,348 @@
+use aws_sdk_cloudwatchlogs::Client as LogsClient;
+use cargo_lambda_interactive::{
+    is_stdin_tty, is_user_cancellation_error, progress::Progress, Confirm,
+};
+use cargo_lambda_remote::{
+    aws_sdk_config::SdkConfig, aws_sdk_lambda::Client as LambdaClient, RemoteConfig,
+};
+use clap::Args;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::fmt::Display;
+
+use crate::{
+    functions,
+    roles::{self, ROLE_NAME_PREFIX},
+};
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "delete",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/delete.html"
+)]
+pub struct Delete {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Delete only the function URL configuration, and keep the function
+    #[arg(long)]
+    function_url: bool,
+
+    /// Delete the function's log group too, if it's the default log group for the function
+    #[arg(long)]
+    log_group: bool,
+
+    /// Delete the function's execution role too, if Cargo Lambda created it when the function was deployed
+    #[arg(long)]
+    role: bool,
+
+    /// Delete the resources without asking for confirmation
+    #[arg(short, long)]
+    yes: bool,
+
+    /// Name of the function to delete
+    #[arg(value_name = "NAME")]
+    name: String,
+}
+
+/// Resource that the command deletes.
+#[derive(Clone, Debug, PartialEq)]
+enum Resource {
+    Function(String),
+    Alias(String, String),
+    FunctionUrl(String, Option<String>),
+    LogGroup(String),
+    Role(String),
+}
+
+impl Display for Resource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Resource::Function(name) => {
+                write!(
+                    f,
+                    "function `{name}`, with all its versions, aliases, and function URLs"
+                )
+            }
+            Resource::Alias(name, alias) => write!(f, "alias `{alias}` of the function `{name}`"),
+            Resource::FunctionUrl(name, Some(alias)) => {
+                write!(
+                    f,
+                    "function URL of the alias `{alias}` of the function `{name}`"
+                )
+            }
+            Resource::FunctionUrl(name, None) => write!(f, "function URL of the function `{name}`"),
+            Resource::LogGroup(group) => write!(f, "log group `{group}`"),
+            Resource::Role(role) => write!(f, "execution role `{role}`"),
+        }
+    }
+}
+
+impl Delete {
+    #[tracing::instrument(skip(self), target = "cargo_lambda")]
+    pub async fn run(&self) -> Result<()> {
+        let sdk_config = self.remote_config.sdk_config(None).await?;
+        let client = LambdaClient::new(&sdk_config);
+
+        let progress = Progress::start("loading function configuration");
+        let conf = client
+            .get_function_configuration()
+            .function_name(&self.name)
+            .set_qualifier(self.remote_config.alias.clone())
+            .send()
+            .await;
+        progress.finish_and_clear();
+
+        let conf = match conf {
+            Ok(conf) => conf,
+            Err(err)
+                if err
+                    .as_service_error()
+                    .is_some_and(|e| e.is_resource_not_found_exception()) =>
+            {
+                return Err(miette::miette!(
+                    "the function `{}` doesn't exist",
+                    self.name
+                ));
+            }
+            Err(err) => {
+                return Err(err)
+                    .into_diagnostic()
+                    .wrap_err("failed to fetch the function configuration")
+            }
+        };
+
+        let log_group = conf.logging_config().and_then(|c| c.log_group());
+        let (resources, skipped) = self.plan(conf.role(), log_group);
+
+        for message in &skipped {
+            eprintln!("⚠️  {message}");
+        }
+
+        println!("🗑️  The following resources will be deleted:");
+        for resource in &resources {
+            println!("  - {resource}");
+        }
+
+        if !self.confirm()? {
+            return Ok(());
+        }
+
+        let progress = Progress::start("deleting resources");
+        let result = delete_resources(&sdk_config, &client, &resources, &progress).await;
+        progress.finish_and_clear();
+        result?;
+
+        println!("✅ Resources deleted");
+        Ok(())
+    }
+
+    /// Resources to delete, and the messages for the resources that are not deleted.
+    fn plan(&self, role: Option<&str>, log_group: Option<&str>) -> (Vec<Resource>, Vec<String>) {
+        let name = self.name.clone();
+        let alias = self.remote_config.alias.clone();
+
+        let mut resources = Vec::new();
+        let mut skipped = Vec::new();
+
+        if self.function_url {
+            resources.push(Resource::FunctionUrl(name, alias));
+            return (resources, skipped);
+        }
+
+        if let Some(alias) = alias {
+            // Deleting an alias keeps the function, its logs, and its role.
+            resources.push(Resource::FunctionUrl(name.clone(), Some(alias.clone())));
+            resources.push(Resource::Alias(name, alias));
+            return (resources, skipped);
+        }
+
+        resources.push(Resource::Function(name.clone()));
+
+        if self.log_group {
+            let default_group = format!("/aws/lambda/{name}");
+            match log_group {
+                Some(group) if group != default_group => skipped.push(format!(
+                    "the log group `{group}` is not deleted, it can be shared with other functions"
+                )),
+                _ => resources.push(Resource::LogGroup(default_group)),
+            }
+        }
+
+        if self.role {
+            let role_name = role
+                .and_then(|arn| arn.rsplit('/').next())
+                .unwrap_or_default();
+            if role_name.starts_with(ROLE_NAME_PREFIX) {
+                resources.push(Resource::Role(role_name.to_string()));
+            } else if !role_name.is_empty() {
+                skipped.push(format!(
+                    "the execution role `{role_name}` is not deleted, it was not created by Cargo Lambda"
+                ));
+            }
+        }
+
+        (resources, skipped)
+    }
+
+    fn confirm(&self) -> Result<bool> {
+        if self.yes {
+            return Ok(true);
+        }
+
+        if !is_stdin_tty() {
+            return Err(miette::miette!(
+                "confirmation required to delete the resources, use the flag `--yes` to delete them without confirmation"
+            ));
+        }
+
+        match Confirm::new("Do you want to delete these resources?")
+            .with_default(false)
+            .prompt()
+        {
+            Err(err) if is_user_cancellation_error(&err) => Ok(false),
+            result => result.into_diagnostic(),
+        }
+    }
+}
+
+async fn delete_resources(
+    sdk_config: &SdkConfig,
+    client: &LambdaClient,
+    resources: &[Resource],
+    progress: &Progress,
+) -> Result<()> {
+    for resource in resources {
+        progress.set_message(&format!("deleting {resource}"));
+
+        match resource {
+            Resource::Function(name) => {
+                client
+                    .delete_function()
+                    .function_name(name)
+                    .send()
+                    .await
+                    .into_diagnostic()
+                    .wrap_err("failed to delete function")?;
+            }
+            Resource::Alias(name, alias) => {
+                client
+                    .delete_alias()
+                    .function_name(name)
+                    .name(alias)
+                    .send()
+                    .await
+                    .into_diagnostic()
+                    .wrap_err("failed to delete alias")?;
+            }
+            Resource::FunctionUrl(name, alias) => {
+                functions::delete_function_url_config(name, alias, client).await?;
+            }
+            Resource::LogGroup(group) => delete_log_group(sdk_config, group).await?,
+            Resource::Role(role) => roles::delete(sdk_config, role).await?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Delete a log group. Functions that never ran don't have a log group.
+async fn delete_log_group(sdk_config: &SdkConfig, group: &str) -> Result<()> {
+    let client = LogsClient::new(sdk_config);
+    let result = client.delete_log_group().log_group_name(group).send().await;
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(err)
+            if err
+                .as_service_error()
+                .is_some_and(|e| e.is_resource_not_found_exception()) =>
+        {
+            Ok(())
+        }
+        Err(err) => Err(err)
+            .into_diagnostic()
+            .wrap_err("failed to delete log group"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delete(name: &str) -> Delete {
+        Delete {
+            remote_config: RemoteConfig::default(),
+            function_url: false,
+            log_group: false,
+            role: false,
+            yes: false,
+            name: name.into(),
+        }
+    }
+
+    #[test]
+    fn test_plan_function() {
+        let cmd = Delete {
+            log_group: true,
+            role: true,
+            ..delete("orders")
+        };
+
+        let (resources, skipped) = cmd.plan(
+            Some("arn:aws:iam::123456789012:role/cargo-lambda-role-0b1c2d3e"),
+            None,
+        );
+        assert_eq!(
+            vec![
+                Resource::Function("orders".into()),
+                Resource::LogGroup("/aws/lambda/orders".into()),
+                Resource::Role("cargo-lambda-role-0b1c2d3e".into()),
+            ],
+            resources
+        );
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn test_plan_skips_shared_resources() {
+        let cmd = Delete {
+            log_group: true,
+            role: true,
+            ..delete("orders")
+        };
+
+        let (resources, skipped) = cmd.plan(
+            Some("arn:aws:iam::123456789012:role/service-role/orders-role"),
+            Some("/shared/functions"),
+        );
+        assert_eq!(vec![Resource::Function("orders".into())], resources);
+        assert_eq!(2, skipped.len());
+        assert!(skipped[0].contains("/shared/functions"));
+        assert!(skipped[1].contains("orders-role"));
+    }
+
+    #[test]
+    fn test_plan_alias_and_function_url() {
+        let mut cmd = delete("orders");
+        cmd.remote_config.alias = Some("live".into());
+        let (resources, _) = cmd.plan(None, None);
+        assert_eq!(
+            vec![
+                Resource::FunctionUrl("orders".into(), Some("live".into())),
+                Resource::Alias("orders".into(), "live".into()),
+            ],
+            resources
+        );
+
+        let cmd = Delete {
+            function_url: true,
+            log_group: true,
+            ..delete("orders")
+        };
+        let (resources, _) = cmd.plan(None, None);
+        assert_eq!(
+            vec![Resource::FunctionUrl("orders".into(), None)],
+            resources
+        );
+    }
+}
@@ -4,7 +4,7 @@ use miette::Result;
 use serde::Serialize;
 use std::{collections::HashMap, fmt::Display, path::PathBuf};
 
-use crate::binary_name_or_default;
+use crate::{binary_name_or_default, cost::CostEstimate};
 
 #[derive(PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -34,6 +34,8 @@ pub(crate) struct DeployOutput {
     bucket: Option<String>,
     config: FunctionDeployConfig,
     binary_modified_at: BinaryModifiedAt,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost_estimate: Option<CostEstimate>,
 }
 
 impl Display for DeployOutput {
@@ -93,6 +95,10 @@ impl Display for DeployOutput {
             }
         }
 
+        if let Some(estimate) = &self.cost_estimate {
+            writeln!(f, "{estimate}")?;
+        }
+
         Ok(())
     }
 }
@@ -110,6 +116,11 @@ impl DeployOutput {
             (DeployKind::Function, binary_name, vec![])
         };
 
+        let cost_estimate = config.estimate_cost.as_ref().map(|profile| {
+            let memory = config.function_config.memory.as_ref().map(Into::into);
+            CostEstimate::new(profile, &archive.architecture(), memory)
+        });
+
         Ok(DeployOutput {
             kind,
             name,
@@ -121,6 +132,7 @@ impl DeployOutput {
             config: config.function_config.clone(),
             files: archive.list()?,
             binary_modified_at: archive.binary_modified_at.clone(),
+            cost_estimate,
         })
     }
 }
@@ -0,0 +1,475 @@
+use cargo_lambda_interactive::{
+    is_stdin_tty, is_user_cancellation_error, progress::Progress, Confirm,
+};
+use cargo_lambda_metadata::env::EnvOptions;
+use cargo_lambda_remote::{
+    aws_sdk_lambda::{types::Environment, Client as LambdaClient},
+    RemoteConfig,
+};
+use clap::{Args, Subcommand, ValueHint};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{Display, Write as _},
+    path::{Path, PathBuf},
+};
+
+const DEFAULT_ENV_FILE: &str = ".env";
+
+type Variables = BTreeMap<String, String>;
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "env",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/env.html"
+)]
+pub struct Env {
+    #[command(subcommand)]
+    command: EnvCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum EnvCommand {
+    /// Print the function's environment variables, or the value of one variable
+    Get(Get),
+    /// Add or update environment variables in the function
+    Set(Set),
+    /// Remove environment variables from the function
+    Unset(Unset),
+    /// Write the function's environment variables into a local file
+    Pull(Pull),
+    /// Replace the function's environment variables with the variables in a local file
+    Push(Push),
+}
+
+/// Function to manage the environment variables of.
+#[derive(Args, Clone, Debug)]
+struct Target {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Name of the function
+    #[arg(value_name = "NAME")]
+    name: String,
+}
+
+#[derive(Args, Clone, Debug)]
+struct Get {
+    #[command(flatten)]
+    target: Target,
+
+    /// Name of the variable to print, prints all the variables if it's not present
+    #[arg(value_name = "KEY")]
+    key: Option<String>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct Set {
+    #[command(flatten)]
+    target: Target,
+
+    /// Variables to set in KEY=VALUE format
+    #[arg(value_name = "KEY=VALUE", required = true)]
+    vars: Vec<String>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct Unset {
+    #[command(flatten)]
+    target: Target,
+
+    /// Names of the variables to remove
+    #[arg(value_name = "KEY", required = true)]
+    keys: Vec<String>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct Pull {
+    #[command(flatten)]
+    target: Target,
+
+    /// Path to the file to write the variables into
+    #[arg(long, default_value = DEFAULT_ENV_FILE, value_hint = ValueHint::FilePath)]
+    env_file: PathBuf,
+
+    /// Overwrite the file without asking for confirmation
+    #[arg(short, long)]
+    yes: bool,
+}
+
+#[derive(Args, Clone, Debug)]
+struct Push {
+    #[command(flatten)]
+    target: Target,
+
+    /// Path to the file to read the variables from
+    #[arg(long, default_value = DEFAULT_ENV_FILE, value_hint = ValueHint::FilePath)]
+    env_file: PathBuf,
+
+    /// Update the function without asking for confirmation
+    #[arg(short, long)]
+    yes: bool,
+}
+
+/// Changes between two sets of variables. Values are not included,
+/// so secrets are not printed in the terminal.
+#[derive(Debug, Default, PartialEq)]
+struct Diff {
+    added: Vec<String>,
+    changed: Vec<String>,
+    removed: Vec<String>,
+}
+
+impl Env {
+    #[tracing::instrument(skip(self), target = "cargo_lambda")]
+    pub async fn run(&self) -> Result<()> {
+        match &self.command {
+            EnvCommand::Get(cmd) => cmd.run().await,
+            EnvCommand::Set(cmd) => cmd.run().await,
+            EnvCommand::Unset(cmd) => cmd.run().await,
+            EnvCommand::Pull(cmd) => cmd.run().await,
+            EnvCommand::Push(cmd) => cmd.run().await,
+        }
+    }
+}
+
+impl Get {
+    async fn run(&self) -> Result<()> {
+        let client = self.target.client().await?;
+        let vars = self.target.load(&client, true).await?;
+
+        match &self.key {
+            Some(key) => {
+                let value = vars.get(key).ok_or_else(|| {
+                    miette::miette!(
+                        "the variable `{key}` is not set in the function `{}`",
+                        self.target.name
+                    )
+                })?;
+                println!("{value}");
+            }
+            None => print!("{}", format_env_file(&vars)),
+        }
+
+        Ok(())
+    }
+}
+
+impl Set {
+    async fn run(&self) -> Result<()> {
+        let options = EnvOptions {
+            env_var: Some(self.vars.clone()),
+            env_file: None,
+        };
+        let new_vars = options.lambda_environment(&HashMap::new())?;
+
+        let client = self.target.client().await?;
+        let current = self.target.load(&client, false).await?;
+
+        let mut vars = current.clone();
+        vars.extend(new_vars);
+        self.target.update(&client, &current, &vars).await
+    }
+}
+
+impl Unset {
+    async fn run(&self) -> Result<()> {
+        let client = self.target.client().await?;
+        let current = self.target.load(&client, false).await?;
+
+        let mut vars = current.clone();
+        for key in &self.keys {
+            if vars.remove(key).is_none() {
+                eprintln!(
+                    "⚠️  the variable `{key}` is not set in the function `{}`",
+                    self.target.name
+                );
+            }
+        }
+
+        self.target.update(&client, &current, &vars).await
+    }
+}
+
+impl Pull {
+    async fn run(&self) -> Result<()> {
+        let client = self.target.client().await?;
+        let vars = self.target.load(&client, true).await?;
+
+        if self.env_file.exists() {
+            let local = read_env_file(&self.env_file)?;
+            let diff = Diff::new(&local, &vars);
+            if diff.is_empty() {
+                println!("✅ {} is up to date", self.env_file.display());
+                return Ok(());
+            }
+
+            println!("🔍 Changes in {}:", self.env_file.display());
+            print!("{diff}");
+            if !confirm(self.yes, "Do you want to overwrite the file?")? {
+                return Ok(());
+            }
+        }
+
+        std::fs::write(&self.env_file, format_env_file(&vars))
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to write {}", self.env_file.display()))?;
+
+        println!(
+            "✅ {} variables written to {}",
+            vars.len(),
+            self.env_file.display()
+        );
+        Ok(())
+    }
+}
+
+impl Push {
+    async fn run(&self) -> Result<()> {
+        if !self.env_file.is_file() {
+            return Err(miette::miette!(
+                "the file {} doesn't exist",
+                self.env_file.display()
+            ));
+        }
+        let vars = read_env_file(&self.env_file)?;
+
+        let client = self.target.client().await?;
+        let current = self.target.load(&client, false).await?;
+
+        let diff = Diff::new(&current, &vars);
+        if diff.is_empty() {
+            println!("✅ the function `{}` is up to date", self.target.name);
+            return Ok(());
+        }
+
+        println!("🔍 Changes in the function `{}`:", self.target.name);
+        print!("{diff}");
+        if !confirm(self.yes, "Do you want to update the function?")? {
+            return Ok(());
+        }
+
+        self.target.update(&client, &current, &vars).await
+    }
+}
+
+impl Target {
+    async fn client(&self) -> Result<LambdaClient> {
+        let sdk_config = self.remote_config.sdk_config(None).await?;
+        Ok(LambdaClient::new(&sdk_config))
+    }
+
+    /// Load the function's variables. Published versions can be read, but only
+    /// the unpublished version of the function, `$LATEST`, can be updated.
+    async fn load(&self, client: &LambdaClient, with_alias: bool) -> Result<Variables> {
+        let qualifier = with_alias
+            .then(|| self.remote_config.alias.clone())
+            .flatten();
+
+        let progress = Progress::start("loading function configuration");
+        let conf = client
+            .get_function_configuration()
+            .function_name(&self.name)
+            .set_qualifier(qualifier)
+            .send()
+            .await;
+        progress.finish_and_clear();
+
+        let conf = conf
+            .into_diagnostic()
+            .wrap_err("failed to fetch the function configuration")?;
+
+        Ok(conf
+            .environment
+            .and_then(|e| e.variables)
+            .unwrap_or_default()
+            .into_iter()
+            .collect())
+    }
+
+    async fn update(
+        &self,
+        client: &LambdaClient,
+        current: &Variables,
+        vars: &Variables,
+    ) -> Result<()> {
+        if current == vars {
+            println!("✅ the function `{}` is up to date", self.name);
+            return Ok(());
+        }
+
+        let environment = Environment::builder()
+            .set_variables(Some(vars.clone().into_iter().collect()))
+            .build();
+
+        let progress = Progress::start("updating environment variables");
+        let result = client
+            .update_function_configuration()
+            .function_name(&self.name)
+            .environment(environment)
+            .send()
+            .await;
+        progress.finish_and_clear();
+
+        result
+            .into_diagnostic()
+            .wrap_err("failed to update the function configuration")?;
+
+        println!(
+            "✅ environment variables updated in the function `{}`",
+            self.name
+        );
+        if self.remote_config.alias.is_some() {
+            println!("   deploy the function to publish a new version for the alias");
+        }
+        Ok(())
+    }
+}
+
+impl Diff {
+    fn new(from: &Variables, to: &Variables) -> Diff {
+        let mut diff = Diff::default();
+        for (key, value) in to {
+            match from.get(key) {
+                None => diff.added.push(key.clone()),
+                Some(current) if current != value => diff.changed.push(key.clone()),
+                _ => {}
+            }
+        }
+        diff.removed = from
+            .keys()
+            .filter(|key| !to.contains_key(*key))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for key in &self.added {
+            writeln!(f, "  + {key}")?;
+        }
+        for key in &self.changed {
+            writeln!(f, "  ~ {key}")?;
+        }
+        for key in &self.removed {
+            writeln!(f, "  - {key}")?;
+        }
+        Ok(())
+    }
+}
+
+fn confirm(yes: bool, message: &str) -> Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+
+    if !is_stdin_tty() {
+        return Err(miette::miette!(
+            "confirmation required to apply the changes, use the flag `--yes` to apply them without confirmation"
+        ));
+    }
+
+    match Confirm::new(message).with_default(false).prompt() {
+        Err(err) if is_user_cancellation_error(&err) => Ok(false),
+        result => result.into_diagnostic(),
+    }
+}
+
+fn read_env_file(path: &Path) -> Result<Variables> {
+    let options = EnvOptions {
+        env_var: None,
+        env_file: Some(path.to_path_buf()),
+    };
+    let vars = options.lambda_environment(&HashMap::new())?;
+    Ok(vars.into_iter().collect())
+}
+
+/// Render the variables in KEY=VALUE format. Values with spaces, quotes,
+/// or comment characters are quoted, so they can be read back.
+fn format_env_file(vars: &Variables) -> String {
+    let mut output = String::new();
+    for (key, value) in vars {
+        let needs_quotes = value.is_empty()
+            || value
+                .chars()
+                .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '#' | '\\' | '$'));
+
+        let _ = if !needs_quotes {
+            writeln!(output, "{key}={value}")
+        } else if value.contains('\'') {
+            let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(output, "{key}=\"{escaped}\"")
+        } else {
+            writeln!(output, "{key}='{value}'")
+        };
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Variables {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let current = vars(&[
+            ("DB_HOST", "localhost"),
+            ("LOG_LEVEL", "info"),
+            ("OLD", "1"),
+        ]);
+        let new = vars(&[
+            ("DB_HOST", "db.internal"),
+            ("LOG_LEVEL", "info"),
+            ("REGION", "eu"),
+        ]);
+
+        let diff = Diff::new(&current, &new);
+        assert_eq!(
+            Diff {
+                added: vec!["REGION".into()],
+                changed: vec!["DB_HOST".into()],
+                removed: vec!["OLD".into()],
+            },
+            diff
+        );
+        assert_eq!("  + REGION\n  ~ DB_HOST\n  - OLD\n", diff.to_string());
+        assert!(Diff::new(&current, &current).is_empty());
+    }
+
+    #[test]
+    fn test_format_env_file() {
+        let vars = vars(&[
+            ("GREETING", "hello world"),
+            ("LOG_LEVEL", "info"),
+            ("QUOTE", "it's \"fine\""),
+        ]);
+
+        assert_eq!(
+            "GREETING='hello world'\nLOG_LEVEL=info\nQUOTE=\"it's \\\"fine\\\"\"\n",
+            format_env_file(&vars)
+        );
+    }
+
+    #[test]
+    fn test_read_env_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".env");
+
+        let vars = vars(&[("GREETING", "hello world"), ("LOG_LEVEL", "info")]);
+        std::fs::write(&path, format_env_file(&vars)).unwrap();
+        assert_eq!(vars, read_env_file(&path).unwrap());
+    }
+}
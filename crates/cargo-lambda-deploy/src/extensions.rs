@@ -1,4 +1,4 @@
-use aws_sdk_s3::{primitives::ByteStream, Client as S3Client};
+use aws_sdk_s3::primitives::ByteStream;
 use cargo_lambda_build::{BinaryArchive, BinaryModifiedAt};
 use cargo_lambda_interactive::progress::Progress;
 use cargo_lambda_metadata::cargo::deploy::Deploy;
@@ -61,12 +61,13 @@ pub(crate) async fn deploy(
             let key = config.s3_key.as_deref().unwrap_or(name);
             debug!(bucket, key, "uploading zip to S3");
 
-            let s3_client = S3Client::new(sdk_config);
+            let s3_client = crate::s3_client(sdk_config, config.s3_accelerate);
             let mut operation = s3_client
                 .put_object()
                 .bucket(bucket)
                 .key(key)
-                .body(ByteStream::from(binary_archive.read()?));
+                .body(ByteStream::from(binary_archive.read()?))
+                .set_storage_class(crate::s3_storage_class(config)?);
 
             if let Some(tags) = config.s3_tags() {
                 operation = operation.tagging(tags);
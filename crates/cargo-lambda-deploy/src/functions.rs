@@ -1,4 +1,8 @@
-use crate::roles::{self, FunctionRole};
+use crate::{
+    alias,
+    cost::CostEstimate,
+    roles::{self, FunctionRole},
+};
 use aws_sdk_s3::{primitives::ByteStream, Client as S3Client};
 use cargo_lambda_build::{BinaryArchive, BinaryModifiedAt};
 use cargo_lambda_interactive::progress::Progress;
@@ -6,7 +10,7 @@ use cargo_lambda_metadata::cargo::deploy::Deploy;
 use cargo_lambda_remote::{
     aws_sdk_config::SdkConfig,
     aws_sdk_lambda::{
-        error::SdkError,
+        error::{DisplayErrorContext, SdkError},
         operation::{
             create_function::CreateFunctionError,
             delete_function_url_config::DeleteFunctionUrlConfigError,
@@ -26,7 +30,7 @@ use miette::{IntoDiagnostic, Result, WrapErr};
 use serde::Serialize;
 use std::{collections::HashMap, str::FromStr};
 use tokio::time::{sleep, Duration};
-use tracing::debug;
+use tracing::{debug, warn};
 use uuid::Uuid;
 
 enum FunctionAction {
@@ -39,6 +43,8 @@ pub(crate) struct DeployOutput {
     function_arn: String,
     function_url: Option<String>,
     binary_modified_at: BinaryModifiedAt,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost_estimate: Option<CostEstimate>,
 }
 
 impl std::fmt::Display for DeployOutput {
@@ -53,6 +59,9 @@ impl std::fmt::Display for DeployOutput {
         if let Some(url) = &self.function_url {
             write!(f, "🔗 function url: {url}")?;
         }
+        if let Some(estimate) = &self.cost_estimate {
+            write!(f, "\n{estimate}")?;
+        }
         Ok(())
     }
 }
@@ -89,10 +98,37 @@ pub(crate) async fn deploy(
         delete_function_url_config(name, &config.remote_config.alias, &client).await?;
     }
 
+    let cost_estimate = match &config.estimate_cost {
+        Some(profile) => {
+            progress.set_message("estimating monthly cost");
+
+            // The estimate is only informative, the deploy already succeeded.
+            let conf = client
+                .get_function_configuration()
+                .function_name(name)
+                .set_qualifier(config.remote_config.alias.clone())
+                .send()
+                .await;
+
+            let (architecture, memory) = match conf {
+                Ok(conf) => (conf.architectures().first().cloned(), conf.memory_size),
+                Err(err) => {
+                    warn!(error = %DisplayErrorContext(&err), "failed to fetch the function configuration, estimating the cost with the deploy configuration");
+                    (None, config.function_config.memory.as_ref().map(Into::into))
+                }
+            };
+
+            let architecture = architecture.unwrap_or_else(|| binary_archive.architecture());
+            Some(CostEstimate::new(profile, &architecture, memory))
+        }
+        None => None,
+    };
+
     Ok(DeployOutput {
         function_arn,
         function_url,
         binary_modified_at: binary_archive.binary_modified_at.clone(),
+        cost_estimate,
     })
 }
 
@@ -117,7 +153,7 @@ async fn upsert_function(
         }
     };
 
-    let s3_client = S3Client::new(sdk_config);
+    let s3_client = crate::s3_client(sdk_config, config.s3_accelerate);
 
     let (arn, version) = match action {
         FunctionAction::Create => {
@@ -210,6 +246,7 @@ async fn create_function(
                 .key(key)
                 .body(ByteStream::from(binary_archive.read()?))
                 .set_tagging(config.s3_tags())
+                .set_storage_class(crate::s3_storage_class(config)?)
                 .send()
                 .await
                 .into_diagnostic()
@@ -422,7 +459,8 @@ async fn update_function_code(
                 .put_object()
                 .bucket(bucket)
                 .key(key)
-                .body(ByteStream::from(binary_archive.read()?));
+                .body(ByteStream::from(binary_archive.read()?))
+                .set_storage_class(crate::s3_storage_class(config)?);
 
             let s3_tags = config.s3_tags();
             if s3_tags.is_some() {
@@ -448,7 +486,7 @@ async fn update_function_code(
 }
 
 /// Wait until the function state has been completely propagated.
-async fn wait_for_ready_state(
+pub(crate) async fn wait_for_ready_state(
     client: &LambdaClient,
     name: &str,
     alias: &Option<String>,
@@ -545,12 +583,22 @@ pub(crate) async fn upsert_alias(
         .await;
 
     match current_alias {
-        Ok(_) => {
+        Ok(current) => {
+            // Record the version that the alias pointed to, so `cargo lambda rollback` can restore it.
+            let description = match current.function_version() {
+                Some(previous) if previous != version => Some(alias::record_previous_version(
+                    current.description(),
+                    previous,
+                )),
+                _ => None,
+            };
+
             client
                 .update_alias()
                 .name(alias)
                 .function_name(name)
                 .function_version(version)
+                .set_description(description)
                 .send()
                 .await
                 .into_diagnostic()
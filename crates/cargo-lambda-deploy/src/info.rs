@@ -0,0 +1,578 @@
+use aws_sdk_cloudwatchlogs::Client as LogsClient;
+use cargo_lambda_interactive::progress::Progress;
+use cargo_lambda_metadata::cargo::deploy::{Deploy, OutputFormat};
+use cargo_lambda_remote::{
+    aws_sdk_config::SdkConfig,
+    aws_sdk_lambda::{types::FunctionConfiguration, Client as LambdaClient},
+    RemoteConfig,
+};
+use clap::{Args, ValueHint};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use serde::Serialize;
+use serde_json::ser::to_string_pretty;
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::functions;
+
+const DEFAULT_MANIFEST_PATH: &str = "Cargo.toml";
+
+/// How far back to look for errors in the function's logs.
+const RECENT_ERRORS_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+/// Maximum number of recent errors to show.
+const RECENT_ERRORS_LIMIT: usize = 5;
+/// CloudWatch Logs filter pattern that matches error messages, and timeouts.
+const RECENT_ERRORS_PATTERN: &str = r#"?ERROR ?Error ?"Task timed out""#;
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "info",
+    visible_alias = "describe",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/info.html"
+)]
+pub struct Info {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Path to Cargo.toml, to compare the function with the deploy configuration in the project
+    #[arg(long, value_name = "PATH", default_value = DEFAULT_MANIFEST_PATH, value_hint = ValueHint::FilePath)]
+    manifest_path: PathBuf,
+
+    /// Format to render the output (text, or json)
+    #[arg(short, long)]
+    output_format: Option<OutputFormat>,
+
+    /// Name of the function to describe
+    #[arg(value_name = "NAME")]
+    name: String,
+
+    /// Deploy configuration of the project, loaded from Cargo.toml and the global configuration
+    #[arg(skip)]
+    pub deploy: Option<Deploy>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct FunctionInfo {
+    name: String,
+    arn: Option<String>,
+    state: Option<String>,
+    runtime: Option<String>,
+    architecture: Option<String>,
+    memory: Option<i32>,
+    timeout: Option<i32>,
+    role: Option<String>,
+    tracing: Option<String>,
+    layers: Vec<String>,
+    environment: Vec<String>,
+    log_group: String,
+    last_modified: Option<String>,
+    code_size: i64,
+    code_sha256: Option<String>,
+    function_url: Option<String>,
+    aliases: Vec<AliasInfo>,
+    event_sources: Vec<EventSourceInfo>,
+    recent_errors: Vec<RecentError>,
+    tags: BTreeMap<String, String>,
+    /// Settings in the project's deploy configuration that don't match the deployed function.
+    drift: Vec<Drift>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct AliasInfo {
+    name: String,
+    version: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct EventSourceInfo {
+    source_arn: String,
+    state: String,
+    batch_size: Option<i32>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct RecentError {
+    timestamp: i64,
+    message: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Drift {
+    setting: String,
+    local: String,
+    remote: String,
+}
+
+impl Info {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn manifest_path(&self) -> &Path {
+        &self.manifest_path
+    }
+
+    #[tracing::instrument(skip(self), target = "cargo_lambda")]
+    pub async fn run(&self) -> Result<()> {
+        let sdk_config = self.remote_config.sdk_config(None).await?;
+
+        let progress = Progress::start("loading function information");
+        let info = self.load(&sdk_config).await;
+        progress.finish_and_clear();
+        let info = info?;
+
+        match self.output_format.clone().unwrap_or_default() {
+            OutputFormat::Text => print!("{info}"),
+            OutputFormat::Json => {
+                let text = to_string_pretty(&info)
+                    .into_diagnostic()
+                    .wrap_err("failed to serialize output into json")?;
+                println!("{text}")
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn load(&self, sdk_config: &SdkConfig) -> Result<FunctionInfo> {
+        let client = LambdaClient::new(sdk_config);
+        let alias = self.remote_config.alias.clone();
+
+        let function = client
+            .get_function()
+            .function_name(&self.name)
+            .set_qualifier(alias.clone())
+            .send()
+            .await;
+        let function = match function {
+            Ok(function) => function,
+            Err(err) if functions::function_doesnt_exist_error(&err) => {
+                return Err(miette::miette!(
+                    "the function `{}` doesn't exist",
+                    self.name
+                ));
+            }
+            Err(err) => {
+                return Err(err)
+                    .into_diagnostic()
+                    .wrap_err("failed to fetch the function")
+            }
+        };
+
+        let mut info = function
+            .configuration()
+            .map(|conf| FunctionInfo::from_configuration(&self.name, conf))
+            .unwrap_or_default();
+        info.tags = function.tags.unwrap_or_default().into_iter().collect();
+
+        let url = client
+            .get_function_url_config()
+            .function_name(&self.name)
+            .set_qualifier(alias)
+            .send()
+            .await;
+        info.function_url = match url {
+            Ok(url) => Some(url.function_url),
+            Err(err) if functions::function_url_config_doesnt_exist_error(&err) => None,
+            Err(err) => {
+                return Err(err)
+                    .into_diagnostic()
+                    .wrap_err("failed to fetch function url configuration")
+            }
+        };
+
+        let mut aliases = client
+            .list_aliases()
+            .function_name(&self.name)
+            .into_paginator()
+            .items()
+            .send();
+        while let Some(alias) = aliases.next().await {
+            let alias = alias.into_diagnostic().wrap_err("failed to list aliases")?;
+            info.aliases.push(AliasInfo {
+                name: alias.name.unwrap_or_default(),
+                version: alias.function_version.unwrap_or_default(),
+            });
+        }
+
+        let mut mappings = client
+            .list_event_source_mappings()
+            .function_name(&self.name)
+            .into_paginator()
+            .items()
+            .send();
+        while let Some(mapping) = mappings.next().await {
+            let mapping = mapping
+                .into_diagnostic()
+                .wrap_err("failed to list event source mappings")?;
+            info.event_sources.push(EventSourceInfo {
+                source_arn: mapping.event_source_arn.unwrap_or_default(),
+                state: mapping.state.unwrap_or_default(),
+                batch_size: mapping.batch_size,
+            });
+        }
+
+        info.recent_errors = recent_errors(sdk_config, &info.log_group).await;
+
+        if let Some(deploy) = &self.deploy {
+            info.drift = drift(deploy, function.configuration.as_ref());
+        }
+
+        Ok(info)
+    }
+}
+
+impl FunctionInfo {
+    fn from_configuration(name: &str, conf: &FunctionConfiguration) -> FunctionInfo {
+        let mut environment = conf
+            .environment()
+            .and_then(|e| e.variables())
+            .map(|vars| vars.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        environment.sort();
+
+        FunctionInfo {
+            name: name.to_string(),
+            arn: conf.function_arn().map(String::from),
+            state: conf.state().map(|s| s.as_str().to_string()),
+            runtime: conf.runtime().map(|r| r.as_str().to_string()),
+            architecture: conf.architectures().first().map(|a| a.as_str().to_string()),
+            memory: conf.memory_size(),
+            timeout: conf.timeout(),
+            role: conf.role().map(String::from),
+            tracing: conf
+                .tracing_config()
+                .and_then(|t| t.mode())
+                .map(|m| m.as_str().to_string()),
+            layers: conf
+                .layers()
+                .iter()
+                .filter_map(|l| l.arn().map(String::from))
+                .collect(),
+            environment,
+            log_group: conf
+                .logging_config()
+                .and_then(|l| l.log_group())
+                .map(String::from)
+                .unwrap_or_else(|| format!("/aws/lambda/{name}")),
+            last_modified: conf.last_modified().map(String::from),
+            code_size: conf.code_size(),
+            code_sha256: conf.code_sha256().map(String::from),
+            ..Default::default()
+        }
+    }
+}
+
+/// Read the most recent errors in the function's logs. The errors are informative,
+/// so failing to read them, like when the log group doesn't exist yet, is not an error.
+async fn recent_errors(sdk_config: &SdkConfig, log_group: &str) -> Vec<RecentError> {
+    let client = LogsClient::new(sdk_config);
+    let start_time = SystemTime::now()
+        .checked_sub(RECENT_ERRORS_WINDOW)
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+
+    let mut pages = client
+        .filter_log_events()
+        .log_group_name(log_group)
+        .start_time(start_time)
+        .filter_pattern(RECENT_ERRORS_PATTERN)
+        .into_paginator()
+        .send();
+
+    let mut errors = VecDeque::with_capacity(RECENT_ERRORS_LIMIT);
+    while let Some(page) = pages.next().await {
+        let page = match page {
+            Ok(page) => page,
+            Err(err) => {
+                tracing::debug!(?err, log_group, "failed to read recent errors");
+                break;
+            }
+        };
+
+        for event in page.events.unwrap_or_default() {
+            if errors.len() == RECENT_ERRORS_LIMIT {
+                errors.pop_front();
+            }
+            errors.push_back(RecentError {
+                timestamp: event.timestamp.unwrap_or_default(),
+                message: event.message.unwrap_or_default().trim_end().to_string(),
+            });
+        }
+    }
+
+    errors.into()
+}
+
+/// Compare the settings in the project's deploy configuration with the deployed function.
+/// Settings that are not in the deploy configuration are not compared.
+fn drift(deploy: &Deploy, conf: Option<&FunctionConfiguration>) -> Vec<Drift> {
+    let Some(conf) = conf else {
+        return Vec::new();
+    };
+
+    let config = &deploy.function_config;
+    let mut drift = Vec::new();
+    let mut compare = |setting: &str, local: Option<String>, remote: Option<String>| {
+        if let Some(local) = local {
+            let remote = remote.unwrap_or_else(|| "-".to_string());
+            if local != remote {
+                drift.push(Drift {
+                    setting: setting.to_string(),
+                    local,
+                    remote,
+                });
+            }
+        }
+    };
+
+    compare(
+        "memory",
+        config.memory.as_ref().map(|m| i32::from(m).to_string()),
+        conf.memory_size().map(|m| m.to_string()),
+    );
+    compare(
+        "timeout",
+        config.timeout.as_ref().map(|t| i32::from(t).to_string()),
+        conf.timeout().map(|t| t.to_string()),
+    );
+    compare(
+        "runtime",
+        config.runtime.clone(),
+        conf.runtime().map(|r| r.as_str().to_string()),
+    );
+    compare(
+        "tracing",
+        config.tracing.as_ref().map(|t| t.as_str().to_string()),
+        conf.tracing_config()
+            .and_then(|t| t.mode())
+            .map(|m| m.as_str().to_string()),
+    );
+    compare("role", config.role.clone(), conf.role().map(String::from));
+    compare(
+        "layers",
+        config
+            .layer
+            .as_ref()
+            .map(|l| sorted_list(l.iter().cloned())),
+        Some(sorted_list(
+            conf.layers()
+                .iter()
+                .filter_map(|l| l.arn().map(String::from)),
+        )),
+    );
+
+    let local_env = deploy
+        .lambda_environment()
+        .ok()
+        .flatten()
+        .and_then(|e| e.variables().map(|vars| sorted_list(vars.keys().cloned())));
+    compare(
+        "environment",
+        local_env,
+        Some(sorted_list(
+            conf.environment()
+                .and_then(|e| e.variables())
+                .into_iter()
+                .flat_map(|vars| vars.keys().cloned()),
+        )),
+    );
+
+    drift
+}
+
+fn sorted_list(values: impl Iterator<Item = String>) -> String {
+    values
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Display for FunctionInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let none = || "-".to_string();
+
+        writeln!(f, "🔍 function: {}", self.name)?;
+        writeln!(f, "  arn: {}", self.arn.clone().unwrap_or_else(none))?;
+        writeln!(f, "  state: {}", self.state.clone().unwrap_or_else(none))?;
+        writeln!(
+            f,
+            "  runtime: {}",
+            self.runtime.clone().unwrap_or_else(none)
+        )?;
+        writeln!(
+            f,
+            "  architecture: {}",
+            self.architecture.clone().unwrap_or_else(none)
+        )?;
+        writeln!(
+            f,
+            "  memory: {}",
+            self.memory.map(|m| format!("{m} MB")).unwrap_or_else(none)
+        )?;
+        writeln!(
+            f,
+            "  timeout: {}",
+            self.timeout
+                .map(|t| format!("{t} seconds"))
+                .unwrap_or_else(none)
+        )?;
+        writeln!(f, "  role: {}", self.role.clone().unwrap_or_else(none))?;
+        writeln!(
+            f,
+            "  tracing: {}",
+            self.tracing.clone().unwrap_or_else(none)
+        )?;
+        writeln!(f, "  log group: {}", self.log_group)?;
+        writeln!(
+            f,
+            "  last modified: {}",
+            self.last_modified.clone().unwrap_or_else(none)
+        )?;
+        writeln!(f, "  code size: {} bytes", self.code_size)?;
+        writeln!(
+            f,
+            "  code sha256: {}",
+            self.code_sha256.clone().unwrap_or_else(none)
+        )?;
+
+        if let Some(url) = &self.function_url {
+            writeln!(f, "🔗 function url: {url}")?;
+        }
+
+        write_list(f, "📚 layers", &self.layers)?;
+        write_list(f, "🌱 environment variables", &self.environment)?;
+
+        let aliases = self
+            .aliases
+            .iter()
+            .map(|a| format!("{} → version {}", a.name, a.version))
+            .collect::<Vec<_>>();
+        write_list(f, "🏷️  aliases", &aliases)?;
+
+        let sources = self
+            .event_sources
+            .iter()
+            .map(|s| match s.batch_size {
+                Some(size) => format!("{} ({}, batch size {size})", s.source_arn, s.state),
+                None => format!("{} ({})", s.source_arn, s.state),
+            })
+            .collect::<Vec<_>>();
+        write_list(f, "📨 event sources", &sources)?;
+
+        let tags = self
+            .tags
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>();
+        write_list(f, "🔖 tags", &tags)?;
+
+        let errors = self
+            .recent_errors
+            .iter()
+            .map(|e| format!("{} {}", format_timestamp(e.timestamp), e.message))
+            .collect::<Vec<_>>();
+        write_list(f, "🚨 recent errors (last 24 hours)", &errors)?;
+
+        if !self.drift.is_empty() {
+            writeln!(f, "⚠️  differences with the deploy configuration:")?;
+            for drift in &self.drift {
+                writeln!(
+                    f,
+                    "  - {}: {} in the project, {} deployed",
+                    drift.setting, drift.local, drift.remote
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_list(f: &mut std::fmt::Formatter<'_>, title: &str, items: &[String]) -> std::fmt::Result {
+    if items.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(f, "{title}:")?;
+    for item in items {
+        writeln!(f, "  - {item}")?;
+    }
+    Ok(())
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(timestamp)
+        .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cargo_lambda_metadata::lambda::{Memory, Tracing};
+    use cargo_lambda_remote::aws_sdk_lambda::types::{
+        Architecture, Runtime, TracingConfigResponse, TracingMode,
+    };
+
+    fn configuration() -> FunctionConfiguration {
+        FunctionConfiguration::builder()
+            .function_arn("arn:aws:lambda:us-east-1:123456789012:function:orders")
+            .runtime(Runtime::Providedal2023)
+            .architectures(Architecture::Arm64)
+            .memory_size(128)
+            .timeout(30)
+            .role("arn:aws:iam::123456789012:role/orders")
+            .tracing_config(
+                TracingConfigResponse::builder()
+                    .mode(TracingMode::PassThrough)
+                    .build(),
+            )
+            .build()
+    }
+
+    #[test]
+    fn test_from_configuration() {
+        let info = FunctionInfo::from_configuration("orders", &configuration());
+        assert_eq!(Some("provided.al2023"), info.runtime.as_deref());
+        assert_eq!(Some("arm64"), info.architecture.as_deref());
+        assert_eq!(Some(128), info.memory);
+        assert_eq!("/aws/lambda/orders", info.log_group);
+
+        let text = info.to_string();
+        assert!(text.contains("🔍 function: orders\n"));
+        assert!(text.contains("  memory: 128 MB\n"));
+        assert!(!text.contains("aliases"));
+    }
+
+    #[test]
+    fn test_drift() {
+        let mut deploy = Deploy::default();
+        deploy.function_config.memory = Some(Memory::Mb512);
+        deploy.function_config.timeout = Some(30.into());
+        deploy.function_config.tracing = Some(Tracing::Active);
+
+        let drift = drift(&deploy, Some(&configuration()));
+        assert_eq!(
+            vec![
+                Drift {
+                    setting: "memory".into(),
+                    local: "512".into(),
+                    remote: "128".into(),
+                },
+                Drift {
+                    setting: "tracing".into(),
+                    local: "Active".into(),
+                    remote: "PassThrough".into(),
+                },
+            ],
+            drift
+        );
+    }
+}
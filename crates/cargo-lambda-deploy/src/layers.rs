@@ -0,0 +1,619 @@
+use cargo_lambda_interactive::{
+    is_stdin_tty, is_user_cancellation_error, progress::Progress, Confirm,
+};
+use cargo_lambda_metadata::cargo::deploy::OutputFormat;
+use cargo_lambda_remote::{
+    aws_sdk_lambda::{
+        primitives::Blob,
+        types::{Architecture, LayerVersionContentInput, LayerVersionsListItem, Runtime},
+        Client as LambdaClient,
+    },
+    RemoteConfig,
+};
+use clap::{Args, Subcommand, ValueHint};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use serde::Serialize;
+use serde_json::ser::to_string_pretty;
+use std::path::PathBuf;
+
+use crate::list::format_rows;
+
+const DEFAULT_COMPATIBLE_RUNTIMES: &str = "provided.al2,provided.al2023";
+
+/// Maximum number of layers that a function can use.
+const MAX_FUNCTION_LAYERS: usize = 5;
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "layers",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/layers.html"
+)]
+pub struct Layers {
+    #[command(subcommand)]
+    command: LayersCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum LayersCommand {
+    /// Publish a new version of a layer from a zip file
+    Publish(Publish),
+    /// List the layers in the account, with their latest version
+    List(ListLayers),
+    /// List the versions of a layer
+    Versions(Versions),
+    /// Delete the versions of a layer, optionally keeping the newest versions
+    Delete(DeleteLayer),
+    /// Add a layer version to a function, or replace the version that the function uses
+    Add(AddLayer),
+}
+
+#[derive(Args, Clone, Debug)]
+struct Publish {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Path to the zip file with the layer's content
+    #[arg(value_name = "ZIP", value_hint = ValueHint::FilePath)]
+    zip: PathBuf,
+
+    /// Name of the layer, the name of the zip file without extension by default
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Description of the layer version
+    #[arg(long)]
+    description: Option<String>,
+
+    /// Comma separated list with the runtimes that the layer is compatible with
+    #[arg(long, value_delimiter = ',', default_value = DEFAULT_COMPATIBLE_RUNTIMES)]
+    compatible_runtimes: Vec<String>,
+
+    /// Comma separated list with the architectures that the layer is compatible with (x86_64, or arm64)
+    #[arg(long, value_delimiter = ',')]
+    compatible_architectures: Vec<String>,
+
+    /// Format to render the output (text, or json)
+    #[arg(short, long)]
+    output_format: Option<OutputFormat>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct ListLayers {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Format to render the output (text, or json)
+    #[arg(short, long)]
+    output_format: Option<OutputFormat>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct Versions {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Name of the layer
+    #[arg(value_name = "NAME")]
+    name: String,
+
+    /// Format to render the output (text, or json)
+    #[arg(short, long)]
+    output_format: Option<OutputFormat>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct DeleteLayer {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Name of the layer
+    #[arg(value_name = "NAME")]
+    name: String,
+
+    /// Number of versions to keep, starting from the newest version
+    #[arg(long, default_value_t = 0)]
+    keep: usize,
+
+    /// Delete the versions without asking for confirmation
+    #[arg(short, long)]
+    yes: bool,
+}
+
+#[derive(Args, Clone, Debug)]
+struct AddLayer {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Name of the function
+    #[arg(value_name = "FUNCTION")]
+    function: String,
+
+    /// ARN of the layer version, like arn:aws:lambda:us-east-1:123456789012:layer:my-layer:3
+    #[arg(value_name = "ARN")]
+    arn: String,
+}
+
+/// Version of a layer, as reported by AWS Lambda.
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct LayerVersion {
+    name: String,
+    version: i64,
+    arn: String,
+    description: Option<String>,
+    created_date: String,
+    compatible_runtimes: Vec<String>,
+    compatible_architectures: Vec<String>,
+}
+
+impl LayerVersion {
+    fn new(name: &str, version: LayerVersionsListItem) -> LayerVersion {
+        LayerVersion {
+            name: name.to_string(),
+            version: version.version,
+            arn: version.layer_version_arn.unwrap_or_default(),
+            description: version.description,
+            created_date: version.created_date.unwrap_or_default(),
+            compatible_runtimes: version
+                .compatible_runtimes
+                .unwrap_or_default()
+                .iter()
+                .map(|r| r.as_str().to_string())
+                .collect(),
+            compatible_architectures: version
+                .compatible_architectures
+                .unwrap_or_default()
+                .iter()
+                .map(|a| a.as_str().to_string())
+                .collect(),
+        }
+    }
+}
+
+impl Layers {
+    #[tracing::instrument(skip(self), target = "cargo_lambda")]
+    pub async fn run(&self) -> Result<()> {
+        match &self.command {
+            LayersCommand::Publish(cmd) => cmd.run().await,
+            LayersCommand::List(cmd) => cmd.run().await,
+            LayersCommand::Versions(cmd) => cmd.run().await,
+            LayersCommand::Delete(cmd) => cmd.run().await,
+            LayersCommand::Add(cmd) => cmd.run().await,
+        }
+    }
+}
+
+async fn client(remote_config: &RemoteConfig) -> Result<LambdaClient> {
+    let sdk_config = remote_config.sdk_config(None).await?;
+    Ok(LambdaClient::new(&sdk_config))
+}
+
+impl Publish {
+    fn layer_name(&self) -> Result<String> {
+        if let Some(name) = &self.name {
+            return Ok(name.clone());
+        }
+
+        self.zip
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .ok_or_else(|| {
+                miette::miette!(
+                    "cannot infer the layer name from {}, use the flag `--name`",
+                    self.zip.display()
+                )
+            })
+    }
+
+    async fn run(&self) -> Result<()> {
+        if !self.zip.is_file() {
+            return Err(miette::miette!(
+                "the file {} doesn't exist",
+                self.zip.display()
+            ));
+        }
+        let name = self.layer_name()?;
+        let content = std::fs::read(&self.zip)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to read {}", self.zip.display()))?;
+
+        let runtimes = self
+            .compatible_runtimes
+            .iter()
+            .map(|r| Runtime::from(r.as_str()))
+            .collect::<Vec<_>>();
+        let architectures = self
+            .compatible_architectures
+            .iter()
+            .map(|a| Architecture::from(a.as_str()))
+            .collect::<Vec<_>>();
+
+        let client = client(&self.remote_config).await?;
+        let progress = Progress::start("publishing new layer version");
+        let result = client
+            .publish_layer_version()
+            .layer_name(&name)
+            .set_description(self.description.clone())
+            .set_compatible_runtimes(Some(runtimes))
+            .set_compatible_architectures((!architectures.is_empty()).then_some(architectures))
+            .content(
+                LayerVersionContentInput::builder()
+                    .zip_file(Blob::new(content))
+                    .build(),
+            )
+            .send()
+            .await;
+        progress.finish_and_clear();
+
+        let output = result
+            .into_diagnostic()
+            .wrap_err("failed to publish the layer")?;
+
+        let version = LayerVersion {
+            name,
+            version: output.version,
+            arn: output.layer_version_arn.unwrap_or_default(),
+            description: output.description,
+            created_date: output.created_date.unwrap_or_default(),
+            compatible_runtimes: output
+                .compatible_runtimes
+                .unwrap_or_default()
+                .iter()
+                .map(|r| r.as_str().to_string())
+                .collect(),
+            compatible_architectures: output
+                .compatible_architectures
+                .unwrap_or_default()
+                .iter()
+                .map(|a| a.as_str().to_string())
+                .collect(),
+        };
+
+        match self.output_format.clone().unwrap_or_default() {
+            OutputFormat::Text => {
+                println!(
+                    "✅ layer `{}` version {} published",
+                    version.name, version.version
+                );
+                println!("🔍 arn: {}", version.arn);
+                Ok(())
+            }
+            OutputFormat::Json => print_json(&version),
+        }
+    }
+}
+
+impl ListLayers {
+    async fn run(&self) -> Result<()> {
+        let client = client(&self.remote_config).await?;
+
+        let progress = Progress::start("loading layers");
+        let layers = list_layers(&client).await;
+        progress.finish_and_clear();
+        let layers = layers?;
+
+        match self.output_format.clone().unwrap_or_default() {
+            OutputFormat::Text => {
+                print!("{}", format_table(&layers, "No layers found"));
+                Ok(())
+            }
+            OutputFormat::Json => print_json(&layers),
+        }
+    }
+}
+
+impl Versions {
+    async fn run(&self) -> Result<()> {
+        let client = client(&self.remote_config).await?;
+
+        let progress = Progress::start("loading layer versions");
+        let versions = list_versions(&client, &self.name).await;
+        progress.finish_and_clear();
+        let versions = versions?;
+
+        match self.output_format.clone().unwrap_or_default() {
+            OutputFormat::Text => {
+                let empty = format!("No versions found for the layer `{}`", self.name);
+                print!("{}", format_table(&versions, &empty));
+                Ok(())
+            }
+            OutputFormat::Json => print_json(&versions),
+        }
+    }
+}
+
+impl DeleteLayer {
+    async fn run(&self) -> Result<()> {
+        let client = client(&self.remote_config).await?;
+
+        let progress = Progress::start("loading layer versions");
+        let versions = list_versions(&client, &self.name).await;
+        progress.finish_and_clear();
+        let versions = versions?;
+
+        let delete = versions_to_delete(&versions, self.keep);
+        if delete.is_empty() {
+            println!(
+                "✅ the layer `{}` has {} versions, nothing to delete",
+                self.name,
+                versions.len()
+            );
+            return Ok(());
+        }
+
+        let list = delete
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("🔍 Versions to delete from `{}`: {list}", self.name);
+        if !self.confirm()? {
+            return Ok(());
+        }
+
+        let progress = Progress::start("deleting layer versions");
+        for version in &delete {
+            let result = client
+                .delete_layer_version()
+                .layer_name(&self.name)
+                .version_number(*version)
+                .send()
+                .await;
+            if let Err(err) = result {
+                progress.finish_and_clear();
+                return Err(err)
+                    .into_diagnostic()
+                    .wrap_err(format!("failed to delete the version {version}"));
+            }
+        }
+        progress.finish_and_clear();
+
+        println!(
+            "✅ {} versions deleted from the layer `{}`",
+            delete.len(),
+            self.name
+        );
+        Ok(())
+    }
+
+    fn confirm(&self) -> Result<bool> {
+        if self.yes {
+            return Ok(true);
+        }
+
+        if !is_stdin_tty() {
+            return Err(miette::miette!(
+                "confirmation required to delete the versions, use the flag `--yes` to delete them without confirmation"
+            ));
+        }
+
+        match Confirm::new("Do you want to delete these versions?")
+            .with_default(false)
+            .prompt()
+        {
+            Err(err) if is_user_cancellation_error(&err) => Ok(false),
+            result => result.into_diagnostic(),
+        }
+    }
+}
+
+impl AddLayer {
+    async fn run(&self) -> Result<()> {
+        let client = client(&self.remote_config).await?;
+
+        let progress = Progress::start("loading function configuration");
+        let conf = client
+            .get_function_configuration()
+            .function_name(&self.function)
+            .send()
+            .await;
+        progress.finish_and_clear();
+
+        let conf = conf
+            .into_diagnostic()
+            .wrap_err("failed to fetch the function configuration")?;
+        let current = conf
+            .layers
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|layer| layer.arn)
+            .collect::<Vec<_>>();
+
+        let layers = with_layer(&current, &self.arn)?;
+        if layers == current {
+            println!(
+                "✅ the function `{}` already uses {}",
+                self.function, self.arn
+            );
+            return Ok(());
+        }
+
+        let progress = Progress::start("updating function layers");
+        let result = client
+            .update_function_configuration()
+            .function_name(&self.function)
+            .set_layers(Some(layers))
+            .send()
+            .await;
+        progress.finish_and_clear();
+
+        result
+            .into_diagnostic()
+            .wrap_err("failed to update the function configuration")?;
+
+        println!("✅ {} added to the function `{}`", self.arn, self.function);
+        Ok(())
+    }
+}
+
+async fn list_layers(client: &LambdaClient) -> Result<Vec<LayerVersion>> {
+    let mut pages = client.list_layers().into_paginator().items().send();
+
+    let mut layers = Vec::new();
+    while let Some(layer) = pages.next().await {
+        let layer = layer.into_diagnostic().wrap_err("failed to list layers")?;
+        if let Some(latest) = layer.latest_matching_version {
+            layers.push(LayerVersion::new(
+                layer.layer_name.as_deref().unwrap_or_default(),
+                latest,
+            ));
+        }
+    }
+
+    layers.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(layers)
+}
+
+async fn list_versions(client: &LambdaClient, name: &str) -> Result<Vec<LayerVersion>> {
+    let mut pages = client
+        .list_layer_versions()
+        .layer_name(name)
+        .into_paginator()
+        .items()
+        .send();
+
+    let mut versions = Vec::new();
+    while let Some(version) = pages.next().await {
+        let version = version
+            .into_diagnostic()
+            .wrap_err("failed to list layer versions")?;
+        versions.push(LayerVersion::new(name, version));
+    }
+
+    versions.sort_by(|a, b| b.version.cmp(&a.version));
+    Ok(versions)
+}
+
+/// Versions to delete, keeping the newest `keep` versions.
+fn versions_to_delete(versions: &[LayerVersion], keep: usize) -> Vec<i64> {
+    let mut numbers = versions.iter().map(|v| v.version).collect::<Vec<_>>();
+    numbers.sort_unstable_by(|a, b| b.cmp(a));
+    numbers.into_iter().skip(keep).collect()
+}
+
+/// Layer ARN without the version number at the end.
+fn unversioned_arn(arn: &str) -> Result<&str> {
+    match arn.rsplit_once(':') {
+        Some((layer, version))
+            if layer.contains(":layer:") && version.parse::<u64>().is_ok() =>
+        {
+            Ok(layer)
+        }
+        _ => Err(miette::miette!(
+            "invalid layer version ARN `{arn}`, it must include the version number, like arn:aws:lambda:us-east-1:123456789012:layer:my-layer:3"
+        )),
+    }
+}
+
+/// Add the layer version to the function's layers. If the function already uses
+/// another version of the same layer, that version is replaced, to keep the layers' order.
+fn with_layer(current: &[String], arn: &str) -> Result<Vec<String>> {
+    let layer = unversioned_arn(arn)?;
+
+    let mut layers = current.to_vec();
+    match layers
+        .iter_mut()
+        .find(|l| unversioned_arn(l).is_ok_and(|l| l == layer))
+    {
+        Some(existing) => *existing = arn.to_string(),
+        None => layers.push(arn.to_string()),
+    }
+
+    if layers.len() > MAX_FUNCTION_LAYERS {
+        return Err(miette::miette!(
+            "functions can use up to {MAX_FUNCTION_LAYERS} layers, remove a layer from the function before adding another one"
+        ));
+    }
+    Ok(layers)
+}
+
+fn format_table(versions: &[LayerVersion], empty: &str) -> String {
+    if versions.is_empty() {
+        return format!("{empty}\n");
+    }
+
+    let header = ["NAME", "VERSION", "RUNTIMES", "ARCHITECTURES", "CREATED"];
+    let rows = versions
+        .iter()
+        .map(|v| {
+            [
+                v.name.clone(),
+                v.version.to_string(),
+                v.compatible_runtimes.join(","),
+                v.compatible_architectures.join(","),
+                v.created_date.clone(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    format_rows(header, rows)
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<()> {
+    let text = to_string_pretty(value)
+        .into_diagnostic()
+        .wrap_err("failed to serialize output into json")?;
+    println!("{text}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARN: &str = "arn:aws:lambda:us-east-1:123456789012:layer";
+
+    fn version(version: i64) -> LayerVersion {
+        LayerVersion {
+            name: "extension".into(),
+            version,
+            arn: format!("{ARN}:extension:{version}"),
+            created_date: "2024-11-14T22:13:20.123+0000".into(),
+            compatible_runtimes: vec!["provided.al2".into(), "provided.al2023".into()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_versions_to_delete() {
+        let versions = [version(3), version(7), version(5), version(1)];
+        assert_eq!(vec![3, 1], versions_to_delete(&versions, 2));
+        assert_eq!(vec![7, 5, 3, 1], versions_to_delete(&versions, 0));
+        assert!(versions_to_delete(&versions, 4).is_empty());
+        assert!(versions_to_delete(&versions, 10).is_empty());
+    }
+
+    #[test]
+    fn test_with_layer() {
+        let current = vec![format!("{ARN}:otel:2"), format!("{ARN}:extension:1")];
+
+        let layers = with_layer(&current, &format!("{ARN}:extension:4")).unwrap();
+        assert_eq!(
+            vec![format!("{ARN}:otel:2"), format!("{ARN}:extension:4")],
+            layers
+        );
+
+        let layers = with_layer(&current, &format!("{ARN}:secrets:1")).unwrap();
+        assert_eq!(3, layers.len());
+        assert_eq!(format!("{ARN}:secrets:1"), layers[2]);
+
+        assert!(with_layer(&current, &format!("{ARN}:extension")).is_err());
+        assert!(with_layer(&current, "extension:4").is_err());
+
+        let full = (1..=5)
+            .map(|i| format!("{ARN}:layer-{i}:1"))
+            .collect::<Vec<_>>();
+        assert!(with_layer(&full, &format!("{ARN}:layer-6:1")).is_err());
+        assert!(with_layer(&full, &format!("{ARN}:layer-1:2")).is_ok());
+    }
+
+    #[test]
+    fn test_format_table() {
+        let table = format_table(&[version(2), version(1)], "No layers found");
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(3, lines.len());
+        assert!(lines[0].starts_with("NAME       VERSION  RUNTIMES"));
+        assert!(lines[1].starts_with("extension  2        provided.al2,provided.al2023"));
+
+        assert_eq!("No layers found\n", format_table(&[], "No layers found"));
+    }
+}
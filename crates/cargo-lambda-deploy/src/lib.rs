@@ -1,19 +1,40 @@
+use aws_sdk_s3::{types::StorageClass, Client as S3Client};
 use aws_smithy_types::retry::{RetryConfig, RetryMode};
-use cargo_lambda_build::{create_binary_archive, zip_binary, BinaryArchive, BinaryData};
+use cargo_lambda_build::{
+    create_binary_archive, find_binary_archive, open_binary_archive, verify_archive, zip_binary,
+    BinaryArchive, BinaryData,
+};
 use cargo_lambda_interactive::progress::Progress;
 use cargo_lambda_metadata::cargo::{
     deploy::{Deploy, OutputFormat},
     main_binary_from_metadata, CargoMetadata,
 };
+use cargo_lambda_remote::{aws_sdk_config::SdkConfig, has_custom_endpoint};
 use miette::{IntoDiagnostic, Result, WrapErr};
 use serde::Serialize;
 use serde_json::ser::to_string_pretty;
 use std::time::Duration;
 
+mod alias;
+pub use alias::{Promote, Rollback};
+mod bench;
+pub use bench::Bench;
+mod cost;
+mod delete;
+pub use delete::Delete;
 mod dry;
+mod env;
+pub use env::Env;
 mod extensions;
 mod functions;
+mod info;
+pub use info::Info;
+mod layers;
+pub use layers::Layers;
+mod list;
+pub use list::List;
 mod roles;
+pub use roles::Role;
 
 #[derive(Serialize)]
 #[serde(untagged)]
@@ -41,6 +62,17 @@ pub async fn run(config: &Deploy, metadata: &CargoMetadata) -> Result<()> {
     if config.function_config.enable_function_url && config.function_config.disable_function_url {
         return Err(miette::miette!("invalid options: --enable-function-url and --disable-function-url cannot be set together"));
     }
+    s3_storage_class(config)?;
+    let signature = match config.verify_signature {
+        true => Some(signature_identity(config)?),
+        false => None,
+    };
+
+    // Ask for the region before the progress bar starts, it can't show the prompt.
+    let mut remote_config = config.remote_config.clone();
+    if !config.dry {
+        remote_config.select_region().await?;
+    }
 
     let progress = Progress::start("loading binary data");
     let (name, archive) = match load_archive(config, metadata) {
@@ -51,12 +83,20 @@ pub async fn run(config: &Deploy, metadata: &CargoMetadata) -> Result<()> {
         }
     };
 
+    if let Some((identity, issuer)) = signature {
+        progress.set_message("verifying signature");
+        if let Err(err) = verify_archive(&archive.path, identity, issuer).await {
+            progress.finish_and_clear();
+            return Err(err);
+        }
+    }
+
     let retry = RetryConfig::standard()
         .with_retry_mode(RetryMode::Adaptive)
         .with_max_attempts(3)
         .with_initial_backoff(Duration::from_secs(5));
 
-    let sdk_config = config.remote_config.sdk_config(Some(retry)).await;
+    let sdk_config = remote_config.sdk_config(Some(retry)).await?;
 
     let result = if config.dry {
         dry::DeployOutput::new(config, &name, &archive).map(DeployResult::Dry)
@@ -86,6 +126,8 @@ pub async fn run(config: &Deploy, metadata: &CargoMetadata) -> Result<()> {
     Ok(())
 }
 
+/// Create the zip archive to deploy. When the signature must be verified, the zip archive
+/// that the build created and signed is deployed as it is, without creating it again.
 fn load_archive(config: &Deploy, metadata: &CargoMetadata) -> Result<(String, BinaryArchive)> {
     match &config.binary_path {
         Some(bp) if bp.is_dir() => Err(miette::miette!("invalid file {:?}", bp)),
@@ -104,7 +146,11 @@ fn load_archive(config: &Deploy, metadata: &CargoMetadata) -> Result<(String, Bi
                 .ok_or_else(|| miette::miette!("invalid binary path {:?}", bp))?;
 
             let data = BinaryData::new(&name, config.extension, config.internal);
-            let arc = zip_binary(bp, destination, &data, config.include.clone())?;
+            let arc = if config.verify_signature {
+                open_binary_archive(destination.join(data.zip_name()), &data)?
+            } else {
+                zip_binary(bp, destination, &data, config.include.clone())?
+            };
             Ok((name, arc))
         }
         None => {
@@ -116,17 +162,37 @@ fn load_archive(config: &Deploy, metadata: &CargoMetadata) -> Result<(String, Bi
             let binary_name = binary_name_or_default(config, &name);
             let data = BinaryData::new(&binary_name, config.extension, config.internal);
 
-            let arc = create_binary_archive(
-                Some(metadata),
-                &config.lambda_dir,
-                &data,
-                config.include.clone(),
-            )?;
+            let arc = if config.verify_signature {
+                find_binary_archive(Some(metadata), &config.lambda_dir, &data)?
+            } else {
+                create_binary_archive(
+                    Some(metadata),
+                    &config.lambda_dir,
+                    &data,
+                    config.include.clone(),
+                )?
+            };
             Ok((name, arc))
         }
     }
 }
 
+/// Identity and OIDC issuer that must have signed the zip file to deploy.
+/// The flags are checked by clap, but the options can also come from the configuration files.
+fn signature_identity(config: &Deploy) -> Result<(&str, &str)> {
+    // The signed zip file is deployed as it is, so it can't include extra files.
+    if config.include.is_some() {
+        return Err(miette::miette!(
+            "invalid options: --verify-signature and --include cannot be set together"
+        ));
+    }
+
+    match (&config.signature_identity, &config.signature_issuer) {
+        (Some(identity), Some(issuer)) => Ok((identity, issuer)),
+        _ => Err(miette::miette!("invalid options: --verify-signature requires --signature-identity and --signature-issuer")),
+    }
+}
+
 pub(crate) fn binary_name_or_default(config: &Deploy, name: &str) -> String {
     config
         .binary_name
@@ -134,6 +200,32 @@ pub(crate) fn binary_name_or_default(config: &Deploy, name: &str) -> String {
         .unwrap_or_else(|| name.to_string())
 }
 
+/// S3 client that uses path-style addressing with custom endpoints,
+/// because emulators like LocalStack don't resolve bucket names as subdomains.
+pub(crate) fn s3_client(sdk_config: &SdkConfig, accelerate: bool) -> S3Client {
+    let config = aws_sdk_s3::config::Builder::from(sdk_config)
+        .force_path_style(has_custom_endpoint(sdk_config, "s3"))
+        .accelerate(accelerate)
+        .build();
+    S3Client::from_conf(config)
+}
+
+/// Storage class for the code uploaded to S3, or `None` to use the bucket's default.
+pub(crate) fn s3_storage_class(config: &Deploy) -> Result<Option<StorageClass>> {
+    let Some(storage_class) = &config.s3_storage_class else {
+        return Ok(None);
+    };
+
+    let storage_class = storage_class.to_uppercase();
+    if !StorageClass::values().contains(&storage_class.as_str()) {
+        return Err(miette::miette!(
+            "invalid S3 storage class `{storage_class}`, use one of: {}",
+            StorageClass::values().join(", ")
+        ));
+    }
+    Ok(Some(StorageClass::from(storage_class.as_str())))
+}
+
 #[cfg(test)]
 mod tests {
     use assertables::assert_contains;
@@ -143,6 +235,41 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_s3_storage_class() {
+        let mut config = Deploy::default();
+        assert_eq!(s3_storage_class(&config).unwrap(), None);
+
+        config.s3_storage_class = Some("standard_ia".into());
+        assert_eq!(
+            s3_storage_class(&config).unwrap(),
+            Some(StorageClass::StandardIa)
+        );
+
+        config.s3_storage_class = Some("FROZEN".into());
+        let err = s3_storage_class(&config).unwrap_err();
+        assert_contains!(err.to_string(), "invalid S3 storage class `FROZEN`");
+    }
+
+    #[test]
+    fn test_signature_identity() {
+        let mut config = Deploy::default();
+        config.verify_signature = true;
+        let err = signature_identity(&config).unwrap_err();
+        assert_contains!(err.to_string(), "requires --signature-identity");
+
+        config.signature_identity = Some("alice@example.com".into());
+        config.signature_issuer = Some("https://accounts.google.com".into());
+        assert_eq!(
+            ("alice@example.com", "https://accounts.google.com"),
+            signature_identity(&config).unwrap()
+        );
+
+        config.include = Some(vec!["src".into()]);
+        let err = signature_identity(&config).unwrap_err();
+        assert_contains!(err.to_string(), "--include cannot be set together");
+    }
+
     #[test]
     fn test_load_archive_from_binary_path() {
         let mut config = Deploy::default();
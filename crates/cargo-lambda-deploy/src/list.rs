@@ -0,0 +1,282 @@
+use cargo_lambda_build::{create_binary_archive, BinaryData};
+use cargo_lambda_interactive::progress::Progress;
+use cargo_lambda_metadata::cargo::{
+    binary_targets_from_metadata, deploy::OutputFormat, CargoMetadata,
+};
+use cargo_lambda_remote::{
+    aws_sdk_lambda::{types::FunctionConfiguration, Client as LambdaClient},
+    RemoteConfig,
+};
+use clap::{Args, ValueHint};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use serde::Serialize;
+use serde_json::ser::to_string_pretty;
+use std::{
+    collections::HashSet,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+const DEFAULT_MANIFEST_PATH: &str = "Cargo.toml";
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "list",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/list.html"
+)]
+pub struct List {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Directory where the lambda binaries are located
+    #[arg(short, long, value_hint = ValueHint::DirPath)]
+    lambda_dir: Option<PathBuf>,
+
+    /// Path to Cargo.toml, to compare the deployed code with the binaries built in the workspace
+    #[arg(long, value_name = "PATH", default_value = DEFAULT_MANIFEST_PATH)]
+    manifest_path: PathBuf,
+
+    /// List only the functions that have a binary in the workspace
+    #[arg(long)]
+    workspace: bool,
+
+    /// Format to render the output (text, or json)
+    #[arg(short, long)]
+    output_format: Option<OutputFormat>,
+}
+
+/// Deployed function, compared with the binary built in the workspace.
+#[derive(Debug, PartialEq, Serialize)]
+struct FunctionSummary {
+    name: String,
+    runtime: String,
+    architecture: String,
+    memory: i32,
+    last_modified: String,
+    local_code: Option<LocalCode>,
+}
+
+/// Status of the deployed code compared with the binary built in the workspace.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, strum_macros::Display)]
+#[serde(rename_all = "snake_case")]
+enum LocalCode {
+    #[strum(serialize = "up to date")]
+    UpToDate,
+    #[strum(serialize = "outdated")]
+    Outdated,
+    #[strum(serialize = "not built")]
+    NotBuilt,
+}
+
+impl List {
+    pub fn manifest_path(&self) -> &Path {
+        &self.manifest_path
+    }
+
+    #[tracing::instrument(skip(self, metadata), target = "cargo_lambda")]
+    pub async fn run(&self, metadata: Option<&CargoMetadata>) -> Result<()> {
+        let sdk_config = self.remote_config.sdk_config(None).await?;
+        let client = LambdaClient::new(&sdk_config);
+
+        let progress = Progress::start("loading functions");
+        let functions = list_functions(&client).await;
+        progress.finish_and_clear();
+        let functions = functions?;
+
+        let binaries = metadata
+            .map(|m| binary_targets_from_metadata(m, false))
+            .unwrap_or_default();
+
+        let summaries = functions
+            .iter()
+            .filter(|f| !self.workspace || binaries.contains(f.function_name().unwrap_or_default()))
+            .map(|f| self.summary(f, metadata, &binaries))
+            .collect::<Vec<_>>();
+
+        match self.output_format.clone().unwrap_or_default() {
+            OutputFormat::Text => print!("{}", format_table(&summaries)),
+            OutputFormat::Json => {
+                let text = to_string_pretty(&summaries)
+                    .into_diagnostic()
+                    .wrap_err("failed to serialize output into json")?;
+                println!("{text}")
+            }
+        }
+
+        Ok(())
+    }
+
+    fn summary(
+        &self,
+        function: &FunctionConfiguration,
+        metadata: Option<&CargoMetadata>,
+        binaries: &HashSet<String>,
+    ) -> FunctionSummary {
+        let name = function.function_name().unwrap_or_default().to_string();
+
+        let local_code = binaries
+            .contains(&name)
+            .then(|| self.local_code(&name, metadata, function.code_sha256()));
+
+        FunctionSummary {
+            runtime: function
+                .runtime()
+                .map(|r| r.as_str().to_string())
+                .unwrap_or_else(|| "container image".to_string()),
+            architecture: function
+                .architectures()
+                .first()
+                .map(|a| a.as_str().to_string())
+                .unwrap_or_else(|| "x86_64".to_string()),
+            memory: function.memory_size().unwrap_or_default(),
+            last_modified: function.last_modified().unwrap_or_default().to_string(),
+            local_code,
+            name,
+        }
+    }
+
+    /// Compare the deployed code with the zip file of the binary built in the workspace.
+    fn local_code(
+        &self,
+        name: &str,
+        metadata: Option<&CargoMetadata>,
+        deployed_sha256: Option<&str>,
+    ) -> LocalCode {
+        let data = BinaryData::new(name, false, false);
+        match create_binary_archive(metadata, &self.lambda_dir, &data, None)
+            .and_then(|archive| archive.code_sha256())
+        {
+            Ok(sha256) => compare_code(deployed_sha256, &sha256),
+            Err(err) => {
+                tracing::debug!(?err, name, "failed to load the local binary");
+                LocalCode::NotBuilt
+            }
+        }
+    }
+}
+
+pub(crate) async fn list_functions(client: &LambdaClient) -> Result<Vec<FunctionConfiguration>> {
+    let mut pages = client.list_functions().into_paginator().send();
+
+    let mut functions = Vec::new();
+    while let Some(page) = pages.next().await {
+        let page = page
+            .into_diagnostic()
+            .wrap_err("failed to list functions")?;
+        functions.extend(page.functions.unwrap_or_default());
+    }
+
+    functions.sort_by(|a, b| a.function_name().cmp(&b.function_name()));
+    Ok(functions)
+}
+
+fn compare_code(deployed_sha256: Option<&str>, local_sha256: &str) -> LocalCode {
+    if deployed_sha256 == Some(local_sha256) {
+        LocalCode::UpToDate
+    } else {
+        LocalCode::Outdated
+    }
+}
+
+fn format_table(functions: &[FunctionSummary]) -> String {
+    if functions.is_empty() {
+        return "No functions found\n".to_string();
+    }
+
+    let header = [
+        "NAME",
+        "RUNTIME",
+        "ARCHITECTURE",
+        "MEMORY",
+        "LAST MODIFIED",
+        "LOCAL CODE",
+    ];
+    let rows = functions
+        .iter()
+        .map(|f| {
+            [
+                f.name.clone(),
+                f.runtime.clone(),
+                f.architecture.clone(),
+                format!("{} MB", f.memory),
+                f.last_modified.clone(),
+                f.local_code
+                    .map(|c| c.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    format_rows(header, rows)
+}
+
+/// Render rows in columns aligned to the widest cell of each column.
+pub(crate) fn format_rows<const N: usize>(header: [&str; N], rows: Vec<[String; N]>) -> String {
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut output = String::new();
+    let lines = std::iter::once(header.map(String::from)).chain(rows);
+    for row in lines {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        let _ = writeln!(output, "{}", line.trim_end());
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(name: &str, local_code: Option<LocalCode>) -> FunctionSummary {
+        FunctionSummary {
+            name: name.into(),
+            runtime: "provided.al2023".into(),
+            architecture: "arm64".into(),
+            memory: 128,
+            last_modified: "2024-11-14T22:13:20.123+0000".into(),
+            local_code,
+        }
+    }
+
+    #[test]
+    fn test_compare_code() {
+        assert_eq!(LocalCode::UpToDate, compare_code(Some("abc="), "abc="));
+        assert_eq!(LocalCode::Outdated, compare_code(Some("abc="), "def="));
+        assert_eq!(LocalCode::Outdated, compare_code(None, "def="));
+    }
+
+    #[test]
+    fn test_format_table() {
+        let table = format_table(&[
+            summary("orders", Some(LocalCode::UpToDate)),
+            summary("payments-processor", None),
+        ]);
+
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(3, lines.len());
+        assert!(lines[0].starts_with("NAME                RUNTIME"));
+        assert!(lines[1].starts_with("orders              provided.al2023"));
+        assert!(lines[1].ends_with("up to date"));
+        assert!(lines[2].ends_with("-"));
+
+        assert_eq!("No functions found\n", format_table(&[]));
+    }
+
+    #[test]
+    fn test_json_output() {
+        let json = serde_json::to_value(summary("orders", Some(LocalCode::NotBuilt))).unwrap();
+        assert_eq!("not_built", json["local_code"]);
+        assert_eq!(128, json["memory"]);
+    }
+}
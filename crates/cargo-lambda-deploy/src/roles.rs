@@ -1,13 +1,473 @@
 use aws_sdk_iam::Client as IamClient;
 use aws_sdk_sts::{Client as StsClient, Error};
-use aws_smithy_types::error::metadata::ProvideErrorMetadata;
-use cargo_lambda_interactive::progress::Progress;
-use cargo_lambda_remote::aws_sdk_config::SdkConfig;
+use aws_smithy_types::{date_time::Format, error::metadata::ProvideErrorMetadata};
+use cargo_lambda_interactive::{
+    is_stdin_tty, is_user_cancellation_error, progress::Progress, Confirm,
+};
+use cargo_lambda_metadata::cargo::deploy::OutputFormat;
+use cargo_lambda_remote::{
+    aws_sdk_config::SdkConfig, aws_sdk_lambda::Client as LambdaClient, Partition, RemoteConfig,
+};
+use clap::{Args, Subcommand};
 use miette::{IntoDiagnostic, Result, WrapErr};
+use serde::Serialize;
+use serde_json::{ser::to_string_pretty, Value};
+use std::{collections::HashSet, fmt::Display};
 use tokio::time::{sleep, Duration};
 
-const BASIC_LAMBDA_EXECUTION_POLICY: &str =
-    "arn:aws:iam::aws:policy/service-role/AWSLambdaBasicExecutionRole";
+use crate::list::list_functions;
+
+/// Name of the policy managed by AWS that lets functions write their logs.
+const BASIC_LAMBDA_EXECUTION_POLICY: &str = "service-role/AWSLambdaBasicExecutionRole";
+
+/// Prefix of the roles that Cargo Lambda creates when it deploys a function without a role.
+pub(crate) const ROLE_NAME_PREFIX: &str = "cargo-lambda-role-";
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "role",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/role.html"
+)]
+pub struct Role {
+    #[command(subcommand)]
+    command: RoleCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum RoleCommand {
+    /// Create an execution role with the basic execution policy attached
+    Create(CreateRole),
+    /// Attach managed policies to a role
+    Attach(AttachPolicies),
+    /// Detach managed policies from a role
+    Detach(DetachPolicies),
+    /// Show a role's trust policy, and its attached policies
+    Show(ShowRole),
+    /// Delete the roles created by Cargo Lambda that no function uses
+    Cleanup(Cleanup),
+}
+
+#[derive(Args, Clone, Debug)]
+struct CreateRole {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Name of the role, a name that starts with `cargo-lambda-role-` by default
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Managed policies to attach to the role, besides the basic execution policy.
+    /// Use the policy ARN, or the name of a policy managed by AWS, like `AmazonS3ReadOnlyAccess`
+    #[arg(long, value_name = "POLICY")]
+    policy: Vec<String>,
+
+    /// Format to render the output (text, or json)
+    #[arg(short, long)]
+    output_format: Option<OutputFormat>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct AttachPolicies {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Name, or ARN of the role
+    #[arg(value_name = "ROLE")]
+    role: String,
+
+    /// Policy ARNs, or names of policies managed by AWS, like `AmazonS3ReadOnlyAccess`
+    #[arg(value_name = "POLICY", required = true)]
+    policies: Vec<String>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct DetachPolicies {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Name, or ARN of the role
+    #[arg(value_name = "ROLE")]
+    role: String,
+
+    /// Policy ARNs, or names of policies managed by AWS, like `AmazonS3ReadOnlyAccess`
+    #[arg(value_name = "POLICY", required = true)]
+    policies: Vec<String>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct ShowRole {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Name, or ARN of the role
+    #[arg(value_name = "ROLE")]
+    role: String,
+
+    /// Format to render the output (text, or json)
+    #[arg(short, long)]
+    output_format: Option<OutputFormat>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct Cleanup {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Print the roles to delete, without deleting them
+    #[arg(long)]
+    dry: bool,
+
+    /// Delete the roles without asking for confirmation
+    #[arg(short, long)]
+    yes: bool,
+
+    /// Format to render the output (text, or json)
+    #[arg(short, long)]
+    output_format: Option<OutputFormat>,
+}
+
+/// Execution role, with its trust policy, and the managed policies attached to it.
+#[derive(Debug, Serialize)]
+struct RoleInfo {
+    name: String,
+    arn: String,
+    created_date: Option<String>,
+    trust_policy: Value,
+    attached_policies: Vec<String>,
+}
+
+impl Display for RoleInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "🔍 role: {}", self.name)?;
+        writeln!(f, "🔗 arn: {}", self.arn)?;
+        if let Some(created_date) = &self.created_date {
+            writeln!(f, "📅 created: {created_date}")?;
+        }
+
+        writeln!(f, "📜 attached policies:")?;
+        if self.attached_policies.is_empty() {
+            writeln!(f, "   none")?;
+        }
+        for policy in &self.attached_policies {
+            writeln!(f, "   - {policy}")?;
+        }
+
+        writeln!(f, "🤝 trust policy:")?;
+        let policy = to_string_pretty(&self.trust_policy).map_err(|_| std::fmt::Error)?;
+        for line in policy.lines() {
+            writeln!(f, "   {line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Roles that the cleanup deletes, and the roles that it keeps because functions use them.
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct CleanupPlan {
+    delete: Vec<String>,
+    in_use: Vec<String>,
+}
+
+impl Role {
+    #[tracing::instrument(skip(self), target = "cargo_lambda")]
+    pub async fn run(&self) -> Result<()> {
+        match &self.command {
+            RoleCommand::Create(cmd) => cmd.run().await,
+            RoleCommand::Attach(cmd) => cmd.run().await,
+            RoleCommand::Detach(cmd) => cmd.run().await,
+            RoleCommand::Show(cmd) => cmd.run().await,
+            RoleCommand::Cleanup(cmd) => cmd.run().await,
+        }
+    }
+}
+
+impl CreateRole {
+    async fn run(&self) -> Result<()> {
+        let sdk_config = self.remote_config.sdk_config(None).await?;
+        let role_name = self
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("{ROLE_NAME_PREFIX}{}", uuid::Uuid::new_v4()));
+
+        let progress = Progress::start("creating execution role");
+        let result = create_with_name(&sdk_config, &role_name, &progress).await;
+        let partition = self.remote_config.partition(&sdk_config);
+        let result = match result {
+            Ok(_) => {
+                let client = IamClient::new(&sdk_config);
+                attach(&client, &role_name, &self.policy, partition).await
+            }
+            Err(err) => Err(err),
+        };
+        progress.finish_and_clear();
+        result?;
+
+        let info = load_role(&IamClient::new(&sdk_config), &role_name).await?;
+        print_role(&info, &self.output_format)
+    }
+}
+
+impl AttachPolicies {
+    async fn run(&self) -> Result<()> {
+        let sdk_config = self.remote_config.sdk_config(None).await?;
+        let role = role_name(&self.role);
+        let partition = self.remote_config.partition(&sdk_config);
+
+        attach(
+            &IamClient::new(&sdk_config),
+            role,
+            &self.policies,
+            partition,
+        )
+        .await?;
+        for policy in &self.policies {
+            println!(
+                "✅ {} attached to the role `{role}`",
+                policy_arn(policy, partition)
+            );
+        }
+        Ok(())
+    }
+}
+
+impl DetachPolicies {
+    async fn run(&self) -> Result<()> {
+        let sdk_config = self.remote_config.sdk_config(None).await?;
+        let client = IamClient::new(&sdk_config);
+        let role = role_name(&self.role);
+        let partition = self.remote_config.partition(&sdk_config);
+
+        for policy in &self.policies {
+            let arn = policy_arn(policy, partition);
+            client
+                .detach_role_policy()
+                .role_name(role)
+                .policy_arn(&arn)
+                .send()
+                .await
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to detach {arn} from the role"))?;
+            println!("✅ {arn} detached from the role `{role}`");
+        }
+        Ok(())
+    }
+}
+
+impl ShowRole {
+    async fn run(&self) -> Result<()> {
+        let sdk_config = self.remote_config.sdk_config(None).await?;
+
+        let progress = Progress::start("loading role");
+        let info = load_role(&IamClient::new(&sdk_config), role_name(&self.role)).await;
+        progress.finish_and_clear();
+
+        print_role(&info?, &self.output_format)
+    }
+}
+
+impl Cleanup {
+    async fn run(&self) -> Result<()> {
+        let sdk_config = self.remote_config.sdk_config(None).await?;
+        let iam_client = IamClient::new(&sdk_config);
+
+        let progress = Progress::start("loading roles and functions");
+        let plan = self.plan(&sdk_config, &iam_client).await;
+        progress.finish_and_clear();
+        let plan = plan?;
+
+        let json = matches!(
+            self.output_format.clone().unwrap_or_default(),
+            OutputFormat::Json
+        );
+
+        if json {
+            let text = to_string_pretty(&plan)
+                .into_diagnostic()
+                .wrap_err("failed to serialize output into json")?;
+            println!("{text}");
+        } else {
+            for role in &plan.in_use {
+                println!("⚠️  the role `{role}` is not deleted, functions still use it");
+            }
+            if plan.delete.is_empty() {
+                println!("✅ there are no roles to clean up");
+                return Ok(());
+            }
+            println!("🔍 Roles to delete:");
+            for role in &plan.delete {
+                println!("   - {role}");
+            }
+        }
+
+        if self.dry || plan.delete.is_empty() || !self.confirm()? {
+            return Ok(());
+        }
+
+        let progress = Progress::start("deleting roles");
+        for role in &plan.delete {
+            progress.set_message(&format!("deleting role `{role}`"));
+            if let Err(err) = delete(&sdk_config, role).await {
+                progress.finish_and_clear();
+                return Err(err);
+            }
+        }
+        progress.finish_and_clear();
+
+        if !json {
+            println!("✅ {} roles deleted", plan.delete.len());
+        }
+        Ok(())
+    }
+
+    async fn plan(&self, sdk_config: &SdkConfig, iam_client: &IamClient) -> Result<CleanupPlan> {
+        let mut pages = iam_client.list_roles().into_paginator().items().send();
+        let mut roles = Vec::new();
+        while let Some(role) = pages.next().await {
+            let role = role.into_diagnostic().wrap_err("failed to list roles")?;
+            roles.push(role.role_name().to_string());
+        }
+
+        let functions = list_functions(&LambdaClient::new(sdk_config)).await?;
+        let used = functions
+            .iter()
+            .filter_map(|f| f.role())
+            .map(|arn| role_name(arn).to_string())
+            .collect::<HashSet<_>>();
+
+        Ok(cleanup_plan(roles, &used))
+    }
+
+    fn confirm(&self) -> Result<bool> {
+        if self.yes {
+            return Ok(true);
+        }
+
+        if !is_stdin_tty() {
+            return Err(miette::miette!(
+                "confirmation required to delete the roles, use the flag `--yes` to delete them without confirmation"
+            ));
+        }
+
+        match Confirm::new("Do you want to delete these roles?")
+            .with_default(false)
+            .prompt()
+        {
+            Err(err) if is_user_cancellation_error(&err) => Ok(false),
+            result => result.into_diagnostic(),
+        }
+    }
+}
+
+fn print_role(info: &RoleInfo, output_format: &Option<OutputFormat>) -> Result<()> {
+    match output_format.clone().unwrap_or_default() {
+        OutputFormat::Text => print!("{info}"),
+        OutputFormat::Json => {
+            let text = to_string_pretty(info)
+                .into_diagnostic()
+                .wrap_err("failed to serialize output into json")?;
+            println!("{text}");
+        }
+    }
+    Ok(())
+}
+
+async fn attach(
+    client: &IamClient,
+    role: &str,
+    policies: &[String],
+    partition: Partition,
+) -> Result<()> {
+    for policy in policies {
+        let arn = policy_arn(policy, partition);
+        client
+            .attach_role_policy()
+            .role_name(role)
+            .policy_arn(&arn)
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to attach {arn} to the role"))?;
+    }
+    Ok(())
+}
+
+async fn load_role(client: &IamClient, name: &str) -> Result<RoleInfo> {
+    let role = client
+        .get_role()
+        .role_name(name)
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to load the role `{name}`"))?
+        .role
+        .ok_or_else(|| miette::miette!("the role `{name}` doesn't exist"))?;
+
+    let attached = client
+        .list_attached_role_policies()
+        .role_name(name)
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to list the role's policies")?;
+
+    let trust_policy = role
+        .assume_role_policy_document()
+        .map(decode_policy)
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(RoleInfo {
+        name: role.role_name().to_string(),
+        arn: role.arn().to_string(),
+        created_date: role.create_date().fmt(Format::DateTime).ok(),
+        trust_policy,
+        attached_policies: attached
+            .attached_policies()
+            .iter()
+            .filter_map(|p| p.policy_arn.clone())
+            .collect(),
+    })
+}
+
+/// IAM returns policy documents URL encoded.
+fn decode_policy(document: &str) -> Result<Value> {
+    let decoded = urlencoding::decode(document)
+        .into_diagnostic()
+        .wrap_err("invalid policy document")?;
+    serde_json::from_str(&decoded)
+        .into_diagnostic()
+        .wrap_err("invalid policy document")
+}
+
+/// Role name from a role name, or a role ARN, like `arn:aws:iam::123456789012:role/service-role/my-role`.
+fn role_name(role: &str) -> &str {
+    role.rsplit('/').next().unwrap_or(role)
+}
+
+/// Policy ARN from a policy ARN, or the name of a policy managed by AWS.
+fn policy_arn(policy: &str, partition: Partition) -> String {
+    if policy.starts_with("arn:") {
+        policy.to_string()
+    } else {
+        partition.managed_policy_arn(policy)
+    }
+}
+
+/// Roles created by Cargo Lambda are deleted only when no function uses them.
+fn cleanup_plan(roles: Vec<String>, used: &HashSet<String>) -> CleanupPlan {
+    let mut plan = CleanupPlan::default();
+    for role in roles {
+        if !role.starts_with(ROLE_NAME_PREFIX) {
+            continue;
+        }
+        if used.contains(&role) {
+            plan.in_use.push(role);
+        } else {
+            plan.delete.push(role);
+        }
+    }
+    plan
+}
 
 #[derive(Debug)]
 pub(crate) struct FunctionRole(String, bool);
@@ -33,9 +493,18 @@ impl FunctionRole {
 }
 
 pub(crate) async fn create(config: &SdkConfig, progress: &Progress) -> Result<FunctionRole> {
+    let role_name = format!("{ROLE_NAME_PREFIX}{}", uuid::Uuid::new_v4());
+    create_with_name(config, &role_name, progress).await
+}
+
+/// Create a role that AWS Lambda can assume, with the basic execution policy attached.
+pub(crate) async fn create_with_name(
+    config: &SdkConfig,
+    role_name: &str,
+    progress: &Progress,
+) -> Result<FunctionRole> {
     progress.set_message("creating execution role");
 
-    let role_name = format!("cargo-lambda-role-{}", uuid::Uuid::new_v4());
     let client = IamClient::new(config);
     let sts_client = StsClient::new(config);
     let identity = sts_client
@@ -44,6 +513,10 @@ pub(crate) async fn create(config: &SdkConfig, progress: &Progress) -> Result<Fu
         .await
         .into_diagnostic()
         .wrap_err("failed to get caller identity")?;
+    let identity_arn = identity.arn().expect("missing account arn");
+    // The caller's ARN has the partition of the account, even when the region doesn't.
+    let partition =
+        Partition::from_arn(identity_arn).unwrap_or_else(|| Partition::from_sdk_config(config));
 
     let mut policy = serde_json::json!({
         "Version": "2012-10-17",
@@ -59,7 +532,7 @@ pub(crate) async fn create(config: &SdkConfig, progress: &Progress) -> Result<Fu
                 "Effect": "Allow",
                 "Action": ["sts:AssumeRole", "sts:SetSourceIdentity", "sts:TagSession"],
                 "Principal": {
-                    "AWS": identity.arn().expect("missing account arn"),
+                    "AWS": identity_arn,
                 }
             }
         ]
@@ -69,7 +542,7 @@ pub(crate) async fn create(config: &SdkConfig, progress: &Progress) -> Result<Fu
 
     let role = client
         .create_role()
-        .role_name(&role_name)
+        .role_name(role_name)
         .assume_role_policy_document(policy.to_string())
         .send()
         .await
@@ -80,8 +553,8 @@ pub(crate) async fn create(config: &SdkConfig, progress: &Progress) -> Result<Fu
 
     client
         .attach_role_policy()
-        .role_name(&role_name)
-        .policy_arn(BASIC_LAMBDA_EXECUTION_POLICY)
+        .role_name(role_name)
+        .policy_arn(partition.managed_policy_arn(BASIC_LAMBDA_EXECUTION_POLICY))
         .send()
         .await
         .into_diagnostic()
@@ -103,7 +576,7 @@ pub(crate) async fn create(config: &SdkConfig, progress: &Progress) -> Result<Fu
 
     client
         .update_assume_role_policy()
-        .role_name(&role_name)
+        .role_name(role_name)
         .policy_document(policy.to_string())
         .send()
         .await
@@ -160,3 +633,117 @@ async fn try_assume_role(client: &StsClient, role_arn: &str) -> Result<()> {
         role_arn
     ))
 }
+
+/// Detach the role's policies, and delete it.
+pub(crate) async fn delete(sdk_config: &SdkConfig, role: &str) -> Result<()> {
+    let client = IamClient::new(sdk_config);
+
+    let attached = client
+        .list_attached_role_policies()
+        .role_name(role)
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to list the role's policies")?;
+    for policy in attached.attached_policies() {
+        client
+            .detach_role_policy()
+            .role_name(role)
+            .set_policy_arn(policy.policy_arn.clone())
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err("failed to detach policy from the role")?;
+    }
+
+    let inline = client
+        .list_role_policies()
+        .role_name(role)
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to list the role's inline policies")?;
+    for policy in inline.policy_names() {
+        client
+            .delete_role_policy()
+            .role_name(role)
+            .policy_name(policy)
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err("failed to delete inline policy from the role")?;
+    }
+
+    client
+        .delete_role()
+        .role_name(role)
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to delete role")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_name() {
+        assert_eq!(
+            "orders-role",
+            role_name("arn:aws:iam::123456789012:role/service-role/orders-role")
+        );
+        assert_eq!("orders-role", role_name("orders-role"));
+    }
+
+    #[test]
+    fn test_policy_arn() {
+        assert_eq!(
+            "arn:aws:iam::aws:policy/AmazonS3ReadOnlyAccess",
+            policy_arn("AmazonS3ReadOnlyAccess", Partition::Aws)
+        );
+        assert_eq!(
+            "arn:aws:iam::aws:policy/service-role/AWSLambdaBasicExecutionRole",
+            policy_arn(
+                "arn:aws:iam::aws:policy/service-role/AWSLambdaBasicExecutionRole",
+                Partition::Aws
+            )
+        );
+        assert_eq!(
+            "arn:aws-us-gov:iam::aws:policy/service-role/AWSLambdaBasicExecutionRole",
+            policy_arn(BASIC_LAMBDA_EXECUTION_POLICY, Partition::AwsUsGov)
+        );
+        assert_eq!(
+            "arn:aws-cn:iam::aws:policy/AmazonS3ReadOnlyAccess",
+            policy_arn("AmazonS3ReadOnlyAccess", Partition::AwsCn)
+        );
+    }
+
+    #[test]
+    fn test_decode_policy() {
+        let document = "%7B%22Version%22%3A%222012-10-17%22%2C%22Statement%22%3A%5B%5D%7D";
+        let policy = decode_policy(document).unwrap();
+        assert_eq!("2012-10-17", policy["Version"]);
+        assert!(decode_policy("not json").is_err());
+    }
+
+    #[test]
+    fn test_cleanup_plan() {
+        let roles = vec![
+            "cargo-lambda-role-0b1c2d3e".to_string(),
+            "cargo-lambda-role-4f5a6b7c".to_string(),
+            "orders-role".to_string(),
+        ];
+        let used = HashSet::from(["cargo-lambda-role-4f5a6b7c".to_string()]);
+
+        assert_eq!(
+            CleanupPlan {
+                delete: vec!["cargo-lambda-role-0b1c2d3e".into()],
+                in_use: vec!["cargo-lambda-role-4f5a6b7c".into()],
+            },
+            cleanup_plan(roles, &used)
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use serde::Deserialize;
+use std::path::PathBuf;
 
+use cargo_lambda_interactive::error::InquireError;
 use miette::Diagnostic;
 use thiserror::Error;
 
@@ -11,17 +13,31 @@ pub enum InvokeError {
         "invalid function name, it must match the name you used to create the function remotely"
     )]
     InvalidFunctionName,
-    #[error("no data payload provided, use one of the data flags: `--data-file`, `--data-ascii`, `--data-example`")]
+    #[error("no data payload provided, use one of the data flags: `--data-file`, `--data-ascii`, `--data-binary`, `--data-example`, `--build-event`")]
     MissingPayload,
+    #[error(
+        "`--build-event` requires an interactive terminal, use one of the other data flags instead"
+    )]
+    NonInteractiveEventBuilder,
+    #[error("the query `{0}` didn't match any value in the response")]
+    QueryNoMatch(String),
+    #[error("unexpected input")]
+    UnexpectedInput(#[from] InquireError),
     #[error("invalid error payload {0}")]
     InvalidErrorPayload(#[from] serde_json::Error),
+    #[error("the example `{0}` is not a JSON payload, it can't be patched: {1}")]
+    InvalidExamplePayload(String, serde_json::Error),
+    #[error("invalid example patch {0}: {1}")]
+    InvalidExamplePatch(PathBuf, serde_json::Error),
+    #[error("failed to apply the example patch {0}: {1}")]
+    ExamplePatchFailed(PathBuf, json_patch::PatchError),
 }
 
 #[derive(Debug, Deserialize)]
 pub struct RemoteInvokeError {
-    #[serde(rename = "errorType", alias = "title")]
+    #[serde(rename = "errorType", alias = "title", alias = "Type")]
     code: String,
-    #[serde(rename = "errorMessage", alias = "detail")]
+    #[serde(rename = "errorMessage", alias = "detail", alias = "message")]
     message: String,
 }
 
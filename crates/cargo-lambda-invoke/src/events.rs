@@ -0,0 +1,203 @@
+use cargo_lambda_interactive::{
+    error::CustomUserError,
+    is_stdin_tty,
+    validator::{ErrorMessage, Validation},
+    CustomType, Text,
+};
+use cargo_lambda_metadata::{DEFAULT_ACCOUNT_ID, DEFAULT_REGION};
+use miette::{IntoDiagnostic, Result};
+use serde_json::{json, Map, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+use strum_macros::{Display, EnumString};
+use uuid::Uuid;
+
+use crate::error::InvokeError;
+
+const DEFAULT_QUEUE_NAME: &str = "my-queue";
+
+/// Event types that can be built interactively with `--build-event`
+#[derive(Clone, Debug, Display, EnumString)]
+#[strum(ascii_case_insensitive, serialize_all = "snake_case")]
+pub(crate) enum EventBuilder {
+    Sqs,
+}
+
+impl EventBuilder {
+    /// Ask the user for the event information, and return the JSON payload
+    pub(crate) fn build(&self) -> Result<String> {
+        if !is_stdin_tty() {
+            return Err(InvokeError::NonInteractiveEventBuilder.into());
+        }
+
+        let event = match self {
+            EventBuilder::Sqs => ask_sqs_event()?,
+        };
+
+        serde_json::to_string(&event).into_diagnostic()
+    }
+}
+
+#[derive(Debug, Default)]
+struct SqsRecordInput {
+    body: String,
+    attributes: Vec<(String, String)>,
+}
+
+fn ask_sqs_event() -> Result<Value, InvokeError> {
+    let queue = Text::new("Queue name")
+        .with_default(DEFAULT_QUEUE_NAME)
+        .prompt()?;
+
+    let count = CustomType::<usize>::new("Number of records")
+        .with_help_message("how many messages the function receives in the batch")
+        .with_error_message("please type a valid number")
+        .prompt()?;
+
+    let mut records = Vec::with_capacity(count);
+    for i in 1..=count {
+        let body = Text::new(&format!("Body for record {i}")).prompt()?;
+
+        let attributes = Text::new(&format!("Message attributes for record {i}"))
+            .with_help_message("comma separated list of KEY=VALUE pairs, leave it empty to skip")
+            .with_validator(validate_attributes)
+            .prompt()?;
+
+        records.push(SqsRecordInput {
+            body,
+            attributes: parse_attributes(&attributes),
+        });
+    }
+
+    Ok(sqs_event(&queue, &records))
+}
+
+fn sqs_event(queue: &str, records: &[SqsRecordInput]) -> Value {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+        .to_string();
+
+    let records = records
+        .iter()
+        .map(|record| {
+            let mut attributes = Map::new();
+            for (key, value) in &record.attributes {
+                attributes.insert(
+                    key.clone(),
+                    json!({
+                        "stringValue": value,
+                        "stringListValues": [],
+                        "binaryListValues": [],
+                        "dataType": "String",
+                    }),
+                );
+            }
+
+            json!({
+                "messageId": Uuid::new_v4().to_string(),
+                "receiptHandle": Uuid::new_v4().to_string(),
+                "body": record.body,
+                "attributes": {
+                    "ApproximateReceiveCount": "1",
+                    "SentTimestamp": now,
+                    "SenderId": DEFAULT_ACCOUNT_ID,
+                    "ApproximateFirstReceiveTimestamp": now,
+                },
+                "messageAttributes": attributes,
+                "eventSource": "aws:sqs",
+                "eventSourceARN": format!("arn:aws:sqs:{DEFAULT_REGION}:{DEFAULT_ACCOUNT_ID}:{queue}"),
+                "awsRegion": DEFAULT_REGION,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({ "Records": records })
+}
+
+fn parse_attributes(input: &str) -> Vec<(String, String)> {
+    input
+        .split(',')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            let key = key.trim();
+            if key.is_empty() {
+                return None;
+            }
+            Some((key.to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+fn validate_attributes(input: &str) -> Result<Validation, CustomUserError> {
+    let invalid = input
+        .split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .find(|pair| {
+            pair.split_once('=')
+                .map_or(true, |(k, _)| k.trim().is_empty())
+        });
+
+    match invalid {
+        None => Ok(Validation::Valid),
+        Some(pair) => Ok(Validation::Invalid(ErrorMessage::Custom(format!(
+            "invalid attribute `{pair}`, use the format KEY=VALUE"
+        )))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_attributes() {
+        assert!(parse_attributes("").is_empty());
+        assert_eq!(
+            parse_attributes("foo=bar, baz = qux"),
+            vec![
+                ("foo".to_string(), "bar".to_string()),
+                ("baz".to_string(), "qux".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_attributes() {
+        assert!(matches!(validate_attributes(""), Ok(Validation::Valid)));
+        assert!(matches!(
+            validate_attributes("foo=bar,baz=qux"),
+            Ok(Validation::Valid)
+        ));
+        assert!(matches!(
+            validate_attributes("foo"),
+            Ok(Validation::Invalid(_))
+        ));
+        assert!(matches!(
+            validate_attributes("=bar"),
+            Ok(Validation::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_sqs_event() {
+        let records = vec![SqsRecordInput {
+            body: "hello".into(),
+            attributes: vec![("foo".into(), "bar".into())],
+        }];
+
+        let event = sqs_event("test-queue", &records);
+        let records = event["Records"].as_array().unwrap();
+        assert_eq!(1, records.len());
+
+        let record = &records[0];
+        assert_eq!("hello", record["body"]);
+        assert_eq!("aws:sqs", record["eventSource"]);
+        assert_eq!(
+            "arn:aws:sqs:us-east-1:123456789012:test-queue",
+            record["eventSourceARN"]
+        );
+        assert_eq!("bar", record["messageAttributes"]["foo"]["stringValue"]);
+        assert_eq!("String", record["messageAttributes"]["foo"]["dataType"]);
+    }
+}
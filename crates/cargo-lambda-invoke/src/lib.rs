@@ -1,22 +1,33 @@
 use base64::{engine::general_purpose as b64, Engine as _};
-use cargo_lambda_metadata::DEFAULT_PACKAGE_FUNCTION;
+use cargo_lambda_metadata::{
+    config::ExamplesConfig, CARGO_LAMBDA_COLD_START_HEADER, DEFAULT_PACKAGE_FUNCTION,
+};
 use cargo_lambda_remote::{
-    aws_sdk_lambda::{primitives::Blob, Client as LambdaClient},
+    aws_sdk_lambda::{
+        operation::RequestId, primitives::Blob, types::LogType, Client as LambdaClient,
+    },
+    network,
     tls::TlsOptions,
     RemoteConfig,
 };
 use clap::{Args, ValueHint};
+use flate2::read::GzDecoder;
 use miette::{IntoDiagnostic, Result, WrapErr};
-use reqwest::{Client, StatusCode};
-use serde::Serialize;
-use serde_json::{from_str, to_string_pretty, value::Value};
+use reqwest::{
+    header::{HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE},
+    Client, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, json, to_string_pretty, value::Value};
 use std::{
     convert::TryFrom,
-    fs::{create_dir_all, read_to_string, File},
-    io::copy,
+    fmt::Display,
+    fs::{create_dir_all, read, read_to_string, File},
+    io::{copy, Read},
     net::IpAddr,
-    path::PathBuf,
+    path::{Path, PathBuf},
     str::{from_utf8, FromStr},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use strum_macros::{Display, EnumString};
 use tracing::debug;
@@ -24,11 +35,27 @@ use tracing::debug;
 mod error;
 use error::*;
 
+mod events;
+use events::EventBuilder;
+
+mod logs;
+use logs::{fetch_invocation_logs, is_remote_cold_start, print_logs, print_remote_logs};
+
+mod query;
+use query::Query;
+
 const EXAMPLES_URL: &str = "https://event-examples.cargo-lambda.info";
 
 const LAMBDA_RUNTIME_CLIENT_CONTEXT: &str = "lambda-runtime-client-context";
 const LAMBDA_RUNTIME_COGNITO_IDENTITY: &str = "lambda-runtime-cognito-identity";
 
+/// Content type sent to the emulator when the payload is base64 encoded binary data
+const BINARY_CONTENT_TYPE: &str = "application/octet-stream";
+
+const GZIP_MAGIC_BYTES: [u8; 2] = [0x1f, 0x8b];
+
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 #[derive(Args, Clone, Debug)]
 #[command(
     name = "invoke",
@@ -58,10 +85,23 @@ pub struct Invoke {
     #[arg(short = 'A', long)]
     data_ascii: Option<String>,
 
+    /// File with binary data to send as the invoke payload, encoded in base64
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    data_binary: Option<PathBuf>,
+
     /// Example payload from AWS Lambda Events
     #[arg(short = 'E', long)]
     data_example: Option<String>,
 
+    /// File with a JSON Merge Patch (RFC 7396), or a JSON Patch (RFC 6902),
+    /// to apply to the example payload before sending it
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath, requires = "data_example")]
+    example_patch: Option<PathBuf>,
+
+    /// Build the payload interactively for the given event type (sqs)
+    #[arg(long, value_name = "EVENT_TYPE")]
+    build_event: Option<EventBuilder>,
+
     /// Invoke the function already deployed on AWS Lambda
     #[arg(short = 'R', long)]
     remote: bool,
@@ -84,16 +124,49 @@ pub struct Invoke {
     #[command(flatten)]
     cognito: Option<CognitoIdentity>,
 
+    /// Path to a file with the JSON representation of the Cognito identity for the function invocation
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    cognito_identity_file: Option<PathBuf>,
+
+    /// Additional header to send to the function through the runtime API, in the format KEY=VALUE.
+    /// Only headers that start with `lambda-runtime-` are forwarded to the function.
+    #[arg(long = "runtime-header", value_name = "KEY=VALUE", value_parser = parse_runtime_header, conflicts_with = "remote")]
+    runtime_headers: Vec<(HeaderName, HeaderValue)>,
+
     /// Ignore data stored in the local cache
     #[arg(long, default_value_t = false)]
     skip_cache: bool,
 
+    /// Print the function logs when a remote invocation returns an error
+    #[arg(long, default_value_t = false)]
+    show_logs_on_error: bool,
+
+    /// Extract a value from the response with a JSONPath expression, like `$.body.items[0].id`
+    #[arg(long, value_name = "JSONPATH")]
+    query: Option<Query>,
+
+    /// Keep running, and invoke the function again every time the emulator reloads it
+    #[arg(long, default_value_t = false, conflicts_with = "remote")]
+    watch: bool,
+
+    /// Print the curl command to invoke the function in the emulator, instead of sending the request
+    #[arg(long, default_value_t = false, conflicts_with_all = ["remote", "watch"])]
+    print_curl: bool,
+
+    /// Print how long the invocation took, and whether the function had a cold start
+    #[arg(long, default_value_t = false)]
+    show_timing: bool,
+
     /// Name of the function to invoke
     #[arg(default_value = DEFAULT_PACKAGE_FUNCTION)]
     function_name: String,
 
     #[command(flatten)]
     tls_options: TlsOptions,
+
+    /// Registry to download examples from, loaded from the global configuration
+    #[arg(skip)]
+    pub examples: ExamplesConfig,
 }
 
 #[derive(Clone, Debug, Display, EnumString)]
@@ -115,6 +188,48 @@ pub struct CognitoIdentity {
     pub identity_pool_id: Option<String>,
 }
 
+/// Response payload and metadata from a function invocation
+struct InvokeOutput {
+    payload: String,
+    cold_start: Option<bool>,
+}
+
+impl InvokeOutput {
+    fn new(payload: String) -> Self {
+        InvokeOutput {
+            payload,
+            cold_start: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Timing {
+    duration_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cold_start: Option<bool>,
+}
+
+impl Timing {
+    fn new(duration: Duration, cold_start: Option<bool>) -> Self {
+        Timing {
+            duration_ms: duration.as_millis(),
+            cold_start,
+        }
+    }
+}
+
+impl Display for Timing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "duration: {}ms", self.duration_ms)?;
+        match self.cold_start {
+            Some(true) => write!(f, ", cold start: yes"),
+            Some(false) => write!(f, ", cold start: no"),
+            None => Ok(()),
+        }
+    }
+}
+
 impl CognitoIdentity {
     fn is_valid(&self) -> bool {
         self.identity_id.is_some() && self.identity_pool_id.is_some()
@@ -122,6 +237,16 @@ impl CognitoIdentity {
 }
 
 impl Invoke {
+    /// Whether the function is invoked on AWS Lambda, instead of the local emulator.
+    pub fn is_remote(&self) -> bool {
+        self.remote
+    }
+
+    /// Use the role to assume in the configuration, when the flags don't select a different role.
+    pub fn inherit_role(&mut self, defaults: &RemoteConfig) {
+        self.remote_config.inherit_role(defaults);
+    }
+
     #[tracing::instrument(skip(self), target = "cargo_lambda")]
     pub async fn run(&self) -> Result<()> {
         tracing::trace!(options = ?self, "invoking function");
@@ -132,39 +257,102 @@ impl Invoke {
                 .wrap_err("error reading data file")?
         } else if let Some(data) = &self.data_ascii {
             data.clone()
+        } else if let Some(file) = &self.data_binary {
+            let content = read(file)
+                .into_diagnostic()
+                .wrap_err("error reading binary data file")?;
+            binary_payload(&content)?
         } else if let Some(example) = &self.data_example {
             let name = example_name(example);
 
-            let cache = dirs::cache_dir()
-                .map(|p| p.join("cargo-lambda").join("invoke-fixtures").join(&name));
+            let cache = dirs::cache_dir().map(|p| {
+                let fixtures = p.join("cargo-lambda").join("invoke-fixtures");
+                match &self.examples.url {
+                    Some(url) => fixtures.join(cache_key(url)).join(&name),
+                    None => fixtures.join(&name),
+                }
+            });
 
-            match cache {
+            let data = match cache {
                 Some(cache) if !self.skip_cache && cache.exists() => {
                     tracing::debug!(?cache, "using example from cache");
                     read_to_string(cache)
                         .into_diagnostic()
                         .wrap_err("error reading data file")?
                 }
-                _ if self.skip_cache => download_example(&name, None, None).await?,
-                _ => download_example(&name, cache, None).await?,
+                _ if self.skip_cache => download_example(&name, None, &self.examples).await?,
+                _ => download_example(&name, cache, &self.examples).await?,
+            };
+
+            match &self.example_patch {
+                Some(path) => {
+                    let patch = read_to_string(path)
+                        .into_diagnostic()
+                        .wrap_err("error reading example patch file")?;
+                    patch_example(example, &data, path, &patch)?
+                }
+                None => data,
             }
+        } else if let Some(builder) = &self.build_event {
+            builder.build()?
         } else {
             return Err(InvokeError::MissingPayload.into());
         };
 
-        let text = if self.remote {
-            self.invoke_remote(&data).await?
+        if self.print_curl {
+            println!("{}", self.curl_command(&data)?);
+            return Ok(());
+        }
+
+        let result = self.invoke_and_print(&data).await;
+        if !self.watch {
+            return result;
+        }
+
+        // The function might be broken until the next reload, so keep waiting for it.
+        if let Err(err) = result {
+            eprintln!("{err:?}");
+        }
+        self.watch_reloads(&data).await
+    }
+
+    async fn invoke_and_print(&self, data: &str) -> Result<()> {
+        let start = Instant::now();
+        let output = if self.remote {
+            self.invoke_remote(data).await?
         } else {
-            self.invoke_local(&data).await?
+            self.invoke_local(data).await?
+        };
+        let timing = Timing::new(start.elapsed(), output.cold_start);
+
+        let payload = match &self.query {
+            None => output.payload,
+            Some(query) => {
+                let obj: Value = from_str(&output.payload)
+                    .into_diagnostic()
+                    .wrap_err("failed to parse the response as json to run the query")?;
+                let value = query
+                    .extract(&obj)
+                    .ok_or_else(|| InvokeError::QueryNoMatch(query.to_string()))?;
+
+                match (&self.output_format, value) {
+                    (OutputFormat::Text, Value::String(s)) => s,
+                    (_, value) => value.to_string(),
+                }
+            }
         };
 
         let text = match &self.output_format {
-            OutputFormat::Text => text,
+            OutputFormat::Text => payload,
             OutputFormat::Json => {
-                let obj: Value = from_str(&text)
+                let mut obj: Value = from_str(&payload)
                     .into_diagnostic()
                     .wrap_err("failed to serialize response into json")?;
 
+                if self.show_timing {
+                    obj = json!({ "response": obj, "timing": timing });
+                }
+
                 to_string_pretty(&obj)
                     .into_diagnostic()
                     .wrap_err("failed to format json output")?
@@ -173,78 +361,197 @@ impl Invoke {
 
         println!("{text}");
 
+        if self.show_timing && matches!(self.output_format, OutputFormat::Text) {
+            eprintln!("{timing}");
+        }
+
         Ok(())
     }
 
-    async fn invoke_remote(&self, data: &str) -> Result<String> {
+    /// Poll the emulator for function reloads, and send the payload
+    /// again after every successful reload, until the process is interrupted.
+    async fn watch_reloads(&self, data: &str) -> Result<()> {
+        let (protocol, client) = self.local_client().await?;
+        let host = parse_invoke_ip_address(&self.invoke_address)?;
+        let url = format!(
+            "{}://{}:{}/lambda/functions/{}/reloads",
+            protocol, &host, self.invoke_port, &self.function_name
+        );
+
+        eprintln!("waiting for the function to reload, press Ctrl-C to stop");
+
+        let mut last_reload = None;
+        loop {
+            match fetch_reloads(&client, &url).await {
+                Ok(reloads) => {
+                    if last_reload.is_some_and(|last| reloads > last) {
+                        if let Err(err) = self.invoke_and_print(data).await {
+                            eprintln!("{err:?}");
+                        }
+                    }
+                    last_reload = Some(reloads);
+                }
+                Err(err) => debug!(?err, "failed to fetch function reloads"),
+            }
+
+            tokio::time::sleep(WATCH_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn invoke_remote(&self, data: &str) -> Result<InvokeOutput> {
         if self.function_name == DEFAULT_PACKAGE_FUNCTION {
             return Err(InvokeError::InvalidFunctionName.into());
         }
 
         let client_context = self.client_context(true)?;
 
-        let sdk_config = self.remote_config.sdk_config(None).await;
+        let sdk_config = self.remote_config.sdk_config(None).await?;
         let client = LambdaClient::new(&sdk_config);
 
-        let resp = client
+        let mut req = client
             .invoke()
             .function_name(&self.function_name)
             .set_qualifier(self.remote_config.alias.clone())
             .payload(Blob::new(data.as_bytes()))
-            .set_client_context(client_context)
+            .set_client_context(client_context);
+
+        if self.show_timing || self.show_logs_on_error {
+            req = req.log_type(LogType::Tail);
+        }
+
+        let invoked_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default();
+
+        let resp = req
             .send()
             .await
             .into_diagnostic()
             .wrap_err("failed to invoke remote function")?;
 
+        let cold_start = resp.log_result.as_deref().map(is_remote_cold_start);
+
         if let Some(payload) = resp.payload {
             let blob = payload.into_inner();
-            let data = from_utf8(&blob)
-                .into_diagnostic()
-                .wrap_err("failed to read response payload")?;
+            let data = decode_payload(&blob).wrap_err("failed to read response payload")?;
 
             if resp.function_error.is_some() {
-                let err = RemoteInvokeError::try_from(data)?;
+                if self.show_logs_on_error {
+                    let logs = match resp.request_id() {
+                        Some(request_id) => fetch_invocation_logs(
+                            &sdk_config,
+                            &self.function_name,
+                            self.remote_config.alias.clone(),
+                            request_id,
+                            invoked_at,
+                        )
+                        .await
+                        .map_err(|err| debug!(?err, "failed to fetch the invocation logs"))
+                        .ok(),
+                        None => None,
+                    };
+                    match logs {
+                        Some(logs) if !logs.is_empty() => print_logs(&logs),
+                        _ => print_remote_logs(resp.log_result.as_deref()),
+                    }
+                }
+
+                let err = RemoteInvokeError::try_from(data.as_str())?;
                 Err(err.into())
             } else {
-                Ok(data.into())
+                Ok(InvokeOutput {
+                    payload: data,
+                    cold_start,
+                })
             }
         } else {
-            Ok("OK".into())
+            Ok(InvokeOutput {
+                payload: "OK".into(),
+                cold_start,
+            })
         }
     }
 
-    async fn invoke_local(&self, data: &str) -> Result<String> {
-        let host = parse_invoke_ip_address(&self.invoke_address)?;
-
-        let (protocol, client) = if self.tls_options.is_secure() {
+    async fn local_client(&self) -> Result<(&'static str, Client)> {
+        if self.tls_options.is_secure() {
             let tls = self.tls_options.client_config().await?;
             let client = Client::builder()
                 .use_preconfigured_tls(tls)
                 .build()
                 .into_diagnostic()?;
 
-            ("https", client)
+            Ok(("https", client))
         } else {
-            ("http", Client::new())
-        };
+            Ok(("http", Client::new()))
+        }
+    }
 
-        let url = format!(
+    fn local_url(&self, protocol: &str) -> Result<String> {
+        let host = parse_invoke_ip_address(&self.invoke_address)?;
+        Ok(format!(
             "{}://{}:{}/2015-03-31/functions/{}/invocations",
             protocol, &host, self.invoke_port, &self.function_name
-        );
+        ))
+    }
 
-        let mut req = client.post(url).body(data.to_string());
-        if let Some(identity) = &self.cognito {
-            if identity.is_valid() {
-                let ser = serde_json::to_string(&identity)
-                    .into_diagnostic()
-                    .wrap_err("failed to serialize Cognito's identity information")?;
-                req = req.header(LAMBDA_RUNTIME_COGNITO_IDENTITY, ser);
-            }
+    /// Headers that the local invoke request sends to the emulator
+    fn local_headers(&self) -> Result<Vec<(String, String)>> {
+        let mut headers = Vec::new();
+        if self.data_binary.is_some() {
+            headers.push((CONTENT_TYPE.to_string(), BINARY_CONTENT_TYPE.to_string()));
+        }
+        if let Some(identity) = self.cognito_identity()? {
+            headers.push((LAMBDA_RUNTIME_COGNITO_IDENTITY.to_string(), identity));
         }
         if let Some(client_context) = self.client_context(false)? {
-            req = req.header(LAMBDA_RUNTIME_CLIENT_CONTEXT, client_context);
+            headers.push((LAMBDA_RUNTIME_CLIENT_CONTEXT.to_string(), client_context));
+        }
+        for (name, value) in &self.runtime_headers {
+            let value = value
+                .to_str()
+                .into_diagnostic()
+                .wrap_err("invalid runtime header value")?;
+            headers.push((name.to_string(), value.to_string()));
+        }
+        Ok(headers)
+    }
+
+    fn curl_command(&self, data: &str) -> Result<String> {
+        let protocol = if self.tls_options.is_secure() {
+            "https"
+        } else {
+            "http"
+        };
+
+        let mut args = vec!["curl".to_string(), "-X POST".to_string()];
+        for (name, value) in self.local_headers()? {
+            args.push(format!("-H {}", shell_quote(&format!("{name}: {value}"))));
+        }
+
+        let tls_files = [
+            ("--cert", &self.tls_options.tls_cert),
+            ("--key", &self.tls_options.tls_key),
+            ("--cacert", &self.tls_options.tls_ca),
+        ];
+        for (flag, path) in tls_files {
+            let Some(path) = path else { continue };
+            args.push(format!("{flag} {}", shell_quote(&path.to_string_lossy())));
+        }
+
+        args.push(format!("--data-raw {}", shell_quote(data)));
+        args.push(shell_quote(&self.local_url(protocol)?));
+
+        Ok(args.join(" \\\n  "))
+    }
+
+    async fn invoke_local(&self, data: &str) -> Result<InvokeOutput> {
+        let (protocol, client) = self.local_client().await?;
+        let url = self.local_url(protocol)?;
+
+        let mut req = client.post(url).body(data.to_string());
+        for (name, value) in self.local_headers()? {
+            req = req.header(name, value);
         }
 
         let resp = req
@@ -253,15 +560,23 @@ impl Invoke {
             .into_diagnostic()
             .wrap_err("error sending request to the runtime emulator")?;
         let success = resp.status() == StatusCode::OK;
-
-        let payload = resp
-            .text()
+        let cold_start = resp
+            .headers()
+            .get(CARGO_LAMBDA_COLD_START_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .map(|h| h == "true");
+
+        let body = resp
+            .bytes()
             .await
             .into_diagnostic()
             .wrap_err("error reading response body")?;
+        let payload = decode_payload(&body).wrap_err("error reading response body")?;
 
         if success {
-            Ok(payload)
+            let mut output = InvokeOutput::new(payload);
+            output.cold_start = cold_start;
+            Ok(output)
         } else {
             debug!(error = ?payload, "error received from server");
             let err = RemoteInvokeError::try_from(payload.as_str())?;
@@ -269,6 +584,33 @@ impl Invoke {
         }
     }
 
+    fn cognito_identity(&self) -> Result<Option<String>> {
+        if let Some(file) = &self.cognito_identity_file {
+            let data = read_to_string(file)
+                .into_diagnostic()
+                .wrap_err("error reading Cognito identity file")?;
+
+            // Validate the identity and remove new lines, since it's sent as a header value.
+            let identity: Value = from_str(&data)
+                .into_diagnostic()
+                .wrap_err("invalid Cognito identity file, it must contain a JSON object")?;
+            let ser = serde_json::to_string(&identity)
+                .into_diagnostic()
+                .wrap_err("failed to serialize Cognito's identity information")?;
+            return Ok(Some(ser));
+        }
+
+        match &self.cognito {
+            Some(identity) if identity.is_valid() => {
+                let ser = serde_json::to_string(&identity)
+                    .into_diagnostic()
+                    .wrap_err("failed to serialize Cognito's identity information")?;
+                Ok(Some(ser))
+            }
+            _ => Ok(None),
+        }
+    }
+
     fn client_context(&self, encode: bool) -> Result<Option<String>> {
         let mut data = if let Some(file) = &self.client_context_file {
             read_to_string(file)
@@ -288,6 +630,92 @@ impl Invoke {
     }
 }
 
+/// Quote a value to use it as a single argument in a POSIX shell.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn parse_runtime_header(input: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, value) = input
+        .split_once('=')
+        .ok_or_else(|| format!("invalid header `{input}`, use the format KEY=VALUE"))?;
+
+    let name = HeaderName::from_str(name.trim())
+        .map_err(|e| format!("invalid header name `{name}`: {e}"))?;
+    let value = HeaderValue::from_str(value.trim())
+        .map_err(|e| format!("invalid header value `{value}`: {e}"))?;
+
+    Ok((name, value))
+}
+
+#[derive(Deserialize)]
+struct FunctionReloads {
+    reloads: u64,
+}
+
+async fn fetch_reloads(client: &Client, url: &str) -> Result<u64> {
+    let body = client
+        .get(url)
+        .send()
+        .await
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?
+        .bytes()
+        .await
+        .into_diagnostic()?;
+
+    let reloads: FunctionReloads = serde_json::from_slice(&body).into_diagnostic()?;
+    Ok(reloads.reloads)
+}
+
+/// Lambda payloads must be valid JSON, so binary data
+/// is sent as a base64 encoded JSON string.
+fn binary_payload(content: &[u8]) -> Result<String> {
+    serde_json::to_string(&b64::STANDARD.encode(content))
+        .into_diagnostic()
+        .wrap_err("failed to encode binary data")
+}
+
+/// Decode a response payload, decompressing it first if the function returned gzip data.
+fn decode_payload(payload: &[u8]) -> Result<String> {
+    if !payload.starts_with(&GZIP_MAGIC_BYTES) {
+        return from_utf8(payload).map(String::from).into_diagnostic();
+    }
+
+    let mut data = String::new();
+    GzDecoder::new(payload)
+        .read_to_string(&mut data)
+        .into_diagnostic()
+        .wrap_err("failed to decompress gzip payload")?;
+    Ok(data)
+}
+
+/// Apply a patch to an example payload. Patches with a list of operations are
+/// JSON Patches (RFC 6902), any other value is a JSON Merge Patch (RFC 7396).
+fn patch_example(
+    example: &str,
+    data: &str,
+    path: &Path,
+    patch: &str,
+) -> Result<String, InvokeError> {
+    let mut payload: Value = from_str(data)
+        .map_err(|err| InvokeError::InvalidExamplePayload(example.to_string(), err))?;
+    let patch: Value =
+        from_str(patch).map_err(|err| InvokeError::InvalidExamplePatch(path.to_path_buf(), err))?;
+
+    if patch.is_array() {
+        let operations: json_patch::Patch = serde_json::from_value(patch)
+            .map_err(|err| InvokeError::InvalidExamplePatch(path.to_path_buf(), err))?;
+        json_patch::patch(&mut payload, &operations)
+            .map_err(|err| InvokeError::ExamplePatchFailed(path.to_path_buf(), err))?;
+    } else {
+        json_patch::merge(&mut payload, &patch);
+    }
+
+    Ok(payload.to_string())
+}
+
 fn example_name(example: &str) -> String {
     let mut name = if example.starts_with("example-") {
         example.to_string()
@@ -300,16 +728,35 @@ fn example_name(example: &str) -> String {
     name
 }
 
+/// Directory name to cache examples from custom registries,
+/// so they don't collide with the public examples.
+fn cache_key(url: &str) -> String {
+    url.trim_end_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
 async fn download_example(
     name: &str,
     cache: Option<PathBuf>,
-    authority: Option<&str>,
+    registry: &ExamplesConfig,
 ) -> Result<String> {
-    let authority = authority.unwrap_or(EXAMPLES_URL);
+    let authority = registry
+        .url
+        .as_deref()
+        .unwrap_or(EXAMPLES_URL)
+        .trim_end_matches('/');
     let target = format!("{authority}/{name}");
 
     tracing::debug!(?target, "downloading remote example");
-    let response = reqwest::get(&target)
+    let mut request = network::http_client()?.get(&target);
+    if let Some(authorization) = &registry.authorization {
+        request = request.header(AUTHORIZATION, authorization);
+    }
+
+    let response = request
+        .send()
         .await
         .into_diagnostic()
         .wrap_err("error dowloading example data")?;
@@ -361,13 +808,14 @@ mod test {
                 .body_from_file("../../tests/fixtures/events/example-apigw-request.json");
         });
 
-        let data = download_example(
-            "example-apigw-request.json",
-            None,
-            Some(&format!("http://{}", server.address())),
-        )
-        .await
-        .expect("failed to download json");
+        let registry = ExamplesConfig {
+            url: Some(format!("http://{}", server.address())),
+            ..Default::default()
+        };
+
+        let data = download_example("example-apigw-request.json", None, &registry)
+            .await
+            .expect("failed to download json");
 
         mock.assert();
         assert!(data.contains("\"path\": \"/hello/world\""));
@@ -390,10 +838,15 @@ mod test {
             .join("cargo-lambda")
             .join("example-apigw-request.json");
 
+        let registry = ExamplesConfig {
+            url: Some(format!("http://{}", server.address())),
+            ..Default::default()
+        };
+
         let data = download_example(
             "example-apigw-request.json",
             Some(cache.to_path_buf()),
-            Some(&format!("http://{}", server.address())),
+            &registry,
         )
         .await
         .unwrap();
@@ -406,6 +859,84 @@ mod test {
         assert_eq!(content, data);
     }
 
+    #[tokio::test]
+    async fn test_download_example_with_authorization() {
+        let server = MockServer::start_async().await;
+
+        let mock = server.mock(|when, then| {
+            when.path("/example-apigw-request.json")
+                .header("Authorization", "Bearer secret");
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body_from_file("../../tests/fixtures/events/example-apigw-request.json");
+        });
+
+        let registry = ExamplesConfig {
+            url: Some(format!("http://{}/", server.address())),
+            authorization: Some("Bearer secret".into()),
+        };
+
+        let data = download_example("example-apigw-request.json", None, &registry)
+            .await
+            .expect("failed to download json");
+
+        mock.assert();
+        assert!(data.contains("\"path\": \"/hello/world\""));
+    }
+
+    #[test]
+    fn test_cache_key() {
+        assert_eq!(
+            "https___examples_example_com",
+            cache_key("https://examples.example.com/")
+        );
+    }
+
+    #[test]
+    fn test_binary_payload() {
+        let payload = binary_payload(&[0xde, 0xad, 0xbe, 0xef]).unwrap();
+        assert_eq!("\"3q2+7w==\"", payload);
+    }
+
+    #[test]
+    fn test_decode_payload() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        assert_eq!("{\"ok\":true}", decode_payload(b"{\"ok\":true}").unwrap());
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"{\"ok\":true}").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert_eq!("{\"ok\":true}", decode_payload(&compressed).unwrap());
+    }
+
+    #[test]
+    fn test_parse_runtime_header() {
+        let (name, value) = parse_runtime_header("Lambda-Runtime-Foo = bar").unwrap();
+        assert_eq!("lambda-runtime-foo", name.as_str());
+        assert_eq!("bar", value);
+
+        assert!(parse_runtime_header("lambda-runtime-foo").is_err());
+        assert!(parse_runtime_header("invalid name=bar").is_err());
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!("'{\"foo\": \"bar\"}'", shell_quote("{\"foo\": \"bar\"}"));
+        assert_eq!("'it'\\''s'", shell_quote("it's"));
+    }
+
+    #[test]
+    fn test_timing_display() {
+        let timing = Timing::new(Duration::from_millis(120), Some(true));
+        assert_eq!("duration: 120ms, cold start: yes", timing.to_string());
+
+        let timing = Timing::new(Duration::from_millis(5), None);
+        assert_eq!("duration: 5ms", timing.to_string());
+    }
+
     #[test]
     fn test_example_name() {
         assert_eq!(example_name("apigw-request"), "example-apigw-request.json");
@@ -422,4 +953,49 @@ mod test {
             "example-apigw-request.json"
         );
     }
+
+    #[test]
+    fn test_patch_example() {
+        let data = r#"{"path":"/hello","body":null,"headers":{"accept":"*/*"}}"#;
+        let path = Path::new("patch.json");
+
+        let patched = patch_example(
+            "apigw-request",
+            data,
+            path,
+            r#"{"path":"/orders","headers":{"accept":null}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            json!({"path": "/orders", "body": null, "headers": {}}),
+            from_str::<Value>(&patched).unwrap()
+        );
+
+        let patched = patch_example(
+            "apigw-request",
+            data,
+            path,
+            r#"[{"op":"replace","path":"/body","value":"{\"id\":1}"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            json!({"path": "/hello", "body": "{\"id\":1}", "headers": {"accept": "*/*"}}),
+            from_str::<Value>(&patched).unwrap()
+        );
+
+        let err = patch_example(
+            "apigw-request",
+            data,
+            path,
+            r#"[{"op":"remove","path":"/missing"}]"#,
+        )
+        .unwrap_err();
+        assert!(matches!(err, InvokeError::ExamplePatchFailed(..)));
+
+        let err = patch_example("apigw-request", data, path, "{").unwrap_err();
+        assert!(matches!(err, InvokeError::InvalidExamplePatch(..)));
+
+        let err = patch_example("apigw-request", "not json", path, "{}").unwrap_err();
+        assert!(matches!(err, InvokeError::InvalidExamplePayload(..)));
+    }
 }
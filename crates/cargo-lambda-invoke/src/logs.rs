@@ -0,0 +1,367 @@
+use aws_sdk_cloudwatchlogs::{types::FilteredLogEvent, Client as LogsClient};
+use base64::{engine::general_purpose as b64, Engine as _};
+use cargo_lambda_remote::{aws_sdk_config::SdkConfig, aws_sdk_lambda::Client as LambdaClient};
+use miette::{IntoDiagnostic, Result};
+use std::time::Duration;
+
+/// Number of times to look for the logs of an invocation, CloudWatch Logs
+/// takes a few seconds to receive them after the invocation finishes.
+const FETCH_ATTEMPTS: u32 = 5;
+
+const FETCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Margin for the difference between the local clock and the clock in AWS, in milliseconds.
+const CLOCK_SKEW_MS: i64 = 60_000;
+
+/// Fetch the logs of an invocation from CloudWatch Logs.
+/// Lambda writes the request id in the `START`, `END`, and `REPORT` lines of each invocation,
+/// so those lines give the log stream, and the time range, of every line that the function wrote.
+/// It returns an empty list if the logs are not in CloudWatch Logs yet.
+pub(crate) async fn fetch_invocation_logs(
+    sdk_config: &SdkConfig,
+    function_name: &str,
+    qualifier: Option<String>,
+    request_id: &str,
+    invoked_at: i64,
+) -> Result<Vec<String>> {
+    let log_group = log_group(sdk_config, function_name, qualifier).await;
+    let client = LogsClient::new(sdk_config);
+    let start_time = invoked_at - CLOCK_SKEW_MS;
+
+    let mut markers = Vec::new();
+    for attempt in 1..=FETCH_ATTEMPTS {
+        markers = filter_events(
+            &client,
+            &log_group,
+            None,
+            start_time,
+            None,
+            Some(format!("\"{request_id}\"")),
+        )
+        .await?;
+
+        if markers.iter().any(is_report) || attempt == FETCH_ATTEMPTS {
+            break;
+        }
+        tokio::time::sleep(FETCH_INTERVAL).await;
+    }
+
+    let Some((log_stream, first, last)) = invocation_range(&markers) else {
+        return Ok(Vec::new());
+    };
+
+    let events = filter_events(
+        &client,
+        &log_group,
+        Some(log_stream),
+        first,
+        Some(last + 1),
+        None,
+    )
+    .await?;
+
+    Ok(events
+        .into_iter()
+        .filter_map(|e| e.message)
+        .map(|m| m.trim_end().to_string())
+        .collect())
+}
+
+/// Log stream, and first and last timestamps, of the lines with the invocation's request id.
+fn invocation_range(markers: &[FilteredLogEvent]) -> Option<(String, i64, i64)> {
+    let log_stream = markers.iter().find_map(|e| e.log_stream_name.clone())?;
+    let timestamps = markers.iter().filter_map(|e| e.timestamp);
+    let first = timestamps.clone().min()?;
+    let last = timestamps.max()?;
+    Some((log_stream, first, last))
+}
+
+/// Log group in the function's logging configuration, or `/aws/lambda/NAME` by default.
+async fn log_group(
+    sdk_config: &SdkConfig,
+    function_name: &str,
+    qualifier: Option<String>,
+) -> String {
+    let client = LambdaClient::new(sdk_config);
+    let configured = client
+        .get_function_configuration()
+        .function_name(function_name)
+        .set_qualifier(qualifier)
+        .send()
+        .await
+        .ok()
+        .and_then(|conf| conf.logging_config)
+        .and_then(|logging| logging.log_group);
+
+    configured.unwrap_or_else(|| format!("/aws/lambda/{}", short_function_name(function_name)))
+}
+
+/// Name of a function from its name, or its ARN, like
+/// `arn:aws:lambda:us-east-1:123456789012:function:my-function:prod`.
+fn short_function_name(function_name: &str) -> &str {
+    match function_name.strip_prefix("arn:") {
+        Some(arn) => arn.split(':').nth(5).unwrap_or(function_name),
+        None => function_name,
+    }
+}
+
+fn is_report(event: &FilteredLogEvent) -> bool {
+    event
+        .message
+        .as_deref()
+        .is_some_and(|m| m.starts_with("REPORT") || m.contains("\"platform.report\""))
+}
+
+/// Lambda returns the last 4KB of the invocation logs encoded in base64.
+/// The tail can start in the middle of a multi-byte character, so invalid UTF-8 is replaced.
+pub(crate) fn decode_log_result(log_result: &str) -> Option<String> {
+    b64::STANDARD
+        .decode(log_result)
+        .ok()
+        .map(|logs| String::from_utf8_lossy(&logs).into_owned())
+}
+
+/// Lambda includes the init duration in the invocation report
+/// only when the function had to be initialized for that request.
+pub(crate) fn is_remote_cold_start(log_result: &str) -> bool {
+    decode_log_result(log_result).is_some_and(|logs| logs.contains("Init Duration"))
+}
+
+pub(crate) fn print_logs(logs: &[String]) {
+    eprintln!("Function logs:");
+    for line in logs {
+        eprintln!("{line}");
+    }
+}
+
+/// Print the last 4KB of logs that Lambda returns with the invocation,
+/// when the logs are not available in CloudWatch Logs.
+pub(crate) fn print_remote_logs(log_result: Option<&str>) {
+    match log_result.and_then(decode_log_result) {
+        Some(logs) => {
+            eprintln!("Function logs:");
+            eprintln!("{}", logs.trim_end());
+        }
+        None => eprintln!("the function logs are not available for this invocation"),
+    }
+}
+
+async fn filter_events(
+    client: &LogsClient,
+    log_group: &str,
+    log_stream: Option<String>,
+    start_time: i64,
+    end_time: Option<i64>,
+    filter_pattern: Option<String>,
+) -> Result<Vec<FilteredLogEvent>> {
+    let mut pages = client
+        .filter_log_events()
+        .log_group_name(log_group)
+        .set_log_stream_names(log_stream.map(|stream| vec![stream]))
+        .start_time(start_time)
+        .set_end_time(end_time)
+        .set_filter_pattern(filter_pattern)
+        .into_paginator()
+        .send();
+
+    let mut events = Vec::new();
+    while let Some(page) = pages.next().await {
+        let page = page.into_diagnostic()?;
+        events.extend(page.events.unwrap_or_default());
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cargo_lambda_remote::RemoteConfig;
+    use httpmock::{Method::POST, MockServer};
+    use serde_json::json;
+
+    /// `LogResult` of an invocation that panicked during a cold start.
+    const LOG_RESULT: &str = "U1RBUlQgUmVxdWVzdElkOiA4ZjVhN2YxZS0xMjM0LTRjMWEtOWQyYi02ZjFlMmEzYjRjNWQgVmVyc2lvbjogJExBVEVTVAp0aHJlYWQgJ21haW4nIHBhbmlja2VkIGF0IHNyYy9tYWluLnJzOjEwOjU6Cm1pc3NpbmcgZmllbGQgYG5hbWVgCkVORCBSZXF1ZXN0SWQ6IDhmNWE3ZjFlLTEyMzQtNGMxYS05ZDJiLTZmMWUyYTNiNGM1ZApSRVBPUlQgUmVxdWVzdElkOiA4ZjVhN2YxZS0xMjM0LTRjMWEtOWQyYi02ZjFlMmEzYjRjNWQJRHVyYXRpb246IDEuNTAgbXMJQmlsbGVkIER1cmF0aW9uOiAyIG1zCU1lbW9yeSBTaXplOiAxMjggTUIJTWF4IE1lbW9yeSBVc2VkOiAxOCBNQglJbml0IER1cmF0aW9uOiAyMC4xMiBtcwkK";
+
+    const REQUEST_ID: &str = "8f5a7f1e-1234-4c1a-9d2b-6f1e2a3b4c5d";
+
+    const LOG_STREAM: &str = "2024/01/01/[$LATEST]0123456789abcdef";
+
+    fn event(timestamp: i64, message: &str) -> FilteredLogEvent {
+        FilteredLogEvent::builder()
+            .log_stream_name(LOG_STREAM)
+            .timestamp(timestamp)
+            .message(message)
+            .build()
+    }
+
+    #[test]
+    fn test_decode_log_result() {
+        let logs = decode_log_result(LOG_RESULT).unwrap();
+        let lines = logs.lines().collect::<Vec<_>>();
+        assert_eq!(5, lines.len());
+        assert_eq!(
+            format!("START RequestId: {REQUEST_ID} Version: $LATEST"),
+            lines[0]
+        );
+        assert_eq!("thread 'main' panicked at src/main.rs:10:5:", lines[1]);
+        assert!(lines[4].starts_with(&format!("REPORT RequestId: {REQUEST_ID}")));
+
+        assert_eq!(None, decode_log_result("not base64"));
+    }
+
+    #[test]
+    fn test_decode_truncated_log_result() {
+        // The tail starts with the last byte of `é`, which is not valid UTF-8.
+        let tail = [&"é".as_bytes()[1..], b"\nEND RequestId: 1234\n"].concat();
+        let logs = decode_log_result(&b64::STANDARD.encode(tail)).unwrap();
+        assert_eq!("\u{FFFD}\nEND RequestId: 1234\n", logs);
+    }
+
+    #[test]
+    fn test_is_remote_cold_start() {
+        assert!(is_remote_cold_start(LOG_RESULT));
+
+        let report = "REPORT RequestId: 1234\tDuration: 1.50 ms";
+        assert!(!is_remote_cold_start(&b64::STANDARD.encode(report)));
+
+        assert!(!is_remote_cold_start("not base64"));
+    }
+
+    #[test]
+    fn test_invocation_range() {
+        let markers = vec![
+            event(
+                1_500,
+                &format!("REPORT RequestId: {REQUEST_ID}\tDuration: 1.50 ms"),
+            ),
+            event(
+                1_000,
+                &format!("START RequestId: {REQUEST_ID} Version: $LATEST"),
+            ),
+            event(1_490, &format!("END RequestId: {REQUEST_ID}")),
+        ];
+        assert_eq!(
+            Some((LOG_STREAM.to_string(), 1_000, 1_500)),
+            invocation_range(&markers)
+        );
+
+        assert_eq!(None, invocation_range(&[]));
+        let without_stream = FilteredLogEvent::builder().timestamp(1_000).build();
+        assert_eq!(None, invocation_range(&[without_stream]));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_invocation_logs() {
+        let server = MockServer::start_async().await;
+
+        let markers = server.mock(|when, then| {
+            when.method(POST)
+                .header("x-amz-target", "Logs_20140328.FilterLogEvents")
+                .json_body_partial(
+                    json!({
+                        "logGroupName": "/aws/lambda/my-function",
+                        "filterPattern": format!("\"{REQUEST_ID}\""),
+                    })
+                    .to_string(),
+                );
+            then.status(200)
+                .header("content-type", "application/x-amz-json-1.1")
+                .json_body(json!({
+                    "events": [
+                        {
+                            "logStreamName": LOG_STREAM,
+                            "timestamp": 1_000,
+                            "message": format!("START RequestId: {REQUEST_ID} Version: $LATEST\n"),
+                        },
+                        {
+                            "logStreamName": LOG_STREAM,
+                            "timestamp": 1_500,
+                            "message": format!("REPORT RequestId: {REQUEST_ID}\tDuration: 1.50 ms\n"),
+                        },
+                    ]
+                }));
+        });
+
+        let lines = server.mock(|when, then| {
+            when.method(POST)
+                .header("x-amz-target", "Logs_20140328.FilterLogEvents")
+                .json_body_partial(
+                    json!({
+                        "logGroupName": "/aws/lambda/my-function",
+                        "logStreamNames": [LOG_STREAM],
+                        "startTime": 1_000,
+                        "endTime": 1_501,
+                    })
+                    .to_string(),
+                );
+            then.status(200)
+                .header("content-type", "application/x-amz-json-1.1")
+                .json_body(json!({
+                    "events": [
+                        {
+                            "logStreamName": LOG_STREAM,
+                            "timestamp": 1_000,
+                            "message": format!("START RequestId: {REQUEST_ID} Version: $LATEST\n"),
+                        },
+                        {
+                            "logStreamName": LOG_STREAM,
+                            "timestamp": 1_200,
+                            "message": "thread 'main' panicked at src/main.rs:10:5:\n",
+                        },
+                        {
+                            "logStreamName": LOG_STREAM,
+                            "timestamp": 1_500,
+                            "message": format!("REPORT RequestId: {REQUEST_ID}\tDuration: 1.50 ms\n"),
+                        },
+                    ]
+                }));
+        });
+
+        let remote_config = RemoteConfig {
+            region: Some("us-east-1".into()),
+            localstack: Some(server.base_url()),
+            ..Default::default()
+        };
+        let sdk_config = remote_config.sdk_config(None).await.unwrap();
+
+        let logs = fetch_invocation_logs(&sdk_config, "my-function", None, REQUEST_ID, 61_000)
+            .await
+            .unwrap();
+
+        markers.assert();
+        lines.assert();
+        assert_eq!(
+            vec![
+                format!("START RequestId: {REQUEST_ID} Version: $LATEST"),
+                "thread 'main' panicked at src/main.rs:10:5:".to_string(),
+                format!("REPORT RequestId: {REQUEST_ID}\tDuration: 1.50 ms"),
+            ],
+            logs
+        );
+    }
+
+    #[test]
+    fn test_short_function_name() {
+        assert_eq!("my-function", short_function_name("my-function"));
+        assert_eq!(
+            "my-function",
+            short_function_name("arn:aws:lambda:us-east-1:123456789012:function:my-function")
+        );
+        assert_eq!(
+            "my-function",
+            short_function_name("arn:aws:lambda:us-east-1:123456789012:function:my-function:prod")
+        );
+    }
+
+    #[test]
+    fn test_is_report() {
+        let event = |message: &str| FilteredLogEvent::builder().message(message).build();
+        assert!(is_report(&event(
+            "REPORT RequestId: 8f5a7f1e Duration: 1.23 ms"
+        )));
+        assert!(is_report(&event(
+            r#"{"type":"platform.report","record":{"requestId":"8f5a7f1e"}}"#
+        )));
+        assert!(!is_report(&event("START RequestId: 8f5a7f1e")));
+    }
+}
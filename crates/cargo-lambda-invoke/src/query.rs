@@ -0,0 +1,154 @@
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Query to extract a value from the function response, with a JSONPath-like syntax:
+/// `$.body.items[0].id`, `$["my key"].value`, or `body.items[0]`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Query {
+    raw: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+impl Query {
+    /// Extract the value that the query points to.
+    /// JSON documents encoded as strings, like API Gateway response bodies,
+    /// are decoded on the fly when the query needs to go inside them.
+    pub(crate) fn extract(&self, value: &Value) -> Option<Value> {
+        let mut current = value.clone();
+
+        for segment in &self.segments {
+            if let Value::String(s) = &current {
+                current = serde_json::from_str(s).ok()?;
+            }
+
+            current = match (segment, current) {
+                (Segment::Key(key), Value::Object(mut map)) => map.remove(key)?,
+                (Segment::Index(index), Value::Array(mut list)) if *index < list.len() => {
+                    list.swap_remove(*index)
+                }
+                _ => return None,
+            };
+        }
+
+        Some(current)
+    }
+}
+
+impl std::fmt::Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let raw = input.trim();
+        let mut rest = raw.strip_prefix('$').unwrap_or(raw);
+        let mut segments = Vec::new();
+
+        while !rest.is_empty() {
+            if let Some(bracket) = rest.strip_prefix('[') {
+                let end = bracket
+                    .find(']')
+                    .ok_or_else(|| format!("unclosed bracket in query `{raw}`"))?;
+                let inner = bracket[..end].trim();
+
+                let segment = if let Some(key) = unquote(inner) {
+                    Segment::Key(key.to_string())
+                } else {
+                    let index = inner
+                        .parse()
+                        .map_err(|_| format!("invalid index `{inner}` in query `{raw}`"))?;
+                    Segment::Index(index)
+                };
+
+                segments.push(segment);
+                rest = &bracket[end + 1..];
+                continue;
+            }
+
+            let field = rest.strip_prefix('.').unwrap_or(rest);
+            let end = field.find(['.', '[']).unwrap_or(field.len());
+            if end == 0 {
+                return Err(format!("empty field name in query `{raw}`"));
+            }
+
+            segments.push(Segment::Key(field[..end].to_string()));
+            rest = &field[end..];
+        }
+
+        Ok(Query {
+            raw: raw.to_string(),
+            segments,
+        })
+    }
+}
+
+fn unquote(input: &str) -> Option<&str> {
+    input
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .or_else(|| input.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_query() {
+        let query = Query::from_str("$.body.items[0].id").unwrap();
+        assert_eq!(
+            query.segments,
+            vec![
+                Segment::Key("body".into()),
+                Segment::Key("items".into()),
+                Segment::Index(0),
+                Segment::Key("id".into()),
+            ]
+        );
+
+        let query = Query::from_str("headers[\"content-type\"]").unwrap();
+        assert_eq!(
+            query.segments,
+            vec![
+                Segment::Key("headers".into()),
+                Segment::Key("content-type".into()),
+            ]
+        );
+
+        assert!(Query::from_str("$").unwrap().segments.is_empty());
+        assert!(Query::from_str("$.items[0").is_err());
+        assert!(Query::from_str("$.items[foo]").is_err());
+        assert!(Query::from_str("$..items").is_err());
+    }
+
+    #[test]
+    fn test_extract() {
+        let response = json!({
+            "statusCode": 200,
+            "body": "{\"items\":[{\"id\":\"abc\"},{\"id\":\"def\"}]}",
+        });
+
+        let query = Query::from_str("$.statusCode").unwrap();
+        assert_eq!(Some(json!(200)), query.extract(&response));
+
+        let query = Query::from_str("$.body.items[1].id").unwrap();
+        assert_eq!(Some(json!("def")), query.extract(&response));
+
+        let query = Query::from_str("$.body.items[2]").unwrap();
+        assert_eq!(None, query.extract(&response));
+
+        let query = Query::from_str("$.headers").unwrap();
+        assert_eq!(None, query.extract(&response));
+    }
+}
@@ -0,0 +1,14 @@
+use miette::Diagnostic;
+use thiserror::Error;
+
+#[derive(Debug, Diagnostic, Error)]
+pub enum LogsError {
+    #[error("invalid duration `{0}`")]
+    #[diagnostic(help("use a number followed by a unit: s, m, h, or d, like 30s, 15m, or 2h"))]
+    InvalidDuration(String),
+    #[error("the log group `{0}` doesn't exist")]
+    #[diagnostic(help(
+        "AWS Lambda creates the log group the first time the function runs, invoke the function and try again"
+    ))]
+    MissingLogGroup(String),
+}
@@ -0,0 +1,141 @@
+use chrono::{DateTime, SecondsFormat};
+use serde_json::{json, Map, Value};
+
+/// Log event read from CloudWatch Logs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct LogEvent {
+    pub(crate) id: String,
+    pub(crate) timestamp: i64,
+    pub(crate) log_stream: String,
+    pub(crate) message: String,
+}
+
+impl LogEvent {
+    /// Format the event as a line of text. When the function uses the JSON log format,
+    /// the line shows the level and the message of the event, instead of the raw JSON.
+    pub(crate) fn to_text(&self, json_format: bool) -> String {
+        let timestamp = format_timestamp(self.timestamp);
+        let message = self.message.trim_end();
+
+        let parsed = json_format
+            .then(|| serde_json::from_str::<Map<String, Value>>(message).ok())
+            .flatten();
+        let Some(fields) = parsed else {
+            return format!("{timestamp} {message}");
+        };
+
+        // Platform events, like `platform.start`, or `platform.report`.
+        if let (Some(kind), Some(record)) = (
+            fields.get("type").and_then(Value::as_str),
+            fields.get("record"),
+        ) {
+            return format!("{timestamp} {} {record}", kind.to_uppercase());
+        }
+
+        let level = fields
+            .get("level")
+            .and_then(Value::as_str)
+            .unwrap_or("INFO");
+        let message = fields
+            .get("message")
+            .or_else(|| fields.get("fields").and_then(|f| f.get("message")))
+            .map(|m| match m {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .unwrap_or_else(|| message.to_string());
+
+        match fields.get("requestId").and_then(Value::as_str) {
+            Some(request_id) => format!("{timestamp} {level} {message} request_id={request_id}"),
+            None => format!("{timestamp} {level} {message}"),
+        }
+    }
+
+    /// Format the event as a JSON object in a single line. Messages
+    /// in the JSON log format are included as objects, not strings.
+    pub(crate) fn to_json(&self) -> String {
+        let message = self.message.trim_end();
+        let message = serde_json::from_str::<Value>(message)
+            .ok()
+            .filter(Value::is_object)
+            .unwrap_or_else(|| Value::String(message.to_string()));
+
+        json!({
+            "timestamp": format_timestamp(self.timestamp),
+            "log_stream": self.log_stream,
+            "message": message,
+        })
+        .to_string()
+    }
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    DateTime::from_timestamp_millis(timestamp)
+        .map(|t| t.to_rfc3339_opts(SecondsFormat::Millis, true))
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_event(message: &str) -> LogEvent {
+        LogEvent {
+            id: "1".into(),
+            timestamp: 1_700_000_000_123,
+            log_stream: "2023/11/14/[$LATEST]abc".into(),
+            message: message.into(),
+        }
+    }
+
+    #[test]
+    fn test_text_format() {
+        let event = log_event("INFO processing order 42\n");
+        assert_eq!(
+            "2023-11-14T22:13:20.123Z INFO processing order 42",
+            event.to_text(false)
+        );
+    }
+
+    #[test]
+    fn test_json_format() {
+        let event = log_event(
+            r#"{"timestamp":"2023-11-14T22:13:20.123Z","level":"ERROR","message":"order not found","requestId":"79b4f56e"}"#,
+        );
+        assert_eq!(
+            "2023-11-14T22:13:20.123Z ERROR order not found request_id=79b4f56e",
+            event.to_text(true)
+        );
+
+        let event = log_event(
+            r#"{"timestamp":"2023-11-14T22:13:20.123Z","level":"WARN","fields":{"message":"slow query"},"target":"orders"}"#,
+        );
+        assert_eq!(
+            "2023-11-14T22:13:20.123Z WARN slow query",
+            event.to_text(true)
+        );
+
+        let event = log_event(
+            r#"{"time":"2023-11-14T22:13:20.123Z","type":"platform.start","record":{"requestId":"79b4f56e"}}"#,
+        );
+        assert_eq!(
+            r#"2023-11-14T22:13:20.123Z PLATFORM.START {"requestId":"79b4f56e"}"#,
+            event.to_text(true)
+        );
+
+        let event = log_event("not json\n");
+        assert_eq!("2023-11-14T22:13:20.123Z not json", event.to_text(true));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let value: Value =
+            serde_json::from_str(&log_event(r#"{"level":"INFO"}"#).to_json()).unwrap();
+        assert_eq!("INFO", value["message"]["level"]);
+        assert_eq!("2023-11-14T22:13:20.123Z", value["timestamp"]);
+
+        let value: Value =
+            serde_json::from_str(&log_event("START RequestId: 1\n").to_json()).unwrap();
+        assert_eq!("START RequestId: 1", value["message"]);
+    }
+}
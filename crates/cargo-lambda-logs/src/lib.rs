@@ -0,0 +1,408 @@
+use aws_sdk_cloudwatchlogs::Client as LogsClient;
+use cargo_lambda_interactive::progress::Progress;
+use cargo_lambda_metadata::cargo::{deploy::Deploy, main_binary_from_metadata, CargoMetadata};
+use cargo_lambda_remote::{
+    aws_sdk_config::SdkConfig,
+    aws_sdk_lambda::{types::LogFormat, Client as LambdaClient},
+    RemoteConfig,
+};
+use clap::{Args, ValueHint};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use strum_macros::{Display, EnumString};
+
+mod error;
+use error::LogsError;
+
+mod format;
+use format::LogEvent;
+
+const DEFAULT_MANIFEST_PATH: &str = "Cargo.toml";
+
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "logs",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/logs.html"
+)]
+pub struct Logs {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Keep the command running, and print new log events as they arrive
+    #[arg(short, long)]
+    follow: bool,
+
+    /// Print the log events since this long ago, like 30s, 15m, 2h, or 1d
+    #[arg(long, default_value = "10m")]
+    since: Since,
+
+    /// CloudWatch Logs filter pattern to select the log events, like `ERROR`, or `{ $.level = "ERROR" }`
+    #[arg(long)]
+    filter: Option<String>,
+
+    /// Log group to read the events from, defaults to the log group configured in the function
+    #[arg(long)]
+    log_group: Option<String>,
+
+    /// Format to render the output (text, or json)
+    #[arg(short, long, default_value_t = OutputFormat::Text)]
+    output_format: OutputFormat,
+
+    /// Path to Cargo.toml, to find the function's name in the deploy configuration
+    #[arg(long, value_name = "PATH", default_value = DEFAULT_MANIFEST_PATH, value_hint = ValueHint::FilePath)]
+    manifest_path: PathBuf,
+
+    /// Name of the function to read the logs from, defaults to the name in the deploy configuration, or the package's binary
+    #[arg(value_name = "NAME")]
+    name: Option<String>,
+
+    /// Deploy configuration of the project, loaded from Cargo.toml and the global configuration
+    #[arg(skip)]
+    pub deploy: Option<Deploy>,
+}
+
+#[derive(Clone, Debug, Display, EnumString)]
+#[strum(ascii_case_insensitive)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// How long ago to start reading the log events from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Since(Duration);
+
+impl FromStr for Since {
+    type Err = LogsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || LogsError::InvalidDuration(s.to_string());
+
+        let s = s.trim();
+        let unit_index = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let (value, unit) = s.split_at(unit_index);
+        let value = value.parse::<u64>().map_err(|_| invalid())?;
+
+        let seconds = match unit {
+            "s" => value,
+            "m" => value * 60,
+            "h" => value * 60 * 60,
+            "d" => value * 60 * 60 * 24,
+            _ => return Err(invalid()),
+        };
+
+        Ok(Since(Duration::from_secs(seconds)))
+    }
+}
+
+/// Log group where the function sends its logs, and whether it uses the JSON log format.
+#[derive(Debug, PartialEq)]
+struct LogGroup {
+    name: String,
+    json_format: bool,
+}
+
+/// Position of the last events printed, so following the logs doesn't print the same event twice.
+/// CloudWatch Logs returns the events with the same start time again in the next request.
+#[derive(Debug, Default)]
+struct Cursor {
+    start_time: i64,
+    seen: HashSet<String>,
+}
+
+impl Logs {
+    pub fn name(&self) -> Option<String> {
+        self.name.clone()
+    }
+
+    pub fn manifest_path(&self) -> &Path {
+        &self.manifest_path
+    }
+
+    #[tracing::instrument(skip(self, metadata), target = "cargo_lambda")]
+    pub async fn run(&self, metadata: Option<&CargoMetadata>) -> Result<()> {
+        let name = self.function_name(metadata)?;
+        let remote_config = self.remote_config();
+        let sdk_config = remote_config.sdk_config(None).await?;
+
+        let progress = Progress::start("loading function configuration");
+        let log_group = self
+            .resolve_log_group(&sdk_config, &name, remote_config.alias.clone())
+            .await;
+        progress.finish_and_clear();
+
+        tracing::debug!(?log_group, "reading logs");
+
+        let client = LogsClient::new(&sdk_config);
+        let mut cursor = Cursor::new(now_millis() - self.since.0.as_millis() as i64);
+
+        loop {
+            let events = self.fetch_events(&client, &log_group.name, &cursor).await?;
+            for event in cursor.accept(events) {
+                match self.output_format {
+                    OutputFormat::Text => println!("{}", event.to_text(log_group.json_format)),
+                    OutputFormat::Json => println!("{}", event.to_json()),
+                }
+            }
+
+            if !self.follow {
+                return Ok(());
+            }
+
+            tokio::time::sleep(FOLLOW_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Name of the function from the command line, the deploy configuration, or the package's only binary.
+    fn function_name(&self, metadata: Option<&CargoMetadata>) -> Result<String> {
+        if let Some(name) = &self.name {
+            return Ok(name.clone());
+        }
+
+        if let Some(deploy) = &self.deploy {
+            if let Some(name) = deploy.name.as_ref().or(deploy.binary_name.as_ref()) {
+                return Ok(name.clone());
+            }
+        }
+
+        let metadata = metadata.ok_or_else(|| {
+            miette::miette!(
+                "missing function name, pass the name of the function to read the logs from"
+            )
+        })?;
+        Ok(main_binary_from_metadata(metadata)?)
+    }
+
+    /// AWS options from the command line, with the deploy configuration's options as defaults.
+    fn remote_config(&self) -> RemoteConfig {
+        let mut config = self.remote_config.clone();
+        if let Some(deploy) = &self.deploy {
+            let defaults = &deploy.remote_config;
+            config.profile = config.profile.or_else(|| defaults.profile.clone());
+            config.region = config.region.or_else(|| defaults.region.clone());
+            config.alias = config.alias.or_else(|| defaults.alias.clone());
+            config.retry_mode = config.retry_mode.or(defaults.retry_mode);
+            config.connect_timeout = config.connect_timeout.or(defaults.connect_timeout);
+            config.read_timeout = config.read_timeout.or(defaults.read_timeout);
+            config.endpoint_url = config
+                .endpoint_url
+                .or_else(|| defaults.endpoint_url.clone());
+            config.localstack = config.localstack.or_else(|| defaults.localstack.clone());
+            config.endpoints = config.endpoints.or_else(|| defaults.endpoints.clone());
+            config.partition = config.partition.or(defaults.partition);
+            config.inherit_role(defaults);
+        }
+        config
+    }
+
+    /// Read the log group from the function's logging configuration. Functions
+    /// that don't have a custom log group send their logs to `/aws/lambda/NAME`.
+    async fn resolve_log_group(
+        &self,
+        sdk_config: &SdkConfig,
+        name: &str,
+        alias: Option<String>,
+    ) -> LogGroup {
+        let default_name = format!("/aws/lambda/{name}");
+
+        let client = LambdaClient::new(sdk_config);
+        let logging_config = match client
+            .get_function_configuration()
+            .function_name(name)
+            .set_qualifier(alias)
+            .send()
+            .await
+        {
+            Ok(output) => output.logging_config,
+            Err(err) => {
+                tracing::warn!(
+                    ?err,
+                    "failed to load the function configuration, using the default log group"
+                );
+                None
+            }
+        };
+
+        let json_format = logging_config
+            .as_ref()
+            .and_then(|c| c.log_format.as_ref())
+            .is_some_and(|f| f == &LogFormat::Json);
+
+        let name = self
+            .log_group
+            .clone()
+            .or_else(|| logging_config.and_then(|c| c.log_group))
+            .unwrap_or(default_name);
+
+        LogGroup { name, json_format }
+    }
+
+    async fn fetch_events(
+        &self,
+        client: &LogsClient,
+        log_group: &str,
+        cursor: &Cursor,
+    ) -> Result<Vec<LogEvent>> {
+        let mut pages = client
+            .filter_log_events()
+            .log_group_name(log_group)
+            .start_time(cursor.start_time)
+            .set_filter_pattern(self.filter.clone())
+            .into_paginator()
+            .send();
+
+        let mut events = Vec::new();
+        while let Some(page) = pages.next().await {
+            let page = match page {
+                Ok(page) => page,
+                Err(err)
+                    if err
+                        .as_service_error()
+                        .is_some_and(|e| e.is_resource_not_found_exception()) =>
+                {
+                    return Err(LogsError::MissingLogGroup(log_group.to_string()).into());
+                }
+                Err(err) => {
+                    return Err(err)
+                        .into_diagnostic()
+                        .wrap_err_with(|| format!("failed to read the logs from {log_group}"))
+                }
+            };
+
+            events.extend(
+                page.events
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|e| LogEvent {
+                        id: e.event_id.unwrap_or_default(),
+                        timestamp: e.timestamp.unwrap_or_default(),
+                        log_stream: e.log_stream_name.unwrap_or_default(),
+                        message: e.message.unwrap_or_default(),
+                    }),
+            );
+        }
+
+        Ok(events)
+    }
+}
+
+impl Cursor {
+    fn new(start_time: i64) -> Self {
+        Cursor {
+            start_time,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Sort the events by time, and skip the events already printed.
+    fn accept(&mut self, mut events: Vec<LogEvent>) -> Vec<LogEvent> {
+        events.sort_by_key(|e| e.timestamp);
+        events.retain(|e| !self.seen.contains(&e.id));
+
+        if let Some(last) = events.last() {
+            if last.timestamp > self.start_time {
+                self.start_time = last.timestamp;
+                self.seen.clear();
+            }
+        }
+
+        for event in &events {
+            if event.timestamp == self.start_time {
+                self.seen.insert(event.id.clone());
+            }
+        }
+
+        events
+    }
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: &str, timestamp: i64) -> LogEvent {
+        LogEvent {
+            id: id.into(),
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(Since(Duration::from_secs(30)), "30s".parse().unwrap());
+        assert_eq!(Since(Duration::from_secs(15 * 60)), "15m".parse().unwrap());
+        assert_eq!(Since(Duration::from_secs(2 * 3600)), "2h".parse().unwrap());
+        assert_eq!(Since(Duration::from_secs(86400)), "1d".parse().unwrap());
+
+        assert!("15".parse::<Since>().is_err());
+        assert!("m".parse::<Since>().is_err());
+        assert!("15w".parse::<Since>().is_err());
+    }
+
+    #[test]
+    fn test_cursor_skips_seen_events() {
+        let mut cursor = Cursor::new(0);
+
+        let events = cursor.accept(vec![event("b", 20), event("a", 10), event("c", 20)]);
+        let ids = events.iter().map(|e| e.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec!["a", "b", "c"], ids);
+        assert_eq!(20, cursor.start_time);
+
+        // The next request starts at the last timestamp, and returns those events again.
+        let events = cursor.accept(vec![event("b", 20), event("c", 20), event("d", 20)]);
+        let ids = events.iter().map(|e| e.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec!["d"], ids);
+
+        let events = cursor.accept(vec![event("d", 20), event("e", 30)]);
+        let ids = events.iter().map(|e| e.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec!["e"], ids);
+        assert_eq!(HashSet::from(["e".to_string()]), cursor.seen);
+    }
+
+    #[test]
+    fn test_function_name() {
+        let logs = Logs {
+            remote_config: RemoteConfig::default(),
+            follow: false,
+            since: Since(Duration::from_secs(600)),
+            filter: None,
+            log_group: None,
+            output_format: OutputFormat::Text,
+            manifest_path: PathBuf::from(DEFAULT_MANIFEST_PATH),
+            name: None,
+            deploy: Some(Deploy {
+                name: Some("orders".into()),
+                ..Default::default()
+            }),
+        };
+        assert_eq!("orders", logs.function_name(None).unwrap());
+
+        let logs = Logs {
+            name: Some("payments".into()),
+            ..logs
+        };
+        assert_eq!("payments", logs.function_name(None).unwrap());
+
+        let logs = Logs {
+            name: None,
+            deploy: None,
+            ..logs
+        };
+        assert!(logs.function_name(None).is_err());
+    }
+}
@@ -64,6 +64,18 @@ pub struct Build {
     #[serde(default)]
     pub disable_optimizations: bool,
 
+    /// Fail when the toolchain differs from the one recorded in `cargo-lambda.lock`,
+    /// instead of updating the file
+    #[arg(long)]
+    #[serde(default)]
+    pub frozen_toolchain: bool,
+
+    /// Sign the zip files with Sigstore keyless signing, using the `cosign` CLI.
+    /// The attestation is written next to each zip file (only works with --output-format=zip)
+    #[arg(long)]
+    #[serde(default)]
+    pub sign: bool,
+
     /// Option to add one or more files and directories to include in the output ZIP file (only works with --output-format=zip).
     #[arg(short, long)]
     #[serde(default)]
@@ -152,6 +164,8 @@ impl Serialize for Build {
             + self.internal as usize
             + self.skip_target_check as usize
             + self.disable_optimizations as usize
+            + self.frozen_toolchain as usize
+            + self.sign as usize
             + self.cargo_opts.manifest_path.is_some() as usize
             + self.cargo_opts.bins as usize
             + !self.cargo_opts.bin.is_empty() as usize
@@ -205,6 +219,12 @@ impl Serialize for Build {
         if self.disable_optimizations {
             state.serialize_field("disable_optimizations", &true)?;
         }
+        if self.frozen_toolchain {
+            state.serialize_field("frozen_toolchain", &true)?;
+        }
+        if self.sign {
+            state.serialize_field("sign", &true)?;
+        }
 
         // Cargo opts fields
         if let Some(ref manifest_path) = self.cargo_opts.manifest_path {
@@ -4,7 +4,7 @@ use cargo_lambda_remote::{
 };
 use clap::{ArgAction, Args, ValueHint};
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
-use std::{collections::HashMap, fmt::Debug, path::PathBuf};
+use std::{collections::HashMap, fmt::Debug, path::PathBuf, str::FromStr};
 use strum_macros::{Display, EnumString};
 
 use crate::{
@@ -62,6 +62,16 @@ pub struct Deploy {
     #[serde(default)]
     pub s3_key: Option<String>,
 
+    /// Upload the code to S3 with Transfer Acceleration. The bucket must have acceleration enabled
+    #[arg(long, requires = "s3_bucket")]
+    #[serde(default)]
+    pub s3_accelerate: bool,
+
+    /// S3 storage class for the uploaded code, like STANDARD_IA, or INTELLIGENT_TIERING
+    #[arg(long, value_name = "CLASS", requires = "s3_bucket")]
+    #[serde(default)]
+    pub s3_storage_class: Option<String>,
+
     /// Whether the code that you're deploying is a Lambda Extension
     #[arg(long)]
     #[serde(default)]
@@ -104,6 +114,34 @@ pub struct Deploy {
     #[serde(default)]
     pub dry: bool,
 
+    /// Verify the Sigstore signature of the zip file before deploying it,
+    /// with the attestation that `cargo lambda build --sign` writes next to the zip file.
+    /// The signed zip file is deployed as it is, without creating it again
+    #[arg(
+        long,
+        requires_all = ["signature_identity", "signature_issuer"],
+        conflicts_with = "include"
+    )]
+    #[serde(default)]
+    pub verify_signature: bool,
+
+    /// Identity that must have signed the zip file, like an email address,
+    /// or the URL of a CI workflow
+    #[arg(long, value_name = "IDENTITY")]
+    #[serde(default)]
+    pub signature_identity: Option<String>,
+
+    /// OIDC issuer of the identity that signed the zip file, like `https://accounts.google.com`
+    #[arg(long, value_name = "URL")]
+    #[serde(default)]
+    pub signature_issuer: Option<String>,
+
+    /// Estimate the monthly cost of the function after deploying it, with the number of invocations
+    /// per month, and their average duration in milliseconds (--estimate-cost requests=1e6,avg-ms=120)
+    #[arg(long, value_name = "PROFILE", conflicts_with = "extension")]
+    #[serde(default)]
+    pub estimate_cost: Option<CostProfile>,
+
     /// Name of the function or extension to deploy
     #[arg(value_name = "NAME")]
     #[serde(default)]
@@ -186,6 +224,8 @@ impl Serialize for Deploy {
             + self.binary_name.is_some() as usize
             + self.s3_bucket.is_some() as usize
             + self.s3_key.is_some() as usize
+            + self.s3_accelerate as usize
+            + self.s3_storage_class.is_some() as usize
             + self.extension as usize
             + self.internal as usize
             + self.compatible_runtimes.is_some() as usize
@@ -193,6 +233,10 @@ impl Serialize for Deploy {
             + self.tag.is_some() as usize
             + self.include.is_some() as usize
             + self.dry as usize
+            + self.verify_signature as usize
+            + self.signature_identity.is_some() as usize
+            + self.signature_issuer.is_some() as usize
+            + self.estimate_cost.is_some() as usize
             + self.name.is_some() as usize
             + self.remote_config.count_fields()
             + self.function_config.count_fields();
@@ -217,6 +261,12 @@ impl Serialize for Deploy {
         if let Some(ref key) = self.s3_key {
             state.serialize_field("s3_key", key)?;
         }
+        if self.s3_accelerate {
+            state.serialize_field("s3_accelerate", &self.s3_accelerate)?;
+        }
+        if let Some(ref storage_class) = self.s3_storage_class {
+            state.serialize_field("s3_storage_class", storage_class)?;
+        }
         if self.extension {
             state.serialize_field("extension", &self.extension)?;
         }
@@ -238,6 +288,18 @@ impl Serialize for Deploy {
         if self.dry {
             state.serialize_field("dry", &self.dry)?;
         }
+        if self.verify_signature {
+            state.serialize_field("verify_signature", &self.verify_signature)?;
+        }
+        if let Some(ref identity) = self.signature_identity {
+            state.serialize_field("signature_identity", identity)?;
+        }
+        if let Some(ref issuer) = self.signature_issuer {
+            state.serialize_field("signature_issuer", issuer)?;
+        }
+        if let Some(ref profile) = self.estimate_cost {
+            state.serialize_field("estimate_cost", profile)?;
+        }
         if let Some(ref name) = self.name {
             state.serialize_field("name", name)?;
         }
@@ -260,6 +322,66 @@ fn default_compatible_runtimes() -> Vec<String> {
         .collect()
 }
 
+/// Usage of a function in a month, to estimate how much it costs.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CostProfile {
+    /// Number of invocations per month
+    pub requests: f64,
+    /// Average duration of each invocation, in milliseconds
+    pub avg_ms: f64,
+}
+
+impl FromStr for CostProfile {
+    type Err = MetadataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || MetadataError::InvalidCostProfile(s.to_string());
+
+        let (mut requests, mut avg_ms) = (None, None);
+        for pair in s.split(',') {
+            let (key, value) = pair.split_once('=').ok_or_else(invalid)?;
+            let value = value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite() && *value >= 0.0)
+                .ok_or_else(invalid)?;
+
+            match key.trim() {
+                "requests" => requests = Some(value),
+                "avg-ms" | "avg_ms" => avg_ms = Some(value),
+                _ => return Err(invalid()),
+            }
+        }
+
+        match (requests, avg_ms) {
+            (Some(requests), Some(avg_ms)) => Ok(CostProfile { requests, avg_ms }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<String> for CostProfile {
+    type Error = MetadataError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<CostProfile> for String {
+    fn from(profile: CostProfile) -> String {
+        profile.to_string()
+    }
+}
+
+impl std::fmt::Display for CostProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "requests={},avg-ms={}", self.requests, self.avg_ms)
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Display, EnumString, Serialize)]
 #[strum(ascii_case_insensitive)]
 #[serde(rename_all = "lowercase")]
@@ -580,4 +702,28 @@ mod tests {
             Some(&"QUUX".to_string())
         );
     }
+
+    #[test]
+    fn test_cost_profile() {
+        let profile: CostProfile = "requests=1e6,avg-ms=120".parse().unwrap();
+        assert_eq!(
+            CostProfile {
+                requests: 1_000_000.0,
+                avg_ms: 120.0
+            },
+            profile
+        );
+        assert_eq!("requests=1000000,avg-ms=120", profile.to_string());
+
+        let profile: CostProfile = "avg-ms=2.5, requests=300".parse().unwrap();
+        assert_eq!(300.0, profile.requests);
+        assert_eq!(2.5, profile.avg_ms);
+
+        assert!("requests=1e6".parse::<CostProfile>().is_err());
+        assert!("requests=1e6,avg-ms=-1".parse::<CostProfile>().is_err());
+        assert!("requests=1e6,avg-ms=120,memory=128"
+            .parse::<CostProfile>()
+            .is_err());
+        assert!("1e6,120".parse::<CostProfile>().is_err());
+    }
 }
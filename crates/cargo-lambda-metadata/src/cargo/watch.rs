@@ -7,13 +7,15 @@ use serde::{
     Deserialize, Serialize,
 };
 use serde_json::{json, Value};
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::HashMap, fmt, path::PathBuf, str::FromStr};
+use strum_macros::{Display, EnumString};
 
 use crate::{
     cargo::{count_common_options, serialize_common_options},
     env::{EnvOptions, Environment},
     error::MetadataError,
     lambda::Timeout,
+    DEFAULT_ACCOUNT_ID,
 };
 
 use cargo_lambda_remote::tls::TlsOptions;
@@ -24,7 +26,14 @@ const DEFAULT_INVOKE_ADDRESS: &str = "127.0.0.1";
 #[cfg(not(windows))]
 const DEFAULT_INVOKE_ADDRESS: &str = "::";
 
-const DEFAULT_INVOKE_PORT: u16 = 9000;
+/// Port where the emulator listens for invoke requests by default.
+pub const DEFAULT_INVOKE_PORT: u16 = 9000;
+
+const DEFAULT_INIT_CONCURRENCY: u16 = 4;
+
+/// Maximum number of invocations that wait for a function by default,
+/// before new invocations are throttled.
+pub const DEFAULT_MAX_QUEUE_DEPTH: u16 = 100;
 
 #[derive(Args, Clone, Debug, Default, Deserialize)]
 #[command(
@@ -38,6 +47,18 @@ pub struct Watch {
     #[serde(default)]
     pub ignore_changes: bool,
 
+    /// Additional path to watch for changes, like assets or crates outside the project.
+    /// This flag can be used multiple times to watch several paths
+    #[arg(long = "watch-path", value_name = "PATH")]
+    #[serde(default, alias = "watch-paths")]
+    pub watch_paths: Vec<PathBuf>,
+
+    /// Glob pattern of files that don't reload the function when they change, like `*.md`.
+    /// This flag can be used multiple times to ignore several patterns
+    #[arg(long, value_name = "GLOB")]
+    #[serde(default)]
+    pub ignore: Vec<String>,
+
     /// Start the Lambda runtime APIs without starting the function.
     /// This is useful if you start (and debug) your function in your IDE.
     #[arg(long)]
@@ -54,6 +75,12 @@ pub struct Watch {
     #[serde(default = "default_invoke_port")]
     pub invoke_port: u16,
 
+    /// Path of a Unix domain socket where users can also send invoke requests,
+    /// like `/tmp/cargo-lambda.sock`
+    #[arg(long, value_name = "PATH")]
+    #[serde(default)]
+    pub invoke_socket: Option<PathBuf>,
+
     /// Print OpenTelemetry traces after each function invocation
     #[arg(long)]
     #[serde(default)]
@@ -64,16 +91,182 @@ pub struct Watch {
     #[serde(default)]
     pub wait: bool,
 
+    /// Compile and start every function in the project when the emulator starts,
+    /// instead of only the default function
+    #[arg(long, conflicts_with_all = ["only_lambda_apis", "wait"])]
+    #[serde(default)]
+    pub init_all: bool,
+
+    /// Maximum number of functions that `--init-all` compiles at the same time [default: 4]
+    #[arg(long, requires = "init_all", value_parser = clap::value_parser!(u16).range(1..))]
+    #[serde(default)]
+    pub init_concurrency: Option<u16>,
+
+    /// Authorization type of the function URLs, acceptable values are [none, aws-iam].
+    /// With `aws-iam`, requests must be signed with SigV4 using the function URL credentials
+    #[arg(long)]
+    #[serde(default)]
+    pub function_url_auth: Option<FunctionUrlAuth>,
+
+    /// Test credentials that can sign requests to the function URLs when the authorization type is `aws-iam`,
+    /// in the format `ACCESS_KEY_ID:SECRET_ACCESS_KEY[:USER_ARN]`.
+    /// This flag can be used multiple times to add several identities
+    #[arg(long = "function-url-credentials", value_name = "CREDENTIALS")]
+    #[serde(default)]
+    pub function_url_credentials: Vec<IamCredentials>,
+
+    /// Function behind an Application Load Balancer. Its HTTP requests are sent
+    /// as ALB target group events instead of function URL events.
+    /// This flag can be used multiple times to select several functions
+    #[arg(long = "alb-function", value_name = "FUNCTION")]
+    #[serde(default)]
+    pub alb_functions: Vec<String>,
+
+    /// Send the headers and query parameters of ALB events as multi-value maps,
+    /// like target groups with `lambda.multi_value_headers.enabled`
+    #[arg(long)]
+    #[serde(default)]
+    pub alb_multi_value_headers: bool,
+
+    /// Executable that receives the event of every invocation in its stdin, and writes the event
+    /// to send to the function in its stdout. It also receives the responses, with `response` as its first argument
+    #[arg(long, value_name = "PATH")]
+    #[serde(default)]
+    pub middleware: Option<PathBuf>,
+
     /// Disable the default CORS configuration
     #[arg(long)]
     #[serde(default)]
     pub disable_cors: bool,
 
+    /// Allow invocations with payloads larger than the Lambda limits,
+    /// 6MB for requests and buffered responses, and 20MB for streaming responses
+    #[arg(long)]
+    #[serde(default)]
+    pub disable_payload_limits: bool,
+
+    /// Maximum number of invocations that wait for each function to be free,
+    /// further invocations are rejected with a 429 status code [default: 100]
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    #[serde(default)]
+    pub max_queue_depth: Option<u16>,
+
     /// How long the invoke request waits for a response
     #[arg(long)]
     #[serde(default)]
     pub timeout: Option<Timeout>,
 
+    /// Emulate the lifecycle of the Lambda execution environment:
+    /// enforce the init timeout, freeze the function between invocations,
+    /// and stop the function when it's idle
+    #[arg(long)]
+    #[serde(default)]
+    pub emulate_lifecycle: bool,
+
+    /// How long the function can take to initialize when the lifecycle is emulated, in seconds
+    #[arg(long, requires = "emulate_lifecycle")]
+    #[serde(default)]
+    pub init_timeout: Option<Timeout>,
+
+    /// How long the function can be idle before it's stopped when the lifecycle is emulated, in seconds
+    #[arg(long, requires = "emulate_lifecycle")]
+    #[serde(default)]
+    pub idle_timeout: Option<Timeout>,
+
+    /// Run the binaries built by `cargo lambda build` in this directory, like `target/lambda`,
+    /// instead of compiling the functions with `cargo run`
+    #[arg(long, value_name = "DIR", conflicts_with = "debug")]
+    #[serde(default)]
+    pub artifact_dir: Option<PathBuf>,
+
+    /// Command to start the functions instead of `cargo run`, like `cross run --bin {function}`.
+    /// The `{function}` placeholder is replaced with the name of the function's binary
+    #[arg(long, value_name = "COMMAND", conflicts_with_all = ["debug", "artifact_dir"])]
+    #[serde(default)]
+    pub runner: Option<String>,
+
+    /// Command to start a service that the functions depend on before the emulator starts,
+    /// like `docker compose up -d`. This flag can be used multiple times to start several services
+    #[arg(long = "service", value_name = "COMMAND")]
+    #[serde(default)]
+    pub services: Vec<String>,
+
+    /// Command to stop the services when the emulator shuts down, like `docker compose down`.
+    /// This flag can be used multiple times to run several commands
+    #[arg(long = "service-shutdown", value_name = "COMMAND")]
+    #[serde(default)]
+    pub services_shutdown: Vec<String>,
+
+    /// Write the output of each function to a log file in `target/lambda/logs`
+    #[arg(long)]
+    #[serde(default)]
+    pub log_files: bool,
+
+    /// Write every invocation, with its request and its response, as a JSON file named after its request id
+    #[arg(long, value_name = "DIR")]
+    #[serde(default)]
+    pub log_requests_dir: Option<PathBuf>,
+
+    /// Build the function with debug information, and start it under the platform debugger (gdb or lldb)
+    #[arg(long, conflicts_with_all = ["only_lambda_apis", "emulate_lifecycle"])]
+    #[serde(default)]
+    pub debug: bool,
+
+    /// Start the function with a debug server on this port, and wait for a debugger to attach
+    #[arg(long, requires = "debug")]
+    #[serde(default)]
+    pub debug_port: Option<u16>,
+
+    /// Path to an extension binary, or a Cargo project with an extension, to run alongside the function.
+    /// This flag can be used multiple times to run several extensions
+    #[arg(long, value_name = "PATH_OR_BINARY")]
+    #[serde(default)]
+    pub with_extension: Vec<PathBuf>,
+
+    /// Event source that invokes the function locally, like `sqs:<queue-url>`.
+    /// Prefix it with `<function-name>=` to invoke a specific function.
+    /// This flag can be used multiple times to add several event sources
+    #[arg(long = "trigger", value_name = "TRIGGER")]
+    #[serde(default)]
+    pub triggers: Vec<Trigger>,
+
+    /// Maximum number of records that the event sources send to the function in each invocation.
+    /// SQS triggers accept 10 records at most, and DynamoDB triggers 1000
+    #[arg(long, requires = "triggers", value_parser = clap::value_parser!(u16).range(1..=1000))]
+    #[serde(default)]
+    pub batch_size: Option<u16>,
+
+    /// Bucket name in the events sent by `s3:` triggers, the name of the watched directory by default
+    #[arg(long, requires = "triggers")]
+    #[serde(default)]
+    pub s3_bucket: Option<String>,
+
+    /// Custom AWS endpoint for triggers that poll AWS services, like LocalStack or DynamoDB local
+    #[arg(long, requires = "triggers")]
+    #[serde(default)]
+    pub trigger_endpoint_url: Option<String>,
+
+    /// Inject the credentials and the region of a real AWS account in the functions' environment.
+    /// The credentials are refreshed before they expire
+    #[arg(long)]
+    #[serde(default)]
+    pub aws_env: bool,
+
+    /// AWS configuration profile used to load the credentials injected with `--aws-env`, and to poll the triggers
+    #[arg(long)]
+    #[serde(default)]
+    pub aws_profile: Option<String>,
+
+    /// AWS region injected with `--aws-env`, and used by triggers whose region is unknown, if there is no default
+    #[arg(long)]
+    #[serde(default)]
+    pub aws_region: Option<String>,
+
+    /// ARN of an IAM role to assume with `--aws-env`, the functions get the role's credentials
+    #[arg(long, requires = "aws_env")]
+    #[serde(default)]
+    pub aws_role_arn: Option<String>,
+
     #[command(flatten)]
     #[serde(flatten)]
     pub cargo_opts: Run,
@@ -99,6 +292,20 @@ impl Watch {
             .unwrap_or_else(|| "Cargo.toml".into())
     }
 
+    /// Maximum number of functions that start at the same time with `init_all`.
+    pub fn init_concurrency(&self) -> usize {
+        self.init_concurrency
+            .unwrap_or(DEFAULT_INIT_CONCURRENCY)
+            .max(1) as usize
+    }
+
+    /// Maximum number of invocations that wait for each function.
+    pub fn max_queue_depth(&self) -> usize {
+        self.max_queue_depth
+            .unwrap_or(DEFAULT_MAX_QUEUE_DEPTH)
+            .max(1) as usize
+    }
+
     /// Returns the package name if there is only one package in the list of `packages`,
     /// otherwise None.
     pub fn package(&self) -> Option<String> {
@@ -116,6 +323,217 @@ impl Watch {
     }
 }
 
+/// Authorization type of the function URLs, like the `AuthType` of the function URLs in AWS.
+#[derive(Clone, Debug, Default, Deserialize, Display, EnumString, Eq, PartialEq, Serialize)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+#[serde(rename_all = "kebab-case")]
+pub enum FunctionUrlAuth {
+    #[default]
+    None,
+    AwsIam,
+}
+
+/// Value that replaces the secret access keys when the credentials are serialized or printed.
+pub const REDACTED_SECRET: &str = "<redacted>";
+
+/// Test credentials of an IAM identity that can invoke the function URLs.
+/// The secret access key is redacted when the credentials are serialized.
+#[derive(Clone, Deserialize, Eq, PartialEq)]
+#[serde(try_from = "String")]
+pub struct IamCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// ARN of the identity, a user in the default test account if it's not specified.
+    pub user_arn: Option<String>,
+}
+
+impl IamCredentials {
+    /// ARN of the identity that signs the requests.
+    pub fn user_arn(&self) -> String {
+        self.user_arn.clone().unwrap_or_else(|| {
+            format!(
+                "arn:aws:iam::{DEFAULT_ACCOUNT_ID}:user/{}",
+                self.access_key_id
+            )
+        })
+    }
+
+    /// Account of the identity, extracted from its ARN.
+    pub fn account_id(&self) -> String {
+        self.user_arn()
+            .split(':')
+            .nth(4)
+            .filter(|id| !id.is_empty())
+            .unwrap_or(DEFAULT_ACCOUNT_ID)
+            .to_string()
+    }
+}
+
+impl Serialize for IamCredentials {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let redacted = IamCredentials {
+            secret_access_key: REDACTED_SECRET.to_string(),
+            ..self.clone()
+        };
+        serializer.serialize_str(&String::from(redacted))
+    }
+}
+
+impl fmt::Debug for IamCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IamCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &REDACTED_SECRET)
+            .field("user_arn", &self.user_arn)
+            .finish()
+    }
+}
+
+impl FromStr for IamCredentials {
+    type Err = MetadataError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut parts = spec.splitn(3, ':');
+        let access_key_id = parts.next().unwrap_or_default().trim();
+        let secret_access_key = parts.next().unwrap_or_default().trim();
+        let user_arn = parts.next().map(str::trim).filter(|arn| !arn.is_empty());
+
+        if access_key_id.is_empty() || secret_access_key.is_empty() {
+            return Err(MetadataError::InvalidIamCredentials);
+        }
+
+        Ok(IamCredentials {
+            access_key_id: access_key_id.to_string(),
+            secret_access_key: secret_access_key.to_string(),
+            user_arn: user_arn.map(String::from),
+        })
+    }
+}
+
+impl TryFrom<String> for IamCredentials {
+    type Error = MetadataError;
+
+    fn try_from(spec: String) -> Result<Self, Self::Error> {
+        spec.parse()
+    }
+}
+
+impl From<IamCredentials> for String {
+    fn from(credentials: IamCredentials) -> String {
+        let mut spec = format!(
+            "{}:{}",
+            credentials.access_key_id, credentials.secret_access_key
+        );
+        if let Some(arn) = credentials.user_arn {
+            spec.push(':');
+            spec.push_str(&arn);
+        }
+        spec
+    }
+}
+
+/// Event source that invokes a function locally.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Trigger {
+    /// Function to invoke, the default function if it's not specified.
+    pub function_name: Option<String>,
+    pub source: TriggerSource,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TriggerSource {
+    /// Poll messages from an SQS queue.
+    Sqs { queue_url: String },
+    /// Invoke the function periodically with an EventBridge schedule expression,
+    /// like `rate(5 minutes)` or `cron(0 12 * * ? *)`.
+    Schedule { expression: String },
+    /// Send S3 object events when files change in a local directory.
+    S3 { dir: PathBuf },
+    /// Read records from a DynamoDB stream.
+    DynamoDb { stream_arn: String },
+}
+
+impl FromStr for Trigger {
+    type Err = MetadataError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = || MetadataError::InvalidTrigger(spec.to_string());
+
+        let (kind, target) = spec.split_once(':').ok_or_else(invalid)?;
+        let (function_name, kind) = match kind.split_once('=') {
+            Some((name, kind)) if !name.trim().is_empty() => (Some(name.trim().to_string()), kind),
+            Some(_) => return Err(invalid()),
+            None => (None, kind),
+        };
+
+        let target = target.trim();
+        if target.is_empty() {
+            return Err(invalid());
+        }
+
+        let source = match kind.trim() {
+            "sqs" => TriggerSource::Sqs {
+                queue_url: target.to_string(),
+            },
+            "schedule" if is_schedule_expression(target) => TriggerSource::Schedule {
+                expression: target.to_string(),
+            },
+            "s3" => TriggerSource::S3 { dir: target.into() },
+            "dynamodb" if target.starts_with("arn:") => TriggerSource::DynamoDb {
+                stream_arn: target.to_string(),
+            },
+            _ => return Err(invalid()),
+        };
+
+        Ok(Trigger {
+            function_name,
+            source,
+        })
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.function_name {
+            write!(f, "{name}=")?;
+        }
+
+        match &self.source {
+            TriggerSource::Sqs { queue_url } => write!(f, "sqs:{queue_url}"),
+            TriggerSource::Schedule { expression } => write!(f, "schedule:{expression}"),
+            TriggerSource::S3 { dir } => write!(f, "s3:{}", dir.display()),
+            TriggerSource::DynamoDb { stream_arn } => write!(f, "dynamodb:{stream_arn}"),
+        }
+    }
+}
+
+fn is_schedule_expression(expression: &str) -> bool {
+    let inner = expression
+        .strip_prefix("rate(")
+        .or_else(|| expression.strip_prefix("cron("))
+        .and_then(|e| e.strip_suffix(')'));
+
+    inner.is_some_and(|e| !e.trim().is_empty())
+}
+
+impl TryFrom<String> for Trigger {
+    type Error = MetadataError;
+
+    fn try_from(spec: String) -> Result<Self, Self::Error> {
+        spec.parse()
+    }
+}
+
+impl From<Trigger> for String {
+    fn from(trigger: Trigger) -> String {
+        trigger.to_string()
+    }
+}
+
 impl Serialize for Watch {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -128,11 +546,43 @@ impl Serialize for Watch {
             + self.only_lambda_apis as usize
             + !self.invoke_address.is_empty() as usize
             + (self.invoke_port != 0) as usize
+            + self.invoke_socket.is_some() as usize
+            + self.function_url_auth.is_some() as usize
+            + self.middleware.is_some() as usize
+            + !self.function_url_credentials.is_empty() as usize
+            + !self.alb_functions.is_empty() as usize
+            + self.alb_multi_value_headers as usize
             + self.print_traces as usize
             + self.wait as usize
+            + self.init_all as usize
+            + self.init_concurrency.is_some() as usize
             + self.disable_cors as usize
+            + self.disable_payload_limits as usize
+            + self.max_queue_depth.is_some() as usize
             + self.timeout.is_some() as usize
+            + self.emulate_lifecycle as usize
+            + self.init_timeout.is_some() as usize
+            + self.idle_timeout.is_some() as usize
             + self.router.is_some() as usize
+            + !self.with_extension.is_empty() as usize
+            + !self.watch_paths.is_empty() as usize
+            + !self.ignore.is_empty() as usize
+            + self.artifact_dir.is_some() as usize
+            + self.runner.is_some() as usize
+            + !self.services.is_empty() as usize
+            + !self.services_shutdown.is_empty() as usize
+            + self.log_files as usize
+            + self.log_requests_dir.is_some() as usize
+            + self.debug as usize
+            + self.debug_port.is_some() as usize
+            + !self.triggers.is_empty() as usize
+            + self.batch_size.is_some() as usize
+            + self.s3_bucket.is_some() as usize
+            + self.trigger_endpoint_url.is_some() as usize
+            + self.aws_env as usize
+            + self.aws_profile.is_some() as usize
+            + self.aws_region.is_some() as usize
+            + self.aws_role_arn.is_some() as usize
             + self.cargo_opts.manifest_path.is_some() as usize
             + self.cargo_opts.release as usize
             + self.cargo_opts.ignore_rust_version as usize
@@ -160,23 +610,119 @@ impl Serialize for Watch {
         if self.invoke_port != 0 {
             state.serialize_field("invoke_port", &self.invoke_port)?;
         }
+        if let Some(invoke_socket) = &self.invoke_socket {
+            state.serialize_field("invoke_socket", invoke_socket)?;
+        }
+        if let Some(function_url_auth) = &self.function_url_auth {
+            state.serialize_field("function_url_auth", function_url_auth)?;
+        }
+        if let Some(middleware) = &self.middleware {
+            state.serialize_field("middleware", middleware)?;
+        }
+        if !self.function_url_credentials.is_empty() {
+            state.serialize_field("function_url_credentials", &self.function_url_credentials)?;
+        }
+        if !self.alb_functions.is_empty() {
+            state.serialize_field("alb_functions", &self.alb_functions)?;
+        }
+        if self.alb_multi_value_headers {
+            state.serialize_field("alb_multi_value_headers", &true)?;
+        }
         if self.print_traces {
             state.serialize_field("print_traces", &true)?;
         }
         if self.wait {
             state.serialize_field("wait", &true)?;
         }
+        if self.init_all {
+            state.serialize_field("init_all", &true)?;
+        }
         if self.disable_cors {
             state.serialize_field("disable_cors", &true)?;
         }
+        if self.disable_payload_limits {
+            state.serialize_field("disable_payload_limits", &true)?;
+        }
+        if self.emulate_lifecycle {
+            state.serialize_field("emulate_lifecycle", &true)?;
+        }
 
         // Only serialize Some values for Options
         if let Some(timeout) = &self.timeout {
             state.serialize_field("timeout", timeout)?;
         }
+        if let Some(init_concurrency) = &self.init_concurrency {
+            state.serialize_field("init_concurrency", init_concurrency)?;
+        }
+        if let Some(max_queue_depth) = &self.max_queue_depth {
+            state.serialize_field("max_queue_depth", max_queue_depth)?;
+        }
+        if let Some(init_timeout) = &self.init_timeout {
+            state.serialize_field("init_timeout", init_timeout)?;
+        }
+        if let Some(idle_timeout) = &self.idle_timeout {
+            state.serialize_field("idle_timeout", idle_timeout)?;
+        }
         if let Some(router) = &self.router {
             state.serialize_field("router", router)?;
         }
+        if !self.watch_paths.is_empty() {
+            state.serialize_field("watch_paths", &self.watch_paths)?;
+        }
+        if !self.ignore.is_empty() {
+            state.serialize_field("ignore", &self.ignore)?;
+        }
+        if let Some(artifact_dir) = &self.artifact_dir {
+            state.serialize_field("artifact_dir", artifact_dir)?;
+        }
+        if let Some(runner) = &self.runner {
+            state.serialize_field("runner", runner)?;
+        }
+        if !self.services.is_empty() {
+            state.serialize_field("services", &self.services)?;
+        }
+        if !self.services_shutdown.is_empty() {
+            state.serialize_field("services_shutdown", &self.services_shutdown)?;
+        }
+        if self.log_files {
+            state.serialize_field("log_files", &true)?;
+        }
+        if let Some(log_requests_dir) = &self.log_requests_dir {
+            state.serialize_field("log_requests_dir", log_requests_dir)?;
+        }
+        if self.debug {
+            state.serialize_field("debug", &true)?;
+        }
+        if let Some(debug_port) = &self.debug_port {
+            state.serialize_field("debug_port", debug_port)?;
+        }
+        if !self.with_extension.is_empty() {
+            state.serialize_field("with_extension", &self.with_extension)?;
+        }
+        if !self.triggers.is_empty() {
+            state.serialize_field("triggers", &self.triggers)?;
+        }
+        if let Some(batch_size) = &self.batch_size {
+            state.serialize_field("batch_size", batch_size)?;
+        }
+        if let Some(s3_bucket) = &self.s3_bucket {
+            state.serialize_field("s3_bucket", s3_bucket)?;
+        }
+        if let Some(trigger_endpoint_url) = &self.trigger_endpoint_url {
+            state.serialize_field("trigger_endpoint_url", trigger_endpoint_url)?;
+        }
+        if self.aws_env {
+            state.serialize_field("aws_env", &true)?;
+        }
+        if let Some(aws_profile) = &self.aws_profile {
+            state.serialize_field("aws_profile", aws_profile)?;
+        }
+        if let Some(aws_region) = &self.aws_region {
+            state.serialize_field("aws_region", aws_region)?;
+        }
+        if let Some(aws_role_arn) = &self.aws_role_arn {
+            state.serialize_field("aws_role_arn", aws_role_arn)?;
+        }
 
         // Flatten the fields from cargo_opts and env_options
         self.env_options.serialize_fields::<S>(&mut state)?;
@@ -240,6 +786,11 @@ impl FunctionRouter {
     pub fn insert(&mut self, path: &str, routes: FunctionRoutes) -> Result<(), InsertError> {
         self.inner.insert(path, routes)
     }
+
+    /// Routes in the order that they were declared.
+    pub fn routes(&self) -> &[(String, FunctionRoutes)] {
+        &self.raw
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -272,16 +823,9 @@ impl<'de> Visitor<'de> for FunctionRouterVisitor {
     {
         let routes: HashMap<String, FunctionRoutes> =
             Deserialize::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
-        let mut inner = Router::new();
 
-        for (path, route) in &routes {
-            inner.insert(path, route.clone()).map_err(|e| {
-                serde::de::Error::custom(format!("Failed to insert route {path}: {e}"))
-            })?;
-        }
-
-        let raw: Vec<(String, FunctionRoutes)> = routes.into_iter().collect();
-        Ok(FunctionRouter { inner, raw })
+        let raw = normalize_routes(routes.into_iter().collect()).map_err(Error::custom)?;
+        build_router(raw).map_err(Error::custom)
     }
 
     fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
@@ -290,15 +834,89 @@ impl<'de> Visitor<'de> for FunctionRouterVisitor {
     {
         let raw: Vec<(String, FunctionRoutes)> =
             Deserialize::deserialize(serde::de::value::SeqAccessDeserializer::new(seq))?;
-        let mut inner = Router::new();
 
-        for (path, route) in &raw {
-            inner.insert(path, route.clone()).map_err(|e| {
-                serde::de::Error::custom(format!("Failed to insert route {path}: {e}"))
-            })?;
+        let raw = normalize_routes(raw).map_err(Error::custom)?;
+        build_router(raw).map_err(Error::custom)
+    }
+}
+
+fn build_router(raw: Vec<(String, FunctionRoutes)>) -> Result<FunctionRouter, String> {
+    let mut inner = Router::new();
+
+    for (path, route) in &raw {
+        inner
+            .insert(path, route.clone())
+            .map_err(|e| format!("Failed to insert route {path}: {e}"))?;
+    }
+
+    Ok(FunctionRouter { inner, raw })
+}
+
+/// Group routes declared as `"METHOD /path" = "function"` by path,
+/// so they can be matched like routes declared with a list of methods.
+fn normalize_routes(
+    entries: Vec<(String, FunctionRoutes)>,
+) -> Result<Vec<(String, FunctionRoutes)>, String> {
+    let mut raw: Vec<(String, FunctionRoutes)> = Vec::with_capacity(entries.len());
+
+    for (key, routes) in entries {
+        let (path, routes) = match split_method_route(&key) {
+            None => (key, routes),
+            Some((method, path)) => match routes {
+                FunctionRoutes::Single(function) => (
+                    path.to_string(),
+                    FunctionRoutes::Multiple(HashMap::from([(method.to_string(), function)])),
+                ),
+                FunctionRoutes::Multiple(_) => {
+                    return Err(format!(
+                        "Route {key} includes an HTTP method, its value must be a function name"
+                    ))
+                }
+            },
+        };
+
+        match raw.iter_mut().find(|(p, _)| *p == path) {
+            None => raw.push((path, routes)),
+            Some((_, FunctionRoutes::Multiple(existing))) => match routes {
+                FunctionRoutes::Multiple(new) => {
+                    for (method, function) in new {
+                        if existing.insert(method.clone(), function).is_some() {
+                            return Err(format!(
+                                "Route {path} is declared more than once for the method {method}"
+                            ));
+                        }
+                    }
+                }
+                FunctionRoutes::Single(_) => {
+                    return Err(format!(
+                        "Route {path} is declared for all methods and for specific methods"
+                    ))
+                }
+            },
+            Some((_, FunctionRoutes::Single(_))) => {
+                return Err(match routes {
+                    FunctionRoutes::Single(_) => format!("Route {path} is declared more than once"),
+                    FunctionRoutes::Multiple(_) => {
+                        format!("Route {path} is declared for all methods and for specific methods")
+                    }
+                })
+            }
         }
+    }
 
-        Ok(FunctionRouter { inner, raw })
+    Ok(raw)
+}
+
+/// Split a route key like `GET /users/:id` into its method and path.
+fn split_method_route(key: &str) -> Option<(&str, &str)> {
+    let (method, path) = key.trim().split_once(char::is_whitespace)?;
+    let path = path.trim();
+
+    let is_method = !method.is_empty() && method.chars().all(|c| c.is_ascii_uppercase());
+    if is_method && path.starts_with('/') {
+        Some((method, path))
+    } else {
+        None
     }
 }
 
@@ -413,6 +1031,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_router_deserialize_with_methods() {
+        let router: FunctionRouter = toml::from_str(
+            r#"
+            "GET /users/:id" = "get-user"
+            "DELETE /users/:id" = "delete-user"
+            "POST /users" = "create-user"
+            "/health" = "health"
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(router.at("/users/1", "GET"), Ok("get-user"));
+        assert_eq!(router.at("/users/1", "DELETE"), Ok("delete-user"));
+        assert_eq!(router.at("/users/1", "PUT"), Err(MatchError::NotFound));
+        assert_eq!(router.at("/users", "POST"), Ok("create-user"));
+        assert_eq!(router.at("/users", "GET"), Err(MatchError::NotFound));
+        assert_eq!(router.at("/health", "GET"), Ok("health"));
+        assert_eq!(router.raw.len(), 3);
+    }
+
+    #[test]
+    fn test_router_deserialize_with_conflicting_methods() {
+        let result: Result<FunctionRouter, _> = toml::from_str(
+            r#"
+            "GET /users" = "get-users"
+            "/users" = "users"
+        "#,
+        );
+        assert!(result.is_err());
+
+        let result: Result<FunctionRouter, _> = toml::from_str(
+            r#"
+            "GET /users" = [{ method = "GET", function = "get-users" }]
+        "#,
+        );
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_router_get() {
         let router = FunctionRouter::default();
@@ -618,4 +1275,98 @@ mod tests {
             watch.cargo_opts.common.timings
         );
     }
+
+    #[test]
+    fn test_parse_iam_credentials() {
+        let credentials: IamCredentials = "AKIDEXAMPLE:secret".parse().unwrap();
+        assert_eq!("AKIDEXAMPLE", credentials.access_key_id);
+        assert_eq!("secret", credentials.secret_access_key);
+        assert_eq!(
+            "arn:aws:iam::123456789012:user/AKIDEXAMPLE",
+            credentials.user_arn()
+        );
+
+        let credentials: IamCredentials = "AKIDEXAMPLE:secret:arn:aws:iam::111122223333:user/alice"
+            .parse()
+            .unwrap();
+        assert_eq!("111122223333", credentials.account_id());
+        assert_eq!(
+            "AKIDEXAMPLE:secret:arn:aws:iam::111122223333:user/alice",
+            String::from(credentials)
+        );
+
+        let credentials: IamCredentials = "AKIDEXAMPLE:secret".parse().unwrap();
+        assert_eq!(
+            Value::String("AKIDEXAMPLE:<redacted>".into()),
+            serde_json::to_value(&credentials).unwrap()
+        );
+
+        assert!("AKIDEXAMPLE".parse::<IamCredentials>().is_err());
+        assert!(":secret".parse::<IamCredentials>().is_err());
+
+        assert_eq!(
+            FunctionUrlAuth::AwsIam,
+            "aws-iam".parse::<FunctionUrlAuth>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_trigger() {
+        let trigger: Trigger = "sqs:https://sqs.us-east-1.amazonaws.com/123456789012/my-queue"
+            .parse()
+            .unwrap();
+        assert_eq!(None, trigger.function_name);
+        assert_eq!(
+            TriggerSource::Sqs {
+                queue_url: "https://sqs.us-east-1.amazonaws.com/123456789012/my-queue".into()
+            },
+            trigger.source
+        );
+
+        let trigger: Trigger = "my-function=sqs:http://localhost:4566/000000000000/my-queue"
+            .parse()
+            .unwrap();
+        assert_eq!(Some("my-function".into()), trigger.function_name);
+        assert_eq!(
+            "my-function=sqs:http://localhost:4566/000000000000/my-queue",
+            trigger.to_string()
+        );
+
+        assert!("sqs".parse::<Trigger>().is_err());
+        assert!("sqs:".parse::<Trigger>().is_err());
+        assert!("=sqs:my-queue".parse::<Trigger>().is_err());
+        assert!("kafka:my-topic".parse::<Trigger>().is_err());
+
+        let trigger: Trigger = "schedule:rate(5 minutes)".parse().unwrap();
+        assert_eq!(
+            TriggerSource::Schedule {
+                expression: "rate(5 minutes)".into()
+            },
+            trigger.source
+        );
+        assert!("schedule:cron(0 12 * * ? *)".parse::<Trigger>().is_ok());
+        assert!("schedule:every 5 minutes".parse::<Trigger>().is_err());
+        assert!("schedule:rate()".parse::<Trigger>().is_err());
+
+        let trigger: Trigger = "process-uploads=s3:./uploads".parse().unwrap();
+        assert_eq!(Some("process-uploads".into()), trigger.function_name);
+        assert_eq!(
+            TriggerSource::S3 {
+                dir: "./uploads".into()
+            },
+            trigger.source
+        );
+
+        let arn =
+            "arn:aws:dynamodb:us-east-1:123456789012:table/users/stream/2024-01-01T00:00:00.000";
+        let trigger: Trigger = format!("dynamodb:{arn}").parse().unwrap();
+        assert_eq!(
+            TriggerSource::DynamoDb {
+                stream_arn: arn.into()
+            },
+            trigger.source
+        );
+        assert_eq!(format!("dynamodb:{arn}"), trigger.to_string());
+        assert!("dynamodb:users".parse::<Trigger>().is_err());
+    }
 }
@@ -1,15 +1,39 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+};
 
-use crate::cargo::{
-    build::Build, deploy::Deploy, watch::Watch, CargoMetadata, Metadata, PackageMetadata,
+use crate::{
+    cargo::{
+        build::Build,
+        deploy::Deploy,
+        watch::{Watch, REDACTED_SECRET},
+        CargoMetadata, Metadata, PackageMetadata,
+    },
+    error::MetadataError,
 };
+pub use cargo_lambda_remote::user_config_path;
+use cargo_lambda_remote::RemoteConfig;
 use cargo_metadata::{Package, Target};
 use figment::{
-    providers::{Env, Format, Serialized, Toml},
+    providers::{Data, Env, Format, Serialized, Toml},
+    value::{Dict, Value as FigmentValue},
     Figment,
 };
 use miette::{IntoDiagnostic, Result};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use strum_macros::Display;
+
+/// Name of the global configuration file, when the flag `--global` is not present.
+const GLOBAL_CONFIG_FILE: &str = "CargoLambda.toml";
+
+/// Sections of the configuration that `load_config` loads.
+const CONFIG_SECTIONS: [&str; 4] = ["env", "build", "deploy", "watch"];
+
+/// Key that a context uses to inherit the configuration of other contexts in the global configuration file.
+pub const INHERITS_KEY: &str = "inherits";
 
 #[derive(Debug, Default)]
 pub struct ConfigOptions {
@@ -28,6 +52,90 @@ pub struct Config {
     pub watch: Watch,
 }
 
+/// Configuration for the registry where `cargo lambda invoke --data-example`
+/// downloads example payloads from.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct ExamplesConfig {
+    /// Base URL of the server that hosts the example payloads
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Value of the `Authorization` header sent with every request to the registry
+    #[serde(default)]
+    pub authorization: Option<String>,
+}
+
+impl fmt::Debug for ExamplesConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExamplesConfig")
+            .field("url", &self.url)
+            .field(
+                "authorization",
+                &self.authorization.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+/// Default templates for `cargo lambda new` and `cargo lambda init`,
+/// used when the `--template` flag is not present.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TemplatesConfig {
+    /// Location of the template to create functions with
+    #[serde(default)]
+    pub function: Option<String>,
+    /// Location of the template to create extensions with
+    #[serde(default)]
+    pub extension: Option<String>,
+    /// Variables to pass to the template engine, the `--render-var` flags take precedence over them
+    #[serde(default)]
+    pub render_vars: HashMap<String, String>,
+}
+
+/// Configuration for the notifications about new Cargo Lambda releases.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct UpdatesConfig {
+    /// Check for new releases in the background, and print a notification when there is one
+    #[serde(default)]
+    pub check: bool,
+}
+
+/// Where a configuration value comes from.
+/// The sources are in precedence order, the sources at the bottom override the sources at the top.
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSource {
+    /// Default value of the option
+    #[strum(to_string = "default")]
+    Default,
+    /// User's configuration file, with the defaults for all the projects
+    #[strum(to_string = "user file")]
+    UserFile,
+    /// `CARGO_LAMBDA_` environment variables
+    #[strum(to_string = "environment variable")]
+    EnvVar,
+    /// Global configuration file
+    #[strum(to_string = "global file")]
+    GlobalFile,
+    /// Metadata in the workspace's Cargo.toml
+    #[strum(to_string = "workspace metadata")]
+    WorkspaceMetadata,
+    /// Metadata in the package's Cargo.toml
+    #[strum(to_string = "package metadata")]
+    PackageMetadata,
+    /// Flags in the command line
+    #[strum(to_string = "CLI flag")]
+    CliFlag,
+}
+
+/// Resolved configuration value, and the source that it comes from.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ConfigValue {
+    /// Path of the value in the configuration, like `deploy.memory`
+    pub key: String,
+    pub value: Value,
+    pub source: ConfigSource,
+}
+
 impl From<PackageMetadata> for Config {
     fn from(meta: PackageMetadata) -> Self {
         Config {
@@ -57,7 +165,11 @@ pub fn load_config(
         figment.merge(args_serialized)
     };
 
-    figment.extract().into_diagnostic()
+    let mut config: Config = interpolate(figment, options, &CONFIG_SECTIONS)?
+        .extract()
+        .into_diagnostic()?;
+    restore_credential_secrets(&mut config, args_config, metadata, options)?;
+    Ok(config)
 }
 
 pub fn load_config_without_cli_flags(
@@ -65,27 +177,511 @@ pub fn load_config_without_cli_flags(
     options: &ConfigOptions,
 ) -> Result<Config> {
     let figment = figment_from_metadata(metadata, options)?;
-    figment.extract().into_diagnostic()
+    let mut config: Config = interpolate(figment, options, &CONFIG_SECTIONS)?
+        .extract()
+        .into_diagnostic()?;
+    restore_credential_secrets(&mut config, &Config::default(), metadata, options)?;
+    Ok(config)
 }
 
-fn figment_from_metadata(metadata: &CargoMetadata, options: &ConfigOptions) -> Result<Figment> {
+/// The secrets of the function URL credentials are redacted when the configuration is serialized,
+/// so the credentials that come from the flags and the Cargo metadata lose them when they're merged.
+/// This takes them back from the original values, with the same precedence as `figment_from_metadata`.
+fn restore_credential_secrets(
+    config: &mut Config,
+    args_config: &Config,
+    metadata: &CargoMetadata,
+    options: &ConfigOptions,
+) -> Result<()> {
+    let credentials = &mut config.watch.function_url_credentials;
+    if !credentials
+        .iter()
+        .any(|c| c.secret_access_key == REDACTED_SECRET)
+    {
+        return Ok(());
+    }
+
     let (ws_metadata, bin_metadata) = workspace_metadata(metadata, options.name.as_deref())?;
-    let package_metadata = package_metadata(metadata, options.name.as_deref())?;
+    let (package_metadata, package_bin_metadata) =
+        package_metadata(metadata, options.name.as_deref())?;
+    let sources = [
+        Some(args_config),
+        package_bin_metadata.as_ref(),
+        package_metadata.as_ref(),
+        bin_metadata.as_ref(),
+        Some(&ws_metadata),
+    ];
+
+    for redacted in credentials
+        .iter_mut()
+        .filter(|c| c.secret_access_key == REDACTED_SECRET)
+    {
+        let original = sources
+            .iter()
+            .flatten()
+            .flat_map(|source| &source.watch.function_url_credentials)
+            .find(|c| c.access_key_id == redacted.access_key_id && c.user_arn == redacted.user_arn);
+        if let Some(original) = original {
+            redacted
+                .secret_access_key
+                .clone_from(&original.secret_access_key);
+        }
+    }
 
-    let mut config_file = options
-        .global
-        .as_ref()
-        .map(Toml::file)
-        .unwrap_or_else(|| Toml::file("CargoLambda.toml"));
+    Ok(())
+}
+
+/// Load the configuration, and annotate each resolved value with the source that it comes from.
+/// When a value is set in several sources, the source with the highest precedence wins.
+/// In admerge mode, arrays merged from several sources are annotated with the source with the highest precedence.
+pub fn load_config_with_sources(
+    args_config: &Config,
+    metadata: &CargoMetadata,
+    options: &ConfigOptions,
+) -> Result<Vec<ConfigValue>> {
+    let config = load_config(args_config, metadata, options)?;
+    let resolved = serde_json::to_value(&config).into_diagnostic()?;
+    let layers = config_layers(args_config, metadata, options)?;
+
+    let mut leaves = Vec::new();
+    collect_leaves(&mut Vec::new(), &resolved, &mut leaves);
+
+    let values = leaves
+        .into_iter()
+        .map(|(path, value)| {
+            let source = layers
+                .iter()
+                .rev()
+                .find(|(_, layer)| lookup(layer, &path).is_some())
+                .map(|(source, _)| *source)
+                .unwrap_or(ConfigSource::Default);
+            ConfigValue {
+                key: path.join("."),
+                value,
+                source,
+            }
+        })
+        .collect();
+    Ok(values)
+}
+
+/// Values of each configuration source, in the same order that `figment_from_metadata` merges them.
+fn config_layers(
+    args_config: &Config,
+    metadata: &CargoMetadata,
+    options: &ConfigOptions,
+) -> Result<Vec<(ConfigSource, Value)>> {
+    let (ws_metadata, ws_bin_metadata) = workspace_metadata(metadata, options.name.as_deref())?;
+    let (package_metadata, package_bin_metadata) =
+        package_metadata(metadata, options.name.as_deref())?;
+
+    let mut figment = Figment::new();
+    if let Some(context) = &options.context {
+        figment = figment.select(context)
+    }
+
+    let mut env_serialized = Env::prefixed("CARGO_LAMBDA_");
+    if let Some(context) = &options.context {
+        env_serialized = env_serialized.profile(context);
+    }
+    let mut layers = Vec::new();
+    if let Some(user_config) = user_config_file(options)? {
+        let user = figment
+            .clone()
+            .merge(user_config)
+            .extract()
+            .into_diagnostic()?;
+        layers.push((ConfigSource::UserFile, user));
+    }
+
+    let env = figment
+        .clone()
+        .merge(env_serialized)
+        .extract()
+        .into_diagnostic()?;
+    let global = figment
+        .merge(global_config_file(options)?)
+        .extract()
+        .into_diagnostic()?;
+    layers.push((ConfigSource::EnvVar, env));
+    layers.push((ConfigSource::GlobalFile, global));
+
+    let metadata_layers = [
+        (ConfigSource::WorkspaceMetadata, Some(ws_metadata)),
+        (ConfigSource::WorkspaceMetadata, ws_bin_metadata),
+        (ConfigSource::PackageMetadata, package_metadata),
+        (ConfigSource::PackageMetadata, package_bin_metadata),
+    ];
+    for (source, config) in metadata_layers {
+        if let Some(config) = config {
+            layers.push((source, serde_json::to_value(config).into_diagnostic()?));
+        }
+    }
+
+    layers.push((
+        ConfigSource::CliFlag,
+        serde_json::to_value(args_config).into_diagnostic()?,
+    ));
+    Ok(layers)
+}
+
+/// Collect the values that are not objects, with their paths.
+/// Arrays are collected as a single value, and null values are skipped.
+fn collect_leaves(path: &mut Vec<String>, value: &Value, leaves: &mut Vec<(Vec<String>, Value)>) {
+    match value {
+        Value::Null => {}
+        Value::Object(map) => {
+            for (key, value) in map {
+                path.push(key.clone());
+                collect_leaves(path, value, leaves);
+                path.pop();
+            }
+        }
+        value => leaves.push((path.clone(), value.clone())),
+    }
+}
+
+fn lookup<'a>(value: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(value, |value, key| value.get(key))
+        .filter(|value| !value.is_null())
+}
+
+/// Load the examples registry configuration from the `[examples]` section.
+pub fn load_examples_config(options: &ConfigOptions) -> Result<ExamplesConfig> {
+    load_global_section(options, "examples")
+}
+
+/// Load the default templates and render variables for new projects from the `[templates]` section.
+pub fn load_templates_config(options: &ConfigOptions) -> Result<TemplatesConfig> {
+    load_global_section(options, "templates")
+}
+
+/// Load the update notifications configuration from the `[updates]` section.
+pub fn load_updates_config(options: &ConfigOptions) -> Result<UpdatesConfig> {
+    load_global_section(options, "updates")
+}
+
+/// Load the AWS options, like the role to assume, from the `[deploy]` section, for the commands
+/// that talk to AWS outside of a project. The role in the package metadata is only used by `deploy`.
+pub fn load_remote_config(options: &ConfigOptions) -> Result<RemoteConfig> {
+    load_global_section(options, "deploy")
+}
+
+/// Load a section of the configuration that doesn't depend on a Cargo project, so it's only read
+/// from the user and global configuration files, and never from the package metadata.
+/// With a context, the section outside of the contexts is the default for every context.
+/// The `CARGO_LAMBDA_{SECTION}_` environment variables take precedence over the files.
+fn load_global_section<T: DeserializeOwned + Default>(
+    options: &ConfigOptions,
+    section: &str,
+) -> Result<T> {
+    let mut figment = Figment::new();
+    if let Some(context) = &options.context {
+        figment = figment.select(context)
+    }
+
+    // The contexts are nested in the files, so their top level is the default for every context.
     if options.context.is_some() {
-        config_file = config_file.nested()
+        let paths = [
+            user_config_path().filter(|path| path.is_file()),
+            find_config_file(&global_config_path(options)),
+        ];
+        for path in paths.into_iter().flatten() {
+            figment = figment.merge(Toml::file(path));
+        }
+    }
+
+    if let Some(user_config) = user_config_file(options)? {
+        figment = figment.merge(user_config);
+    }
+    figment = figment.merge(global_config_file(options)?);
+
+    let prefix = format!("CARGO_LAMBDA_{}_", section.to_uppercase());
+    let key_section = section.to_string();
+    let env = Env::prefixed(&prefix).map(move |key| format!("{key_section}.{key}").into());
+    // Global values take precedence over the selected context.
+    let figment = figment.merge(env.global());
+
+    match interpolate(figment, options, &[section])?.extract_inner::<T>(section) {
+        Ok(config) => Ok(config),
+        Err(err) if err.missing() => Ok(T::default()),
+        Err(err) => Err(err).into_diagnostic(),
     }
+}
+
+fn global_config_path(options: &ConfigOptions) -> PathBuf {
+    options
+        .global
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(GLOBAL_CONFIG_FILE))
+}
+
+fn global_config_file(options: &ConfigOptions) -> Result<Data<Toml>> {
+    config_file(global_config_path(options), options)
+}
+
+fn user_config_file(options: &ConfigOptions) -> Result<Option<Data<Toml>>> {
+    match user_config_path().filter(|path| path.is_file()) {
+        Some(path) => config_file(path, options).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Open a configuration file with the same format as the global configuration file.
+/// When there is a context, the file has a table for each context,
+/// and the tables of the contexts in the list, and the contexts that they inherit from,
+/// are resolved into the table of the selected context.
+fn config_file(path: PathBuf, options: &ConfigOptions) -> Result<Data<Toml>> {
+    let Some(context) = &options.context else {
+        return Ok(Toml::file(path));
+    };
+    let Some(path) = find_config_file(&path) else {
+        return Ok(Toml::file(path).nested());
+    };
+
+    let source = std::fs::read_to_string(&path)
+        .map_err(|err| MetadataError::InvalidGlobalConfigFile(path.clone(), err))?;
+    let mut contexts: toml::Table = toml::from_str(&source)
+        .map_err(|err| MetadataError::InvalidGlobalConfigToml(path.clone(), err))?;
+
+    let resolved = resolve_context(&contexts, context, options.admerge)?;
+    contexts.insert(context.clone(), toml::Value::Table(resolved));
+
+    let source = toml::to_string(&contexts).into_diagnostic()?;
+    Ok(Toml::string(&source).nested())
+}
+
+/// Expand the variables in the strings of the given sections, like `${env:AWS_ACCOUNT_ID}`, and `${context}`.
+/// Other sections are not expanded, so their variables don't need to be set to load these sections.
+/// The expanded values are merged in the global profile, so they take precedence over every source.
+fn interpolate(figment: Figment, options: &ConfigOptions, sections: &[&str]) -> Result<Figment> {
+    let mut dict: Dict = figment.extract().into_diagnostic()?;
+    dict.retain(|key, _| sections.contains(&key.as_str()));
+
+    let context = options
+        .context
+        .as_deref()
+        .and_then(|c| c.split(',').map(str::trim).filter(|c| !c.is_empty()).last());
+    let env = |name: &str| std::env::var(name).ok();
+
+    match interpolate_value(&FigmentValue::from(dict), context, &env)? {
+        Some(changes) => Ok(figment.merge(Serialized::globals(changes))),
+        None => Ok(figment),
+    }
+}
+
+/// Expand the variables in a value. It returns `None` when the value doesn't have any variables,
+/// and for dictionaries, it only returns the keys that changed.
+fn interpolate_value(
+    value: &FigmentValue,
+    context: Option<&str>,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> Result<Option<FigmentValue>, MetadataError> {
+    match value {
+        FigmentValue::String(_, s) => Ok(interpolate_str(s, context, env)?.map(FigmentValue::from)),
+        FigmentValue::Dict(_, dict) => {
+            let mut changes = Dict::new();
+            for (key, value) in dict {
+                if let Some(value) = interpolate_value(value, context, env)? {
+                    changes.insert(key.clone(), value);
+                }
+            }
+            Ok((!changes.is_empty()).then(|| FigmentValue::from(changes)))
+        }
+        // Arrays are replaced when they are merged, so they are returned complete.
+        FigmentValue::Array(_, items) => {
+            let mut changed = false;
+            let mut expanded = Vec::with_capacity(items.len());
+            for item in items {
+                match interpolate_value(item, context, env)? {
+                    Some(item) => {
+                        changed = true;
+                        expanded.push(item);
+                    }
+                    None => expanded.push(item.clone()),
+                }
+            }
+            Ok(changed.then(|| FigmentValue::from(expanded)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Expand the variables in a string. `$${` escapes the variable syntax.
+fn interpolate_str(
+    input: &str,
+    context: Option<&str>,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> Result<Option<String>, MetadataError> {
+    if !input.contains("${") {
+        return Ok(None);
+    }
+
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            output.push_str(&rest[..start - 1]);
+            output.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        output.push_str(&rest[..start]);
+
+        let Some(end) = rest[start..].find('}') else {
+            return Err(MetadataError::InvalidInterpolation(
+                rest[start..].to_string(),
+            ));
+        };
+        let variable = &rest[start + 2..start + end];
+        let value = match variable.strip_prefix("env:") {
+            Some(name) => env(name)
+                .ok_or_else(|| MetadataError::MissingInterpolationVariable(name.to_string()))?,
+            None if variable == "context" => context
+                .ok_or(MetadataError::MissingInterpolationContext)?
+                .to_string(),
+            None => {
+                return Err(MetadataError::InvalidInterpolation(format!(
+                    "${{{variable}}}"
+                )))
+            }
+        };
+        output.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    output.push_str(rest);
+
+    Ok(Some(output))
+}
+
+/// Find the file like figment does, relative paths are searched in
+/// the current directory, and in all its parent directories.
+fn find_config_file(path: &Path) -> Option<PathBuf> {
+    if path.is_absolute() {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+
+    let cwd = std::env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(path))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Merge the tables of a list of contexts separated by commas, like `base,staging`.
+/// Contexts are merged after the contexts that they inherit from,
+/// and the contexts at the end of the list take precedence.
+fn resolve_context(
+    contexts: &toml::Table,
+    context: &str,
+    admerge: bool,
+) -> Result<toml::Table, MetadataError> {
+    let mut resolved = toml::Table::new();
+    let mut merged = Vec::new();
+    for name in context.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        merge_context(
+            contexts,
+            name,
+            &mut Vec::new(),
+            &mut merged,
+            &mut resolved,
+            admerge,
+        )?;
+    }
+    Ok(resolved)
+}
+
+fn merge_context<'a>(
+    contexts: &'a toml::Table,
+    name: &'a str,
+    chain: &mut Vec<&'a str>,
+    merged: &mut Vec<&'a str>,
+    resolved: &mut toml::Table,
+    admerge: bool,
+) -> Result<(), MetadataError> {
+    if chain.contains(&name) {
+        chain.push(name);
+        return Err(MetadataError::RecursiveContext(
+            name.to_string(),
+            chain.join(" -> "),
+        ));
+    }
+    if merged.contains(&name) {
+        return Ok(());
+    }
+
+    // Contexts in the command line that are not in the file don't change the configuration.
+    let Some(context) = contexts.get(name) else {
+        return match chain.last() {
+            Some(child) => Err(MetadataError::MissingParentContext(
+                child.to_string(),
+                name.to_string(),
+            )),
+            None => Ok(()),
+        };
+    };
+    let Some(context) = context.as_table() else {
+        return Ok(());
+    };
+
+    let parents = match context.get(INHERITS_KEY) {
+        None => Vec::new(),
+        Some(toml::Value::String(parent)) => vec![parent.as_str()],
+        Some(toml::Value::Array(parents)) => parents
+            .iter()
+            .map(|parent| parent.as_str())
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| MetadataError::InvalidContextInherits(name.to_string()))?,
+        Some(_) => return Err(MetadataError::InvalidContextInherits(name.to_string())),
+    };
+
+    chain.push(name);
+    for parent in parents {
+        merge_context(contexts, parent, chain, merged, resolved, admerge)?;
+    }
+    chain.pop();
+
+    let mut sections = context.clone();
+    sections.remove(INHERITS_KEY);
+    merge_tables(resolved, sections, admerge);
+    merged.push(name);
+    Ok(())
+}
+
+/// Merge the source into the target, like figment merges providers.
+/// Arrays are replaced, unless `admerge` is enabled, which concatenates them.
+fn merge_tables(target: &mut toml::Table, source: toml::Table, admerge: bool) {
+    for (key, value) in source {
+        match (target.get_mut(&key), value) {
+            (Some(toml::Value::Table(target)), toml::Value::Table(source)) => {
+                merge_tables(target, source, admerge)
+            }
+            (Some(toml::Value::Array(target)), toml::Value::Array(source)) if admerge => {
+                target.extend(source)
+            }
+            (_, value) => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+fn figment_from_metadata(metadata: &CargoMetadata, options: &ConfigOptions) -> Result<Figment> {
+    let (ws_metadata, bin_metadata) = workspace_metadata(metadata, options.name.as_deref())?;
+    let (package_metadata, package_bin_metadata) =
+        package_metadata(metadata, options.name.as_deref())?;
+
+    let config_file = global_config_file(options)?;
 
     let mut figment = Figment::new();
     if let Some(context) = &options.context {
         figment = figment.select(context)
     }
 
+    if let Some(user_config) = user_config_file(options)? {
+        figment = figment.merge(user_config);
+    }
+
     let mut env_serialized = Env::prefixed("CARGO_LAMBDA_");
     if let Some(context) = &options.context {
         env_serialized = env_serialized.profile(context);
@@ -121,7 +717,11 @@ fn figment_from_metadata(metadata: &CargoMetadata, options: &ConfigOptions) -> R
         }
     }
 
-    if let Some(package_metadata) = package_metadata {
+    // The binary configuration is merged after the package configuration, so it takes precedence.
+    for package_metadata in [package_metadata, package_bin_metadata]
+        .into_iter()
+        .flatten()
+    {
         let mut package_serialized = Serialized::defaults(package_metadata);
         if let Some(context) = &options.context {
             package_serialized = package_serialized.profile(context);
@@ -158,18 +758,23 @@ fn workspace_metadata(
     Ok((ws_config, None))
 }
 
-fn package_metadata(metadata: &CargoMetadata, name: Option<&str>) -> Result<Option<Config>> {
+/// Return the configuration of the package that includes the binary,
+/// and the configuration of the binary in that package, if it has any.
+fn package_metadata(
+    metadata: &CargoMetadata,
+    name: Option<&str>,
+) -> Result<(Option<Config>, Option<Config>)> {
     let Some(name) = name else {
         let Some(root) = metadata.root_package() else {
-            return Ok(None);
+            return Ok((None, None));
         };
 
         if root.metadata.is_null() || !root.metadata.is_object() {
-            return Ok(None);
+            return Ok((None, None));
         }
 
         let meta: Metadata = serde_json::from_value(root.metadata.clone()).into_diagnostic()?;
-        return Ok(Some(meta.lambda.package.into()));
+        return Ok((Some(meta.lambda.package.into()), None));
     };
 
     let kind_condition = |pkg: &Package, target: &Target| {
@@ -182,16 +787,13 @@ fn package_metadata(metadata: &CargoMetadata, name: Option<&str>) -> Result<Opti
                 let meta: Metadata =
                     serde_json::from_value(pkg.metadata.clone()).into_diagnostic()?;
 
-                if let Some(bin_config) = meta.lambda.bin.get(name) {
-                    return Ok(Some(bin_config.clone().into()));
-                }
-
-                return Ok(Some(meta.lambda.package.into()));
+                let bin_config = meta.lambda.bin.get(name).cloned().map(Config::from);
+                return Ok((Some(meta.lambda.package.into()), bin_config));
             }
         }
     }
 
-    Ok(None)
+    Ok((None, None))
 }
 
 #[cfg(test)]
@@ -312,6 +914,40 @@ mod tests {
         assert_eq!(config.env.get("AWS_REGION"), Some(&"us-west-2".to_string()));
     }
 
+    #[test]
+    fn test_load_env_per_binary() {
+        let metadata = load_metadata(fixture_metadata("multi-binary-package")).unwrap();
+
+        let options = ConfigOptions {
+            name: Some("get-product".to_string()),
+            ..Default::default()
+        };
+        let config = load_config_without_cli_flags(&metadata, &options).unwrap();
+        assert_eq!(config.env.get("FOO"), Some(&"BAR".to_string()));
+        assert_eq!(config.env.get("TABLE_NAME"), Some(&"products".to_string()));
+        assert_eq!(config.env.get("BAZ"), None);
+
+        let options = ConfigOptions {
+            name: Some("delete-product".to_string()),
+            ..Default::default()
+        };
+        let config = load_config_without_cli_flags(&metadata, &options).unwrap();
+        assert_eq!(config.env.get("BAZ"), Some(&"QUX".to_string()));
+        assert_eq!(
+            config.env.get("TABLE_NAME"),
+            Some(&"deleted-products".to_string())
+        );
+        assert_eq!(config.env.get("FOO"), None);
+
+        let options = ConfigOptions {
+            name: Some("get-products".to_string()),
+            ..Default::default()
+        };
+        let config = load_config_without_cli_flags(&metadata, &options).unwrap();
+        assert_eq!(config.env.get("TABLE_NAME"), Some(&"products".to_string()));
+        assert_eq!(config.env.get("FOO"), None);
+    }
+
     #[test]
     fn test_config_with_context() {
         let manifest = fixture_metadata("config-with-context");
@@ -345,6 +981,104 @@ mod tests {
         assert_eq!(config.deploy.function_config.memory, Some(Memory::Mb256));
     }
 
+    #[test]
+    fn test_config_with_inherited_context() {
+        let manifest = fixture_metadata("config-with-context");
+        let global = manifest.parent().unwrap().join("CargoLambda.toml");
+        let metadata = load_metadata(manifest).unwrap();
+
+        let options = ConfigOptions {
+            context: Some("staging".to_string()),
+            global: Some(global.clone()),
+            ..Default::default()
+        };
+        let config = load_config_without_cli_flags(&metadata, &options).unwrap();
+        assert_eq!(config.deploy.function_config.memory, Some(Memory::Mb1024));
+        assert_eq!(config.deploy.function_config.timeout, Some(120.into()));
+
+        let options = ConfigOptions {
+            context: Some("staging, development".to_string()),
+            global: Some(global),
+            ..Default::default()
+        };
+        let config = load_config_without_cli_flags(&metadata, &options).unwrap();
+        assert_eq!(config.deploy.function_config.memory, Some(Memory::Mb512));
+        assert_eq!(config.deploy.function_config.timeout, Some(120.into()));
+    }
+
+    #[test]
+    fn test_resolve_context() {
+        let contexts: toml::Table = toml::from_str(
+            r#"
+[base.deploy]
+memory = 256
+layer = ["arn:aws:lambda:us-east-1:xxxxxxxx:layers:base"]
+
+[eu]
+inherits = "base"
+deploy = { region = "eu-west-1" }
+
+[staging]
+inherits = ["base", "eu"]
+
+[staging.deploy]
+memory = 512
+layer = ["arn:aws:lambda:us-east-1:xxxxxxxx:layers:staging"]
+"#,
+        )
+        .unwrap();
+
+        let resolved = resolve_context(&contexts, "staging", false).unwrap();
+        let deploy = resolved["deploy"].as_table().unwrap();
+        assert_eq!(deploy["memory"].as_integer(), Some(512));
+        assert_eq!(deploy["region"].as_str(), Some("eu-west-1"));
+        assert_eq!(deploy["layer"].as_array().unwrap().len(), 1);
+        assert!(!resolved.contains_key(INHERITS_KEY));
+
+        let resolved = resolve_context(&contexts, "staging", true).unwrap();
+        assert_eq!(resolved["deploy"]["layer"].as_array().unwrap().len(), 2);
+
+        let resolved = resolve_context(&contexts, "staging,base", false).unwrap();
+        assert_eq!(resolved["deploy"]["memory"].as_integer(), Some(512));
+
+        let resolved = resolve_context(&contexts, "missing", false).unwrap();
+        assert!(resolved.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_context_errors() {
+        let contexts: toml::Table = toml::from_str(
+            r#"
+[a]
+inherits = "b"
+
+[b]
+inherits = "a"
+
+[c]
+inherits = "missing"
+
+[d]
+inherits = 1
+"#,
+        )
+        .unwrap();
+
+        let err = resolve_context(&contexts, "a", false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the context `a` inherits from itself: a -> b -> a"
+        );
+
+        let err = resolve_context(&contexts, "c", false).unwrap_err();
+        assert!(
+            matches!(err, MetadataError::MissingParentContext(child, parent) if child == "c" && parent == "missing")
+        );
+
+        let err = resolve_context(&contexts, "d", false).unwrap_err();
+        assert!(matches!(err, MetadataError::InvalidContextInherits(name) if name == "d"));
+    }
+
     #[test]
     fn test_config_with_context_and_cli_flags() {
         let manifest = fixture_metadata("config-with-context");
@@ -368,4 +1102,311 @@ mod tests {
         let config = load_config(&args_config, &metadata, &options).unwrap();
         assert_eq!(config.deploy.function_config.memory, Some(Memory::Mb2048));
     }
+
+    #[test]
+    fn test_load_examples_config() {
+        let manifest = fixture_metadata("config-with-context");
+        let global = manifest.parent().unwrap().join("CargoLambda.toml");
+
+        let options = ConfigOptions {
+            global: Some(global.clone()),
+            ..Default::default()
+        };
+
+        let config = load_examples_config(&options).unwrap();
+        assert_eq!(config.url.as_deref(), Some("https://examples.example.com"));
+        assert_eq!(config.authorization.as_deref(), Some("Bearer secret"));
+
+        let options = ConfigOptions {
+            context: Some("production".to_string()),
+            global: Some(global),
+            ..Default::default()
+        };
+
+        let config = load_examples_config(&options).unwrap();
+        assert_eq!(
+            config.url.as_deref(),
+            Some("https://examples.production.example.com")
+        );
+        assert_eq!(config.authorization.as_deref(), Some("Bearer secret"));
+    }
+
+    #[test]
+    fn test_load_examples_config_with_context_and_env() {
+        let manifest = std::fs::canonicalize(fixture_metadata("config-with-context")).unwrap();
+        let global = manifest.parent().unwrap().join("CargoLambda.toml");
+
+        figment::Jail::expect_with(|jail| {
+            jail.set_env(
+                "CARGO_LAMBDA_EXAMPLES_URL",
+                "https://examples.env.example.com",
+            );
+
+            let options = ConfigOptions {
+                context: Some("production".to_string()),
+                global: Some(global),
+                ..Default::default()
+            };
+
+            let config = load_examples_config(&options).unwrap();
+            assert_eq!(
+                config.url.as_deref(),
+                Some("https://examples.env.example.com")
+            );
+            assert_eq!(config.authorization.as_deref(), Some("Bearer secret"));
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_load_examples_config_without_section() {
+        let manifest = fixture_metadata("package-with-global-config");
+        let global = manifest.parent().unwrap().join("CargoLambda.toml");
+
+        let options = ConfigOptions {
+            global: Some(global),
+            ..Default::default()
+        };
+
+        let config = load_examples_config(&options).unwrap();
+        assert_eq!(config.url, None);
+        assert_eq!(config.authorization, None);
+    }
+
+    #[test]
+    fn test_load_templates_config() {
+        let manifest = fixture_metadata("config-with-context");
+        let global = manifest.parent().unwrap().join("CargoLambda.toml");
+
+        let options = ConfigOptions {
+            global: Some(global.clone()),
+            ..Default::default()
+        };
+
+        let config = load_templates_config(&options).unwrap();
+        assert_eq!(
+            config.function.as_deref(),
+            Some("https://git.example.com/platform/function-template")
+        );
+        assert_eq!(config.extension, None);
+        assert_eq!(
+            config.render_vars.get("team").map(String::as_str),
+            Some("platform")
+        );
+
+        let options = ConfigOptions {
+            global: Some(manifest.parent().unwrap().join("missing.toml")),
+            ..Default::default()
+        };
+
+        let config = load_templates_config(&options).unwrap();
+        assert_eq!(config.function, None);
+        assert!(config.render_vars.is_empty());
+    }
+
+    #[test]
+    fn test_load_updates_config() {
+        let manifest = fixture_metadata("config-with-context");
+        let global = manifest.parent().unwrap().join("CargoLambda.toml");
+
+        let options = ConfigOptions {
+            global: Some(global),
+            ..Default::default()
+        };
+        assert!(load_updates_config(&options).unwrap().check);
+
+        let manifest = fixture_metadata("package-with-global-config");
+        let options = ConfigOptions {
+            global: Some(manifest.parent().unwrap().join("CargoLambda.toml")),
+            ..Default::default()
+        };
+        assert!(!load_updates_config(&options).unwrap().check);
+    }
+
+    #[test]
+    fn test_load_config_with_function_url_credentials() {
+        let metadata = load_metadata(fixture_metadata("single-binary-package")).unwrap();
+        let mut args_config = Config::default();
+        args_config.watch.function_url_credentials = vec!["AKIDEXAMPLE:secret".parse().unwrap()];
+
+        let config = load_config(&args_config, &metadata, &ConfigOptions::default()).unwrap();
+        assert_eq!(
+            "secret",
+            config.watch.function_url_credentials[0].secret_access_key
+        );
+
+        let values =
+            load_config_with_sources(&args_config, &metadata, &ConfigOptions::default()).unwrap();
+        let credentials = values
+            .iter()
+            .find(|v| v.key == "watch.function_url_credentials")
+            .unwrap();
+        assert_eq!(
+            Value::Array(vec!["AKIDEXAMPLE:<redacted>".into()]),
+            credentials.value
+        );
+    }
+
+    #[test]
+    fn test_load_config_with_sources() {
+        let metadata = load_metadata(fixture_metadata("single-binary-package")).unwrap();
+        let mut args_config = Config::default();
+        args_config.deploy.function_config.timeout = Some(30.into());
+
+        let values =
+            load_config_with_sources(&args_config, &metadata, &ConfigOptions::default()).unwrap();
+        let source = |key: &str| {
+            values
+                .iter()
+                .find(|v| v.key == key)
+                .map(|v| (v.value.clone(), v.source))
+        };
+
+        assert_eq!(
+            source("deploy.memory"),
+            Some((512.into(), ConfigSource::PackageMetadata))
+        );
+        assert_eq!(
+            source("deploy.timeout"),
+            Some((30.into(), ConfigSource::CliFlag))
+        );
+        assert_eq!(
+            source("env.FOO"),
+            Some(("BAR".into(), ConfigSource::PackageMetadata))
+        );
+        assert_eq!(
+            source("watch.invoke_port"),
+            Some((9000.into(), ConfigSource::Default))
+        );
+    }
+
+    #[test]
+    fn test_load_config_with_sources_from_global_file() {
+        let manifest = fixture_metadata("package-with-global-config");
+        let metadata = load_metadata(&manifest).unwrap();
+        let options = ConfigOptions {
+            global: Some(manifest.parent().unwrap().join("CargoLambda.toml")),
+            ..Default::default()
+        };
+
+        let values = load_config_with_sources(&Config::default(), &metadata, &options).unwrap();
+        let output_format = values
+            .iter()
+            .find(|v| v.key == "build.output_format")
+            .unwrap();
+        assert_eq!(output_format.value, Value::from("zip"));
+        assert_eq!(output_format.source, ConfigSource::GlobalFile);
+    }
+
+    #[test]
+    fn test_interpolate_str() {
+        let env = |name: &str| (name == "AWS_ACCOUNT_ID").then(|| "123456789012".to_string());
+
+        assert_eq!(
+            interpolate_str(
+                "arn:aws:iam::${env:AWS_ACCOUNT_ID}:role/${context}",
+                Some("staging"),
+                &env
+            )
+            .unwrap(),
+            Some("arn:aws:iam::123456789012:role/staging".to_string())
+        );
+        assert_eq!(interpolate_str("no variables", None, &env).unwrap(), None);
+        assert_eq!(
+            interpolate_str("literal $${context}", None, &env).unwrap(),
+            Some("literal ${context}".to_string())
+        );
+
+        let err = interpolate_str("${env:MISSING}", None, &env).unwrap_err();
+        assert!(
+            matches!(err, MetadataError::MissingInterpolationVariable(name) if name == "MISSING")
+        );
+
+        let err = interpolate_str("bucket-${context}", None, &env).unwrap_err();
+        assert!(matches!(err, MetadataError::MissingInterpolationContext));
+
+        let err = interpolate_str("${region}", None, &env).unwrap_err();
+        assert!(
+            matches!(err, MetadataError::InvalidInterpolation(variable) if variable == "${region}")
+        );
+
+        let err = interpolate_str("${env:AWS_ACCOUNT_ID", None, &env).unwrap_err();
+        assert!(matches!(err, MetadataError::InvalidInterpolation(_)));
+    }
+
+    #[test]
+    fn test_interpolate_config() {
+        figment::Jail::expect_with(|jail| {
+            jail.set_env("CARGO_LAMBDA_TEST_ACCOUNT_ID", "123456789012");
+
+            let figment = Figment::new().merge(Toml::string(
+                r#"
+[deploy]
+role = "arn:aws:iam::${env:CARGO_LAMBDA_TEST_ACCOUNT_ID}:role/${context}"
+tag = ["stage=${context}", "team=platform"]
+
+[examples]
+url = "${env:CARGO_LAMBDA_TEST_MISSING}"
+"#,
+            ));
+            let options = ConfigOptions {
+                context: Some("base,staging".to_string()),
+                ..Default::default()
+            };
+
+            let deploy: Deploy = interpolate(figment, &options, &CONFIG_SECTIONS)
+                .unwrap()
+                .extract_inner("deploy")
+                .unwrap();
+            assert_eq!(
+                deploy.function_config.role.as_deref(),
+                Some("arn:aws:iam::123456789012:role/staging")
+            );
+            assert_eq!(
+                deploy.tag,
+                Some(vec![
+                    "stage=staging".to_string(),
+                    "team=platform".to_string()
+                ])
+            );
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_load_user_config() {
+        let manifest = std::fs::canonicalize(fixture_metadata("single-binary-package")).unwrap();
+        let metadata = load_metadata(manifest).unwrap();
+
+        figment::Jail::expect_with(|jail| {
+            jail.set_env("XDG_CONFIG_HOME", jail.directory().display());
+            std::fs::create_dir_all(jail.directory().join("cargo-lambda")).unwrap();
+            jail.create_file(
+                "cargo-lambda/config.toml",
+                r#"
+[deploy]
+region = "eu-west-1"
+memory = 128
+
+[watch]
+invoke_port = 9090
+"#,
+            )?;
+
+            let options = ConfigOptions::default();
+            let config = load_config_without_cli_flags(&metadata, &options).unwrap();
+            assert_eq!(
+                config.deploy.remote_config.region.as_deref(),
+                Some("eu-west-1")
+            );
+            assert_eq!(config.deploy.function_config.memory, Some(Memory::Mb512));
+            assert_eq!(config.watch.invoke_port, 9090);
+
+            let values = load_config_with_sources(&Config::default(), &metadata, &options).unwrap();
+            let region = values.iter().find(|v| v.key == "deploy.region").unwrap();
+            assert_eq!(region.source, ConfigSource::UserFile);
+            Ok(())
+        });
+    }
 }
@@ -7,39 +7,74 @@ use thiserror::Error;
 #[derive(Debug, Diagnostic, Error)]
 pub enum MetadataError {
     #[error("invalid memory value `{0}`")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_memory))]
     InvalidMemory(i32),
     #[error("invalid lambda metadata in Cargo.toml file: {0}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_cargo_metadata))]
     InvalidCargoMetadata(#[from] serde_json::Error),
     #[error("invalid timeout value")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_timeout))]
     InvalidTimeout(#[from] ParseIntError),
     #[error("invalid tracing option `{0}`")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_tracing))]
     InvalidTracing(String),
     #[error("there are more than one binary in the project, please specify a binary name with --binary-name or --binary-path. This is the list of binaries I found: {0}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::metadata::multiple_binaries_in_project))]
     MultipleBinariesInProject(String),
     #[error("there are no binaries in this project")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::metadata::missing_binary_in_project))]
     MissingBinaryInProject,
     #[error("invalid environment variable `{0}`")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_env_var))]
     InvalidEnvVar(String),
     #[error("invalid environment file `{0}`: {1}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_env_file))]
     InvalidEnvFile(PathBuf, std::io::Error),
     #[error(transparent)]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::metadata::failed_cmd_execution))]
     FailedCmdExecution(#[from] cargo_metadata::Error),
     #[error("invalid manifest file `{0}`: {1}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_manifest_file))]
     InvalidManifestFile(PathBuf, std::io::Error),
     #[error(transparent)]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_toml_manifest))]
     InvalidTomlManifest(toml::de::Error),
     #[error(transparent)]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::metadata::merge_error))]
     MergeError(#[from] MergeError),
+    #[error("invalid trigger `{0}`, use the format `[FUNCTION_NAME=]TYPE:TARGET`, like `sqs:https://sqs.us-east-1.amazonaws.com/123456789012/my-queue`")]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_trigger))]
+    InvalidTrigger(String),
+    #[error("invalid function URL credentials, use the format `ACCESS_KEY_ID:SECRET_ACCESS_KEY[:USER_ARN]`")]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_iam_credentials))]
+    InvalidIamCredentials,
+    #[error("invalid cost profile `{0}`, use the format `requests=NUMBER,avg-ms=NUMBER`, like `requests=1e6,avg-ms=120`")]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_cost_profile))]
+    InvalidCostProfile(String),
+    #[error("invalid global configuration file `{0}`: {1}")]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_global_config_file))]
+    InvalidGlobalConfigFile(PathBuf, std::io::Error),
+    #[error("invalid TOML in the global configuration file `{0}`: {1}")]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_global_config_toml))]
+    InvalidGlobalConfigToml(PathBuf, toml::de::Error),
+    #[error("the context `{0}` inherits from itself: {1}")]
+    #[diagnostic(code(cargo_lambda::metadata::recursive_context))]
+    RecursiveContext(String, String),
+    #[error(
+        "the context `{0}` inherits from `{1}`, which is not in the global configuration file"
+    )]
+    #[diagnostic(code(cargo_lambda::metadata::missing_parent_context))]
+    MissingParentContext(String, String),
+    #[error("invalid `inherits` value in the context `{0}`, use a context name, or a list of context names")]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_context_inherits))]
+    InvalidContextInherits(String),
+    #[error("the environment variable `{0}` in the configuration is not set")]
+    #[diagnostic(code(cargo_lambda::metadata::missing_interpolation_variable))]
+    MissingInterpolationVariable(String),
+    #[error("the configuration uses `${{context}}`, but there is no context, use the flag `--context` to select one")]
+    #[diagnostic(code(cargo_lambda::metadata::missing_interpolation_context))]
+    MissingInterpolationContext,
+    #[error("invalid variable `{0}` in the configuration, use `${{env:NAME}}`, or `${{context}}`")]
+    #[diagnostic(code(cargo_lambda::metadata::invalid_interpolation))]
+    InvalidInterpolation(String),
 }
@@ -4,6 +4,7 @@ pub mod env;
 pub mod error;
 pub mod fs;
 pub mod lambda;
+pub mod validate;
 
 /// Name for the function when no name is provided.
 /// This will make the watch command to compile
@@ -12,6 +13,18 @@ pub mod lambda;
 /// which is the main binary for that package.
 pub const DEFAULT_PACKAGE_FUNCTION: &str = "_";
 
+/// Header in the emulator's responses that tells whether the invocation
+/// started a new function process.
+pub const CARGO_LAMBDA_COLD_START_HEADER: &str = "cargo-lambda-cold-start";
+
+/// Region of the events that the emulator and the invoke command generate,
+/// and of the AWS requests when no region is configured.
+pub use cargo_lambda_remote::DEFAULT_REGION;
+
+/// Account of the ARNs in the events that the emulator and the invoke command generate,
+/// and of the test identities that sign function URL requests.
+pub const DEFAULT_ACCOUNT_ID: &str = "123456789012";
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
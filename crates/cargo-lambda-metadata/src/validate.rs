@@ -0,0 +1,659 @@
+use std::{fmt, ops::Range, path::Path};
+
+use miette::{Diagnostic, LabeledSpan, NamedSource, Severity, SourceCode, SourceSpan};
+use serde::de::DeserializeOwned;
+use toml_edit::{ImDocument, Item, TableLike};
+
+use crate::{
+    cargo::{build::Build, deploy::Deploy, watch::Watch},
+    config::{ExamplesConfig, TemplatesConfig, UpdatesConfig, INHERITS_KEY},
+};
+
+/// Tables in the Cargo metadata where Cargo Lambda reads its configuration from.
+const METADATA_TABLES: [[&str; 3]; 2] = [
+    ["package", "metadata", "lambda"],
+    ["workspace", "metadata", "lambda"],
+];
+
+/// Sections that configure the commands that work with a project.
+const PROJECT_SECTIONS: [&str; 4] = ["env", "build", "deploy", "watch"];
+
+/// Sections that only the global configuration file can have.
+const GLOBAL_SECTIONS: [&str; 3] = ["examples", "templates", "updates"];
+
+/// Flags in a section that cannot be enabled at the same time.
+const CONFLICTS: [(&str, &str, &str); 2] = [
+    ("build", "arm64", "x86_64"),
+    ("deploy", "enable_function_url", "disable_function_url"),
+];
+
+/// Kind of table that holds the configuration sections.
+#[derive(Clone, Copy, PartialEq)]
+enum Scope {
+    /// `lambda` table in the Cargo metadata, which can have `bin` tables
+    Metadata,
+    /// Table for a binary in the Cargo metadata
+    Bin,
+    /// Context in the global configuration file, which can inherit other contexts
+    Context,
+}
+
+impl Scope {
+    /// Key that the scope accepts besides the configuration sections.
+    fn extra_key(&self) -> Option<&'static str> {
+        match self {
+            Scope::Metadata => Some("bin"),
+            Scope::Bin => None,
+            Scope::Context => Some(INHERITS_KEY),
+        }
+    }
+}
+
+/// Problem found in a configuration source, with its location in that source.
+#[derive(Debug)]
+pub struct ConfigIssue {
+    severity: Severity,
+    message: String,
+    source_code: NamedSource,
+    span: Option<SourceSpan>,
+    label: String,
+    help: Option<String>,
+}
+
+impl ConfigIssue {
+    /// Whether the issue prevents Cargo Lambda from loading the configuration.
+    /// Unknown keys are only warnings, because they are ignored.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ConfigIssue {}
+
+impl Diagnostic for ConfigIssue {
+    fn severity(&self) -> Option<Severity> {
+        Some(self.severity)
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn fmt::Display>)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source_code)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let span = self.span?;
+        let label = LabeledSpan::new_with_span(Some(self.label.clone()), span);
+        Some(Box::new(std::iter::once(label)))
+    }
+}
+
+/// Validate the Cargo Lambda configuration in the package and workspace metadata of a Cargo.toml file.
+pub fn validate_manifest(path: &Path, source: &str) -> Vec<ConfigIssue> {
+    let mut validator = Validator::new(path.display().to_string(), source);
+    let Some((doc, values)) = validator.parse() else {
+        return validator.issues;
+    };
+
+    for path in METADATA_TABLES {
+        let mut item = Some(doc.as_item());
+        let mut value = Some(&values);
+        for key in path {
+            item = item.and_then(|i| i.get(key));
+            value = value.and_then(|v| v.get(key)).and_then(|v| v.as_table());
+        }
+
+        if let (Some(table), Some(values)) = (item.and_then(|i| i.as_table_like()), value) {
+            validator.validate_lambda(table, values, Scope::Metadata, &PROJECT_SECTIONS);
+        }
+    }
+
+    validator.issues
+}
+
+/// Validate the global configuration file, including the sections for each context.
+pub fn validate_global_config(path: &Path, source: &str) -> Vec<ConfigIssue> {
+    let mut validator = Validator::new(path.display().to_string(), source);
+    let Some((doc, values)) = validator.parse() else {
+        return validator.issues;
+    };
+
+    let sections = [PROJECT_SECTIONS.as_slice(), GLOBAL_SECTIONS.as_slice()].concat();
+    let table = doc.as_table();
+    for (key, item) in table.iter() {
+        let Some(value) = values.get(key) else {
+            continue;
+        };
+        if sections.contains(&key) {
+            validator.validate_known_section(key, item, value);
+            continue;
+        }
+
+        // Any other table is a context, when it has sections in it, or it inherits other contexts.
+        let context = item.as_table_like().zip(value.as_table()).filter(|(t, _)| {
+            t.iter()
+                .any(|(k, _)| sections.contains(&k) || k == INHERITS_KEY)
+        });
+        match context {
+            Some((context, values)) => {
+                validator.validate_lambda(context, values, Scope::Context, &sections)
+            }
+            None => validator.unknown_key(None, key, key_span(table, key), sections.as_slice()),
+        }
+    }
+
+    validator.issues
+}
+
+/// Validate the environment variables that Cargo Lambda reads the configuration from,
+/// like `CARGO_LAMBDA_DEPLOY.MEMORY`.
+pub fn validate_env_vars<I>(vars: I) -> Vec<ConfigIssue>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut issues = Vec::new();
+    for (name, value) in vars {
+        let Some(path) = name.strip_prefix("CARGO_LAMBDA_") else {
+            continue;
+        };
+        // Variables without a dot are not configuration sections, like `CARGO_LAMBDA_COLOR`.
+        let path = path.to_lowercase();
+        let Some((section, key)) = path.split_once('.') else {
+            continue;
+        };
+
+        if section == "env" {
+            continue;
+        }
+
+        let source = format!("{name}={value}");
+        let span = Some(0..name.len());
+        let mut validator = Validator::new("environment".to_string(), &source);
+
+        let Some(rules) = SectionRules::new(section) else {
+            validator.unknown_key(None, section, span, &PROJECT_SECTIONS);
+            issues.extend(validator.issues);
+            continue;
+        };
+
+        // Nested keys, like `CARGO_LAMBDA_BUILD.COMPILER.TYPE`, are only checked by their first segment.
+        let (key, nested) = match key.split_once('.') {
+            Some((key, _)) => (key, true),
+            None => (key, false),
+        };
+
+        if !rules.keys.iter().any(|k| k == key) {
+            validator.unknown_key(Some(section), key, span, rules.keys.as_slice());
+        } else if !nested {
+            if let Err(err) = (rules.check)(key, parse_env_value(&value)) {
+                validator.error(
+                    format!("invalid value for `{section}.{key}`"),
+                    Some(name.len() + 1..source.len()),
+                    err.message(),
+                    None,
+                );
+            }
+        }
+        issues.extend(validator.issues);
+    }
+    issues
+}
+
+/// Figment parses the values of environment variables as TOML values,
+/// and it falls back to strings when they are not valid values.
+fn parse_env_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {value}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
+/// Keys that a section accepts, and the function that checks the type of their values.
+struct SectionRules {
+    keys: Vec<String>,
+    check: fn(&str, toml::Value) -> Result<(), toml::de::Error>,
+}
+
+impl SectionRules {
+    fn new(section: &str) -> Option<SectionRules> {
+        let rules = match section {
+            "build" => SectionRules {
+                keys: arg_keys::<Build>(&[]),
+                check: check_value::<Build>,
+            },
+            "deploy" => SectionRules {
+                keys: arg_keys::<Deploy>(&[
+                    "iam_role",
+                    "layers",
+                    "tags",
+                    "endpoints",
+                    "role_arn",
+                    "source_profile",
+                    "external_id",
+                ]),
+                check: check_value::<Deploy>,
+            },
+            "watch" => SectionRules {
+                keys: arg_keys::<Watch>(&["router", "watch-paths"]),
+                check: check_value::<Watch>,
+            },
+            "examples" => SectionRules {
+                keys: to_strings(&["url", "authorization"]),
+                check: check_value::<ExamplesConfig>,
+            },
+            "templates" => SectionRules {
+                keys: to_strings(&["function", "extension", "render_vars"]),
+                check: check_value::<TemplatesConfig>,
+            },
+            "updates" => SectionRules {
+                keys: to_strings(&["check"]),
+                check: check_value::<UpdatesConfig>,
+            },
+            _ => return None,
+        };
+        Some(rules)
+    }
+}
+
+/// The configuration keys are the same as the flags of the commands,
+/// plus the aliases that only the configuration files accept.
+fn arg_keys<T: clap::Args>(aliases: &[&str]) -> Vec<String> {
+    let cmd = T::augment_args(clap::Command::new("config"));
+    cmd.get_arguments()
+        .map(|arg| arg.get_id().as_str().to_string())
+        .chain(aliases.iter().map(|alias| alias.to_string()))
+        .collect()
+}
+
+fn to_strings(keys: &[&str]) -> Vec<String> {
+    keys.iter().map(|key| key.to_string()).collect()
+}
+
+/// Deserialize the section with only the given key, so errors point to that key.
+fn check_value<T: DeserializeOwned>(key: &str, value: toml::Value) -> Result<(), toml::de::Error> {
+    let table = toml::Table::from_iter([(key.to_string(), value)]);
+    toml::Value::Table(table).try_into::<T>().map(|_| ())
+}
+
+fn key_span(table: &dyn TableLike, key: &str) -> Option<Range<usize>> {
+    table.get_key_value(key).and_then(|(k, _)| k.span())
+}
+
+fn suggestion(key: &str, candidates: &[impl AsRef<str>]) -> Option<String> {
+    candidates
+        .iter()
+        .map(|candidate| {
+            let candidate = candidate.as_ref();
+            (strsim::jaro_winkler(key, candidate), candidate)
+        })
+        .filter(|(confidence, _)| *confidence > 0.8)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, candidate)| format!("did you mean `{candidate}`?"))
+}
+
+struct Validator<'a> {
+    name: String,
+    source: &'a str,
+    issues: Vec<ConfigIssue>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(name: String, source: &'a str) -> Self {
+        Validator {
+            name,
+            source,
+            issues: Vec::new(),
+        }
+    }
+
+    /// Parse the source twice, once to find the location of the keys,
+    /// and once to get the values that the configuration loader sees.
+    fn parse(&mut self) -> Option<(ImDocument<&'a str>, toml::Table)> {
+        let doc = match ImDocument::parse(self.source) {
+            Ok(doc) => doc,
+            Err(err) => {
+                let message = err.message().trim().to_string();
+                self.error(
+                    "invalid TOML syntax".to_string(),
+                    err.span(),
+                    &message,
+                    None,
+                );
+                return None;
+            }
+        };
+        let values = toml::from_str(self.source).ok()?;
+        Some((doc, values))
+    }
+
+    /// Validate a `lambda` table, or a context in the global configuration file.
+    fn validate_lambda(
+        &mut self,
+        table: &dyn TableLike,
+        values: &toml::Table,
+        scope: Scope,
+        sections: &[&str],
+    ) {
+        for (key, item) in table.iter() {
+            let Some(value) = values.get(key) else {
+                continue;
+            };
+
+            if sections.contains(&key) {
+                self.validate_known_section(key, item, value);
+            } else if key == "bin" && scope == Scope::Metadata {
+                let Some((bins, bin_values)) = item.as_table_like().zip(value.as_table()) else {
+                    self.expected_table(key, item);
+                    continue;
+                };
+                for (name, bin_item) in bins.iter() {
+                    let bin = bin_item
+                        .as_table_like()
+                        .zip(bin_values.get(name).and_then(|v| v.as_table()));
+                    match bin {
+                        Some((bin, values)) => {
+                            self.validate_lambda(bin, values, Scope::Bin, sections)
+                        }
+                        None => self.expected_table(&format!("bin.{name}"), bin_item),
+                    }
+                }
+            } else if key == INHERITS_KEY && scope == Scope::Context {
+                let is_context_list = |v: &toml::Value| match v {
+                    toml::Value::String(_) => true,
+                    toml::Value::Array(names) => names.iter().all(|n| n.is_str()),
+                    _ => false,
+                };
+                if !is_context_list(value) {
+                    self.error(
+                        format!("invalid value for `{key}`"),
+                        item.span(),
+                        "expected a context name, or a list of context names",
+                        None,
+                    );
+                }
+            } else {
+                let mut candidates = sections.to_vec();
+                candidates.extend(scope.extra_key());
+                self.unknown_key(None, key, key_span(table, key), candidates.as_slice());
+            }
+        }
+    }
+
+    fn validate_known_section(&mut self, section: &str, item: &Item, value: &toml::Value) {
+        let (Some(table), Some(values)) = (item.as_table_like(), value.as_table()) else {
+            self.expected_table(section, item);
+            return;
+        };
+
+        if section == "env" {
+            for (key, item) in table.iter() {
+                if !values.get(key).is_some_and(|v| v.is_str()) {
+                    self.error(
+                        format!("invalid value for `env.{key}`"),
+                        item.span(),
+                        "expected a string",
+                        None,
+                    );
+                }
+            }
+            return;
+        }
+
+        let Some(rules) = SectionRules::new(section) else {
+            return;
+        };
+
+        for (key, item) in table.iter() {
+            let span = key_span(table, key);
+            if !rules.keys.iter().any(|k| k == key) {
+                self.unknown_key(Some(section), key, span, rules.keys.as_slice());
+                continue;
+            }
+
+            let Some(value) = values.get(key) else {
+                continue;
+            };
+            if let Err(err) = (rules.check)(key, value.clone()) {
+                self.error(
+                    format!("invalid value for `{section}.{key}`"),
+                    item.span().or(span),
+                    err.message(),
+                    None,
+                );
+            }
+        }
+
+        let enabled = |key: &str| values.get(key).and_then(|v| v.as_bool()) == Some(true);
+        for (_, first, second) in CONFLICTS.iter().filter(|(s, _, _)| *s == section) {
+            if enabled(first) && enabled(second) {
+                self.error(
+                    format!("`{section}.{first}` and `{section}.{second}` cannot be used together"),
+                    key_span(table, second),
+                    &format!("conflicts with `{first}`"),
+                    Some(format!(
+                        "remove `{first}` or `{second}` from the configuration"
+                    )),
+                );
+            }
+        }
+    }
+
+    fn expected_table(&mut self, section: &str, item: &Item) {
+        self.error(
+            format!("invalid value for `{section}`"),
+            item.span(),
+            "expected a table",
+            None,
+        );
+    }
+
+    fn unknown_key(
+        &mut self,
+        section: Option<&str>,
+        key: &str,
+        span: Option<Range<usize>>,
+        candidates: &[impl AsRef<str>],
+    ) {
+        let (message, label) = match section {
+            Some(section) => (
+                format!("unknown key `{key}` in the `{section}` section"),
+                "this key is ignored",
+            ),
+            None => (
+                format!("unknown section `{key}`"),
+                "this section is ignored",
+            ),
+        };
+        self.push(
+            Severity::Warning,
+            message,
+            span,
+            label,
+            suggestion(key, candidates),
+        );
+    }
+
+    fn error(
+        &mut self,
+        message: String,
+        span: Option<Range<usize>>,
+        label: &str,
+        help: Option<String>,
+    ) {
+        self.push(Severity::Error, message, span, label, help);
+    }
+
+    fn push(
+        &mut self,
+        severity: Severity,
+        message: String,
+        span: Option<Range<usize>>,
+        label: &str,
+        help: Option<String>,
+    ) {
+        self.issues.push(ConfigIssue {
+            severity,
+            message,
+            source_code: NamedSource::new(&self.name, self.source.to_string()),
+            span: span.map(SourceSpan::from),
+            label: label.to_string(),
+            help,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "orders"
+version = "0.1.0"
+
+[package.metadata.lambda.deploy]
+memroy = 512
+tracing = "sometimes"
+
+[package.metadata.lambda.build]
+arm64 = true
+x86_64 = true
+
+[package.metadata.lambda.bin.orders.watch]
+invoke_port = 9001
+"#;
+
+    fn spanned<'a>(source: &'a str, issue: &ConfigIssue) -> &'a str {
+        let span = issue.span.unwrap();
+        &source[span.offset()..span.offset() + span.len()]
+    }
+
+    #[test]
+    fn test_validate_manifest() {
+        let issues = validate_manifest(Path::new("Cargo.toml"), MANIFEST);
+        assert_eq!(3, issues.len(), "{issues:?}");
+
+        let unknown = &issues[0];
+        assert!(!unknown.is_error());
+        assert_eq!(
+            "unknown key `memroy` in the `deploy` section",
+            unknown.message
+        );
+        assert_eq!("memroy", spanned(MANIFEST, unknown));
+        assert_eq!(Some("did you mean `memory`?"), unknown.help.as_deref());
+
+        let invalid = &issues[1];
+        assert!(invalid.is_error());
+        assert_eq!("invalid value for `deploy.tracing`", invalid.message);
+        assert_eq!("\"sometimes\"", spanned(MANIFEST, invalid).trim());
+
+        let conflict = &issues[2];
+        assert!(conflict.is_error());
+        assert_eq!(
+            "`build.arm64` and `build.x86_64` cannot be used together",
+            conflict.message
+        );
+        assert_eq!("x86_64", spanned(MANIFEST, conflict));
+    }
+
+    #[test]
+    fn test_validate_workspace_bin_config() {
+        let source = r#"[workspace]
+members = ["orders"]
+
+[workspace.metadata.lambda.bin.orders]
+deploy = { layers = ["arn:aws:lambda:us-east-1:xxxxxxxx:layers:layer1"] }
+wtach = { invoke_port = 9001 }
+"#;
+        let issues = validate_manifest(Path::new("Cargo.toml"), source);
+        assert_eq!(1, issues.len(), "{issues:?}");
+        assert_eq!("unknown section `wtach`", issues[0].message);
+        assert_eq!(Some("did you mean `watch`?"), issues[0].help.as_deref());
+    }
+
+    #[test]
+    fn test_validate_invalid_syntax() {
+        let source = "[package.metadata.lambda.deploy]\nmemory = \n";
+        let issues = validate_manifest(Path::new("Cargo.toml"), source);
+        assert_eq!(1, issues.len());
+        assert!(issues[0].is_error());
+        assert_eq!("invalid TOML syntax", issues[0].message);
+        assert!(issues[0].span.is_some());
+    }
+
+    #[test]
+    fn test_validate_global_config() {
+        let source = r#"[updates]
+check = "yes"
+
+[env]
+PORT = 8080
+
+[production]
+inherits = "base"
+
+[base.deploy]
+memory = 512
+
+[staging]
+inherits = 1
+
+[production.deploy]
+timeout = 60
+enable_function_url = true
+disable_function_url = true
+
+[colors]
+always = true
+"#;
+        let issues = validate_global_config(Path::new("CargoLambda.toml"), source);
+        let messages = issues
+            .iter()
+            .map(|i| i.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "invalid value for `updates.check`",
+                "invalid value for `env.PORT`",
+                "`deploy.enable_function_url` and `deploy.disable_function_url` cannot be used together",
+                "invalid value for `inherits`",
+                "unknown section `colors`",
+            ],
+            messages
+        );
+        assert_eq!("8080", spanned(source, &issues[1]).trim());
+    }
+
+    #[test]
+    fn test_validate_env_vars() {
+        let vars = [
+            ("CARGO_LAMBDA_COLOR", "always"),
+            ("CARGO_LAMBDA_ENV.FOO", "BAR"),
+            ("CARGO_LAMBDA_DEPLOY.MEMORY", "512"),
+            ("CARGO_LAMBDA_DEPLOY.TRACING", "sometimes"),
+            ("CARGO_LAMBDA_WATCH.INVOKE_PROT", "9001"),
+            ("CARGO_LAMBDA_BUILD.COMPILER.TYPE", "cargo"),
+            ("PATH", "/usr/bin"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        let issues = validate_env_vars(vars);
+        assert_eq!(2, issues.len(), "{issues:?}");
+        assert!(issues[0].is_error());
+        assert_eq!("invalid value for `deploy.tracing`", issues[0].message);
+        assert!(!issues[1].is_error());
+        assert_eq!(
+            Some("did you mean `invoke_port`?"),
+            issues[1].help.as_deref()
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use cargo_lambda_interactive::{error::InquireError, Select};
+use liquid::Object;
+
+/// Architecture that CI workflows and infrastructure templates compile and deploy the function for.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, strum_macros::Display, strum_macros::EnumString,
+)]
+#[strum(ascii_case_insensitive)]
+pub(crate) enum Architecture {
+    #[default]
+    #[strum(to_string = "x86_64", serialize = "x86-64")]
+    X86_64,
+    #[strum(to_string = "arm64", serialize = "aarch64")]
+    Arm64,
+}
+
+impl Architecture {
+    pub(crate) fn ask() -> Result<Architecture, InquireError> {
+        Select::new(
+            "Which architecture would you like to compile the function for?",
+            vec![Architecture::X86_64, Architecture::Arm64],
+        )
+        .prompt()
+    }
+
+    /// Flag for `cargo lambda build` that compiles the function for this architecture.
+    fn build_flag(&self) -> &'static str {
+        match self {
+            Architecture::X86_64 => "--x86-64",
+            Architecture::Arm64 => "--arm64",
+        }
+    }
+
+    /// Variables that CI workflows and infrastructure templates use to build and deploy the function.
+    pub(crate) fn variables(&self, package_name: &str, binary_name: &Option<String>) -> Object {
+        let function_name = binary_name.as_deref().unwrap_or(package_name);
+
+        liquid::object!({
+            "architecture": self.to_string(),
+            "build_flag": self.build_flag(),
+            "lambda_function_name": function_name,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use liquid::model::Value;
+
+    #[test]
+    fn test_parse_architecture() {
+        assert_eq!(Architecture::Arm64, "arm64".parse().unwrap());
+        assert_eq!(Architecture::Arm64, "AArch64".parse().unwrap());
+        assert_eq!(Architecture::X86_64, "x86-64".parse().unwrap());
+        assert_eq!("x86_64", Architecture::X86_64.to_string());
+    }
+
+    #[test]
+    fn test_architecture_variables() {
+        let variables = Architecture::Arm64.variables("basic-lambda", &Some("handler".into()));
+        assert_eq!(Some(&Value::scalar("arm64")), variables.get("architecture"));
+        assert_eq!(Some(&Value::scalar("--arm64")), variables.get("build_flag"));
+        assert_eq!(
+            Some(&Value::scalar("handler")),
+            variables.get("lambda_function_name")
+        );
+    }
+}
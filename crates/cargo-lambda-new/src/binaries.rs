@@ -0,0 +1,247 @@
+use clap::Args;
+use liquid::{
+    model::{Value, ValueView},
+    Object,
+};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use regex::Regex;
+use std::{
+    fs::{copy, create_dir_all, read_to_string, remove_dir_all, remove_file, write},
+    path::Path,
+};
+use walkdir::WalkDir;
+
+#[derive(Args, Clone, Debug, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    /// Name of a binary to create in the package. Repeat the flag to create a package with several functions
+    #[arg(long = "bin", value_name = "NAME", conflicts_with_all = ["extension", "bin_name"])]
+    bins: Option<Vec<String>>,
+}
+
+impl Options {
+    /// Whether the package includes several binaries.
+    pub(crate) fn enabled(&self) -> bool {
+        !self.names().is_empty()
+    }
+
+    pub(crate) fn names(&self) -> &[String] {
+        self.bins.as_deref().unwrap_or_default()
+    }
+
+    pub(crate) fn variables(&self) -> Object {
+        liquid::object!({
+            "binary_names": self.names(),
+        })
+    }
+
+    /// Restore the binaries recorded when the project was created, unless they're set in the command line.
+    pub(crate) fn restore(&mut self, variables: &Object) {
+        if self.bins.is_some() {
+            return;
+        }
+
+        if let Some(Value::Array(names)) = variables.get("binary_names") {
+            if !names.is_empty() {
+                self.bins = Some(names.iter().map(|n| n.to_kstr().to_string()).collect());
+            }
+        }
+    }
+
+    /// Turn the rendered `src/main.rs` into one binary for each name, with a copy of
+    /// the handler modules that it declares, and add a `[[bin]]` entry for each one to Cargo.toml.
+    /// HTTP functions also get a route for each binary in the watch router.
+    pub(crate) fn render_binaries(&self, project_path: &Path, globals: &Object) -> Result<()> {
+        if !self.enabled() {
+            return Ok(());
+        }
+
+        if !move_main_to_bins(&project_path.join("src"), self.names())? {
+            tracing::warn!(
+                "the template doesn't include a src/main.rs file, binaries are not created"
+            );
+            return Ok(());
+        }
+
+        let manifest_path = project_path.join("Cargo.toml");
+        if !manifest_path.is_file() {
+            return Ok(());
+        }
+
+        let manifest = read_to_string(&manifest_path).into_diagnostic()?;
+        let parsed: toml::Table = toml::from_str(&manifest)
+            .into_diagnostic()
+            .wrap_err("invalid Cargo.toml in the template")?;
+
+        let has_router = parsed
+            .get("package")
+            .and_then(|p| p.get("metadata"))
+            .and_then(|m| m.get("lambda"))
+            .and_then(|l| l.get("watch"))
+            .is_some_and(|w| w.get("router").is_some());
+
+        // The template's routes take precedence over the generated ones.
+        let http = !has_router
+            && globals
+                .get("http_function")
+                .and_then(|v| v.as_scalar())
+                .and_then(|v| v.to_bool())
+                .unwrap_or_default();
+
+        let manifest = format!("{}\n\n{}", manifest.trim_end(), self.manifest_toml(http));
+        write(&manifest_path, manifest)
+            .into_diagnostic()
+            .wrap_err("failed to add the binaries to Cargo.toml")
+    }
+
+    fn manifest_toml(&self, http: bool) -> String {
+        let mut toml = String::new();
+        for name in self.names() {
+            toml.push_str(&format!(
+                "[[bin]]\nname = \"{name}\"\npath = \"src/bin/{name}/main.rs\"\n\n"
+            ));
+        }
+
+        if http {
+            toml.push_str("[package.metadata.lambda.watch.router]\n");
+            for name in self.names() {
+                toml.push_str(&format!("\"/{name}\" = \"{name}\"\n"));
+            }
+        }
+
+        format!("{}\n", toml.trim_end())
+    }
+}
+
+/// Move `src/main.rs`, and the handler modules that it declares, to `src/bin/<NAME>/`
+/// for each name. It returns false if the project doesn't have a `src/main.rs` file.
+pub(crate) fn move_main_to_bins(src: &Path, names: &[String]) -> Result<bool> {
+    let main_path = src.join("main.rs");
+    if !main_path.is_file() {
+        return Ok(false);
+    }
+
+    let main = read_to_string(&main_path).into_diagnostic()?;
+    let modules = declared_modules(&main)?;
+
+    for name in names {
+        let bin_path = src.join("bin").join(name);
+        create_dir_all(&bin_path).into_diagnostic()?;
+        write(bin_path.join("main.rs"), &main).into_diagnostic()?;
+
+        for module in &modules {
+            copy_module(src, &bin_path, module)?;
+        }
+    }
+
+    remove_file(&main_path).into_diagnostic()?;
+    for module in &modules {
+        let _ = remove_file(src.join(format!("{module}.rs")));
+        let _ = remove_dir_all(src.join(module));
+    }
+
+    Ok(true)
+}
+
+/// Modules declared in a file with `mod name;`.
+fn declared_modules(source: &str) -> Result<Vec<String>> {
+    let re = Regex::new(r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+(\w+)\s*;").into_diagnostic()?;
+    Ok(re.captures_iter(source).map(|c| c[1].to_string()).collect())
+}
+
+/// Copy a module, either `name.rs`, or the `name` directory, from `src` to `dest`.
+fn copy_module(src: &Path, dest: &Path, module: &str) -> Result<()> {
+    let file = src.join(format!("{module}.rs"));
+    if file.is_file() {
+        copy(&file, dest.join(format!("{module}.rs"))).into_diagnostic()?;
+    }
+
+    let dir = src.join(module);
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    for entry in WalkDir::new(&dir) {
+        let entry = entry.into_diagnostic()?;
+        let relative = entry.path().strip_prefix(src).into_diagnostic()?;
+        let target = dest.join(relative);
+        if entry.file_type().is_dir() {
+            create_dir_all(&target).into_diagnostic()?;
+        } else {
+            copy(entry.path(), &target).into_diagnostic()?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_declared_modules() {
+        let source = "mod http_handler;\npub(crate) mod events;\nmod tests {\n}\n// mod comment;\n";
+        assert_eq!(
+            vec!["http_handler", "events"],
+            declared_modules(source).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_render_binaries() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path();
+        create_dir_all(project.join("src")).unwrap();
+        write(
+            project.join("Cargo.toml"),
+            "[package]\nname = \"my-api\"\n\n[dependencies]\nlambda_http = \"0.13\"\n",
+        )
+        .unwrap();
+        write(
+            project.join("src/main.rs"),
+            "mod http_handler;\nfn main() {}\n",
+        )
+        .unwrap();
+        write(project.join("src/http_handler.rs"), "pub fn handler() {}\n").unwrap();
+
+        let options = Options {
+            bins: Some(vec!["get-users".into(), "create-user".into()]),
+        };
+        let globals = liquid::object!({ "http_function": true });
+        options.render_binaries(project, &globals).unwrap();
+
+        for name in ["get-users", "create-user"] {
+            let bin = project.join("src/bin").join(name);
+            assert!(bin.join("main.rs").is_file());
+            assert!(bin.join("http_handler.rs").is_file());
+        }
+        assert!(!project.join("src/main.rs").exists());
+        assert!(!project.join("src/http_handler.rs").exists());
+
+        let manifest: toml::Table =
+            toml::from_str(&read_to_string(project.join("Cargo.toml")).unwrap()).unwrap();
+        let bins = manifest["bin"].as_array().unwrap();
+        assert_eq!(2, bins.len());
+        assert_eq!("get-users", bins[0]["name"].as_str().unwrap());
+        assert_eq!(
+            "src/bin/create-user/main.rs",
+            bins[1]["path"].as_str().unwrap()
+        );
+
+        let router = &manifest["package"]["metadata"]["lambda"]["watch"]["router"];
+        assert_eq!("get-users", router["/get-users"].as_str().unwrap());
+        assert_eq!("create-user", router["/create-user"].as_str().unwrap());
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut options = Options::default();
+        options.restore(&liquid::object!({ "binary_names": ["get-users", "create-user"] }));
+        assert_eq!(vec!["get-users", "create-user"], options.names());
+
+        let mut options = Options::default();
+        options.restore(&liquid::object!({ "binary_names": [] }));
+        assert!(!options.enabled());
+    }
+}
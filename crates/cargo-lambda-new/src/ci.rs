@@ -0,0 +1,154 @@
+use cargo_lambda_interactive::{error::InquireError, is_stdin_tty, Select};
+use clap::Args;
+use liquid::{model::ValueView, Object, Parser};
+use miette::Result;
+use std::path::Path;
+
+const GITHUB_WORKFLOW: &str = include_str!("../templates/ci/github.yml");
+const GITLAB_PIPELINE: &str = include_str!("../templates/ci/gitlab.yml");
+
+#[derive(Args, Clone, Debug, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    /// CI provider to generate build and deploy workflows for: github, gitlab, or none
+    #[arg(long, conflicts_with = "extension")]
+    ci: Option<CiProvider>,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, strum_macros::Display, strum_macros::EnumString,
+)]
+#[strum(ascii_case_insensitive, serialize_all = "snake_case")]
+pub(crate) enum CiProvider {
+    Github,
+    Gitlab,
+    #[default]
+    None,
+}
+
+impl CiProvider {
+    /// Workflow files that the provider uses, with their path in the project.
+    fn workflows(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            CiProvider::Github => &[(".github/workflows/lambda.yml", GITHUB_WORKFLOW)],
+            CiProvider::Gitlab => &[(".gitlab-ci.yml", GITLAB_PIPELINE)],
+            CiProvider::None => &[],
+        }
+    }
+}
+
+impl Options {
+    pub(crate) fn validate_options(&mut self, no_interactive: bool) -> Result<(), InquireError> {
+        if no_interactive || !is_stdin_tty() || self.ci.is_some() {
+            return Ok(());
+        }
+
+        let provider = Select::new(
+            "Would you like to add build and deploy workflows for a CI provider?",
+            vec![CiProvider::None, CiProvider::Github, CiProvider::Gitlab],
+        )
+        .prompt()?;
+        self.ci = Some(provider);
+
+        Ok(())
+    }
+
+    /// Whether the project includes CI workflows.
+    pub(crate) fn enabled(&self) -> bool {
+        self.provider() != CiProvider::None
+    }
+
+    pub(crate) fn variables(&self) -> Object {
+        liquid::object!({
+            "ci_provider": self.provider().to_string(),
+        })
+    }
+
+    /// Restore the option recorded when the project was created, unless it's set in the command line.
+    pub(crate) fn restore(&mut self, variables: &Object) {
+        if self.ci.is_none() {
+            self.ci = variables
+                .get("ci_provider")
+                .and_then(|v| v.to_kstr().parse().ok());
+        }
+    }
+
+    fn provider(&self) -> CiProvider {
+        self.ci.unwrap_or_default()
+    }
+
+    /// Render the workflow files for the CI provider in the project.
+    /// Files that the template already includes are not replaced.
+    pub(crate) fn render_workflows(
+        &self,
+        project_path: &Path,
+        parser: &Parser,
+        globals: &Object,
+    ) -> Result<()> {
+        crate::render_embedded_files(project_path, parser, globals, self.provider().workflows())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::architecture::Architecture;
+    use liquid::ParserBuilder;
+    use std::fs::read_to_string;
+
+    fn render(options: &Options, arch: Architecture, project_path: &Path) {
+        let parser = ParserBuilder::with_stdlib().build().unwrap();
+        let mut globals = options.variables();
+        globals.extend(arch.variables("basic-lambda", &None));
+        options
+            .render_workflows(project_path, &parser, &globals)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(CiProvider::Github, "GitHub".parse().unwrap());
+        assert_eq!(CiProvider::None, "none".parse().unwrap());
+    }
+
+    #[test]
+    fn test_render_github_workflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = Options {
+            ci: Some(CiProvider::Github),
+        };
+        render(&options, Architecture::Arm64, dir.path());
+
+        let workflow = read_to_string(dir.path().join(".github/workflows/lambda.yml")).unwrap();
+        assert!(workflow.contains("cargo lambda build --release --arm64"));
+        assert!(workflow.contains("cargo lambda deploy basic-lambda"));
+        assert!(workflow.contains("role-to-assume: ${{ secrets.AWS_ROLE_ARN }}"));
+        assert!(!dir.path().join(".gitlab-ci.yml").exists());
+    }
+
+    #[test]
+    fn test_render_gitlab_pipeline() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = Options {
+            ci: Some(CiProvider::Gitlab),
+        };
+        render(&options, Architecture::X86_64, dir.path());
+
+        let pipeline = read_to_string(dir.path().join(".gitlab-ci.yml")).unwrap();
+        assert!(pipeline.contains("cargo lambda build --release --x86-64"));
+    }
+
+    #[test]
+    fn test_keep_template_workflows() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".gitlab-ci.yml"), "custom").unwrap();
+
+        let options = Options {
+            ci: Some(CiProvider::Gitlab),
+        };
+        render(&options, Architecture::X86_64, dir.path());
+
+        let pipeline = read_to_string(dir.path().join(".gitlab-ci.yml")).unwrap();
+        assert_eq!("custom", pipeline);
+    }
+}
@@ -0,0 +1,388 @@
+use cargo_lambda_interactive::{error::InquireError, is_stdin_tty, Confirm, CustomType, Select};
+use clap::{Args, Subcommand, ValueHint};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::{
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use crate::{architecture::Architecture, config_validate::ConfigValidate, error::CreateError};
+
+pub(crate) const GLOBAL_CONFIG_FILE: &str = "CargoLambda.toml";
+
+const MEMORY_OPTIONS: [u32; 6] = [128, 256, 512, 1024, 2048, 3008];
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "config",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/config.html"
+)]
+pub struct ConfigCommand {
+    #[command(subcommand)]
+    command: ConfigSubcommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum ConfigSubcommand {
+    /// Generate the build, deploy, and watch configuration, and write it into the package's manifest
+    Init(ConfigInit),
+    /// Check the configuration in the manifests, the global configuration file, and the environment,
+    /// and report unknown keys, invalid values, and conflicting options
+    Validate(ConfigValidate),
+}
+
+#[derive(Args, Clone, Debug)]
+struct ConfigInit {
+    /// Path to Cargo.toml
+    #[arg(long, value_name = "PATH", default_value = "Cargo.toml", value_hint = ValueHint::FilePath)]
+    manifest_path: PathBuf,
+
+    /// Write the configuration into a CargoLambda.toml file next to the manifest, instead of the manifest's metadata
+    #[arg(long)]
+    global_config: bool,
+
+    /// Replace the configuration if it already exists
+    #[arg(long)]
+    force: bool,
+
+    /// Don't ask questions, and write the default configuration
+    #[arg(long)]
+    no_interactive: bool,
+}
+
+/// Compilers that `cargo lambda build` can use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, strum_macros::Display)]
+enum Compiler {
+    #[default]
+    #[strum(to_string = "cargo-zigbuild")]
+    CargoZigbuild,
+    #[strum(to_string = "cargo")]
+    Cargo,
+    #[strum(to_string = "cross")]
+    Cross,
+}
+
+/// Settings that the prompts ask for.
+#[derive(Debug, PartialEq)]
+struct Answers {
+    architecture: Architecture,
+    compiler: Compiler,
+    memory: u32,
+    timeout: u32,
+    tracing: bool,
+    invoke_port: u16,
+}
+
+impl Default for Answers {
+    fn default() -> Self {
+        Answers {
+            architecture: Architecture::default(),
+            compiler: Compiler::default(),
+            memory: 128,
+            timeout: 30,
+            tracing: false,
+            invoke_port: 9000,
+        }
+    }
+}
+
+impl ConfigCommand {
+    #[tracing::instrument(skip(self), target = "cargo_lambda")]
+    pub async fn run(&self, global: Option<PathBuf>) -> Result<()> {
+        match &self.command {
+            ConfigSubcommand::Init(init) => init.run(),
+            ConfigSubcommand::Validate(validate) => validate.run(global.as_deref()),
+        }
+    }
+}
+
+impl ConfigInit {
+    fn run(&self) -> Result<()> {
+        if !self.manifest_path.is_file() {
+            return Err(miette::miette!(
+                "the manifest {} doesn't exist",
+                self.manifest_path.display()
+            ));
+        }
+
+        let answers = if self.no_interactive || !is_stdin_tty() {
+            Answers::default()
+        } else {
+            Answers::ask().map_err(CreateError::from)?
+        };
+
+        let path = if self.global_config {
+            self.write_global_config(&answers)?
+        } else {
+            self.write_metadata(&answers)?
+        };
+
+        println!("✅ configuration written to {}", path.display());
+        Ok(())
+    }
+
+    fn write_global_config(&self, answers: &Answers) -> Result<PathBuf> {
+        let dir = self
+            .manifest_path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        let path = dir.join(GLOBAL_CONFIG_FILE);
+
+        if path.exists() && !self.force {
+            return Err(CreateError::ConfigAlreadyExists(path).into());
+        }
+
+        std::fs::write(&path, render(answers, ""))
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    fn write_metadata(&self, answers: &Answers) -> Result<PathBuf> {
+        let path = &self.manifest_path;
+        let manifest = std::fs::read_to_string(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+
+        let prefix = metadata_prefix(&manifest);
+        if has_tables(&manifest, prefix) && !self.force {
+            return Err(CreateError::ConfigAlreadyExists(path.clone()).into());
+        }
+
+        let manifest = remove_tables(&manifest, prefix);
+        let manifest = format!(
+            "{}\n\n{}",
+            manifest.trim_end(),
+            render(answers, &format!("{prefix}."))
+        );
+
+        std::fs::write(path, manifest)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+        Ok(path.clone())
+    }
+}
+
+impl Answers {
+    fn ask() -> Result<Answers, InquireError> {
+        let defaults = Answers::default();
+
+        let architecture = Architecture::ask()?;
+        let compiler = Select::new(
+            "Which compiler would you like to use?",
+            vec![Compiler::CargoZigbuild, Compiler::Cargo, Compiler::Cross],
+        )
+        .with_help_message("cargo-zigbuild cross compiles from any OS, cargo only works on Linux, cross needs Docker")
+        .prompt()?;
+
+        let memory = Select::new(
+            "How much memory, in MB, would you like to give the function?",
+            MEMORY_OPTIONS.to_vec(),
+        )
+        .prompt()?;
+        let timeout = CustomType::<u32>::new("What is the function's timeout, in seconds?")
+            .with_default(defaults.timeout)
+            .with_error_message("the timeout must be a number of seconds")
+            .prompt()?;
+        let tracing = Confirm::new("Would you like to enable X-Ray tracing?")
+            .with_default(defaults.tracing)
+            .prompt()?;
+
+        let invoke_port =
+            CustomType::<u16>::new("Which port would you like `cargo lambda watch` to listen on?")
+                .with_default(defaults.invoke_port)
+                .with_error_message("the port must be a number between 0 and 65535")
+                .prompt()?;
+
+        Ok(Answers {
+            architecture,
+            compiler,
+            memory,
+            timeout,
+            tracing,
+            invoke_port,
+        })
+    }
+}
+
+/// Render the configuration, with the tables prefixed by `prefix`,
+/// like `package.metadata.lambda.`, or without prefix for the global configuration.
+fn render(answers: &Answers, prefix: &str) -> String {
+    let mut output = String::new();
+
+    let _ = writeln!(output, "[{prefix}build]");
+    let arch_key = match answers.architecture {
+        Architecture::X86_64 => "x86_64",
+        Architecture::Arm64 => "arm64",
+    };
+    let _ = writeln!(output, "{arch_key} = true");
+    match answers.compiler {
+        Compiler::CargoZigbuild => {}
+        Compiler::Cargo => {
+            let _ = writeln!(output, "compiler = {{ type = \"cargo\" }}");
+        }
+        Compiler::Cross => {
+            let _ = writeln!(output, "compiler = {{ type = \"cross\" }}");
+        }
+    }
+
+    let _ = writeln!(output, "\n[{prefix}deploy]");
+    let _ = writeln!(output, "memory = {}", answers.memory);
+    let _ = writeln!(output, "timeout = {}", answers.timeout);
+    if answers.tracing {
+        let _ = writeln!(output, "tracing = \"active\"");
+    }
+
+    let _ = writeln!(output, "\n[{prefix}watch]");
+    let _ = writeln!(output, "invoke_port = {}", answers.invoke_port);
+
+    output
+}
+
+/// Virtual workspaces keep the configuration in the workspace's metadata.
+fn metadata_prefix(manifest: &str) -> &'static str {
+    let has_table = |name: &str| manifest.lines().any(|l| l.trim() == name);
+    if !has_table("[package]") && has_table("[workspace]") {
+        "workspace.metadata.lambda"
+    } else {
+        "package.metadata.lambda"
+    }
+}
+
+fn is_table_header(line: &str, prefix: &str) -> bool {
+    let Some(name) = line.trim().strip_prefix('[') else {
+        return false;
+    };
+    let name = name.trim_start_matches('[').trim_start();
+    name.strip_prefix(prefix)
+        .is_some_and(|rest| rest.starts_with('.') || rest.starts_with(']'))
+}
+
+fn has_tables(manifest: &str, prefix: &str) -> bool {
+    manifest.lines().any(|l| is_table_header(l, prefix))
+}
+
+/// Remove the tables that start with the prefix, and their keys.
+fn remove_tables(manifest: &str, prefix: &str) -> String {
+    let mut output = String::new();
+    let mut skipping = false;
+    for line in manifest.lines() {
+        if line.trim_start().starts_with('[') {
+            skipping = is_table_header(line, prefix);
+        }
+        if !skipping {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "orders"
+version = "0.1.0"
+
+[dependencies]
+lambda_runtime = "0.13"
+"#;
+
+    #[test]
+    fn test_render_defaults() {
+        let expected = r#"[build]
+x86_64 = true
+
+[deploy]
+memory = 128
+timeout = 30
+
+[watch]
+invoke_port = 9000
+"#;
+        assert_eq!(expected, render(&Answers::default(), ""));
+    }
+
+    #[test]
+    fn test_render_metadata() {
+        let answers = Answers {
+            architecture: Architecture::Arm64,
+            compiler: Compiler::Cross,
+            memory: 512,
+            tracing: true,
+            ..Default::default()
+        };
+        let rendered = render(&answers, "package.metadata.lambda.");
+
+        let value: toml::Value = toml::from_str(&rendered).unwrap();
+        let lambda = &value["package"]["metadata"]["lambda"];
+        assert_eq!(Some(true), lambda["build"]["arm64"].as_bool());
+        assert_eq!(Some("cross"), lambda["build"]["compiler"]["type"].as_str());
+        assert_eq!(Some(512), lambda["deploy"]["memory"].as_integer());
+        assert_eq!(Some("active"), lambda["deploy"]["tracing"].as_str());
+        assert_eq!(Some(9000), lambda["watch"]["invoke_port"].as_integer());
+    }
+
+    #[test]
+    fn test_metadata_prefix() {
+        assert_eq!("package.metadata.lambda", metadata_prefix(MANIFEST));
+        assert_eq!(
+            "workspace.metadata.lambda",
+            metadata_prefix("[workspace]\nmembers = [\"orders\"]\n")
+        );
+    }
+
+    #[test]
+    fn test_remove_tables() {
+        let manifest = format!(
+            "{MANIFEST}\n[package.metadata.lambda.deploy]\nmemory = 256\n\n[package.metadata.lambda.build.compiler]\ntype = \"cargo\"\n\n[package.metadata.docs]\nall-features = true\n"
+        );
+        assert!(has_tables(&manifest, "package.metadata.lambda"));
+
+        let cleaned = remove_tables(&manifest, "package.metadata.lambda");
+        assert!(!has_tables(&cleaned, "package.metadata.lambda"));
+        assert!(cleaned.contains("[package.metadata.docs]\nall-features = true"));
+        assert!(cleaned.contains("lambda_runtime = \"0.13\""));
+        assert!(!has_tables(MANIFEST, "package.metadata.lambda"));
+        assert!(!is_table_header(
+            "[package.metadata.lambdas]",
+            "package.metadata.lambda"
+        ));
+    }
+
+    #[test]
+    fn test_write_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = dir.path().join("Cargo.toml");
+        std::fs::write(&manifest_path, MANIFEST).unwrap();
+
+        let init = ConfigInit {
+            manifest_path: manifest_path.clone(),
+            global_config: false,
+            force: false,
+            no_interactive: true,
+        };
+        init.write_metadata(&Answers::default()).unwrap();
+
+        let manifest = std::fs::read_to_string(&manifest_path).unwrap();
+        assert!(manifest.starts_with(MANIFEST));
+        assert!(manifest.contains("[package.metadata.lambda.deploy]\nmemory = 128"));
+        assert!(init.write_metadata(&Answers::default()).is_err());
+
+        let init = ConfigInit {
+            force: true,
+            ..init
+        };
+        let answers = Answers {
+            memory: 1024,
+            ..Default::default()
+        };
+        init.write_metadata(&answers).unwrap();
+        let manifest = std::fs::read_to_string(&manifest_path).unwrap();
+        assert!(manifest.contains("memory = 1024"));
+        assert!(!manifest.contains("memory = 128"));
+    }
+}
@@ -0,0 +1,87 @@
+use cargo_lambda_metadata::{
+    cargo::load_metadata,
+    config::user_config_path,
+    validate::{validate_env_vars, validate_global_config, validate_manifest, ConfigIssue},
+};
+use clap::{Args, ValueHint};
+use miette::{IntoDiagnostic, Report, Result, WrapErr};
+use std::path::{Path, PathBuf};
+
+use crate::config_init::GLOBAL_CONFIG_FILE;
+
+#[derive(Args, Clone, Debug)]
+pub(crate) struct ConfigValidate {
+    /// Path to Cargo.toml
+    #[arg(long, value_name = "PATH", default_value = "Cargo.toml", value_hint = ValueHint::FilePath)]
+    manifest_path: PathBuf,
+}
+
+impl ConfigValidate {
+    pub(crate) fn run(&self, global: Option<&Path>) -> Result<()> {
+        let metadata = load_metadata(&self.manifest_path)?;
+
+        let mut manifests = vec![metadata
+            .workspace_root
+            .join("Cargo.toml")
+            .into_std_path_buf()];
+        for package in metadata.workspace_packages() {
+            let path = package.manifest_path.clone().into_std_path_buf();
+            if !manifests.contains(&path) {
+                manifests.push(path);
+            }
+        }
+
+        let mut issues = Vec::new();
+        for path in &manifests {
+            let source = read_source(path)?;
+            issues.extend(validate_manifest(path, &source));
+        }
+
+        // The global file is optional, unless the flag `--global` points to it.
+        let global_path = global.unwrap_or_else(|| Path::new(GLOBAL_CONFIG_FILE));
+        let mut sources = manifests.len();
+        if global.is_some() || global_path.is_file() {
+            let source = read_source(global_path)?;
+            issues.extend(validate_global_config(global_path, &source));
+            sources += 1;
+        }
+
+        if let Some(user_path) = user_config_path().filter(|p| p.is_file()) {
+            let source = read_source(&user_path)?;
+            issues.extend(validate_global_config(&user_path, &source));
+            sources += 1;
+        }
+
+        issues.extend(validate_env_vars(std::env::vars()));
+
+        report(issues, sources)
+    }
+}
+
+fn read_source(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to read {}", path.display()))
+}
+
+fn report(issues: Vec<ConfigIssue>, sources: usize) -> Result<()> {
+    let errors = issues.iter().filter(|issue| issue.is_error()).count();
+    let warnings = issues.len() - errors;
+
+    for issue in issues {
+        eprintln!("{:?}", Report::new(issue));
+    }
+
+    if errors > 0 {
+        return Err(miette::miette!(
+            "the configuration has {errors} errors and {warnings} warnings"
+        ));
+    }
+
+    if warnings > 0 {
+        println!("⚠️  the configuration is valid, but it has {warnings} warnings");
+    } else {
+        println!("✅ the configuration in {sources} files and the environment is valid");
+    }
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use chrono::Datelike;
+use liquid::Object;
+use std::process::Command;
+
+/// Edition for toolchains that don't support the 2024 edition.
+const DEFAULT_RUST_EDITION: &str = "2021";
+
+/// Variables resolved from the user's environment when the template is rendered:
+/// the git author, the current year, the Rust edition that the toolchain supports,
+/// and the version of Cargo Lambda that renders the template.
+pub(crate) fn variables() -> Object {
+    let rust_edition = command_output("rustc", &["--version"])
+        .and_then(|version| rust_edition(&version))
+        .unwrap_or(DEFAULT_RUST_EDITION);
+
+    liquid::object!({
+        "git_user_name": command_output("git", &["config", "--get", "user.name"]),
+        "git_user_email": command_output("git", &["config", "--get", "user.email"]),
+        "current_year": chrono::Utc::now().year(),
+        "rust_edition": rust_edition,
+        "cargo_lambda_version": env!("CARGO_PKG_VERSION"),
+    })
+}
+
+/// Trimmed output of a command, or None if the command fails or doesn't print anything.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let output = String::from_utf8(output.stdout).ok()?;
+    let output = output.trim();
+    (!output.is_empty()).then(|| output.to_string())
+}
+
+/// Latest edition that a `rustc --version` output supports.
+fn rust_edition(version: &str) -> Option<&'static str> {
+    let version = version.split_whitespace().nth(1)?;
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse::<u32>().ok()?;
+    let minor = parts.next()?.parse::<u32>().ok()?;
+
+    if major > 1 || minor >= 85 {
+        Some("2024")
+    } else {
+        Some(DEFAULT_RUST_EDITION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use liquid::model::ValueView;
+
+    #[test]
+    fn test_rust_edition() {
+        assert_eq!(
+            Some("2024"),
+            rust_edition("rustc 1.85.0 (4d91de4e4 2025-02-17)")
+        );
+        assert_eq!(
+            Some("2021"),
+            rust_edition("rustc 1.80.1 (3f5fd8dd4 2024-08-06)")
+        );
+        assert_eq!(Some("2024"), rust_edition("rustc 1.90.0-nightly"));
+        assert_eq!(None, rust_edition("rustc"));
+    }
+
+    #[test]
+    fn test_variables() {
+        let variables = variables();
+        assert_eq!(
+            env!("CARGO_PKG_VERSION"),
+            variables.get("cargo_lambda_version").unwrap().to_kstr()
+        );
+        assert!(variables.contains_key("git_user_name"));
+        assert!(variables.contains_key("rust_edition"));
+    }
+}
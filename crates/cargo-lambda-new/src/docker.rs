@@ -0,0 +1,106 @@
+use clap::Args;
+use liquid::{model::ValueView, Object, Parser};
+use miette::Result;
+use std::path::Path;
+
+use crate::architecture::Architecture;
+
+const DOCKERFILE: &str = include_str!("../templates/docker/Dockerfile");
+const DOCKERIGNORE: &str = include_str!("../templates/docker/dockerignore");
+
+#[derive(Args, Clone, Debug, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    /// Add a Dockerfile to deploy the function as a container image
+    #[arg(long, conflicts_with = "extension")]
+    docker: bool,
+}
+
+impl Options {
+    /// Whether the project includes a Dockerfile.
+    pub(crate) fn enabled(&self) -> bool {
+        self.docker
+    }
+
+    /// Restore the option recorded when the project was created, unless it's set in the command line.
+    pub(crate) fn restore(&mut self, variables: &Object) {
+        if !self.docker {
+            self.docker = variables
+                .get("docker")
+                .and_then(|v| v.as_scalar())
+                .and_then(|v| v.to_bool())
+                .unwrap_or_default();
+        }
+    }
+
+    pub(crate) fn variables(&self, arch: Architecture) -> Object {
+        let platform = match arch {
+            Architecture::X86_64 => "linux/amd64",
+            Architecture::Arm64 => "linux/arm64",
+        };
+
+        liquid::object!({
+            "docker": self.docker,
+            "docker_platform": platform,
+        })
+    }
+
+    /// Render the Dockerfile and the .dockerignore file in the project.
+    /// Files that the template already includes are not replaced.
+    pub(crate) fn render_files(
+        &self,
+        project_path: &Path,
+        parser: &Parser,
+        globals: &Object,
+    ) -> Result<()> {
+        if !self.docker {
+            return Ok(());
+        }
+
+        crate::render_embedded_files(
+            project_path,
+            parser,
+            globals,
+            &[("Dockerfile", DOCKERFILE), (".dockerignore", DOCKERIGNORE)],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use liquid::ParserBuilder;
+    use std::fs::read_to_string;
+
+    #[test]
+    fn test_render_dockerfile() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = Options { docker: true };
+
+        let parser = ParserBuilder::with_stdlib().build().unwrap();
+        let mut globals = options.variables(Architecture::Arm64);
+        globals.extend(Architecture::Arm64.variables("basic-lambda", &None));
+        options.render_files(dir.path(), &parser, &globals).unwrap();
+
+        let dockerfile = read_to_string(dir.path().join("Dockerfile")).unwrap();
+        assert!(dockerfile.contains("RUN cargo lambda build --release --arm64"));
+        assert!(dockerfile
+            .contains("FROM --platform=linux/arm64 public.ecr.aws/lambda/provided:al2023"));
+        assert!(dockerfile.contains(
+            "/build/target/lambda/basic-lambda/bootstrap ${LAMBDA_RUNTIME_DIR}/bootstrap"
+        ));
+        assert!(dir.path().join(".dockerignore").exists());
+    }
+
+    #[test]
+    fn test_skip_dockerfile() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = Options::default();
+
+        let parser = ParserBuilder::with_stdlib().build().unwrap();
+        let globals = options.variables(Architecture::X86_64);
+        options.render_files(dir.path(), &parser, &globals).unwrap();
+
+        assert!(!dir.path().join("Dockerfile").exists());
+    }
+}
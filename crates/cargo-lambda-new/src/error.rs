@@ -1,27 +1,99 @@
 use std::{io, path::PathBuf};
 
 use cargo_lambda_interactive::error::InquireError;
-use miette::Diagnostic;
+use miette::{Diagnostic, NamedSource, SourceSpan};
 use thiserror::Error;
 
 #[derive(Debug, Diagnostic, Error)]
 pub(crate) enum CreateError {
     #[error("missing options: --event-type, --http")]
+    #[diagnostic(code(cargo_lambda::new::missing_function_options))]
     MissingFunctionOptions,
     #[error("invalid options: --event-type and --http cannot be specified at the same time")]
+    #[diagnostic(code(cargo_lambda::new::invalid_function_options))]
     InvalidFunctionOptions,
     #[error("unexpected input")]
+    #[diagnostic(code(cargo_lambda::new::unexpected_input))]
     UnexpectedInput(#[from] InquireError),
     #[error("invalid file path in template {0:?}")]
+    #[diagnostic(code(cargo_lambda::new::invalid_template_entry))]
     InvalidTemplateEntry(PathBuf),
     #[error("project created in {0}, but the EDITOR variable is missing")]
+    #[diagnostic(code(cargo_lambda::new::invalid_editor))]
     InvalidEditor(String),
     #[error("invalid package name: {0}")]
+    #[diagnostic(code(cargo_lambda::new::invalid_package_name))]
     InvalidPackageName(String),
     #[error("the path is not a directory: {0}")]
+    #[diagnostic(code(cargo_lambda::new::not_a_directory_path))]
     NotADirectoryPath(PathBuf),
     #[error(transparent)]
+    #[diagnostic(code(cargo_lambda::new::invalid_path))]
     InvalidPath(#[from] io::Error),
-    #[error("`cargo lambda init` cannot be run on existing Cargo packages")]
+    #[error("`cargo lambda init` cannot be run on packages created from a template")]
+    #[diagnostic(
+        code(cargo_lambda::new::invalid_package_root),
+        help("use `cargo lambda init --sync` to update a project with its template")
+    )]
     InvalidPackageRoot,
+    #[error(
+        "the manifest doesn't have a [package] table, it looks like a virtual workspace: {0:?}"
+    )]
+    #[diagnostic(
+        code(cargo_lambda::new::missing_package_table),
+        help("run `cargo lambda init` in one of the workspace's packages, or in a new directory")
+    )]
+    MissingPackageTable(PathBuf),
+    #[error(
+        "the binary name `{name}` is already used by the package `{package}` in the workspace"
+    )]
+    #[diagnostic(
+        code(cargo_lambda::new::binary_name_collision),
+        help(
+            "choose a different binary name, `cargo lambda watch` and `cargo lambda deploy` select functions by binary name across the workspace"
+        )
+    )]
+    BinaryNameCollision { name: String, package: String },
+    #[error("the project doesn't have a record of the template that created it: {0:?}")]
+    #[diagnostic(
+        code(cargo_lambda::new::missing_template_record),
+        help("only projects created with this version of Cargo Lambda, or newer, can be synced")
+    )]
+    MissingTemplateRecord(PathBuf),
+    #[error("the template doesn't have a test manifest: {0:?}")]
+    #[diagnostic(
+        code(cargo_lambda::new::missing_test_manifest),
+        help("add a CargoLambdaTests.toml file to the template, or use the --manifest flag")
+    )]
+    MissingTestManifest(PathBuf),
+    #[error("the test case answers a prompt that the template doesn't define: {0}")]
+    #[diagnostic(code(cargo_lambda::new::unknown_test_prompt))]
+    UnknownTestPrompt(String),
+    #[error("{0} of {1} template tests failed")]
+    #[diagnostic(code(cargo_lambda::new::template_tests_failed))]
+    TemplateTestsFailed(usize, usize),
+    #[error("the template rendered an invalid Cargo.toml: {message}")]
+    #[diagnostic(
+        code(cargo_lambda::new::invalid_rendered_manifest),
+        help("fix the template's Cargo.toml, no files were written in the project's directory")
+    )]
+    InvalidRenderedManifest {
+        message: String,
+        #[source_code]
+        src: NamedSource,
+        #[label("invalid manifest")]
+        span: Option<SourceSpan>,
+    },
+    #[error("the template rendered an invalid Cargo package:\n{0}")]
+    #[diagnostic(
+        code(cargo_lambda::new::invalid_rendered_package),
+        help("fix the template's Cargo.toml, no files were written in the project's directory")
+    )]
+    InvalidRenderedPackage(String),
+    #[error("the Cargo Lambda configuration already exists in {0:?}")]
+    #[diagnostic(
+        code(cargo_lambda::new::config_already_exists),
+        help("use the flag `--force` to replace it")
+    )]
+    ConfigAlreadyExists(PathBuf),
 }
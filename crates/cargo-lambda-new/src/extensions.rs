@@ -1,10 +1,25 @@
 use clap::Args;
-use liquid::{model::Value, Object};
-use miette::Result;
+use liquid::{model::Value, Object, Parser};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::{
+    fs::{read_to_string, remove_file, write},
+    path::Path,
+};
 
 pub(crate) const DEFAULT_TEMPLATE_URL: &str =
     "https://github.com/cargo-lambda/new-extensions-template/archive/refs/heads/main.zip";
 
+const TELEMETRY_SPANS_SKELETON: &str = include_str!("../templates/extensions/telemetry_spans.rs");
+const SECRETS_SKELETON: &str = include_str!("../templates/extensions/secrets.rs");
+const LOG_SHIPPER_SKELETON: &str = include_str!("../templates/extensions/log_shipper.rs");
+
+/// Default time that Lambda buffers logs before sending them to the extension.
+const DEFAULT_LOG_BUFFER_TIMEOUT_MS: usize = 1_000;
+/// Default number of logs that Lambda buffers before sending them to the extension.
+const DEFAULT_LOG_BUFFER_MAX_ITEMS: usize = 10_000;
+/// Maximum size of the logs that Lambda buffers, in bytes.
+const LOG_BUFFER_MAX_BYTES: usize = 262_144;
+
 #[derive(Args, Clone, Debug, Default)]
 #[group(requires = "extension", id = "extension-opts")]
 pub(crate) struct Options {
@@ -17,6 +32,59 @@ pub(crate) struct Options {
     /// Whether the extension includes an Events processor
     #[arg(long)]
     events: bool,
+    /// Kind of extension to create: telemetry-spans, secrets, or log-shipper.
+    /// The default template creates a complete skeleton for the extension instead of a minimal stub
+    #[arg(long, conflicts_with_all = ["logs", "telemetry", "events"])]
+    archetype: Option<Archetype>,
+    /// Secret that the secrets extension fetches before the function runs. Repeat the flag to fetch several secrets
+    #[arg(long = "secret", value_name = "SECRET_ID")]
+    secrets: Option<Vec<String>>,
+    /// Maximum time, in milliseconds, that Lambda buffers logs before sending them to the log shipper
+    #[arg(long)]
+    log_buffer_timeout_ms: Option<usize>,
+    /// Maximum number of logs that Lambda buffers before sending them to the log shipper
+    #[arg(long)]
+    log_buffer_max_items: Option<usize>,
+}
+
+/// Kinds of extensions that Cargo Lambda has skeletons for.
+#[derive(Clone, Copy, Debug, PartialEq, strum_macros::Display, strum_macros::EnumString)]
+#[strum(ascii_case_insensitive, serialize_all = "kebab-case")]
+pub(crate) enum Archetype {
+    /// Processes the spans that Lambda reports in the Telemetry API.
+    TelemetrySpans,
+    /// Fetches secrets from Secrets Manager before the function runs.
+    Secrets,
+    /// Sends the function's logs, in batches, to an HTTP destination.
+    LogShipper,
+}
+
+impl Archetype {
+    fn skeleton(&self) -> &'static str {
+        match self {
+            Archetype::TelemetrySpans => TELEMETRY_SPANS_SKELETON,
+            Archetype::Secrets => SECRETS_SKELETON,
+            Archetype::LogShipper => LOG_SHIPPER_SKELETON,
+        }
+    }
+
+    /// Dependencies that the skeleton needs, besides the ones in the default template.
+    fn dependencies(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Archetype::TelemetrySpans => &[],
+            Archetype::Secrets => &[
+                ("aws-config", r#""1""#),
+                ("aws-sdk-secretsmanager", r#""1""#),
+            ],
+            Archetype::LogShipper => &[
+                (
+                    "reqwest",
+                    r#"{ version = "0.12", default-features = false, features = ["json", "rustls-tls"] }"#,
+                ),
+                ("serde_json", r#""1""#),
+            ],
+        }
+    }
 }
 
 impl Options {
@@ -29,22 +97,76 @@ impl Options {
             .map(|v| Value::scalar(v.to_string()))
             .unwrap_or(Value::Nil);
 
+        let archetype = self
+            .archetype
+            .map(|a| Value::scalar(a.to_string()))
+            .unwrap_or(Value::Nil);
+
         Ok(liquid::object!({
-            "logs": self.logs,
-            "telemetry": self.telemetry,
+            "logs": self.logs || self.archetype == Some(Archetype::LogShipper),
+            "telemetry": self.telemetry || self.archetype == Some(Archetype::TelemetrySpans),
             "events": self.add_events_extension(),
             "lambda_extension_version": lv,
+            "archetype": archetype,
+            "secret_ids": self.secrets.clone().unwrap_or_default(),
+            "log_buffer_timeout_ms": self.log_buffer_timeout_ms.unwrap_or(DEFAULT_LOG_BUFFER_TIMEOUT_MS),
+            "log_buffer_max_items": self.log_buffer_max_items.unwrap_or(DEFAULT_LOG_BUFFER_MAX_ITEMS),
+            "log_buffer_max_bytes": LOG_BUFFER_MAX_BYTES,
         }))
     }
 
     fn add_events_extension(&self) -> bool {
-        self.events || (!self.logs && !self.telemetry)
+        match self.archetype {
+            Some(archetype) => archetype == Archetype::Secrets,
+            None => self.events || (!self.logs && !self.telemetry),
+        }
+    }
+
+    /// Replace the stub in `src/main.rs` with the skeleton for the extension's archetype,
+    /// and add the dependencies that the skeleton needs to Cargo.toml.
+    /// Custom templates receive the `archetype` variable to render their own skeletons,
+    /// so their files are never replaced.
+    pub(crate) fn render_archetype(
+        &self,
+        project_path: &Path,
+        parser: &Parser,
+        globals: &Object,
+        default_template: bool,
+    ) -> Result<()> {
+        let Some(archetype) = self.archetype else {
+            return Ok(());
+        };
+
+        if !default_template {
+            tracing::debug!(%archetype, "custom template, skipping the archetype skeleton");
+            return Ok(());
+        }
+
+        let _ = remove_file(project_path.join("src").join("main.rs"));
+        crate::render_embedded_files(
+            project_path,
+            parser,
+            globals,
+            &[("src/main.rs", archetype.skeleton())],
+        )?;
+
+        let manifest_path = project_path.join("Cargo.toml");
+        if !manifest_path.is_file() {
+            return Ok(());
+        }
+
+        let manifest = read_to_string(&manifest_path).into_diagnostic()?;
+        let manifest = crate::manifest::add_dependencies(&manifest, archetype.dependencies())?;
+        write(&manifest_path, manifest)
+            .into_diagnostic()
+            .wrap_err("failed to add the extension's dependencies to Cargo.toml")
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use liquid::ParserBuilder;
 
     #[test]
     fn test_add_events_extension() {
@@ -86,4 +208,88 @@ mod test {
             assert_eq!(exp, opt.add_events_extension(), "options: {:?}", opt);
         }
     }
+
+    #[test]
+    fn test_archetype_variables() {
+        let options = Options {
+            archetype: Some(Archetype::LogShipper),
+            log_buffer_max_items: Some(500),
+            ..Default::default()
+        };
+
+        let variables = options.variables().unwrap();
+        assert_eq!(
+            Some(&Value::scalar("log-shipper")),
+            variables.get("archetype")
+        );
+        assert_eq!(Some(&Value::scalar(true)), variables.get("logs"));
+        assert_eq!(Some(&Value::scalar(false)), variables.get("events"));
+        assert_eq!(
+            Some(&Value::scalar(500)),
+            variables.get("log_buffer_max_items")
+        );
+        assert_eq!(
+            Some(&Value::scalar(1000)),
+            variables.get("log_buffer_timeout_ms")
+        );
+
+        assert_eq!(
+            Archetype::TelemetrySpans,
+            "telemetry-spans".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_render_archetype() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"secrets\"\n\n[dependencies]\nlambda-extension = \"0.11\"\naws-config = { version = \"1\" }\n",
+        )
+        .unwrap();
+
+        let options = Options {
+            archetype: Some(Archetype::Secrets),
+            secrets: Some(vec!["prod/db".into(), "prod/api".into()]),
+            ..Default::default()
+        };
+
+        let parser = ParserBuilder::with_stdlib().build().unwrap();
+        let globals = options.variables().unwrap();
+        options
+            .render_archetype(dir.path(), &parser, &globals, true)
+            .unwrap();
+
+        let main = read_to_string(dir.path().join("src/main.rs")).unwrap();
+        assert!(main.contains(r#"const SECRET_IDS: &[&str] = &["prod/db", "prod/api", ];"#));
+
+        let manifest = read_to_string(dir.path().join("Cargo.toml")).unwrap();
+        assert!(
+            manifest.contains("aws-config = { version = \"1\" }\naws-sdk-secretsmanager = \"1\"\n")
+        );
+        assert_eq!(1, manifest.matches("aws-config").count());
+    }
+
+    #[test]
+    fn test_keep_custom_template_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+
+        let options = Options {
+            archetype: Some(Archetype::TelemetrySpans),
+            ..Default::default()
+        };
+
+        let parser = ParserBuilder::with_stdlib().build().unwrap();
+        let globals = options.variables().unwrap();
+        options
+            .render_archetype(dir.path(), &parser, &globals, false)
+            .unwrap();
+
+        let main = read_to_string(dir.path().join("src/main.rs")).unwrap();
+        assert_eq!("fn main() {}\n", main);
+    }
 }
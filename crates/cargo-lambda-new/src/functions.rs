@@ -1,9 +1,4 @@
-use cargo_lambda_interactive::{
-    error::{CustomUserError, InquireError},
-    is_stdin_tty,
-    validator::{ErrorMessage, Validation},
-    Confirm, Text,
-};
+use cargo_lambda_interactive::{error::InquireError, is_stdin_tty, Confirm, MultiSelect};
 use clap::Args;
 use liquid::{model::Value, Object};
 use miette::Result;
@@ -24,9 +19,10 @@ pub(crate) struct Options {
     #[arg(long, conflicts_with = "http")]
     http_feature: Option<HttpFeature>,
 
-    /// Type of AWS event that this function is going to receive, from the aws_lambda_events crate, for example s3::S3Event
-    #[arg(long, conflicts_with_all = ["http", "http_feature"])]
-    event_type: Option<String>,
+    /// Type of AWS event that this function is going to receive, from the aws_lambda_events crate, for example s3::S3Event.
+    /// Use a comma separated list, like `sqs::SqsEvent,s3::S3Event`, to receive several types of events
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["http", "http_feature"])]
+    event_type: Option<Vec<String>>,
 }
 
 #[derive(Clone, Debug, strum_macros::Display, strum_macros::EnumString)]
@@ -76,13 +72,14 @@ impl Options {
         }
 
         if !self.http {
-            let help = format!("{PROMPT_WITH_OPTIONS_HELP_MESSAGE}.\nLeave this input empty if you want to use a predefined example");
-            let event_type = Text::new("Event type that this function receives")
-                .with_autocomplete(suggest_event_type)
-                .with_validator(validate_event_type)
-                .with_help_message(&help)
-                .prompt()?;
-            self.event_type = Some(event_type);
+            let help = format!("{PROMPT_WITH_OPTIONS_HELP_MESSAGE}, space to select one or more types.\nDon't select any type if you want to use a predefined example");
+            let event_types = MultiSelect::new(
+                "Event types that this function receives",
+                crate::events::WELL_KNOWN_EVENTS.to_vec(),
+            )
+            .with_help_message(&help)
+            .prompt()?;
+            self.event_type = Some(event_types.into_iter().map(String::from).collect());
         }
 
         Ok(())
@@ -96,6 +93,8 @@ impl Options {
         let use_basic_example = !self.http && !self.has_event_type();
 
         let (ev_import, ev_feat, ev_type) = self.event_type_triple()?;
+        let event_types = self.event_types_variables()?;
+        let event_type_features = self.event_type_features();
 
         let fn_name = match binary_name {
             Some(name) if name != package_name => Value::scalar(name.clone()),
@@ -128,63 +127,134 @@ impl Options {
             "event_type": ev_type,
             "event_type_feature": ev_feat,
             "event_type_import": ev_import,
+            "event_types": event_types,
+            "event_type_features": event_type_features,
             "lambda_http_version": lhv,
             "lambda_runtime_version": lrv,
             "aws_lambda_events_version": lev,
         }))
     }
 
+    /// Use the triggers of a deployed function to choose the kind of function,
+    /// unless the options already set it.
+    pub(crate) fn seed_from_remote(&mut self, http: bool, event_types: &[String]) {
+        if !self.missing_options() {
+            return;
+        }
+
+        if http {
+            self.http = true;
+        } else if !event_types.is_empty() {
+            self.event_type = Some(event_types.to_vec());
+        }
+    }
+
     fn missing_options(&self) -> bool {
         !self.http && self.event_type.is_none()
     }
 
     fn has_event_type(&self) -> bool {
-        matches!(&self.event_type, Some(s) if !s.is_empty())
+        self.event_types().next().is_some()
     }
 
+    /// Event types selected for the function, ignoring empty values.
+    fn event_types(&self) -> impl Iterator<Item = &str> {
+        self.event_type
+            .iter()
+            .flatten()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Import, feature, and type of the first event type, for templates that only handle one type of event.
     fn event_type_triple(&self) -> Result<(Value, Value, Value)> {
-        match &self.event_type {
-            Some(s) if s == "serde_json::Value" => Ok((
-                Value::scalar(s.clone()),
-                Value::scalar("serde_json"),
-                Value::scalar("Value"),
-            )),
-            Some(s) if !s.is_empty() => {
-                let import = Value::scalar(format!("aws_lambda_events::event::{s}"));
-                match s.rsplitn(2, "::").collect::<Vec<_>>()[..] {
-                    [ev_type, ev_mod] => Ok((
-                        import,
-                        Value::scalar(ev_mod.to_string()),
-                        Value::scalar(ev_type.to_string()),
-                    )),
-                    _ => Err(miette::miette!("unexpected event type")),
-                }
+        match self.event_types().next() {
+            Some(s) => event_type_triple(s),
+            None => Ok((Value::Nil, Value::Nil, Value::Nil)),
+        }
+    }
+
+    /// Variables for each event type, so templates can generate one handler module for each one of them.
+    fn event_types_variables(&self) -> Result<Value> {
+        let mut variables = Vec::new();
+        for event_type in self.event_types() {
+            let (ev_import, ev_feat, ev_type) = event_type_triple(event_type)?;
+            variables.push(Value::Object(liquid::object!({
+                "event_type": ev_type,
+                "event_type_feature": ev_feat,
+                "event_type_import": ev_import,
+                "handler_module": handler_module(event_type),
+            })));
+        }
+        Ok(Value::Array(variables))
+    }
+
+    /// Features of the aws_lambda_events crate that the event types need, without duplicates.
+    fn event_type_features(&self) -> Value {
+        let mut features: Vec<&str> = Vec::new();
+        for event_type in self.event_types() {
+            let feature = event_type.split("::").next().unwrap_or_default();
+            if feature != "serde_json" && !features.contains(&feature) {
+                features.push(feature);
             }
-            _ => Ok((Value::Nil, Value::Nil, Value::Nil)),
         }
+        Value::array(features.into_iter().map(|f| Value::scalar(f.to_string())))
+    }
+}
+
+fn event_type_triple(event_type: &str) -> Result<(Value, Value, Value)> {
+    if event_type == "serde_json::Value" {
+        return Ok((
+            Value::scalar(event_type.to_string()),
+            Value::scalar("serde_json"),
+            Value::scalar("Value"),
+        ));
+    }
+
+    let import = Value::scalar(format!("aws_lambda_events::event::{event_type}"));
+    match event_type.rsplitn(2, "::").collect::<Vec<_>>()[..] {
+        [ev_type, ev_mod] => Ok((
+            import,
+            Value::scalar(ev_mod.to_string()),
+            Value::scalar(ev_type.to_string()),
+        )),
+        _ => Err(miette::miette!("unexpected event type: {event_type}")),
     }
 }
 
-fn validate_event_type(name: &str) -> Result<Validation, CustomUserError> {
-    match name.is_empty() || crate::events::WELL_KNOWN_EVENTS.contains(&name) {
-        true => Ok(Validation::Valid),
-        false => Ok(Validation::Invalid(ErrorMessage::Custom(format!(
-            "invalid event type: {name}"
-        )))),
+/// Name of the module that handles an event type, like `sqs_event` for `sqs::SqsEvent`.
+/// The event's module is added as a prefix when the type name doesn't include it, like `dynamodb_event` for `dynamodb::Event`.
+pub(crate) fn handler_module(event_type: &str) -> String {
+    let mut segments = event_type.rsplit("::");
+    let type_name = snake_case(segments.next().unwrap_or_default());
+    let module = segments.next().unwrap_or_default();
+
+    if module.is_empty() || type_name.starts_with(module) {
+        type_name
+    } else {
+        format!("{module}_{type_name}")
     }
 }
 
-fn suggest_event_type(text: &str) -> Result<Vec<String>, CustomUserError> {
-    Ok(crate::events::WELL_KNOWN_EVENTS
-        .iter()
-        .filter_map(|s| {
-            if s.starts_with(text) {
-                Some(s.to_string())
-            } else {
-                None
+fn snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut result = String::with_capacity(name.len() + 4);
+
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower)
+            {
+                result.push('_');
             }
-        })
-        .collect())
+        }
+        result.extend(c.to_lowercase());
+    }
+
+    result
 }
 
 #[cfg(test)]
@@ -201,7 +271,7 @@ mod test {
         let opt = Options {
             http: false,
             http_feature: None,
-            event_type: Some("serde_json::Value".to_string()),
+            event_type: Some(vec!["serde_json::Value".to_string()]),
         };
 
         let (imp, module, kind) = opt.event_type_triple().unwrap();
@@ -215,7 +285,7 @@ mod test {
         let opt = Options {
             http: false,
             http_feature: None,
-            event_type: Some("sns::SnsEvent".to_string()),
+            event_type: Some(vec!["sns::SnsEvent".to_string()]),
         };
 
         let (imp, module, kind) = opt.event_type_triple().unwrap();
@@ -232,9 +302,9 @@ mod test {
         let opt = Options {
             http: false,
             http_feature: None,
-            event_type: Some(
+            event_type: Some(vec![
                 "cloudformation::provider::CloudFormationCustomResourceRequest".to_string(),
-            ),
+            ]),
         };
 
         let (imp, module, kind) = opt.event_type_triple().unwrap();
@@ -245,4 +315,61 @@ mod test {
         assert_eq!(Value::scalar("cloudformation::provider"), module);
         assert_eq!(Value::scalar("CloudFormationCustomResourceRequest"), kind);
     }
+
+    #[test]
+    fn test_multiple_event_types() {
+        let opt = Options {
+            http: false,
+            http_feature: None,
+            event_type: Some(vec![
+                "sqs::SqsEvent".to_string(),
+                "s3::S3Event".to_string(),
+                "sqs::SqsApiEvent".to_string(),
+                "".to_string(),
+            ]),
+        };
+
+        let (_, module, kind) = opt.event_type_triple().unwrap();
+        assert_eq!(Value::scalar("sqs"), module);
+        assert_eq!(Value::scalar("SqsEvent"), kind);
+
+        let Value::Array(events) = opt.event_types_variables().unwrap() else {
+            panic!("event types are not an array");
+        };
+        assert_eq!(3, events.len());
+
+        let Value::Object(event) = &events[1] else {
+            panic!("event type is not an object");
+        };
+        assert_eq!(Some(&Value::scalar("S3Event")), event.get("event_type"));
+        assert_eq!(Some(&Value::scalar("s3")), event.get("event_type_feature"));
+        assert_eq!(
+            Some(&Value::scalar("s3_event")),
+            event.get("handler_module")
+        );
+
+        assert_eq!(
+            Value::array(vec![Value::scalar("sqs"), Value::scalar("s3")]),
+            opt.event_type_features()
+        );
+    }
+
+    #[test]
+    fn test_handler_module() {
+        assert_eq!("sqs_event", handler_module("sqs::SqsEvent"));
+        assert_eq!(
+            "s3_batch_job_event",
+            handler_module("s3_batch_job::S3BatchJobEvent")
+        );
+        assert_eq!("dynamodb_event", handler_module("dynamodb::Event"));
+        assert_eq!("serde_json_value", handler_module("serde_json::Value"));
+        assert_eq!(
+            "provider_cloud_formation_custom_resource_request",
+            handler_module("cloudformation::provider::CloudFormationCustomResourceRequest")
+        );
+        assert_eq!(
+            "cloudwatch_alarms_cloud_watch_alarm",
+            handler_module("cloudwatch_alarms::CloudWatchAlarm")
+        );
+    }
 }
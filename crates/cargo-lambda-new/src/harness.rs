@@ -0,0 +1,410 @@
+use cargo_lambda_interactive::{command::new_command, progress::Progress};
+use clap::{Args, Command, FromArgMatches};
+use indexmap::IndexMap;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use serde::Deserialize;
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+use tempfile::TempDir;
+
+use crate::{
+    error::CreateError,
+    template::{
+        config::{parse_template_config, PromptValue, TemplateConfig},
+        TemplateSource,
+    },
+    Config,
+};
+
+/// Name of the file with the test cases of a template, next to the template's `CargoLambda.toml`.
+pub(crate) const TEST_MANIFEST: &str = "CargoLambdaTests.toml";
+
+/// Name of the package that the test projects are rendered with.
+const TEST_PROJECT_NAME: &str = "template-test";
+
+#[derive(Args, Clone, Debug)]
+pub(crate) struct TestTemplate {
+    /// Where to find the template to test. It accepts the same locations as `cargo lambda new --template`
+    #[arg(value_name = "PATH")]
+    template: String,
+
+    /// Path to the test manifest, defaults to the CargoLambdaTests.toml file in the template
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+
+    /// Directory to share the build artifacts between test projects, defaults to a temporary directory
+    #[arg(long)]
+    target_dir: Option<PathBuf>,
+
+    /// Render the test projects without running `cargo check` on them
+    #[arg(long)]
+    skip_check: bool,
+
+    /// Use the templates downloaded previously without connecting to the network
+    #[arg(long)]
+    offline: bool,
+}
+
+/// List of test cases to render a template with.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct TestManifest {
+    #[serde(default)]
+    tests: Vec<TestDefinition>,
+}
+
+/// Test case in the manifest. Each combination of values in the matrix
+/// renders a different project, with the rest of the prompt answers.
+#[derive(Debug, Default, Deserialize)]
+struct TestDefinition {
+    name: String,
+    /// Flags to pass to `cargo lambda new`, like `--http`, or `--event-type`
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    prompts: IndexMap<String, PromptValue>,
+    #[serde(default)]
+    matrix: IndexMap<String, Vec<PromptValue>>,
+}
+
+/// Project to render, with all the answers for the template prompts.
+#[derive(Debug, PartialEq)]
+pub(crate) struct TestCase {
+    name: String,
+    args: Vec<String>,
+    prompts: IndexMap<String, PromptValue>,
+}
+
+/// Result of rendering and checking a test case.
+#[derive(Debug)]
+pub(crate) struct TestResult {
+    name: String,
+    failure: Option<String>,
+}
+
+impl TestTemplate {
+    #[tracing::instrument(skip(self), target = "cargo_lambda")]
+    pub(crate) async fn run(&self) -> Result<()> {
+        let progress = Progress::start("downloading template");
+        let template = TemplateSource::try_from(self.template.as_str())?
+            .expand(self.offline)
+            .await;
+        progress.finish_and_clear();
+
+        let template = template?;
+        template.cleanup();
+
+        let config_path = template.config_path();
+        let template_config = parse_template_config(&config_path)?;
+
+        let manifest_path = self
+            .manifest
+            .clone()
+            .unwrap_or_else(|| config_path.join(TEST_MANIFEST));
+        let cases = load_manifest(&manifest_path)?.cases();
+
+        let tmp_target_dir = tempfile::tempdir().into_diagnostic()?;
+        let target_dir = self
+            .target_dir
+            .as_deref()
+            .unwrap_or_else(|| tmp_target_dir.path());
+
+        let template_path = template.final_path();
+        let mut results = Vec::with_capacity(cases.len());
+        for case in &cases {
+            let progress = Progress::start(format!("testing {}", case.name));
+            let failure = match render_case(&template_path, &template_config, case) {
+                Err(err) => Some(format!("{err:?}")),
+                Ok(_) if self.skip_check => None,
+                Ok(project) => check_project(project.path(), target_dir).await?,
+            };
+            progress.finish_and_clear();
+
+            results.push(TestResult {
+                name: case.name.clone(),
+                failure,
+            });
+        }
+
+        print!("{}", format_results(&results));
+
+        let failed = results.iter().filter(|r| r.failure.is_some()).count();
+        if failed > 0 {
+            Err(CreateError::TemplateTestsFailed(failed, results.len()))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TestManifest {
+    /// Expand the test definitions into one case for each combination of values in their matrix.
+    pub(crate) fn cases(&self) -> Vec<TestCase> {
+        let mut cases = Vec::new();
+
+        for test in &self.tests {
+            let mut combinations: Vec<Vec<(&String, &PromptValue)>> = vec![Vec::new()];
+            for (name, values) in &test.matrix {
+                combinations = combinations
+                    .into_iter()
+                    .flat_map(|combination| {
+                        values.iter().map(move |value| {
+                            let mut combination = combination.clone();
+                            combination.push((name, value));
+                            combination
+                        })
+                    })
+                    .collect();
+            }
+
+            for combination in combinations {
+                let mut name = test.name.clone();
+                if !combination.is_empty() {
+                    let values = combination
+                        .iter()
+                        .map(|(k, v)| format!("{k}={}", display_value(v)))
+                        .collect::<Vec<_>>();
+                    name = format!("{name} ({})", values.join(", "));
+                }
+
+                let mut prompts = test.prompts.clone();
+                prompts.extend(combination.into_iter().map(|(k, v)| (k.clone(), v.clone())));
+
+                cases.push(TestCase {
+                    name,
+                    args: test.args.clone(),
+                    prompts,
+                });
+            }
+        }
+
+        cases
+    }
+}
+
+fn load_manifest(path: &Path) -> Result<TestManifest> {
+    if !path.is_file() {
+        Err(CreateError::MissingTestManifest(path.to_path_buf()))?;
+    }
+
+    let contents = fs::read_to_string(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to read test manifest at {path:?}"))?;
+
+    toml::from_str(&contents)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to parse test manifest at {path:?}"))
+}
+
+/// Render a test case in a temporary directory, without asking for any input.
+fn render_case(
+    template_path: &Path,
+    template_config: &TemplateConfig,
+    case: &TestCase,
+) -> Result<TempDir> {
+    let command = Config::augment_args(Command::new("test").no_binary_name(true));
+    let matches = command
+        .try_get_matches_from(&case.args)
+        .into_diagnostic()
+        .wrap_err("invalid arguments in test case")?;
+    let mut config = Config::from_arg_matches(&matches).into_diagnostic()?;
+    config.no_interactive = true;
+
+    if config.extension {
+        config.extension_options.validate_options()?;
+    } else {
+        config.function_options.validate_options(true)?;
+    }
+
+    let mut globals = crate::build_template_variables(&config, template_config, TEST_PROJECT_NAME)?;
+    for (name, value) in &case.prompts {
+        if !template_config.prompts.contains_key(name) {
+            Err(CreateError::UnknownTestPrompt(name.clone()))?;
+        }
+        globals.insert(name.clone().into(), value.to_value());
+    }
+    // The `--render-var` flags in the arguments take precedence over the prompt answers.
+    globals.extend(crate::render_variables(&config));
+
+    crate::render_template(template_path, template_config, &globals, &config)
+}
+
+/// Run `cargo check` in the project, and return the compiler errors if it fails.
+async fn check_project(path: &Path, target_dir: &Path) -> Result<Option<String>> {
+    let output = new_command("cargo")
+        .args(["check", "--quiet"])
+        .env("CARGO_TARGET_DIR", target_dir)
+        .current_dir(path)
+        .output()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to run `cargo check`")?;
+
+    if output.status.success() {
+        Ok(None)
+    } else {
+        Ok(Some(String::from_utf8_lossy(&output.stderr).into_owned()))
+    }
+}
+
+/// Format the result of every test case, followed by the failures' output.
+pub(crate) fn format_results(results: &[TestResult]) -> String {
+    let mut output = String::new();
+
+    for result in results {
+        let marker = if result.failure.is_some() {
+            '✘'
+        } else {
+            '✔'
+        };
+        let _ = writeln!(output, "  {marker} {}", result.name);
+    }
+
+    for result in results {
+        if let Some(failure) = &result.failure {
+            let _ = writeln!(output, "\n--- {} ---", result.name);
+            let _ = writeln!(output, "{}", failure.trim_end());
+        }
+    }
+
+    let failed = results.iter().filter(|r| r.failure.is_some()).count();
+    let _ = writeln!(
+        output,
+        "\n{} passed, {failed} failed",
+        results.len() - failed
+    );
+
+    output
+}
+
+fn display_value(value: &PromptValue) -> String {
+    match value {
+        PromptValue::Boolean(b) => b.to_string(),
+        PromptValue::Integer(i) => i.to_string(),
+        PromptValue::String(s) => s.clone(),
+        PromptValue::List(l) => format!("[{}]", l.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, read_to_string, write};
+
+    fn manifest(contents: &str) -> TestManifest {
+        toml::from_str(contents).unwrap()
+    }
+
+    #[test]
+    fn test_expand_matrix() {
+        let manifest = manifest(
+            r#"
+            [[tests]]
+            name = "http"
+            args = ["--http"]
+            prompts = { tracing = true }
+            matrix = { database = ["postgres", "dynamodb"], retries = [1, 3] }
+
+            [[tests]]
+            name = "sqs"
+            args = ["--event-type", "sqs::SqsEvent"]
+            "#,
+        );
+
+        let cases = manifest.cases();
+        let names = cases.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "http (database=postgres, retries=1)",
+                "http (database=postgres, retries=3)",
+                "http (database=dynamodb, retries=1)",
+                "http (database=dynamodb, retries=3)",
+                "sqs",
+            ],
+            names
+        );
+
+        assert_eq!(vec!["--http"], cases[1].args);
+        assert_eq!(
+            Some(&PromptValue::Boolean(true)),
+            cases[1].prompts.get("tracing")
+        );
+        assert_eq!(
+            Some(&PromptValue::Integer(3)),
+            cases[1].prompts.get("retries")
+        );
+        assert!(cases[4].prompts.is_empty());
+    }
+
+    #[test]
+    fn test_render_case() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = dir.path();
+        create_dir_all(template.join("src")).unwrap();
+        write(
+            template.join("Cargo.toml"),
+            "[package]\nname = \"{{ project_name }}\"\n",
+        )
+        .unwrap();
+        write(
+            template.join("src/main.rs"),
+            "// database: {{ database }}, http: {{ http_function }}\n",
+        )
+        .unwrap();
+        write(template.join(TEST_MANIFEST), "[[tests]]\nname = \"http\"\n").unwrap();
+
+        let template_config: TemplateConfig = toml::from_str(
+            r#"
+            [prompts.database]
+            message = "Which database does the function use?"
+            choices = ["postgres", "dynamodb"]
+            default = "postgres"
+            "#,
+        )
+        .unwrap();
+
+        let case = TestCase {
+            name: "http".into(),
+            args: vec!["--http".into()],
+            prompts: IndexMap::from([("database".into(), PromptValue::String("dynamodb".into()))]),
+        };
+
+        let project = render_case(template, &template_config, &case).unwrap();
+        let cargo_toml = read_to_string(project.path().join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains("name = \"template-test\""));
+
+        let main = read_to_string(project.path().join("src/main.rs")).unwrap();
+        assert_eq!("// database: dynamodb, http: true\n", main);
+        assert!(!project.path().join(TEST_MANIFEST).exists());
+
+        let case = TestCase {
+            name: "unknown".into(),
+            args: vec!["--http".into()],
+            prompts: IndexMap::from([("region".into(), PromptValue::String("eu".into()))]),
+        };
+        let err = render_case(template, &template_config, &case).unwrap_err();
+        assert!(err.to_string().contains("region"));
+    }
+
+    #[test]
+    fn test_format_results() {
+        let results = vec![
+            TestResult {
+                name: "http".into(),
+                failure: None,
+            },
+            TestResult {
+                name: "sqs".into(),
+                failure: Some("error[E0425]: cannot find value `event`\n".into()),
+            },
+        ];
+
+        let output = format_results(&results);
+        assert!(output.contains("  ✔ http\n"));
+        assert!(output.contains("  ✘ sqs\n"));
+        assert!(output.contains("--- sqs ---\nerror[E0425]: cannot find value `event`\n"));
+        assert!(output.ends_with("1 passed, 1 failed\n"));
+    }
+}
@@ -0,0 +1,168 @@
+use cargo_lambda_interactive::{error::InquireError, is_stdin_tty, Select};
+use clap::Args;
+use liquid::{model::ValueView, Object, Parser};
+use miette::Result;
+use std::path::Path;
+
+const SAM_TEMPLATE: &str = include_str!("../templates/iac/sam/template.yaml");
+const CDK_CONFIG: &str = include_str!("../templates/iac/cdk/cdk.json");
+const CDK_PACKAGE: &str = include_str!("../templates/iac/cdk/package.json");
+const CDK_TSCONFIG: &str = include_str!("../templates/iac/cdk/tsconfig.json");
+const CDK_APP: &str = include_str!("../templates/iac/cdk/bin/app.ts");
+const CDK_STACK: &str = include_str!("../templates/iac/cdk/lib/stack.ts");
+const TERRAFORM_MAIN: &str = include_str!("../templates/iac/terraform/main.tf");
+
+#[derive(Args, Clone, Debug, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    /// Infrastructure as code tool to generate a stack that deploys the function: sam, cdk, terraform, or none
+    #[arg(long, conflicts_with = "extension")]
+    iac: Option<IacProvider>,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, strum_macros::Display, strum_macros::EnumString,
+)]
+#[strum(ascii_case_insensitive, serialize_all = "snake_case")]
+pub(crate) enum IacProvider {
+    Sam,
+    Cdk,
+    Terraform,
+    #[default]
+    None,
+}
+
+impl IacProvider {
+    /// Infrastructure files that the tool uses, with their path in the project.
+    fn files(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            IacProvider::Sam => &[("template.yaml", SAM_TEMPLATE)],
+            IacProvider::Cdk => &[
+                ("infra/cdk.json", CDK_CONFIG),
+                ("infra/package.json", CDK_PACKAGE),
+                ("infra/tsconfig.json", CDK_TSCONFIG),
+                ("infra/bin/app.ts", CDK_APP),
+                ("infra/lib/stack.ts", CDK_STACK),
+            ],
+            IacProvider::Terraform => &[("infra/main.tf", TERRAFORM_MAIN)],
+            IacProvider::None => &[],
+        }
+    }
+}
+
+impl Options {
+    pub(crate) fn validate_options(&mut self, no_interactive: bool) -> Result<(), InquireError> {
+        if no_interactive || !is_stdin_tty() || self.iac.is_some() {
+            return Ok(());
+        }
+
+        let provider = Select::new(
+            "Would you like to add an infrastructure stack to deploy the function?",
+            vec![
+                IacProvider::None,
+                IacProvider::Sam,
+                IacProvider::Cdk,
+                IacProvider::Terraform,
+            ],
+        )
+        .prompt()?;
+        self.iac = Some(provider);
+
+        Ok(())
+    }
+
+    /// Whether the project includes infrastructure files.
+    pub(crate) fn enabled(&self) -> bool {
+        self.provider() != IacProvider::None
+    }
+
+    pub(crate) fn variables(&self) -> Object {
+        liquid::object!({
+            "iac_provider": self.provider().to_string(),
+        })
+    }
+
+    /// Restore the option recorded when the project was created, unless it's set in the command line.
+    pub(crate) fn restore(&mut self, variables: &Object) {
+        if self.iac.is_none() {
+            self.iac = variables
+                .get("iac_provider")
+                .and_then(|v| v.to_kstr().parse().ok());
+        }
+    }
+
+    fn provider(&self) -> IacProvider {
+        self.iac.unwrap_or_default()
+    }
+
+    /// Render the infrastructure files for the tool in the project.
+    /// Files that the template already includes are not replaced.
+    pub(crate) fn render_files(
+        &self,
+        project_path: &Path,
+        parser: &Parser,
+        globals: &Object,
+    ) -> Result<()> {
+        crate::render_embedded_files(project_path, parser, globals, self.provider().files())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::architecture::Architecture;
+    use liquid::ParserBuilder;
+    use std::fs::read_to_string;
+
+    fn render(provider: IacProvider, arch: Architecture, project_path: &Path) {
+        let options = Options {
+            iac: Some(provider),
+        };
+        let parser = ParserBuilder::with_stdlib().build().unwrap();
+        let mut globals = liquid::object!({ "project_name": "basic-lambda" });
+        globals.extend(options.variables());
+        globals.extend(arch.variables("basic-lambda", &None));
+        options
+            .render_files(project_path, &parser, &globals)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(IacProvider::Sam, "SAM".parse().unwrap());
+        assert_eq!(IacProvider::Terraform, "terraform".parse().unwrap());
+    }
+
+    #[test]
+    fn test_render_sam_template() {
+        let dir = tempfile::tempdir().unwrap();
+        render(IacProvider::Sam, Architecture::Arm64, dir.path());
+
+        let template = read_to_string(dir.path().join("template.yaml")).unwrap();
+        assert!(template.contains("CodeUri: target/lambda/basic-lambda/"));
+        assert!(template.contains("- arm64"));
+    }
+
+    #[test]
+    fn test_render_cdk_stack() {
+        let dir = tempfile::tempdir().unwrap();
+        render(IacProvider::Cdk, Architecture::Arm64, dir.path());
+
+        let stack = read_to_string(dir.path().join("infra/lib/stack.ts")).unwrap();
+        assert!(stack.contains("'target', 'lambda', 'basic-lambda'"));
+        assert!(stack.contains("lambda.Architecture.ARM_64"));
+        assert!(!stack.contains("lambda.Architecture.X86_64"));
+        assert!(dir.path().join("infra/bin/app.ts").exists());
+        assert!(dir.path().join("infra/cdk.json").exists());
+    }
+
+    #[test]
+    fn test_render_terraform_config() {
+        let dir = tempfile::tempdir().unwrap();
+        render(IacProvider::Terraform, Architecture::X86_64, dir.path());
+
+        let config = read_to_string(dir.path().join("infra/main.tf")).unwrap();
+        assert!(config.contains("target/lambda/basic-lambda/bootstrap.zip"));
+        assert!(config.contains("architectures    = [\"x86_64\"]"));
+    }
+}
@@ -1,9 +1,16 @@
 use cargo_lambda_interactive::{
-    command::new_command, is_user_cancellation_error, progress::Progress,
+    command::new_command, error::InquireError, is_stdin_tty, is_user_cancellation_error,
+    progress::Progress,
+};
+use cargo_lambda_metadata::{
+    config::TemplatesConfig,
+    fs::{copy_and_replace, copy_without_replace},
+};
+use clap::{Args, Subcommand};
+use liquid::{
+    model::{Value, ValueView},
+    Object, Parser, ParserBuilder,
 };
-use cargo_lambda_metadata::fs::{copy_and_replace, copy_without_replace};
-use clap::Args;
-use liquid::{model::Value, Object, Parser, ParserBuilder};
 use miette::{IntoDiagnostic, Result, WrapErr};
 use regex::Regex;
 use std::{
@@ -13,18 +20,43 @@ use std::{
     fs::{copy as copy_file, create_dir_all, File},
     path::{Path, PathBuf},
 };
-use template::{config::TemplateConfig, TemplateRoot};
+use tempfile::TempDir;
+use template::{
+    config::{RenderCondition, TemplateConfig},
+    TemplateRoot,
+};
 use walkdir::WalkDir;
 
-use crate::template::TemplateSource;
+use crate::{architecture::Architecture, template::TemplateSource};
 
+mod architecture;
+mod binaries;
+mod ci;
+mod config_init;
+pub use config_init::ConfigCommand;
+mod config_validate;
+mod context;
+mod docker;
 mod error;
 use error::CreateError;
 
 mod events;
 mod extensions;
 mod functions;
+mod harness;
+mod iac;
+mod manifest;
+mod merge;
+mod package;
+mod preview;
+mod remote;
+mod sync;
 mod template;
+mod validate;
+mod workspace;
+
+/// Path variable that renders a template file once for each event type.
+const HANDLER_MODULE_VARIABLE: &str = "{{handler_module}}";
 
 #[derive(Args, Clone, Debug)]
 #[group(skip)]
@@ -45,6 +77,34 @@ struct Config {
     #[command(flatten)]
     extension_options: extensions::Options,
 
+    /// Options for packages with several binaries
+    #[command(flatten)]
+    binaries_options: binaries::Options,
+
+    /// Options for the package's license and description
+    #[command(flatten)]
+    package_options: package::Options,
+
+    /// Options for CI workflows
+    #[command(flatten)]
+    ci_options: ci::Options,
+
+    /// Options for infrastructure as code stacks
+    #[command(flatten)]
+    iac_options: iac::Options,
+
+    /// Options for container images
+    #[command(flatten)]
+    docker_options: docker::Options,
+
+    /// Options to import the configuration of a deployed function
+    #[command(flatten)]
+    remote_options: remote::Options,
+
+    /// Architecture that CI workflows, infrastructure stacks, and container images compile the function for: x86_64, or arm64
+    #[arg(long, alias = "ci-arch", conflicts_with = "extension")]
+    arch: Option<Architecture>,
+
     /// Open the project in a code editor defined by the environment variable EDITOR
     #[arg(short, long)]
     open: bool,
@@ -68,6 +128,43 @@ struct Config {
     /// List of files to ignore from the template
     #[arg(long)]
     ignore_file: Option<Vec<PathBuf>>,
+
+    /// Use the templates downloaded previously without connecting to the network
+    #[arg(long)]
+    offline: bool,
+
+    /// Render the template and print the files that it would create, without writing anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Run `cargo metadata` on the rendered project to validate it before writing any file
+    #[arg(long)]
+    validate: bool,
+}
+
+impl Config {
+    /// Use the default template and render variables from the global configuration.
+    /// The `--template` and `--render-var` flags take precedence over them.
+    fn apply_templates_config(&mut self, templates: &TemplatesConfig) {
+        if self.template.is_none() {
+            self.template = if self.extension {
+                templates.extension.clone()
+            } else {
+                templates.function.clone()
+            };
+        }
+
+        if !templates.render_vars.is_empty() {
+            // Later variables override earlier ones, so the flags go last.
+            let mut render_var = templates
+                .render_vars
+                .iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect::<Vec<_>>();
+            render_var.extend(self.render_var.take().unwrap_or_default());
+            self.render_var = Some(render_var);
+        }
+    }
 }
 
 #[derive(Args, Clone, Debug)]
@@ -83,8 +180,16 @@ pub struct Init {
     #[arg(long)]
     name: Option<String>,
 
+    /// Update the project with the latest version of its template, using the variables recorded when the project was created
+    #[arg(long, conflicts_with = "name")]
+    sync: bool,
+
     #[arg(default_value = ".")]
     path: PathBuf,
+
+    /// Default templates, loaded from the global configuration
+    #[arg(skip)]
+    pub templates: TemplatesConfig,
 }
 
 impl Init {
@@ -94,15 +199,32 @@ impl Init {
             Err(CreateError::NotADirectoryPath(self.path.to_path_buf()))?;
         }
 
-        if self.path.join("Cargo.toml").is_file() {
+        // Syncing uses the template recorded in the project, not the default template.
+        if self.sync {
+            let path = dunce::canonicalize(&self.path).map_err(CreateError::InvalidPath)?;
+            return sync_project(&path, &mut self.config).await;
+        }
+
+        self.config.apply_templates_config(&self.templates);
+
+        // Projects created from a template are updated with `--sync`,
+        // other packages get the template merged into their files.
+        if self.path.join(sync::RECORD_PATH).is_file() {
             Err(CreateError::InvalidPackageRoot)?;
         }
 
         let path = dunce::canonicalize(&self.path).map_err(CreateError::InvalidPath)?;
 
+        let package_name = if path.join("Cargo.toml").is_file() {
+            Some(merge::package_name(&path)?)
+        } else {
+            None
+        };
+
         let name = self
             .name
             .as_deref()
+            .or(package_name.as_deref())
             .or_else(|| path.file_name().and_then(|s| s.to_str()))
             .ok_or_else(|| miette::miette!("invalid package name"))?;
 
@@ -122,15 +244,45 @@ pub struct New {
     /// Name of the Rust package to create
     #[arg()]
     name: String,
+
+    /// Default templates, loaded from the global configuration
+    #[arg(skip)]
+    pub templates: TemplatesConfig,
 }
 
 impl New {
     #[tracing::instrument(skip(self), target = "cargo_lambda")]
     pub async fn run(&mut self) -> Result<()> {
+        self.config.apply_templates_config(&self.templates);
         new_project(&self.name, &self.name, &mut self.config, true).await
     }
 }
 
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "template",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/template.html"
+)]
+pub struct Template {
+    #[command(subcommand)]
+    command: TemplateCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum TemplateCommand {
+    /// Render a template with the test cases in its manifest, and check that the generated projects compile
+    Test(harness::TestTemplate),
+}
+
+impl Template {
+    #[tracing::instrument(skip(self), target = "cargo_lambda")]
+    pub async fn run(&self) -> Result<()> {
+        match &self.command {
+            TemplateCommand::Test(test) => test.run().await,
+        }
+    }
+}
+
 #[tracing::instrument(target = "cargo_lambda")]
 async fn new_project<T: AsRef<Path> + Debug>(
     name: &str,
@@ -144,6 +296,15 @@ async fn new_project<T: AsRef<Path> + Debug>(
     if let Some(name) = &config.bin_name {
         validate_name(name)?;
     }
+    for name in config.binaries_options.names() {
+        validate_name(name)?;
+    }
+
+    match workspace::check_binary_names(config, name, path.as_ref()) {
+        Err(CreateError::UnexpectedInput(err)) if is_user_cancellation_error(&err) => return Ok(()),
+        Err(err) => return Err(err.into()),
+        Ok(()) => {}
+    }
 
     let template = get_template(config).await?;
     template.cleanup();
@@ -151,6 +312,25 @@ async fn new_project<T: AsRef<Path> + Debug>(
     let template_config = template::config::parse_template_config(template.config_path())?;
     let ignore_default_prompts = template_config.disable_default_prompts || config.no_interactive;
 
+    config.remote_options.fetch_function().await?;
+    if let Some(function) = config.remote_options.function() {
+        config
+            .function_options
+            .seed_from_remote(function.http(), function.event_types());
+        if config.arch.is_none() {
+            config.arch = function.architecture();
+        }
+    }
+
+    match config
+        .package_options
+        .validate_options(ignore_default_prompts, &template_config)
+    {
+        Err(err) if is_user_cancellation_error(&err) => return Ok(()),
+        Err(err) => return Err(CreateError::UnexpectedInput(err).into()),
+        Ok(()) => {}
+    }
+
     if config.extension {
         config.extension_options.validate_options()?;
     } else {
@@ -164,23 +344,26 @@ async fn new_project<T: AsRef<Path> + Debug>(
             Err(err) => return Err(err.into()),
             Ok(()) => {}
         }
+
+        match validate_deployment_options(config, ignore_default_prompts) {
+            Err(err) if is_user_cancellation_error(&err) => return Ok(()),
+            Err(err) => return Err(CreateError::UnexpectedInput(err).into()),
+            Ok(()) => {}
+        }
     }
 
     let globals = build_template_variables(config, &template_config, name)?;
-    let render_files = build_render_files(config, &template_config);
-    let ignore_files = build_ignore_files(config, &template_config);
 
     create_project(
         &path,
         &template.final_path(),
         &template_config,
         &globals,
-        &render_files,
-        &ignore_files,
+        config,
         replace,
     )
     .await?;
-    if config.open {
+    if config.open && !config.dry_run {
         let path_ref = path.as_ref();
         let path_str = path_ref
             .to_str()
@@ -191,19 +374,97 @@ async fn new_project<T: AsRef<Path> + Debug>(
     }
 }
 
-async fn get_template(config: &Config) -> Result<TemplateRoot> {
-    let progress = Progress::start("downloading template");
+/// Ask for the CI workflows and infrastructure stack to add to the project,
+/// and for the architecture to compile the function for when the project includes any deployment files.
+fn validate_deployment_options(
+    config: &mut Config,
+    no_interactive: bool,
+) -> Result<(), InquireError> {
+    config.ci_options.validate_options(no_interactive)?;
+    config.iac_options.validate_options(no_interactive)?;
+
+    let needs_arch = config.ci_options.enabled()
+        || config.iac_options.enabled()
+        || config.docker_options.enabled();
+    if needs_arch && config.arch.is_none() && !no_interactive && is_stdin_tty() {
+        config.arch = Some(Architecture::ask()?);
+    }
 
-    let template_option = match config.template.as_deref() {
+    Ok(())
+}
+
+#[tracing::instrument(target = "cargo_lambda")]
+async fn sync_project(path: &Path, config: &mut Config) -> Result<()> {
+    tracing::trace!(?path, ?config, "syncing project with its template");
+
+    let record = sync::TemplateRecord::load(path)?;
+    if config.template.is_none() {
+        config.template = Some(record.template.clone());
+    }
+
+    let template = get_template(config).await?;
+    template.cleanup();
+
+    let template_config = template::config::parse_template_config(template.config_path())?;
+
+    let mut globals = record.variables()?;
+    globals.extend(template_config.ask_secret_options(config.no_interactive)?);
+    globals.extend(render_variables(config));
+    restore_deployment_options(config, &globals);
+
+    let render_dir = render_template(&template.final_path(), &template_config, &globals, config)?;
+    let render_path = render_dir.path();
+    validate::validate_project(render_path, config.validate).await?;
+
+    let entries = sync::compare_files(render_path, path, &record)?;
+    print!("{}", sync::format_sync(&entries, config.dry_run));
+
+    if config.dry_run {
+        return Ok(());
+    }
+
+    sync::apply_changes(path, &entries)?;
+    sync::TemplateRecord::new(
+        template_location(config),
+        &globals,
+        &template_config.secret_prompts(),
+        render_path,
+    )?
+    .save(path)
+}
+
+/// Restore the package metadata, binaries, CI, infrastructure, and container options from the variables
+/// recorded when the project was created, unless they are set in the command line.
+fn restore_deployment_options(config: &mut Config, variables: &Object) {
+    config.package_options.restore(variables);
+    config.binaries_options.restore(variables);
+    config.ci_options.restore(variables);
+    config.iac_options.restore(variables);
+    config.docker_options.restore(variables);
+
+    if config.arch.is_none() {
+        config.arch = variables
+            .get("architecture")
+            .and_then(|v| v.to_kstr().parse().ok());
+    }
+}
+
+/// Location of the template to create the project with.
+fn template_location(config: &Config) -> &str {
+    match config.template.as_deref() {
         Some(t) => t,
         None if config.extension => extensions::DEFAULT_TEMPLATE_URL,
         None => functions::DEFAULT_TEMPLATE_URL,
-    };
+    }
+}
 
-    let template_source = TemplateSource::try_from(template_option);
+async fn get_template(config: &Config) -> Result<TemplateRoot> {
+    let progress = Progress::start("downloading template");
+
+    let template_source = TemplateSource::try_from(template_location(config));
     match template_source {
         Ok(ts) => {
-            let result = ts.expand().await;
+            let result = ts.expand(config.offline).await;
             progress.finish_and_clear();
             result
         }
@@ -220,12 +481,82 @@ async fn create_project<T: AsRef<Path> + Debug>(
     template_path: &Path,
     template_config: &TemplateConfig,
     globals: &Object,
-    render_files: &[PathBuf],
-    ignore_files: &[PathBuf],
+    config: &Config,
     replace: bool,
 ) -> Result<()> {
+    let render_dir = render_template(template_path, template_config, globals, config)?;
+    let render_path = render_dir.path();
+
+    // Templates rendered in an existing package are merged into its Cargo.toml,
+    // the rest of the package's files are preserved.
+    let merged_manifest = if !replace && path.as_ref().join("Cargo.toml").is_file() {
+        Some(merge::merge_into_package(
+            render_path,
+            path.as_ref(),
+            config.bin_name.as_deref(),
+        )?)
+    } else {
+        None
+    };
+
+    // `cargo metadata` can't validate a merged manifest until the new files are in the package.
+    validate::validate_project(render_path, config.validate && merged_manifest.is_none()).await?;
+
+    if config.dry_run {
+        let mut entries = preview::preview_project(render_path, path.as_ref(), replace)?;
+        if merged_manifest.is_some() {
+            for entry in entries
+                .iter_mut()
+                .filter(|e| e.path == Path::new("Cargo.toml"))
+            {
+                if entry.status == preview::FileStatus::Keep {
+                    entry.status = preview::FileStatus::Replace;
+                }
+            }
+        }
+        print!("{}", preview::format_preview(path.as_ref(), &entries));
+        return Ok(());
+    }
+
+    // The record needs to be built before copying the files, because copying removes the render directory.
+    let record = sync::TemplateRecord::new(
+        template_location(config),
+        globals,
+        &template_config.secret_prompts(),
+        render_path,
+    )?;
+
+    let res = if replace {
+        copy_and_replace(render_path, &path)
+    } else {
+        copy_without_replace(render_path, &path)
+    };
+
+    res.into_diagnostic().wrap_err_with(|| {
+        format!("failed to create package: template {render_path:?} to {path:?}")
+    })?;
+
+    if let Some(manifest) = merged_manifest {
+        std::fs::write(path.as_ref().join("Cargo.toml"), manifest)
+            .into_diagnostic()
+            .wrap_err("failed to update the package's Cargo.toml")?;
+    }
+
+    record.save(path.as_ref())
+}
+
+/// Render the template in a temporary directory.
+fn render_template(
+    template_path: &Path,
+    template_config: &TemplateConfig,
+    globals: &Object,
+    config: &Config,
+) -> Result<TempDir> {
     tracing::trace!("rendering new project's template");
 
+    let render_files = build_render_files(config, template_config);
+    let ignore_files = build_ignore_files(config, template_config);
+
     let parser = ParserBuilder::with_stdlib().build().into_diagnostic()?;
 
     let render_dir = tempfile::tempdir().into_diagnostic()?;
@@ -246,65 +577,86 @@ async fn create_project<T: AsRef<Path> + Debug>(
                     .into_diagnostic()
                     .wrap_err_with(|| format!("unable to create directory: {entry_path:?}"))?;
             }
-        } else if entry_name == "cargo-lambda-template.zip" {
+        } else if entry_name == "cargo-lambda-template.zip" || entry_name == harness::TEST_MANIFEST
+        {
             continue;
         } else {
             let relative = entry_path.strip_prefix(template_path).into_diagnostic()?;
 
-            if should_ignore_file(relative, ignore_files, template_config, globals) {
+            if should_ignore_file(relative, &ignore_files, template_config, globals) {
                 continue;
             }
 
-            let mut new_path = render_path.join(relative);
-            if let Some(path) = render_path_with_variables(&new_path, &parser, globals) {
-                new_path = path;
-            }
-
-            let parent_name = if let Some(parent) = new_path.parent() {
-                create_dir_all(parent).into_diagnostic()?;
-                parent.file_name().and_then(|p| p.to_str())
-            } else {
-                None
-            };
-
-            if entry_name == "Cargo.toml"
-                || entry_name == "README.md"
-                || (entry_name == "main.rs" && parent_name == Some("src"))
-                || (entry_name == "lib.rs" && parent_name == Some("src"))
-                || parent_name == Some("bin")
-                || should_render_file(relative, render_files, template_config, globals)
-            {
-                let template = parser.parse_file(entry_path).into_diagnostic()?;
-
-                let mut file = File::create(&new_path)
-                    .into_diagnostic()
-                    .wrap_err_with(|| format!("unable to create file: {new_path:?}"))?;
-
-                template
-                    .render_to(&mut file, globals)
-                    .into_diagnostic()
-                    .wrap_err_with(|| format!("failed to render template file: {:?}", &new_path))?;
-            } else {
-                copy_file(entry_path, &new_path)
-                    .into_diagnostic()
-                    .wrap_err_with(|| {
-                        format!(
-                            "failed to copy file: from {:?} to {:?}",
-                            &entry_path, &new_path
-                        )
-                    })?;
+            for variables in file_variables(relative, globals) {
+                let mut new_path = render_path.join(relative);
+                if let Some(path) = render_path_with_variables(&new_path, &parser, &variables) {
+                    new_path = path;
+                }
+
+                let parent_name = if let Some(parent) = new_path.parent() {
+                    create_dir_all(parent).into_diagnostic()?;
+                    parent.file_name().and_then(|p| p.to_str())
+                } else {
+                    None
+                };
+
+                if entry_name == "Cargo.toml"
+                    || entry_name == "README.md"
+                    || (entry_name == "main.rs" && parent_name == Some("src"))
+                    || (entry_name == "lib.rs" && parent_name == Some("src"))
+                    || parent_name == Some("bin")
+                    || should_render_file(relative, &render_files, template_config, &variables)
+                {
+                    let template = parser.parse_file(entry_path).into_diagnostic()?;
+
+                    let mut file = File::create(&new_path)
+                        .into_diagnostic()
+                        .wrap_err_with(|| format!("unable to create file: {new_path:?}"))?;
+
+                    template
+                        .render_to(&mut file, &variables)
+                        .into_diagnostic()
+                        .wrap_err_with(|| {
+                            format!("failed to render template file: {:?}", &new_path)
+                        })?;
+                } else {
+                    copy_file(entry_path, &new_path)
+                        .into_diagnostic()
+                        .wrap_err_with(|| {
+                            format!(
+                                "failed to copy file: from {:?} to {:?}",
+                                &entry_path, &new_path
+                            )
+                        })?;
+                }
             }
         }
     }
 
-    let res = if replace {
-        copy_and_replace(render_path, &path)
-    } else {
-        copy_without_replace(render_path, &path)
-    };
-
-    res.into_diagnostic()
-        .wrap_err_with(|| format!("failed to create package: template {render_path:?} to {path:?}"))
+    config
+        .binaries_options
+        .render_binaries(render_path, globals)?;
+    config
+        .package_options
+        .render_files(render_path, &parser, globals)?;
+    config
+        .ci_options
+        .render_workflows(render_path, &parser, globals)?;
+    config
+        .iac_options
+        .render_files(render_path, &parser, globals)?;
+    config
+        .docker_options
+        .render_files(render_path, &parser, globals)?;
+    config.remote_options.seed_metadata(render_path)?;
+    config.extension_options.render_archetype(
+        render_path,
+        &parser,
+        globals,
+        template_location(config) == extensions::DEFAULT_TEMPLATE_URL,
+    )?;
+
+    Ok(render_dir)
 }
 
 pub(crate) fn validate_name(name: &str) -> Result<()> {
@@ -363,15 +715,24 @@ fn build_template_variables(
     template_config: &TemplateConfig,
     name: &str,
 ) -> Result<Object> {
-    let mut variables = liquid::object!({
+    let mut variables = context::variables();
+    variables.extend(liquid::object!({
         "project_name": name,
         "binary_name": config.bin_name,
-    });
+    }));
+    variables.extend(config.package_options.variables());
 
     if config.extension {
         variables.extend(config.extension_options.variables()?);
     } else {
         variables.extend(config.function_options.variables(name, &config.bin_name)?);
+        variables.extend(config.binaries_options.variables());
+        variables.extend(config.ci_options.variables());
+        variables.extend(config.iac_options.variables());
+
+        let arch = config.arch.unwrap_or_default();
+        variables.extend(config.docker_options.variables(arch));
+        variables.extend(arch.variables(name, &config.bin_name));
     };
 
     if !template_config.prompts.is_empty() {
@@ -415,38 +776,20 @@ fn should_render_file(
         return false;
     };
 
-    if render_files.contains(&PathBuf::from(&unix_path)) {
+    if render_files
+        .iter()
+        .any(|p| p.to_str().is_some_and(|p| path_matches(p, &unix_path)))
+    {
         return true;
     }
 
-    let condition = template_config
-        .render_conditional_files
-        .get(&unix_path)
+    matching_condition(&template_config.render_conditional_files, &unix_path)
         .or_else(|| {
             relative
                 .to_str()
                 .and_then(|s| template_config.render_conditional_files.get(s))
-        });
-
-    if let Some(condition) = condition {
-        let Some(variable) = variables.get::<str>(&condition.var) else {
-            return false;
-        };
-
-        if let Some(condition_value) = &condition.r#match {
-            if condition_value.to_value() == *variable {
-                return true;
-            }
-        }
-
-        if let Some(condition_value) = &condition.not_match {
-            if condition_value.to_value() != *variable {
-                return true;
-            }
-        }
-    }
-
-    false
+        })
+        .is_some_and(|condition| condition_applies(condition, variables))
 }
 
 fn should_ignore_file(
@@ -463,40 +806,159 @@ fn should_ignore_file(
         return false;
     };
 
-    if ignore_files.contains(&PathBuf::from(&unix_path)) {
+    if ignore_files
+        .iter()
+        .any(|p| p.to_str().is_some_and(|p| path_matches(p, &unix_path)))
+    {
         return true;
     }
 
-    let condition = template_config
-        .ignore_conditional_files
-        .get(&unix_path)
+    matching_condition(&template_config.ignore_conditional_files, &unix_path)
         .or_else(|| {
             relative
                 .to_str()
                 .and_then(|s| template_config.ignore_conditional_files.get(s))
-        });
+        })
+        .is_some_and(|condition| condition_applies(condition, variables))
+}
 
-    if let Some(condition) = condition {
-        let Some(variable) = variables.get::<str>(&condition.var) else {
-            return false;
-        };
+/// Find the condition for a file in the conditions map.
+/// An exact match for the file wins over directories and glob patterns,
+/// and longer patterns win over shorter ones, so nested conditions override their parents.
+fn matching_condition<'a>(
+    conditions: &'a HashMap<String, RenderCondition>,
+    unix_path: &str,
+) -> Option<&'a RenderCondition> {
+    if let Some(condition) = conditions.get(unix_path) {
+        return Some(condition);
+    }
 
-        if let Some(condition_value) = &condition.r#match {
-            if condition_value.to_value() == *variable {
-                return true;
-            }
+    conditions
+        .iter()
+        .filter(|(pattern, _)| path_matches(pattern, unix_path))
+        .max_by_key(|(pattern, _)| pattern.len())
+        .map(|(_, condition)| condition)
+}
+
+fn condition_applies(condition: &RenderCondition, variables: &Object) -> bool {
+    let Some(variable) = variables.get::<str>(&condition.var) else {
+        return false;
+    };
+
+    if let Some(condition_value) = &condition.r#match {
+        if condition_value.to_value() == *variable {
+            return true;
         }
+    }
 
-        if let Some(condition_value) = &condition.not_match {
-            if condition_value.to_value() != *variable {
-                return true;
-            }
+    if let Some(condition_value) = &condition.not_match {
+        if condition_value.to_value() != *variable {
+            return true;
         }
     }
 
     false
 }
 
+/// Whether a file path matches a pattern from the template configuration.
+/// Patterns can be a file path, a directory that contains the file, like `infra` or `infra/`,
+/// or a glob, where `*` matches any character except `/`, and `**` matches across directories.
+fn path_matches(pattern: &str, unix_path: &str) -> bool {
+    let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+    if pattern == unix_path {
+        return true;
+    }
+
+    if !pattern.contains(['*', '?']) {
+        let dir = pattern.trim_end_matches('/');
+        return !dir.is_empty()
+            && unix_path
+                .strip_prefix(dir)
+                .is_some_and(|rest| rest.starts_with('/'));
+    }
+
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    // A glob that matches a directory also matches all the files in it.
+    regex.push_str("(?:/.*)?$");
+
+    Regex::new(&regex).is_ok_and(|re| re.is_match(unix_path))
+}
+
+/// Render files embedded in Cargo Lambda, like CI workflows, in the project.
+/// Files that the template already includes are not replaced.
+pub(crate) fn render_embedded_files(
+    project_path: &Path,
+    parser: &Parser,
+    globals: &Object,
+    files: &[(&str, &str)],
+) -> Result<()> {
+    for (path, content) in files {
+        let path = project_path.join(path);
+        if path.exists() {
+            tracing::debug!(?path, "template already includes the file, skipping it");
+            continue;
+        }
+
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent).into_diagnostic()?;
+        }
+
+        let template = parser.parse(content).into_diagnostic()?;
+        let mut file = File::create(&path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("unable to create file: {path:?}"))?;
+
+        template
+            .render_to(&mut file, globals)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to render template file: {path:?}"))?;
+    }
+
+    Ok(())
+}
+
+/// Variables to render a template file with.
+/// Files with `{{handler_module}}` in their path are rendered once for each event type,
+/// with the variables of that event type added to the global variables.
+fn file_variables(relative: &Path, globals: &Object) -> Vec<Object> {
+    if !relative.to_string_lossy().contains(HANDLER_MODULE_VARIABLE) {
+        return vec![globals.clone()];
+    }
+
+    let Some(Value::Array(event_types)) = globals.get("event_types") else {
+        return vec![globals.clone()];
+    };
+
+    event_types
+        .iter()
+        .filter_map(|event_type| match event_type {
+            Value::Object(event_type) => {
+                let mut variables = globals.clone();
+                variables.extend(event_type.clone());
+                Some(variables)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
 fn render_path_with_variables(path: &Path, parser: &Parser, variables: &Object) -> Option<PathBuf> {
     let re = regex::Regex::new(r"\{\{[^/]*\}\}").ok()?;
 
@@ -701,4 +1163,182 @@ mod tests {
             &variables
         ));
     }
+
+    #[test]
+    fn test_path_matches() {
+        assert!(path_matches("src/main.rs", "src/main.rs"));
+        assert!(path_matches("infra", "infra/lib/stack.ts"));
+        assert!(path_matches("infra/", "infra/main.tf"));
+        assert!(path_matches("./infra", "infra/main.tf"));
+        assert!(!path_matches("infra", "infrastructure.md"));
+        assert!(!path_matches("src/main.rs", "src/main.rs.bak"));
+
+        assert!(path_matches("src/*.rs", "src/http.rs"));
+        assert!(!path_matches("src/*.rs", "src/bin/http.rs"));
+        assert!(path_matches("src/**/*.rs", "src/http.rs"));
+        assert!(path_matches("src/**/*.rs", "src/bin/handlers/http.rs"));
+        assert!(path_matches("**/*.tf", "infra/modules/main.tf"));
+        assert!(path_matches("infra/*", "infra/lib/stack.ts"));
+        assert!(path_matches("src/handler_?.rs", "src/handler_a.rs"));
+        assert!(!path_matches("*.md", "docs/README.md"));
+        assert!(path_matches("*.md", "README.md"));
+    }
+
+    #[test]
+    fn test_ignore_directory_with_condition() {
+        let mut template_config = TemplateConfig::default();
+        template_config.ignore_conditional_files.insert(
+            "infra".into(),
+            RenderCondition {
+                var: "iac_provider".into(),
+                r#match: Some(PromptValue::String("none".into())),
+                not_match: None,
+            },
+        );
+        template_config.ignore_conditional_files.insert(
+            "infra/README.md".into(),
+            RenderCondition {
+                var: "docs".into(),
+                r#match: Some(PromptValue::Boolean(false)),
+                not_match: None,
+            },
+        );
+
+        let variables = liquid::object!({ "iac_provider": "none", "docs": true });
+        let path: PathBuf = ["infra", "lib", "stack.ts"].iter().collect();
+        assert!(should_ignore_file(&path, &[], &template_config, &variables));
+
+        // The exact match for the file wins over the directory condition.
+        let path: PathBuf = ["infra", "README.md"].iter().collect();
+        assert!(!should_ignore_file(
+            &path,
+            &[],
+            &template_config,
+            &variables
+        ));
+
+        let variables = liquid::object!({ "iac_provider": "cdk", "docs": true });
+        let path: PathBuf = ["infra", "lib", "stack.ts"].iter().collect();
+        assert!(!should_ignore_file(
+            &path,
+            &[],
+            &template_config,
+            &variables
+        ));
+    }
+
+    #[test]
+    fn test_render_files_with_glob_condition() {
+        let mut template_config = TemplateConfig::default();
+        template_config.render_conditional_files.insert(
+            ".github/**/*.yml".into(),
+            RenderCondition {
+                var: "github_actions".into(),
+                r#match: Some(PromptValue::Boolean(true)),
+                not_match: None,
+            },
+        );
+
+        let variables = liquid::object!({ "github_actions": true });
+        let path: PathBuf = [".github", "workflows", "build.yml"].iter().collect();
+        assert!(should_render_file(&path, &[], &template_config, &variables));
+
+        let path: PathBuf = [".github", "CODEOWNERS"].iter().collect();
+        assert!(!should_render_file(
+            &path,
+            &[],
+            &template_config,
+            &variables
+        ));
+
+        let render_files = vec![PathBuf::from("docs/")];
+        let path: PathBuf = ["docs", "index.md"].iter().collect();
+        assert!(should_render_file(
+            &path,
+            &render_files,
+            &TemplateConfig::default(),
+            &variables
+        ));
+    }
+
+    #[test]
+    fn test_file_variables_for_each_event_type() {
+        let globals = liquid::object!({
+            "project_name": "basic-lambda",
+            "event_types": [
+                { "event_type": "SqsEvent", "handler_module": "sqs_event" },
+                { "event_type": "S3Event", "handler_module": "s3_event" },
+            ],
+        });
+
+        let variables = file_variables(Path::new("src/{{handler_module}}.rs"), &globals);
+        assert_eq!(2, variables.len());
+        assert_eq!(
+            Some(&Value::scalar("s3_event")),
+            variables[1].get("handler_module")
+        );
+        assert_eq!(
+            Some(&Value::scalar("basic-lambda")),
+            variables[1].get("project_name")
+        );
+
+        let parser = ParserBuilder::with_stdlib().build().unwrap();
+        let path = render_path_with_variables(
+            Path::new("src/{{handler_module}}.rs"),
+            &parser,
+            &variables[0],
+        );
+        assert_eq!(Some(PathBuf::from("src/sqs_event.rs")), path);
+
+        let variables = file_variables(Path::new("src/main.rs"), &globals);
+        assert_eq!(1, variables.len());
+        assert!(variables[0].get("handler_module").is_none());
+    }
+
+    #[test]
+    fn test_apply_templates_config() {
+        #[derive(clap::Parser)]
+        struct Cli {
+            #[command(flatten)]
+            config: Config,
+        }
+
+        let templates = TemplatesConfig {
+            function: Some("https://git.example.com/function-template".into()),
+            extension: Some("https://git.example.com/extension-template".into()),
+            render_vars: HashMap::from([
+                ("team".into(), "platform".into()),
+                ("region".into(), "us-east-1".into()),
+            ]),
+        };
+
+        let mut cli = <Cli as clap::Parser>::parse_from(["new", "--render-var", "team=payments"]);
+        cli.config.apply_templates_config(&templates);
+        assert_eq!(
+            Some("https://git.example.com/function-template"),
+            cli.config.template.as_deref()
+        );
+
+        let variables = render_variables(&cli.config);
+        assert_eq!(Some(&Value::scalar("payments")), variables.get("team"));
+        assert_eq!(Some(&Value::scalar("us-east-1")), variables.get("region"));
+
+        let mut cli = <Cli as clap::Parser>::parse_from([
+            "new",
+            "--extension",
+            "--template",
+            "https://github.com/calavera/custom-template",
+        ]);
+        cli.config.apply_templates_config(&templates);
+        assert_eq!(
+            Some("https://github.com/calavera/custom-template"),
+            cli.config.template.as_deref()
+        );
+
+        let mut cli = <Cli as clap::Parser>::parse_from(["new", "--extension"]);
+        cli.config
+            .apply_templates_config(&TemplatesConfig::default());
+        assert_eq!(None, cli.config.template);
+        assert_eq!(None, cli.config.render_var);
+    }
 }
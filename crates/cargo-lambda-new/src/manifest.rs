@@ -0,0 +1,134 @@
+use miette::{IntoDiagnostic, Result, WrapErr};
+use toml_edit::{table, value, DocumentMut, Item, Value};
+
+/// Add dependencies at the end of the `[dependencies]` table,
+/// unless the manifest already includes them.
+pub(crate) fn add_dependencies(manifest: &str, dependencies: &[(&str, &str)]) -> Result<String> {
+    let mut doc = manifest
+        .parse::<DocumentMut>()
+        .into_diagnostic()
+        .wrap_err("failed to parse Cargo.toml")?;
+
+    let Some(declared) = doc
+        .entry("dependencies")
+        .or_insert(table())
+        .as_table_like_mut()
+    else {
+        return Ok(manifest.to_string());
+    };
+
+    for (name, spec) in dependencies {
+        if declared.contains_key(name) {
+            continue;
+        }
+        let spec = spec
+            .parse::<Value>()
+            .into_diagnostic()
+            .wrap_err_with(|| format!("invalid version requirement for `{name}`"))?;
+        declared.insert(name, Item::Value(spec));
+    }
+
+    Ok(doc.to_string())
+}
+
+/// Set a string field in the `[package]` table of a manifest,
+/// replacing the field if the manifest already declares it.
+pub(crate) fn set_package_field(manifest: &str, key: &str, field: &str) -> Result<String> {
+    let mut doc = manifest
+        .parse::<DocumentMut>()
+        .into_diagnostic()
+        .wrap_err("failed to parse Cargo.toml")?;
+
+    let Some(package) = doc.get_mut("package").and_then(|p| p.as_table_like_mut()) else {
+        return Ok(manifest.to_string());
+    };
+
+    match package.get_mut(key).and_then(|i| i.as_value_mut()) {
+        Some(existing) => {
+            // Keep the comments around the value that the field replaces.
+            let decor = existing.decor().clone();
+            *existing = Value::from(field);
+            *existing.decor_mut() = decor;
+        }
+        None => {
+            package.insert(key, value(field));
+        }
+    }
+
+    Ok(doc.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_dependencies() {
+        let manifest =
+            "[package]\nname = \"basic-lambda\"\n\n[dependencies]\ntokio.workspace = true\n";
+
+        let manifest =
+            add_dependencies(manifest, &[("tokio", "\"1\""), ("serde", "\"1\"")]).unwrap();
+        assert_eq!(
+            "[package]\nname = \"basic-lambda\"\n\n[dependencies]\ntokio.workspace = true\nserde = \"1\"\n",
+            manifest
+        );
+
+        let manifest = add_dependencies(
+            "[package]\nname = \"basic-lambda\"\n",
+            &[("serde", "\"1\"")],
+        )
+        .unwrap();
+        assert_eq!(
+            "[package]\nname = \"basic-lambda\"\n\n[dependencies]\nserde = \"1\"\n",
+            manifest
+        );
+
+        let manifest = add_dependencies(
+            "[package]\nname = \"basic-lambda\"\n\n[dependencies]\n# Runtime\nlambda_runtime = \"0.13\"\n\n[dev-dependencies]\nserde = \"1\"\n",
+            &[("serde", r#"{ version = "1", features = ["derive"] }"#)],
+        )
+        .unwrap();
+        assert_eq!(
+            "[package]\nname = \"basic-lambda\"\n\n[dependencies]\n# Runtime\nlambda_runtime = \"0.13\"\nserde = { version = \"1\", features = [\"derive\"] }\n\n[dev-dependencies]\nserde = \"1\"\n",
+            manifest
+        );
+    }
+
+    #[test]
+    fn test_set_package_field() {
+        let manifest = "[package]\nname = \"basic-lambda\"\ndescription = \"old\"\n\n[dependencies]\ntokio = \"1\"\n";
+
+        let manifest = set_package_field(manifest, "license", "MIT").unwrap();
+        assert_eq!(
+            "[package]\nname = \"basic-lambda\"\ndescription = \"old\"\nlicense = \"MIT\"\n\n[dependencies]\ntokio = \"1\"\n",
+            manifest
+        );
+
+        let manifest =
+            crate::manifest::set_package_field(&manifest, "description", "Sends \"emails\"")
+                .unwrap();
+        let parsed: toml::Table = toml::from_str(&manifest).unwrap();
+        assert_eq!(
+            "Sends \"emails\"",
+            parsed["package"]["description"].as_str().unwrap()
+        );
+        assert_eq!(1, manifest.matches("description").count());
+    }
+
+    #[test]
+    fn test_set_package_field_with_multiline_strings() {
+        let manifest = "[package] # the function\nname = \"basic-lambda\"\ndescription = \"\"\"\nline = \"one\"\n[two]\n\"\"\"\n\n[dependencies]\ntokio = \"1\"\n";
+
+        let manifest = set_package_field(manifest, "description", "Sends emails").unwrap();
+        assert_eq!(
+            "[package] # the function\nname = \"basic-lambda\"\ndescription = \"Sends emails\"\n\n[dependencies]\ntokio = \"1\"\n",
+            manifest
+        );
+
+        let manifest = set_package_field(&manifest, "license", "MIT").unwrap();
+        let parsed: toml::Table = toml::from_str(&manifest).unwrap();
+        assert_eq!("MIT", parsed["package"]["license"].as_str().unwrap());
+        assert!(parsed.get("two").is_none());
+    }
+}
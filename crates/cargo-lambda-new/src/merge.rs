@@ -0,0 +1,387 @@
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::{
+    fs::{read_to_string, write},
+    path::Path,
+};
+use toml::{Table, Value};
+use toml_edit::{value, ArrayOfTables, DocumentMut, Item};
+
+use crate::{binaries, error::CreateError, manifest};
+
+/// Name of the package in an existing Cargo.toml file.
+pub(crate) fn package_name(package_path: &Path) -> Result<String> {
+    let manifest = read_manifest(package_path)?;
+    manifest
+        .get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        .map(String::from)
+        .ok_or_else(|| CreateError::MissingPackageTable(package_path.join("Cargo.toml")).into())
+}
+
+/// Prepare a rendered project to be merged into an existing package.
+/// The template's `src/main.rs` becomes a new binary, so it doesn't collide with the package's files,
+/// and the rendered Cargo.toml is replaced by the package's Cargo.toml, with the dependencies,
+/// binaries, and Lambda metadata that the template adds. It returns the merged Cargo.toml.
+pub(crate) fn merge_into_package(
+    render_path: &Path,
+    package_path: &Path,
+    bin_name: Option<&str>,
+) -> Result<String> {
+    let existing = read_to_string(package_path.join("Cargo.toml")).into_diagnostic()?;
+    let existing_table = read_manifest(package_path)?;
+
+    let name = package_name(package_path)?;
+    let bin = bin_name
+        .map(String::from)
+        .unwrap_or_else(|| default_bin_name(&existing_table, package_path, &name));
+
+    let moved = binaries::move_main_to_bins(&render_path.join("src"), &[bin.clone()])?;
+
+    let rendered = match read_to_string(render_path.join("Cargo.toml")) {
+        Ok(rendered) => toml::from_str(&rendered)
+            .into_diagnostic()
+            .wrap_err("invalid Cargo.toml in the template")?,
+        Err(_) => Table::new(),
+    };
+
+    let merged = merge_manifest(&existing, &existing_table, &rendered, moved.then_some(&bin))?;
+    write(render_path.join("Cargo.toml"), &merged).into_diagnostic()?;
+
+    Ok(merged)
+}
+
+fn read_manifest(package_path: &Path) -> Result<Table> {
+    let manifest_path = package_path.join("Cargo.toml");
+    let manifest = read_to_string(&manifest_path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to read {manifest_path:?}"))?;
+
+    toml::from_str(&manifest)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to parse {manifest_path:?}"))
+}
+
+/// Use the package name for the function's binary, unless the package already has a binary with that name.
+fn default_bin_name(existing: &Table, package_path: &Path, name: &str) -> String {
+    let src = package_path.join("src");
+    let taken = src.join("main.rs").is_file()
+        || src.join("bin").join(format!("{name}.rs")).is_file()
+        || src.join("bin").join(name).is_dir()
+        || bin_names(existing).iter().any(|b| b == name);
+
+    if taken {
+        format!("{name}-lambda")
+    } else {
+        name.to_string()
+    }
+}
+
+fn bin_names(manifest: &Table) -> Vec<String> {
+    manifest
+        .get("bin")
+        .and_then(|b| b.as_array())
+        .map(|bins| {
+            bins.iter()
+                .filter_map(|b| b.get("name").and_then(|n| n.as_str()))
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Add the rendered dependencies, binaries, and Lambda metadata that the existing manifest doesn't have,
+/// preserving the existing manifest's content and formatting.
+fn merge_manifest(
+    existing: &str,
+    existing_table: &Table,
+    rendered: &Table,
+    bin: Option<&String>,
+) -> Result<String> {
+    let existing_deps = existing_table
+        .get("dependencies")
+        .and_then(|d| d.as_table());
+    let dependencies = rendered
+        .get("dependencies")
+        .and_then(|d| d.as_table())
+        .map(|deps| {
+            deps.iter()
+                .filter(|(name, _)| !existing_deps.is_some_and(|d| d.contains_key(*name)))
+                .map(|(name, spec)| (name.clone(), spec.to_string()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let dependencies = dependencies
+        .iter()
+        .map(|(name, spec)| (name.as_str(), spec.as_str()))
+        .collect::<Vec<_>>();
+
+    let merged = manifest::add_dependencies(existing, &dependencies)?;
+    let mut doc = merged
+        .parse::<DocumentMut>()
+        .into_diagnostic()
+        .wrap_err("failed to parse Cargo.toml")?;
+
+    let existing_bins = bin_names(existing_table);
+    let rendered_bins = rendered
+        .get("bin")
+        .and_then(|b| b.as_array())
+        .cloned()
+        .unwrap_or_default();
+    for entry in rendered_bins {
+        let Value::Table(entry) = entry else {
+            continue;
+        };
+        let (Some(name), Some(path)) = (
+            entry.get("name").and_then(|n| n.as_str()),
+            entry.get("path").and_then(|p| p.as_str()),
+        ) else {
+            continue;
+        };
+        // The template's main binary moved to its own directory.
+        if path == "src/main.rs" || existing_bins.iter().any(|b| b == name) {
+            continue;
+        }
+        if let Item::Table(entry) = to_item(&Value::Table(entry))? {
+            push_bin(&mut doc, entry)?;
+        }
+    }
+
+    if let Some(bin) = bin.filter(|b| !existing_bins.contains(b)) {
+        let mut entry = toml_edit::Table::new();
+        entry.insert("name", value(bin.as_str()));
+        entry.insert("path", value(format!("src/bin/{bin}/main.rs")));
+        push_bin(&mut doc, entry)?;
+    }
+
+    let has_lambda_metadata = existing_table
+        .get("package")
+        .and_then(|p| p.get("metadata"))
+        .is_some_and(|m| m.get("lambda").is_some());
+    let rendered_lambda_metadata = rendered
+        .get("package")
+        .and_then(|p| p.get("metadata"))
+        .and_then(|m| m.get("lambda"));
+    if let (false, Some(lambda)) = (has_lambda_metadata, rendered_lambda_metadata) {
+        let metadata = doc
+            .get_mut("package")
+            .and_then(|p| p.as_table_like_mut())
+            .and_then(|p| {
+                p.entry("metadata")
+                    .or_insert(implicit_table())
+                    .as_table_like_mut()
+            })
+            .ok_or_else(|| miette::miette!("invalid `package.metadata` in Cargo.toml"))?;
+        metadata.insert("lambda", to_item(lambda)?);
+    }
+
+    Ok(doc.to_string())
+}
+
+/// Add a binary to the `[[bin]]` entries, or to the `bin` array if the manifest declares it inline.
+fn push_bin(doc: &mut DocumentMut, entry: toml_edit::Table) -> Result<()> {
+    let bins = doc
+        .entry("bin")
+        .or_insert(Item::ArrayOfTables(ArrayOfTables::new()));
+
+    if let Some(bins) = bins.as_array_of_tables_mut() {
+        bins.push(entry);
+    } else if let Some(bins) = bins.as_array_mut() {
+        bins.push(entry.into_inline_table());
+    } else {
+        return Err(miette::miette!("invalid `bin` section in Cargo.toml"));
+    }
+
+    Ok(())
+}
+
+/// Convert a value from the rendered manifest into an item for the existing manifest.
+/// Tables become standard tables, written with their full headers.
+pub(crate) fn to_item(value: &Value) -> Result<Item> {
+    match value {
+        Value::Table(table) => {
+            let mut output = toml_edit::Table::new();
+            output.set_implicit(true);
+            for (key, value) in table {
+                output.insert(key, to_item(value)?);
+            }
+            Ok(Item::Table(output))
+        }
+        value => value
+            .to_string()
+            .parse::<toml_edit::Value>()
+            .map(Item::Value)
+            .into_diagnostic()
+            .wrap_err("invalid value in the template's Cargo.toml"),
+    }
+}
+
+pub(crate) fn implicit_table() -> Item {
+    let mut table = toml_edit::Table::new();
+    table.set_implicit(true);
+    Item::Table(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::create_dir_all;
+
+    const EXISTING: &str = r#"[package]
+name = "inventory"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+# Shared with the rest of the workspace
+tokio = { workspace = true }
+"#;
+
+    const RENDERED: &str = r#"[package]
+name = "inventory"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+lambda_runtime = "0.13"
+tokio = { version = "1", features = ["macros"] }
+
+[package.metadata.lambda.deploy]
+memory = 512
+env = { RUST_LOG = "info" }
+"#;
+
+    #[test]
+    fn test_merge_into_package() {
+        let package = tempfile::tempdir().unwrap();
+        create_dir_all(package.path().join("src")).unwrap();
+        write(package.path().join("Cargo.toml"), EXISTING).unwrap();
+        write(package.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+
+        let render = tempfile::tempdir().unwrap();
+        create_dir_all(render.path().join("src")).unwrap();
+        write(render.path().join("Cargo.toml"), RENDERED).unwrap();
+        write(
+            render.path().join("src/main.rs"),
+            "mod event_handler;\nfn main() {}\n",
+        )
+        .unwrap();
+        write(render.path().join("src/event_handler.rs"), "").unwrap();
+
+        let merged = merge_into_package(render.path(), package.path(), None).unwrap();
+
+        // The existing content is preserved as it was.
+        assert!(merged.starts_with(
+            "[package]\nname = \"inventory\"\nversion = \"0.1.0\"\nedition = \"2021\"\n"
+        ));
+        assert!(merged.contains(
+            "[dependencies]\n# Shared with the rest of the workspace\ntokio = { workspace = true }\n"
+        ));
+        assert!(merged.contains("lambda_runtime = \"0.13\""));
+
+        let table: Table = toml::from_str(&merged).unwrap();
+        assert_eq!(
+            "inventory-lambda",
+            table["bin"][0]["name"].as_str().unwrap()
+        );
+        assert_eq!(
+            "src/bin/inventory-lambda/main.rs",
+            table["bin"][0]["path"].as_str().unwrap()
+        );
+        assert_eq!(
+            512,
+            table["package"]["metadata"]["lambda"]["deploy"]["memory"]
+                .as_integer()
+                .unwrap()
+        );
+        assert_eq!(
+            "info",
+            table["package"]["metadata"]["lambda"]["deploy"]["env"]["RUST_LOG"]
+                .as_str()
+                .unwrap()
+        );
+
+        let bin = render.path().join("src/bin/inventory-lambda");
+        assert!(bin.join("main.rs").is_file());
+        assert!(bin.join("event_handler.rs").is_file());
+        assert!(!render.path().join("src/main.rs").exists());
+        assert_eq!(
+            merged,
+            read_to_string(render.path().join("Cargo.toml")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_merge_manifest_with_bins_and_metadata() {
+        let existing = format!(
+            "{EXISTING}\n[[bin]]\nname = \"cli\"\npath = \"src/cli.rs\"\n\n[package.metadata.docs.rs]\nall-features = true\n"
+        );
+        let existing_table: Table = toml::from_str(&existing).unwrap();
+        let rendered: Table = toml::from_str(&format!(
+            "{RENDERED}\n[[bin]]\nname = \"worker\"\npath = \"src/bin/worker.rs\"\nrequired-features = [\"worker\"]\n"
+        ))
+        .unwrap();
+
+        let merged = merge_manifest(
+            &existing,
+            &existing_table,
+            &rendered,
+            Some(&"inventory-lambda".to_string()),
+        )
+        .unwrap();
+        assert!(merged.contains("[package.metadata.docs.rs]\nall-features = true\n"));
+
+        let table: Table = toml::from_str(&merged).unwrap();
+        let bins = table["bin"].as_array().unwrap();
+        assert_eq!(
+            vec!["cli", "worker", "inventory-lambda"],
+            bins.iter()
+                .map(|b| b["name"].as_str().unwrap())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("worker", bins[1]["required-features"][0].as_str().unwrap());
+        assert!(table["package"]["metadata"]["docs"]["rs"]["all-features"]
+            .as_bool()
+            .unwrap());
+        assert_eq!(
+            512,
+            table["package"]["metadata"]["lambda"]["deploy"]["memory"]
+                .as_integer()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_default_bin_name() {
+        let package = tempfile::tempdir().unwrap();
+        let existing: Table = toml::from_str(EXISTING).unwrap();
+        assert_eq!(
+            "inventory",
+            default_bin_name(&existing, package.path(), "inventory")
+        );
+
+        let existing: Table = toml::from_str(&format!(
+            "{EXISTING}\n[[bin]]\nname = \"inventory\"\npath = \"src/cli.rs\"\n"
+        ))
+        .unwrap();
+        assert_eq!(
+            "inventory-lambda",
+            default_bin_name(&existing, package.path(), "inventory")
+        );
+    }
+
+    #[test]
+    fn test_package_name() {
+        let package = tempfile::tempdir().unwrap();
+        write(package.path().join("Cargo.toml"), EXISTING).unwrap();
+        assert_eq!("inventory", package_name(package.path()).unwrap());
+
+        write(
+            package.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"inventory\"]\n",
+        )
+        .unwrap();
+        let err = package_name(package.path()).unwrap_err();
+        assert!(err.to_string().contains("virtual workspace"));
+    }
+}
@@ -0,0 +1,213 @@
+use cargo_lambda_interactive::{error::InquireError, is_stdin_tty, Select, Text};
+use chrono::Datelike;
+use clap::Args;
+use liquid::{model::ValueView, Object, Parser};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::{
+    fs::{read_to_string, write},
+    path::Path,
+};
+
+use crate::template::config::TemplateConfig;
+
+const MIT_LICENSE: &str = include_str!("../templates/license/MIT");
+const APACHE_LICENSE: &str = include_str!("../templates/license/Apache-2.0");
+
+#[derive(Args, Clone, Debug, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    /// License of the package: mit, apache-2.0, or none
+    #[arg(long)]
+    license: Option<License>,
+
+    /// Description of the package, added to Cargo.toml
+    #[arg(long)]
+    description: Option<String>,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, strum_macros::Display, strum_macros::EnumString,
+)]
+#[strum(ascii_case_insensitive)]
+pub(crate) enum License {
+    #[strum(serialize = "MIT")]
+    Mit,
+    #[strum(serialize = "Apache-2.0")]
+    Apache2,
+    #[default]
+    #[strum(serialize = "none")]
+    None,
+}
+
+impl License {
+    /// SPDX identifier of the license, used in Cargo.toml.
+    fn spdx(&self) -> Option<String> {
+        match self {
+            License::None => None,
+            license => Some(license.to_string()),
+        }
+    }
+
+    fn files(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            License::Mit => &[("LICENSE", MIT_LICENSE)],
+            License::Apache2 => &[("LICENSE", APACHE_LICENSE)],
+            License::None => &[],
+        }
+    }
+}
+
+impl Options {
+    /// Ask for the description and the license of the package, unless they're set in the command line.
+    /// The description is not asked if the template has its own `project_description` prompt.
+    pub(crate) fn validate_options(
+        &mut self,
+        no_interactive: bool,
+        template_config: &TemplateConfig,
+    ) -> Result<(), InquireError> {
+        if no_interactive || !is_stdin_tty() {
+            return Ok(());
+        }
+
+        if self.description.is_none()
+            && !template_config.prompts.contains_key("project_description")
+        {
+            let description = Text::new("What is the description of your project?")
+                .with_help_message("leave it empty to skip the description")
+                .prompt()?;
+            if !description.trim().is_empty() {
+                self.description = Some(description.trim().to_string());
+            }
+        }
+
+        if self.license.is_none() {
+            let license = Select::new(
+                "Which license would you like to use?",
+                vec![License::None, License::Mit, License::Apache2],
+            )
+            .prompt()?;
+            self.license = Some(license);
+        }
+
+        Ok(())
+    }
+
+    fn license(&self) -> License {
+        self.license.unwrap_or_default()
+    }
+
+    pub(crate) fn variables(&self) -> Object {
+        liquid::object!({
+            "project_description": self.description,
+            "project_license": self.license().spdx(),
+            "license_year": chrono::Utc::now().year(),
+        })
+    }
+
+    /// Restore the options recorded when the project was created, unless they're set in the command line.
+    pub(crate) fn restore(&mut self, variables: &Object) {
+        if self.description.is_none() {
+            self.description = variables
+                .get("project_description")
+                .filter(|v| !v.is_nil())
+                .map(|v| v.to_kstr().to_string());
+        }
+
+        if self.license.is_none() {
+            self.license = variables
+                .get("project_license")
+                .filter(|v| !v.is_nil())
+                .and_then(|v| v.to_kstr().parse().ok());
+        }
+    }
+
+    /// Render the LICENSE file, and add the license and the description to Cargo.toml.
+    /// Files that the template already includes are not replaced.
+    pub(crate) fn render_files(
+        &self,
+        project_path: &Path,
+        parser: &Parser,
+        globals: &Object,
+    ) -> Result<()> {
+        crate::render_embedded_files(project_path, parser, globals, self.license().files())?;
+
+        let manifest_path = project_path.join("Cargo.toml");
+        if !manifest_path.is_file() {
+            return Ok(());
+        }
+
+        let mut manifest = read_to_string(&manifest_path).into_diagnostic()?;
+        if let Some(description) = &self.description {
+            manifest = crate::manifest::set_package_field(&manifest, "description", description)?;
+        }
+        if let Some(license) = self.license().spdx() {
+            manifest = crate::manifest::set_package_field(&manifest, "license", &license)?;
+        }
+
+        write(&manifest_path, manifest)
+            .into_diagnostic()
+            .wrap_err("failed to add the package metadata to Cargo.toml")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use liquid::{model::Value, ParserBuilder};
+
+    #[test]
+    fn test_parse_license() {
+        assert_eq!(License::Mit, "mit".parse().unwrap());
+        assert_eq!(License::Apache2, "apache-2.0".parse().unwrap());
+        assert_eq!(License::None, "none".parse().unwrap());
+        assert_eq!(Some("Apache-2.0".to_string()), License::Apache2.spdx());
+    }
+
+    #[test]
+    fn test_render_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"basic-lambda\"\n",
+        )
+        .unwrap();
+
+        let options = Options {
+            license: Some(License::Mit),
+            description: Some("Resizes images".into()),
+        };
+
+        let parser = ParserBuilder::with_stdlib().build().unwrap();
+        let mut globals = options.variables();
+        globals.insert("project_name".into(), Value::scalar("basic-lambda"));
+        options.render_files(dir.path(), &parser, &globals).unwrap();
+
+        let license = read_to_string(dir.path().join("LICENSE")).unwrap();
+        assert!(license.contains("The basic-lambda authors"));
+        assert!(license.contains("MIT License"));
+
+        let manifest: toml::Table =
+            toml::from_str(&read_to_string(dir.path().join("Cargo.toml")).unwrap()).unwrap();
+        assert_eq!("MIT", manifest["package"]["license"].as_str().unwrap());
+        assert_eq!(
+            "Resizes images",
+            manifest["package"]["description"].as_str().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut options = Options::default();
+        options.restore(&liquid::object!({
+            "project_description": "Resizes images",
+            "project_license": "Apache-2.0",
+        }));
+        assert_eq!(Some(License::Apache2), options.license);
+        assert_eq!(Some("Resizes images"), options.description.as_deref());
+
+        let mut options = Options::default();
+        options.restore(&liquid::object!({ "project_description": nil, "project_license": nil }));
+        assert_eq!(None, options.license);
+        assert_eq!(None, options.description);
+    }
+}
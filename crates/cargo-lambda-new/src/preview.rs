@@ -0,0 +1,221 @@
+use miette::{IntoDiagnostic, Result};
+use similar::TextDiff;
+use std::{
+    fmt::Write as _,
+    fs::read,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// What would happen to a rendered file if the project was created.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum FileStatus {
+    /// The file doesn't exist, and it would be created.
+    Create,
+    /// The file exists with different content, and it would be replaced.
+    Replace,
+    /// The file exists with different content, and it would be preserved.
+    Keep,
+    /// The file exists with the same content.
+    Unchanged,
+}
+
+impl FileStatus {
+    fn marker(&self) -> char {
+        match self {
+            FileStatus::Create => '+',
+            FileStatus::Replace => '~',
+            FileStatus::Keep => '!',
+            FileStatus::Unchanged => '=',
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            FileStatus::Create => "new file",
+            FileStatus::Replace => "replaces the existing file",
+            FileStatus::Keep => "the existing file is preserved",
+            FileStatus::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// Rendered file compared with the file in the destination directory.
+#[derive(Debug)]
+pub(crate) struct PreviewEntry {
+    pub(crate) path: PathBuf,
+    pub(crate) status: FileStatus,
+    pub(crate) diff: Option<String>,
+}
+
+/// Compare the files rendered in `render_path` with the files in the destination `path`.
+/// Existing files are replaced only if `replace` is true, like when the project is created.
+pub(crate) fn preview_project(
+    render_path: &Path,
+    path: &Path,
+    replace: bool,
+) -> Result<Vec<PreviewEntry>> {
+    let mut entries = Vec::new();
+
+    let walk_dir = WalkDir::new(render_path)
+        .follow_links(false)
+        .sort_by_file_name();
+    for entry in walk_dir {
+        let entry = entry.into_diagnostic()?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+
+        let relative = entry
+            .path()
+            .strip_prefix(render_path)
+            .into_diagnostic()?
+            .to_path_buf();
+
+        let existing_path = path.join(&relative);
+        if !existing_path.is_file() {
+            entries.push(PreviewEntry {
+                path: relative,
+                status: FileStatus::Create,
+                diff: None,
+            });
+            continue;
+        }
+
+        let existing = read(&existing_path).into_diagnostic()?;
+        let rendered = read(entry.path()).into_diagnostic()?;
+        if existing == rendered {
+            entries.push(PreviewEntry {
+                path: relative,
+                status: FileStatus::Unchanged,
+                diff: None,
+            });
+            continue;
+        }
+
+        let diff = match (String::from_utf8(existing), String::from_utf8(rendered)) {
+            (Ok(existing), Ok(rendered)) => {
+                let name = relative.to_string_lossy();
+                let diff = TextDiff::from_lines(&existing, &rendered)
+                    .unified_diff()
+                    .context_radius(3)
+                    .header(&format!("a/{name}"), &format!("b/{name}"))
+                    .to_string();
+                Some(diff)
+            }
+            _ => None,
+        };
+
+        let status = if replace {
+            FileStatus::Replace
+        } else {
+            FileStatus::Keep
+        };
+
+        entries.push(PreviewEntry {
+            path: relative,
+            status,
+            diff,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Format the list of files that the template would create, followed by
+/// the differences with the files that already exist in the destination.
+pub(crate) fn format_preview(path: &Path, entries: &[PreviewEntry]) -> String {
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "Dry run: no files were written to {}\n",
+        path.display()
+    );
+
+    for entry in entries {
+        let _ = writeln!(
+            output,
+            "  {} {} ({})",
+            entry.status.marker(),
+            entry.path.display(),
+            entry.status.description()
+        );
+    }
+
+    for entry in entries {
+        match (&entry.status, &entry.diff) {
+            (FileStatus::Replace | FileStatus::Keep, Some(diff)) => {
+                let _ = write!(output, "\n{diff}");
+            }
+            (FileStatus::Replace | FileStatus::Keep, None) => {
+                let _ = writeln!(
+                    output,
+                    "\nBinary files a/{0} and b/{0} differ",
+                    entry.path.display()
+                );
+            }
+            _ => {}
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+
+    fn write_file(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        create_dir_all(path.parent().unwrap()).unwrap();
+        write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_preview_project() {
+        let render_dir = tempfile::tempdir().unwrap();
+        write_file(
+            render_dir.path(),
+            "Cargo.toml",
+            "[package]\nname = \"basic\"\n",
+        );
+        write_file(render_dir.path(), "README.md", "# Basic\n");
+        write_file(render_dir.path(), "src/main.rs", "fn main() {}\n");
+
+        let project_dir = tempfile::tempdir().unwrap();
+        write_file(project_dir.path(), "README.md", "# Basic\n");
+        write_file(
+            project_dir.path(),
+            "src/main.rs",
+            "fn main() {\n    println!(\"hello\");\n}\n",
+        );
+
+        let entries = preview_project(render_dir.path(), project_dir.path(), false).unwrap();
+        let statuses = entries
+            .iter()
+            .map(|e| (e.path.to_string_lossy().replace('\\', "/"), e.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("Cargo.toml".to_string(), FileStatus::Create),
+                ("README.md".to_string(), FileStatus::Unchanged),
+                ("src/main.rs".to_string(), FileStatus::Keep),
+            ],
+            statuses
+        );
+
+        let diff = entries[2].diff.as_deref().unwrap();
+        assert!(diff.contains("-    println!(\"hello\");"));
+        assert!(diff.contains("+fn main() {}"));
+
+        let entries = preview_project(render_dir.path(), project_dir.path(), true).unwrap();
+        assert_eq!(FileStatus::Replace, entries[2].status);
+
+        let output = format_preview(project_dir.path(), &entries);
+        assert!(output.contains("+ Cargo.toml (new file)"));
+        assert!(output.contains("= README.md (unchanged)"));
+
+        assert!(!project_dir.path().join("Cargo.toml").exists());
+    }
+}
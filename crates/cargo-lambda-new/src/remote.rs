@@ -0,0 +1,462 @@
+use cargo_lambda_remote::{
+    aws_sdk_lambda::{
+        operation::get_function_configuration::GetFunctionConfigurationOutput,
+        Client as LambdaClient,
+    },
+    RemoteConfig,
+};
+use clap::Args;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fs::{read_to_string, write},
+    path::Path,
+};
+use toml_edit::DocumentMut;
+
+use crate::{
+    architecture::Architecture,
+    merge::{implicit_table, to_item},
+};
+
+/// Runtime that Cargo Lambda deploys functions with by default.
+const DEFAULT_RUNTIME: &str = "provided.al2023";
+
+#[derive(Args, Clone, Debug, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    /// Name or ARN of a function deployed in AWS Lambda to import its configuration into the new project
+    #[arg(long, value_name = "FUNCTION_NAME", conflicts_with = "extension")]
+    from_remote: Option<String>,
+
+    /// Add the environment variables of the deployed function to the new project's Cargo.toml.
+    /// Review them before committing the file, they can include secrets
+    #[arg(long, requires = "from_remote")]
+    from_remote_env: bool,
+
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Configuration fetched from the deployed function.
+    #[arg(skip)]
+    function: Option<RemoteFunction>,
+}
+
+/// Configuration of a function deployed in AWS Lambda.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct RemoteFunction {
+    memory: Option<i32>,
+    timeout: Option<i32>,
+    env: BTreeMap<String, String>,
+    layers: Vec<String>,
+    role: Option<String>,
+    tracing: Option<String>,
+    runtime: Option<String>,
+    architecture: Option<Architecture>,
+    /// Whether the function has a function URL, which makes it an HTTP function.
+    http: bool,
+    /// Event types that the function receives from its event source mappings.
+    event_types: Vec<String>,
+}
+
+/// Lambda metadata seeded in the new project's Cargo.toml.
+#[derive(Debug, Serialize)]
+struct LambdaMetadata<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    build: Option<BuildMetadata>,
+    deploy: DeployMetadata<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct BuildMetadata {
+    arm64: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct DeployMetadata<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracing: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    runtime: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    layers: &'a [String],
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    env: &'a BTreeMap<String, String>,
+}
+
+impl Options {
+    /// Fetch the configuration of the deployed function, if the project imports one.
+    pub(crate) async fn fetch_function(&mut self) -> Result<()> {
+        let Some(name) = &self.from_remote else {
+            return Ok(());
+        };
+
+        let sdk_config = self.remote_config.sdk_config(None).await?;
+        let client = LambdaClient::new(&sdk_config);
+
+        let configuration = client
+            .get_function_configuration()
+            .function_name(name)
+            .set_qualifier(self.remote_config.alias.clone())
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err_with(|| {
+                format!("failed to fetch the configuration of the function {name}")
+            })?;
+
+        let mut function = RemoteFunction::from_configuration(&configuration);
+
+        function.http = client
+            .get_function_url_config()
+            .function_name(name)
+            .set_qualifier(self.remote_config.alias.clone())
+            .send()
+            .await
+            .is_ok();
+
+        if !function.http {
+            let mappings = client
+                .list_event_source_mappings()
+                .function_name(name)
+                .send()
+                .await
+                .into_diagnostic()
+                .wrap_err_with(|| {
+                    format!("failed to fetch the event sources of the function {name}")
+                })?;
+
+            for mapping in mappings.event_source_mappings() {
+                let event_type = mapping
+                    .event_source_arn()
+                    .and_then(event_type_from_source_arn);
+                if let Some(event_type) = event_type {
+                    if !function.event_types.iter().any(|e| e == event_type) {
+                        function.event_types.push(event_type.to_string());
+                    }
+                }
+            }
+        }
+
+        if let Some(runtime) = &configuration.runtime {
+            if !runtime.as_str().starts_with("provided") {
+                tracing::warn!(
+                    runtime = runtime.as_str(),
+                    "the function doesn't use an OS-only runtime, the new project deploys it with {DEFAULT_RUNTIME}"
+                );
+            }
+        }
+
+        tracing::debug!(?function, "imported deployed function configuration");
+        self.function = Some(function);
+        Ok(())
+    }
+
+    pub(crate) fn function(&self) -> Option<&RemoteFunction> {
+        self.function.as_ref()
+    }
+
+    /// Add the deploy configuration of the imported function to the new project's Cargo.toml.
+    pub(crate) fn seed_metadata(&self, project_path: &Path) -> Result<()> {
+        let Some(function) = &self.function else {
+            return Ok(());
+        };
+
+        let manifest_path = project_path.join("Cargo.toml");
+        if !manifest_path.is_file() {
+            return Ok(());
+        }
+
+        let manifest = read_to_string(&manifest_path).into_diagnostic()?;
+        let mut doc = manifest
+            .parse::<DocumentMut>()
+            .into_diagnostic()
+            .wrap_err("invalid Cargo.toml in the template")?;
+
+        let lambda_metadata = doc
+            .get("package")
+            .and_then(|p| p.get("metadata"))
+            .and_then(|m| m.get("lambda"));
+        if lambda_metadata.is_some_and(|l| l.get("deploy").is_some() || l.get("build").is_some()) {
+            tracing::warn!(
+                "the template already includes Lambda metadata, the deployed function's configuration is not added to Cargo.toml"
+            );
+            return Ok(());
+        }
+
+        if !function.env.is_empty() {
+            let variables = function.env.keys().cloned().collect::<Vec<_>>().join(", ");
+            if self.from_remote_env {
+                tracing::warn!(
+                    %variables,
+                    "adding the deployed function's environment variables to Cargo.toml, review them before committing the file, they can include secrets"
+                );
+            } else {
+                tracing::warn!(
+                    %variables,
+                    "the deployed function's environment variables are not added to Cargo.toml because they can include secrets, use the flag `--from-remote-env` to add them"
+                );
+            }
+        }
+
+        let metadata = doc
+            .get_mut("package")
+            .and_then(|p| p.as_table_like_mut())
+            .and_then(|p| {
+                p.entry("metadata")
+                    .or_insert(implicit_table())
+                    .as_table_like_mut()
+            })
+            .ok_or_else(|| {
+                miette::miette!("invalid `package.metadata` in the template's Cargo.toml")
+            })?;
+        metadata.insert(
+            "lambda",
+            to_item(&function.metadata(self.from_remote_env)?)?,
+        );
+
+        write(&manifest_path, doc.to_string())
+            .into_diagnostic()
+            .wrap_err("failed to add the deployed function's configuration to Cargo.toml")
+    }
+}
+
+impl RemoteFunction {
+    fn from_configuration(configuration: &GetFunctionConfigurationOutput) -> Self {
+        let env = configuration
+            .environment()
+            .and_then(|e| e.variables())
+            .map(|vars| vars.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default();
+
+        let layers = configuration
+            .layers()
+            .iter()
+            .filter_map(|l| l.arn().map(String::from))
+            .collect();
+
+        let tracing = configuration
+            .tracing_config()
+            .and_then(|t| t.mode())
+            .map(|m| m.as_str().to_lowercase());
+
+        // provided.al2023 is the default runtime, only older OS-only runtimes need to be recorded.
+        let runtime = configuration
+            .runtime()
+            .map(|r| r.as_str())
+            .filter(|r| r.starts_with("provided") && *r != DEFAULT_RUNTIME)
+            .map(String::from);
+
+        let architecture = configuration
+            .architectures()
+            .first()
+            .and_then(|a| a.as_str().parse().ok());
+
+        RemoteFunction {
+            memory: configuration.memory_size(),
+            timeout: configuration.timeout(),
+            env,
+            layers,
+            role: configuration.role().map(String::from),
+            tracing,
+            runtime,
+            architecture,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn architecture(&self) -> Option<Architecture> {
+        self.architecture
+    }
+
+    pub(crate) fn http(&self) -> bool {
+        self.http
+    }
+
+    pub(crate) fn event_types(&self) -> &[String] {
+        &self.event_types
+    }
+
+    /// Lambda metadata with the function's deploy configuration.
+    /// The environment variables are only included when `include_env` is true.
+    fn metadata(&self, include_env: bool) -> Result<toml::Value> {
+        let build = match self.architecture {
+            Some(Architecture::Arm64) => Some(BuildMetadata { arm64: true }),
+            _ => None,
+        };
+
+        let no_env = BTreeMap::new();
+        let metadata = LambdaMetadata {
+            build,
+            deploy: DeployMetadata {
+                memory: self.memory,
+                timeout: self.timeout,
+                tracing: self.tracing.as_deref(),
+                role: self.role.as_deref(),
+                runtime: self.runtime.as_deref(),
+                layers: &self.layers,
+                env: if include_env { &self.env } else { &no_env },
+            },
+        };
+
+        toml::Value::try_from(metadata).into_diagnostic()
+    }
+}
+
+/// Event type from the aws_lambda_events crate for the source of an event source mapping.
+fn event_type_from_source_arn(arn: &str) -> Option<&'static str> {
+    match arn.split(':').nth(2)? {
+        "sqs" => Some("sqs::SqsEvent"),
+        "dynamodb" => Some("dynamodb::Event"),
+        "kinesis" => Some("kinesis::KinesisEvent"),
+        "kafka" => Some("kafka::KafkaEvent"),
+        "mq" => Some("activemq::ActiveMqEvent"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cargo_lambda_remote::aws_sdk_lambda::types::{
+        Architecture as LambdaArchitecture, EnvironmentResponse, Layer, Runtime,
+        TracingConfigResponse, TracingMode,
+    };
+
+    fn configuration() -> GetFunctionConfigurationOutput {
+        GetFunctionConfigurationOutput::builder()
+            .memory_size(512)
+            .timeout(30)
+            .role("arn:aws:iam::123456789012:role/lambda-role")
+            .runtime(Runtime::ProvidedAl2)
+            .architectures(LambdaArchitecture::Arm64)
+            .layers(
+                Layer::builder()
+                    .arn("arn:aws:lambda:us-east-1:123456789012:layer:extension:1")
+                    .build(),
+            )
+            .tracing_config(
+                TracingConfigResponse::builder()
+                    .mode(TracingMode::Active)
+                    .build(),
+            )
+            .environment(
+                EnvironmentResponse::builder()
+                    .variables("TABLE_NAME", "products")
+                    .build(),
+            )
+            .build()
+    }
+
+    #[test]
+    fn test_from_configuration() {
+        let function = RemoteFunction::from_configuration(&configuration());
+        assert_eq!(Some(512), function.memory);
+        assert_eq!(Some(30), function.timeout);
+        assert_eq!(Some("active".to_string()), function.tracing);
+        assert_eq!(Some("provided.al2".to_string()), function.runtime);
+        assert_eq!(Some(Architecture::Arm64), function.architecture);
+        assert_eq!(
+            vec!["arn:aws:lambda:us-east-1:123456789012:layer:extension:1".to_string()],
+            function.layers
+        );
+        assert_eq!(
+            Some(&"products".to_string()),
+            function.env.get("TABLE_NAME")
+        );
+    }
+
+    #[test]
+    fn test_seed_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"basic-lambda\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+
+        let options = Options {
+            function: Some(RemoteFunction::from_configuration(&configuration())),
+            ..Default::default()
+        };
+        options.seed_metadata(dir.path()).unwrap();
+
+        let manifest = read_to_string(dir.path().join("Cargo.toml")).unwrap();
+        assert!(manifest.starts_with("[package]\nname = \"basic-lambda\"\nversion = \"0.1.0\"\n"));
+        assert!(manifest.contains("[package.metadata.lambda.deploy]"));
+
+        let parsed: toml::Table = toml::from_str(&manifest).unwrap();
+        let lambda = &parsed["package"]["metadata"]["lambda"];
+        assert_eq!(512, lambda["deploy"]["memory"].as_integer().unwrap());
+        assert_eq!("active", lambda["deploy"]["tracing"].as_str().unwrap());
+        assert!(lambda["deploy"].get("env").is_none());
+        assert!(lambda["build"]["arm64"].as_bool().unwrap());
+        assert_eq!("basic-lambda", parsed["package"]["name"].as_str().unwrap());
+    }
+
+    #[test]
+    fn test_seed_metadata_with_env() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"basic-lambda\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+
+        let options = Options {
+            from_remote_env: true,
+            function: Some(RemoteFunction::from_configuration(&configuration())),
+            ..Default::default()
+        };
+        options.seed_metadata(dir.path()).unwrap();
+
+        let manifest = read_to_string(dir.path().join("Cargo.toml")).unwrap();
+        let parsed: toml::Table = toml::from_str(&manifest).unwrap();
+        assert_eq!(
+            "products",
+            parsed["package"]["metadata"]["lambda"]["deploy"]["env"]["TABLE_NAME"]
+                .as_str()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_keep_template_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = "[package]\nname = \"basic-lambda\"\n\n[package.metadata.lambda.deploy]\nmemory = 128\n";
+        write(dir.path().join("Cargo.toml"), manifest).unwrap();
+
+        let options = Options {
+            function: Some(RemoteFunction::from_configuration(&configuration())),
+            ..Default::default()
+        };
+        options.seed_metadata(dir.path()).unwrap();
+
+        assert_eq!(
+            manifest,
+            read_to_string(dir.path().join("Cargo.toml")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_event_type_from_source_arn() {
+        assert_eq!(
+            Some("sqs::SqsEvent"),
+            event_type_from_source_arn("arn:aws:sqs:us-east-1:123456789012:queue")
+        );
+        assert_eq!(
+            Some("dynamodb::Event"),
+            event_type_from_source_arn(
+                "arn:aws:dynamodb:us-east-1:123456789012:table/products/stream/2024-01-01T00:00:00.000"
+            )
+        );
+        assert_eq!(None, event_type_from_source_arn("arn:aws:s3:::bucket"));
+    }
+}
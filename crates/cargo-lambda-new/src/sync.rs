@@ -0,0 +1,479 @@
+use liquid::Object;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs::{create_dir_all, read, read_to_string, write},
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+use crate::{convert_to_unix_path, error::CreateError};
+
+/// Path, relative to the project, of the file that records how the project was created.
+pub(crate) const RECORD_PATH: &str = ".cargo-lambda/template.json";
+
+/// TemplateRecord keeps the template, the variables, and the rendered files
+/// used to create a project, so the project can be synced with newer versions of the template.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub(crate) struct TemplateRecord {
+    /// Location of the template, like a local path or a URL.
+    pub(crate) template: String,
+    /// Variables used to render the template.
+    #[serde(default)]
+    variables: serde_json::Map<String, serde_json::Value>,
+    /// Content of the text files that the template rendered, indexed by their path in the project.
+    #[serde(default)]
+    files: BTreeMap<String, String>,
+}
+
+impl TemplateRecord {
+    /// Build a record with the files rendered in `render_path`.
+    /// The answers to the `secrets` prompts are left out, because the record is usually committed.
+    pub(crate) fn new(
+        template: &str,
+        globals: &Object,
+        secrets: &[&str],
+        render_path: &Path,
+    ) -> Result<Self> {
+        let mut variables = match serde_json::to_value(globals).into_diagnostic()? {
+            serde_json::Value::Object(variables) => variables,
+            _ => serde_json::Map::new(),
+        };
+        for secret in secrets {
+            variables.remove(*secret);
+        }
+
+        let mut files = BTreeMap::new();
+        for (relative, path) in rendered_files(render_path)? {
+            // Binary files are not recorded, they are synced without looking at local changes.
+            if let Ok(content) = read_to_string(&path) {
+                files.insert(relative, content);
+            }
+        }
+
+        Ok(TemplateRecord {
+            template: template.to_string(),
+            variables,
+            files,
+        })
+    }
+
+    pub(crate) fn load(project_path: &Path) -> Result<Self> {
+        let path = project_path.join(RECORD_PATH);
+        if !path.is_file() {
+            return Err(CreateError::MissingTemplateRecord(path).into());
+        }
+
+        let content = read_to_string(&path).into_diagnostic()?;
+        serde_json::from_str(&content)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("invalid template record: {path:?}"))
+    }
+
+    pub(crate) fn save(&self, project_path: &Path) -> Result<()> {
+        let path = project_path.join(RECORD_PATH);
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent).into_diagnostic()?;
+        }
+
+        let content = serde_json::to_string_pretty(self).into_diagnostic()?;
+        write(&path, content)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("unable to write template record: {path:?}"))
+    }
+
+    /// Variables to render the template again.
+    pub(crate) fn variables(&self) -> Result<Object> {
+        liquid::to_object(&self.variables).into_diagnostic()
+    }
+}
+
+/// What syncing a file does to the project.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SyncStatus {
+    /// The template added a new file.
+    Create,
+    /// The template changed a file that was not changed in the project.
+    Update,
+    /// The file was changed in the project, but not in the template.
+    Keep,
+    /// The file was deleted from the project.
+    Deleted,
+    /// The file is the same in the project and the template.
+    Unchanged,
+    /// The file was changed in the project and in the template.
+    Conflict,
+}
+
+impl SyncStatus {
+    fn marker(&self) -> char {
+        match self {
+            SyncStatus::Create => '+',
+            SyncStatus::Update => '~',
+            SyncStatus::Keep => '=',
+            SyncStatus::Deleted => '-',
+            SyncStatus::Unchanged => '=',
+            SyncStatus::Conflict => '!',
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            SyncStatus::Create => "new file",
+            SyncStatus::Update => "updated",
+            SyncStatus::Keep => "local changes preserved",
+            SyncStatus::Deleted => "deleted in the project, skipped",
+            SyncStatus::Unchanged => "unchanged",
+            SyncStatus::Conflict => {
+                "changed in the project and in the template, review the changes"
+            }
+        }
+    }
+
+    /// Whether syncing writes the template's version of the file in the project.
+    fn writes_file(&self) -> bool {
+        matches!(self, SyncStatus::Create | SyncStatus::Update)
+    }
+}
+
+/// File rendered by the template, compared with the project and with the recorded version.
+#[derive(Debug)]
+pub(crate) struct SyncEntry {
+    pub(crate) path: String,
+    pub(crate) status: SyncStatus,
+    /// Three-way diff for conflicts: the changes in the project, and the changes in the template.
+    pub(crate) diff: Option<String>,
+    source: PathBuf,
+}
+
+/// Compare the files rendered in `render_path` with the files in the project, and with
+/// the files recorded when the project was created, or synced the last time.
+pub(crate) fn compare_files(
+    render_path: &Path,
+    project_path: &Path,
+    record: &TemplateRecord,
+) -> Result<Vec<SyncEntry>> {
+    let mut entries = Vec::new();
+
+    for (relative, source) in rendered_files(render_path)? {
+        let base = record.files.get(&relative);
+        let local_path = project_path.join(&relative);
+
+        if !local_path.is_file() {
+            let status = if base.is_some() {
+                SyncStatus::Deleted
+            } else {
+                SyncStatus::Create
+            };
+            entries.push(SyncEntry {
+                path: relative,
+                status,
+                diff: None,
+                source,
+            });
+            continue;
+        }
+
+        let local = read(&local_path).into_diagnostic()?;
+        let rendered = read(&source).into_diagnostic()?;
+        if local == rendered {
+            entries.push(SyncEntry {
+                path: relative,
+                status: SyncStatus::Unchanged,
+                diff: None,
+                source,
+            });
+            continue;
+        }
+
+        let (local, rendered) = match (String::from_utf8(local), String::from_utf8(rendered)) {
+            (Ok(local), Ok(rendered)) => (local, rendered),
+            // Binary files don't have a recorded version, the template's version wins.
+            _ => {
+                entries.push(SyncEntry {
+                    path: relative,
+                    status: SyncStatus::Update,
+                    diff: None,
+                    source,
+                });
+                continue;
+            }
+        };
+
+        let (status, diff) = match base {
+            Some(base) if *base == local => (SyncStatus::Update, None),
+            Some(base) if *base == rendered => (SyncStatus::Keep, None),
+            Some(base) => {
+                let mut diff = unified_diff(base, &local, &relative, "base", "project");
+                diff.push_str(&unified_diff(
+                    base, &rendered, &relative, "base", "template",
+                ));
+                (SyncStatus::Conflict, Some(diff))
+            }
+            None => {
+                let diff = unified_diff(&local, &rendered, &relative, "project", "template");
+                (SyncStatus::Conflict, Some(diff))
+            }
+        };
+
+        entries.push(SyncEntry {
+            path: relative,
+            status,
+            diff,
+            source,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Write the new and updated files in the project.
+/// Files with conflicts are not modified, the user needs to review the changes.
+pub(crate) fn apply_changes(project_path: &Path, entries: &[SyncEntry]) -> Result<()> {
+    for entry in entries.iter().filter(|e| e.status.writes_file()) {
+        let path = project_path.join(&entry.path);
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent).into_diagnostic()?;
+        }
+
+        std::fs::copy(&entry.source, &path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("unable to update file: {path:?}"))?;
+    }
+
+    Ok(())
+}
+
+/// Format the list of files that syncing changes, followed by the diffs of the files with conflicts.
+pub(crate) fn format_sync(entries: &[SyncEntry], dry_run: bool) -> String {
+    let mut output = String::new();
+    if dry_run {
+        let _ = writeln!(output, "Dry run: no files were written to the project\n");
+    }
+
+    for entry in entries {
+        let _ = writeln!(
+            output,
+            "  {} {} ({})",
+            entry.status.marker(),
+            entry.path,
+            entry.status.description()
+        );
+    }
+
+    for entry in entries {
+        if let Some(diff) = &entry.diff {
+            let _ = write!(output, "\n{diff}");
+        }
+    }
+
+    output
+}
+
+fn unified_diff(old: &str, new: &str, path: &str, old_name: &str, new_name: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("{old_name}/{path}"), &format!("{new_name}/{path}"))
+        .to_string()
+}
+
+/// Files in the render directory, with their path relative to the project in unix format.
+fn rendered_files(render_path: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+
+    let walk_dir = WalkDir::new(render_path)
+        .follow_links(false)
+        .sort_by_file_name();
+    for entry in walk_dir {
+        let entry = entry.into_diagnostic()?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+
+        let relative = entry.path().strip_prefix(render_path).into_diagnostic()?;
+        let Some(relative) = convert_to_unix_path(relative) else {
+            continue;
+        };
+        files.push((relative, entry.path().to_path_buf()));
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use liquid::model::Value;
+
+    fn write_file(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        create_dir_all(path.parent().unwrap()).unwrap();
+        write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_record_roundtrip() {
+        let render_dir = tempfile::tempdir().unwrap();
+        write_file(render_dir.path(), "src/main.rs", "fn main() {}\n");
+
+        let globals = liquid::object!({
+            "project_name": "basic-lambda",
+            "http_function": true,
+            "function_name": Value::Nil,
+            "event_types": [{ "event_type": "SqsEvent" }],
+        });
+
+        let project_dir = tempfile::tempdir().unwrap();
+        TemplateRecord::new(
+            "gh:cargo-lambda/default-template",
+            &globals,
+            &[],
+            render_dir.path(),
+        )
+        .unwrap()
+        .save(project_dir.path())
+        .unwrap();
+
+        let record = TemplateRecord::load(project_dir.path()).unwrap();
+        assert_eq!("gh:cargo-lambda/default-template", record.template);
+        assert_eq!(
+            Some(&"fn main() {}\n".to_string()),
+            record.files.get("src/main.rs")
+        );
+
+        let variables = record.variables().unwrap();
+        assert_eq!(
+            Some(&Value::scalar("basic-lambda")),
+            variables.get("project_name")
+        );
+        assert_eq!(Some(&Value::scalar(true)), variables.get("http_function"));
+    }
+
+    #[test]
+    fn test_record_without_secrets() {
+        let render_dir = tempfile::tempdir().unwrap();
+        write_file(render_dir.path(), "src/main.rs", "fn main() {}\n");
+
+        let globals = liquid::object!({
+            "project_name": "basic-lambda",
+            "api_key": "sk-live-1234",
+        });
+
+        let project_dir = tempfile::tempdir().unwrap();
+        TemplateRecord::new(
+            "gh:cargo-lambda/default-template",
+            &globals,
+            &["api_key"],
+            render_dir.path(),
+        )
+        .unwrap()
+        .save(project_dir.path())
+        .unwrap();
+
+        let content = read_to_string(project_dir.path().join(RECORD_PATH)).unwrap();
+        assert!(!content.contains("sk-live-1234"));
+
+        let variables = TemplateRecord::load(project_dir.path())
+            .unwrap()
+            .variables()
+            .unwrap();
+        assert_eq!(None, variables.get("api_key"));
+        assert_eq!(
+            Some(&Value::scalar("basic-lambda")),
+            variables.get("project_name")
+        );
+    }
+
+    #[test]
+    fn test_missing_record() {
+        let project_dir = tempfile::tempdir().unwrap();
+        let err = TemplateRecord::load(project_dir.path()).unwrap_err();
+        assert!(err.to_string().contains("template.json"));
+    }
+
+    #[test]
+    fn test_compare_files() {
+        let mut record = TemplateRecord::default();
+        for (path, content) in [
+            ("Cargo.toml", "[package]\nversion = \"0.1.0\"\n"),
+            ("README.md", "# Basic\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("LICENSE", "MIT\n"),
+            (".gitignore", "target\n"),
+        ] {
+            record.files.insert(path.into(), content.into());
+        }
+
+        let render_dir = tempfile::tempdir().unwrap();
+        write_file(
+            render_dir.path(),
+            "Cargo.toml",
+            "[package]\nversion = \"0.2.0\"\n",
+        );
+        write_file(render_dir.path(), "README.md", "# Basic\n");
+        write_file(
+            render_dir.path(),
+            "src/main.rs",
+            "fn main() {\n    run();\n}\n",
+        );
+        write_file(render_dir.path(), "LICENSE", "MIT\n");
+        write_file(render_dir.path(), ".gitignore", "target\n");
+        write_file(render_dir.path(), "src/lib.rs", "pub fn run() {}\n");
+
+        let project_dir = tempfile::tempdir().unwrap();
+        write_file(
+            project_dir.path(),
+            "Cargo.toml",
+            "[package]\nversion = \"0.1.0\"\n",
+        );
+        write_file(project_dir.path(), "README.md", "# My function\n");
+        write_file(
+            project_dir.path(),
+            "src/main.rs",
+            "fn main() {\n    start();\n}\n",
+        );
+        write_file(project_dir.path(), ".gitignore", "target\n");
+
+        let entries = compare_files(render_dir.path(), project_dir.path(), &record).unwrap();
+        let statuses = entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (".gitignore", SyncStatus::Unchanged),
+                ("Cargo.toml", SyncStatus::Update),
+                ("LICENSE", SyncStatus::Deleted),
+                ("README.md", SyncStatus::Keep),
+                ("src/lib.rs", SyncStatus::Create),
+                ("src/main.rs", SyncStatus::Conflict),
+            ],
+            statuses
+        );
+
+        let diff = entries[5].diff.as_deref().unwrap();
+        assert!(diff.contains("+++ project/src/main.rs"));
+        assert!(diff.contains("+    start();"));
+        assert!(diff.contains("+++ template/src/main.rs"));
+        assert!(diff.contains("+    run();"));
+
+        apply_changes(project_dir.path(), &entries).unwrap();
+        assert_eq!(
+            "[package]\nversion = \"0.2.0\"\n",
+            read_to_string(project_dir.path().join("Cargo.toml")).unwrap()
+        );
+        assert_eq!(
+            "pub fn run() {}\n",
+            read_to_string(project_dir.path().join("src/lib.rs")).unwrap()
+        );
+        assert_eq!(
+            "fn main() {\n    start();\n}\n",
+            read_to_string(project_dir.path().join("src/main.rs")).unwrap()
+        );
+        assert!(!project_dir.path().join("LICENSE").exists());
+    }
+}
@@ -1,12 +1,15 @@
 use std::{
     fmt,
-    fs::{remove_dir_all, remove_file, File},
+    fs::{create_dir_all, read_to_string, remove_dir_all, remove_file, write, File},
     io::{copy, Cursor},
-    path::{Path, PathBuf},
+    num::NonZeroU32,
+    path::{Component, Path, PathBuf},
 };
 
+use cargo_lambda_remote::network;
 use gix::refs::PartialName;
 use miette::{Context, IntoDiagnostic, Result};
+use sha2::{Digest, Sha256};
 use tempfile::{tempdir, TempDir};
 use zip::ZipArchive;
 
@@ -36,6 +39,8 @@ pub(crate) struct GitRepo {
     host: String,
     repo: String,
     reference: Option<String>,
+    /// Directory inside the repository where the template is.
+    subdir: Option<String>,
     auth_user: Option<String>,
     protocol: GitProtocol,
 }
@@ -63,14 +68,19 @@ pub(crate) enum TemplateSource {
 
 impl TemplateSource {
     #[tracing::instrument(target = "cargo_lambda")]
-    pub(crate) async fn expand(&self) -> Result<TemplateRoot> {
+    pub(crate) async fn expand(&self, offline: bool) -> Result<TemplateRoot> {
         tracing::debug!("expanding template");
 
         let base = match self {
             Self::RemoteZip(url) => {
                 let tmp_dir = tempdir().into_diagnostic()?;
 
-                let local_zip = download_zip_template(url, tmp_dir.path()).await?;
+                let cache = TemplateCache::for_url(url);
+                let local_zip = if offline {
+                    cached_zip_template(url, cache.as_ref())?
+                } else {
+                    download_zip_template(url, tmp_dir.path(), cache.as_ref()).await?
+                };
                 unzip_template(&local_zip, tmp_dir.path())?;
 
                 let base = adjust_remote_zip_base(url, tmp_dir.path());
@@ -84,11 +94,18 @@ impl TemplateSource {
                 TemplateRoot::TempDir((tmp_dir, None))
             }
             Self::LocalDir(path) => TemplateRoot::LocalDir(path.clone()),
+            Self::RemoteRepo(repo) if offline => {
+                return Err(miette::miette!(
+                    "the template {} is a Git repository, it cannot be used with --offline",
+                    repo.to_url()
+                ));
+            }
             Self::RemoteRepo(repo) => {
                 let tmp_dir = tempdir().into_diagnostic()?;
 
                 clone_git_repo(repo, tmp_dir.path())?;
-                TemplateRoot::TempDir((tmp_dir, None))
+                let base = repo_subdir(repo, tmp_dir.path())?;
+                TemplateRoot::TempDir((tmp_dir, base))
             }
         };
 
@@ -104,11 +121,15 @@ impl TryFrom<&str> for TemplateSource {
             return Ok(Self::RemoteZip(value.into()));
         }
 
-        if let Some(repo) = match_git_http_url(value) {
-            return Ok(Self::RemoteRepo(repo));
-        }
-
-        if let Some(repo) = match_git_ssh_url(value) {
+        let (url, reference, subdir) = split_git_fragment(value);
+        if let Some(mut repo) = match_git_http_url(url).or_else(|| match_git_ssh_url(url)) {
+            if reference.is_some() {
+                repo.reference = reference;
+            }
+            if let Some(subdir) = &subdir {
+                validate_subdir(value, subdir)?;
+            }
+            repo.subdir = subdir;
             return Ok(Self::RemoteRepo(repo));
         }
 
@@ -179,11 +200,115 @@ impl TemplateRoot {
     }
 }
 
+/// TemplateCache keeps the template archives downloaded from remote URLs,
+/// so projects can be created when the network is not available.
+#[derive(Debug)]
+struct TemplateCache {
+    dir: PathBuf,
+}
+
+impl TemplateCache {
+    fn for_url(url: &str) -> Option<TemplateCache> {
+        dirs::cache_dir().map(|p| TemplateCache {
+            dir: p
+                .join("cargo-lambda")
+                .join("templates")
+                .join(cache_key(url)),
+        })
+    }
+
+    /// Path of the cached archive, if the template was downloaded before.
+    fn archive(&self) -> Option<PathBuf> {
+        let archive = self.dir.join("template.zip");
+        archive.is_file().then_some(archive)
+    }
+
+    /// ETag of the cached archive, to check if the template changed since it was downloaded.
+    fn etag(&self) -> Option<String> {
+        self.archive()?;
+        read_to_string(self.dir.join("etag"))
+            .ok()
+            .map(|etag| etag.trim().to_string())
+    }
+
+    fn store(&self, archive: &[u8], etag: Option<&str>) -> Result<()> {
+        create_dir_all(&self.dir).into_diagnostic()?;
+        write(self.dir.join("template.zip"), archive).into_diagnostic()?;
+
+        let etag_path = self.dir.join("etag");
+        match etag {
+            Some(etag) => write(etag_path, etag).into_diagnostic()?,
+            None => {
+                let _ = remove_file(etag_path);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Maximum length of the readable part of a cache key.
+const CACHE_KEY_PREFIX_LEN: usize = 48;
+
+/// Directory name for a template URL. The readable prefix helps finding the template in the cache,
+/// the hash of the full URL, with its ref, keeps URLs with the same prefix apart.
+fn cache_key(url: &str) -> String {
+    let prefix = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .trim_end_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .take(CACHE_KEY_PREFIX_LEN)
+        .collect::<String>();
+    let hash = format!("{:x}", Sha256::digest(url.as_bytes()));
+
+    format!("{prefix}-{hash}")
+}
+
+/// Use the archive in the cache without connecting to the network.
+fn cached_zip_template(url: &str, cache: Option<&TemplateCache>) -> Result<PathBuf> {
+    tracing::debug!(?cache, "using template from cache");
+
+    cache.and_then(TemplateCache::archive).ok_or_else(|| {
+        miette::miette!(
+            "the template {url} is not in the cache, run the command without --offline to download it"
+        )
+    })
+}
+
 #[tracing::instrument(target = "cargo_lambda")]
-async fn download_zip_template(url: &str, template_root: &Path) -> Result<PathBuf> {
+async fn download_zip_template(
+    url: &str,
+    template_root: &Path,
+    cache: Option<&TemplateCache>,
+) -> Result<PathBuf> {
     tracing::debug!("downloading template");
 
-    let response = reqwest::get(url).await.into_diagnostic()?;
+    let mut request = network::http_client()?.get(url);
+    if let Some(etag) = cache.and_then(TemplateCache::etag) {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(err) => {
+            // Fall back to the cached archive when the network is not available.
+            if let Some(archive) = cache.and_then(TemplateCache::archive) {
+                tracing::warn!(%err, "failed to download the template, using the cached version");
+                return Ok(archive);
+            }
+            return Err(err).into_diagnostic();
+        }
+    };
+
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some(archive) = cache.and_then(TemplateCache::archive) {
+            tracing::debug!("template not modified, using the cached version");
+            return Ok(archive);
+        }
+    }
+
     if response.status() != reqwest::StatusCode::OK {
         return Err(miette::miette!(
             "error downloading template from {} - {}",
@@ -192,7 +317,20 @@ async fn download_zip_template(url: &str, template_root: &Path) -> Result<PathBu
         ));
     }
 
-    let mut bytes = Cursor::new(response.bytes().await.into_diagnostic()?);
+    let etag = response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+    let archive = response.bytes().await.into_diagnostic()?;
+
+    if let Some(cache) = cache {
+        if let Err(err) = cache.store(&archive, etag.as_deref()) {
+            tracing::warn!(?err, "failed to store the template in the cache");
+        }
+    }
+
+    let mut bytes = Cursor::new(archive);
 
     let tmp_file = template_root.join("cargo-lambda-template.zip");
     let mut writer = File::create(&tmp_file)
@@ -282,7 +420,7 @@ fn match_git_http_url(original: &str) -> Option<GitRepo> {
     let uri = uri.as_deref().unwrap_or(original);
 
     let repo_regex = regex::Regex::new(
-        r"https://(?P<host>[a-zA-Z0-9.-]+)/(?P<repo>[a-zA-Z0-9][a-zA-Z0-9_-]+/[a-zA-Z0-9][a-zA-Z0-9_-]+)/?((branch|tag|tree)/(?P<ref>.+))?$",
+        r"https://(?P<host>[a-zA-Z0-9.-]+)/(?P<repo>[a-zA-Z0-9][a-zA-Z0-9_-]+/[a-zA-Z0-9][a-zA-Z0-9_-]+)(\.git)?/?((branch|tag|tree)/(?P<ref>.+))?$",
     )
     .into_diagnostic()
     .expect("invalid HTTP regex");
@@ -301,12 +439,13 @@ fn match_git_http_url(original: &str) -> Option<GitRepo> {
         reference,
         auth_user: None,
         protocol: GitProtocol::Http,
+        ..Default::default()
     })
 }
 
 fn match_git_ssh_url(value: &str) -> Option<GitRepo> {
     let ssh_regex = regex::Regex::new(
-        r"ssh://(?P<host>[a-zA-Z0-9.-]+)/(?P<repo>[a-zA-Z0-9][a-zA-Z0-9_-]+/[a-zA-Z0-9][a-zA-Z0-9_-]+)(\.git)?$",
+        r"ssh://((?P<user>[a-zA-Z0-9_.-]+)@)?(?P<host>[a-zA-Z0-9.-]+)/(?P<repo>[a-zA-Z0-9][a-zA-Z0-9_-]+/[a-zA-Z0-9][a-zA-Z0-9_-]+)(\.git)?$",
     )
     .into_diagnostic()
     .expect("invalid SSH regex");
@@ -322,7 +461,7 @@ fn match_git_ssh_url(value: &str) -> Option<GitRepo> {
             None => return None,
             Some(caps) => (Some("git".into()), caps),
         },
-        Some(caps) => (None, caps),
+        Some(caps) => (caps.name("user").map(|u| u.as_str().to_string()), caps),
     };
 
     let host = caps.name("host")?;
@@ -343,7 +482,11 @@ fn clone_git_repo(repo: &GitRepo, path: &Path) -> Result<()> {
     let mut url = gix::url::parse(git_url.as_str().into()).into_diagnostic()?;
     url.set_user(repo.auth_user.clone());
 
-    let mut prepare_clone = gix::prepare_clone(url, path).into_diagnostic()?;
+    // Templates only need the latest commit, skip the rest of the history.
+    let depth = NonZeroU32::new(1).expect("1 is not zero");
+    let mut prepare_clone = gix::prepare_clone(url, path)
+        .into_diagnostic()?
+        .with_shallow(gix::remote::fetch::Shallow::DepthAtRemote(depth));
     if let Some(ref_name) = &repo.reference {
         let name = PartialName::try_from(ref_name.as_str()).into_diagnostic()?;
         prepare_clone = prepare_clone.with_ref_name(Some(&name)).into_diagnostic()?;
@@ -360,6 +503,53 @@ fn clone_git_repo(repo: &GitRepo, path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Split the reference and the subdirectory from a Git URL in the format `URL#REFERENCE:SUBDIRECTORY`.
+/// Both parts are optional, `URL#:SUBDIRECTORY` uses the default branch.
+fn split_git_fragment(value: &str) -> (&str, Option<String>, Option<String>) {
+    let Some((url, fragment)) = value.split_once('#') else {
+        return (value, None, None);
+    };
+
+    let (reference, subdir) = fragment.split_once(':').unwrap_or((fragment, ""));
+    let reference = Some(reference).filter(|r| !r.is_empty()).map(String::from);
+    let subdir = Some(subdir.trim_matches('/'))
+        .filter(|s| !s.is_empty())
+        .map(String::from);
+
+    (url, reference, subdir)
+}
+
+/// Subdirectories must be relative to the root of the repository.
+fn validate_subdir(value: &str, subdir: &str) -> Result<()> {
+    let valid = Path::new(subdir)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)));
+    if !valid {
+        return Err(miette::miette!(
+            "invalid template option {value}: the subdirectory must be a relative path inside the repository"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Path of the template's subdirectory in the cloned repository, if the template is not in the root.
+fn repo_subdir(repo: &GitRepo, path: &Path) -> Result<Option<PathBuf>> {
+    let Some(subdir) = &repo.subdir else {
+        return Ok(None);
+    };
+
+    let base = path.join(subdir);
+    if !base.is_dir() {
+        return Err(miette::miette!(
+            "the directory {subdir} doesn't exist in the repository {}",
+            repo.to_url()
+        ));
+    }
+
+    Ok(Some(base))
+}
+
 fn cleanup_tmp_dir(path: &Path) {
     let _ = remove_dir_all(path.join(".git"));
     let _ = remove_file(path.join("cargo-lambda-template.zip"));
@@ -549,6 +739,160 @@ mod test {
         assert_contains!(source.to_string(), "invalid template option ../../tests/templates/function-template/Cargo.toml: No such directory");
     }
 
+    #[test]
+    fn test_split_git_fragment() {
+        assert_eq!(
+            ("gh:org/repo", None, None),
+            split_git_fragment("gh:org/repo")
+        );
+        assert_eq!(
+            ("gh:org/repo", Some("main".into()), None),
+            split_git_fragment("gh:org/repo#main")
+        );
+        assert_eq!(
+            (
+                "gh:org/repo",
+                Some("feature/templates".into()),
+                Some("templates/function".into())
+            ),
+            split_git_fragment("gh:org/repo#feature/templates:templates/function/")
+        );
+        assert_eq!(
+            ("git@github.com:org/repo.git", None, Some("function".into())),
+            split_git_fragment("git@github.com:org/repo.git#:function")
+        );
+    }
+
+    #[test]
+    fn test_template_source_with_git_fragment() {
+        let source = TemplateSource::try_from("gh:org/monorepo#stable:templates/function")
+            .expect("failed to parse shortcut with fragment");
+        let expected = TemplateSource::RemoteRepo(GitRepo {
+            host: "github.com".into(),
+            repo: "org/monorepo".into(),
+            reference: Some("stable".into()),
+            subdir: Some("templates/function".into()),
+            protocol: GitProtocol::Http,
+            ..Default::default()
+        });
+        assert_eq!(expected, source);
+
+        let source = TemplateSource::try_from("https://gitlab.com/org/monorepo.git#:function")
+            .expect("failed to parse HTTP URL with fragment");
+        let expected = TemplateSource::RemoteRepo(GitRepo {
+            host: "gitlab.com".into(),
+            repo: "org/monorepo".into(),
+            subdir: Some("function".into()),
+            protocol: GitProtocol::Http,
+            ..Default::default()
+        });
+        assert_eq!(expected, source);
+
+        let source = TemplateSource::try_from("git@github.com:org/private-monorepo.git#v1.0.0")
+            .expect("failed to parse SSH URL with fragment");
+        let expected = TemplateSource::RemoteRepo(GitRepo {
+            host: "github.com".into(),
+            repo: "org/private-monorepo".into(),
+            reference: Some("v1.0.0".into()),
+            auth_user: Some("git".into()),
+            protocol: GitProtocol::Ssh,
+            ..Default::default()
+        });
+        assert_eq!(expected, source);
+
+        let source = TemplateSource::try_from("ssh://git@github.com/org/private-monorepo")
+            .expect("failed to parse SSH URL with user");
+        let expected = TemplateSource::RemoteRepo(GitRepo {
+            host: "github.com".into(),
+            repo: "org/private-monorepo".into(),
+            auth_user: Some("git".into()),
+            protocol: GitProtocol::Ssh,
+            ..Default::default()
+        });
+        assert_eq!(expected, source);
+
+        let source = TemplateSource::try_from("gh:org/monorepo#main:../outside")
+            .expect_err("failed to reject a subdirectory outside the repository");
+        assert_contains!(
+            source.to_string(),
+            "the subdirectory must be a relative path inside the repository"
+        );
+    }
+
+    #[test]
+    fn test_repo_subdir() {
+        let tmp_dir = tempdir().unwrap();
+        std::fs::create_dir_all(tmp_dir.path().join("templates/function")).unwrap();
+
+        let mut repo = GitRepo {
+            host: "github.com".into(),
+            repo: "org/monorepo".into(),
+            ..Default::default()
+        };
+        assert_eq!(None, repo_subdir(&repo, tmp_dir.path()).unwrap());
+
+        repo.subdir = Some("templates/function".into());
+        assert_eq!(
+            Some(tmp_dir.path().join("templates/function")),
+            repo_subdir(&repo, tmp_dir.path()).unwrap()
+        );
+
+        repo.subdir = Some("templates/missing".into());
+        let err = repo_subdir(&repo, tmp_dir.path()).unwrap_err();
+        assert_contains!(err.to_string(), "doesn't exist in the repository");
+    }
+
+    #[test]
+    fn test_template_cache() {
+        let tmp_dir = tempdir().unwrap();
+        let cache = TemplateCache {
+            dir: tmp_dir.path().join("templates"),
+        };
+        assert_eq!(None, cache.archive());
+        assert_eq!(None, cache.etag());
+
+        let url = "https://github.com/cargo-lambda/default-template/archive/refs/heads/main.zip";
+        let err = cached_zip_template(url, Some(&cache)).unwrap_err();
+        assert_contains!(err.to_string(), "is not in the cache");
+
+        cache.store(b"archive", Some("\"abc\"")).unwrap();
+        assert_eq!(
+            Some(tmp_dir.path().join("templates/template.zip")),
+            cache.archive()
+        );
+        assert_eq!(Some("\"abc\"".into()), cache.etag());
+        assert_eq!(
+            tmp_dir.path().join("templates/template.zip"),
+            cached_zip_template(url, Some(&cache)).unwrap()
+        );
+
+        cache.store(b"archive", None).unwrap();
+        assert_eq!(None, cache.etag());
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let key = cache_key("https://github.com/org/repo/archive/main.zip");
+        assert!(key.starts_with("github_com_org_repo_archive_main_zip-"));
+        assert_eq!(
+            key,
+            cache_key("https://github.com/org/repo/archive/main.zip")
+        );
+
+        assert_ne!(
+            cache_key("https://github.com/org/x-y/archive/main.zip"),
+            cache_key("https://github.com/org/x_y/archive/main.zip")
+        );
+        assert_ne!(
+            cache_key("https://github.com/a/b-c/archive/main.zip"),
+            cache_key("https://github.com/a-b/c/archive/main.zip")
+        );
+        assert_ne!(
+            cache_key("https://github.com/org/repo/archive/main.zip"),
+            cache_key("https://github.com/org/repo/archive/v1.zip")
+        );
+    }
+
     #[test]
     fn test_adjust_remote_zip_base() {
         let tmp_dir = tempdir().unwrap();
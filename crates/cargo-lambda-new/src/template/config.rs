@@ -1,6 +1,6 @@
 use cargo_lambda_interactive::{
     validator::{ErrorMessage, Validation},
-    Confirm, CustomUserError, Text,
+    Confirm, CustomUserError, MultiSelect, Password, PasswordDisplayMode, Text,
 };
 use indexmap::IndexMap;
 use liquid::{model::Value, Object};
@@ -19,14 +19,18 @@ use crate::template::PROMPT_WITH_OPTIONS_HELP_MESSAGE;
 #[serde(untagged)]
 pub(crate) enum PromptValue {
     Boolean(bool),
+    Integer(i64),
     String(String),
+    List(Vec<String>),
 }
 
 impl PromptValue {
     pub fn to_value(&self) -> Value {
         match self {
             PromptValue::Boolean(b) => Value::scalar(*b),
+            PromptValue::Integer(i) => Value::scalar(*i),
             PromptValue::String(s) => Value::scalar(s.clone()),
+            PromptValue::List(l) => Value::array(l.iter().cloned().map(Value::scalar)),
         }
     }
 }
@@ -50,15 +54,36 @@ pub(crate) struct RenderCondition {
     pub not_match: Option<PromptValue>,
 }
 
+/// Kind of input that a prompt asks for.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PromptKind {
+    Text,
+    Boolean,
+    /// Number, optionally between `min` and `max`.
+    Integer,
+    /// Several values from the prompt's choices.
+    MultiSelect,
+    /// Text that is not displayed while the user types it.
+    Secret,
+}
+
 #[derive(Debug, Default, Deserialize)]
 pub(crate) struct TemplatePrompt {
     pub message: String,
+    /// Kind of input, inferred from the default value when it's not set.
+    #[serde(default, rename = "type")]
+    pub kind: Option<PromptKind>,
     #[serde(default)]
     pub choices: Option<Vec<String>>,
     #[serde(default)]
     pub default: Option<PromptValue>,
     #[serde(default)]
     pub help: Option<String>,
+    #[serde(default)]
+    pub min: Option<i64>,
+    #[serde(default)]
+    pub max: Option<i64>,
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -104,10 +129,32 @@ pub(crate) fn parse_template_config<P: AsRef<Path> + Debug>(path: P) -> Result<T
 
 impl TemplateConfig {
     pub(crate) fn ask_template_options(&self, no_interactive: bool) -> Result<Object> {
+        self.ask_prompts(no_interactive, |_| true)
+    }
+
+    /// Ask for the secrets again, because they are never recorded in the project.
+    pub(crate) fn ask_secret_options(&self, no_interactive: bool) -> Result<Object> {
+        self.ask_prompts(no_interactive, TemplatePrompt::is_secret)
+    }
+
+    /// Names of the prompts that ask for secrets.
+    pub(crate) fn secret_prompts(&self) -> Vec<&str> {
+        self.prompts
+            .iter()
+            .filter(|(_, prompt)| prompt.is_secret())
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    fn ask_prompts(
+        &self,
+        no_interactive: bool,
+        filter: impl Fn(&TemplatePrompt) -> bool,
+    ) -> Result<Object> {
         let mut variables = Object::new();
-        for (name, prompt) in &self.prompts {
+        for (name, prompt) in self.prompts.iter().filter(|(_, prompt)| filter(prompt)) {
             let value = if no_interactive {
-                prompt.default.clone().unwrap_or_default()
+                prompt.default_value()
             } else {
                 prompt.ask()?
             };
@@ -118,37 +165,140 @@ impl TemplateConfig {
 }
 
 impl TemplatePrompt {
+    pub(crate) fn kind(&self) -> PromptKind {
+        if let Some(kind) = self.kind {
+            return kind;
+        }
+
+        match &self.default {
+            Some(PromptValue::Boolean(_)) => PromptKind::Boolean,
+            Some(PromptValue::Integer(_)) => PromptKind::Integer,
+            Some(PromptValue::List(_)) => PromptKind::MultiSelect,
+            Some(PromptValue::String(_)) | None => PromptKind::Text,
+        }
+    }
+
+    fn is_secret(&self) -> bool {
+        self.kind() == PromptKind::Secret
+    }
+
+    /// Value used when the prompt is not displayed.
+    pub(crate) fn default_value(&self) -> PromptValue {
+        if let Some(default) = &self.default {
+            return default.clone();
+        }
+
+        match self.kind() {
+            PromptKind::Boolean => PromptValue::Boolean(false),
+            PromptKind::Integer => PromptValue::Integer(self.min.unwrap_or_default()),
+            PromptKind::MultiSelect => PromptValue::List(Vec::new()),
+            PromptKind::Text | PromptKind::Secret => PromptValue::default(),
+        }
+    }
+
     pub(crate) fn ask(&self) -> Result<PromptValue> {
+        match self.kind() {
+            PromptKind::Boolean => self.ask_boolean(),
+            PromptKind::Integer => self.ask_integer(),
+            PromptKind::MultiSelect => self.ask_multi_select(),
+            PromptKind::Secret => self.ask_secret(),
+            PromptKind::Text => self.ask_text(),
+        }
+    }
+
+    fn ask_boolean(&self) -> Result<PromptValue> {
+        let default = matches!(self.default, Some(PromptValue::Boolean(true)));
+        let prompt = Confirm::new(&self.message).with_default(default);
+        let value = if let Some(help_message) = self.help_message() {
+            prompt.with_help_message(&help_message).prompt()
+        } else {
+            prompt.prompt()
+        };
+        Ok(PromptValue::Boolean(value.into_diagnostic()?))
+    }
+
+    fn ask_text(&self) -> Result<PromptValue> {
+        let default = match &self.default {
+            Some(PromptValue::String(s)) => Some(s.clone()),
+            Some(PromptValue::Integer(i)) => Some(i.to_string()),
+            _ => None,
+        };
+
+        let mut prompt = self.text_prompt();
+        if let Some(default) = &default {
+            prompt = prompt.with_default(default);
+        }
         let help_message = self.help_message();
+        if let Some(help_message) = &help_message {
+            prompt = prompt.with_help_message(help_message);
+        }
 
-        match &self.default {
-            Some(PromptValue::Boolean(b)) => {
-                let prompt = Confirm::new(&self.message).with_default(*b);
-                let value = if let Some(help_message) = help_message {
-                    prompt.with_help_message(&help_message).prompt()
-                } else {
-                    prompt.prompt()
-                };
-                Ok(PromptValue::Boolean(value.into_diagnostic()?))
-            }
-            Some(PromptValue::String(s)) => {
-                let prompt = self.text_prompt().with_default(s);
-                let value = if let Some(help_message) = help_message {
-                    prompt.with_help_message(&help_message).prompt()
-                } else {
-                    prompt.prompt()
-                };
-                Ok(PromptValue::String(value.into_diagnostic()?))
-            }
-            None => {
-                let prompt = self.text_prompt();
-                let value = if let Some(help_message) = help_message {
-                    prompt.with_help_message(&help_message).prompt()
-                } else {
-                    prompt.prompt()
-                };
-                Ok(PromptValue::String(value.into_diagnostic()?))
-            }
+        Ok(PromptValue::String(prompt.prompt().into_diagnostic()?))
+    }
+
+    fn ask_integer(&self) -> Result<PromptValue> {
+        let (min, max) = (self.min, self.max);
+        let validator = move |input: &str| validate_integer(input, min, max);
+
+        let default = match &self.default {
+            Some(PromptValue::Integer(i)) => Some(i.to_string()),
+            Some(PromptValue::String(s)) => Some(s.clone()),
+            _ => None,
+        };
+
+        let mut prompt = Text::new(&self.message).with_validator(validator);
+        if let Some(default) = &default {
+            prompt = prompt.with_default(default);
+        }
+        let help_message = self.help.clone().or_else(|| integer_range_help(min, max));
+        if let Some(help_message) = &help_message {
+            prompt = prompt.with_help_message(help_message);
+        }
+
+        let value = prompt.prompt().into_diagnostic()?;
+        let value = value.trim().parse::<i64>().into_diagnostic()?;
+        Ok(PromptValue::Integer(value))
+    }
+
+    fn ask_multi_select(&self) -> Result<PromptValue> {
+        let choices = self.choices.clone().unwrap_or_default();
+        if choices.is_empty() {
+            return Err(miette::miette!(
+                "the prompt `{}` doesn't have any choices to select",
+                self.message
+            ));
+        }
+
+        let defaults: Vec<usize> = match &self.default {
+            Some(PromptValue::List(values)) => choices
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| values.contains(c))
+                .map(|(i, _)| i)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let mut prompt = MultiSelect::new(&self.message, choices).with_default(&defaults);
+        if let Some(help_message) = &self.help {
+            prompt = prompt.with_help_message(help_message);
+        }
+
+        let values = prompt.prompt().into_diagnostic()?;
+        Ok(PromptValue::List(values))
+    }
+
+    fn ask_secret(&self) -> Result<PromptValue> {
+        let mut prompt =
+            Password::new(&self.message).with_display_mode(PasswordDisplayMode::Masked);
+        if let Some(help_message) = &self.help {
+            prompt = prompt.with_help_message(help_message);
+        }
+
+        let value = prompt.prompt().into_diagnostic()?;
+        match (&self.default, value.is_empty()) {
+            (Some(default), true) => Ok(default.clone()),
+            _ => Ok(PromptValue::String(value)),
         }
     }
 
@@ -196,6 +346,36 @@ fn suggest_choice(input: &str, choices: &[String]) -> Result<Vec<String>, Custom
         .collect())
 }
 
+fn validate_integer(
+    input: &str,
+    min: Option<i64>,
+    max: Option<i64>,
+) -> Result<Validation, CustomUserError> {
+    let Ok(value) = input.trim().parse::<i64>() else {
+        return Ok(Validation::Invalid(ErrorMessage::Custom(format!(
+            "invalid number: {input}"
+        ))));
+    };
+
+    if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+        let range = integer_range_help(min, max).unwrap_or_default();
+        return Ok(Validation::Invalid(ErrorMessage::Custom(format!(
+            "invalid number: {value}, {range}"
+        ))));
+    }
+
+    Ok(Validation::Valid)
+}
+
+fn integer_range_help(min: Option<i64>, max: Option<i64>) -> Option<String> {
+    match (min, max) {
+        (Some(min), Some(max)) => Some(format!("the value must be between {min} and {max}")),
+        (Some(min), None) => Some(format!("the value must be at least {min}")),
+        (None, Some(max)) => Some(format!("the value must be at most {max}")),
+        (None, None) => None,
+    }
+}
+
 fn validate_choice(input: &str, choices: &[String]) -> Result<Validation, CustomUserError> {
     if choices.contains(&input.to_string()) {
         Ok(Validation::Valid)
@@ -325,6 +505,81 @@ mod tests {
         assert_eq!(variables["license"], "Ignore license");
     }
 
+    #[test]
+    fn test_parse_prompt_kinds() {
+        let config: CargoLambdaConfig = toml::from_str(
+            r#"
+            [template.prompts]
+            memory = { message = "Memory size", default = 128, min = 128, max = 10240 }
+            regions = { message = "Regions", choices = ["us-east-1", "eu-west-1"], default = ["eu-west-1"] }
+            features = { message = "Features", type = "multi_select", choices = ["tracing", "metrics"] }
+            api_key = { message = "API key", type = "secret" }
+            retries = { message = "Retries", type = "integer", min = 1 }
+            "#,
+        )
+        .unwrap();
+        let prompts = config.template.prompts;
+
+        assert_eq!(PromptKind::Integer, prompts["memory"].kind());
+        assert_eq!(Some(PromptValue::Integer(128)), prompts["memory"].default);
+        assert_eq!(Some(10240), prompts["memory"].max);
+
+        assert_eq!(PromptKind::MultiSelect, prompts["regions"].kind());
+        assert_eq!(
+            Some(PromptValue::List(vec!["eu-west-1".to_string()])),
+            prompts["regions"].default
+        );
+
+        assert_eq!(PromptKind::MultiSelect, prompts["features"].kind());
+        assert_eq!(
+            PromptValue::List(Vec::new()),
+            prompts["features"].default_value()
+        );
+
+        assert_eq!(PromptKind::Secret, prompts["api_key"].kind());
+        assert_eq!(
+            PromptValue::String(String::new()),
+            prompts["api_key"].default_value()
+        );
+
+        assert_eq!(PromptKind::Integer, prompts["retries"].kind());
+        assert_eq!(PromptValue::Integer(1), prompts["retries"].default_value());
+
+        let config = TemplateConfig {
+            prompts,
+            ..Default::default()
+        };
+        assert_eq!(vec!["api_key"], config.secret_prompts());
+        let secrets = config.ask_secret_options(true).unwrap();
+        assert_eq!(1, secrets.len());
+        assert_eq!(secrets["api_key"], Value::scalar(""));
+
+        let variables = config.ask_template_options(true).unwrap();
+        assert_eq!(variables["memory"], Value::scalar(128_i64));
+        assert_eq!(
+            variables["regions"],
+            Value::array(vec![Value::scalar("eu-west-1")])
+        );
+    }
+
+    #[test]
+    fn test_validate_integer() {
+        assert_eq!(
+            validate_integer("512", Some(128), Some(10240)).unwrap(),
+            Validation::Valid
+        );
+        assert_eq!(
+            validate_integer("64", Some(128), Some(10240)).unwrap(),
+            Validation::Invalid(ErrorMessage::Custom(
+                "invalid number: 64, the value must be between 128 and 10240".to_string()
+            ))
+        );
+        assert_eq!(
+            validate_integer("lots", None, None).unwrap(),
+            Validation::Invalid(ErrorMessage::Custom("invalid number: lots".to_string()))
+        );
+    }
+
     #[test]
     fn test_parse_template_config_render_conditions() {
         let config = parse_template_config("../../tests/templates/config-template").unwrap();
@@ -0,0 +1,78 @@
+use cargo_lambda_interactive::command::new_command;
+use miette::{IntoDiagnostic, NamedSource, Result, WrapErr};
+use std::{fs::read_to_string, path::Path};
+
+use crate::error::CreateError;
+
+/// Check that the rendered project is a valid Cargo package before writing it in the destination,
+/// so broken templates don't leave half-created projects behind.
+/// The manifest is always parsed; `cargo metadata` only runs when `check_metadata` is true.
+pub(crate) async fn validate_project(render_path: &Path, check_metadata: bool) -> Result<()> {
+    let manifest_path = render_path.join("Cargo.toml");
+    if !manifest_path.is_file() {
+        return Ok(());
+    }
+
+    let manifest = read_to_string(&manifest_path).into_diagnostic()?;
+    validate_manifest(&manifest)?;
+
+    if check_metadata {
+        validate_metadata(&manifest_path).await?;
+    }
+
+    Ok(())
+}
+
+fn validate_manifest(manifest: &str) -> Result<(), CreateError> {
+    let Err(err) = toml::from_str::<toml::Table>(manifest) else {
+        return Ok(());
+    };
+
+    Err(CreateError::InvalidRenderedManifest {
+        message: err.message().to_string(),
+        src: NamedSource::new("Cargo.toml", manifest.to_string()),
+        span: err.span().map(Into::into),
+    })
+}
+
+async fn validate_metadata(manifest_path: &Path) -> Result<()> {
+    let output = new_command("cargo")
+        .args([
+            "metadata",
+            "--no-deps",
+            "--format-version",
+            "1",
+            "--manifest-path",
+        ])
+        .arg(manifest_path)
+        .output()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to run `cargo metadata`")?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(CreateError::InvalidRenderedPackage(stderr.trim().to_string()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_manifest() {
+        validate_manifest("[package]\nname = \"basic-lambda\"\n").unwrap();
+
+        let err = validate_manifest("[package]\nname = \"basic-lambda\n").unwrap_err();
+        match err {
+            CreateError::InvalidRenderedManifest { span, .. } => {
+                let span = span.unwrap();
+                assert!(span.offset() >= "[package]\nname = ".len());
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use cargo_lambda_interactive::{
+    is_stdin_tty,
+    validator::{ErrorMessage, Validation},
+    Text,
+};
+use cargo_lambda_metadata::cargo::{kind_bin_filter, load_metadata};
+use std::{
+    collections::HashMap,
+    env,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use crate::{error::CreateError, Config};
+
+/// Check that the binaries of the new package don't collide with the binaries of other packages
+/// in the workspace that contains it, because `cargo lambda watch` and `cargo lambda deploy` select
+/// functions by binary name. When the package has a single binary, it asks for a new name if the
+/// terminal is interactive.
+pub(crate) fn check_binary_names(
+    config: &mut Config,
+    name: &str,
+    path: &Path,
+) -> Result<(), CreateError> {
+    let Some(manifest_path) = find_workspace_manifest(path) else {
+        return Ok(());
+    };
+
+    let owners = binary_owners(&manifest_path, path);
+    if owners.is_empty() {
+        return Ok(());
+    }
+
+    if config.binaries_options.enabled() {
+        let names = config.binaries_options.names().iter().map(String::as_str);
+        return match find_collision(names, &owners) {
+            Some((name, package)) => Err(CreateError::BinaryNameCollision { name, package }),
+            None => Ok(()),
+        };
+    }
+
+    let bin_name = config.bin_name.as_deref().unwrap_or(name);
+    let Some((bin_name, package)) = find_collision([bin_name], &owners) else {
+        return Ok(());
+    };
+
+    if config.no_interactive || !is_stdin_tty() {
+        return Err(CreateError::BinaryNameCollision {
+            name: bin_name,
+            package,
+        });
+    }
+
+    let message = format!(
+        "The binary name `{bin_name}` is already used by the package `{package}`. What name would you like to use?"
+    );
+    let default = format!("{name}-lambda");
+    let default_available = !owners.contains_key(&default);
+    let validator = move |input: &str| {
+        let input = input.trim();
+        match owners.get(input) {
+            Some(package) => Ok(Validation::Invalid(ErrorMessage::Custom(format!(
+                "the binary name `{input}` is already used by the package `{package}`"
+            )))),
+            None if crate::validate_name(input).is_err() => Ok(Validation::Invalid(
+                ErrorMessage::Custom(format!("invalid binary name: {input}")),
+            )),
+            None => Ok(Validation::Valid),
+        }
+    };
+
+    let mut prompt = Text::new(&message).with_validator(validator);
+    if default_available {
+        prompt = prompt.with_default(&default);
+    }
+
+    let bin_name = prompt.prompt()?;
+    config.bin_name = Some(bin_name.trim().to_string());
+
+    Ok(())
+}
+
+/// Find the manifest of the workspace that contains the path, looking for
+/// a Cargo.toml file with a `[workspace]` table in the path and its ancestors.
+pub(crate) fn find_workspace_manifest(path: &Path) -> Option<PathBuf> {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        env::current_dir().ok()?.join(path)
+    };
+
+    path.ancestors()
+        .map(|dir| dir.join("Cargo.toml"))
+        .filter(|manifest| manifest.is_file())
+        .find(|manifest| {
+            read_to_string(manifest)
+                .ok()
+                .and_then(|content| toml::from_str::<toml::Table>(&content).ok())
+                .is_some_and(|table| table.contains_key("workspace"))
+        })
+}
+
+/// Names of the binaries in the workspace, with the name of the package that defines them.
+/// The package in `exclude`, if it's already a member of the workspace, is not included.
+fn binary_owners(manifest_path: &Path, exclude: &Path) -> HashMap<String, String> {
+    let metadata = match load_metadata(manifest_path) {
+        Ok(metadata) => metadata,
+        Err(err) => {
+            tracing::warn!(
+                ?err,
+                "failed to load the workspace metadata, binary names are not checked"
+            );
+            return HashMap::new();
+        }
+    };
+
+    metadata
+        .packages
+        .iter()
+        .filter(|package| metadata.workspace_members.contains(&package.id))
+        .filter(|package| package.manifest_path.parent().map(|p| p.as_std_path()) != Some(exclude))
+        .flat_map(|package| {
+            package
+                .targets
+                .iter()
+                .filter(|target| kind_bin_filter(target))
+                .map(|target| (target.name.clone(), package.name.clone()))
+        })
+        .collect()
+}
+
+/// First name that is already used in the workspace, with the package that uses it.
+fn find_collision<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+    owners: &HashMap<String, String>,
+) -> Option<(String, String)> {
+    names.into_iter().find_map(|name| {
+        owners
+            .get(name)
+            .map(|package| (name.to_string(), package.clone()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+
+    #[test]
+    fn test_find_workspace_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"functions/*\"]\n",
+        )
+        .unwrap();
+        create_dir_all(root.join("functions/orders")).unwrap();
+        write(
+            root.join("functions/orders/Cargo.toml"),
+            "[package]\nname = \"orders\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some(root.join("Cargo.toml")),
+            find_workspace_manifest(&root.join("functions/orders"))
+        );
+        // The package doesn't need to exist yet.
+        assert_eq!(
+            Some(root.join("Cargo.toml")),
+            find_workspace_manifest(&root.join("functions/payments"))
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(None, find_workspace_manifest(&dir.path().join("orders")));
+    }
+
+    #[test]
+    fn test_find_collision() {
+        let owners = HashMap::from([
+            ("get-orders".to_string(), "orders".to_string()),
+            ("create-order".to_string(), "orders".to_string()),
+        ]);
+
+        assert_eq!(None, find_collision(["payments"], &owners));
+        assert_eq!(
+            Some(("create-order".to_string(), "orders".to_string())),
+            find_collision(["list-payments", "create-order"], &owners)
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use lambda_extension::{
+    service_fn, tracing, Error, Extension, LambdaLog, LambdaLogRecord, LogBuffering,
+    SharedService,
+};
+use std::env;
+
+/// Send a batch of function logs to the destination as a JSON array.
+/// Lambda buffers the logs, and sends them in batches, according to the buffering configuration.
+async fn ship(client: &reqwest::Client, destination: &str, logs: Vec<LambdaLog>) -> Result<(), Error> {
+    let lines = logs
+        .into_iter()
+        .filter_map(|log| match log.record {
+            LambdaLogRecord::Function(message) => Some(serde_json::json!({
+                "time": log.time.to_rfc3339(),
+                "message": message,
+            })),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    if lines.is_empty() {
+        return Ok(());
+    }
+
+    client
+        .post(destination)
+        .json(&lines)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing::init_default_subscriber();
+
+    // URL that receives the logs, like an HTTP log collector.
+    let destination = env::var("LOG_DESTINATION_URL")?;
+    let client = reqwest::Client::new();
+
+    let processor = SharedService::new(service_fn(move |logs: Vec<LambdaLog>| {
+        let client = client.clone();
+        let destination = destination.clone();
+        async move { ship(&client, &destination, logs).await }
+    }));
+
+    Extension::new()
+        .with_logs_processor(processor)
+        .with_log_types(&["function"])
+        .with_log_buffering(LogBuffering {
+            timeout_ms: {{ log_buffer_timeout_ms }},
+            max_bytes: {{ log_buffer_max_bytes }},
+            max_items: {{ log_buffer_max_items }},
+        })
+        .run()
+        .await
+}
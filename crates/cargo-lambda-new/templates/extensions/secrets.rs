@@ -0,0 +1,74 @@
+use aws_sdk_secretsmanager::Client;
+use lambda_extension::{service_fn, tracing, Error, Extension, LambdaEvent, NextEvent};
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Secrets to fetch before the function runs.
+const SECRET_IDS: &[&str] = &[{% for id in secret_ids %}"{{ id }}", {% endfor %}];
+
+/// Time before the secrets are fetched again.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Directory where the function reads the secrets from, one file for each secret.
+const SECRETS_DIR: &str = "/tmp/secrets";
+
+/// Fetch the secrets from Secrets Manager, and write them in the secrets directory.
+async fn fetch_secrets(client: &Client) -> Result<(), Error> {
+    fs::create_dir_all(SECRETS_DIR)?;
+
+    for id in SECRET_IDS {
+        let output = client.get_secret_value().secret_id(*id).send().await?;
+        let value = output.secret_string().unwrap_or_default();
+
+        let name = id.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "_");
+        fs::write(Path::new(SECRETS_DIR).join(name), value)?;
+    }
+
+    tracing::info!(secrets = SECRET_IDS.len(), "secrets fetched");
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing::init_default_subscriber();
+
+    let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+    let client = Client::new(&config);
+
+    // Fetch the secrets during the extension's initialization, so they're ready for the first invocation.
+    fetch_secrets(&client).await?;
+    let last_fetch = Arc::new(Mutex::new(Instant::now()));
+
+    let processor = service_fn(move |event: LambdaEvent| {
+        let client = client.clone();
+        let last_fetch = last_fetch.clone();
+
+        async move {
+            if let NextEvent::Invoke(_) = event.next {
+                let expired = {
+                    let mut last_fetch = last_fetch.lock().unwrap();
+                    let expired = last_fetch.elapsed() >= REFRESH_INTERVAL;
+                    if expired {
+                        *last_fetch = Instant::now();
+                    }
+                    expired
+                };
+
+                if expired {
+                    fetch_secrets(&client).await?;
+                }
+            }
+
+            Ok::<(), Error>(())
+        }
+    });
+
+    Extension::new()
+        .with_events_processor(processor)
+        .run()
+        .await
+}
@@ -0,0 +1,39 @@
+use lambda_extension::{
+    service_fn, tracing, Error, Extension, LambdaTelemetry, LambdaTelemetryRecord, SharedService,
+};
+
+/// Process the spans that Lambda reports when the runtime finishes an invocation.
+/// Replace the log statements with your own exporter, like an OpenTelemetry collector.
+async fn handler(events: Vec<LambdaTelemetry>) -> Result<(), Error> {
+    for event in events {
+        if let LambdaTelemetryRecord::PlatformRuntimeDone {
+            request_id, spans, ..
+        } = event.record
+        {
+            for span in spans {
+                tracing::info!(
+                    request_id = %request_id,
+                    span = %span.name,
+                    duration_ms = span.duration_ms,
+                    start = %span.start,
+                    "invocation span"
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing::init_default_subscriber();
+
+    let processor = SharedService::new(service_fn(handler));
+
+    Extension::new()
+        .with_telemetry_processor(processor)
+        .with_telemetry_types(&["platform"])
+        .run()
+        .await
+}
@@ -0,0 +1,6 @@
+#!/usr/bin/env node
+import * as cdk from 'aws-cdk-lib';
+import { FunctionStack } from '../lib/stack';
+
+const app = new cdk.App();
+new FunctionStack(app, '{{ project_name }}');
@@ -0,0 +1,27 @@
+import * as path from 'path';
+import * as cdk from 'aws-cdk-lib';
+import * as lambda from 'aws-cdk-lib/aws-lambda';
+import { Construct } from 'constructs';
+
+// Build the function with `cargo lambda build --release {{ build_flag }}` before deploying this stack.
+export class FunctionStack extends cdk.Stack {
+  constructor(scope: Construct, id: string, props?: cdk.StackProps) {
+    super(scope, id, props);
+
+    const fn = new lambda.Function(this, 'Function', {
+      functionName: '{{ lambda_function_name }}',
+      code: lambda.Code.fromAsset(path.join(__dirname, '..', '..', 'target', 'lambda', '{{ lambda_function_name }}')),
+      handler: 'bootstrap',
+      runtime: lambda.Runtime.PROVIDED_AL2023,
+{%- if architecture == "arm64" %}
+      architecture: lambda.Architecture.ARM_64,
+{%- else %}
+      architecture: lambda.Architecture.X86_64,
+{%- endif %}
+      memorySize: 128,
+      timeout: cdk.Duration.seconds(30),
+    });
+
+    new cdk.CfnOutput(this, 'FunctionArn', { value: fn.functionArn });
+  }
+}
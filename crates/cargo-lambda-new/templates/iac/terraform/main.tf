@@ -0,0 +1,50 @@
+# Build the function with `cargo lambda build --release {{ build_flag }} --output-format zip` before applying this configuration.
+terraform {
+  required_providers {
+    aws = {
+      source  = "hashicorp/aws"
+      version = "~> 5.0"
+    }
+  }
+}
+
+provider "aws" {}
+
+locals {
+  artifact_path = "${path.module}/../target/lambda/{{ lambda_function_name }}/bootstrap.zip"
+}
+
+resource "aws_iam_role" "function" {
+  name = "{{ lambda_function_name }}-role"
+  assume_role_policy = jsonencode({
+    Version = "2012-10-17"
+    Statement = [
+      {
+        Action    = "sts:AssumeRole"
+        Effect    = "Allow"
+        Principal = { Service = "lambda.amazonaws.com" }
+      }
+    ]
+  })
+}
+
+resource "aws_iam_role_policy_attachment" "function_logs" {
+  role       = aws_iam_role.function.name
+  policy_arn = "arn:aws:iam::aws:policy/service-role/AWSLambdaBasicExecutionRole"
+}
+
+resource "aws_lambda_function" "function" {
+  function_name    = "{{ lambda_function_name }}"
+  role             = aws_iam_role.function.arn
+  filename         = local.artifact_path
+  source_code_hash = filebase64sha256(local.artifact_path)
+  handler          = "bootstrap"
+  runtime          = "provided.al2023"
+  architectures    = ["{{ architecture }}"]
+  memory_size      = 128
+  timeout          = 30
+}
+
+output "function_arn" {
+  value = aws_lambda_function.function.arn
+}
@@ -0,0 +1,131 @@
+use aws_types::{
+    service_config::{LoadServiceConfig, ServiceConfigKey},
+    SdkConfig,
+};
+use std::collections::HashMap;
+
+/// Default endpoint of LocalStack, when `--localstack` doesn't have a URL.
+pub const LOCALSTACK_ENDPOINT: &str = "http://localhost:4566";
+
+/// Names that the configuration accepts for each service, and the service ids in the SDK.
+const SERVICES: [(&str, &str); 5] = [
+    ("lambda", "Lambda"),
+    ("s3", "S3"),
+    ("iam", "IAM"),
+    ("logs", "CloudWatch Logs"),
+    ("sts", "STS"),
+];
+
+/// ServiceEndpoints sets the endpoint URL of each service in the SDK clients.
+/// Services without an endpoint in the configuration use the `AWS_ENDPOINT_URL_<SERVICE>`
+/// environment variables, or the global endpoint URL.
+#[derive(Debug)]
+pub(crate) struct ServiceEndpoints {
+    endpoints: HashMap<String, String>,
+}
+
+impl ServiceEndpoints {
+    pub(crate) fn new(endpoints: &HashMap<String, String>) -> Self {
+        let endpoints = endpoints
+            .iter()
+            .map(|(service, url)| (service_id(service).to_lowercase(), url.clone()))
+            .collect();
+        ServiceEndpoints { endpoints }
+    }
+}
+
+impl LoadServiceConfig for ServiceEndpoints {
+    fn load_config(&self, key: ServiceConfigKey<'_>) -> Option<String> {
+        if key.profile() == "endpoint_url" {
+            if let Some(url) = self.endpoints.get(&key.service_id().to_lowercase()) {
+                return Some(url.clone());
+            }
+        }
+
+        let env = format!(
+            "{}_{}",
+            key.env(),
+            key.service_id().to_uppercase().replace(' ', "_")
+        );
+        std::env::var(env).ok()
+    }
+}
+
+/// Service id in the SDK for a service name in the configuration, like `logs`.
+fn service_id(service: &str) -> &str {
+    SERVICES
+        .iter()
+        .find(|(name, id)| service.eq_ignore_ascii_case(name) || service.eq_ignore_ascii_case(id))
+        .map(|(_, id)| *id)
+        .unwrap_or(service)
+}
+
+/// Whether the clients for a service send requests to a custom endpoint, like an emulator.
+/// The service name is the same as in the `endpoints` configuration, like `s3`.
+pub fn has_custom_endpoint(sdk_config: &SdkConfig, service: &str) -> bool {
+    if sdk_config.endpoint_url().is_some() {
+        return true;
+    }
+
+    let Ok(key) = ServiceConfigKey::builder()
+        .service_id(service_id(service))
+        .env("AWS_ENDPOINT_URL")
+        .profile("endpoint_url")
+        .build()
+    else {
+        return false;
+    };
+
+    sdk_config
+        .service_config()
+        .and_then(|config| config.load_config(key))
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(service_id: &str) -> ServiceConfigKey<'_> {
+        ServiceConfigKey::builder()
+            .service_id(service_id)
+            .env("AWS_ENDPOINT_URL")
+            .profile("endpoint_url")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_service_endpoints() {
+        let endpoints = ServiceEndpoints::new(&HashMap::from([
+            ("lambda".to_string(), "http://localhost:9001".to_string()),
+            ("logs".to_string(), "http://localhost:9002".to_string()),
+        ]));
+
+        assert_eq!(
+            endpoints.load_config(key("Lambda")).as_deref(),
+            Some("http://localhost:9001")
+        );
+        assert_eq!(
+            endpoints.load_config(key("CloudWatch Logs")).as_deref(),
+            Some("http://localhost:9002")
+        );
+        assert_eq!(endpoints.load_config(key("IAM")), None);
+    }
+
+    #[test]
+    fn test_has_custom_endpoint() {
+        let endpoints = HashMap::from([("s3".to_string(), "http://localhost:9000".to_string())]);
+        let config = SdkConfig::builder()
+            .service_config(ServiceEndpoints::new(&endpoints))
+            .build();
+        assert!(has_custom_endpoint(&config, "s3"));
+        assert!(!has_custom_endpoint(&config, "lambda"));
+
+        let config = SdkConfig::builder()
+            .endpoint_url(LOCALSTACK_ENDPOINT)
+            .build();
+        assert!(has_custom_endpoint(&config, "lambda"));
+        assert!(!has_custom_endpoint(&SdkConfig::builder().build(), "s3"));
+    }
+}
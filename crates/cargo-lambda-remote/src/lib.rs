@@ -1,16 +1,47 @@
 use aws_config::{
     meta::region::RegionProviderChain,
-    profile::{ProfileFileCredentialsProvider, ProfileFileRegionProvider},
-    provider_config::ProviderConfig,
-    retry::RetryConfig,
+    profile::ProfileFileRegionProvider,
+    retry::{RetryConfig, RetryMode as SdkRetryMode},
+    timeout::TimeoutConfig,
     BehaviorVersion,
 };
+use aws_credential_types::Credentials;
 use aws_types::{region::Region, SdkConfig};
-use clap::Args;
+use clap::{Args, ValueEnum};
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf, time::Duration};
 pub mod tls;
 
-const DEFAULT_REGION: &str = "us-east-1";
+mod endpoints;
+use endpoints::ServiceEndpoints;
+pub use endpoints::{has_custom_endpoint, LOCALSTACK_ENDPOINT};
+
+mod mfa;
+use mfa::{MfaCredentialsProvider, MfaProfile};
+
+pub mod network;
+
+mod partition;
+pub use partition::Partition;
+
+mod region;
+pub use region::DEFAULT_REGION;
+
+mod role;
+pub use role::assume_role_provider;
+use role::profile_credentials;
+
+/// Session name of the roles that Cargo Lambda assumes, so CloudTrail shows where the requests come from.
+const ROLE_SESSION_NAME: &str = "cargo-lambda";
+
+/// Name of the user's configuration file, in the Cargo Lambda directory of the user's configuration directory.
+const USER_CONFIG_FILE: &str = "config.toml";
+
+/// Location of the user's configuration file, with the defaults for all the projects,
+/// like `~/.config/cargo-lambda/config.toml` in Linux.
+pub fn user_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|p| p.join("cargo-lambda").join(USER_CONFIG_FILE))
+}
 
 #[derive(Args, Clone, Debug, Default, Deserialize, Serialize)]
 pub struct RemoteConfig {
@@ -34,10 +65,81 @@ pub struct RemoteConfig {
     #[serde(default)]
     pub retry_attempts: Option<u32>,
 
+    /// Retry mode for failed operations, `adaptive` also slows down requests when AWS throttles them
+    #[arg(long, value_enum)]
+    #[serde(default)]
+    pub retry_mode: Option<RetryMode>,
+
+    /// Maximum time in seconds to establish a connection with AWS
+    #[arg(long, value_name = "SECONDS")]
+    #[serde(default)]
+    pub connect_timeout: Option<u64>,
+
+    /// Maximum time in seconds to wait for the response of an AWS request
+    #[arg(long, value_name = "SECONDS")]
+    #[serde(default)]
+    pub read_timeout: Option<u64>,
+
     /// Custom endpoint URL to target
     #[arg(long)]
     #[serde(default)]
     pub endpoint_url: Option<String>,
+
+    /// Send the requests to LocalStack, on http://localhost:4566 unless you use a different URL
+    #[arg(
+        long,
+        value_name = "URL",
+        num_args = 0..=1,
+        default_missing_value = LOCALSTACK_ENDPOINT,
+        conflicts_with = "endpoint_url"
+    )]
+    #[serde(default)]
+    pub localstack: Option<String>,
+
+    // `endpoints`, `role_arn`, `source_profile`, and `external_id` can only be set
+    // in the configuration files, so they don't have flags.
+    /// Custom endpoint URL for each service: `lambda`, `s3`, `iam`, `logs`, and `sts`
+    #[arg(skip)]
+    #[serde(default)]
+    pub endpoints: Option<HashMap<String, String>>,
+
+    /// AWS partition of the account: `aws`, `aws-us-gov`, or `aws-cn`. It's inferred from the region by default
+    #[arg(long, value_enum)]
+    #[serde(default)]
+    pub partition: Option<Partition>,
+
+    /// ARN of an IAM role to assume, with the credentials of the profile, or the source profile
+    #[arg(skip)]
+    #[serde(default)]
+    pub role_arn: Option<String>,
+
+    /// AWS profile with the credentials to assume the role in `role_arn`
+    #[arg(skip)]
+    #[serde(default)]
+    pub source_profile: Option<String>,
+
+    /// External ID that the trust policy of the role in `role_arn` requires
+    #[arg(skip)]
+    #[serde(default)]
+    pub external_id: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum RetryMode {
+    /// Retry with exponential backoff
+    Standard,
+    /// Retry with exponential backoff, and limit the request rate when AWS throttles requests
+    Adaptive,
+}
+
+impl From<RetryMode> for SdkRetryMode {
+    fn from(mode: RetryMode) -> Self {
+        match mode {
+            RetryMode::Standard => SdkRetryMode::Standard,
+            RetryMode::Adaptive => SdkRetryMode::Adaptive,
+        }
+    }
 }
 
 impl RemoteConfig {
@@ -46,23 +148,96 @@ impl RemoteConfig {
         RetryConfig::standard().with_max_attempts(attempts)
     }
 
-    pub async fn sdk_config(&self, retry: Option<RetryConfig>) -> SdkConfig {
+    /// Partition of the account, from the flag `--partition`, or from the region in the SDK configuration.
+    pub fn partition(&self, sdk_config: &SdkConfig) -> Partition {
+        self.partition
+            .unwrap_or_else(|| Partition::from_sdk_config(sdk_config))
+    }
+
+    /// Use the role to assume in the defaults, when this configuration doesn't have one.
+    pub fn inherit_role(&mut self, defaults: &RemoteConfig) {
+        if self.role_arn.is_none() {
+            self.role_arn = defaults.role_arn.clone();
+            self.source_profile = defaults.source_profile.clone();
+            self.external_id = defaults.external_id.clone();
+        }
+    }
+
+    /// Timeouts for the SDK clients, only when the configuration has any.
+    fn timeout_config(&self) -> Option<TimeoutConfig> {
+        if self.connect_timeout.is_none() && self.read_timeout.is_none() {
+            return None;
+        }
+
+        let mut builder = TimeoutConfig::builder();
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(Duration::from_secs(timeout));
+        }
+        if let Some(timeout) = self.read_timeout {
+            builder = builder.read_timeout(Duration::from_secs(timeout));
+        }
+        Some(builder.build())
+    }
+
+    /// Region from the flags, the environment, or the AWS profile.
+    async fn resolve_region(&self) -> Option<Region> {
         let explicit_region = self.region.clone().map(Region::new);
 
-        let region_provider = RegionProviderChain::first_try(explicit_region.clone())
-            .or_default_provider()
-            .or_else(Region::new(DEFAULT_REGION));
+        match &self.profile {
+            Some(profile) => {
+                let profile_region = ProfileFileRegionProvider::builder()
+                    .profile_name(profile)
+                    .build();
+                RegionProviderChain::first_try(explicit_region)
+                    .or_else(profile_region)
+                    .region()
+                    .await
+            }
+            None => {
+                RegionProviderChain::first_try(explicit_region)
+                    .or_default_provider()
+                    .region()
+                    .await
+            }
+        }
+    }
 
-        let retry = retry.unwrap_or_else(|| self.retry_policy());
-        let mut config_loader = if let Some(ref endpoint_url) = self.endpoint_url {
-            aws_config::defaults(BehaviorVersion::latest())
-                .endpoint_url(endpoint_url)
-                .region(region_provider)
-                .retry_config(retry)
-        } else {
-            aws_config::defaults(BehaviorVersion::latest())
-                .region(region_provider)
-                .retry_config(retry)
+    /// Ask for a region in interactive terminals, when the flags, the environment, and the AWS profile
+    /// don't have one. Commands that create resources use it, so they don't use the default region by mistake.
+    pub async fn select_region(&mut self) -> miette::Result<()> {
+        if self.resolve_region().await.is_none() {
+            self.region = region::ask_region()?;
+        }
+        Ok(())
+    }
+
+    /// AWS SDK configuration for the remote options.
+    /// It fails when the CA bundle can't be read, instead of ignoring its certificates.
+    pub async fn sdk_config(
+        &self,
+        retry: Option<RetryConfig>,
+    ) -> Result<SdkConfig, network::NetworkError> {
+        let explicit_region = self.region.clone().map(Region::new);
+
+        let mut retry = retry.unwrap_or_else(|| self.retry_policy());
+        if let Some(mode) = self.retry_mode {
+            retry = retry.with_retry_mode(mode.into());
+        }
+        let endpoint_url = self.endpoint_url.as_ref().or(self.localstack.as_ref());
+        let mut config_loader = aws_config::defaults(BehaviorVersion::latest()).retry_config(retry);
+        if let Some(endpoint_url) = endpoint_url {
+            config_loader = config_loader.endpoint_url(endpoint_url);
+        }
+
+        // Profiles that require MFA are not supported by the SDK's profile provider.
+        // The MFA provider is only used for profiles selected explicitly, because the SDK
+        // prefers the credentials in the environment over the profile in `AWS_PROFILE`.
+        let mfa_profile = match (&self.profile, std::env::var("AWS_PROFILE")) {
+            (Some(profile), _) => MfaProfile::load(profile).await,
+            (None, Ok(profile)) if std::env::var_os("AWS_ACCESS_KEY_ID").is_none() => {
+                MfaProfile::load(&profile).await
+            }
+            _ => None,
         };
 
         if let Some(profile) = &self.profile {
@@ -74,19 +249,74 @@ impl RemoteConfig {
                 RegionProviderChain::first_try(explicit_region).or_else(profile_region);
             let region = region_provider.region().await;
 
-            let conf = ProviderConfig::default().with_region(region);
+            config_loader = config_loader.region(region_provider);
+            config_loader = match mfa_profile {
+                Some(mfa_profile) => config_loader
+                    .credentials_provider(MfaCredentialsProvider::new(mfa_profile, region)),
+                None => config_loader.credentials_provider(profile_credentials(profile, region)),
+            };
+        } else {
+            let region = self
+                .resolve_region()
+                .await
+                .unwrap_or_else(region::default_region);
+
+            config_loader = config_loader.region(region.clone());
+            if let Some(mfa_profile) = mfa_profile {
+                config_loader = config_loader
+                    .credentials_provider(MfaCredentialsProvider::new(mfa_profile, Some(region)));
+            }
+        }
 
-            let creds_provider = ProfileFileCredentialsProvider::builder()
-                .profile_name(profile)
-                .configure(&conf)
-                .build();
+        if let Some(timeout_config) = self.timeout_config() {
+            config_loader = config_loader.timeout_config(timeout_config);
+        }
+
+        if let Some(http_client) = network::sdk_http_client()? {
+            config_loader = config_loader.http_client(http_client);
+        }
 
-            config_loader = config_loader
-                .region(region_provider)
-                .credentials_provider(creds_provider);
+        // LocalStack accepts any credentials, these ones select its default account.
+        if self.localstack.is_some() && self.profile.is_none() {
+            config_loader = config_loader.credentials_provider(Credentials::new(
+                "test",
+                "test",
+                None,
+                None,
+                "LocalStack",
+            ));
         }
 
-        config_loader.load().await
+        let sdk_config = config_loader.load().await;
+        let sdk_config = match &self.role_arn {
+            Some(role_arn) => self.assume_role(sdk_config, role_arn).await,
+            None => sdk_config,
+        };
+        let sdk_config = match &self.endpoints {
+            Some(endpoints) if !endpoints.is_empty() => sdk_config
+                .into_builder()
+                .service_config(ServiceEndpoints::new(endpoints))
+                .build(),
+            _ => sdk_config,
+        };
+        Ok(sdk_config)
+    }
+
+    /// Replace the credentials in the SDK configuration with the credentials of the role.
+    async fn assume_role(&self, sdk_config: SdkConfig, role_arn: &str) -> SdkConfig {
+        let provider = assume_role_provider(
+            &sdk_config,
+            role_arn,
+            ROLE_SESSION_NAME,
+            self.external_id.as_deref(),
+            self.source_profile.as_deref(),
+        )
+        .await;
+
+        sdk_config
+            .into_builder()
+            .credentials_provider(provider)
+            .build()
     }
 
     pub fn count_fields(&self) -> usize {
@@ -94,7 +324,16 @@ impl RemoteConfig {
             + self.region.is_some() as usize
             + self.alias.is_some() as usize
             + self.retry_attempts.is_some() as usize
+            + self.retry_mode.is_some() as usize
+            + self.connect_timeout.is_some() as usize
+            + self.read_timeout.is_some() as usize
             + self.endpoint_url.is_some() as usize
+            + self.localstack.is_some() as usize
+            + self.endpoints.is_some() as usize
+            + self.partition.is_some() as usize
+            + self.role_arn.is_some() as usize
+            + self.source_profile.is_some() as usize
+            + self.external_id.is_some() as usize
     }
 
     pub fn serialize_fields<S>(
@@ -116,9 +355,36 @@ impl RemoteConfig {
         if let Some(ref retry_attempts) = self.retry_attempts {
             state.serialize_field("retry_attempts", retry_attempts)?;
         }
+        if let Some(ref retry_mode) = self.retry_mode {
+            state.serialize_field("retry_mode", retry_mode)?;
+        }
+        if let Some(ref connect_timeout) = self.connect_timeout {
+            state.serialize_field("connect_timeout", connect_timeout)?;
+        }
+        if let Some(ref read_timeout) = self.read_timeout {
+            state.serialize_field("read_timeout", read_timeout)?;
+        }
         if let Some(ref endpoint_url) = self.endpoint_url {
             state.serialize_field("endpoint_url", endpoint_url)?;
         }
+        if let Some(ref localstack) = self.localstack {
+            state.serialize_field("localstack", localstack)?;
+        }
+        if let Some(ref endpoints) = self.endpoints {
+            state.serialize_field("endpoints", endpoints)?;
+        }
+        if let Some(ref partition) = self.partition {
+            state.serialize_field("partition", partition)?;
+        }
+        if let Some(ref role_arn) = self.role_arn {
+            state.serialize_field("role_arn", role_arn)?;
+        }
+        if let Some(ref source_profile) = self.source_profile {
+            state.serialize_field("source_profile", source_profile)?;
+        }
+        if let Some(ref external_id) = self.external_id {
+            state.serialize_field("external_id", external_id)?;
+        }
 
         Ok(())
     }
@@ -133,7 +399,10 @@ pub use aws_sdk_lambda;
 mod tests {
     use aws_sdk_lambda::config::{ProvideCredentials, Region};
 
-    use crate::RemoteConfig;
+    use aws_config::retry::RetryMode as SdkRetryMode;
+    use std::time::Duration;
+
+    use crate::{RemoteConfig, RetryMode};
 
     fn setup() {
         let manifest_dir = env!("CARGO_MANIFEST_DIR");
@@ -160,10 +429,19 @@ mod tests {
             region: None,
             alias: None,
             retry_attempts: Some(1),
+            retry_mode: None,
+            connect_timeout: None,
+            read_timeout: None,
             endpoint_url: None,
+            localstack: None,
+            endpoints: None,
+            partition: None,
+            role_arn: None,
+            source_profile: None,
+            external_id: None,
         };
 
-        let config = args.sdk_config(None).await;
+        let config = args.sdk_config(None).await.unwrap();
         let creds = config
             .credentials_provider()
             .unwrap()
@@ -187,10 +465,19 @@ mod tests {
             region: None,
             alias: None,
             retry_attempts: Some(1),
+            retry_mode: None,
+            connect_timeout: None,
+            read_timeout: None,
             endpoint_url: None,
+            localstack: None,
+            endpoints: None,
+            partition: None,
+            role_arn: None,
+            source_profile: None,
+            external_id: None,
         };
 
-        let config = args.sdk_config(None).await;
+        let config = args.sdk_config(None).await.unwrap();
         let creds = config
             .credentials_provider()
             .unwrap()
@@ -215,10 +502,19 @@ mod tests {
             region: None,
             alias: None,
             retry_attempts: Some(1),
+            retry_mode: None,
+            connect_timeout: None,
+            read_timeout: None,
             endpoint_url: None,
+            localstack: None,
+            endpoints: None,
+            partition: None,
+            role_arn: None,
+            source_profile: None,
+            external_id: None,
         };
 
-        let config = args.sdk_config(None).await;
+        let config = args.sdk_config(None).await.unwrap();
         let creds = config
             .credentials_provider()
             .unwrap()
@@ -243,10 +539,19 @@ mod tests {
             region: None,
             alias: None,
             retry_attempts: Some(1),
+            retry_mode: None,
+            connect_timeout: None,
+            read_timeout: None,
             endpoint_url: None,
+            localstack: None,
+            endpoints: None,
+            partition: None,
+            role_arn: None,
+            source_profile: None,
+            external_id: None,
         };
 
-        let config = args.sdk_config(None).await;
+        let config = args.sdk_config(None).await.unwrap();
         let creds = config
             .credentials_provider()
             .unwrap()
@@ -271,10 +576,19 @@ mod tests {
             region: None,
             alias: None,
             retry_attempts: Some(1),
+            retry_mode: None,
+            connect_timeout: None,
+            read_timeout: None,
             endpoint_url: None,
+            localstack: None,
+            endpoints: None,
+            partition: None,
+            role_arn: None,
+            source_profile: None,
+            external_id: None,
         };
 
-        let config = args.sdk_config(None).await;
+        let config = args.sdk_config(None).await.unwrap();
         let creds = config
             .credentials_provider()
             .unwrap()
@@ -285,4 +599,61 @@ mod tests {
         assert_eq!(config.region(), Some(&Region::from_static("af-south-1")));
         assert_eq!(creds.access_key_id(), "DDDDDDDDDDDDDDDDDDDD");
     }
+
+    /// Set the retry mode and timeouts
+    /// Expectations:
+    /// - The retry mode overrides the mode of the default retry policy
+    /// - Timeouts are used from the configuration
+    #[tokio::test]
+    async fn retry_mode_and_timeouts() {
+        setup();
+
+        let args = RemoteConfig {
+            retry_attempts: Some(3),
+            retry_mode: Some(RetryMode::Adaptive),
+            connect_timeout: Some(5),
+            read_timeout: Some(30),
+            ..Default::default()
+        };
+
+        let config = args.sdk_config(None).await.unwrap();
+        let retry = config.retry_config().unwrap();
+        assert_eq!(retry.mode(), SdkRetryMode::Adaptive);
+        assert_eq!(retry.max_attempts(), 3);
+
+        let timeouts = config.timeout_config().unwrap();
+        assert_eq!(timeouts.connect_timeout(), Some(Duration::from_secs(5)));
+        assert_eq!(timeouts.read_timeout(), Some(Duration::from_secs(30)));
+    }
+
+    /// Inherit the role to assume from the defaults
+    /// Expectations:
+    /// - The role, source profile, and external ID are inherited together
+    /// - A role in the configuration is not replaced
+    #[test]
+    fn inherit_role() {
+        let defaults = RemoteConfig {
+            role_arn: Some("arn:aws:iam::123456789012:role/deployer".to_owned()),
+            source_profile: Some("apple".to_owned()),
+            external_id: Some("cargo-lambda".to_owned()),
+            ..Default::default()
+        };
+
+        let mut args = RemoteConfig::default();
+        args.inherit_role(&defaults);
+        assert_eq!(args.role_arn, defaults.role_arn);
+        assert_eq!(args.source_profile, defaults.source_profile);
+        assert_eq!(args.external_id, defaults.external_id);
+
+        let mut args = RemoteConfig {
+            role_arn: Some("arn:aws:iam::123456789012:role/reader".to_owned()),
+            ..Default::default()
+        };
+        args.inherit_role(&defaults);
+        assert_eq!(
+            args.role_arn.as_deref(),
+            Some("arn:aws:iam::123456789012:role/reader")
+        );
+        assert_eq!(args.source_profile, None);
+    }
 }
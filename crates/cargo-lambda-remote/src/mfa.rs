@@ -0,0 +1,357 @@
+use aws_config::{profile::profile_file::ProfileFiles, BehaviorVersion};
+use aws_credential_types::{
+    provider::{self, error::CredentialsError, future, ProvideCredentials},
+    Credentials,
+};
+use aws_types::{
+    os_shim_internal::{Env, Fs},
+    region::Region,
+};
+use cargo_lambda_interactive::{
+    is_stdin_tty,
+    validator::{ErrorMessage, Validation},
+    Text,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::Mutex;
+
+use crate::role::profile_credentials;
+
+/// Name of the provider in the credentials that it resolves.
+const PROVIDER_NAME: &str = "CargoLambdaMfa";
+/// Session name used when the profile doesn't set `role_session_name`.
+const SESSION_NAME: &str = "cargo-lambda";
+/// Cached sessions that expire sooner than this are not used.
+const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Profile in the AWS config file that assumes a role with an MFA device.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MfaProfile {
+    name: String,
+    role_arn: String,
+    mfa_serial: String,
+    source_profile: Option<String>,
+    role_session_name: Option<String>,
+    duration_seconds: Option<i32>,
+}
+
+impl MfaProfile {
+    /// Load the profile from the AWS config files, if it assumes a role with an MFA device.
+    pub(crate) async fn load(name: &str) -> Option<MfaProfile> {
+        MfaProfile::load_from(&Fs::real(), &Env::real(), name).await
+    }
+
+    async fn load_from(fs: &Fs, env: &Env, name: &str) -> Option<MfaProfile> {
+        let profiles =
+            match aws_config::profile::load(fs, env, &ProfileFiles::default(), None).await {
+                Ok(profiles) => profiles,
+                Err(err) => {
+                    tracing::debug!(?err, "failed to load the AWS config files");
+                    return None;
+                }
+            };
+        let profile = profiles.get_profile(name)?;
+
+        Some(MfaProfile {
+            name: name.to_string(),
+            role_arn: profile.get("role_arn")?.to_string(),
+            mfa_serial: profile.get("mfa_serial")?.to_string(),
+            source_profile: profile.get("source_profile").map(String::from),
+            role_session_name: profile.get("role_session_name").map(String::from),
+            duration_seconds: profile.get("duration_seconds").and_then(|d| d.parse().ok()),
+        })
+    }
+}
+
+/// MfaCredentialsProvider assumes the role in a profile that requires MFA.
+/// It prompts for the MFA code the first time that the credentials are needed,
+/// and caches the session, so the next commands don't prompt again until it expires.
+#[derive(Debug)]
+pub(crate) struct MfaCredentialsProvider {
+    profile: MfaProfile,
+    region: Option<Region>,
+    credentials: Mutex<Option<Credentials>>,
+}
+
+impl MfaCredentialsProvider {
+    pub(crate) fn new(profile: MfaProfile, region: Option<Region>) -> Self {
+        MfaCredentialsProvider {
+            profile,
+            region,
+            credentials: Mutex::default(),
+        }
+    }
+
+    async fn credentials(&self) -> provider::Result {
+        // The lock ensures that concurrent requests only prompt for the MFA code once.
+        let mut credentials = self.credentials.lock().await;
+        let now = SystemTime::now();
+
+        if let Some(current) = credentials.as_ref().filter(|c| is_fresh(c, now)) {
+            return Ok(current.clone());
+        }
+
+        let cache = session_cache_path(&self.profile.name);
+        if let Some(cached) = cache
+            .as_deref()
+            .and_then(|path| read_session(path, &self.profile.role_arn))
+            .filter(|c| is_fresh(c, now))
+        {
+            *credentials = Some(cached.clone());
+            return Ok(cached);
+        }
+
+        let assumed = self.assume_role().await?;
+        if let Some(path) = &cache {
+            if let Err(err) = write_session(path, &self.profile.role_arn, &assumed) {
+                tracing::debug!(?err, ?path, "failed to cache the MFA session");
+            }
+        }
+
+        *credentials = Some(assumed.clone());
+        Ok(assumed)
+    }
+
+    async fn assume_role(&self) -> provider::Result {
+        let profile = &self.profile;
+        let Some(source_profile) = &profile.source_profile else {
+            return Err(CredentialsError::invalid_configuration(format!(
+                "the profile `{}` requires MFA, but it doesn't have a `source_profile`",
+                profile.name
+            )));
+        };
+
+        if !is_stdin_tty() {
+            return Err(CredentialsError::not_loaded(format!(
+                "the profile `{}` requires an MFA code, run the command in an interactive terminal to enter it",
+                profile.name
+            )));
+        }
+
+        let token_code = Text::new(&format!("MFA code for {}:", profile.mfa_serial))
+            .with_validator(|code: &str| {
+                let code = code.trim();
+                if code.len() == 6 && code.chars().all(|c| c.is_ascii_digit()) {
+                    Ok(Validation::Valid)
+                } else {
+                    Ok(Validation::Invalid(ErrorMessage::Custom(
+                        "the MFA code must have 6 digits".into(),
+                    )))
+                }
+            })
+            .prompt()
+            .map_err(|err| CredentialsError::not_loaded(err.to_string()))?;
+        let token_code = token_code.trim();
+
+        let source = profile_credentials(source_profile, self.region.clone());
+
+        let sdk_config = aws_config::defaults(BehaviorVersion::latest())
+            .region(self.region.clone())
+            .credentials_provider(source)
+            .load()
+            .await;
+
+        let output = aws_sdk_sts::Client::new(&sdk_config)
+            .assume_role()
+            .role_arn(&profile.role_arn)
+            .role_session_name(profile.role_session_name.as_deref().unwrap_or(SESSION_NAME))
+            .serial_number(&profile.mfa_serial)
+            .token_code(token_code)
+            .set_duration_seconds(profile.duration_seconds)
+            .send()
+            .await
+            .map_err(CredentialsError::provider_error)?;
+
+        let Some(credentials) = output.credentials() else {
+            return Err(CredentialsError::provider_error(
+                "the AssumeRole response doesn't include credentials",
+            ));
+        };
+
+        Ok(Credentials::new(
+            credentials.access_key_id(),
+            credentials.secret_access_key(),
+            Some(credentials.session_token().to_string()),
+            SystemTime::try_from(*credentials.expiration()).ok(),
+            PROVIDER_NAME,
+        ))
+    }
+}
+
+impl ProvideCredentials for MfaCredentialsProvider {
+    fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+    where
+        Self: 'a,
+    {
+        future::ProvideCredentials::new(self.credentials())
+    }
+}
+
+/// Session assumed with MFA, cached between runs.
+#[derive(Debug, Deserialize, Serialize)]
+struct CachedSession {
+    role_arn: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+    /// Seconds since the Unix epoch when the session expires
+    expiration: u64,
+}
+
+fn is_fresh(credentials: &Credentials, now: SystemTime) -> bool {
+    match credentials.expiry() {
+        Some(expiry) => expiry > now + REFRESH_MARGIN,
+        None => true,
+    }
+}
+
+fn session_cache_path(profile: &str) -> Option<PathBuf> {
+    dirs::cache_dir().map(|p| {
+        p.join("cargo-lambda")
+            .join("mfa-sessions")
+            .join(format!("{profile}.json"))
+    })
+}
+
+/// Read the cached session, if it was assumed for the same role.
+fn read_session(path: &Path, role_arn: &str) -> Option<Credentials> {
+    let data = std::fs::read(path).ok()?;
+    let session: CachedSession = serde_json::from_slice(&data).ok()?;
+    if session.role_arn != role_arn {
+        return None;
+    }
+
+    Some(Credentials::new(
+        session.access_key_id,
+        session.secret_access_key,
+        session.session_token,
+        Some(UNIX_EPOCH + Duration::from_secs(session.expiration)),
+        PROVIDER_NAME,
+    ))
+}
+
+fn write_session(path: &Path, role_arn: &str, credentials: &Credentials) -> std::io::Result<()> {
+    let expiration = credentials
+        .expiry()
+        .and_then(|e| e.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let session = CachedSession {
+        role_arn: role_arn.to_string(),
+        access_key_id: credentials.access_key_id().to_string(),
+        secret_access_key: credentials.secret_access_key().to_string(),
+        session_token: credentials.session_token().map(String::from),
+        expiration,
+    };
+    let data = serde_json::to_vec(&session)?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // The session has temporary credentials, only the user can read them.
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    std::io::Write::write_all(&mut options.open(path)?, &data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+[default]
+region = us-east-1
+
+[profile deployer]
+region = eu-west-1
+role_arn = arn:aws:iam::123456789012:role/deployer
+mfa_serial = arn:aws:iam::123456789012:mfa/alice
+source_profile = default
+duration_seconds = 3600
+s3 =
+    max_concurrent_requests = 10
+
+[profile reader]
+role_arn = arn:aws:iam::123456789012:role/reader
+source_profile = default
+"#;
+
+    async fn load_profile(name: &str) -> Option<MfaProfile> {
+        let fs = Fs::from_slice(&[("/aws/config", CONFIG)]);
+        let env = Env::from_slice(&[
+            ("AWS_CONFIG_FILE", "/aws/config"),
+            ("AWS_SHARED_CREDENTIALS_FILE", "/aws/credentials"),
+        ]);
+        MfaProfile::load_from(&fs, &env, name).await
+    }
+
+    #[tokio::test]
+    async fn test_load_mfa_profile() {
+        let profile = load_profile("deployer").await.unwrap();
+        assert_eq!(
+            profile,
+            MfaProfile {
+                name: "deployer".into(),
+                role_arn: "arn:aws:iam::123456789012:role/deployer".into(),
+                mfa_serial: "arn:aws:iam::123456789012:mfa/alice".into(),
+                source_profile: Some("default".into()),
+                role_session_name: None,
+                duration_seconds: Some(3600),
+            }
+        );
+
+        assert_eq!(load_profile("reader").await, None);
+        assert_eq!(load_profile("default").await, None);
+        assert_eq!(load_profile("missing").await, None);
+    }
+
+    #[test]
+    fn test_session_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mfa-sessions").join("deployer.json");
+        let role_arn = "arn:aws:iam::123456789012:role/deployer";
+        assert!(read_session(&path, role_arn).is_none());
+
+        let expiry = UNIX_EPOCH + Duration::from_secs(1_900_000_000);
+        let credentials = Credentials::new(
+            "ASIAEXAMPLE",
+            "secret",
+            Some("token".into()),
+            Some(expiry),
+            PROVIDER_NAME,
+        );
+        write_session(&path, role_arn, &credentials).unwrap();
+
+        let cached = read_session(&path, role_arn).unwrap();
+        assert_eq!(cached.access_key_id(), "ASIAEXAMPLE");
+        assert_eq!(cached.session_token(), Some("token"));
+        assert_eq!(cached.expiry(), Some(expiry));
+
+        assert!(read_session(&path, "arn:aws:iam::123456789012:role/other").is_none());
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let credentials = |expiry| Credentials::new("AKID", "SECRET", None, expiry, "test");
+
+        assert!(is_fresh(&credentials(None), now));
+        assert!(is_fresh(
+            &credentials(Some(now + Duration::from_secs(3600))),
+            now
+        ));
+        assert!(!is_fresh(
+            &credentials(Some(now + Duration::from_secs(60))),
+            now
+        ));
+    }
+}
@@ -0,0 +1,236 @@
+use aws_smithy_runtime_api::{
+    client::{
+        http::{
+            HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings,
+            SharedHttpClient, SharedHttpConnector,
+        },
+        orchestrator::{HttpRequest, HttpResponse},
+        result::ConnectorError,
+        runtime_components::RuntimeComponents,
+    },
+    http::StatusCode,
+};
+use aws_smithy_types::body::SdkBody;
+use miette::Diagnostic;
+use std::{path::PathBuf, time::Duration};
+use thiserror::Error;
+
+/// Environment variable with the path to a CA bundle, set by the global flag `--ca-bundle`.
+pub const CA_BUNDLE_ENV: &str = "CARGO_LAMBDA_CA_BUNDLE";
+
+/// Environment variables that send requests through a proxy.
+const PROXY_ENV_VARS: [&str; 6] = [
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+#[derive(Debug, Diagnostic, Error)]
+pub enum NetworkError {
+    #[error("failed to read the CA bundle `{0}`: {1}")]
+    #[diagnostic(code(cargo_lambda::network::invalid_ca_bundle))]
+    InvalidCaBundle(PathBuf, std::io::Error),
+
+    #[error("the CA bundle `{0}` doesn't have valid PEM certificates: {1}")]
+    #[diagnostic(code(cargo_lambda::network::invalid_ca_certificates))]
+    InvalidCaCertificates(PathBuf, reqwest::Error),
+
+    #[error("failed to create the HTTP client: {0}")]
+    #[diagnostic(code(cargo_lambda::network::http_client))]
+    HttpClient(#[from] reqwest::Error),
+}
+
+/// Path to the CA bundle that verifies the certificates of remote servers,
+/// from the flag `--ca-bundle`, or the `AWS_CA_BUNDLE` environment variable.
+pub fn ca_bundle() -> Option<PathBuf> {
+    [CA_BUNDLE_ENV, "AWS_CA_BUNDLE"]
+        .iter()
+        .filter_map(std::env::var_os)
+        .find(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Whether the proxy environment variables are set.
+fn has_proxy() -> bool {
+    PROXY_ENV_VARS
+        .iter()
+        .filter_map(std::env::var_os)
+        .any(|value| !value.is_empty())
+}
+
+/// Certificates in the CA bundle, if there is one.
+pub fn load_ca_certificates() -> Result<Vec<reqwest::Certificate>, NetworkError> {
+    let Some(path) = ca_bundle() else {
+        return Ok(Vec::new());
+    };
+
+    let pem =
+        std::fs::read(&path).map_err(|err| NetworkError::InvalidCaBundle(path.clone(), err))?;
+    reqwest::Certificate::from_pem_bundle(&pem)
+        .map_err(|err| NetworkError::InvalidCaCertificates(path, err))
+}
+
+/// Builder for HTTP clients that trust the certificates in the CA bundle,
+/// besides the system's certificates.
+/// Proxies in `HTTPS_PROXY`, `HTTP_PROXY`, and `NO_PROXY` are used by default.
+pub fn http_client_builder() -> Result<reqwest::ClientBuilder, NetworkError> {
+    let builder = load_ca_certificates()?
+        .into_iter()
+        .fold(reqwest::Client::builder(), |builder, cert| {
+            builder.add_root_certificate(cert)
+        });
+    Ok(builder)
+}
+
+/// HTTP client that trusts the certificates in the CA bundle, and uses the proxy environment variables.
+pub fn http_client() -> Result<reqwest::Client, NetworkError> {
+    Ok(http_client_builder()?.build()?)
+}
+
+/// HTTP client for the AWS SDK, only when the requests need a custom CA bundle,
+/// or go through a proxy. The SDK's default client doesn't support either.
+pub(crate) fn sdk_http_client() -> Result<Option<SharedHttpClient>, NetworkError> {
+    if ca_bundle().is_none() && !has_proxy() {
+        return Ok(None);
+    }
+
+    let client = http_client()?;
+    Ok(Some(SharedHttpClient::new(ReqwestHttpClient { client })))
+}
+
+#[derive(Debug)]
+struct ReqwestHttpClient {
+    client: reqwest::Client,
+}
+
+impl HttpClient for ReqwestHttpClient {
+    fn http_connector(
+        &self,
+        settings: &HttpConnectorSettings,
+        _components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        let timeout = settings
+            .read_timeout()
+            .map(|read| read + settings.connect_timeout().unwrap_or_default());
+
+        SharedHttpConnector::new(ReqwestConnector {
+            client: self.client.clone(),
+            timeout,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct ReqwestConnector {
+    client: reqwest::Client,
+    timeout: Option<Duration>,
+}
+
+impl HttpConnector for ReqwestConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let client = self.client.clone();
+        let timeout = self.timeout;
+        HttpConnectorFuture::new(async move { send(client, request, timeout).await })
+    }
+}
+
+async fn send(
+    client: reqwest::Client,
+    request: HttpRequest,
+    timeout: Option<Duration>,
+) -> Result<HttpResponse, ConnectorError> {
+    let method = reqwest::Method::from_bytes(request.method().as_bytes())
+        .map_err(|err| ConnectorError::user(err.into()))?;
+
+    let Some(body) = request.body().bytes() else {
+        return Err(ConnectorError::user(
+            "streaming request bodies are not supported with a proxy, or a CA bundle".into(),
+        ));
+    };
+
+    let mut builder = client.request(method, request.uri()).body(body.to_vec());
+    for (name, value) in request.headers() {
+        builder = builder.header(name, value);
+    }
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+
+    let response = builder.send().await.map_err(connector_error)?;
+    let status = StatusCode::try_from(response.status().as_u16())
+        .map_err(|err| ConnectorError::other(err.into(), None))?;
+    let headers = response.headers().clone();
+    let body = response.bytes().await.map_err(connector_error)?;
+
+    let mut sdk_response = HttpResponse::new(status, SdkBody::from(body.to_vec()));
+    for (name, value) in &headers {
+        if let Ok(value) = value.to_str() {
+            sdk_response
+                .headers_mut()
+                .append(name.as_str().to_string(), value.to_string());
+        }
+    }
+
+    Ok(sdk_response)
+}
+
+fn connector_error(err: reqwest::Error) -> ConnectorError {
+    if err.is_timeout() {
+        ConnectorError::timeout(err.into())
+    } else if err.is_connect() {
+        ConnectorError::io(err.into())
+    } else {
+        ConnectorError::other(err.into(), None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ca_bundle() {
+        figment::Jail::expect_with(|jail| {
+            jail.clear_env();
+            assert_eq!(ca_bundle(), None);
+            assert!(load_ca_certificates().unwrap().is_empty());
+
+            jail.set_env("AWS_CA_BUNDLE", "/etc/ssl/aws.pem");
+            assert_eq!(ca_bundle(), Some(PathBuf::from("/etc/ssl/aws.pem")));
+
+            jail.set_env(CA_BUNDLE_ENV, "/etc/ssl/corporate.pem");
+            assert_eq!(ca_bundle(), Some(PathBuf::from("/etc/ssl/corporate.pem")));
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_sdk_http_client() {
+        figment::Jail::expect_with(|jail| {
+            jail.clear_env();
+            assert!(sdk_http_client().unwrap().is_none());
+
+            jail.set_env("HTTPS_PROXY", "http://proxy.example.com:3128");
+            assert!(sdk_http_client().unwrap().is_some());
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_load_ca_certificates() {
+        figment::Jail::expect_with(|jail| {
+            jail.clear_env();
+            jail.set_env(CA_BUNDLE_ENV, "missing-bundle.pem");
+            let err = load_ca_certificates().unwrap_err();
+            assert!(matches!(err, NetworkError::InvalidCaBundle(..)));
+
+            let bundle = concat!(env!("CARGO_MANIFEST_DIR"), "/../../tests/certs/ca.pem");
+            jail.set_env(CA_BUNDLE_ENV, bundle);
+            assert_eq!(load_ca_certificates().unwrap().len(), 1);
+            Ok(())
+        });
+    }
+}
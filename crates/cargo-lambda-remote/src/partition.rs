@@ -0,0 +1,129 @@
+use aws_types::SdkConfig;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// AWS partition, the group of regions that an account belongs to.
+/// ARNs are different in each partition, like `arn:aws-us-gov:iam::123456789012:role/deployer`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Partition {
+    /// Commercial regions
+    #[default]
+    Aws,
+    /// AWS GovCloud (US) regions
+    AwsUsGov,
+    /// China regions
+    AwsCn,
+}
+
+impl Partition {
+    /// Partition of a region, like `us-gov-west-1`, or `cn-north-1`.
+    pub fn from_region(region: &str) -> Partition {
+        if region.starts_with("us-gov-") {
+            Partition::AwsUsGov
+        } else if region.starts_with("cn-") {
+            Partition::AwsCn
+        } else {
+            Partition::Aws
+        }
+    }
+
+    /// Partition in an ARN, like `arn:aws-us-gov:iam::123456789012:role/deployer`.
+    pub fn from_arn(arn: &str) -> Option<Partition> {
+        let mut segments = arn.split(':');
+        if segments.next() != Some("arn") {
+            return None;
+        }
+
+        match segments.next()? {
+            "aws" => Some(Partition::Aws),
+            "aws-us-gov" => Some(Partition::AwsUsGov),
+            "aws-cn" => Some(Partition::AwsCn),
+            _ => None,
+        }
+    }
+
+    /// Partition of the region in the SDK configuration.
+    pub fn from_sdk_config(sdk_config: &SdkConfig) -> Partition {
+        sdk_config
+            .region()
+            .map(|region| Partition::from_region(region.as_ref()))
+            .unwrap_or_default()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Partition::Aws => "aws",
+            Partition::AwsUsGov => "aws-us-gov",
+            Partition::AwsCn => "aws-cn",
+        }
+    }
+
+    /// ARN of a policy managed by AWS, like `AmazonS3ReadOnlyAccess`,
+    /// or `service-role/AWSLambdaBasicExecutionRole`.
+    pub fn managed_policy_arn(&self, name: &str) -> String {
+        format!("arn:{self}:iam::aws:policy/{name}")
+    }
+}
+
+impl fmt::Display for Partition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_types::region::Region;
+
+    #[test]
+    fn test_from_region() {
+        assert_eq!(Partition::Aws, Partition::from_region("us-east-1"));
+        assert_eq!(Partition::AwsUsGov, Partition::from_region("us-gov-west-1"));
+        assert_eq!(Partition::AwsCn, Partition::from_region("cn-northwest-1"));
+
+        let config = SdkConfig::builder()
+            .region(Region::from_static("us-gov-east-1"))
+            .build();
+        assert_eq!(Partition::AwsUsGov, Partition::from_sdk_config(&config));
+        assert_eq!(
+            Partition::Aws,
+            Partition::from_sdk_config(&SdkConfig::builder().build())
+        );
+    }
+
+    #[test]
+    fn test_from_arn() {
+        assert_eq!(
+            Some(Partition::AwsUsGov),
+            Partition::from_arn("arn:aws-us-gov:iam::123456789012:role/deployer")
+        );
+        assert_eq!(
+            Some(Partition::AwsCn),
+            Partition::from_arn("arn:aws-cn:sts::123456789012:assumed-role/deployer/alice")
+        );
+        assert_eq!(
+            Some(Partition::Aws),
+            Partition::from_arn("arn:aws:iam::123456789012:user/alice")
+        );
+        assert_eq!(None, Partition::from_arn("deployer"));
+        assert_eq!(
+            None,
+            Partition::from_arn("arn:aws-iso:iam::123456789012:role/deployer")
+        );
+    }
+
+    #[test]
+    fn test_managed_policy_arn() {
+        assert_eq!(
+            "arn:aws-us-gov:iam::aws:policy/service-role/AWSLambdaBasicExecutionRole",
+            Partition::AwsUsGov.managed_policy_arn("service-role/AWSLambdaBasicExecutionRole")
+        );
+        assert_eq!(
+            "arn:aws-cn:iam::aws:policy/AmazonS3ReadOnlyAccess",
+            Partition::AwsCn.managed_policy_arn("AmazonS3ReadOnlyAccess")
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use aws_types::region::Region;
+use cargo_lambda_interactive::{choose_option, is_stdin_tty, is_user_cancellation_error};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::path::Path;
+
+use crate::user_config_path;
+use toml_edit::{table, value, DocumentMut};
+
+/// Region to use when no region can be resolved.
+pub const DEFAULT_REGION: &str = "us-east-1";
+
+/// Commercial regions that Lambda is available in.
+const REGIONS: [&str; 29] = [
+    "us-east-1",
+    "us-east-2",
+    "us-west-1",
+    "us-west-2",
+    "af-south-1",
+    "ap-east-1",
+    "ap-northeast-1",
+    "ap-northeast-2",
+    "ap-northeast-3",
+    "ap-south-1",
+    "ap-south-2",
+    "ap-southeast-1",
+    "ap-southeast-2",
+    "ap-southeast-3",
+    "ap-southeast-4",
+    "ap-southeast-5",
+    "ca-central-1",
+    "ca-west-1",
+    "eu-central-1",
+    "eu-central-2",
+    "eu-north-1",
+    "eu-south-1",
+    "eu-south-2",
+    "eu-west-1",
+    "eu-west-2",
+    "eu-west-3",
+    "il-central-1",
+    "me-central-1",
+    "me-south-1",
+];
+
+/// Region to use when the flags, the environment, and the AWS profile don't have one.
+pub(crate) fn default_region() -> Region {
+    eprintln!("⚠️  no AWS region is configured, using {DEFAULT_REGION}. Use the flag `--region`, or the environment variable `AWS_REGION`, to select a different region");
+    Region::new(DEFAULT_REGION)
+}
+
+/// Ask for a region in interactive terminals, and remember it in the user configuration file,
+/// so the next commands use it. Other terminals don't get a region, so they use the default region.
+pub(crate) fn ask_region() -> Result<Option<String>> {
+    if !is_stdin_tty() {
+        return Ok(None);
+    }
+
+    eprintln!("⚠️  no AWS region is configured");
+    let region = match choose_option("Select the AWS region to use:", REGIONS.to_vec()) {
+        Ok(region) => region,
+        Err(err) if is_user_cancellation_error(&err) => {
+            return Err(miette::miette!(
+                "no AWS region selected, use the flag `--region` to select one"
+            ))
+        }
+        Err(err) => {
+            return Err(err)
+                .into_diagnostic()
+                .wrap_err("failed to select an AWS region")
+        }
+    };
+
+    if let Some(path) = user_config_path() {
+        match remember_region(&path, region) {
+            Ok(()) => eprintln!(
+                "🔖 {region} is the default region now, change it in {}",
+                path.display()
+            ),
+            Err(err) => tracing::warn!(?err, "failed to save the default region"),
+        }
+    }
+
+    Ok(Some(region.to_string()))
+}
+
+/// Set the deploy region in the user configuration file, and keep the rest of the file as it is.
+fn remember_region(path: &Path, region: &str) -> Result<()> {
+    let content = if path.exists() {
+        std::fs::read_to_string(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?
+    } else {
+        String::new()
+    };
+
+    let mut doc = content
+        .parse::<DocumentMut>()
+        .into_diagnostic()
+        .wrap_err_with(|| format!("invalid TOML in {}", path.display()))?;
+
+    let deploy = doc.entry("deploy").or_insert(table());
+    let Some(deploy) = deploy.as_table_like_mut() else {
+        return Err(miette::miette!(
+            "the `deploy` key in {} is not a table",
+            path.display()
+        ));
+    };
+    deploy.insert("region", value(region));
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).into_diagnostic()?;
+    }
+    std::fs::write(path, doc.to_string())
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remember_region() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cargo-lambda").join("config.toml");
+
+        remember_region(&path, "eu-west-1").unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "[deploy]\nregion = \"eu-west-1\"\n");
+
+        std::fs::write(
+            &path,
+            "# defaults\n[deploy]\nregion = \"eu-west-1\"\nmemory = 512\n\n[watch]\ninvoke_port = 9090\n",
+        )
+        .unwrap();
+        remember_region(&path, "ap-south-1").unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "# defaults\n[deploy]\nregion = \"ap-south-1\"\nmemory = 512\n\n[watch]\ninvoke_port = 9090\n"
+        );
+    }
+}
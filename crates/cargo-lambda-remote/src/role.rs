@@ -0,0 +1,45 @@
+use aws_config::{
+    profile::ProfileFileCredentialsProvider, provider_config::ProviderConfig,
+    sts::AssumeRoleProvider,
+};
+use aws_credential_types::provider::SharedCredentialsProvider;
+use aws_types::{region::Region, SdkConfig};
+
+/// Credentials provider that assumes a role with the credentials of the source profile,
+/// if there is one, or with the credentials that the SDK configuration already has.
+pub async fn assume_role_provider(
+    sdk_config: &SdkConfig,
+    role_arn: &str,
+    session_name: &str,
+    external_id: Option<&str>,
+    source_profile: Option<&str>,
+) -> SharedCredentialsProvider {
+    let mut builder = AssumeRoleProvider::builder(role_arn)
+        .session_name(session_name)
+        .configure(sdk_config);
+    if let Some(external_id) = external_id {
+        builder = builder.external_id(external_id);
+    }
+
+    let provider = match source_profile {
+        Some(source_profile) => {
+            let source = profile_credentials(source_profile, sdk_config.region().cloned());
+            builder.build_from_provider(source).await
+        }
+        None => builder.build().await,
+    };
+
+    SharedCredentialsProvider::new(provider)
+}
+
+/// Credentials of a profile in the AWS config and credentials files.
+pub(crate) fn profile_credentials(
+    profile: &str,
+    region: Option<Region>,
+) -> ProfileFileCredentialsProvider {
+    let conf = ProviderConfig::default().with_region(region);
+    ProfileFileCredentialsProvider::builder()
+        .profile_name(profile)
+        .configure(&conf)
+        .build()
+}
@@ -5,6 +5,7 @@ use rustls_pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
 use std::{
     path::{Path, PathBuf},
+    process::Command,
     sync::OnceLock,
 };
 use thiserror::Error;
@@ -32,6 +33,18 @@ pub enum TlsError {
     #[error("failed to parse config: {0}")]
     #[diagnostic()]
     FailedToParseConfig(#[from] rustls::Error),
+
+    #[error(
+        "mkcert is not installed, it's required to generate a local certificate with `--https`"
+    )]
+    #[diagnostic(help(
+        "install mkcert from https://github.com/FiloSottile/mkcert, or use `--tls-cert` and `--tls-key` with your own certificate"
+    ))]
+    MissingMkcert,
+
+    #[error("failed to generate a local certificate with mkcert: {0}")]
+    #[diagnostic()]
+    FailedToGenerateCert(String),
 }
 
 #[derive(Args, Clone, Debug, Deserialize, Serialize)]
@@ -48,6 +61,10 @@ pub struct TlsOptions {
     #[arg(long, conflicts_with = "remote")]
     #[serde(default)]
     pub tls_ca: Option<PathBuf>,
+    /// Use a locally-trusted certificate generated with mkcert when no certificate is provided
+    #[arg(long, conflicts_with = "remote")]
+    #[serde(default)]
+    pub https: bool,
 
     #[cfg(test)]
     pub config_dir: PathBuf,
@@ -64,6 +81,7 @@ impl TlsOptions {
             tls_cert,
             tls_key,
             tls_ca,
+            https: false,
         }
     }
 
@@ -77,6 +95,7 @@ impl TlsOptions {
             tls_cert,
             tls_key,
             tls_ca,
+            https: false,
             config_dir: tempfile::TempDir::new().unwrap().path().to_path_buf(),
         }
     }
@@ -85,6 +104,52 @@ impl TlsOptions {
         self.cert_path().is_some() && self.key_path().is_some()
     }
 
+    /// Generate a locally-trusted certificate with mkcert when `--https` is enabled,
+    /// and there is no other certificate available.
+    /// The certificate is stored in the global configuration directory, and reused in the next runs.
+    pub fn ensure_local_certificate(&self) -> Result<()> {
+        if !self.https || self.is_secure() {
+            return Ok(());
+        }
+
+        let Some(dir) = self.local_dir() else {
+            return Err(TlsError::FailedToGenerateCert(
+                "the configuration directory is not available".into(),
+            )
+            .into());
+        };
+        std::fs::create_dir_all(&dir).map_err(|e| TlsError::FailedToGenerateCert(e.to_string()))?;
+
+        let mkcert = which::which("mkcert").map_err(|_| TlsError::MissingMkcert)?;
+
+        // Install the local CA in the system and browser trust stores, if it's not installed yet.
+        run_mkcert(&mkcert, &["-install"])?;
+
+        let cert = dir.join("cert.pem");
+        let key = dir.join("key.pem");
+        run_mkcert(
+            &mkcert,
+            &[
+                "-cert-file",
+                &cert.to_string_lossy(),
+                "-key-file",
+                &key.to_string_lossy(),
+                "localhost",
+                "127.0.0.1",
+                "::1",
+            ],
+        )?;
+
+        let ca_root = run_mkcert(&mkcert, &["-CAROOT"])?;
+        std::fs::copy(
+            Path::new(ca_root.trim()).join("rootCA.pem"),
+            dir.join("ca.pem"),
+        )
+        .map_err(|e| TlsError::FailedToGenerateCert(e.to_string()))?;
+
+        Ok(())
+    }
+
     pub async fn server_config(&self) -> Result<Option<ServerConfig>> {
         if !self.is_secure() {
             return Ok(None);
@@ -134,15 +199,24 @@ impl TlsOptions {
     }
 
     fn cert_path(&self) -> Option<PathBuf> {
-        self.tls_cert.clone().or_else(|| self.cached_cert_path())
+        self.tls_cert
+            .clone()
+            .or_else(|| self.cached_cert_path())
+            .or_else(|| self.local_path("cert.pem"))
     }
 
     fn key_path(&self) -> Option<PathBuf> {
-        self.tls_key.clone().or_else(|| self.cached_key_path())
+        self.tls_key
+            .clone()
+            .or_else(|| self.cached_key_path())
+            .or_else(|| self.local_path("key.pem"))
     }
 
     fn ca_path(&self) -> Option<PathBuf> {
-        self.tls_ca.clone().or_else(|| self.cached_ca_path())
+        self.tls_ca
+            .clone()
+            .or_else(|| self.cached_ca_path())
+            .or_else(|| self.local_path("ca.pem"))
     }
 
     fn cached_cert_path(&self) -> Option<PathBuf> {
@@ -172,6 +246,24 @@ impl TlsOptions {
         None
     }
 
+    /// Path to a file of the certificate generated with mkcert, only used with `--https`.
+    fn local_path(&self, name: &str) -> Option<PathBuf> {
+        if !self.https {
+            return None;
+        }
+
+        let path = self.local_dir().map(|p| p.join(name));
+        if path.as_ref().is_some_and(|p| p.exists() && p.is_file()) {
+            return path;
+        }
+
+        None
+    }
+
+    fn local_dir(&self) -> Option<PathBuf> {
+        self.config_dir().map(|p| p.join("local"))
+    }
+
     #[cfg(not(test))]
     fn config_dir(&self) -> Option<PathBuf> {
         dirs::config_dir().map(|p| p.join("cargo-lambda"))
@@ -186,6 +278,7 @@ impl TlsOptions {
         self.tls_cert.is_some() as usize
             + self.tls_key.is_some() as usize
             + self.tls_ca.is_some() as usize
+            + self.https as usize
     }
 
     pub fn serialize_fields<S>(
@@ -204,6 +297,9 @@ impl TlsOptions {
         if let Some(tls_ca) = &self.tls_ca {
             state.serialize_field("tls_ca", tls_ca)?;
         }
+        if self.https {
+            state.serialize_field("https", &true)?;
+        }
         Ok(())
     }
 }
@@ -242,6 +338,20 @@ fn parse_cert_and_key(
     Ok((cert, key))
 }
 
+fn run_mkcert(mkcert: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new(mkcert)
+        .args(args)
+        .output()
+        .map_err(|e| TlsError::FailedToGenerateCert(e.to_string()))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(TlsError::FailedToGenerateCert(stderr.trim().to_string()).into());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
 fn install_default_tls_provider() -> bool {
     rustls::crypto::aws_lc_rs::default_provider()
         .install_default()
@@ -338,6 +448,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_local_paths_require_https() {
+        let mut opts = TlsOptions::default();
+        let local_dir = opts.config_dir.join("local");
+        create_test_file("../../tests/certs/cert.pem", &local_dir.join("cert.pem"));
+        create_test_file("../../tests/certs/key.pem", &local_dir.join("key.pem"));
+
+        assert!(!opts.is_secure());
+        assert!(opts.ensure_local_certificate().is_ok());
+
+        opts.https = true;
+        assert!(opts.is_secure());
+        assert_eq!(opts.cert_path().unwrap(), local_dir.join("cert.pem"));
+        assert_eq!(opts.key_path().unwrap(), local_dir.join("key.pem"));
+        assert!(opts.ca_path().is_none());
+
+        // The certificate already exists, so mkcert doesn't run again.
+        assert!(opts.ensure_local_certificate().is_ok());
+    }
+
     #[tokio::test]
     async fn test_server_config_with_valid_files_in_temp_dir() {
         let opts = TlsOptions::new(
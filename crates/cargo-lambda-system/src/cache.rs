@@ -0,0 +1,218 @@
+use clap::ValueEnum;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::path::{Path, PathBuf};
+
+/// Caches that `cargo lambda system --clean-cache` removes.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum CacheKind {
+    /// Templates downloaded by `cargo lambda new`, and `cargo lambda init`
+    Templates,
+    /// Example payloads downloaded by `cargo lambda invoke --data-example`
+    Fixtures,
+    /// Files that cargo-zigbuild, and Zig write when they cross compile functions
+    Zig,
+    /// All the caches
+    All,
+}
+
+impl CacheKind {
+    fn name(&self) -> &'static str {
+        match self {
+            CacheKind::Templates => "templates",
+            CacheKind::Fixtures => "fixtures",
+            CacheKind::Zig => "zig",
+            CacheKind::All => "all",
+        }
+    }
+
+    fn includes(&self, other: CacheKind) -> bool {
+        *self == CacheKind::All || *self == other
+    }
+}
+
+/// Directory with files that can be downloaded, or generated again.
+#[derive(Debug, PartialEq)]
+struct CacheDir {
+    kind: CacheKind,
+    path: PathBuf,
+}
+
+/// Remove the caches, and report how much space each one used.
+pub(crate) fn clean_cache(kind: CacheKind) -> Result<()> {
+    let cache_dir = dirs::cache_dir()
+        .ok_or_else(|| miette::miette!("failed to find the cache directory for this user"))?;
+
+    let mut total = 0;
+    for dir in cache_dirs(kind, &cache_dir, zig_cache_dir()) {
+        match remove_dir(&dir.path)? {
+            Some(size) => {
+                println!(
+                    "🧹 removed {} of {} from {}",
+                    format_size(size),
+                    dir.kind.name(),
+                    dir.path.display()
+                );
+                total += size;
+            }
+            None => println!(
+                "🔍 no {} in {}, nothing to remove",
+                dir.kind.name(),
+                dir.path.display()
+            ),
+        }
+    }
+
+    println!("✅ {} removed from the cache", format_size(total));
+    Ok(())
+}
+
+fn cache_dirs(kind: CacheKind, cache_dir: &Path, zig_cache_dir: Option<PathBuf>) -> Vec<CacheDir> {
+    let cargo_lambda = cache_dir.join("cargo-lambda");
+    let mut dirs = Vec::new();
+
+    if kind.includes(CacheKind::Templates) {
+        dirs.push(CacheDir {
+            kind: CacheKind::Templates,
+            path: cargo_lambda.join("templates"),
+        });
+    }
+    if kind.includes(CacheKind::Fixtures) {
+        dirs.push(CacheDir {
+            kind: CacheKind::Fixtures,
+            path: cargo_lambda.join("invoke-fixtures"),
+        });
+    }
+    if kind.includes(CacheKind::Zig) {
+        dirs.push(CacheDir {
+            kind: CacheKind::Zig,
+            path: cache_dir.join("cargo-zigbuild"),
+        });
+        if let Some(path) = zig_cache_dir {
+            dirs.push(CacheDir {
+                kind: CacheKind::Zig,
+                path,
+            });
+        }
+    }
+
+    dirs
+}
+
+/// Global cache of Zig, in the same location that Zig uses.
+/// Zig installations are not removed, because package managers install them.
+fn zig_cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("ZIG_GLOBAL_CACHE_DIR") {
+        return Some(dir.into());
+    }
+
+    if cfg!(windows) {
+        return dirs::data_local_dir().map(|p| p.join("zig"));
+    }
+
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|p| p.join(".cache")))
+        .map(|p| p.join("zig"))
+}
+
+/// Remove the directory, and return its size, or `None` if it doesn't exist.
+fn remove_dir(path: &Path) -> Result<Option<u64>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let size = dir_size(path);
+    std::fs::remove_dir_all(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to remove {}", path.display()))?;
+    Ok(Some(size))
+}
+
+/// Size of the files in the directory. Symbolic links are not followed.
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_dirs() {
+        let cache = Path::new("/cache");
+        let zig = Some(PathBuf::from("/home/.cache/zig"));
+
+        let dirs = cache_dirs(CacheKind::Fixtures, cache, zig.clone());
+        assert_eq!(
+            dirs,
+            vec![CacheDir {
+                kind: CacheKind::Fixtures,
+                path: PathBuf::from("/cache/cargo-lambda/invoke-fixtures"),
+            }]
+        );
+
+        let dirs = cache_dirs(CacheKind::All, cache, zig.clone())
+            .into_iter()
+            .map(|dir| dir.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dirs,
+            vec![
+                PathBuf::from("/cache/cargo-lambda/templates"),
+                PathBuf::from("/cache/cargo-lambda/invoke-fixtures"),
+                PathBuf::from("/cache/cargo-zigbuild"),
+                PathBuf::from("/home/.cache/zig"),
+            ]
+        );
+
+        assert_eq!(cache_dirs(CacheKind::Zig, cache, None).len(), 1);
+    }
+
+    #[test]
+    fn test_remove_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let fixtures = dir.path().join("invoke-fixtures");
+        std::fs::create_dir_all(fixtures.join("example.com")).unwrap();
+        std::fs::write(fixtures.join("apigw-request.json"), [0; 100]).unwrap();
+        std::fs::write(fixtures.join("example.com").join("sqs-event.json"), [0; 28]).unwrap();
+
+        assert_eq!(remove_dir(&fixtures).unwrap(), Some(128));
+        assert!(!fixtures.exists());
+        assert_eq!(remove_dir(&fixtures).unwrap(), None);
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(20 * 1024 * 1024), "20.0 MB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+}
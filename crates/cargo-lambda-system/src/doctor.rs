@@ -0,0 +1,349 @@
+use aws_sdk_sts::Client as StsClient;
+use cargo_lambda_build::{rustup_cmd, Zig};
+use cargo_lambda_interactive::{command::new_command, progress::Progress};
+use cargo_lambda_metadata::cargo::load_metadata;
+use cargo_lambda_remote::{network, RemoteConfig};
+use clap::{Args, ValueHint};
+use miette::Result;
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Targets that AWS Lambda runs functions on.
+const LAMBDA_TARGETS: [&str; 2] = ["aarch64-unknown-linux-gnu", "x86_64-unknown-linux-gnu"];
+
+/// Hosts where Cargo Lambda downloads the templates for new projects from.
+const TEMPLATE_HOSTS: [&str; 2] = ["https://github.com", "https://codeload.github.com"];
+
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "doctor",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/doctor.html"
+)]
+pub struct Doctor {
+    #[command(flatten)]
+    remote_config: RemoteConfig,
+
+    /// Path to Cargo.toml, to check the project's dependencies
+    #[arg(long, value_name = "PATH", default_value = "Cargo.toml", value_hint = ValueHint::FilePath)]
+    manifest_path: PathBuf,
+
+    /// Skip the checks that need network access, like the AWS credentials
+    #[arg(long)]
+    offline: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Status {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// Result of one diagnostic, and how to fix it when it's not ok.
+#[derive(Debug, PartialEq)]
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Check {
+        Check {
+            name,
+            status: Status::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warning(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Check {
+        Check {
+            name,
+            status: Status::Warning,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn error(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Check {
+        Check {
+            name,
+            status: Status::Error,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let icon = match self.status {
+            Status::Ok => "✅",
+            Status::Warning => "⚠️ ",
+            Status::Error => "❌",
+        };
+        writeln!(f, "{icon} {}: {}", self.name, self.detail)?;
+        if let Some(fix) = &self.fix {
+            writeln!(f, "   fix: {fix}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Doctor {
+    #[tracing::instrument(skip(self), target = "cargo_lambda")]
+    pub async fn run(&self) -> Result<()> {
+        let progress = Progress::start("checking the system");
+
+        let mut checks = vec![
+            check_cargo().await,
+            check_targets().await,
+            check_zig().await,
+            check_c_headers(),
+        ];
+        if let Some(check) = check_openssl(&self.manifest_path) {
+            checks.push(check);
+        }
+        if !self.offline {
+            checks.push(self.check_credentials().await);
+            for host in TEMPLATE_HOSTS {
+                checks.push(check_host(host).await);
+            }
+        }
+
+        progress.finish_and_clear();
+
+        for check in &checks {
+            print!("{check}");
+        }
+
+        let errors = checks.iter().filter(|c| c.status == Status::Error).count();
+        if errors > 0 {
+            return Err(miette::miette!("{errors} checks failed"));
+        }
+        Ok(())
+    }
+
+    async fn check_credentials(&self) -> Check {
+        const NAME: &str = "AWS credentials";
+
+        let sdk_config = match self.remote_config.sdk_config(None).await {
+            Ok(sdk_config) => sdk_config,
+            Err(err) => {
+                return Check::error(
+                    NAME,
+                    err.to_string(),
+                    "fix the CA bundle in `--ca-bundle`, or `AWS_CA_BUNDLE`",
+                )
+            }
+        };
+        let client = StsClient::new(&sdk_config);
+        match client.get_caller_identity().send().await {
+            Ok(identity) => Check::ok(
+                NAME,
+                format!(
+                    "{} in account {}",
+                    identity.arn().unwrap_or_default(),
+                    identity.account().unwrap_or_default()
+                ),
+            ),
+            Err(err) => Check::warning(
+                NAME,
+                format!("the credentials are missing, or invalid: {err}"),
+                "configure the credentials with `aws configure`, or select a profile with `--profile`. They're only needed to deploy, and invoke remote functions",
+            ),
+        }
+    }
+}
+
+/// Run a command, and return its output if it succeeded.
+async fn command_output(cmd: &str, args: &[&str]) -> Option<String> {
+    let output = new_command(cmd).args(args).output().await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+async fn check_cargo() -> Check {
+    const NAME: &str = "cargo";
+
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    match command_output(&cargo, &["--version"]).await {
+        Some(version) => Check::ok(NAME, version),
+        None => Check::error(
+            NAME,
+            "cargo is not installed",
+            "install Rust with rustup, https://rustup.rs",
+        ),
+    }
+}
+
+async fn check_targets() -> Check {
+    const NAME: &str = "Rust targets";
+
+    let Some(installed) = command_output(&rustup_cmd(), &["target", "list", "--installed"]).await
+    else {
+        return Check::warning(
+            NAME,
+            "rustup is not installed, Cargo Lambda cannot install the Linux targets automatically",
+            format!(
+                "install the targets `{}` with your Rust distribution",
+                LAMBDA_TARGETS.join("`, and `")
+            ),
+        );
+    };
+
+    let missing = missing_targets(&installed);
+    if missing.is_empty() {
+        Check::ok(NAME, LAMBDA_TARGETS.join(", "))
+    } else {
+        Check::warning(
+            NAME,
+            format!(
+                "{} not installed, `cargo lambda build` installs them when it needs them",
+                missing.join(", ")
+            ),
+            format!("rustup target add {}", missing.join(" ")),
+        )
+    }
+}
+
+fn missing_targets(installed: &str) -> Vec<&'static str> {
+    LAMBDA_TARGETS
+        .into_iter()
+        .filter(|target| !installed.lines().any(|line| line.trim() == *target))
+        .collect()
+}
+
+async fn check_zig() -> Check {
+    const NAME: &str = "Zig";
+
+    let Ok((path, args)) = Zig::find_zig() else {
+        return Check::warning(
+            NAME,
+            "Zig is not installed, Cargo Lambda uses it to cross compile functions, unless you use another compiler",
+            "run `cargo lambda system --install`",
+        );
+    };
+
+    let mut cmd_args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    cmd_args.push("version");
+    match command_output(&path.to_string_lossy(), &cmd_args).await {
+        Some(version) => Check::ok(NAME, format!("{version} at {}", path.display())),
+        None => Check::error(
+            NAME,
+            format!("failed to run Zig at {}", path.display()),
+            "reinstall Zig with `cargo lambda system --install`",
+        ),
+    }
+}
+
+/// Build scripts run on the host, and some of them compile C code that needs the system headers.
+fn check_c_headers() -> Check {
+    const NAME: &str = "C headers";
+
+    if !cfg!(target_os = "linux") {
+        return Check::ok(NAME, "provided by the system toolchain");
+    }
+
+    if Path::new("/usr/include/stdio.h").exists() {
+        Check::ok(NAME, "/usr/include")
+    } else {
+        Check::warning(
+            NAME,
+            "the C standard library headers are not installed, build scripts that compile C code will fail",
+            "install the package `libc6-dev` in Debian and Ubuntu, `glibc-devel` in Fedora, or `musl-dev` in Alpine",
+        )
+    }
+}
+
+/// OpenSSL needs headers and libraries for the target architecture, which are rarely available when cross compiling.
+fn check_openssl(manifest_path: &Path) -> Option<Check> {
+    const NAME: &str = "OpenSSL";
+
+    if !manifest_path.is_file() {
+        return None;
+    }
+
+    let metadata = match load_metadata(manifest_path) {
+        Ok(metadata) => metadata,
+        Err(err) => {
+            tracing::debug!(?err, "failed to load the project's metadata");
+            return None;
+        }
+    };
+
+    let check = if metadata.packages.iter().any(|p| p.name == "openssl-sys") {
+        Check::warning(
+            NAME,
+            "the project depends on `openssl-sys`, which fails to cross compile without OpenSSL for the target",
+            "use the `rustls` features of your dependencies, or enable the `vendored` feature of the `openssl` crate",
+        )
+    } else {
+        Check::ok(NAME, "the project doesn't depend on OpenSSL")
+    };
+    Some(check)
+}
+
+async fn check_host(url: &'static str) -> Check {
+    const NAME: &str = "Network";
+
+    let client = network::http_client_builder()
+        .and_then(|builder| Ok(builder.timeout(NETWORK_TIMEOUT).build()?));
+    let client = match client {
+        Ok(client) => client,
+        Err(err) => return Check::error(NAME, err.to_string(), "check your TLS configuration"),
+    };
+
+    match client.head(url).send().await {
+        Ok(_) => Check::ok(NAME, format!("{url} is reachable")),
+        Err(err) => Check::warning(
+            NAME,
+            format!("{url} is not reachable: {err}"),
+            "check your proxy settings, `cargo lambda new` downloads the templates from this host",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_targets() {
+        let installed = "aarch64-apple-darwin\nx86_64-unknown-linux-gnu\n";
+        assert_eq!(
+            vec!["aarch64-unknown-linux-gnu"],
+            missing_targets(installed)
+        );
+
+        let installed = "aarch64-unknown-linux-gnu\nx86_64-unknown-linux-gnu\n";
+        assert!(missing_targets(installed).is_empty());
+    }
+
+    #[test]
+    fn test_check_display() {
+        let check = Check::warning("Rust targets", "not installed", "rustup target add");
+        assert_eq!(
+            "⚠️  Rust targets: not installed\n   fix: rustup target add\n",
+            check.to_string()
+        );
+        assert_eq!(
+            "✅ cargo: cargo 1.82.0\n",
+            Check::ok("cargo", "cargo 1.82.0").to_string()
+        );
+    }
+
+    #[test]
+    fn test_check_openssl_outside_project() {
+        assert_eq!(None, check_openssl(Path::new("missing/Cargo.toml")));
+    }
+}
@@ -1,10 +1,20 @@
-use clap::Args;
-use miette::Result;
+use clap::{Args, ValueHint};
+use miette::{IntoDiagnostic, Result};
+use std::{fmt::Write as _, path::PathBuf};
 
 use cargo_lambda_build::{install_options, install_zig, print_install_options, Zig};
 use cargo_lambda_interactive::is_stdin_tty;
+use cargo_lambda_metadata::{
+    cargo::{deploy::OutputFormat, load_metadata},
+    config::{load_config_with_sources, Config, ConfigOptions, ConfigValue},
+};
 use tracing::trace;
 
+mod cache;
+use cache::CacheKind;
+mod doctor;
+pub use doctor::Doctor;
+
 #[derive(Args, Clone, Debug)]
 #[command(
     name = "system",
@@ -14,13 +24,58 @@ pub struct System {
     /// Setup and install Zig if it is not already installed.
     #[arg(long, visible_alias = "install")]
     setup: bool,
+
+    /// Show the configuration that the commands resolve for the project, and the source of each value
+    #[arg(long, conflicts_with = "setup")]
+    config: bool,
+
+    /// Remove cached files, and report how much space they used: templates, fixtures, zig, or all
+    #[arg(
+        long,
+        value_name = "CACHE",
+        num_args = 0..=1,
+        default_missing_value = "all",
+        conflicts_with_all = ["setup", "config"]
+    )]
+    clean_cache: Option<CacheKind>,
+
+    /// Path to Cargo.toml, to load the project's configuration
+    #[arg(long, value_name = "PATH", default_value = "Cargo.toml", value_hint = ValueHint::FilePath)]
+    manifest_path: PathBuf,
+
+    /// Name of the binary to load the configuration for, including its `bin` configuration
+    #[arg(long, value_name = "NAME", requires = "config")]
+    binary_name: Option<String>,
+
+    /// Format to render the output (text, or json)
+    #[arg(short, long, requires = "config")]
+    output_format: Option<OutputFormat>,
 }
 
 impl System {
     #[tracing::instrument(skip(self), target = "cargo_lambda")]
-    pub async fn run(&self) -> Result<()> {
+    pub async fn run(
+        &self,
+        global: Option<PathBuf>,
+        context: Option<String>,
+        admerge: bool,
+    ) -> Result<()> {
         trace!(options = ?self, "running system command");
 
+        if self.config {
+            let options = ConfigOptions {
+                name: self.binary_name.clone(),
+                context,
+                global,
+                admerge,
+            };
+            return self.print_config(&options);
+        }
+
+        if let Some(kind) = self.clean_cache {
+            return cache::clean_cache(kind);
+        }
+
         if let Ok((path, _)) = Zig::find_zig() {
             println!("Zig installation found at:");
             println!("{}", path.display());
@@ -35,4 +90,69 @@ impl System {
 
         Ok(())
     }
+
+    fn print_config(&self, options: &ConfigOptions) -> Result<()> {
+        let metadata = load_metadata(&self.manifest_path)?;
+        let values = load_config_with_sources(&Config::default(), &metadata, options)?;
+
+        match self.output_format.clone().unwrap_or_default() {
+            OutputFormat::Text => print!("{}", format_config(&values)),
+            OutputFormat::Json => {
+                let text = serde_json::to_string_pretty(&values).into_diagnostic()?;
+                println!("{text}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Render the configuration values in columns, with their sources at the end of each line.
+fn format_config(values: &[ConfigValue]) -> String {
+    let rows = values
+        .iter()
+        .map(|v| (v.key.as_str(), v.value.to_string(), v.source.to_string()))
+        .collect::<Vec<_>>();
+
+    let key_width = rows.iter().map(|r| r.0.len()).max().unwrap_or_default();
+    let value_width = rows.iter().map(|r| r.1.len()).max().unwrap_or_default();
+
+    let mut output = String::new();
+    for (key, value, source) in rows {
+        let _ = writeln!(output, "{key:key_width$}  {value:value_width$}  ({source})");
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cargo_lambda_metadata::config::ConfigSource;
+
+    #[test]
+    fn test_format_config() {
+        let values = vec![
+            ConfigValue {
+                key: "deploy.memory".to_string(),
+                value: 512.into(),
+                source: ConfigSource::PackageMetadata,
+            },
+            ConfigValue {
+                key: "watch.invoke_port".to_string(),
+                value: 9000.into(),
+                source: ConfigSource::Default,
+            },
+            ConfigValue {
+                key: "build.compiler.type".to_string(),
+                value: "cargo".into(),
+                source: ConfigSource::EnvVar,
+            },
+        ];
+
+        let expected = "\
+deploy.memory        512      (package metadata)
+watch.invoke_port    9000     (default)
+build.compiler.type  \"cargo\"  (environment variable)
+";
+        assert_eq!(expected, format_config(&values));
+    }
 }
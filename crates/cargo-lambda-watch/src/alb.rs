@@ -0,0 +1,316 @@
+use crate::{error::ServerError, trigger_router::is_text_content_type};
+use axum::{body::Body, response::Response};
+use base64::{engine::general_purpose as b64, Engine as _};
+use bytes::Bytes;
+use cargo_lambda_metadata::{DEFAULT_ACCOUNT_ID, DEFAULT_REGION};
+use http::{header, request::Parts, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Maximum size of the request and the response payloads between a load balancer and a function.
+pub(crate) const MAX_ALB_PAYLOAD_SIZE: usize = 1_048_576;
+
+/// User agent that load balancers send in the health check requests.
+const HEALTH_CHECK_USER_AGENT: &str = "ELB-HealthChecker/2.0";
+
+/// Response that a function returns to a load balancer.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AlbResponse {
+    status_code: u16,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    multi_value_headers: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    is_base64_encoded: bool,
+}
+
+/// Whether the request comes from the load balancer's health checker.
+pub(crate) fn is_health_check(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::USER_AGENT)
+        .is_some_and(|ua| ua == HEALTH_CHECK_USER_AGENT)
+}
+
+/// Build the target group event that a load balancer sends to a function.
+/// Query parameters are sent without decoding, and duplicated values keep the last one,
+/// unless the target group has multi-value headers enabled.
+pub(crate) fn request_event(
+    function_name: &str,
+    parts: &Parts,
+    path: &str,
+    body: &Bytes,
+    multi_value: bool,
+) -> Value {
+    let target_group_arn = format!(
+        "arn:aws:elasticloadbalancing:{DEFAULT_REGION}:{DEFAULT_ACCOUNT_ID}:targetgroup/{function_name}/0123456789abcdef"
+    );
+
+    let mut event = json!({
+        "requestContext": {
+            "elb": {
+                "targetGroupArn": target_group_arn,
+            },
+        },
+        "httpMethod": parts.method.as_str(),
+        "path": path,
+    });
+
+    // Health checks don't forward any information from the original request.
+    if is_health_check(&parts.headers) {
+        event["queryStringParameters"] = json!({});
+        event["headers"] = json!({ "user-agent": HEALTH_CHECK_USER_AGENT });
+        event["body"] = json!("");
+        event["isBase64Encoded"] = json!(false);
+        return event;
+    }
+
+    let query = parse_query(parts.uri.query().unwrap_or_default());
+    let headers = forwarded_headers(&parts.headers);
+
+    if multi_value {
+        event["multiValueQueryStringParameters"] = Value::Object(query);
+        event["multiValueHeaders"] = Value::Object(
+            headers
+                .keys()
+                .map(|name| {
+                    let values = headers
+                        .get_all(name)
+                        .iter()
+                        .map(|v| String::from_utf8_lossy(v.as_bytes()).to_string())
+                        .collect::<Vec<_>>();
+                    (name.to_string(), json!(values))
+                })
+                .collect(),
+        );
+    } else {
+        event["queryStringParameters"] = Value::Object(
+            query
+                .into_iter()
+                .filter_map(|(key, values)| {
+                    let value = values.as_array()?.last()?.clone();
+                    Some((key, value))
+                })
+                .collect(),
+        );
+        event["headers"] = Value::Object(
+            headers
+                .keys()
+                .filter_map(|name| {
+                    let value = headers.get_all(name).iter().last()?;
+                    Some((
+                        name.to_string(),
+                        json!(String::from_utf8_lossy(value.as_bytes())),
+                    ))
+                })
+                .collect(),
+        );
+    }
+
+    let (body, is_base64_encoded) = if body.is_empty() || is_text_content_type(&parts.headers) {
+        (String::from_utf8_lossy(body).to_string(), false)
+    } else {
+        (b64::STANDARD.encode(body), true)
+    };
+    event["body"] = json!(body);
+    event["isBase64Encoded"] = json!(is_base64_encoded);
+
+    event
+}
+
+/// Convert the function's response into the HTTP response that the load balancer returns.
+/// It returns `None` if the function's response is not a valid target group response,
+/// in which case the load balancer responds with `502 Bad Gateway`.
+pub(crate) fn http_response(payload: &[u8]) -> Option<(StatusCode, HeaderMap, Body)> {
+    let response: AlbResponse = serde_json::from_slice(payload).ok()?;
+    let status = StatusCode::from_u16(response.status_code).ok()?;
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in response.headers {
+        headers.insert(
+            HeaderName::try_from(name).ok()?,
+            HeaderValue::try_from(value).ok()?,
+        );
+    }
+    for (name, values) in response.multi_value_headers {
+        let name = HeaderName::try_from(name).ok()?;
+        headers.remove(&name);
+        for value in values {
+            headers.append(name.clone(), HeaderValue::try_from(value).ok()?);
+        }
+    }
+
+    let body = match response.body {
+        None => Body::empty(),
+        Some(body) if response.is_base64_encoded => Body::from(b64::STANDARD.decode(body).ok()?),
+        Some(body) => Body::from(body),
+    };
+
+    Some((status, headers, body))
+}
+
+/// Response that load balancers return when the function fails or returns an invalid response.
+pub(crate) fn respond_with_bad_gateway(
+    function_name: &str,
+    reason: &str,
+) -> Result<Response<Body>, ServerError> {
+    tracing::error!(function = ?function_name, reason, "load balancer request failed");
+
+    Response::builder()
+        .status(StatusCode::BAD_GATEWAY)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(Body::from("502 Bad Gateway"))
+        .map_err(ServerError::ResponseBuild)
+}
+
+/// Response that load balancers return when the request is larger than the payload limit.
+pub(crate) fn respond_with_request_too_large() -> Result<Response<Body>, ServerError> {
+    tracing::error!(
+        limit = MAX_ALB_PAYLOAD_SIZE,
+        "load balancer request payload too large, use `--disable-payload-limits` to allow it"
+    );
+
+    Response::builder()
+        .status(StatusCode::PAYLOAD_TOO_LARGE)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(Body::from("413 Request Entity Too Large"))
+        .map_err(ServerError::ResponseBuild)
+}
+
+/// Headers of the original request, with the forwarding headers that load balancers add.
+fn forwarded_headers(headers: &HeaderMap) -> HeaderMap {
+    let mut headers = headers.clone();
+    for (name, value) in [
+        ("x-forwarded-for", "127.0.0.1"),
+        ("x-forwarded-port", "80"),
+        ("x-forwarded-proto", "http"),
+    ] {
+        if !headers.contains_key(name) {
+            headers.insert(name, HeaderValue::from_static(value));
+        }
+    }
+    headers
+}
+
+/// Split a query string in its parameters, keeping the values encoded like load balancers do.
+fn parse_query(query: &str) -> Map<String, Value> {
+    let mut params: Map<String, Value> = Map::new();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let values = params.entry(key).or_insert_with(|| json!([]));
+        if let Value::Array(values) = values {
+            values.push(json!(value));
+        }
+    }
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::Request;
+    use http_body_util::BodyExt;
+
+    fn request_parts(uri: &str, headers: &[(&str, &str)]) -> Parts {
+        let mut builder = Request::builder().method("POST").uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(()).unwrap().into_parts().0
+    }
+
+    #[test]
+    fn test_request_event() {
+        let parts = request_parts(
+            "/products?id=1&id=2&q=hello%20world",
+            &[
+                ("content-type", "application/json"),
+                ("accept", "text/html"),
+                ("accept", "application/json"),
+            ],
+        );
+        let body = Bytes::from_static(b"{\"name\":\"product\"}");
+
+        let event = request_event("basic-lambda", &parts, "/products", &body, false);
+        assert_eq!("POST", event["httpMethod"]);
+        assert_eq!("/products", event["path"]);
+        assert_eq!("2", event["queryStringParameters"]["id"]);
+        assert_eq!("hello%20world", event["queryStringParameters"]["q"]);
+        assert_eq!("application/json", event["headers"]["accept"]);
+        assert_eq!("http", event["headers"]["x-forwarded-proto"]);
+        assert_eq!("{\"name\":\"product\"}", event["body"]);
+        assert_eq!(false, event["isBase64Encoded"]);
+        assert!(event["requestContext"]["elb"]["targetGroupArn"]
+            .as_str()
+            .unwrap()
+            .contains("targetgroup/basic-lambda/"));
+        assert!(event.get("multiValueHeaders").is_none());
+
+        let event = request_event("basic-lambda", &parts, "/products", &body, true);
+        assert_eq!(
+            json!(["1", "2"]),
+            event["multiValueQueryStringParameters"]["id"]
+        );
+        assert_eq!(
+            json!(["text/html", "application/json"]),
+            event["multiValueHeaders"]["accept"]
+        );
+        assert!(event.get("headers").is_none());
+    }
+
+    #[test]
+    fn test_request_event_binary_body() {
+        let parts = request_parts("/", &[("content-type", "image/png")]);
+        let body = Bytes::from_static(&[0, 1, 2]);
+
+        let event = request_event("basic-lambda", &parts, "/", &body, false);
+        assert_eq!("AAEC", event["body"]);
+        assert_eq!(true, event["isBase64Encoded"]);
+    }
+
+    #[test]
+    fn test_health_check_event() {
+        let parts = request_parts(
+            "/health?foo=bar",
+            &[
+                ("user-agent", HEALTH_CHECK_USER_AGENT),
+                ("accept", "text/html"),
+            ],
+        );
+
+        let event = request_event("basic-lambda", &parts, "/health", &Bytes::new(), true);
+        assert_eq!(json!({}), event["queryStringParameters"]);
+        assert_eq!(
+            json!({ "user-agent": HEALTH_CHECK_USER_AGENT }),
+            event["headers"]
+        );
+        assert_eq!("", event["body"]);
+    }
+
+    #[tokio::test]
+    async fn test_http_response() {
+        let payload = json!({
+            "statusCode": 201,
+            "statusDescription": "201 Created",
+            "headers": { "content-type": "text/plain" },
+            "multiValueHeaders": { "set-cookie": ["a=1", "b=2"] },
+            "body": "aGVsbG8=",
+            "isBase64Encoded": true,
+        });
+
+        let (status, headers, body) = http_response(payload.to_string().as_bytes()).unwrap();
+        assert_eq!(StatusCode::CREATED, status);
+        assert_eq!("text/plain", headers[header::CONTENT_TYPE]);
+        assert_eq!(2, headers.get_all(header::SET_COOKIE).iter().count());
+
+        let body = body.collect().await.unwrap().to_bytes();
+        assert_eq!(Bytes::from_static(b"hello"), body);
+
+        assert!(http_response(b"{\"message\":\"hello\"}").is_none());
+        assert!(http_response(b"not json").is_none());
+    }
+}
@@ -0,0 +1,239 @@
+use crate::error::ServerError;
+use aws_credential_types::{
+    provider::{ProvideCredentials, SharedCredentialsProvider},
+    Credentials,
+};
+use cargo_lambda_remote::{assume_role_provider, RemoteConfig};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::sync::{watch, RwLock};
+use tokio_graceful_shutdown::SubsystemHandle;
+use tracing::{error, info};
+
+/// How long before the credentials expire the emulator refreshes them.
+const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+/// Time to wait before trying to refresh the credentials again after an error,
+/// and the shortest time between refreshes.
+const ERROR_BACKOFF: Duration = Duration::from_secs(30);
+/// Session name used when the emulator assumes a role.
+const SESSION_NAME: &str = "cargo-lambda-watch";
+
+/// AwsEnvCache keeps the AWS credentials and region injected in the functions' environment
+/// with `--aws-env`, and notifies the functions when the credentials are refreshed.
+#[derive(Clone)]
+pub(crate) struct AwsEnvCache {
+    env: Arc<RwLock<HashMap<String, String>>>,
+    refreshes: Arc<watch::Sender<u64>>,
+}
+
+impl Default for AwsEnvCache {
+    fn default() -> Self {
+        AwsEnvCache {
+            env: Arc::default(),
+            refreshes: Arc::new(watch::channel(0).0),
+        }
+    }
+}
+
+impl AwsEnvCache {
+    /// Environment variables with the current credentials, empty without `--aws-env`.
+    pub(crate) async fn env(&self) -> HashMap<String, String> {
+        self.env.read().await.clone()
+    }
+
+    /// Receive a notification every time the credentials are refreshed.
+    pub(crate) fn subscribe(&self) -> watch::Receiver<u64> {
+        self.refreshes.subscribe()
+    }
+
+    async fn update(&self, env: HashMap<String, String>, notify: bool) {
+        *self.env.write().await = env;
+        if notify {
+            self.refreshes.send_modify(|count| *count += 1);
+        }
+    }
+}
+
+/// AwsEnv resolves the credentials of a real AWS account, with the same profiles,
+/// regions, and roles that the other commands use.
+pub(crate) struct AwsEnv {
+    provider: SharedCredentialsProvider,
+    region: Option<String>,
+    cache: AwsEnvCache,
+    expiry: Option<SystemTime>,
+}
+
+impl AwsEnv {
+    /// Load the credentials for the first time, failing if they cannot be resolved.
+    pub(crate) async fn load(
+        remote_config: &RemoteConfig,
+        role_arn: Option<&str>,
+    ) -> Result<AwsEnv, ServerError> {
+        let sdk_config = remote_config.sdk_config(None).await?;
+        let region = sdk_config.region().map(|r| r.to_string());
+
+        let Some(provider) = sdk_config.credentials_provider() else {
+            return Err(ServerError::MissingAwsCredentials(
+                "there is no credentials provider configured".into(),
+            ));
+        };
+
+        let provider = match role_arn {
+            Some(role_arn) => {
+                assume_role_provider(&sdk_config, role_arn, SESSION_NAME, None, None).await
+            }
+            None => provider,
+        };
+
+        let credentials = provider
+            .provide_credentials()
+            .await
+            .map_err(|e| ServerError::MissingAwsCredentials(e.to_string()))?;
+
+        let cache = AwsEnvCache::default();
+        cache
+            .update(credentials_env(&credentials, region.as_deref()), false)
+            .await;
+
+        info!(
+            access_key_id = credentials.access_key_id(),
+            ?region,
+            "injecting AWS credentials in the functions' environment"
+        );
+
+        Ok(AwsEnv {
+            provider,
+            region,
+            cache,
+            expiry: credentials.expiry(),
+        })
+    }
+
+    pub(crate) fn cache(&self) -> AwsEnvCache {
+        self.cache.clone()
+    }
+
+    /// Refresh the credentials before they expire, until the emulator shuts down.
+    /// Credentials without expiration, like static access keys, are never refreshed.
+    pub(crate) async fn refresh(mut self, subsys: SubsystemHandle) -> Result<(), ServerError> {
+        while let Some(expiry) = self.expiry {
+            tokio::select! {
+                _ = tokio::time::sleep(refresh_delay(expiry, SystemTime::now())) => {}
+                _ = subsys.on_shutdown_requested() => return Ok(()),
+            }
+
+            match self.provider.provide_credentials().await {
+                Ok(credentials) => {
+                    self.expiry = credentials.expiry();
+                    let env = credentials_env(&credentials, self.region.as_deref());
+                    // Providers return the same credentials until they can renew them,
+                    // the functions don't need to restart for those.
+                    if env == self.cache.env().await {
+                        continue;
+                    }
+
+                    info!(
+                        access_key_id = credentials.access_key_id(),
+                        "AWS credentials refreshed, reloading the functions"
+                    );
+                    self.cache.update(env, true).await;
+                }
+                Err(error) => {
+                    error!(%error, "failed to refresh the AWS credentials");
+                    self.expiry = Some(SystemTime::now() + REFRESH_MARGIN + ERROR_BACKOFF);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Time to wait before refreshing credentials that expire at `expiry`.
+/// Credentials that expire within the margin are refreshed after the backoff,
+/// so short sessions don't refresh in a loop.
+fn refresh_delay(expiry: SystemTime, now: SystemTime) -> Duration {
+    expiry
+        .duration_since(now)
+        .unwrap_or_default()
+        .saturating_sub(REFRESH_MARGIN)
+        .max(ERROR_BACKOFF)
+}
+
+/// Environment variables that the AWS SDKs read the credentials and the region from.
+fn credentials_env(credentials: &Credentials, region: Option<&str>) -> HashMap<String, String> {
+    let mut env = HashMap::from([
+        (
+            "AWS_ACCESS_KEY_ID".to_string(),
+            credentials.access_key_id().to_string(),
+        ),
+        (
+            "AWS_SECRET_ACCESS_KEY".to_string(),
+            credentials.secret_access_key().to_string(),
+        ),
+    ]);
+
+    if let Some(token) = credentials.session_token() {
+        env.insert("AWS_SESSION_TOKEN".into(), token.into());
+    }
+
+    if let Some(region) = region {
+        env.insert("AWS_REGION".into(), region.into());
+        env.insert("AWS_DEFAULT_REGION".into(), region.into());
+    }
+
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credentials_env() {
+        let credentials = Credentials::new("AKID", "SECRET", Some("TOKEN".into()), None, "test");
+        let env = credentials_env(&credentials, Some("eu-west-1"));
+        assert_eq!("AKID", env["AWS_ACCESS_KEY_ID"]);
+        assert_eq!("SECRET", env["AWS_SECRET_ACCESS_KEY"]);
+        assert_eq!("TOKEN", env["AWS_SESSION_TOKEN"]);
+        assert_eq!("eu-west-1", env["AWS_REGION"]);
+        assert_eq!("eu-west-1", env["AWS_DEFAULT_REGION"]);
+
+        let credentials = Credentials::new("AKID", "SECRET", None, None, "test");
+        let env = credentials_env(&credentials, None);
+        assert_eq!(2, env.len());
+    }
+
+    #[test]
+    fn test_refresh_delay() {
+        let now = SystemTime::now();
+        let expiry = now + Duration::from_secs(3600);
+        assert_eq!(
+            Duration::from_secs(3600) - REFRESH_MARGIN,
+            refresh_delay(expiry, now)
+        );
+
+        let expiry = now + Duration::from_secs(60);
+        assert_eq!(ERROR_BACKOFF, refresh_delay(expiry, now));
+
+        let expiry = now - Duration::from_secs(60);
+        assert_eq!(ERROR_BACKOFF, refresh_delay(expiry, now));
+    }
+
+    #[tokio::test]
+    async fn test_aws_env_cache() {
+        let cache = AwsEnvCache::default();
+        assert!(cache.env().await.is_empty());
+
+        let mut refreshes = cache.subscribe();
+        let env = HashMap::from([("AWS_REGION".to_string(), "us-east-1".to_string())]);
+        cache.update(env.clone(), true).await;
+
+        assert!(refreshes.has_changed().unwrap());
+        assert_eq!(1, *refreshes.borrow_and_update());
+        assert_eq!(env, cache.env().await);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::error::ServerError;
+use cargo_options::Run as CargoOptions;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use tracing::info;
+use watchexec::command::Command;
+
+/// Debuggers that can run the function, in order of preference for each platform.
+#[cfg(target_os = "macos")]
+const DEBUGGERS: &[Debugger] = &[Debugger::Lldb, Debugger::Gdb];
+#[cfg(not(target_os = "macos"))]
+const DEBUGGERS: &[Debugger] = &[Debugger::Gdb, Debugger::Lldb];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Debugger {
+    Gdb,
+    Lldb,
+}
+
+impl Debugger {
+    /// Program that runs the function in the terminal, or that waits for a remote debugger to attach.
+    fn program(&self, remote: bool) -> &'static str {
+        match (self, remote) {
+            (Debugger::Gdb, false) => "gdb",
+            (Debugger::Gdb, true) => "gdbserver",
+            (Debugger::Lldb, false) => "lldb",
+            (Debugger::Lldb, true) => "lldb-server",
+        }
+    }
+
+    fn runner(&self, port: Option<u16>) -> Vec<String> {
+        let program = self.program(port.is_some()).to_string();
+        let args: Vec<String> = match (self, port) {
+            (Debugger::Gdb, None) => vec!["-ex".into(), "run".into(), "--args".into()],
+            (Debugger::Lldb, None) => vec!["-o".into(), "run".into(), "--".into()],
+            (Debugger::Gdb, Some(port)) => vec![format!("127.0.0.1:{port}")],
+            (Debugger::Lldb, Some(port)) => vec!["gdbserver".into(), format!("127.0.0.1:{port}")],
+        };
+
+        std::iter::once(program).chain(args).collect()
+    }
+}
+
+/// Configuration to run functions under a debugger.
+#[derive(Clone, Debug)]
+pub(crate) struct DebugConfig {
+    pub debugger: Debugger,
+    pub port: Option<u16>,
+    pub binary_dir: PathBuf,
+}
+
+impl DebugConfig {
+    /// Find a debugger installed in the system.
+    /// Attaching with a port requires `gdbserver` or `lldb-server`.
+    pub(crate) fn new(port: Option<u16>, binary_dir: PathBuf) -> Result<Self, ServerError> {
+        let debugger = DEBUGGERS
+            .iter()
+            .find(|d| which::which(d.program(port.is_some())).is_ok())
+            .copied()
+            .ok_or_else(|| {
+                let programs = DEBUGGERS
+                    .iter()
+                    .map(|d| d.program(port.is_some()))
+                    .collect::<Vec<_>>();
+                ServerError::DebuggerNotFound(programs.join(", "))
+            })?;
+
+        Ok(DebugConfig {
+            debugger,
+            port,
+            binary_dir,
+        })
+    }
+
+    /// Add the flags to the `cargo run` command that build the function with debug information,
+    /// and start it with the debugger as Cargo's target runner.
+    pub(crate) fn wrap_command(&self, cmd: Command, cargo_options: &CargoOptions) -> Command {
+        let Command::Exec { prog, mut args } = cmd else {
+            return cmd;
+        };
+
+        let position = args
+            .iter()
+            .position(|arg| arg == "run")
+            .map(|p| p + 1)
+            .unwrap_or_default();
+
+        let runner = serde_json::to_string(&self.debugger.runner(self.port)).unwrap_or_default();
+        let config = [
+            format!("profile.{}.debug=true", profile_name(cargo_options)),
+            format!("target.'cfg(all())'.runner={runner}"),
+        ];
+
+        for (i, value) in config.into_iter().enumerate() {
+            args.insert(position + i * 2, "--config".into());
+            args.insert(position + i * 2 + 1, value);
+        }
+
+        Command::Exec { prog, args }
+    }
+
+    /// Print the instructions to attach a debugger to the function.
+    pub(crate) fn print_instructions(&self, function_name: &str, bin_name: Option<&String>) {
+        let Some(port) = self.port else {
+            info!(function = ?function_name, debugger = ?self.debugger, "starting function under the debugger in this terminal");
+            return;
+        };
+
+        info!("");
+        info!(function = ?function_name, "the function waits for a debugger to attach on 127.0.0.1:{port}");
+
+        let Some(bin_name) = bin_name else {
+            return;
+        };
+
+        let path = self.binary_dir.join(bin_name);
+        let launch = launch_configuration(function_name, &path, port);
+        let launch = serde_json::to_string_pretty(&launch).unwrap_or_default();
+
+        info!("add this configuration to your .vscode/launch.json file to attach with CodeLLDB:");
+        println!("{launch}");
+    }
+}
+
+/// Directory where Cargo puts the function binaries, like `target/debug`.
+pub(crate) fn binary_dir(target_dir: &Path, cargo_options: &CargoOptions) -> PathBuf {
+    let mut dir = target_dir.to_path_buf();
+    if let Some(target) = cargo_options.target.first() {
+        dir.push(target);
+    }
+
+    match profile_name(cargo_options) {
+        "dev" | "test" => dir.join("debug"),
+        "bench" => dir.join("release"),
+        profile => dir.join(profile),
+    }
+}
+
+fn profile_name(cargo_options: &CargoOptions) -> &str {
+    match &cargo_options.profile {
+        Some(profile) => profile,
+        None if cargo_options.release => "release",
+        None => "dev",
+    }
+}
+
+fn launch_configuration(function_name: &str, path: &Path, port: u16) -> Value {
+    json!({
+        "type": "lldb",
+        "request": "custom",
+        "name": format!("Attach to {function_name} (cargo lambda watch)"),
+        "targetCreateCommands": [format!("target create {}", path.display())],
+        "processCreateCommands": [format!("gdb-remote 127.0.0.1:{port}")],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_command() {
+        let config = DebugConfig {
+            debugger: Debugger::Gdb,
+            port: None,
+            binary_dir: PathBuf::from("target/debug"),
+        };
+        let cmd = Command::Exec {
+            prog: "cargo".into(),
+            args: vec!["run".into(), "--bin".into(), "basic".into()],
+        };
+
+        let Command::Exec { args, .. } = config.wrap_command(cmd, &CargoOptions::default()) else {
+            panic!("unexpected command");
+        };
+        assert_eq!(
+            vec![
+                "run",
+                "--config",
+                "profile.dev.debug=true",
+                "--config",
+                r#"target.'cfg(all())'.runner=["gdb","-ex","run","--args"]"#,
+                "--bin",
+                "basic"
+            ],
+            args
+        );
+    }
+
+    #[test]
+    fn test_remote_runner() {
+        assert_eq!(
+            vec!["gdbserver", "127.0.0.1:9229"],
+            Debugger::Gdb.runner(Some(9229))
+        );
+        assert_eq!(
+            vec!["lldb-server", "gdbserver", "127.0.0.1:9229"],
+            Debugger::Lldb.runner(Some(9229))
+        );
+    }
+
+    #[test]
+    fn test_binary_dir() {
+        let target = Path::new("target");
+        let mut options = CargoOptions::default();
+        assert_eq!(target.join("debug"), binary_dir(target, &options));
+
+        options.release = true;
+        options.target = vec!["x86_64-unknown-linux-gnu".into()];
+        assert_eq!(
+            target.join("x86_64-unknown-linux-gnu").join("release"),
+            binary_dir(target, &options)
+        );
+    }
+
+    #[test]
+    fn test_launch_configuration() {
+        let config = launch_configuration("basic", Path::new("target/debug/basic"), 9229);
+        assert_eq!("lldb", config["type"]);
+        assert_eq!(
+            "target create target/debug/basic",
+            config["targetCreateCommands"][0]
+        );
+        assert_eq!(
+            "gdb-remote 127.0.0.1:9229",
+            config["processCreateCommands"][0]
+        );
+    }
+}
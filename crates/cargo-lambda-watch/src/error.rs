@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use axum::response::{IntoResponse, Response};
 use cargo_lambda_metadata::error::MetadataError;
-use cargo_lambda_remote::tls::TlsError;
+use cargo_lambda_remote::{network::NetworkError, tls::TlsError};
 use http::StatusCode;
 use miette::Diagnostic;
 use serde::Serialize;
@@ -13,96 +13,186 @@ use crate::requests::{Action, InvokeRequest, NextEvent};
 #[derive(Debug, Diagnostic, Error)]
 pub enum ServerError {
     #[error("failed to build a response")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::response_build))]
     ResponseBuild(#[from] axum::http::Error),
 
     #[error("failed to decode a base64 encoded body: {0}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::body_decode_error))]
     BodyDecodeError(#[from] base64::DecodeError),
 
     #[error("failed to send message to api")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::send_function_message))]
     SendFunctionMessage,
 
     #[error("failed to send message to function: {0}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::send_action_message))]
     SendActionMessage(#[from] Box<tokio::sync::mpsc::error::SendError<Action>>),
 
     #[error("failed to send message to function: {0}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::send_invoke_message))]
     SendInvokeMessage(#[from] Box<tokio::sync::mpsc::error::SendError<InvokeRequest>>),
 
     #[error("failed to receive message from function: {0}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::receive_function_message))]
     ReceiveFunctionMessage(#[from] tokio::sync::oneshot::error::RecvError),
 
     #[error("failed to start function process")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::spawn_command))]
     SpawnCommand(#[from] std::io::Error),
 
+    #[error("invalid schedule expression `{0}`: {1}")]
+    #[diagnostic(code(cargo_lambda::watch::invalid_schedule))]
+    InvalidSchedule(String, String),
+
+    #[error("no debugger found in your system, install one of: {0}")]
+    #[diagnostic(code(cargo_lambda::watch::debugger_not_found))]
+    DebuggerNotFound(String),
+
+    #[error("failed to start extension `{0}`")]
+    #[diagnostic(code(cargo_lambda::watch::spawn_extension))]
+    SpawnExtension(std::path::PathBuf, #[source] std::io::Error),
+
     #[error("invalid request id header: {0}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::invalid_request_id_header))]
     InvalidRequestIdHeader(#[from] axum::http::header::ToStrError),
 
     #[error("failed to deserialize data {0}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::data_deserialization))]
     DataDeserialization(#[from] axum::Error),
 
     #[error("failed to deserialize streaming prelude")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::streaming_prelude_deserialization))]
     StreamingPreludeDeserialization,
 
     #[error("failed to deserialize the request body: {0}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::string_body))]
     StringBody(#[from] std::string::FromUtf8Error),
 
     #[error(transparent)]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::serialization_error))]
     SerializationError(#[from] serde_json::Error),
 
     #[error("failed to run watcher")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::watcher_error))]
     WatcherError(#[from] watchexec::error::CriticalError),
 
     #[error("failed to load ignore files")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::invalid_ignore_files))]
     InvalidIgnoreFiles(#[from] ignore_files::Error),
 
     #[error("missing extension id header")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::missing_extension_id_header))]
     MissingExtensionIdHeader,
 
     #[error("failed to send message to extension: {0}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::send_event_message))]
     SendEventMessage(#[from] Box<tokio::sync::mpsc::error::SendError<NextEvent>>),
 
     #[error("no extension event received")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::no_extension_event))]
     NoExtensionEvent,
 
     #[error("client context cannot be longer than 3583 bytes after base64 encoding, the current size is {0}")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::invalid_client_context))]
     InvalidClientContext(usize),
 
     #[error(transparent)]
-    #[diagnostic()]
+    #[diagnostic(transparent)]
     FailedToReadMetadata(#[from] MetadataError),
 
+    #[error("the function binary `{0}` doesn't exist")]
+    #[diagnostic(
+        code(cargo_lambda::watch::missing_artifact),
+        help(
+            "build your functions with `cargo lambda build` before running them with `--artifact-dir`"
+        )
+    )]
+    MissingArtifact(std::path::PathBuf),
+
+    #[error("invalid runner command `{0}`: {1}")]
+    #[diagnostic(
+        code(cargo_lambda::watch::invalid_runner),
+        help(
+            "the runner must be a command like `cross run --bin {{function}}`, quote arguments that include spaces"
+        )
+    )]
+    InvalidRunner(String, String),
+
+    #[error("service command `{0}` failed: {1}")]
+    #[diagnostic(code(cargo_lambda::watch::service_failed))]
+    ServiceFailed(String, String),
+
+    #[error("invoke sockets are only supported on Unix systems")]
+    #[diagnostic(
+        code(cargo_lambda::watch::unsupported_invoke_socket),
+        help("use `--invoke-address` and `--invoke-port` to send invoke requests over TCP")
+    )]
+    UnsupportedInvokeSocket,
+
+    #[error("the invoke socket `{0}` already exists, and it's not a socket")]
+    #[diagnostic(code(cargo_lambda::watch::invalid_invoke_socket))]
+    InvalidInvokeSocket(std::path::PathBuf),
+
+    #[error("the port {0} is already in use{}", .1.as_ref().map(|p| format!(" by {p}")).unwrap_or_default())]
+    #[diagnostic(
+        code(cargo_lambda::watch::port_in_use),
+        help(
+            "stop the process that's using the port, or use `--invoke-port` to listen on a different port"
+        )
+    )]
+    PortInUse(u16, Option<String>),
+
+    #[error("the function URL authorization type is `aws-iam`, but there are no function URL credentials")]
+    #[diagnostic(
+        code(cargo_lambda::watch::missing_function_url_credentials),
+        help(
+            "add test credentials with `--function-url-credentials ACCESS_KEY_ID:SECRET_ACCESS_KEY`"
+        )
+    )]
+    MissingFunctionUrlCredentials,
+
+    #[error("failed to create the directory to log requests `{0}`")]
+    #[diagnostic(code(cargo_lambda::watch::invalid_log_requests_dir))]
+    InvalidLogRequestsDir(std::path::PathBuf, #[source] std::io::Error),
+
+    #[error("failed to load the AWS credentials for the functions: {0}")]
+    #[diagnostic(
+        code(cargo_lambda::watch::missing_aws_credentials),
+        help(
+            "use `--aws-profile` to select a profile with credentials, or configure the credentials in your environment"
+        )
+    )]
+    MissingAwsCredentials(String),
+
     #[error("the project doesn't include any binary packages")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::no_binary_packages))]
     NoBinaryPackages,
 
+    #[error("the trigger `{0}` invokes the function `{1}`, but that function doesn't exist as a binary in your project")]
+    #[diagnostic(code(cargo_lambda::watch::missing_trigger_function))]
+    MissingTriggerFunction(String, String, #[help] Option<String>),
+
+    #[error(
+        "the trigger `{0}` accepts a batch size of {1} records at most, but the batch size is {2}"
+    )]
+    #[diagnostic(code(cargo_lambda::watch::invalid_batch_size))]
+    InvalidBatchSize(String, u16, u16),
+
     #[error("the streaming prelude is missing from the Lambda response")]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::missing_streaming_prelude))]
     MissingStreamingPrelude,
 
     #[error(transparent)]
-    #[diagnostic()]
+    #[diagnostic(code(cargo_lambda::watch::invalid_status_code))]
     InvalidStatusCode(#[from] hyper::http::status::InvalidStatusCode),
 
     #[error(transparent)]
-    #[diagnostic()]
+    #[diagnostic(transparent)]
     TlsError(#[from] TlsError),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    NetworkError(#[from] NetworkError),
 }
 
 // Explicitly implement Send + Sync
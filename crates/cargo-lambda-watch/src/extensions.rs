@@ -0,0 +1,107 @@
+use crate::{error::ServerError, requests::NextEvent, state::ExtensionCache};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+use tokio::process::{Child, Command};
+use tokio_graceful_shutdown::SubsystemHandle;
+use tracing::{debug, error, info, warn};
+
+/// How long the emulator waits for extensions to register before starting the function.
+pub(crate) const EXTENSIONS_INIT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long extensions have to exit after receiving the SHUTDOWN event,
+/// like the shutdown phase in Lambda.
+const EXTENSIONS_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Start the external extensions, and stop them when the emulator shuts down.
+pub(crate) async fn run_extensions(
+    subsys: SubsystemHandle,
+    extensions: Vec<PathBuf>,
+    env: HashMap<String, String>,
+    runtime_api: String,
+    ext_cache: ExtensionCache,
+) -> Result<(), ServerError> {
+    let mut children = Vec::with_capacity(extensions.len());
+
+    for path in extensions {
+        info!(extension = ?path, "starting lambda extension");
+
+        let child = extension_command(&path)
+            .envs(&env)
+            .env("AWS_LAMBDA_RUNTIME_API", &runtime_api)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| ServerError::SpawnExtension(path.clone(), e))?;
+
+        children.push((path, child));
+    }
+
+    subsys.on_shutdown_requested().await;
+
+    let event = NextEvent::shutdown("runtime emulator shutting down");
+    if let Err(error) = ext_cache.send_event(event).await {
+        debug!(?error, "failed to send shutdown event to extensions");
+    }
+
+    for (path, child) in children {
+        stop_extension(path, child).await;
+    }
+
+    Ok(())
+}
+
+async fn stop_extension(path: PathBuf, mut child: Child) {
+    match tokio::time::timeout(EXTENSIONS_SHUTDOWN_TIMEOUT, child.wait()).await {
+        Ok(Ok(status)) => debug!(extension = ?path, ?status, "lambda extension stopped"),
+        Ok(Err(error)) => error!(extension = ?path, ?error, "failed to stop lambda extension"),
+        Err(_) => {
+            warn!(extension = ?path, "lambda extension didn't stop after the shutdown event, killing it");
+            if let Err(error) = child.kill().await {
+                error!(extension = ?path, ?error, "failed to kill lambda extension");
+            }
+        }
+    }
+}
+
+/// Extensions in Cargo projects run with `cargo run`,
+/// any other path is executed directly, or looked up in the PATH.
+fn extension_command(path: &Path) -> Command {
+    let manifest_path = path.join("Cargo.toml");
+    if manifest_path.is_file() {
+        let mut cmd = Command::new("cargo");
+        cmd.arg("run").arg("--manifest-path").arg(manifest_path);
+        return cmd;
+    }
+
+    Command::new(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extension_command() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+
+        let cmd = extension_command(dir.path());
+        let cmd = cmd.as_std();
+        assert_eq!("cargo", cmd.get_program());
+        assert_eq!(
+            vec![
+                "run".into(),
+                "--manifest-path".into(),
+                dir.path().join("Cargo.toml").into_os_string()
+            ],
+            cmd.get_args().map(|a| a.to_os_string()).collect::<Vec<_>>()
+        );
+
+        let cmd = extension_command(Path::new("my-extension"));
+        assert_eq!("my-extension", cmd.as_std().get_program());
+        assert_eq!(0, cmd.as_std().get_args().count());
+    }
+}
@@ -0,0 +1,261 @@
+use crate::telemetry::InvocationStatus;
+use base64::{engine::general_purpose as b64, Engine as _};
+use bytes::Bytes;
+use chrono::{SecondsFormat, Utc};
+use http::HeaderMap;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::{collections::VecDeque, path::PathBuf, sync::Arc};
+use tokio::{sync::Mutex, time::Instant};
+use tracing::error;
+
+/// Maximum number of invocations kept in the history.
+const HISTORY_CAPACITY: usize = 100;
+
+/// Invocation records the request and the response of a function invocation.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Invocation {
+    pub request_id: String,
+    pub function_name: String,
+    pub timestamp: String,
+    pub status: Option<InvocationStatus>,
+    pub duration_ms: Option<f64>,
+    pub request: Payload,
+    pub response: Option<Payload>,
+    #[serde(skip)]
+    started_at: Instant,
+}
+
+/// Payload is the headers and the body of an invocation request or response.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct Payload {
+    #[serde(with = "http_serde::header_map")]
+    pub headers: HeaderMap,
+    #[serde(serialize_with = "serialize_body")]
+    pub body: Option<Bytes>,
+}
+
+/// HistoryCache keeps the latest invocations processed by the functions,
+/// so they can be inspected and replayed.
+#[derive(Clone, Default)]
+pub(crate) struct HistoryCache {
+    inner: Arc<Mutex<VecDeque<Invocation>>>,
+    /// Directory where every invocation is written as a JSON file, named after its request id.
+    log_dir: Option<PathBuf>,
+}
+
+impl HistoryCache {
+    /// Create a history that also writes every invocation to the given directory.
+    pub(crate) fn with_log_dir(dir: PathBuf) -> Self {
+        HistoryCache {
+            log_dir: Some(dir),
+            ..Default::default()
+        }
+    }
+
+    /// Record that a function started processing an invocation.
+    pub(crate) async fn started(
+        &self,
+        req_id: &str,
+        function_name: &str,
+        headers: &HeaderMap,
+        body: Bytes,
+    ) {
+        let invocation = Invocation {
+            request_id: req_id.into(),
+            function_name: function_name.into(),
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            status: None,
+            duration_ms: None,
+            request: Payload {
+                headers: headers.clone(),
+                body: Some(body),
+            },
+            response: None,
+            started_at: Instant::now(),
+        };
+
+        self.write_log(&invocation);
+
+        let mut inner = self.inner.lock().await;
+        if inner.len() >= HISTORY_CAPACITY {
+            inner.pop_front();
+        }
+        inner.push_back(invocation);
+    }
+
+    /// Record the response of an invocation.
+    /// Streaming responses are not buffered, so their body is not recorded.
+    pub(crate) async fn completed(
+        &self,
+        req_id: &str,
+        status: InvocationStatus,
+        headers: &HeaderMap,
+        body: Option<Bytes>,
+    ) {
+        let mut inner = self.inner.lock().await;
+        let Some(invocation) = inner.iter_mut().rev().find(|i| i.request_id == req_id) else {
+            return;
+        };
+
+        invocation.status = Some(status);
+        invocation.duration_ms = Some(invocation.started_at.elapsed().as_secs_f64() * 1000.0);
+        invocation.response = Some(Payload {
+            headers: headers.clone(),
+            body,
+        });
+
+        let invocation = invocation.clone();
+        drop(inner);
+        self.write_log(&invocation);
+    }
+
+    /// Write an invocation to the log directory, replacing the file written when it started.
+    fn write_log(&self, invocation: &Invocation) {
+        let Some(dir) = &self.log_dir else {
+            return;
+        };
+
+        // Clients can set their own request ids, don't let them write outside the directory.
+        let valid_id = invocation
+            .request_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_id {
+            error!(request_id = ?invocation.request_id, "invalid request id, the invocation won't be logged");
+            return;
+        }
+
+        let path = dir.join(format!("{}.json", invocation.request_id));
+        let result = serde_json::to_vec_pretty(invocation)
+            .map_err(std::io::Error::other)
+            .and_then(|json| std::fs::write(&path, json));
+        if let Err(error) = result {
+            error!(?error, ?path, "failed to write the invocation log");
+        }
+    }
+
+    /// Return all the invocations in the history, from oldest to newest.
+    pub(crate) async fn list(&self) -> Vec<Invocation> {
+        let inner = self.inner.lock().await;
+        inner.iter().cloned().collect()
+    }
+
+    /// Return the name of the function that processed the invocation with the given request id.
+    pub(crate) async fn function_name(&self, req_id: &str) -> Option<String> {
+        let inner = self.inner.lock().await;
+        inner
+            .iter()
+            .rev()
+            .find(|i| i.request_id == req_id)
+            .map(|i| i.function_name.clone())
+    }
+
+    /// Return the invocation with the given request id.
+    pub(crate) async fn get(&self, req_id: &str) -> Option<Invocation> {
+        let inner = self.inner.lock().await;
+        inner.iter().find(|i| i.request_id == req_id).cloned()
+    }
+}
+
+/// Bodies are serialized as JSON when they are valid JSON documents,
+/// as strings when they are valid UTF-8, and encoded in base64 otherwise.
+fn serialize_body<S: Serializer>(body: &Option<Bytes>, serializer: S) -> Result<S::Ok, S::Error> {
+    let Some(body) = body else {
+        return serializer.serialize_none();
+    };
+
+    if let Ok(value) = serde_json::from_slice::<Value>(body) {
+        return value.serialize(serializer);
+    }
+
+    match std::str::from_utf8(body) {
+        Ok(text) => serializer.serialize_str(text),
+        Err(_) => serializer.serialize_str(&b64::STANDARD.encode(body)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_history_cache() {
+        let cache = HistoryCache::default();
+        for i in 0..HISTORY_CAPACITY + 1 {
+            let body = Bytes::from(format!(r#"{{"id":{i}}}"#));
+            cache
+                .started(&i.to_string(), "basic", &HeaderMap::new(), body)
+                .await;
+        }
+
+        let list = cache.list().await;
+        assert_eq!(HISTORY_CAPACITY, list.len());
+        assert_eq!("1", list[0].request_id);
+        assert!(cache.get("0").await.is_none());
+
+        cache
+            .completed(
+                "1",
+                InvocationStatus::Success,
+                &HeaderMap::new(),
+                Some(Bytes::from("ok")),
+            )
+            .await;
+
+        let invocation = cache.get("1").await.unwrap();
+        assert_eq!(Some(InvocationStatus::Success), invocation.status);
+        assert!(invocation.duration_ms.is_some());
+
+        let value = serde_json::to_value(&invocation).unwrap();
+        assert_eq!("1", value["requestId"]);
+        assert_eq!("basic", value["functionName"]);
+        assert_eq!("success", value["status"]);
+        assert_eq!(json!({"id": 1}), value["request"]["body"]);
+        assert_eq!("ok", value["response"]["body"]);
+    }
+
+    #[tokio::test]
+    async fn test_write_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = HistoryCache::with_log_dir(dir.path().to_path_buf());
+
+        let body = Bytes::from(r#"{"command":"hi"}"#);
+        cache
+            .started("request-1", "basic", &HeaderMap::new(), body.clone())
+            .await;
+        let log = dir.path().join("request-1.json");
+        let value: Value = serde_json::from_slice(&std::fs::read(&log).unwrap()).unwrap();
+        assert_eq!(json!({"command": "hi"}), value["request"]["body"]);
+        assert!(value["response"].is_null());
+
+        cache
+            .completed(
+                "request-1",
+                InvocationStatus::Success,
+                &HeaderMap::new(),
+                Some(Bytes::from("ok")),
+            )
+            .await;
+        let value: Value = serde_json::from_slice(&std::fs::read(&log).unwrap()).unwrap();
+        assert_eq!("basic", value["functionName"]);
+        assert_eq!("ok", value["response"]["body"]);
+
+        cache
+            .started("../escape", "basic", &HeaderMap::new(), body)
+            .await;
+        assert!(!dir.path().join("../escape.json").exists());
+    }
+
+    #[test]
+    fn test_serialize_binary_body() {
+        let payload = Payload {
+            headers: HeaderMap::new(),
+            body: Some(Bytes::from_static(&[0xff, 0xfe])),
+        };
+        let value = serde_json::to_value(&payload).unwrap();
+        assert_eq!("//4=", value["body"]);
+    }
+}
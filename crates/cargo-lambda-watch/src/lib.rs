@@ -2,16 +2,18 @@ use axum::{extract::Extension, http::header::HeaderName, Router};
 use bytes::Bytes;
 use cargo_lambda_metadata::{
     cargo::{
-        filter_binary_targets_from_metadata, kind_bin_filter, selected_bin_filter, watch::Watch,
+        filter_binary_targets_from_metadata, kind_bin_filter, selected_bin_filter,
+        watch::{FunctionUrlAuth, Watch},
         CargoMetadata, CargoPackage,
     },
     lambda::Timeout,
     DEFAULT_PACKAGE_FUNCTION,
 };
-use cargo_lambda_remote::tls::TlsOptions;
+use cargo_lambda_remote::{tls::TlsOptions, RemoteConfig};
 use cargo_options::Run as CargoOptions;
+use http::{uri::Scheme, Uri, Version};
 use http_body_util::{combinators::BoxBody, BodyExt};
-use hyper::{body::Incoming, client::conn::http1, service::service_fn, Request, Response};
+use hyper::{body::Incoming, client::conn::http2, service::service_fn, Request, Response};
 use hyper_util::{
     rt::{TokioExecutor, TokioIo},
     server::conn::auto::Builder,
@@ -25,12 +27,14 @@ use opentelemetry_aws::trace::XrayPropagator;
 use rustls::ServerConfig;
 use std::{
     collections::{HashMap, HashSet},
+    io::IsTerminal,
     net::{IpAddr, SocketAddr},
-    path::Path,
+    path::{Path, PathBuf},
     str::FromStr,
     sync::Arc,
 };
 use tokio::{
+    io::{AsyncRead, AsyncWrite},
     net::{TcpListener, TcpStream},
     pin,
     time::Duration,
@@ -49,15 +53,37 @@ use tracing::{error, info, Subscriber};
 use tracing_opentelemetry::OpenTelemetryLayer;
 use tracing_subscriber::registry::LookupSpan;
 
+mod alb;
+mod aws_env;
+use aws_env::AwsEnv;
+mod debugger;
+use debugger::DebugConfig;
 mod error;
+mod extensions;
+mod history;
+use history::HistoryCache;
+mod lifecycle;
+use lifecycle::{LifecycleCache, LifecycleConfig};
+mod logs;
+mod middleware;
+mod notifications;
 mod requests;
 mod runtime;
 
+mod ports;
 mod scheduler;
 use scheduler::*;
+mod services;
+mod sigv4;
 mod state;
 use state::*;
+mod summary;
+mod telemetry;
+mod test_runner;
+pub use test_runner::{run_tests, Test};
 mod trigger_router;
+mod triggers;
+use triggers::TriggerConfig;
 mod watcher;
 use watcher::WatcherConfig;
 
@@ -74,57 +100,217 @@ pub async fn run(
 ) -> Result<()> {
     tracing::trace!("watching project");
 
-    let manifest_path = config.manifest_path();
+    let emulator = Emulator::new(config, base_env, metadata, color).await?;
+    let manifest_dir = emulator.manifest_dir.clone();
 
-    let mut cargo_options = config.cargo_opts.clone();
-    cargo_options.color = Some(color.into());
-    if cargo_options.manifest_path.is_none() {
-        cargo_options.manifest_path = Some(manifest_path.clone());
+    with_services(config, &manifest_dir, async move {
+        let _ = Toplevel::new(move |s| async move {
+            emulator.start(&s);
+        })
+        .catch_signals()
+        .handle_shutdown_requests(Duration::from_secs(1))
+        .await;
+    })
+    .await
+}
+
+/// Start the services that the functions depend on, run the emulator, and stop the services when it finishes.
+pub(crate) async fn with_services(
+    config: &Watch,
+    manifest_dir: &Path,
+    emulator: impl std::future::Future<Output = ()>,
+) -> Result<()> {
+    if let Err(error) = services::start_services(&config.services, manifest_dir).await {
+        services::stop_services(&config.services_shutdown, manifest_dir).await;
+        Err(error)?;
     }
 
-    let base = dunce::canonicalize(".").into_diagnostic()?;
-    let ignore_files = watcher::ignore::discover_files(&base).await;
+    emulator.await;
 
-    let env = config.lambda_environment(base_env).into_diagnostic()?;
+    services::stop_services(&config.services_shutdown, manifest_dir).await;
 
-    let package_filter = if !cargo_options.packages.is_empty() {
-        let packages = cargo_options.packages.clone();
-        Some(move |p: &&CargoPackage| packages.contains(&p.name))
-    } else {
-        None
-    };
+    Ok(())
+}
 
-    let binary_filter = if config.cargo_opts.bin.is_empty() {
-        Box::new(kind_bin_filter)
-    } else {
-        selected_bin_filter(config.cargo_opts.bin.clone())
-    };
+/// Emulator configured from the watch options, ready to start its servers.
+pub(crate) struct Emulator {
+    aws_env: Option<AwsEnv>,
+    runtime_state: RuntimeState,
+    cargo_options: CargoOptions,
+    watcher_config: WatcherConfig,
+    tls_options: TlsOptions,
+    invoke_socket: Option<PathBuf>,
+    disable_cors: bool,
+    timeout: Option<Timeout>,
+    trigger_config: TriggerConfig,
+    manifest_dir: PathBuf,
+}
 
-    let binary_packages =
-        filter_binary_targets_from_metadata(metadata, binary_filter, package_filter);
+impl Emulator {
+    pub(crate) async fn new(
+        config: &Watch,
+        base_env: &HashMap<String, String>,
+        metadata: &CargoMetadata,
+        color: &str,
+    ) -> Result<Emulator> {
+        let manifest_path = config.manifest_path();
+
+        let mut cargo_options = config.cargo_opts.clone();
+        cargo_options.color = Some(color.into());
+        if cargo_options.manifest_path.is_none() {
+            cargo_options.manifest_path = Some(manifest_path.clone());
+        }
 
-    if binary_packages.is_empty() {
-        Err(ServerError::NoBinaryPackages)?;
-    }
+        let base = dunce::canonicalize(".").into_diagnostic()?;
+        let ignore_files = watcher::ignore::discover_files(&base).await;
 
-    let watcher_config = WatcherConfig {
-        base,
-        ignore_files,
-        env,
-        ignore_changes: config.ignore_changes,
-        only_lambda_apis: config.only_lambda_apis,
-        manifest_path: manifest_path.clone(),
-        wait: config.wait,
-        ..Default::default()
-    };
+        let env = config.lambda_environment(base_env).into_diagnostic()?;
+
+        let package_filter = if !cargo_options.packages.is_empty() {
+            let packages = cargo_options.packages.clone();
+            Some(move |p: &&CargoPackage| packages.contains(&p.name))
+        } else {
+            None
+        };
+
+        let binary_filter = if config.cargo_opts.bin.is_empty() {
+            Box::new(kind_bin_filter)
+        } else {
+            selected_bin_filter(config.cargo_opts.bin.clone())
+        };
+
+        let binary_packages =
+            filter_binary_targets_from_metadata(metadata, binary_filter, package_filter);
+
+        if binary_packages.is_empty() {
+            Err(ServerError::NoBinaryPackages)?;
+        }
+
+        let target_dir = target_dir_from_metadata(metadata)?;
+        let debug = if config.debug {
+            let binary_dir = debugger::binary_dir(&target_dir, &cargo_options);
+            Some(DebugConfig::new(config.debug_port, binary_dir)?)
+        } else {
+            None
+        };
 
-    let runtime_state = build_runtime_state(config, &manifest_path, binary_packages)?;
+        let env_files = config
+            .env_options
+            .env_file
+            .iter()
+            .map(|f| dunce::canonicalize(f).unwrap_or_else(|_| base.join(f)))
+            .collect();
+
+        // Relative watch paths are resolved from the package's directory, where the configuration lives.
+        let manifest_dir = manifest_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| base.clone());
+        let watch_paths = config
+            .watch_paths
+            .iter()
+            .map(|p| {
+                let path = manifest_dir.join(p);
+                dunce::canonicalize(&path).unwrap_or(path)
+            })
+            .collect();
+
+        let artifact_dir = config
+            .artifact_dir
+            .as_ref()
+            .map(|dir| dunce::canonicalize(dir).unwrap_or_else(|_| base.join(dir)));
+
+        let watcher_config = WatcherConfig {
+            base,
+            ignore_files,
+            ignore_globs: config.ignore.clone(),
+            watch_paths,
+            env,
+            env_options: config.env_options.clone(),
+            env_files,
+            ignore_changes: config.ignore_changes,
+            only_lambda_apis: config.only_lambda_apis,
+            manifest_path: manifest_path.clone(),
+            wait: config.wait,
+            extensions: config.with_extension.clone(),
+            debug,
+            artifact_dir,
+            runner: config.runner.clone(),
+            init_all: config.init_all,
+            init_concurrency: config.init_concurrency(),
+            prefix_output: binary_packages.len() > 1,
+            color: use_color(color),
+            logs_dir: config
+                .log_files
+                .then(|| target_dir.join("lambda").join("logs")),
+            ..Default::default()
+        };
+
+        config.tls_options.ensure_local_certificate()?;
+
+        let mut runtime_state = build_runtime_state(config, &manifest_path, binary_packages)?;
 
-    let disable_cors = config.disable_cors;
-    let timeout = config.timeout.clone();
-    let tls_options = config.tls_options.clone();
+        let remote_config = RemoteConfig {
+            profile: config.aws_profile.clone(),
+            region: config.aws_region.clone(),
+            ..Default::default()
+        };
+        let aws_env = if config.aws_env {
+            let aws_env = AwsEnv::load(&remote_config, config.aws_role_arn.as_deref()).await?;
+            runtime_state.aws_env = aws_env.cache();
+            Some(aws_env)
+        } else {
+            None
+        };
+
+        let disable_cors = config.disable_cors;
+        let timeout = config.timeout.clone();
+        let tls_options = config.tls_options.clone();
+        let invoke_socket = config.invoke_socket.clone();
+        if cfg!(not(unix)) && invoke_socket.is_some() {
+            Err(ServerError::UnsupportedInvokeSocket)?;
+        }
+        let trigger_config = TriggerConfig::new(config, &remote_config);
+
+        Ok(Emulator {
+            aws_env,
+            runtime_state,
+            cargo_options,
+            watcher_config,
+            tls_options,
+            invoke_socket,
+            disable_cors,
+            timeout,
+            trigger_config,
+            manifest_dir,
+        })
+    }
 
-    let _ = Toplevel::new(move |s| async move {
+    /// Address of the server that receives the invoke requests, without TLS.
+    pub(crate) fn runtime_addr(&self) -> SocketAddr {
+        self.runtime_state.addresses().0
+    }
+
+    /// Start the emulator's servers as subsystems of the given subsystem.
+    pub(crate) fn start(self, s: &SubsystemHandle) {
+        let Emulator {
+            aws_env,
+            runtime_state,
+            cargo_options,
+            watcher_config,
+            tls_options,
+            invoke_socket,
+            disable_cors,
+            timeout,
+            trigger_config,
+            ..
+        } = self;
+
+        if let Some(aws_env) = aws_env {
+            s.start(SubsystemBuilder::new("AWS credentials", move |s| {
+                aws_env.refresh(s)
+            }));
+        }
         s.start(SubsystemBuilder::new("Lambda server", move |s| {
             start_server(
                 s,
@@ -132,16 +318,22 @@ pub async fn run(
                 cargo_options,
                 watcher_config,
                 tls_options,
+                invoke_socket,
                 disable_cors,
                 timeout,
+                trigger_config,
             )
         }));
-    })
-    .catch_signals()
-    .handle_shutdown_requests(Duration::from_secs(1))
-    .await;
+    }
+}
 
-    Ok(())
+/// Whether the function prefixes are colored, following the `--color` flag.
+fn use_color(color: &str) -> bool {
+    match color {
+        "always" => true,
+        "never" => false,
+        _ => std::io::stdout().is_terminal(),
+    }
 }
 
 pub fn xray_layer<S>(config: &Watch) -> OpenTelemetryLayer<S, Tracer>
@@ -171,40 +363,90 @@ fn build_runtime_state(
     let ip = IpAddr::from_str(&config.invoke_address)
         .into_diagnostic()
         .wrap_err("invalid invoke address")?;
-    let (runtime_port, proxy_addr) = if config.tls_options.is_secure() {
-        (
-            config.invoke_port + 1,
-            Some(SocketAddr::from((ip, config.invoke_port))),
-        )
+    let secure = config.tls_options.is_secure();
+    let invoke_port = ports::invoke_port(ip, config.invoke_port, secure)?;
+    let (runtime_port, proxy_addr) = if secure {
+        (invoke_port + 1, Some(SocketAddr::from((ip, invoke_port))))
     } else {
-        (config.invoke_port, None)
+        (invoke_port, None)
     };
     let runtime_addr = SocketAddr::from((ip, runtime_port));
 
-    Ok(RuntimeState::new(
+    let mut state = RuntimeState::new(
         runtime_addr,
         proxy_addr,
         manifest_path.to_path_buf(),
         binary_packages,
         config.router.clone(),
-    ))
+    );
+
+    if config.function_url_auth == Some(FunctionUrlAuth::AwsIam) {
+        if config.function_url_credentials.is_empty() {
+            Err(ServerError::MissingFunctionUrlCredentials)?;
+        }
+        state.function_url_credentials = Some(config.function_url_credentials.clone());
+    }
+
+    // Relative paths are resolved from the package's directory, commands without a path are searched in the PATH.
+    state.middleware = config.middleware.as_ref().map(|path| {
+        match (path.components().count(), manifest_path.parent()) {
+            (count, Some(dir)) if count > 1 => dir.join(path),
+            _ => path.clone(),
+        }
+    });
+
+    state.payload_limits = !config.disable_payload_limits;
+    state.req_cache = RequestCache::new(config.max_queue_depth());
+    state.alb_functions = config.alb_functions.iter().cloned().collect();
+    state.alb_multi_value_headers = config.alb_multi_value_headers;
+
+    if let Some(dir) = &config.log_requests_dir {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| ServerError::InvalidLogRequestsDir(dir.clone(), e))?;
+        state.history = HistoryCache::with_log_dir(dir.clone());
+    }
+
+    if config.emulate_lifecycle {
+        let lifecycle = LifecycleConfig::new(
+            config.init_timeout.as_ref().map(Timeout::duration),
+            config.idle_timeout.as_ref().map(Timeout::duration),
+        );
+        state.lifecycle = LifecycleCache::new(lifecycle);
+    }
+
+    Ok(state)
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn start_server(
     subsys: SubsystemHandle,
     runtime_state: RuntimeState,
     cargo_options: CargoOptions,
     watcher_config: WatcherConfig,
     tls_options: TlsOptions,
+    invoke_socket: Option<PathBuf>,
     disable_cors: bool,
     timeout: Option<Timeout>,
+    trigger_config: TriggerConfig,
 ) -> Result<()> {
     let only_lambda_apis = watcher_config.only_lambda_apis;
-    let init_default_function =
-        runtime_state.is_default_function_enabled() && watcher_config.send_function_init();
+    let init_all = watcher_config.init_all && watcher_config.send_function_init();
+    let init_concurrency = watcher_config.init_concurrency;
+    let init_default_function = !init_all
+        && runtime_state.is_default_function_enabled()
+        && watcher_config.send_function_init();
 
     let (runtime_addr, proxy_addr, runtime_url) = runtime_state.addresses();
 
+    let start_mode = if only_lambda_apis {
+        summary::StartMode::External
+    } else if init_default_function || init_all {
+        summary::StartMode::Eager
+    } else {
+        summary::StartMode::Lazy
+    };
+    let summary = summary::summary_lines(&runtime_state, start_mode, proxy_addr.is_some());
+
     let x_request_id = HeaderName::from_static("lambda-runtime-aws-request-id");
     let req_tx = init_scheduler(
         &subsys,
@@ -214,6 +456,9 @@ async fn start_server(
     )
     .await;
 
+    triggers::start_triggers(&subsys, &runtime_state, trigger_config, req_tx.clone())?;
+
+    let init_all_state = init_all.then(|| runtime_state.clone());
     let state_ref = Arc::new(runtime_state);
     let mut app = Router::new()
         .merge(trigger_router::routes().with_state(state_ref.clone()))
@@ -249,10 +494,16 @@ async fn start_server(
         info!("AWS_LAMBDA_RUNTIME_API={}", runtime_url);
         info!("AWS_LAMBDA_FUNCTION_NAME={DEFAULT_PACKAGE_FUNCTION}");
     } else {
-        let print_start_info = if init_default_function {
+        let print_start_info = if let Some(state) = init_all_state {
+            init_functions(state, req_tx.clone(), init_concurrency);
+            false
+        } else if init_default_function {
             // This call ignores any error sending the action.
             // The function can still be lazy loaded later if there is any error.
-            req_tx.send(Action::Init).await.is_err()
+            req_tx
+                .send(Action::Init(DEFAULT_PACKAGE_FUNCTION.into()))
+                .await
+                .is_err()
         } else {
             false
         };
@@ -265,6 +516,12 @@ async fn start_server(
         }
     }
 
+    info!("");
+    for line in summary {
+        info!("{line}");
+    }
+    info!("");
+
     let tls_config = tls_options.server_config().await?;
     let tls_tracker = TaskTracker::new();
 
@@ -276,6 +533,20 @@ async fn start_server(
         }));
     }
 
+    #[cfg(unix)]
+    if let Some(socket_path) = invoke_socket {
+        let socket_tracker = tls_tracker.clone();
+
+        subsys.start(SubsystemBuilder::new(
+            "Unix socket proxy",
+            move |s| async move {
+                start_socket_proxy(s, socket_tracker, socket_path, runtime_addr).await
+            },
+        ));
+    }
+    #[cfg(not(unix))]
+    let _ = invoke_socket;
+
     info!(?runtime_addr, "starting Runtime server");
     let out = axum::serve(
         TcpListener::bind(runtime_addr).await.into_diagnostic()?,
@@ -325,10 +596,6 @@ async fn start_tls_proxy(
             let connection_tracker = connection_tracker.clone();
 
             async move {
-                let hyper_service = service_fn(move |request: Request<Incoming>| {
-                    proxy(connection_tracker.clone(), request, addr.clone())
-                });
-
                 let tls_stream = match acceptor.accept(stream).await {
                     Ok(tls_stream) => tls_stream,
                     Err(e) => {
@@ -337,49 +604,160 @@ async fn start_tls_proxy(
                     }
                 };
 
-                let builder = Builder::new(TokioExecutor::new());
-                let conn = builder.serve_connection(TokioIo::new(tls_stream), hyper_service);
+                serve_proxy_connection(
+                    TokioIo::new(tls_stream),
+                    cancellation_token,
+                    connection_tracker,
+                    addr,
+                )
+                .await
+            }
+        });
+    }
+}
 
-                pin!(conn);
+/// Listen for requests in a Unix domain socket, and forward them to the runtime server.
+#[cfg(unix)]
+async fn start_socket_proxy(
+    subsys: SubsystemHandle,
+    connection_tracker: TaskTracker,
+    socket_path: PathBuf,
+    runtime_addr: SocketAddr,
+) -> Result<()> {
+    use std::os::unix::fs::FileTypeExt;
 
-                let result = tokio::select! {
-                    res = conn.as_mut() => res,
-                    _ = cancellation_token.cancelled() => {
-                        conn.as_mut().graceful_shutdown();
-                        conn.await
-                    }
-                };
+    // Remove the socket left behind by a previous run, but never other kinds of files.
+    if let Ok(metadata) = std::fs::symlink_metadata(&socket_path) {
+        if !metadata.file_type().is_socket() {
+            return Err(ServerError::InvalidInvokeSocket(socket_path).into());
+        }
+        std::fs::remove_file(&socket_path).into_diagnostic()?;
+    }
+
+    let listener = tokio::net::UnixListener::bind(&socket_path).into_diagnostic()?;
+    info!(
+        ?socket_path,
+        "starting Unix socket server, use this socket to send requests to the runtime"
+    );
+
+    let addr = Arc::new(runtime_addr);
+
+    loop {
+        let stream = tokio::select! {
+            res = listener.accept() => res.into_diagnostic()?.0,
+            _ = subsys.on_shutdown_requested() => break,
+        };
+
+        let addr = addr.clone();
 
-                if let Err(e) = result {
-                    error!(error = ?e, "Failed to serve connection");
-                }
+        connection_tracker.spawn({
+            let cancellation_token = subsys.create_cancellation_token();
+            let connection_tracker = connection_tracker.clone();
 
-                Ok(())
+            async move {
+                serve_proxy_connection(
+                    TokioIo::new(stream),
+                    cancellation_token,
+                    connection_tracker,
+                    addr,
+                )
+                .await
             }
         });
     }
+
+    if let Err(error) = std::fs::remove_file(&socket_path) {
+        error!(?error, ?socket_path, "failed to remove the invoke socket");
+    }
+
+    Ok(())
 }
 
-async fn proxy(
+/// Serve the requests in a client connection, forwarding them to the runtime server.
+async fn serve_proxy_connection<I>(
+    io: TokioIo<I>,
+    cancellation_token: tokio_util::sync::CancellationToken,
     connection_tracker: TaskTracker,
-    req: Request<hyper::body::Incoming>,
     addr: Arc<SocketAddr>,
-) -> Result<Response<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
-    let stream = TcpStream::connect(&*addr).await.unwrap();
-    let io = TokioIo::new(stream);
+) -> Result<()>
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    // Each client connection reuses a single HTTP/2 connection to the runtime server,
+    // so concurrent requests are multiplexed instead of opening a connection per request.
+    let sender = match runtime_connection(&connection_tracker, &addr).await {
+        Ok(sender) => sender,
+        Err(e) => {
+            error!(error = ?e, "Failed to connect to the runtime server");
+            return Err(e);
+        }
+    };
+
+    let hyper_service =
+        service_fn(move |request: Request<Incoming>| proxy(sender.clone(), request, addr.clone()));
 
-    let (mut sender, conn) = http1::Builder::new()
-        .preserve_header_case(true)
-        .title_case_headers(true)
-        .handshake(io)
-        .await?;
+    let builder = Builder::new(TokioExecutor::new());
+    let conn = builder.serve_connection(io, hyper_service);
+
+    pin!(conn);
+
+    let result = tokio::select! {
+        res = conn.as_mut() => res,
+        _ = cancellation_token.cancelled() => {
+            conn.as_mut().graceful_shutdown();
+            conn.await
+        }
+    };
+
+    if let Err(e) = result {
+        error!(error = ?e, "Failed to serve connection");
+    }
+
+    Ok(())
+}
+
+/// Open an HTTP/2 connection with prior knowledge (h2c) to the runtime server.
+async fn runtime_connection(
+    connection_tracker: &TaskTracker,
+    addr: &SocketAddr,
+) -> Result<http2::SendRequest<Incoming>> {
+    let stream = TcpStream::connect(addr).await.into_diagnostic()?;
+
+    let (sender, conn) = http2::handshake(TokioExecutor::new(), TokioIo::new(stream))
+        .await
+        .into_diagnostic()?;
 
     connection_tracker.spawn(async move {
         if let Err(err) = conn.await {
-            println!("Connection failed: {:?}", err);
+            error!(error = ?err, "runtime connection failed");
         }
     });
 
+    Ok(sender)
+}
+
+async fn proxy(
+    mut sender: http2::SendRequest<Incoming>,
+    mut req: Request<Incoming>,
+    addr: Arc<SocketAddr>,
+) -> Result<Response<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
+    // HTTP/2 requests need an absolute URI, and HTTP/1.1 clients only send the path.
+    let path = req
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/")
+        .to_string();
+    let uri = Uri::builder()
+        .scheme(Scheme::HTTP)
+        .authority(addr.to_string())
+        .path_and_query(path)
+        .build();
+    if let Ok(uri) = uri {
+        *req.uri_mut() = uri;
+    }
+    *req.version_mut() = Version::HTTP_2;
+
     let resp = sender.send_request(req).await?;
     Ok(resp.map(|b| b.boxed()))
 }
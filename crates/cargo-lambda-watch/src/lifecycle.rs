@@ -0,0 +1,331 @@
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+use tokio::{sync::Mutex, time::Instant};
+use tracing::{debug, warn};
+
+const DEFAULT_INIT_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+const LIFECYCLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Configuration to emulate the lifecycle of Lambda execution environments.
+#[derive(Clone, Debug)]
+pub(crate) struct LifecycleConfig {
+    pub init_timeout: Duration,
+    pub idle_timeout: Duration,
+}
+
+impl LifecycleConfig {
+    pub(crate) fn new(init_timeout: Option<Duration>, idle_timeout: Option<Duration>) -> Self {
+        LifecycleConfig {
+            init_timeout: init_timeout.unwrap_or(DEFAULT_INIT_TIMEOUT),
+            idle_timeout: idle_timeout.unwrap_or(DEFAULT_IDLE_TIMEOUT),
+        }
+    }
+}
+
+/// Reason why the emulator stops a function process.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ShutdownReason {
+    InitTimeout(Duration),
+    Idle(Duration),
+}
+
+impl fmt::Display for ShutdownReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShutdownReason::InitTimeout(d) => {
+                write!(f, "the init phase timed out after {}s", d.as_secs())
+            }
+            ShutdownReason::Idle(d) => {
+                write!(f, "the function was idle for more than {}s", d.as_secs())
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Instance {
+    pid: Option<u32>,
+    spawned_at: Instant,
+    last_activity: Instant,
+    initialized: bool,
+    busy: bool,
+    frozen: bool,
+    pending: usize,
+}
+
+impl Instance {
+    fn new() -> Self {
+        let now = Instant::now();
+        Instance {
+            pid: None,
+            spawned_at: now,
+            last_activity: now,
+            initialized: false,
+            busy: false,
+            frozen: false,
+            pending: 0,
+        }
+    }
+
+    fn thaw(&mut self) {
+        if self.frozen {
+            if let Some(pid) = self.pid {
+                signal::resume(pid);
+            }
+            self.frozen = false;
+        }
+    }
+
+    fn shutdown_reason(&self, config: &LifecycleConfig, now: Instant) -> Option<ShutdownReason> {
+        if self.pid.is_none() {
+            return None;
+        }
+
+        if !self.initialized {
+            return (now.duration_since(self.spawned_at) > config.init_timeout)
+                .then_some(ShutdownReason::InitTimeout(config.init_timeout));
+        }
+
+        let idle = !self.busy && self.pending == 0;
+        (idle && now.duration_since(self.last_activity) > config.idle_timeout)
+            .then_some(ShutdownReason::Idle(config.idle_timeout))
+    }
+}
+
+/// LifecycleCache keeps track of the state of each function process
+/// to emulate the init, invoke, and shutdown phases of Lambda.
+/// All operations are no-ops when the lifecycle emulation is disabled.
+#[derive(Clone, Default)]
+pub(crate) struct LifecycleCache {
+    config: Option<LifecycleConfig>,
+    instances: Arc<Mutex<HashMap<String, Instance>>>,
+}
+
+impl LifecycleCache {
+    pub(crate) fn new(config: LifecycleConfig) -> Self {
+        if !signal::SUPPORTED {
+            warn!("freezing functions between invocations is not supported in this platform");
+        }
+
+        LifecycleCache {
+            config: Some(config),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.config.is_some()
+    }
+
+    /// Record the process id of a function that just started.
+    pub(crate) async fn spawned(&self, function_name: &str, pid: u32) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut instances = self.instances.lock().await;
+        let instance = instances
+            .entry(function_name.into())
+            .or_insert_with(Instance::new);
+
+        let now = Instant::now();
+        instance.pid = Some(pid);
+        instance.spawned_at = now;
+        instance.last_activity = now;
+        instance.initialized = false;
+        instance.busy = false;
+        instance.frozen = false;
+    }
+
+    /// Record a new invocation for a function, and thaw its process if it's frozen.
+    pub(crate) async fn invoke_scheduled(&self, function_name: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut instances = self.instances.lock().await;
+        let instance = instances
+            .entry(function_name.into())
+            .or_insert_with(Instance::new);
+
+        instance.pending += 1;
+        instance.thaw();
+    }
+
+    /// Record that an invocation has been sent to the function.
+    pub(crate) async fn invoke_started(&self, function_name: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut instances = self.instances.lock().await;
+        if let Some(instance) = instances.get_mut(function_name) {
+            instance.pending = instance.pending.saturating_sub(1);
+            instance.busy = true;
+        }
+    }
+
+    /// Record that the function is waiting for the next invocation.
+    /// Like in Lambda, the process is frozen if there are no invocations pending.
+    pub(crate) async fn waiting_next(&self, function_name: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut instances = self.instances.lock().await;
+        let Some(instance) = instances.get_mut(function_name) else {
+            return;
+        };
+
+        instance.initialized = true;
+        instance.busy = false;
+        instance.last_activity = Instant::now();
+
+        if instance.pending == 0 && !instance.frozen {
+            if let Some(pid) = instance.pid {
+                debug!(function = function_name, pid, "freezing function");
+                signal::freeze(pid);
+                instance.frozen = true;
+            }
+        }
+    }
+
+    /// Thaw the function process, usually before watchexec restarts or stops it.
+    pub(crate) async fn thaw(&self, function_name: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut instances = self.instances.lock().await;
+        if let Some(instance) = instances.get_mut(function_name) {
+            instance.thaw();
+        }
+    }
+
+    /// Thaw the function process and forget its state, usually before stopping it.
+    pub(crate) async fn release(&self, function_name: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut instances = self.instances.lock().await;
+        if let Some(mut instance) = instances.remove(function_name) {
+            instance.thaw();
+        }
+    }
+
+    /// Wait until the function process must be stopped.
+    /// This future never completes when the lifecycle emulation is disabled.
+    pub(crate) async fn wait_for_shutdown(&self, function_name: &str) -> ShutdownReason {
+        let Some(config) = &self.config else {
+            return std::future::pending().await;
+        };
+
+        loop {
+            tokio::time::sleep(LIFECYCLE_CHECK_INTERVAL).await;
+
+            let instances = self.instances.lock().await;
+            let reason = instances
+                .get(function_name)
+                .and_then(|instance| instance.shutdown_reason(config, Instant::now()));
+
+            if let Some(reason) = reason {
+                return reason;
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+mod signal {
+    pub(super) const SUPPORTED: bool = true;
+
+    /// Functions run in their own process group,
+    /// so signals reach both cargo and the function binary.
+    fn send(pid: u32, signal: libc::c_int) {
+        // SAFETY: `kill` doesn't access any memory, it only sends a signal to the process group.
+        let res = unsafe { libc::kill(-(pid as libc::pid_t), signal) };
+        if res != 0 {
+            tracing::debug!(pid, signal, error = ?std::io::Error::last_os_error(), "failed to send signal to function");
+        }
+    }
+
+    pub(super) fn freeze(pid: u32) {
+        send(pid, libc::SIGSTOP)
+    }
+
+    pub(super) fn resume(pid: u32) {
+        send(pid, libc::SIGCONT)
+    }
+}
+
+#[cfg(not(unix))]
+mod signal {
+    pub(super) const SUPPORTED: bool = false;
+
+    pub(super) fn freeze(_pid: u32) {}
+
+    pub(super) fn resume(_pid: u32) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> LifecycleConfig {
+        LifecycleConfig::new(Some(Duration::from_secs(10)), Some(Duration::from_secs(60)))
+    }
+
+    #[test]
+    fn test_shutdown_reason_init_timeout() {
+        let config = config();
+        let mut instance = Instance::new();
+        let now = instance.spawned_at;
+
+        assert_eq!(
+            None,
+            instance.shutdown_reason(&config, now + Duration::from_secs(20))
+        );
+
+        instance.pid = Some(1);
+        assert_eq!(
+            None,
+            instance.shutdown_reason(&config, now + Duration::from_secs(5))
+        );
+        assert_eq!(
+            Some(ShutdownReason::InitTimeout(config.init_timeout)),
+            instance.shutdown_reason(&config, now + Duration::from_secs(11))
+        );
+    }
+
+    #[test]
+    fn test_shutdown_reason_idle() {
+        let config = config();
+        let mut instance = Instance::new();
+        let now = instance.spawned_at;
+        instance.pid = Some(1);
+        instance.initialized = true;
+
+        assert_eq!(
+            None,
+            instance.shutdown_reason(&config, now + Duration::from_secs(30))
+        );
+        assert_eq!(
+            Some(ShutdownReason::Idle(config.idle_timeout)),
+            instance.shutdown_reason(&config, now + Duration::from_secs(61))
+        );
+
+        instance.busy = true;
+        assert_eq!(
+            None,
+            instance.shutdown_reason(&config, now + Duration::from_secs(61))
+        );
+
+        instance.busy = false;
+        instance.pending = 1;
+        assert_eq!(
+            None,
+            instance.shutdown_reason(&config, now + Duration::from_secs(61))
+        );
+    }
+}
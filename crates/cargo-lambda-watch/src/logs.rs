@@ -0,0 +1,378 @@
+use crate::requests::{EventsDestination, LogBuffering, SubcribeEvent};
+use chrono::{SecondsFormat, Utc};
+use reqwest::{Client, Url};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs::File,
+    io::{BufRead, BufReader, Read, Write},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, Mutex},
+    time::Instant,
+};
+use tracing::{debug, error};
+
+/// Hostnames that extensions use in their destination URIs.
+/// In Lambda, they resolve to the extension's own sandbox.
+const SANDBOX_HOSTS: [&str; 2] = ["sandbox", "sandbox.localdomain"];
+const LOCAL_HOST: &str = "127.0.0.1";
+
+/// A log event in the format that the Logs and Telemetry APIs deliver to extensions.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct LogRecord {
+    pub time: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub record: Value,
+}
+
+impl LogRecord {
+    pub(crate) fn new(kind: &str, record: Value) -> Self {
+        LogRecord {
+            time: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            kind: kind.into(),
+            record,
+        }
+    }
+
+    /// A line that the function wrote to stdout or stderr.
+    pub(crate) fn function(line: &str) -> Self {
+        Self::new("function", Value::String(line.into()))
+    }
+
+    /// The category of the record, like `function` or `platform`,
+    /// which is what extensions subscribe to.
+    fn category(&self) -> &str {
+        self.kind.split('.').next().unwrap_or_default()
+    }
+
+    fn size(&self) -> usize {
+        self.time.len() + self.kind.len() + self.record.to_string().len()
+    }
+}
+
+struct Subscriber {
+    types: HashSet<String>,
+    tx: mpsc::Sender<LogRecord>,
+}
+
+/// LogsCache keeps track of the extensions subscribed to receive log events,
+/// and sends those events to them in batches.
+#[derive(Clone, Default)]
+pub(crate) struct LogsCache {
+    subscribers: Arc<Mutex<HashMap<String, Subscriber>>>,
+}
+
+impl LogsCache {
+    pub(crate) async fn subscribe(&self, extension_id: &str, subscription: SubcribeEvent) {
+        let (tx, rx) = mpsc::channel::<LogRecord>(1_000);
+        let buffering = subscription.buffering.unwrap_or_default();
+        let destination = subscription.destination;
+
+        tokio::spawn(async move { deliver(rx, destination, buffering).await });
+
+        let subscriber = Subscriber {
+            types: subscription.types.into_iter().collect(),
+            tx,
+        };
+
+        let mut subscribers = self.subscribers.lock().await;
+        subscribers.insert(extension_id.into(), subscriber);
+    }
+
+    pub(crate) async fn has_subscribers(&self) -> bool {
+        let subscribers = self.subscribers.lock().await;
+        !subscribers.is_empty()
+    }
+
+    pub(crate) async fn send(&self, record: LogRecord) {
+        let subscribers = self.subscribers.lock().await;
+
+        for (extension_id, subscriber) in subscribers.iter() {
+            if !subscriber.types.contains(record.category()) {
+                continue;
+            }
+
+            if let Err(error) = subscriber.tx.try_send(record.clone()) {
+                debug!(%extension_id, ?error, "dropping log event for extension");
+            }
+        }
+    }
+}
+
+async fn deliver(
+    mut rx: mpsc::Receiver<LogRecord>,
+    destination: EventsDestination,
+    buffering: LogBuffering,
+) {
+    let uri = match local_destination(&destination.uri) {
+        Ok(uri) => uri,
+        Err(error) => {
+            error!(uri = ?destination.uri, %error, "invalid log destination");
+            return;
+        }
+    };
+
+    let client = Client::new();
+    let timeout = Duration::from_millis(buffering.timeout_ms as u64);
+
+    loop {
+        let Some(first) = rx.recv().await else {
+            return;
+        };
+
+        let deadline = Instant::now() + timeout;
+        let mut size = first.size();
+        let mut batch = vec![first];
+
+        while batch.len() < buffering.max_items && size < buffering.max_bytes {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(record)) => {
+                    size += record.size();
+                    batch.push(record);
+                }
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        debug!(%uri, records = batch.len(), "delivering log events to extension");
+        let result = client
+            .post(uri.clone())
+            .header("content-type", "application/json")
+            .body(serde_json::to_vec(&batch).unwrap_or_default())
+            .send()
+            .await;
+
+        if let Err(error) = result {
+            error!(%uri, %error, "failed to deliver log events to extension");
+        }
+    }
+}
+
+/// Replace the sandbox hostname in the destination URI,
+/// since extensions run in the local machine.
+fn local_destination(uri: &str) -> Result<Url, String> {
+    let mut url = Url::parse(uri).map_err(|e| e.to_string())?;
+
+    if url
+        .host_str()
+        .is_some_and(|host| SANDBOX_HOSTS.contains(&host))
+    {
+        url.set_host(Some(LOCAL_HOST)).map_err(|e| e.to_string())?;
+    }
+
+    Ok(url)
+}
+
+/// ANSI colors used for the function prefixes, picked by the function name.
+const PREFIX_COLORS: [u8; 6] = [36, 35, 33, 32, 34, 31];
+
+/// Prefix that identifies the output of a function, like `[function-name] `.
+pub(crate) fn function_prefix(function_name: &str, color: bool) -> String {
+    if !color {
+        return format!("[{function_name}] ");
+    }
+
+    let index = function_name.bytes().map(|b| b as usize).sum::<usize>() % PREFIX_COLORS.len();
+    format!("\x1b[{}m[{function_name}]\x1b[0m ", PREFIX_COLORS[index])
+}
+
+/// Maximum number of lines that an `OutputTail` keeps.
+const OUTPUT_TAIL_LINES: usize = 20;
+
+/// OutputTail keeps the last lines that a function process wrote,
+/// to explain why the process failed to start.
+#[derive(Clone, Default)]
+pub(crate) struct OutputTail {
+    lines: Arc<std::sync::Mutex<VecDeque<String>>>,
+}
+
+impl OutputTail {
+    pub(crate) fn push(&self, line: &str) {
+        if let Ok(mut lines) = self.lines.lock() {
+            if lines.len() == OUTPUT_TAIL_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(strip_ansi(line));
+        }
+    }
+
+    pub(crate) fn lines(&self) -> Vec<String> {
+        self.lines
+            .lock()
+            .map(|lines| lines.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn clear(&self) {
+        if let Ok(mut lines) = self.lines.lock() {
+            lines.clear();
+        }
+    }
+}
+
+/// Remove the ANSI escape sequences that color a line of output.
+fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            stripped.push(c);
+        } else if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        }
+    }
+    stripped
+}
+
+/// Where the function output goes, besides the emulator's own output.
+#[derive(Clone, Default)]
+pub(crate) struct OutputOptions {
+    /// Prefix added to every line printed in the emulator's output.
+    pub prefix: Option<String>,
+    /// File where every line is written, without the prefix.
+    pub log_file: Option<Arc<std::sync::Mutex<File>>>,
+    /// Caches of the extensions subscribed to function logs.
+    pub caches: Vec<LogsCache>,
+    /// Last lines of the output, kept in memory.
+    pub tail: Option<OutputTail>,
+}
+
+/// Copy the function output to the emulator's output, line by line,
+/// and send every line to the log file and to the extensions subscribed to function logs.
+pub(crate) fn capture_output<R, W>(reader: R, mut output: W, options: OutputOptions)
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    let OutputOptions {
+        prefix,
+        log_file,
+        caches,
+        tail,
+    } = options;
+    let send_records = !caches.is_empty();
+
+    std::thread::spawn(move || {
+        let prefix = prefix.unwrap_or_default();
+
+        for line in BufReader::new(reader).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            let _ = writeln!(output, "{prefix}{line}");
+            if let Some(file) = &log_file {
+                if let Ok(mut file) = file.lock() {
+                    let _ = writeln!(file, "{line}");
+                }
+            }
+            if let Some(tail) = &tail {
+                tail.push(&line);
+            }
+
+            if send_records && tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        while let Some(line) = rx.recv().await {
+            let record = LogRecord::function(&line);
+            for cache in &caches {
+                cache.send(record.clone()).await;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_destination() {
+        let url = local_destination("http://sandbox.localdomain:8080/logs").unwrap();
+        assert_eq!("http://127.0.0.1:8080/logs", url.as_str());
+
+        let url = local_destination("http://sandbox:4243").unwrap();
+        assert_eq!("http://127.0.0.1:4243/", url.as_str());
+
+        let url = local_destination("http://localhost:4243/logs").unwrap();
+        assert_eq!("http://localhost:4243/logs", url.as_str());
+
+        assert!(local_destination("not a url").is_err());
+    }
+
+    #[test]
+    fn test_function_prefix() {
+        assert_eq!("[basic] ", function_prefix("basic", false));
+
+        let prefix = function_prefix("basic", true);
+        assert!(prefix.starts_with("\x1b["));
+        assert!(prefix.ends_with("[basic]\x1b[0m "));
+        assert_eq!(prefix, function_prefix("basic", true));
+    }
+
+    #[test]
+    fn test_output_tail() {
+        let tail = OutputTail::default();
+        for i in 0..OUTPUT_TAIL_LINES + 5 {
+            tail.push(&format!("line {i}"));
+        }
+
+        let lines = tail.lines();
+        assert_eq!(OUTPUT_TAIL_LINES, lines.len());
+        assert_eq!("line 5", lines[0]);
+        assert_eq!(
+            format!("line {}", OUTPUT_TAIL_LINES + 4),
+            lines[lines.len() - 1]
+        );
+
+        tail.clear();
+        assert!(tail.lines().is_empty());
+
+        tail.push("\x1b[1m\x1b[31merror\x1b[0m: could not compile `basic`");
+        assert_eq!(vec!["error: could not compile `basic`"], tail.lines());
+    }
+
+    #[test]
+    fn test_log_record_category() {
+        assert_eq!("function", LogRecord::function("hello").category());
+        assert_eq!(
+            "platform",
+            LogRecord::new("platform.start", Value::Null).category()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_only_to_subscribed_types() {
+        let logs = LogsCache::default();
+        let (tx, mut rx) = mpsc::channel(10);
+        logs.subscribers.lock().await.insert(
+            "extension-id".into(),
+            Subscriber {
+                types: HashSet::from(["platform".to_string()]),
+                tx,
+            },
+        );
+
+        logs.send(LogRecord::function("hello")).await;
+        logs.send(LogRecord::new("platform.start", Value::Null))
+            .await;
+
+        let record = rx.recv().await.unwrap();
+        assert_eq!("platform.start", record.kind);
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use bytes::Bytes;
+use std::{fmt, path::Path, process::Stdio, time::Duration};
+use tokio::{io::AsyncWriteExt, process::Command};
+use tracing::{debug, error};
+
+/// How long the middleware has to process a payload before the emulator uses the original one.
+const MIDDLEWARE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Stage of the invocation that the middleware processes,
+/// it's sent to the middleware as its first argument.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Stage {
+    Request,
+    Response,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Request => write!(f, "request"),
+            Stage::Response => write!(f, "response"),
+        }
+    }
+}
+
+/// Send a payload to the middleware through its stdin, and return the payload that it writes in its stdout.
+/// The original payload is returned if the middleware doesn't write anything, or if it fails,
+/// so a broken middleware never blocks the invocations.
+pub(crate) async fn apply(
+    middleware: &Path,
+    stage: Stage,
+    function_name: &str,
+    req_id: &str,
+    payload: Bytes,
+) -> Bytes {
+    let result = tokio::time::timeout(
+        MIDDLEWARE_TIMEOUT,
+        run(middleware, stage, function_name, req_id, &payload),
+    )
+    .await;
+
+    match result {
+        Ok(Ok(output)) if output.is_empty() => payload,
+        Ok(Ok(output)) => {
+            debug!(?middleware, %stage, function = ?function_name, "payload modified by middleware");
+            output
+        }
+        Ok(Err(reason)) => {
+            error!(?middleware, %stage, function = ?function_name, %reason, "middleware failed, using the original payload");
+            payload
+        }
+        Err(_) => {
+            error!(?middleware, %stage, function = ?function_name, timeout = ?MIDDLEWARE_TIMEOUT, "middleware timed out, using the original payload");
+            payload
+        }
+    }
+}
+
+async fn run(
+    middleware: &Path,
+    stage: Stage,
+    function_name: &str,
+    req_id: &str,
+    payload: &[u8],
+) -> Result<Bytes, String> {
+    let mut child = Command::new(middleware)
+        .arg(stage.to_string())
+        .env("CARGO_LAMBDA_FUNCTION_NAME", function_name)
+        .env("CARGO_LAMBDA_REQUEST_ID", req_id)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    // The payload is written while the output is read, so a middleware that streams
+    // its output doesn't block on a full stdout pipe while the emulator writes stdin.
+    let stdin = child.stdin.take();
+    let write = async move {
+        let Some(mut stdin) = stdin else {
+            return Ok(());
+        };
+        match stdin.write_all(payload).await {
+            // The middleware can exit without reading the whole payload.
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+            result => result,
+        }
+    };
+
+    let (written, output) = tokio::join!(write, child.wait_with_output());
+    written.map_err(|e| e.to_string())?;
+    let output = output.map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("the middleware exited with {}", output.status));
+    }
+
+    Ok(Bytes::from(output.stdout))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_apply_middleware() {
+        let dir = tempfile::tempdir().unwrap();
+        let middleware = dir.path().join("middleware.sh");
+        std::fs::write(
+            &middleware,
+            "#!/bin/sh\nif [ \"$1\" = \"request\" ]; then echo '{\"modified\":true}'; else exit 1; fi\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(
+            &middleware,
+            std::os::unix::fs::PermissionsExt::from_mode(0o755),
+        )
+        .unwrap();
+
+        let payload = Bytes::from_static(b"{}");
+        let output = apply(&middleware, Stage::Request, "basic", "1", payload.clone()).await;
+        assert_eq!("{\"modified\":true}\n", output);
+
+        let output = apply(&middleware, Stage::Response, "basic", "1", payload.clone()).await;
+        assert_eq!(payload, output);
+    }
+
+    #[tokio::test]
+    async fn test_apply_middleware_with_large_payload() {
+        let dir = tempfile::tempdir().unwrap();
+        let middleware = dir.path().join("middleware.sh");
+        std::fs::write(
+            &middleware,
+            "#!/bin/sh
+exec tr a b\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(
+            &middleware,
+            std::os::unix::fs::PermissionsExt::from_mode(0o755),
+        )
+        .unwrap();
+
+        // Larger than the pipe buffers, so the middleware writes its output before it reads all the input.
+        let payload = Bytes::from(vec![b'a'; 1024 * 1024]);
+        let output = apply(&middleware, Stage::Request, "basic", "1", payload).await;
+        assert_eq!(Bytes::from(vec![b'b'; 1024 * 1024]), output);
+    }
+}
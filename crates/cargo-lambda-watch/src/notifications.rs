@@ -0,0 +1,159 @@
+use axum::response::sse::Event;
+use futures_util::{stream, Stream};
+use serde::Serialize;
+use std::{convert::Infallible, sync::Arc};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Number of notifications kept for subscribers that fall behind.
+const NOTIFICATIONS_CAPACITY: usize = 128;
+
+/// Notification describes a change in the state of a function,
+/// so editor plugins and live-reload tools can react to it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub(crate) enum Notification {
+    /// The function is compiling, or starting, after a change in the code.
+    Building { function_name: String },
+    /// The function started and is waiting for invocations.
+    Ready { function_name: String },
+    /// The function received an invocation.
+    Invocation {
+        function_name: String,
+        request_id: String,
+    },
+    /// The function failed to start, crashed, or returned an error.
+    Error {
+        function_name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+        message: String,
+    },
+}
+
+impl Notification {
+    fn kind(&self) -> &'static str {
+        match self {
+            Notification::Building { .. } => "building",
+            Notification::Ready { .. } => "ready",
+            Notification::Invocation { .. } => "invocation",
+            Notification::Error { .. } => "error",
+        }
+    }
+
+    /// Server-sent event with the notification type as the event name.
+    fn event(&self) -> Event {
+        let data = serde_json::to_string(self).unwrap_or_default();
+        Event::default().event(self.kind()).data(data)
+    }
+}
+
+/// NotificationCache broadcasts the notifications to every client
+/// connected to the `/__lambda/events` endpoint.
+#[derive(Clone)]
+pub(crate) struct NotificationCache {
+    tx: Arc<broadcast::Sender<Notification>>,
+}
+
+impl Default for NotificationCache {
+    fn default() -> Self {
+        NotificationCache {
+            tx: Arc::new(broadcast::channel(NOTIFICATIONS_CAPACITY).0),
+        }
+    }
+}
+
+impl NotificationCache {
+    /// Send a notification to the connected clients, if there are any.
+    fn send(&self, notification: Notification) {
+        let _ = self.tx.send(notification);
+    }
+
+    pub(crate) fn building(&self, function_name: &str) {
+        self.send(Notification::Building {
+            function_name: function_name.into(),
+        });
+    }
+
+    pub(crate) fn ready(&self, function_name: &str) {
+        self.send(Notification::Ready {
+            function_name: function_name.into(),
+        });
+    }
+
+    pub(crate) fn invocation(&self, function_name: &str, request_id: &str) {
+        self.send(Notification::Invocation {
+            function_name: function_name.into(),
+            request_id: request_id.into(),
+        });
+    }
+
+    pub(crate) fn error(&self, function_name: &str, request_id: Option<&str>, message: &str) {
+        self.send(Notification::Error {
+            function_name: function_name.into(),
+            request_id: request_id.map(Into::into),
+            message: message.into(),
+        });
+    }
+
+    /// Stream of server-sent events with the notifications sent after subscribing.
+    /// Clients that fall behind skip the notifications that they missed.
+    pub(crate) fn events(&self) -> impl Stream<Item = Result<Event, Infallible>> {
+        stream::unfold(self.tx.subscribe(), |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(notification) => return Some((Ok(notification.event()), rx)),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    #[test]
+    fn test_notification_serialization() {
+        let notification = Notification::Invocation {
+            function_name: "basic-lambda".into(),
+            request_id: "123".into(),
+        };
+        assert_eq!(
+            serde_json::json!({"type": "invocation", "function_name": "basic-lambda", "request_id": "123"}),
+            serde_json::to_value(&notification).unwrap()
+        );
+
+        let notification = Notification::Error {
+            function_name: "basic-lambda".into(),
+            request_id: None,
+            message: "function failed to initialize".into(),
+        };
+        assert_eq!(
+            serde_json::json!({"type": "error", "function_name": "basic-lambda", "message": "function failed to initialize"}),
+            serde_json::to_value(&notification).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_notification_cache() {
+        let cache = NotificationCache::default();
+        // Notifications without subscribers are dropped.
+        cache.building("basic-lambda");
+
+        let mut rx = cache.tx.subscribe();
+        let events = cache.events();
+        tokio::pin!(events);
+
+        cache.ready("basic-lambda");
+        assert_eq!(
+            Notification::Ready {
+                function_name: "basic-lambda".into()
+            },
+            rx.recv().await.unwrap()
+        );
+        assert!(events.next().await.is_some());
+    }
+}
@@ -0,0 +1,120 @@
+use crate::error::ServerError;
+use cargo_lambda_metadata::cargo::watch::DEFAULT_INVOKE_PORT;
+use std::{
+    io::ErrorKind,
+    net::{IpAddr, SocketAddr, TcpListener},
+    process::Command,
+};
+use tracing::warn;
+
+/// How many ports after the default one the emulator tries when the default port is in use.
+const FALLBACK_ATTEMPTS: u16 = 10;
+
+/// Find the port where the emulator listens for invoke requests.
+/// When the default port is in use, the emulator picks the next free port.
+/// Ports selected by the user are never replaced, the emulator fails with an error instead.
+/// Secure connections use the port after the invoke port for the runtime server, so both must be free.
+pub(crate) fn invoke_port(ip: IpAddr, port: u16, secure: bool) -> Result<u16, ServerError> {
+    let ports_needed = if secure { 2 } else { 1 };
+
+    let conflict = match first_port_in_use(ip, port, ports_needed) {
+        None => return Ok(port),
+        Some(conflict) => conflict,
+    };
+
+    if port == DEFAULT_INVOKE_PORT {
+        let candidates = (1..=FALLBACK_ATTEMPTS).filter_map(|i| port.checked_add(i * ports_needed));
+        for candidate in candidates {
+            if first_port_in_use(ip, candidate, ports_needed).is_none() {
+                warn!(
+                    port = conflict,
+                    "the port is already in use, the emulator is listening on port {candidate} instead"
+                );
+                warn!(
+                    "use `cargo lambda invoke --invoke-port {candidate}` to send invoke requests"
+                );
+                return Ok(candidate);
+            }
+        }
+    }
+
+    Err(ServerError::PortInUse(
+        conflict,
+        process_listening(conflict),
+    ))
+}
+
+/// Return the first port in the range that's already in use.
+fn first_port_in_use(ip: IpAddr, port: u16, count: u16) -> Option<u16> {
+    (0..count)
+        .filter_map(|i| port.checked_add(i))
+        .find(|p| is_port_in_use(ip, *p))
+}
+
+fn is_port_in_use(ip: IpAddr, port: u16) -> bool {
+    match TcpListener::bind(SocketAddr::from((ip, port))) {
+        Ok(_) => false,
+        Err(err) => err.kind() == ErrorKind::AddrInUse,
+    }
+}
+
+/// Description of the process listening on a port, like `node (pid 1234)`.
+/// This uses `lsof`, so it's only available on systems where it's installed.
+fn process_listening(port: u16) -> Option<String> {
+    let output = Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{port}"), "-sTCP:LISTEN", "-Fcp"])
+        .output()
+        .ok()?;
+
+    parse_lsof_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse the output of `lsof -F cp`, where each line starts with the field name.
+fn parse_lsof_output(output: &str) -> Option<String> {
+    let mut pid = None;
+    let mut command = None;
+
+    for line in output.lines() {
+        if let Some(value) = line.strip_prefix('p') {
+            pid = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix('c') {
+            command = Some(value.to_string());
+        }
+
+        if let (Some(pid), Some(command)) = (&pid, &command) {
+            return Some(format!("{command} (pid {pid})"));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_parse_lsof_output() {
+        assert_eq!(
+            Some("node (pid 1234)".to_string()),
+            parse_lsof_output("p1234\ncnode\n")
+        );
+        assert_eq!(None, parse_lsof_output(""));
+    }
+
+    #[test]
+    fn test_invoke_port_in_use() {
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let listener = TcpListener::bind(SocketAddr::from((ip, 0))).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        assert!(matches!(
+            invoke_port(ip, port, false),
+            Err(ServerError::PortInUse(p, _)) if p == port
+        ));
+
+        drop(listener);
+        assert_eq!(port, invoke_port(ip, port, false).unwrap());
+    }
+}
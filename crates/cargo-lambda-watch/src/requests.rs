@@ -16,7 +16,82 @@ pub type LambdaResponse = Request<Body>;
 #[derive(Debug)]
 pub enum Action {
     Invoke(InvokeRequest),
-    Init,
+    /// Start a function before it receives any invocation.
+    Init(String),
+}
+
+/// ColdStart indicates whether an invocation was the first one
+/// processed by a new function process.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ColdStart(pub bool);
+
+/// FunctionReloads reports how many times a function
+/// has been (re)loaded since the emulator started.
+#[derive(Debug, Serialize)]
+pub struct FunctionReloads {
+    pub function_name: String,
+    pub reloads: u64,
+}
+
+/// HealthReport describes the state of the scheduler and the function processes.
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    /// Whether the scheduler is running, and none of the functions is compiling or crashed.
+    pub ready: bool,
+    pub scheduler: &'static str,
+    pub functions: Vec<FunctionHealth>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FunctionHealth {
+    pub function_name: String,
+    pub status: ProcessStatus,
+    pub reloads: u64,
+}
+
+/// State of a function process.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessStatus {
+    #[default]
+    NotStarted,
+    Compiling,
+    Running,
+    Crashed,
+}
+
+/// InitFailure describes a function process that exited before it requested its first invocation,
+/// usually because of a panic in `main`, or an invalid environment.
+#[derive(Clone, Debug)]
+pub struct InitFailure {
+    /// Number of consecutive times that the function failed to start.
+    pub failures: u32,
+    /// Last lines that the function wrote to stderr.
+    pub stderr: Vec<String>,
+}
+
+impl InitFailure {
+    /// Respond to an invocation with the error that the function failed to initialize,
+    /// in the same format that Lambda uses for runtime errors.
+    pub fn respond(&self, req: InvokeRequest) {
+        let mut message = "function failed to initialize".to_string();
+        if !self.stderr.is_empty() {
+            message.push_str(", stderr:\n");
+            message.push_str(&self.stderr.join("\n"));
+        }
+
+        let body = serde_json::json!({
+            "errorType": "Runtime.ExitError",
+            "errorMessage": message,
+        });
+
+        let mut resp = Request::new(Body::from(body.to_string()));
+        resp.extensions_mut()
+            .insert(StatusCode::INTERNAL_SERVER_ERROR);
+
+        // The caller might have stopped waiting for the response.
+        let _ = req.resp_tx.send(resp);
+    }
 }
 
 #[derive(Debug)]
@@ -26,6 +101,18 @@ pub struct InvokeRequest {
     pub resp_tx: Sender<LambdaResponse>,
 }
 
+impl InvokeRequest {
+    /// Respond to an invocation that was rejected because the function's queue is full,
+    /// like Lambda throttles invocations when the function reaches its concurrency limit.
+    pub fn throttle(self) {
+        let mut resp = Request::new(Body::empty());
+        resp.extensions_mut().insert(StatusCode::TOO_MANY_REQUESTS);
+
+        // The caller might have stopped waiting for the response.
+        let _ = self.resp_tx.send(resp);
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct StreamingPrelude {
     #[serde(deserialize_with = "http_serde::status_code::deserialize")]
@@ -123,15 +210,25 @@ impl NextEvent {
     }
 }
 
-#[allow(dead_code)]
 #[derive(Clone, Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub(crate) struct LogBuffering {
     pub timeout_ms: usize,
     pub max_bytes: usize,
     pub max_items: usize,
 }
 
+impl Default for LogBuffering {
+    /// Default buffering configuration for the Logs and Telemetry APIs.
+    fn default() -> Self {
+        LogBuffering {
+            timeout_ms: 1_000,
+            max_bytes: 262_144,
+            max_items: 10_000,
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Clone, Debug, Deserialize)]
 pub(crate) struct EventsDestination {
@@ -4,7 +4,7 @@ use http_body_util::BodyExt;
 use hyper::HeaderMap;
 use serde::{de::DeserializeOwned, Serialize};
 use tokio::sync::mpsc;
-use tracing::{debug, warn};
+use tracing::debug;
 
 const EXTENSION_ID_HEADER: &str = "Lambda-Extension-Identifier";
 
@@ -75,8 +75,24 @@ pub(crate) async fn next_extension_event(
     }
 }
 
-pub(crate) async fn subcribe_extension_events(
-    State(_state): State<RefRuntimeState>,
+pub(crate) async fn subscribe_logs(
+    State(state): State<RefRuntimeState>,
+    req: Request<Body>,
+) -> Result<Response<Body>, ServerError> {
+    let extension_id = match req.headers().get(EXTENSION_ID_HEADER) {
+        None => Err(ServerError::MissingExtensionIdHeader)?,
+        Some(id) => id.to_str().unwrap().to_string(),
+    };
+    let payload: SubcribeEvent = extract_json(req).await?;
+
+    debug!(%extension_id, ?payload.types, destination = ?payload.destination.uri, "subscribing extension to logs");
+    state.logs.subscribe(&extension_id, payload).await;
+
+    Ok(Response::new(Body::empty()))
+}
+
+pub(crate) async fn subscribe_telemetry(
+    State(state): State<RefRuntimeState>,
     req: Request<Body>,
 ) -> Result<Response<Body>, ServerError> {
     let extension_id = match req.headers().get(EXTENSION_ID_HEADER) {
@@ -85,8 +101,12 @@ pub(crate) async fn subcribe_extension_events(
     };
     let payload: SubcribeEvent = extract_json(req).await?;
 
-    debug!(%extension_id, ?payload.types, "received events subscription request");
-    warn!(%extension_id, ?payload.types, "!!! Events subcription is not supported at the moment !!!");
+    debug!(%extension_id, ?payload.types, destination = ?payload.destination.uri, "subscribing extension to telemetry");
+    state
+        .telemetry
+        .events
+        .subscribe(&extension_id, payload)
+        .await;
 
     Ok(Response::new(Body::empty()))
 }
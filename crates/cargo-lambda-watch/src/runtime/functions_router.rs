@@ -1,8 +1,10 @@
 use crate::{
     error::ServerError,
+    middleware::{self, Stage},
     requests::*,
     runtime::LAMBDA_RUNTIME_XRAY_TRACE_HEADER,
-    state::{RequestCache, ResponseCache},
+    state::RequestCache,
+    telemetry::InvocationStatus,
     RefRuntimeState,
 };
 use axum::{
@@ -13,7 +15,8 @@ use axum::{
 };
 use base64::{engine::general_purpose as b64, Engine as _};
 use cargo_lambda_metadata::DEFAULT_PACKAGE_FUNCTION;
-use http::request::Parts;
+use http::{request::Parts, HeaderMap, HeaderName, HeaderValue};
+use http_body_util::BodyExt;
 use tracing::debug;
 
 use super::LAMBDA_RUNTIME_AWS_REQUEST_ID;
@@ -23,6 +26,16 @@ pub(crate) const LAMBDA_RUNTIME_COGNITO_IDENTITY: &str = "lambda-runtime-cognito
 pub(crate) const LAMBDA_RUNTIME_DEADLINE_MS: &str = "lambda-runtime-deadline-ms";
 pub(crate) const LAMBDA_RUNTIME_FUNCTION_ARN: &str = "lambda-runtime-invoked-function-arn";
 
+const LAMBDA_RUNTIME_HEADER_PREFIX: &str = "lambda-runtime-";
+const RESERVED_RUNTIME_HEADERS: [&str; 6] = [
+    LAMBDA_RUNTIME_AWS_REQUEST_ID,
+    LAMBDA_RUNTIME_CLIENT_CONTEXT,
+    LAMBDA_RUNTIME_COGNITO_IDENTITY,
+    LAMBDA_RUNTIME_DEADLINE_MS,
+    LAMBDA_RUNTIME_FUNCTION_ARN,
+    LAMBDA_RUNTIME_XRAY_TRACE_HEADER,
+];
+
 pub(crate) async fn next_request(
     State(state): State<RefRuntimeState>,
     Path(function_name): Path<String>,
@@ -49,11 +62,28 @@ pub(crate) async fn process_next_request(
         function_name
     };
 
+    // Don't send new invocations to a process that's going to be replaced.
+    state.drains.wait_for_resume(function_name).await;
+
     let req_id = parts
         .headers
         .get(LAMBDA_RUNTIME_AWS_REQUEST_ID)
         .expect("missing request id");
 
+    // The first request for the next event after the process starts
+    // means that the function compiled and started successfully.
+    state.reloads.ready(function_name).await;
+    if state.processes.get(function_name).await != ProcessStatus::Running {
+        state.notifications.ready(function_name);
+    }
+    state
+        .processes
+        .set(function_name, ProcessStatus::Running)
+        .await;
+    state.crashes.recovered(function_name).await;
+    state.lifecycle.waiting_next(function_name).await;
+    state.telemetry.init_done(function_name).await;
+
     let mut builder = Response::builder()
         .header(LAMBDA_RUNTIME_AWS_REQUEST_ID, req_id)
         .header(LAMBDA_RUNTIME_DEADLINE_MS, 600_000_u32)
@@ -67,13 +97,33 @@ pub(crate) async fn process_next_request(
                 .map_err(ServerError::InvalidRequestIdHeader)?;
 
             debug!(req_id = ?req_id, function = ?function_name, "processing request");
+            state.notifications.invocation(function_name, req_id);
+            state.lifecycle.invoke_started(function_name).await;
+            state.telemetry.invoke_started(req_id).await;
+            state.drains.invoke_started(function_name, req_id).await;
             let next_event = NextEvent::invoke(req_id, &invoke);
             state.ext_cache.send_event(next_event).await?;
 
             let (parts, body) = invoke.req.into_parts();
+            let body = body
+                .collect()
+                .await
+                .map_err(ServerError::DataDeserialization)?
+                .to_bytes();
+            let body = match &state.middleware {
+                Some(path) => {
+                    middleware::apply(path, Stage::Request, function_name, req_id, body).await
+                }
+                None => body,
+            };
+            state
+                .history
+                .started(req_id, function_name, &parts.headers, body.clone())
+                .await;
 
             let resp_tx = invoke.resp_tx;
-            state.res_cache.push(req_id, resp_tx).await;
+            let cold_start = state.cold_starts.warm_up(function_name).await;
+            state.res_cache.push(req_id, resp_tx, cold_start).await;
 
             let headers = parts.headers;
             if let Some(h) = headers.get(LAMBDA_RUNTIME_CLIENT_CONTEXT) {
@@ -90,20 +140,34 @@ pub(crate) async fn process_next_request(
             if let Some(h) = headers.get(LAMBDA_RUNTIME_XRAY_TRACE_HEADER) {
                 builder = builder.header(LAMBDA_RUNTIME_XRAY_TRACE_HEADER, h);
             }
+            for (name, value) in custom_runtime_headers(&headers) {
+                builder = builder.header(name, value);
+            }
 
-            builder.status(StatusCode::OK).body(body)
+            builder.status(StatusCode::OK).body(Body::from(body))
         }
     };
 
     resp.map_err(ServerError::ResponseBuild)
 }
 
+/// Custom `lambda-runtime-` headers sent by the client, like `cargo lambda invoke --runtime-header`.
+/// Headers that the runtime emulator already manages are never overwritten.
+fn custom_runtime_headers(
+    headers: &HeaderMap,
+) -> impl Iterator<Item = (&HeaderName, &HeaderValue)> {
+    headers.iter().filter(|(name, _)| {
+        let name = name.as_str();
+        name.starts_with(LAMBDA_RUNTIME_HEADER_PREFIX) && !RESERVED_RUNTIME_HEADERS.contains(&name)
+    })
+}
+
 pub(crate) async fn next_invocation_response(
     State(state): State<RefRuntimeState>,
     Path((_function_name, req_id)): Path<(String, String)>,
     req: Request<Body>,
 ) -> Result<Response<Body>, ServerError> {
-    respond_to_next_invocation(&state.res_cache, &req_id, req, StatusCode::OK).await
+    respond_to_next_invocation(&state, &req_id, req, StatusCode::OK).await
 }
 
 pub(crate) async fn bare_next_invocation_response(
@@ -111,7 +175,7 @@ pub(crate) async fn bare_next_invocation_response(
     Path(req_id): Path<String>,
     req: Request<Body>,
 ) -> Result<Response<Body>, ServerError> {
-    respond_to_next_invocation(&state.res_cache, &req_id, req, StatusCode::OK).await
+    respond_to_next_invocation(&state, &req_id, req, StatusCode::OK).await
 }
 
 pub(crate) async fn next_invocation_error(
@@ -119,13 +183,7 @@ pub(crate) async fn next_invocation_error(
     Path((_function_name, req_id)): Path<(String, String)>,
     req: Request<Body>,
 ) -> Result<Response<Body>, ServerError> {
-    respond_to_next_invocation(
-        &state.res_cache,
-        &req_id,
-        req,
-        StatusCode::INTERNAL_SERVER_ERROR,
-    )
-    .await
+    respond_to_next_invocation(&state, &req_id, req, StatusCode::INTERNAL_SERVER_ERROR).await
 }
 
 pub(crate) async fn bare_next_invocation_error(
@@ -133,23 +191,63 @@ pub(crate) async fn bare_next_invocation_error(
     Path(req_id): Path<String>,
     req: Request<Body>,
 ) -> Result<Response<Body>, ServerError> {
-    respond_to_next_invocation(
-        &state.res_cache,
-        &req_id,
-        req,
-        StatusCode::INTERNAL_SERVER_ERROR,
-    )
-    .await
+    respond_to_next_invocation(&state, &req_id, req, StatusCode::INTERNAL_SERVER_ERROR).await
 }
 
 async fn respond_to_next_invocation(
-    cache: &ResponseCache,
+    state: &RefRuntimeState,
     req_id: &str,
     mut req: Request<Body>,
     response_status: StatusCode,
 ) -> Result<Response<Body>, ServerError> {
-    if let Some(resp_tx) = cache.pop(req_id).await {
+    let status = if response_status.is_success() {
+        InvocationStatus::Success
+    } else {
+        InvocationStatus::Error
+    };
+    state.telemetry.invoke_done(req_id, status).await;
+    state.drains.invoke_done(req_id).await;
+
+    let function_name = state
+        .history
+        .function_name(req_id)
+        .await
+        .unwrap_or_default();
+    if !response_status.is_success() {
+        state
+            .notifications
+            .error(&function_name, Some(req_id), "function returned an error");
+    }
+
+    // Streaming responses are sent to the client as they arrive, they are not recorded in the history.
+    if is_streaming_response(req.headers()) {
+        state
+            .history
+            .completed(req_id, status, req.headers(), None)
+            .await;
+    } else {
+        let (parts, body) = req.into_parts();
+        let body = body
+            .collect()
+            .await
+            .map_err(ServerError::DataDeserialization)?
+            .to_bytes();
+        let body = match &state.middleware {
+            Some(path) => {
+                middleware::apply(path, Stage::Response, &function_name, req_id, body).await
+            }
+            None => body,
+        };
+        state
+            .history
+            .completed(req_id, status, &parts.headers, Some(body.clone()))
+            .await;
+        req = Request::from_parts(parts, Body::from(body));
+    }
+
+    if let Some((resp_tx, cold_start)) = state.res_cache.pop(req_id).await {
         req.extensions_mut().insert(response_status);
+        req.extensions_mut().insert(cold_start);
 
         resp_tx
             .send(req)
@@ -159,6 +257,12 @@ async fn respond_to_next_invocation(
     Ok(Response::new(Body::empty()))
 }
 
+fn is_streaming_response(headers: &HeaderMap) -> bool {
+    headers
+        .get("lambda-runtime-function-response-mode")
+        .is_some_and(|v| v == "streaming")
+}
+
 pub(crate) async fn init_error(
     State(state): State<RefRuntimeState>,
     Path(_function_name): Path<String>,
@@ -20,8 +20,8 @@ pub(crate) fn routes() -> Router<RefRuntimeState> {
             "/2020-01-01/extension/event/next",
             get(next_extension_event),
         )
-        .route("/2020-08-15/logs", put(subcribe_extension_events))
-        .route("/2022-07-01/telemetry", put(subcribe_extension_events))
+        .route("/2020-08-15/logs", put(subscribe_logs))
+        .route("/2022-07-01/telemetry", put(subscribe_telemetry))
         .route(
             "/:function_name/2018-06-01/runtime/invocation/next",
             get(next_request),
@@ -1,16 +1,32 @@
 use crate::{
     error::ServerError,
-    requests::{Action, NextEvent},
-    state::{ExtensionCache, RuntimeState},
+    extensions::{run_extensions, EXTENSIONS_INIT_TIMEOUT},
+    requests::{Action, NextEvent, ProcessStatus},
+    state::RuntimeState,
     watcher::WatcherConfig,
 };
 use cargo_lambda_metadata::DEFAULT_PACKAGE_FUNCTION;
 use cargo_options::Run as CargoOptions;
-use tokio::sync::mpsc::{self, Receiver, Sender};
+use futures_util::{
+    future::{self, BoxFuture, Shared},
+    FutureExt,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::{
+    mpsc::{self, Receiver, Sender},
+    Semaphore,
+};
 use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use watchexec::command::Command;
 
+/// Future that completes when the external extensions registered, or when they didn't register on time.
+type ExtensionsReady = Shared<BoxFuture<'static, ()>>;
+
 pub(crate) async fn init_scheduler(
     subsys: &SubsystemHandle,
     state: RuntimeState,
@@ -35,28 +51,48 @@ async fn start_scheduler(
 ) -> Result<(), ServerError> {
     let (gc_tx, mut gc_rx) = mpsc::channel::<String>(10);
 
+    // Invocations are queued while the extensions register, the functions wait for them before starting.
+    let extensions_ready =
+        if watcher_config.start_function() && !watcher_config.extensions.is_empty() {
+            start_extensions(&subsys, &state, &watcher_config)
+        } else {
+            future::ready(()).boxed().shared()
+        };
+
     loop {
         tokio::select! {
             Some(action) = req_rx.recv() => {
                 tracing::trace!(?action, "request action received");
                 let start_function_name = match action {
                     Action::Invoke(req) => {
-                        state.req_cache.upsert(req).await?
+                        if let Some(failure) = state.crashes.backoff(&req.function_name).await {
+                            // Don't start the function in a loop, fail fast with the last error.
+                            failure.respond(req);
+                            None
+                        } else {
+                            let function_name = req.function_name.clone();
+                            state.lifecycle.invoke_scheduled(&function_name).await;
+                            let start = state.req_cache.upsert(req).await?;
+                            if start.is_none() && state.crashes.restart(&function_name).await {
+                                info!(function = ?function_name, "starting function again after it failed to initialize");
+                            }
+                            start
+                        }
                     },
-                    Action::Init => {
-                        state.req_cache.init(DEFAULT_PACKAGE_FUNCTION).await;
-                        Some(DEFAULT_PACKAGE_FUNCTION.into())
+                    Action::Init(name) => {
+                        state.req_cache.init(&name).await;
+                        Some(name)
                     },
                 };
 
                 if watcher_config.start_function() {
                     if let Some(name) = start_function_name {
-                        let runtime_api = state.function_addr(&name);
                         let gc_tx = gc_tx.clone();
                         let cargo_options = cargo_options.clone();
                         let watcher_config = watcher_config.clone();
-                        let ext_cache = state.ext_cache.clone();
-                        subsys.start(SubsystemBuilder::new("lambda runtime", move |s| start_function(s, name, runtime_api, cargo_options, watcher_config, gc_tx, ext_cache)));
+                        let state = state.clone();
+                        let extensions_ready = extensions_ready.clone();
+                        subsys.start(SubsystemBuilder::new("lambda runtime", move |s| start_function(s, name, state, cargo_options, watcher_config, gc_tx, extensions_ready)));
                     }
                 }
             }
@@ -71,47 +107,245 @@ async fn start_scheduler(
     }
 }
 
+/// Maximum time that `--init-all` waits for a function to start
+/// before it lets the next function compile.
+const INIT_ALL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Start every function in the project when the emulator starts, with `--init-all`.
+/// At most `concurrency` functions are compiled at the same time,
+/// the next function starts compiling when a previous one is running, or it crashed.
+pub(crate) fn init_functions(state: RuntimeState, req_tx: Sender<Action>, concurrency: usize) {
+    let mut names = if state.is_default_function_enabled() {
+        vec![DEFAULT_PACKAGE_FUNCTION.to_string()]
+    } else {
+        state.initial_functions.iter().cloned().collect::<Vec<_>>()
+    };
+    names.sort();
+
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+
+    tokio::spawn(async move {
+        for name in names {
+            let Ok(permit) = semaphore.clone().acquire_owned().await else {
+                return;
+            };
+
+            // The function can still be lazy loaded later if there is any error sending the action.
+            if req_tx.send(Action::Init(name.clone())).await.is_err() {
+                return;
+            }
+
+            let state = state.clone();
+            tokio::spawn(async move {
+                if tokio::time::timeout(INIT_ALL_TIMEOUT, wait_for_start(&state, &name))
+                    .await
+                    .is_err()
+                {
+                    warn!(function = ?name, timeout = ?INIT_ALL_TIMEOUT, "function didn't start on time, starting the next function");
+                }
+                drop(permit);
+            });
+        }
+    });
+}
+
+/// Wait until a function process is running, or it stopped after compiling.
+async fn wait_for_start(state: &RuntimeState, name: &str) {
+    let mut compiling = false;
+    loop {
+        match state.processes.get(name).await {
+            ProcessStatus::Running | ProcessStatus::Crashed => return,
+            ProcessStatus::Compiling => compiling = true,
+            ProcessStatus::NotStarted if compiling => return,
+            ProcessStatus::NotStarted => {}
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+/// Start the external extensions before any function,
+/// so they register and subscribe to events before the function's init phase, like in Lambda.
+fn start_extensions(
+    subsys: &SubsystemHandle,
+    state: &RuntimeState,
+    watcher_config: &WatcherConfig,
+) -> ExtensionsReady {
+    let extensions = watcher_config.extensions.clone();
+    let expected = extensions.len();
+    let env = watcher_config.env.clone();
+    let (_, _, runtime_api) = state.addresses();
+    let ext_cache = state.ext_cache.clone();
+
+    subsys.start(SubsystemBuilder::new("lambda extensions", move |s| {
+        run_extensions(s, extensions, env, runtime_api, ext_cache)
+    }));
+
+    // The timeout starts with the extensions, not when the first function waits for them.
+    let ext_cache = state.ext_cache.clone();
+    tokio::spawn(async move {
+        if !ext_cache
+            .wait_for_registrations(expected, EXTENSIONS_INIT_TIMEOUT)
+            .await
+        {
+            warn!(timeout = ?EXTENSIONS_INIT_TIMEOUT, "not all extensions registered on time, starting the function anyway");
+        }
+    })
+    .map(|_| ())
+    .boxed()
+    .shared()
+}
+
 async fn start_function(
     subsys: SubsystemHandle,
     name: String,
-    runtime_api: String,
+    state: RuntimeState,
     cargo_options: CargoOptions,
     mut watcher_config: WatcherConfig,
     gc_tx: Sender<String>,
-    ext_cache: ExtensionCache,
+    extensions_ready: ExtensionsReady,
 ) -> Result<(), ServerError> {
-    let cmd = cargo_command(&name, &cargo_options)?;
-    info!(function = ?name, manifest = ?cargo_options.manifest_path, ?cmd, "starting lambda function");
+    tokio::select! {
+        _ = extensions_ready => {}
+        _ = subsys.on_shutdown_requested() => return Ok(()),
+    }
 
-    watcher_config.bin_name = if is_valid_bin_name(&name) {
+    let mut cmd = cargo_command(&name, &cargo_options)?;
+    if let Some(debug) = &watcher_config.debug {
+        cmd = debug.wrap_command(cmd, &cargo_options);
+    }
+    // The default function uses the only binary in the package, so it loads its configuration too.
+    let bin_name = if is_valid_bin_name(&name) {
         Some(name.clone())
     } else {
-        None
+        default_bin_name(&state)
     };
+    if let Some(dir) = &watcher_config.artifact_dir {
+        let artifact = artifact_path(dir, bin_name.as_deref())?;
+        cmd = Command::Exec {
+            prog: artifact.to_string_lossy().to_string(),
+            args: cargo_options.args.clone(),
+        };
+        watcher_config.artifact = Some(artifact);
+    }
+    if let Some(runner) = &watcher_config.runner {
+        cmd = runner_command(runner, bin_name.as_deref().unwrap_or_default())?;
+    }
+    info!(function = ?name, manifest = ?cargo_options.manifest_path, ?cmd, "starting lambda function");
+
+    watcher_config.bin_name = bin_name;
     watcher_config.name.clone_from(&name);
-    watcher_config.runtime_api = runtime_api;
+    if let Some(debug) = &watcher_config.debug {
+        debug.print_instructions(&name, watcher_config.bin_name.as_ref());
+    }
+    watcher_config.runtime_api = state.function_addr(&name);
+    if state.lifecycle.is_enabled()
+        && watcher_config.artifact.is_none()
+        && watcher_config.runner.is_none()
+    {
+        watcher_config.build_command = Some(cargo_build_command(&name, &cargo_options)?);
+    }
 
-    let wx = crate::watcher::new(cmd, watcher_config, ext_cache.clone()).await?;
+    let ext_cache = state.ext_cache.clone();
+    let lifecycle = state.lifecycle.clone();
+    let processes = state.processes.clone();
+    let state_crashes = state.crashes.clone();
+    let mut aws_env_refreshes = state.aws_env.subscribe();
+    let wx = crate::watcher::new(cmd, watcher_config, state).await?;
+    let mut main = wx.main();
 
-    tokio::select! {
-        res = wx.main() => match res {
-            Ok(_) => {},
-            Err(error) => {
-                error!(?error, "failed to obtain the watchexec task");
-                if let Err(error) = gc_tx.send(name.clone()).await {
-                    error!(%error, function = ?name, "failed to send message to cleanup dead function");
+    loop {
+        tokio::select! {
+            _ = state_crashes.wait_for_restart(&name) => {
+                crate::watcher::restart(&wx).await?;
+                continue;
+            }
+            Ok(_) = aws_env_refreshes.changed() => {
+                // Running processes keep the old credentials, start them again with the new ones.
+                if processes.get(&name).await == ProcessStatus::Running {
+                    crate::watcher::restart(&wx).await?;
+                }
+                continue;
+            }
+            res = &mut main => match res {
+                Ok(_) => {},
+                Err(error) => {
+                    error!(?error, "failed to obtain the watchexec task");
+                    send_gc(&gc_tx, &name).await;
                 }
+            },
+        reason = lifecycle.wait_for_shutdown(&name) => {
+            info!(function = ?name, %reason, "stopping lambda function, it will start again with the next invocation");
+            lifecycle.release(&name).await;
+            processes.set(&name, ProcessStatus::NotStarted).await;
+
+            crate::watcher::terminate(&wx).await?;
+            if let Err(error) = main.await {
+                error!(?error, "failed to stop the watchexec task");
+            }
+            send_gc(&gc_tx, &name).await;
+        }
+            _ = subsys.on_shutdown_requested() => {
+                info!(function = ?name, "terminating lambda function");
             }
-        },
-        _ = subsys.on_shutdown_requested() => {
-            info!(function = ?name, "terminating lambda function");
         }
+        break;
     }
 
     let event = NextEvent::shutdown(&format!("{name} function shutting down"));
     ext_cache.send_event(event).await
 }
 
+async fn send_gc(gc_tx: &Sender<String>, name: &str) {
+    if let Err(error) = gc_tx.send(name.to_string()).await {
+        error!(%error, function = ?name, "failed to send message to cleanup dead function");
+    }
+}
+
+/// The binary name of the default function, when the project only has one binary.
+fn default_bin_name(state: &RuntimeState) -> Option<String> {
+    if !state.is_default_function_enabled() {
+        return None;
+    }
+    state.initial_functions.iter().next().cloned()
+}
+
+/// Path of the function binary built by `cargo lambda build`, like `target/lambda/basic/bootstrap`.
+/// Binaries built with the `--flatten` flag are directly in the artifact directory.
+fn artifact_path(dir: &Path, bin_name: Option<&str>) -> Result<PathBuf, ServerError> {
+    let nested = bin_name.map(|name| dir.join(name).join("bootstrap"));
+    if let Some(path) = nested.as_ref().filter(|p| p.is_file()) {
+        return Ok(path.clone());
+    }
+
+    let flattened = dir.join("bootstrap");
+    if flattened.is_file() {
+        return Ok(flattened);
+    }
+
+    Err(ServerError::MissingArtifact(nested.unwrap_or(flattened)))
+}
+
+/// Command to start the function with a custom runner, like `cross run --bin {function}`.
+/// The `{function}` placeholder is replaced with the function's binary name.
+fn runner_command(runner: &str, bin_name: &str) -> Result<Command, ServerError> {
+    let mut args = shell_words::split(runner)
+        .map_err(|err| ServerError::InvalidRunner(runner.into(), err.to_string()))?
+        .into_iter()
+        .map(|arg| arg.replace("{function}", bin_name));
+
+    let Some(prog) = args.next() else {
+        return Err(ServerError::InvalidRunner(
+            runner.into(),
+            "the command is empty".into(),
+        ));
+    };
+
+    Ok(Command::Exec {
+        prog,
+        args: args.collect(),
+    })
+}
+
 fn is_valid_bin_name(name: &str) -> bool {
     !name.is_empty() && name != DEFAULT_PACKAGE_FUNCTION
 }
@@ -136,3 +370,86 @@ fn cargo_command(
             .collect(),
     })
 }
+
+/// Command to compile the function before starting it,
+/// so the compilation time doesn't count as part of the init phase.
+fn cargo_build_command(
+    name: &str,
+    cargo_options: &CargoOptions,
+) -> Result<watchexec::command::Command, ServerError> {
+    let mut build_options = cargo_options.clone();
+    build_options.args.clear();
+
+    match cargo_command(name, &build_options)? {
+        Command::Exec { prog, mut args } => {
+            if let Some(subcommand) = args.iter_mut().find(|arg| *arg == "run") {
+                *subcommand = "build".into();
+            }
+            Ok(Command::Exec { prog, args })
+        }
+        cmd => Ok(cmd),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_artifact_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("basic");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(nested.join("bootstrap"), "").unwrap();
+
+        assert_eq!(
+            nested.join("bootstrap"),
+            artifact_path(dir.path(), Some("basic")).unwrap()
+        );
+        assert!(matches!(
+            artifact_path(dir.path(), Some("other")),
+            Err(ServerError::MissingArtifact(path)) if path == dir.path().join("other").join("bootstrap")
+        ));
+
+        std::fs::write(dir.path().join("bootstrap"), "").unwrap();
+        assert_eq!(
+            dir.path().join("bootstrap"),
+            artifact_path(dir.path(), Some("other")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_runner_command() {
+        let cmd = runner_command(
+            "cross run --target x86_64-unknown-linux-gnu --bin {function} -- --config 'a b'",
+            "basic",
+        )
+        .unwrap();
+        let Command::Exec { prog, args } = cmd else {
+            panic!("unexpected command: {cmd:?}");
+        };
+        assert_eq!("cross", prog);
+        assert_eq!(
+            vec![
+                "run",
+                "--target",
+                "x86_64-unknown-linux-gnu",
+                "--bin",
+                "basic",
+                "--",
+                "--config",
+                "a b"
+            ],
+            args
+        );
+
+        assert!(matches!(
+            runner_command("", "basic"),
+            Err(ServerError::InvalidRunner(_, _))
+        ));
+        assert!(matches!(
+            runner_command("cross run 'unclosed", "basic"),
+            Err(ServerError::InvalidRunner(_, _))
+        ));
+    }
+}
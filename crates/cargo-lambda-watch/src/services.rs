@@ -0,0 +1,69 @@
+use crate::error::ServerError;
+use std::{path::Path, process::Stdio};
+use tokio::process::Command;
+use tracing::{error, info};
+
+/// Run the commands that start the services that the functions depend on,
+/// like `docker compose up -d`, before the emulator starts.
+/// Each command must complete successfully before the next one runs.
+pub(crate) async fn start_services(commands: &[String], dir: &Path) -> Result<(), ServerError> {
+    for command in commands {
+        info!(?command, "starting service");
+        run_service_command(command, dir).await?;
+    }
+
+    Ok(())
+}
+
+/// Run the commands that stop the services, like `docker compose down`, after the emulator shuts down.
+/// Failures are logged, so every command has a chance to run.
+pub(crate) async fn stop_services(commands: &[String], dir: &Path) {
+    for command in commands {
+        info!(?command, "stopping service");
+        if let Err(error) = run_service_command(command, dir).await {
+            error!(%error, "failed to stop service");
+        }
+    }
+}
+
+async fn run_service_command(command: &str, dir: &Path) -> Result<(), ServerError> {
+    let failed = |reason: String| ServerError::ServiceFailed(command.into(), reason);
+
+    let args = shell_words::split(command).map_err(|e| failed(e.to_string()))?;
+    let Some((program, args)) = args.split_first() else {
+        return Err(failed("the command is empty".into()));
+    };
+
+    let status = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .status()
+        .await
+        .map_err(|e| failed(e.to_string()))?;
+
+    if !status.success() {
+        return Err(failed(format!("the command exited with {status}")));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_service_command() {
+        let dir = std::env::current_dir().unwrap();
+
+        assert!(matches!(
+            run_service_command("", &dir).await,
+            Err(ServerError::ServiceFailed(_, _))
+        ));
+        assert!(matches!(
+            run_service_command("cargo-lambda-missing-service-command", &dir).await,
+            Err(ServerError::ServiceFailed(_, _))
+        ));
+    }
+}
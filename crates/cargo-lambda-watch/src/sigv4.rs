@@ -0,0 +1,325 @@
+use cargo_lambda_metadata::cargo::watch::IamCredentials;
+use hmac::{Hmac, Mac};
+use http::{request::Parts, HeaderMap};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const AMZ_DATE_HEADER: &str = "x-amz-date";
+const AMZ_CONTENT_SHA256_HEADER: &str = "x-amz-content-sha256";
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Reasons why a request to a function URL with `aws-iam` authorization is rejected.
+#[derive(Debug, Error, PartialEq)]
+pub(crate) enum SignatureError {
+    #[error("the request is not signed, the Authorization header is missing")]
+    MissingAuthorization,
+    #[error("the Authorization header is not a valid SigV4 signature")]
+    InvalidAuthorization,
+    #[error("the X-Amz-Date header is missing")]
+    MissingDate,
+    #[error("the access key `{0}` is not in the function URL credentials")]
+    UnknownAccessKey(String),
+    #[error("the signature doesn't match the signature calculated with the secret access key")]
+    SignatureMismatch,
+    #[error("function URLs don't accept unsigned payloads")]
+    UnsignedPayload,
+    #[error("the X-Amz-Content-Sha256 header doesn't match the hash of the request body")]
+    PayloadHashMismatch,
+}
+
+/// Components of the SigV4 Authorization header, like
+/// `AWS4-HMAC-SHA256 Credential=AKID/20240101/us-east-1/lambda/aws4_request, SignedHeaders=host;x-amz-date, Signature=...`
+#[derive(Debug, PartialEq)]
+struct Authorization<'a> {
+    access_key_id: &'a str,
+    scope: &'a str,
+    signed_headers: Vec<&'a str>,
+    signature: &'a str,
+}
+
+/// Verify the SigV4 signature of a request, and return the credentials that signed it.
+pub(crate) fn verify_request<'a>(
+    parts: &Parts,
+    body: &[u8],
+    credentials: &'a [IamCredentials],
+) -> Result<&'a IamCredentials, SignatureError> {
+    let header = parts
+        .headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .ok_or(SignatureError::MissingAuthorization)?;
+    let auth = parse_authorization(header).ok_or(SignatureError::InvalidAuthorization)?;
+
+    let identity = credentials
+        .iter()
+        .find(|c| c.access_key_id == auth.access_key_id)
+        .ok_or_else(|| SignatureError::UnknownAccessKey(auth.access_key_id.to_string()))?;
+
+    let amz_date = parts
+        .headers
+        .get(AMZ_DATE_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .ok_or(SignatureError::MissingDate)?;
+
+    // The signature only covers the body through its hash, so the hash is always calculated
+    // from the body that was received, and the header must match it.
+    let payload_hash = hex::encode(Sha256::digest(body));
+    match parts
+        .headers
+        .get(AMZ_CONTENT_SHA256_HEADER)
+        .map(|h| h.to_str().unwrap_or_default())
+    {
+        Some(UNSIGNED_PAYLOAD) => return Err(SignatureError::UnsignedPayload),
+        Some(hash) if !hash.eq_ignore_ascii_case(&payload_hash) => {
+            return Err(SignatureError::PayloadHashMismatch)
+        }
+        _ => {}
+    }
+
+    let canonical_request = canonical_request(parts, &auth.signed_headers, &payload_hash);
+    let string_to_sign = format!(
+        "{ALGORITHM}\n{amz_date}\n{}\n{}",
+        auth.scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let signature = hex::decode(auth.signature).map_err(|_| SignatureError::SignatureMismatch)?;
+    if !verify(
+        &identity.secret_access_key,
+        auth.scope,
+        &string_to_sign,
+        &signature,
+    ) {
+        return Err(SignatureError::SignatureMismatch);
+    }
+
+    Ok(identity)
+}
+
+fn parse_authorization(header: &str) -> Option<Authorization<'_>> {
+    let components = header.strip_prefix(ALGORITHM)?.trim();
+
+    let mut credential = None;
+    let mut signed_headers = None;
+    let mut signature = None;
+    for component in components.split(',') {
+        match component.trim().split_once('=')? {
+            ("Credential", value) => credential = Some(value),
+            ("SignedHeaders", value) => signed_headers = Some(value.split(';').collect()),
+            ("Signature", value) => signature = Some(value),
+            _ => {}
+        }
+    }
+
+    let (access_key_id, scope) = credential?.split_once('/')?;
+    Some(Authorization {
+        access_key_id,
+        scope,
+        signed_headers: signed_headers?,
+        signature: signature?,
+    })
+}
+
+fn canonical_request(parts: &Parts, signed_headers: &[&str], payload_hash: &str) -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        parts.method,
+        canonical_uri(parts.uri.path()),
+        canonical_query(parts.uri.query().unwrap_or_default()),
+        canonical_headers(parts, signed_headers),
+        signed_headers.join(";"),
+        payload_hash
+    )
+}
+
+/// Services other than S3 encode the path segments twice,
+/// the path in the request is already encoded once.
+fn canonical_uri(path: &str) -> String {
+    if path.is_empty() {
+        return "/".into();
+    }
+
+    path.split('/')
+        .map(|segment| uri_encode(segment, true))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn canonical_query(query: &str) -> String {
+    let mut params = form_urlencoded::parse(query.as_bytes())
+        .map(|(k, v)| (uri_encode(&k, true), uri_encode(&v, true)))
+        .collect::<Vec<_>>();
+    params.sort();
+
+    params
+        .into_iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn canonical_headers(parts: &Parts, signed_headers: &[&str]) -> String {
+    signed_headers
+        .iter()
+        .map(|name| {
+            let value = header_values(&parts.headers, name).or_else(|| {
+                // HTTP/2 requests send the host in the URI authority instead of a header.
+                (*name == "host")
+                    .then(|| parts.uri.authority().map(|a| a.to_string()))
+                    .flatten()
+            });
+            format!("{name}:{}\n", value.unwrap_or_default())
+        })
+        .collect()
+}
+
+fn header_values(headers: &HeaderMap, name: &str) -> Option<String> {
+    let values = headers
+        .get_all(name)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .map(|v| v.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>();
+
+    (!values.is_empty()).then(|| values.join(","))
+}
+
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Check the signature with the signing key derived from the secret access key and the scope,
+/// like `20240101/us-east-1/lambda/aws4_request`. The signatures are compared in constant time.
+fn verify(secret_access_key: &str, scope: &str, string_to_sign: &str, signature: &[u8]) -> bool {
+    let mut key = format!("AWS4{secret_access_key}").into_bytes();
+    for component in scope.split('/') {
+        match hmac_sha256(&key, component.as_bytes()) {
+            Some(derived) => key = derived,
+            None => return false,
+        }
+    }
+
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(&key) else {
+        return false;
+    };
+    mac.update(string_to_sign.as_bytes());
+    mac.verify_slice(signature).is_ok()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Option<Vec<u8>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).ok()?;
+    mac.update(data);
+    Some(mac.finalize().into_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::Request;
+
+    fn credentials() -> Vec<IamCredentials> {
+        vec!["AKIDEXAMPLE:wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY"
+            .parse()
+            .unwrap()]
+    }
+
+    // Example from the AWS Signature Version 4 test suite, `get-vanilla-query-order-key-case`.
+    fn signed_request(signature: &str) -> Parts {
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date, Signature={signature}"
+        );
+        let (parts, _) = Request::get("/?Param2=value2&Param1=value1")
+            .header("host", "example.amazonaws.com")
+            .header("x-amz-date", "20150830T123600Z")
+            .header("authorization", authorization)
+            .body(())
+            .unwrap()
+            .into_parts();
+        parts
+    }
+
+    #[test]
+    fn test_verify_request() {
+        let credentials = credentials();
+
+        let parts =
+            signed_request("b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500");
+        let identity = verify_request(&parts, b"", &credentials).unwrap();
+        assert_eq!("AKIDEXAMPLE", identity.access_key_id);
+
+        for signature in [
+            "0000",
+            "not-hex",
+            "b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2501",
+        ] {
+            let parts = signed_request(signature);
+            assert_eq!(
+                Err(SignatureError::SignatureMismatch),
+                verify_request(&parts, b"", &credentials).map(|c| c.access_key_id.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_request_payload_hash() {
+        let credentials = credentials();
+        let empty_hash = hex::encode(Sha256::digest(b""));
+
+        let mut parts =
+            signed_request("b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500");
+        parts
+            .headers
+            .insert(AMZ_CONTENT_SHA256_HEADER, empty_hash.parse().unwrap());
+        assert_eq!(
+            Err(SignatureError::PayloadHashMismatch),
+            verify_request(&parts, b"changed body", &credentials).map(|c| c.access_key_id.clone())
+        );
+
+        parts
+            .headers
+            .insert(AMZ_CONTENT_SHA256_HEADER, UNSIGNED_PAYLOAD.parse().unwrap());
+        assert_eq!(
+            Err(SignatureError::UnsignedPayload),
+            verify_request(&parts, b"", &credentials).map(|c| c.access_key_id.clone())
+        );
+
+        // The body doesn't match the hash in the signature, even without the header.
+        let parts =
+            signed_request("b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500");
+        assert_eq!(
+            Err(SignatureError::SignatureMismatch),
+            verify_request(&parts, b"changed body", &credentials).map(|c| c.access_key_id.clone())
+        );
+    }
+
+    #[test]
+    fn test_verify_unsigned_request() {
+        let (parts, _) = Request::get("/").body(()).unwrap().into_parts();
+        assert_eq!(
+            Err(SignatureError::MissingAuthorization),
+            verify_request(&parts, b"", &credentials()).map(|c| c.access_key_id.clone())
+        );
+    }
+
+    #[test]
+    fn test_parse_authorization() {
+        let auth = parse_authorization("AWS4-HMAC-SHA256 Credential=AKID/20240101/us-east-1/lambda/aws4_request, SignedHeaders=host;x-amz-date, Signature=abc").unwrap();
+        assert_eq!("AKID", auth.access_key_id);
+        assert_eq!("20240101/us-east-1/lambda/aws4_request", auth.scope);
+        assert_eq!(vec!["host", "x-amz-date"], auth.signed_headers);
+        assert_eq!("abc", auth.signature);
+
+        assert!(parse_authorization("Bearer token").is_none());
+    }
+}
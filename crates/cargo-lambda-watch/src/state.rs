@@ -1,19 +1,38 @@
 use crate::{
+    aws_env::AwsEnvCache,
     error::ServerError,
-    requests::{InvokeRequest, LambdaResponse, NextEvent},
+    history::HistoryCache,
+    lifecycle::LifecycleCache,
+    logs::LogsCache,
+    notifications::NotificationCache,
+    requests::{ColdStart, InitFailure, InvokeRequest, LambdaResponse, NextEvent, ProcessStatus},
+    telemetry::TelemetryCache,
     RUNTIME_EMULATOR_PATH,
 };
-use cargo_lambda_metadata::cargo::{binary_targets, watch::FunctionRouter};
+use cargo_lambda_metadata::{
+    cargo::{
+        binary_targets,
+        watch::{FunctionRouter, IamCredentials, DEFAULT_MAX_QUEUE_DEPTH},
+    },
+    DEFAULT_PACKAGE_FUNCTION,
+};
 use miette::Result;
-use mpsc::{channel, Receiver, Sender};
+use mpsc::{
+    channel,
+    error::{SendError, TrySendError},
+    Receiver, Sender,
+};
 use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
     net::SocketAddr,
     path::PathBuf,
     sync::Arc,
 };
-use tokio::sync::{mpsc, oneshot, Mutex, RwLock};
-use tracing::debug;
+use tokio::{
+    sync::{mpsc, oneshot, Mutex, Notify, RwLock},
+    time::{Duration, Instant},
+};
+use tracing::{debug, warn};
 use uuid::Uuid;
 
 #[derive(Clone)]
@@ -24,9 +43,30 @@ pub(crate) struct RuntimeState {
     manifest_path: PathBuf,
     pub initial_functions: HashSet<String>,
     pub function_router: Option<FunctionRouter>,
+    /// Credentials that can sign requests to the function URLs, when they use `aws-iam` authorization.
+    pub function_url_credentials: Option<Vec<IamCredentials>>,
+    /// Executable that can modify the events and the responses of every invocation.
+    pub middleware: Option<PathBuf>,
+    /// Whether the invocations must respect the Lambda payload size limits.
+    pub payload_limits: bool,
+    /// Functions behind an Application Load Balancer, that receive ALB events instead of function URL events.
+    pub alb_functions: HashSet<String>,
+    /// Whether ALB events use multi-value headers and query parameters.
+    pub alb_multi_value_headers: bool,
     pub req_cache: RequestCache,
     pub res_cache: ResponseCache,
     pub ext_cache: ExtensionCache,
+    pub cold_starts: ColdStartCache,
+    pub reloads: ReloadCache,
+    pub drains: DrainCache,
+    pub processes: ProcessCache,
+    pub crashes: CrashCache,
+    pub lifecycle: LifecycleCache,
+    pub logs: LogsCache,
+    pub telemetry: TelemetryCache,
+    pub history: HistoryCache,
+    pub aws_env: AwsEnvCache,
+    pub notifications: NotificationCache,
 }
 
 pub(crate) type RefRuntimeState = Arc<RuntimeState>;
@@ -45,10 +85,26 @@ impl RuntimeState {
             manifest_path,
             initial_functions,
             function_router,
+            function_url_credentials: None,
+            middleware: None,
+            payload_limits: true,
+            alb_functions: HashSet::new(),
+            alb_multi_value_headers: false,
             runtime_url: format!("http://{runtime_addr}{RUNTIME_EMULATOR_PATH}"),
-            req_cache: RequestCache::new(),
+            req_cache: RequestCache::new(DEFAULT_MAX_QUEUE_DEPTH as usize),
             res_cache: ResponseCache::new(),
             ext_cache: ExtensionCache::default(),
+            cold_starts: ColdStartCache::default(),
+            reloads: ReloadCache::default(),
+            drains: DrainCache::default(),
+            processes: ProcessCache::default(),
+            crashes: CrashCache::default(),
+            lifecycle: LifecycleCache::default(),
+            logs: LogsCache::default(),
+            telemetry: TelemetryCache::default(),
+            history: HistoryCache::default(),
+            aws_env: AwsEnvCache::default(),
+            notifications: NotificationCache::default(),
         }
     }
 
@@ -64,6 +120,21 @@ impl RuntimeState {
         self.initial_functions.len() == 1
     }
 
+    /// Whether the function receives ALB events for its HTTP requests.
+    /// The default function receives them when its binary is selected.
+    pub(crate) fn is_alb_function(&self, name: &str) -> bool {
+        if self.alb_functions.contains(name) {
+            return true;
+        }
+
+        name == DEFAULT_PACKAGE_FUNCTION
+            && self.is_default_function_enabled()
+            && self
+                .initial_functions
+                .iter()
+                .any(|f| self.alb_functions.contains(f))
+    }
+
     pub(crate) fn is_function_available(&self, name: &str) -> Result<(), HashSet<String>> {
         if self.initial_functions.contains(name) {
             return Ok(());
@@ -87,8 +158,8 @@ pub(crate) struct RequestQueue {
 }
 
 impl RequestQueue {
-    pub fn new() -> RequestQueue {
-        let (tx, rx) = channel::<InvokeRequest>(100);
+    pub fn new(depth: usize) -> RequestQueue {
+        let (tx, rx) = channel::<InvokeRequest>(depth.max(1));
 
         RequestQueue {
             tx: Arc::new(tx),
@@ -101,29 +172,55 @@ impl RequestQueue {
         rx.recv().await
     }
 
-    pub async fn push(&self, req: InvokeRequest) -> Result<(), ServerError> {
-        self.tx
-            .send(req)
-            .await
-            .map_err(|e| ServerError::SendInvokeMessage(Box::new(e)))
+    /// Add a request to the queue, without waiting for space in it.
+    /// Requests that don't fit in the queue are throttled.
+    pub fn push(&self, req: InvokeRequest) -> Result<(), ServerError> {
+        match self.tx.try_send(req) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(req)) => {
+                warn!(
+                    function = ?req.function_name,
+                    depth = self.tx.max_capacity(),
+                    "request queue is full, throttling invocation"
+                );
+                req.throttle();
+                Ok(())
+            }
+            Err(TrySendError::Closed(req)) => {
+                Err(ServerError::SendInvokeMessage(Box::new(SendError(req))))
+            }
+        }
+    }
+
+    /// Remove all the requests in the queue, without waiting for new ones.
+    pub async fn drain(&self) -> Vec<InvokeRequest> {
+        let mut rx = self.rx.lock().await;
+        let mut requests = Vec::new();
+        while let Ok(req) = rx.try_recv() {
+            requests.push(req);
+        }
+        requests
     }
 }
 
 #[derive(Clone, Debug)]
 pub(crate) struct RequestCache {
     inner: Arc<RwLock<HashMap<String, RequestQueue>>>,
+    /// Maximum number of requests that wait for each function.
+    depth: usize,
 }
 
 impl RequestCache {
-    pub fn new() -> RequestCache {
+    pub fn new(depth: usize) -> RequestCache {
         RequestCache {
             inner: Arc::new(RwLock::new(HashMap::new())),
+            depth,
         }
     }
 
     pub async fn init(&self, function_name: &str) {
         let mut inner = self.inner.write().await;
-        inner.insert(function_name.into(), RequestQueue::new());
+        inner.insert(function_name.into(), RequestQueue::new(self.depth));
         debug!(
             function_name,
             "request stack initialized before compilation"
@@ -136,8 +233,8 @@ impl RequestCache {
 
         match inner.entry(function_name.clone()) {
             Entry::Vacant(v) => {
-                let stack = RequestQueue::new();
-                stack.push(req).await?;
+                let stack = RequestQueue::new(self.depth);
+                stack.push(req)?;
                 v.insert(stack);
 
                 debug!(?function_name, "request stack initialized in first request");
@@ -145,7 +242,7 @@ impl RequestCache {
                 Ok(Some(function_name))
             }
             Entry::Occupied(o) => {
-                o.into_mut().push(req).await?;
+                o.into_mut().push(req)?;
                 debug!(?function_name, "request stack increased");
 
                 Ok(None)
@@ -162,7 +259,7 @@ impl RequestCache {
                 let stack = match inner.entry(function_name.to_owned()) {
                     Entry::Occupied(o) => o.into_mut().clone(),
                     Entry::Vacant(v) => {
-                        let stack = v.insert(RequestQueue::new()).clone();
+                        let stack = v.insert(RequestQueue::new(self.depth)).clone();
                         debug!(
                             ?function_name,
                             "request stack initialized in first lambda connection"
@@ -183,6 +280,15 @@ impl RequestCache {
         stack.pop().await
     }
 
+    /// Remove the requests waiting for a function, so they can be answered without it.
+    pub async fn take_pending(&self, function_name: &str) -> Vec<InvokeRequest> {
+        let stack = self.inner.read().await.get(function_name).cloned();
+        match stack {
+            Some(stack) => stack.drain().await,
+            None => Vec::new(),
+        }
+    }
+
     pub async fn clean(&self, function_name: &str) {
         let mut inner = self.inner.write().await;
         inner.remove(function_name);
@@ -197,7 +303,7 @@ impl RequestCache {
 
 #[derive(Clone)]
 pub(crate) struct ResponseCache {
-    inner: Arc<Mutex<HashMap<String, oneshot::Sender<LambdaResponse>>>>,
+    inner: Arc<Mutex<HashMap<String, (oneshot::Sender<LambdaResponse>, ColdStart)>>>,
 }
 
 impl ResponseCache {
@@ -207,14 +313,323 @@ impl ResponseCache {
         }
     }
 
-    pub async fn pop(&self, req_id: &str) -> Option<oneshot::Sender<LambdaResponse>> {
+    pub async fn pop(&self, req_id: &str) -> Option<(oneshot::Sender<LambdaResponse>, ColdStart)> {
         let mut cache = self.inner.lock().await;
         cache.remove(req_id)
     }
 
-    pub async fn push(&self, req_id: &str, resp_tx: oneshot::Sender<LambdaResponse>) {
+    pub async fn push(
+        &self,
+        req_id: &str,
+        resp_tx: oneshot::Sender<LambdaResponse>,
+        cold_start: ColdStart,
+    ) {
         let mut cache = self.inner.lock().await;
-        cache.insert(req_id.into(), resp_tx);
+        cache.insert(req_id.into(), (resp_tx, cold_start));
+    }
+}
+
+/// ColdStartCache keeps track of the functions that have
+/// already processed an invocation since their process started.
+#[derive(Clone, Default)]
+pub(crate) struct ColdStartCache {
+    warm: Arc<Mutex<HashSet<String>>>,
+}
+
+impl ColdStartCache {
+    /// Mark a function as cold, usually because its process is (re)starting.
+    pub async fn reset(&self, function_name: &str) {
+        let mut warm = self.warm.lock().await;
+        warm.remove(function_name);
+    }
+
+    /// Mark a function as warm, and return whether the function was cold before.
+    pub async fn warm_up(&self, function_name: &str) -> ColdStart {
+        let mut warm = self.warm.lock().await;
+        ColdStart(warm.insert(function_name.into()))
+    }
+}
+
+/// ReloadCache counts how many times each function process
+/// has started and connected to the runtime successfully.
+#[derive(Clone, Default)]
+pub(crate) struct ReloadCache {
+    inner: Arc<Mutex<HashMap<String, ReloadStatus>>>,
+}
+
+#[derive(Default)]
+struct ReloadStatus {
+    ready: bool,
+    count: u64,
+}
+
+impl ReloadCache {
+    /// Mark a function as not ready, usually because its process is (re)starting.
+    pub async fn reset(&self, function_name: &str) {
+        let mut inner = self.inner.lock().await;
+        if let Some(status) = inner.get_mut(function_name) {
+            status.ready = false;
+        }
+    }
+
+    /// Mark a function as ready, and count a new reload if it wasn't ready before.
+    pub async fn ready(&self, function_name: &str) {
+        let mut inner = self.inner.lock().await;
+        let status = inner.entry(function_name.into()).or_default();
+        if !status.ready {
+            status.ready = true;
+            status.count += 1;
+        }
+    }
+
+    /// Return how many times a function has been (re)loaded.
+    pub async fn count(&self, function_name: &str) -> u64 {
+        let inner = self.inner.lock().await;
+        inner
+            .get(function_name)
+            .map(|s| s.count)
+            .unwrap_or_default()
+    }
+}
+
+/// ProcessCache keeps track of the state of each function process.
+#[derive(Clone, Default)]
+pub(crate) struct ProcessCache {
+    inner: Arc<Mutex<HashMap<String, ProcessStatus>>>,
+}
+
+impl ProcessCache {
+    pub async fn set(&self, function_name: &str, status: ProcessStatus) {
+        let mut inner = self.inner.lock().await;
+        inner.insert(function_name.into(), status);
+    }
+
+    /// Return the state of a function process, `NotStarted` if it never started.
+    pub async fn get(&self, function_name: &str) -> ProcessStatus {
+        let inner = self.inner.lock().await;
+        inner.get(function_name).copied().unwrap_or_default()
+    }
+
+    /// Return the names of all the functions that have started a process.
+    pub async fn names(&self) -> Vec<String> {
+        let inner = self.inner.lock().await;
+        inner.keys().cloned().collect()
+    }
+}
+
+/// Maximum time that the emulator waits to start a function again after it failed to initialize.
+const MAX_CRASH_BACKOFF: Duration = Duration::from_secs(30);
+
+/// CrashCache keeps track of the functions that failed to initialize,
+/// so they are not started again in a loop when every invocation makes them fail.
+#[derive(Clone, Default)]
+pub(crate) struct CrashCache {
+    inner: Arc<Mutex<HashMap<String, CrashStatus>>>,
+    notify: Arc<Notify>,
+}
+
+struct CrashStatus {
+    failure: InitFailure,
+    failed_at: Instant,
+    /// The function needs to start again, the watcher hasn't started it yet.
+    restart: bool,
+    /// The function is starting again after the failure.
+    restarting: bool,
+}
+
+impl CrashStatus {
+    fn in_backoff(&self) -> bool {
+        !self.restarting && self.failed_at.elapsed() < crash_backoff(self.failure.failures)
+    }
+}
+
+/// Time to wait before starting a function again, after it failed to initialize `failures` times in a row.
+/// The first failure doesn't wait, the next ones double the time, up to `MAX_CRASH_BACKOFF`.
+pub(crate) fn crash_backoff(failures: u32) -> Duration {
+    if failures < 2 {
+        return Duration::ZERO;
+    }
+
+    let exp = (failures - 2).min(5);
+    Duration::from_secs(1 << exp).min(MAX_CRASH_BACKOFF)
+}
+
+impl CrashCache {
+    /// Record that a function failed to initialize, and return the failure with the consecutive count.
+    pub async fn failed(&self, function_name: &str, stderr: Vec<String>) -> InitFailure {
+        let mut inner = self.inner.lock().await;
+        let failures = inner
+            .get(function_name)
+            .map(|s| s.failure.failures)
+            .unwrap_or_default()
+            + 1;
+
+        let failure = InitFailure { failures, stderr };
+        inner.insert(
+            function_name.into(),
+            CrashStatus {
+                failure: failure.clone(),
+                failed_at: Instant::now(),
+                restart: false,
+                restarting: false,
+            },
+        );
+        failure
+    }
+
+    /// Forget the failures of a function, usually because it started correctly.
+    pub async fn recovered(&self, function_name: &str) {
+        let mut inner = self.inner.lock().await;
+        inner.remove(function_name);
+    }
+
+    /// Return the last failure of a function if it's still waiting to be started again.
+    pub async fn backoff(&self, function_name: &str) -> Option<InitFailure> {
+        let inner = self.inner.lock().await;
+        inner
+            .get(function_name)
+            .filter(|s| s.in_backoff())
+            .map(|s| s.failure.clone())
+    }
+
+    /// Ask the watcher of a function that failed to initialize to start it again.
+    /// It returns false if the function didn't fail, or if it's already starting again.
+    pub async fn restart(&self, function_name: &str) -> bool {
+        let mut inner = self.inner.lock().await;
+        let requested = match inner.get_mut(function_name) {
+            Some(status) if !status.restarting && !status.in_backoff() => {
+                status.restart = true;
+                status.restarting = true;
+                true
+            }
+            _ => false,
+        };
+        drop(inner);
+
+        if requested {
+            self.notify.notify_waiters();
+        }
+        requested
+    }
+
+    /// Record that a function is starting again, like after a code change,
+    /// so invocations wait for it instead of failing with the last error.
+    pub async fn starting(&self, function_name: &str) {
+        let mut inner = self.inner.lock().await;
+        if let Some(status) = inner.get_mut(function_name) {
+            status.restarting = true;
+        }
+    }
+
+    /// Wait until a function that failed to initialize needs to start again.
+    pub async fn wait_for_restart(&self, function_name: &str) {
+        loop {
+            let notified = self.notify.notified();
+            if self.take_restart(function_name).await {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    async fn take_restart(&self, function_name: &str) -> bool {
+        let mut inner = self.inner.lock().await;
+        inner
+            .get_mut(function_name)
+            .is_some_and(|s| std::mem::take(&mut s.restart))
+    }
+}
+
+/// DrainCache keeps track of the invocations that each function process is handling,
+/// so a reload can wait for them to complete before stopping the process.
+#[derive(Clone, Default)]
+pub(crate) struct DrainCache {
+    inner: Arc<Mutex<HashMap<String, DrainStatus>>>,
+    notify: Arc<Notify>,
+}
+
+#[derive(Default)]
+struct DrainStatus {
+    in_flight: HashSet<String>,
+    draining: bool,
+}
+
+impl DrainCache {
+    /// Record that a function started handling an invocation.
+    pub async fn invoke_started(&self, function_name: &str, req_id: &str) {
+        let mut inner = self.inner.lock().await;
+        let status = inner.entry(function_name.into()).or_default();
+        status.in_flight.insert(req_id.into());
+    }
+
+    /// Record that an invocation completed.
+    pub async fn invoke_done(&self, req_id: &str) {
+        let mut inner = self.inner.lock().await;
+        for status in inner.values_mut() {
+            if status.in_flight.remove(req_id) {
+                break;
+            }
+        }
+        drop(inner);
+        self.notify.notify_waiters();
+    }
+
+    /// Stop sending new invocations to a function, and wait until the invocations
+    /// that it's handling complete, or until the timeout expires.
+    /// It returns the number of invocations that didn't complete on time.
+    pub async fn drain(&self, function_name: &str, timeout: Duration) -> usize {
+        {
+            let mut inner = self.inner.lock().await;
+            inner.entry(function_name.into()).or_default().draining = true;
+        }
+
+        let drained = async {
+            loop {
+                let notified = self.notify.notified();
+                if self.in_flight(function_name).await == 0 {
+                    return;
+                }
+                notified.await;
+            }
+        };
+
+        let _ = tokio::time::timeout(timeout, drained).await;
+        self.in_flight(function_name).await
+    }
+
+    /// Send invocations to a function again, usually because its new process is starting.
+    pub async fn resume(&self, function_name: &str) {
+        let mut inner = self.inner.lock().await;
+        if let Some(status) = inner.get_mut(function_name) {
+            status.draining = false;
+            status.in_flight.clear();
+        }
+        drop(inner);
+        self.notify.notify_waiters();
+    }
+
+    /// Wait until a function is not draining anymore.
+    pub async fn wait_for_resume(&self, function_name: &str) {
+        loop {
+            let notified = self.notify.notified();
+            if !self.is_draining(function_name).await {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    async fn is_draining(&self, function_name: &str) -> bool {
+        let inner = self.inner.lock().await;
+        inner.get(function_name).is_some_and(|s| s.draining)
+    }
+
+    async fn in_flight(&self, function_name: &str) -> usize {
+        let inner = self.inner.lock().await;
+        inner
+            .get(function_name)
+            .map(|s| s.in_flight.len())
+            .unwrap_or_default()
     }
 }
 
@@ -242,6 +657,21 @@ impl ExtensionCache {
         extension_id.to_string()
     }
 
+    /// Wait until the given number of extensions have registered, or until the timeout expires.
+    /// It returns whether all the extensions registered on time.
+    pub async fn wait_for_registrations(&self, expected: usize, timeout: Duration) -> bool {
+        let registered = async {
+            loop {
+                if self.extensions.lock().await.len() >= expected {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        };
+
+        tokio::time::timeout(timeout, registered).await.is_ok()
+    }
+
     pub async fn set_senders(&self, extension_id: &str, sender: mpsc::Sender<NextEvent>) {
         let extensions = self.extensions.lock().await;
         if let Some(events) = extensions.get(extension_id) {
@@ -285,3 +715,109 @@ impl ExtensionCache {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crash_backoff() {
+        assert_eq!(Duration::ZERO, crash_backoff(1));
+        assert_eq!(Duration::from_secs(1), crash_backoff(2));
+        assert_eq!(Duration::from_secs(2), crash_backoff(3));
+        assert_eq!(Duration::from_secs(16), crash_backoff(6));
+        assert_eq!(MAX_CRASH_BACKOFF, crash_backoff(7));
+        assert_eq!(MAX_CRASH_BACKOFF, crash_backoff(100));
+    }
+
+    #[tokio::test]
+    async fn test_crash_cache() {
+        let cache = CrashCache::default();
+        assert!(!cache.restart("basic").await);
+
+        // The first failure starts the function again right away.
+        let failure = cache.failed("basic", vec!["panic".into()]).await;
+        assert_eq!(1, failure.failures);
+        assert!(cache.backoff("basic").await.is_none());
+        assert!(cache.restart("basic").await);
+        assert!(!cache.restart("basic").await);
+        cache.wait_for_restart("basic").await;
+
+        // The second failure waits before starting the function again.
+        let failure = cache.failed("basic", vec!["panic".into()]).await;
+        assert_eq!(2, failure.failures);
+        let backoff = cache.backoff("basic").await.unwrap();
+        assert_eq!(vec!["panic".to_string()], backoff.stderr);
+        assert!(!cache.restart("basic").await);
+
+        cache.recovered("basic").await;
+        assert!(cache.backoff("basic").await.is_none());
+        assert_eq!(1, cache.failed("basic", Vec::new()).await.failures);
+    }
+
+    #[tokio::test]
+    async fn test_drain_cache() {
+        let cache = DrainCache::default();
+        cache.invoke_started("basic", "1").await;
+
+        let drain = {
+            let cache = cache.clone();
+            tokio::spawn(async move { cache.drain("basic", Duration::from_secs(5)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(cache.is_draining("basic").await);
+
+        cache.invoke_done("1").await;
+        assert_eq!(0, drain.await.unwrap());
+
+        cache.resume("basic").await;
+        tokio::time::timeout(Duration::from_secs(1), cache.wait_for_resume("basic"))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_drain_cache_timeout() {
+        let cache = DrainCache::default();
+        cache.invoke_started("basic", "1").await;
+        assert_eq!(1, cache.drain("basic", Duration::from_millis(50)).await);
+    }
+
+    fn invoke_request(function_name: &str) -> (InvokeRequest, oneshot::Receiver<LambdaResponse>) {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let req = InvokeRequest {
+            function_name: function_name.into(),
+            req: axum::http::Request::new(axum::body::Body::empty()),
+            resp_tx,
+        };
+        (req, resp_rx)
+    }
+
+    #[tokio::test]
+    async fn test_request_cache_throttling() {
+        let cache = RequestCache::new(2);
+
+        let (req, _first) = invoke_request("basic");
+        assert_eq!(Some("basic".to_string()), cache.upsert(req).await.unwrap());
+        let (req, _second) = invoke_request("basic");
+        assert_eq!(None, cache.upsert(req).await.unwrap());
+
+        // The queue is full, the next invocation is throttled right away.
+        let (req, mut third) = invoke_request("basic");
+        assert_eq!(None, cache.upsert(req).await.unwrap());
+        let resp = third.try_recv().unwrap();
+        assert_eq!(
+            Some(&axum::http::StatusCode::TOO_MANY_REQUESTS),
+            resp.extensions().get::<axum::http::StatusCode>()
+        );
+
+        // Other functions have their own queues.
+        let (req, _other) = invoke_request("other");
+        assert_eq!(Some("other".to_string()), cache.upsert(req).await.unwrap());
+
+        assert!(cache.pop("basic").await.is_some());
+        let (req, mut fourth) = invoke_request("basic");
+        assert_eq!(None, cache.upsert(req).await.unwrap());
+        assert!(fourth.try_recv().is_err());
+    }
+}
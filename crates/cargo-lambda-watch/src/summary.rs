@@ -0,0 +1,162 @@
+use crate::state::RuntimeState;
+use cargo_lambda_metadata::{cargo::watch::FunctionRoutes, DEFAULT_PACKAGE_FUNCTION};
+use std::net::SocketAddr;
+
+/// How the emulator starts a function.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum StartMode {
+    /// The function starts with the emulator.
+    Eager,
+    /// The function starts with the first invocation.
+    Lazy,
+    /// The function is started outside the emulator, with `--only-lambda-apis`.
+    External,
+}
+
+impl StartMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            StartMode::Eager => "on startup",
+            StartMode::Lazy => "on first invoke",
+            StartMode::External => "externally",
+        }
+    }
+}
+
+/// Lines of the table printed when the emulator starts, with the functions in the project,
+/// the URLs to invoke them, and the custom HTTP routes.
+pub(crate) fn summary_lines(state: &RuntimeState, mode: StartMode, secure: bool) -> Vec<String> {
+    let (runtime_addr, proxy_addr, _) = state.addresses();
+    let base_url = base_url(proxy_addr.unwrap_or(runtime_addr), secure);
+
+    let mut functions = state.initial_functions.iter().collect::<Vec<_>>();
+    functions.sort();
+
+    let rows = functions
+        .into_iter()
+        .map(|name| {
+            let (invoke_name, furl_path) = if state.is_default_function_enabled() {
+                (DEFAULT_PACKAGE_FUNCTION, "/".to_string())
+            } else {
+                (name.as_str(), format!("/lambda-url/{name}/"))
+            };
+            [
+                name.clone(),
+                mode.as_str().to_string(),
+                format!("{base_url}/2015-03-31/functions/{invoke_name}/invocations"),
+                format!("{base_url}{furl_path}"),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut lines = table(["FUNCTION", "STARTS", "INVOKE URL", "FUNCTION URL"], &rows);
+
+    if let Some(router) = &state.function_router {
+        let mut rows = Vec::new();
+        for (path, routes) in router.routes() {
+            match routes {
+                FunctionRoutes::Single(function) => {
+                    rows.push(["ANY".into(), format!("{base_url}{path}"), function.clone()]);
+                }
+                FunctionRoutes::Multiple(methods) => {
+                    let mut methods = methods.iter().collect::<Vec<_>>();
+                    methods.sort();
+                    for (method, function) in methods {
+                        rows.push([
+                            method.clone(),
+                            format!("{base_url}{path}"),
+                            function.clone(),
+                        ]);
+                    }
+                }
+            }
+        }
+
+        lines.push(String::new());
+        lines.extend(table(["METHOD", "ROUTE", "FUNCTION"], &rows));
+    }
+
+    lines
+}
+
+/// Address where users send requests. Unspecified addresses, like `::`, accept requests in localhost.
+fn base_url(addr: SocketAddr, secure: bool) -> String {
+    let scheme = if secure { "https" } else { "http" };
+    let host = if addr.ip().is_unspecified() || addr.ip().is_loopback() {
+        "localhost".to_string()
+    } else {
+        addr.ip().to_string()
+    };
+    format!("{scheme}://{host}:{}", addr.port())
+}
+
+fn table<const N: usize>(header: [&str; N], rows: &[[String; N]]) -> Vec<String> {
+    let mut widths = header.map(|h| h.len());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let format_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut lines = vec![format_row(header.to_vec())];
+    lines.extend(
+        rows.iter()
+            .map(|row| format_row(row.iter().map(String::as_str).collect())),
+    );
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        collections::HashSet,
+        net::{IpAddr, Ipv6Addr},
+        path::PathBuf,
+    };
+
+    fn state(functions: &[&str]) -> RuntimeState {
+        RuntimeState::new(
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 9000),
+            None,
+            PathBuf::new(),
+            functions
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<HashSet<_>>(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_summary_with_default_function() {
+        let lines = summary_lines(&state(&["basic"]), StartMode::Eager, false);
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with("FUNCTION  STARTS"));
+        assert_eq!(
+            "basic     on startup  http://localhost:9000/2015-03-31/functions/_/invocations  http://localhost:9000/",
+            lines[1]
+        );
+    }
+
+    #[test]
+    fn test_summary_with_several_functions() {
+        let lines = summary_lines(&state(&["users", "orders"]), StartMode::Lazy, false);
+        assert_eq!(3, lines.len());
+        assert!(lines[1].starts_with("orders"));
+        assert!(lines[1].contains("http://localhost:9000/2015-03-31/functions/orders/invocations"));
+        assert!(lines[1].ends_with("http://localhost:9000/lambda-url/orders/"));
+        assert!(lines[2].starts_with("users"));
+    }
+}
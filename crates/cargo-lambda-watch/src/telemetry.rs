@@ -0,0 +1,173 @@
+use crate::logs::{LogRecord, LogsCache};
+use serde::Serialize;
+use serde_json::json;
+use std::{collections::HashMap, sync::Arc};
+use tokio::{sync::Mutex, time::Instant};
+
+const FUNCTION_VERSION: &str = "$LATEST";
+const MEMORY_SIZE_MB: u32 = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum InvocationStatus {
+    Success,
+    Error,
+}
+
+impl InvocationStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            InvocationStatus::Success => "success",
+            InvocationStatus::Error => "error",
+        }
+    }
+}
+
+/// TelemetryCache synthesizes the platform events of the Telemetry API
+/// from the phases of each function, and sends them to the subscribed extensions.
+#[derive(Clone, Default)]
+pub(crate) struct TelemetryCache {
+    pub events: LogsCache,
+    inits: Arc<Mutex<HashMap<String, Instant>>>,
+    invocations: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl TelemetryCache {
+    /// Record that a function process is starting its init phase.
+    pub(crate) async fn init_started(&self, function_name: &str) {
+        let mut inits = self.inits.lock().await;
+        inits.insert(function_name.into(), Instant::now());
+
+        self.events
+            .send(LogRecord::new(
+                "platform.initStart",
+                json!({
+                    "initializationType": "on-demand",
+                    "phase": "init",
+                    "functionName": function_name,
+                    "functionVersion": FUNCTION_VERSION,
+                }),
+            ))
+            .await;
+    }
+
+    /// Record that the function finished its init phase,
+    /// which happens when it asks for its first invocation.
+    pub(crate) async fn init_done(&self, function_name: &str) {
+        let mut inits = self.inits.lock().await;
+        let Some(started_at) = inits.remove(function_name) else {
+            return;
+        };
+
+        let status = InvocationStatus::Success.as_str();
+        self.events
+            .send(LogRecord::new(
+                "platform.initRuntimeDone",
+                json!({
+                    "initializationType": "on-demand",
+                    "phase": "init",
+                    "status": status,
+                }),
+            ))
+            .await;
+
+        self.events
+            .send(LogRecord::new(
+                "platform.initReport",
+                json!({
+                    "initializationType": "on-demand",
+                    "phase": "init",
+                    "status": status,
+                    "metrics": { "durationMs": duration_ms(started_at) },
+                }),
+            ))
+            .await;
+    }
+
+    /// Record that an invocation has been sent to the function.
+    pub(crate) async fn invoke_started(&self, req_id: &str) {
+        let mut invocations = self.invocations.lock().await;
+        invocations.insert(req_id.into(), Instant::now());
+
+        self.events
+            .send(LogRecord::new(
+                "platform.start",
+                json!({ "requestId": req_id, "version": FUNCTION_VERSION }),
+            ))
+            .await;
+    }
+
+    /// Record that the function sent the response for an invocation.
+    pub(crate) async fn invoke_done(&self, req_id: &str, status: InvocationStatus) {
+        let mut invocations = self.invocations.lock().await;
+        let Some(started_at) = invocations.remove(req_id) else {
+            return;
+        };
+
+        let duration = duration_ms(started_at);
+        for record in invocation_records(req_id, status, duration) {
+            self.events.send(record).await;
+        }
+    }
+}
+
+fn invocation_records(req_id: &str, status: InvocationStatus, duration: f64) -> Vec<LogRecord> {
+    let status = status.as_str();
+    vec![
+        LogRecord::new(
+            "platform.runtimeDone",
+            json!({
+                "requestId": req_id,
+                "status": status,
+                "metrics": { "durationMs": duration, "producedBytes": 0 },
+            }),
+        ),
+        LogRecord::new(
+            "platform.report",
+            json!({
+                "requestId": req_id,
+                "status": status,
+                "metrics": {
+                    "durationMs": duration,
+                    "billedDurationMs": duration.ceil() as u64,
+                    "memorySizeMB": MEMORY_SIZE_MB,
+                },
+            }),
+        ),
+    ]
+}
+
+fn duration_ms(started_at: Instant) -> f64 {
+    let millis = started_at.elapsed().as_secs_f64() * 1000.0;
+    (millis * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invocation_records() {
+        let records = invocation_records("req-id", InvocationStatus::Error, 12.5);
+        assert_eq!(2, records.len());
+
+        assert_eq!("platform.runtimeDone", records[0].kind);
+        assert_eq!("req-id", records[0].record["requestId"]);
+        assert_eq!("error", records[0].record["status"]);
+        assert_eq!(12.5, records[0].record["metrics"]["durationMs"]);
+
+        assert_eq!("platform.report", records[1].kind);
+        assert_eq!(13, records[1].record["metrics"]["billedDurationMs"]);
+        assert_eq!(4096, records[1].record["metrics"]["memorySizeMB"]);
+    }
+
+    #[tokio::test]
+    async fn test_init_done_once() {
+        let telemetry = TelemetryCache::default();
+        telemetry.init_started("my-function").await;
+        assert!(telemetry.inits.lock().await.contains_key("my-function"));
+
+        telemetry.init_done("my-function").await;
+        assert!(telemetry.inits.lock().await.is_empty());
+    }
+}
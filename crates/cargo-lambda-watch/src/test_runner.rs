@@ -0,0 +1,386 @@
+use cargo_lambda_metadata::{
+    cargo::{watch::Watch, CargoMetadata},
+    DEFAULT_PACKAGE_FUNCTION,
+};
+use clap::{Args, ValueHint};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, TcpListener},
+    path::{Path, PathBuf},
+};
+use tokio::{net::TcpStream, sync::oneshot, time::Duration};
+use tokio_graceful_shutdown::{SubsystemBuilder, Toplevel};
+
+use crate::{with_services, Emulator};
+
+/// Environment variable with the emulator's URL, exported to `cargo test`.
+const INVOKE_URL_VAR: &str = "CARGO_LAMBDA_INVOKE_URL";
+
+/// How long to wait for the emulator to accept connections.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Args, Clone, Debug)]
+#[command(
+    name = "test",
+    after_help = "Full command documentation: https://www.cargo-lambda.info/commands/test.html"
+)]
+pub struct Test {
+    /// Path to Cargo.toml
+    #[arg(long, value_name = "PATH", default_value = "Cargo.toml", value_hint = ValueHint::FilePath)]
+    manifest_path: PathBuf,
+
+    /// TOML file with test cases to invoke the functions with, instead of running `cargo test`
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    cases: Option<PathBuf>,
+
+    /// Arguments for `cargo test`, after `--`, like `cargo lambda test -- --test integration`
+    #[arg(last = true, value_name = "ARGS")]
+    test_args: Vec<String>,
+}
+
+/// Test cases in the file passed with `--cases`.
+#[derive(Debug, Deserialize)]
+struct CasesFile {
+    #[serde(default, rename = "case")]
+    cases: Vec<TestCase>,
+}
+
+/// Invocation of a function, and the response that it must return.
+#[derive(Debug, Default, Deserialize)]
+struct TestCase {
+    name: String,
+    /// Function to invoke, the package's only function by default
+    function: Option<String>,
+    /// Payload to send, written in TOML, and sent as JSON
+    payload: Option<toml::Value>,
+    /// File with the JSON payload to send, relative to the cases file
+    payload_file: Option<PathBuf>,
+    /// Fields that the response must include
+    expect: Option<toml::Value>,
+    /// Error type that the function must return, any error if it's empty
+    expect_error: Option<String>,
+}
+
+enum Suite {
+    CargoTest {
+        manifest_path: PathBuf,
+        args: Vec<String>,
+    },
+    Cases {
+        base: PathBuf,
+        cases: Vec<TestCase>,
+    },
+}
+
+impl Test {
+    pub fn manifest_path(&self) -> &Path {
+        &self.manifest_path
+    }
+
+    fn suite(&self) -> Result<Suite> {
+        let Some(path) = &self.cases else {
+            return Ok(Suite::CargoTest {
+                manifest_path: self.manifest_path.clone(),
+                args: self.test_args.clone(),
+            });
+        };
+
+        let content = std::fs::read_to_string(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to read the test cases from {}", path.display()))?;
+        let file: CasesFile = toml::from_str(&content)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("invalid test cases in {}", path.display()))?;
+        if file.cases.is_empty() {
+            return Err(miette::miette!(
+                "the file {} doesn't have any test cases, add them with `[[case]]` tables",
+                path.display()
+            ));
+        }
+
+        let base = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(Suite::Cases {
+            base,
+            cases: file.cases,
+        })
+    }
+}
+
+/// Start the emulator on a free port, run the tests against it, and stop it.
+#[tracing::instrument(skip(test, base_env, metadata), target = "cargo_lambda")]
+pub async fn run_tests(
+    test: &Test,
+    config: &Watch,
+    base_env: &HashMap<String, String>,
+    metadata: &CargoMetadata,
+    color: &str,
+) -> Result<()> {
+    let suite = test.suite()?;
+
+    let mut config = config.clone();
+    config.invoke_address = "127.0.0.1".into();
+    config.invoke_port = free_port()?;
+    config.invoke_socket = None;
+    config.ignore_changes = true;
+
+    let emulator = Emulator::new(&config, base_env, metadata, color).await?;
+    let addr = emulator.runtime_addr();
+    let manifest_dir = emulator.manifest_dir.clone();
+
+    let (tx, rx) = oneshot::channel();
+    with_services(&config, &manifest_dir, async move {
+        let _ = Toplevel::new(move |s| async move {
+            emulator.start(&s);
+            s.start(SubsystemBuilder::new("Test runner", move |s| async move {
+                let result = suite.run(addr).await;
+                let _ = tx.send(result);
+                s.request_shutdown();
+                Ok::<_, miette::Report>(())
+            }));
+        })
+        .catch_signals()
+        .handle_shutdown_requests(Duration::from_secs(1))
+        .await;
+    })
+    .await?;
+
+    rx.await
+        .unwrap_or_else(|_| Err(miette::miette!("the tests were interrupted")))
+}
+
+impl Suite {
+    async fn run(self, addr: SocketAddr) -> Result<()> {
+        wait_for_server(addr).await?;
+        let url = format!("http://{addr}");
+
+        match self {
+            Suite::CargoTest {
+                manifest_path,
+                args,
+            } => run_cargo_test(&url, &manifest_path, &args).await,
+            Suite::Cases { base, cases } => run_cases(&url, &base, &cases).await,
+        }
+    }
+}
+
+async fn run_cargo_test(url: &str, manifest_path: &Path, args: &[String]) -> Result<()> {
+    let status = tokio::process::Command::new("cargo")
+        .arg("test")
+        .arg("--manifest-path")
+        .arg(manifest_path)
+        .args(args)
+        .env(INVOKE_URL_VAR, url)
+        .status()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to run `cargo test`")?;
+
+    if !status.success() {
+        return Err(miette::miette!("`cargo test` failed with {status}"));
+    }
+    Ok(())
+}
+
+async fn run_cases(url: &str, base: &Path, cases: &[TestCase]) -> Result<()> {
+    let client = reqwest::Client::new();
+
+    let mut failed = 0;
+    for case in cases {
+        match run_case(&client, url, base, case).await {
+            Ok(()) => println!("✅ {}", case.name),
+            Err(reason) => {
+                failed += 1;
+                println!("❌ {}: {reason}", case.name);
+            }
+        }
+    }
+
+    println!();
+    println!("{} passed, {failed} failed", cases.len() - failed);
+
+    if failed > 0 {
+        return Err(miette::miette!(
+            "{failed} of {} test cases failed",
+            cases.len()
+        ));
+    }
+    Ok(())
+}
+
+async fn run_case(
+    client: &reqwest::Client,
+    url: &str,
+    base: &Path,
+    case: &TestCase,
+) -> Result<(), String> {
+    let payload = case.payload(base)?;
+    let function = case.function.as_deref().unwrap_or(DEFAULT_PACKAGE_FUNCTION);
+
+    let response = client
+        .post(format!("{url}/2015-03-31/functions/{function}/invocations"))
+        .body(payload)
+        .send()
+        .await
+        .map_err(|e| format!("failed to invoke the function: {e}"))?;
+    let success = response.status().is_success();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("failed to read the response: {e}"))?;
+
+    case.check(success, &body)
+}
+
+impl TestCase {
+    fn payload(&self, base: &Path) -> Result<String, String> {
+        match (&self.payload, &self.payload_file) {
+            (Some(_), Some(_)) => Err("use either `payload` or `payload_file`, not both".into()),
+            (Some(payload), None) => serde_json::to_string(payload).map_err(|e| e.to_string()),
+            (None, Some(file)) => {
+                let path = base.join(file);
+                std::fs::read_to_string(&path)
+                    .map_err(|e| format!("failed to read {}: {e}", path.display()))
+            }
+            (None, None) => Ok("{}".into()),
+        }
+    }
+
+    /// Compare the function's response with the expectations of the test case.
+    fn check(&self, success: bool, body: &str) -> Result<(), String> {
+        let response =
+            serde_json::from_str::<Value>(body).unwrap_or_else(|_| Value::String(body.to_string()));
+
+        if let Some(expected) = &self.expect_error {
+            if success {
+                return Err(format!(
+                    "expected an error, but the function returned {body}"
+                ));
+            }
+            let error_type = response["errorType"].as_str().unwrap_or_default();
+            if !expected.is_empty() && error_type != expected {
+                return Err(format!("expected the error `{expected}`, but got {body}"));
+            }
+            return Ok(());
+        }
+
+        if !success {
+            return Err(format!("the function returned an error: {body}"));
+        }
+
+        if let Some(expected) = &self.expect {
+            let expected = serde_json::to_value(expected).map_err(|e| e.to_string())?;
+            if !includes(&response, &expected) {
+                return Err(format!(
+                    "expected a response with {expected}, but got {body}"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether the actual value includes the expected value. Objects can have more fields than expected.
+fn includes(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => expected
+            .iter()
+            .all(|(key, value)| actual.get(key).is_some_and(|a| includes(a, value))),
+        (actual, expected) => actual == expected,
+    }
+}
+
+/// Ask the system for a free port, so the emulator doesn't collide with other emulators running.
+fn free_port() -> Result<u16> {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .into_diagnostic()
+        .wrap_err("failed to find a free port for the emulator")?;
+    let port = listener.local_addr().into_diagnostic()?.port();
+    Ok(port)
+}
+
+async fn wait_for_server(addr: SocketAddr) -> Result<()> {
+    let start = tokio::time::Instant::now();
+    while TcpStream::connect(addr).await.is_err() {
+        if start.elapsed() > STARTUP_TIMEOUT {
+            return Err(miette::miette!(
+                "the emulator didn't start after {} seconds",
+                STARTUP_TIMEOUT.as_secs()
+            ));
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_cases() {
+        let file: CasesFile = toml::from_str(
+            r#"
+            [[case]]
+            name = "greets by name"
+            payload = { name = "Ferris" }
+            expect = { message = "Hello, Ferris!" }
+
+            [[case]]
+            name = "rejects empty names"
+            function = "greeter"
+            payload_file = "payloads/empty.json"
+            expect_error = "InvalidName"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(2, file.cases.len());
+        assert_eq!(
+            r#"{"name":"Ferris"}"#,
+            file.cases[0].payload(Path::new(".")).unwrap()
+        );
+        assert_eq!(Some("greeter"), file.cases[1].function.as_deref());
+    }
+
+    #[test]
+    fn test_check_response() {
+        let case = TestCase {
+            name: "greets".into(),
+            expect: Some(toml::from_str("message = \"Hello\"").unwrap()),
+            ..Default::default()
+        };
+        assert!(case
+            .check(true, r#"{"message":"Hello","request_id":"1"}"#)
+            .is_ok());
+        assert!(case.check(true, r#"{"message":"Bye"}"#).is_err());
+        assert!(case.check(false, r#"{"errorType":"Boom"}"#).is_err());
+
+        let case = TestCase {
+            name: "fails".into(),
+            expect_error: Some("InvalidName".into()),
+            ..Default::default()
+        };
+        assert!(case
+            .check(
+                false,
+                r#"{"errorType":"InvalidName","errorMessage":"empty"}"#
+            )
+            .is_ok());
+        assert!(case.check(false, r#"{"errorType":"Other"}"#).is_err());
+        assert!(case.check(true, "{}").is_err());
+    }
+
+    #[test]
+    fn test_includes() {
+        let actual = json!({"user": {"name": "Ferris", "id": 1}, "ok": true});
+        assert!(includes(&actual, &json!({"user": {"name": "Ferris"}})));
+        assert!(!includes(&actual, &json!({"user": {"name": "Corro"}})));
+        assert!(!includes(&actual, &json!({"missing": 1})));
+        assert!(includes(&json!([1, 2]), &json!([1, 2])));
+    }
+}
@@ -1,12 +1,16 @@
 use crate::{
+    alb,
     error::ServerError,
+    history::Invocation,
     requests::*,
     runtime::{LAMBDA_RUNTIME_AWS_REQUEST_ID, LAMBDA_RUNTIME_XRAY_TRACE_HEADER},
-    RefRuntimeState,
+    sigv4, RefRuntimeState,
 };
 use aws_lambda_events::{
     apigw::{
         ApiGatewayV2httpRequest, ApiGatewayV2httpRequestContext,
+        ApiGatewayV2httpRequestContextAuthorizerDescription,
+        ApiGatewayV2httpRequestContextAuthorizerIamDescription,
         ApiGatewayV2httpRequestContextHttpDescription, ApiGatewayV2httpResponse,
     },
     encodings::Body as LambdaBody,
@@ -15,15 +19,22 @@ use axum::{
     body::Body,
     extract::{Extension, Path, State},
     http::{response::Builder, HeaderValue, Request},
-    response::Response,
-    routing::{any, post},
-    Router,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
+    },
+    routing::{any, get, post},
+    Json, Router,
 };
 use base64::{engine::general_purpose as b64, Engine as _};
-use cargo_lambda_metadata::DEFAULT_PACKAGE_FUNCTION;
+use bytes::Bytes;
+use cargo_lambda_metadata::{
+    cargo::watch::IamCredentials, CARGO_LAMBDA_COLD_START_HEADER, DEFAULT_PACKAGE_FUNCTION,
+};
 use chrono::Utc;
+use futures_util::Stream;
 use http::Method;
-use http_body_util::BodyExt;
+use http_body_util::{BodyExt, LengthLimitError, Limited};
 use hyper::{header, HeaderMap, StatusCode};
 use miette::Result;
 use opentelemetry::{
@@ -32,21 +43,163 @@ use opentelemetry::{
     Context, KeyValue,
 };
 use query_map::QueryMap;
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+};
 use tokio::sync::{mpsc::Sender, oneshot};
 
 const LAMBDA_URL_PREFIX: &str = "lambda-url";
 
+/// Maximum size of the payload of a synchronous invocation in Lambda.
+const MAX_REQUEST_PAYLOAD_SIZE: usize = 6_291_456;
+/// Maximum size of a buffered response in Lambda, the limit is a bit larger than for requests.
+const MAX_RESPONSE_PAYLOAD_SIZE: usize = 6_291_556;
+/// Maximum size of a streaming response in Lambda.
+const MAX_STREAMING_RESPONSE_SIZE: usize = 20 * 1024 * 1024;
+
+const TEXT_CONTENT_TYPES: [&str; 6] = [
+    "application/json",
+    "application/javascript",
+    "application/xml",
+    "application/yaml",
+    "application/x-www-form-urlencoded",
+    "application/graphql",
+];
+
 pub(crate) fn routes() -> Router<RefRuntimeState> {
     Router::new()
         .route(
             "/2015-03-31/functions/:function_name/invocations",
             post(invoke_handler),
         )
+        .route(
+            "/lambda/functions/:function_name/reloads",
+            get(reloads_handler),
+        )
+        .route("/__lambda/health", get(health_handler))
+        .route("/__lambda/events", get(events_handler))
+        .route("/lambda/invocations", get(invocations_handler))
+        .route(
+            "/lambda/invocations/:request_id/replay",
+            post(replay_handler),
+        )
         .route("/lambda-url/:function_name/*path", any(furls_handler))
+        .route("/lambda-alb/:function_name/*path", any(alb_handler))
         .fallback(furls_handler)
 }
 
+async fn reloads_handler(
+    State(state): State<RefRuntimeState>,
+    Path(function_name): Path<String>,
+) -> Json<FunctionReloads> {
+    let reloads = state.reloads.count(&function_name).await;
+    Json(FunctionReloads {
+        function_name,
+        reloads,
+    })
+}
+
+/// Report the state of the scheduler and the function processes.
+/// It responds with `503 Service Unavailable` until the emulator is ready to receive invocations,
+/// so scripts can poll it before sending requests.
+async fn health_handler(
+    State(state): State<RefRuntimeState>,
+    Extension(cmd_tx): Extension<Sender<Action>>,
+) -> (StatusCode, Json<HealthReport>) {
+    let mut names = state.initial_functions.iter().cloned().collect::<Vec<_>>();
+    for name in state.processes.names().await {
+        if name != DEFAULT_PACKAGE_FUNCTION && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names.sort();
+
+    let mut functions = Vec::with_capacity(names.len());
+    for function_name in names {
+        // The only function in a package starts with the default name when it's not invoked by name.
+        let process_name = if state.is_default_function_enabled()
+            && state.processes.get(&function_name).await == ProcessStatus::NotStarted
+        {
+            DEFAULT_PACKAGE_FUNCTION
+        } else {
+            &function_name
+        };
+
+        let status = state.processes.get(process_name).await;
+        let reloads = state.reloads.count(process_name).await;
+        functions.push(FunctionHealth {
+            function_name,
+            status,
+            reloads,
+        });
+    }
+
+    let scheduler_running = !cmd_tx.is_closed();
+    let ready = scheduler_running
+        && functions
+            .iter()
+            .all(|f| matches!(f.status, ProcessStatus::NotStarted | ProcessStatus::Running));
+
+    let report = HealthReport {
+        ready,
+        scheduler: if scheduler_running {
+            "running"
+        } else {
+            "stopped"
+        },
+        functions,
+    };
+
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
+
+/// Stream the state changes of the functions as server-sent events,
+/// so editor plugins and live-reload tools can react to them.
+async fn events_handler(
+    State(state): State<RefRuntimeState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    Sse::new(state.notifications.events()).keep_alive(KeepAlive::default())
+}
+
+async fn invocations_handler(State(state): State<RefRuntimeState>) -> Json<Vec<Invocation>> {
+    Json(state.history.list().await)
+}
+
+/// Invoke the function again with the payload of a past invocation.
+async fn replay_handler(
+    State(state): State<RefRuntimeState>,
+    Extension(cmd_tx): Extension<Sender<Action>>,
+    Path(request_id): Path<String>,
+) -> Result<Response<Body>, ServerError> {
+    let Some(invocation) = state.history.get(&request_id).await else {
+        return respond_with_missing_invocation(&request_id);
+    };
+    tracing::debug!(%request_id, function_name = %invocation.function_name, "replaying invocation");
+
+    let mut req = Request::builder()
+        .method(Method::POST)
+        .body(Body::from(invocation.request.body.unwrap_or_default()))
+        .map_err(ServerError::ResponseBuild)?;
+
+    let headers = req.headers_mut();
+    headers.extend(invocation.request.headers);
+    headers.remove(LAMBDA_RUNTIME_AWS_REQUEST_ID);
+
+    invoke_handler(
+        State(state),
+        Extension(cmd_tx),
+        Path(invocation.function_name),
+        req,
+    )
+    .await
+}
+
 async fn furls_handler(
     State(state): State<RefRuntimeState>,
     Extension(cmd_tx): Extension<Sender<Action>>,
@@ -66,25 +219,32 @@ async fn furls_handler(
 
     if function_name != DEFAULT_PACKAGE_FUNCTION {
         if let Err(binaries) = state.is_function_available(&function_name) {
-            return respond_with_missing_function(&binaries);
+            return respond_with_missing_function(&function_name, &binaries);
         }
     }
 
+    if state.is_alb_function(&function_name) {
+        let req = Request::from_parts(parts, body);
+        return alb_invocation(&state, &cmd_tx, function_name, path, req).await;
+    }
+
     let headers = &parts.headers;
 
-    let body = body
-        .collect()
-        .await
-        .map_err(ServerError::DataDeserialization)?
-        .to_bytes();
-    let text_content_type = match headers.get("content-type") {
-        None => true,
-        Some(c) => {
-            let c = c.to_str().unwrap_or_default();
-            c.starts_with("text/") || c.starts_with("application/json")
-        }
+    let limit = state.payload_limits.then_some(MAX_REQUEST_PAYLOAD_SIZE);
+    let Some(body) = read_body(body, limit).await? else {
+        return respond_with_request_too_large(true);
+    };
+
+    let identity = match &state.function_url_credentials {
+        None => None,
+        Some(credentials) => match sigv4::verify_request(&parts, &body, credentials) {
+            Ok(identity) => Some(identity),
+            Err(reason) => return respond_with_forbidden(&function_name, reason),
+        },
     };
 
+    let text_content_type = is_text_content_type(headers);
+
     let (body, is_base64_encoded) = if body.is_empty() {
         (None, false)
     } else if text_content_type {
@@ -137,8 +297,8 @@ async fn furls_handler(
         },
         time: Some(time.format("%d/%b/%Y:%T %z").to_string()),
         time_epoch: time.timestamp(),
-        account_id: None,
-        authorizer: None,
+        account_id: identity.map(IamCredentials::account_id),
+        authorizer: identity.map(iam_authorizer),
         authentication: None,
         apiid: None,
     };
@@ -165,6 +325,9 @@ async fn furls_handler(
         .get::<StatusCode>()
         .cloned()
         .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    if status_code == StatusCode::TOO_MANY_REQUESTS {
+        return respond_with_too_many_requests(true);
+    }
 
     let (info, mut body) = resp.into_parts();
 
@@ -173,10 +336,15 @@ async fn furls_handler(
     let response = if status_code == StatusCode::OK {
         if is_streaming_response(&info.headers) {
             let status = create_streaming_response(&mut builder, &mut body).await?;
+            let body = limit_streaming_body(&state, body);
 
             builder.status(status).body(body)
         } else {
-            let (status, body) = create_buffered_response(&mut builder, &mut body).await?;
+            let limit = state.payload_limits.then_some(MAX_RESPONSE_PAYLOAD_SIZE);
+            let Some(body) = read_body(body, limit).await? else {
+                return respond_with_response_too_large(true);
+            };
+            let (status, body) = create_buffered_response(&mut builder, &body)?;
 
             builder.status(status).body(body)
         }
@@ -187,6 +355,86 @@ async fn furls_handler(
     response.map_err(ServerError::ResponseBuild)
 }
 
+/// Send HTTP requests to a function as an Application Load Balancer does.
+async fn alb_handler(
+    State(state): State<RefRuntimeState>,
+    Extension(cmd_tx): Extension<Sender<Action>>,
+    Path((function_name, path)): Path<(String, String)>,
+    req: Request<Body>,
+) -> Result<Response<Body>, ServerError> {
+    tracing::debug!(%function_name, %path, method = %req.method(), "load balancer invocation received");
+
+    if function_name == DEFAULT_PACKAGE_FUNCTION && !state.is_default_function_enabled() {
+        return respond_with_disabled_default_function(&state, false);
+    }
+
+    if function_name != DEFAULT_PACKAGE_FUNCTION {
+        if let Err(binaries) = state.is_function_available(&function_name) {
+            return respond_with_missing_function(&function_name, &binaries);
+        }
+    }
+
+    alb_invocation(&state, &cmd_tx, function_name, format!("/{path}"), req).await
+}
+
+async fn alb_invocation(
+    state: &RefRuntimeState,
+    cmd_tx: &Sender<Action>,
+    function_name: String,
+    path: String,
+    req: Request<Body>,
+) -> Result<Response<Body>, ServerError> {
+    let (parts, body) = req.into_parts();
+
+    let limit = state.payload_limits.then_some(alb::MAX_ALB_PAYLOAD_SIZE);
+    let Some(body) = read_body(body, limit).await? else {
+        return alb::respond_with_request_too_large();
+    };
+
+    let health_check = alb::is_health_check(&parts.headers);
+    let event = alb::request_event(
+        &function_name,
+        &parts,
+        &path,
+        &body,
+        state.alb_multi_value_headers,
+    );
+
+    let req = Request::from_parts(parts, Body::from(event.to_string()));
+    let resp = schedule_invocation(cmd_tx, function_name.clone(), req).await?;
+    let status_code = resp
+        .extensions()
+        .get::<StatusCode>()
+        .cloned()
+        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+    if status_code != StatusCode::OK {
+        return alb::respond_with_bad_gateway(&function_name, "the function returned an error");
+    }
+
+    let Some(body) = read_body(resp.into_body(), limit).await? else {
+        return alb::respond_with_bad_gateway(&function_name, "the response is too large");
+    };
+
+    let Some((status, headers, body)) = alb::http_response(&body) else {
+        return alb::respond_with_bad_gateway(
+            &function_name,
+            "the response is not a valid target group response",
+        );
+    };
+
+    if health_check {
+        let healthy = status == StatusCode::OK;
+        tracing::info!(function = ?function_name, %status, healthy, "load balancer health check");
+    }
+
+    let mut response = Response::builder().status(status);
+    if let Some(h) = response.headers_mut() {
+        h.extend(headers);
+    }
+    response.body(body).map_err(ServerError::ResponseBuild)
+}
+
 async fn invoke_handler(
     State(state): State<RefRuntimeState>,
     Extension(cmd_tx): Extension<Sender<Action>>,
@@ -202,30 +450,77 @@ async fn invoke_handler(
 
     if function_name != DEFAULT_PACKAGE_FUNCTION {
         if let Err(binaries) = state.is_function_available(&function_name) {
-            return respond_with_missing_function(&binaries);
+            return respond_with_missing_function(&function_name, &binaries);
         }
     }
 
+    let (parts, body) = req.into_parts();
+    let limit = state.payload_limits.then_some(MAX_REQUEST_PAYLOAD_SIZE);
+    let Some(body) = read_body(body, limit).await? else {
+        return respond_with_request_too_large(false);
+    };
+    let req = Request::from_parts(parts, Body::from(body));
+
     let resp = schedule_invocation(&cmd_tx, function_name, req).await?;
     let status_code = resp
         .extensions()
         .get::<StatusCode>()
         .cloned()
         .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    if status_code == StatusCode::TOO_MANY_REQUESTS {
+        return respond_with_too_many_requests(false);
+    }
+
+    let cold_start = resp.extensions().get::<ColdStart>().cloned();
 
     let (info, mut body) = resp.into_parts();
 
     let mut builder = Response::builder().status(status_code);
+    if let Some(ColdStart(cold_start)) = cold_start {
+        builder = builder.header(CARGO_LAMBDA_COLD_START_HEADER, cold_start.to_string());
+    }
 
     if is_streaming_response(&info.headers) && status_code == StatusCode::OK {
         let status = create_streaming_response(&mut builder, &mut body).await?;
         builder = builder.status(status);
+        body = limit_streaming_body(&state, body);
+    } else if state.payload_limits {
+        let Some(bytes) = read_body(body, Some(MAX_RESPONSE_PAYLOAD_SIZE)).await? else {
+            return respond_with_response_too_large(false);
+        };
+        body = Body::from(bytes);
     }
 
     builder.body(body).map_err(ServerError::ResponseBuild)
 }
 
-async fn schedule_invocation(
+/// Read a request or a response body completely.
+/// It returns `None` if the body is larger than the limit.
+async fn read_body(body: Body, limit: Option<usize>) -> Result<Option<Bytes>, ServerError> {
+    let Some(limit) = limit else {
+        let body = body
+            .collect()
+            .await
+            .map_err(ServerError::DataDeserialization)?;
+        return Ok(Some(body.to_bytes()));
+    };
+
+    match Limited::new(body, limit).collect().await {
+        Ok(body) => Ok(Some(body.to_bytes())),
+        Err(error) if error.is::<LengthLimitError>() => Ok(None),
+        Err(error) => Err(ServerError::DataDeserialization(axum::Error::new(error))),
+    }
+}
+
+/// Cut streaming responses when they reach the Lambda limit, like Lambda does.
+fn limit_streaming_body(state: &RefRuntimeState, body: Body) -> Body {
+    if !state.payload_limits {
+        return body;
+    }
+    Body::new(Limited::new(body, MAX_STREAMING_RESPONSE_SIZE))
+}
+
+pub(crate) async fn schedule_invocation(
     cmd_tx: &Sender<Action>,
     function_name: String,
     mut req: Request<Body>,
@@ -365,17 +660,11 @@ fn is_streaming_response(headers: &HeaderMap) -> bool {
         .unwrap_or_default()
 }
 
-async fn create_buffered_response(
+fn create_buffered_response(
     builder: &mut Builder,
-    body: &mut Body,
+    body: &[u8],
 ) -> Result<(StatusCode, Body), ServerError> {
-    let body = body
-        .collect()
-        .await
-        .map_err(ServerError::DataDeserialization)?
-        .to_bytes();
-    let resp_event: ApiGatewayV2httpResponse =
-        serde_json::from_slice(&body).map_err(ServerError::SerializationError)?;
+    let resp_event = function_url_response(body)?;
 
     let is_base64_encoded = resp_event.is_base64_encoded;
     let resp_body = match resp_event.body.unwrap_or(LambdaBody::Empty) {
@@ -406,6 +695,50 @@ async fn create_buffered_response(
     Ok((status, resp_body))
 }
 
+/// Function URLs send request bodies as text only for these content types,
+/// other bodies are encoded in base64.
+pub(crate) fn is_text_content_type(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(header::CONTENT_TYPE) else {
+        return true;
+    };
+
+    let content_type = content_type
+        .to_str()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+
+    mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || TEXT_CONTENT_TYPES.contains(&mime)
+}
+
+/// Function URLs accept responses that are not in the API Gateway v2 format.
+/// In that case, the whole payload is returned as a JSON body with a 200 status code.
+fn function_url_response(body: &[u8]) -> Result<ApiGatewayV2httpResponse, ServerError> {
+    let payload: serde_json::Value =
+        serde_json::from_slice(body).map_err(ServerError::SerializationError)?;
+
+    if payload.get("statusCode").is_some() {
+        return serde_json::from_value(payload).map_err(ServerError::SerializationError);
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+
+    Ok(ApiGatewayV2httpResponse {
+        status_code: StatusCode::OK.as_u16() as i64,
+        headers,
+        body: Some(LambdaBody::Text(payload.to_string())),
+        is_base64_encoded: false,
+        ..Default::default()
+    })
+}
+
 fn respond_with_disabled_default_function(
     state: &RefRuntimeState,
     invoke_call: bool,
@@ -430,16 +763,145 @@ fn respond_with_disabled_default_function(
         .map_err(ServerError::ResponseBuild)
 }
 
+/// Identity of the IAM credentials that signed a function URL request, like Lambda sends it
+/// when the function URL uses `AWS_IAM` authorization.
+fn iam_authorizer(
+    identity: &IamCredentials,
+) -> ApiGatewayV2httpRequestContextAuthorizerDescription {
+    let user_arn = identity.user_arn();
+    let user_id = user_arn.rsplit('/').next().unwrap_or_default().to_string();
+
+    ApiGatewayV2httpRequestContextAuthorizerDescription {
+        iam: Some(ApiGatewayV2httpRequestContextAuthorizerIamDescription {
+            access_key: Some(identity.access_key_id.clone()),
+            account_id: Some(identity.account_id()),
+            caller_id: Some(user_id.clone()),
+            user_arn: Some(user_arn),
+            user_id: Some(user_id),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+fn respond_with_forbidden(
+    function_name: &str,
+    reason: sigv4::SignatureError,
+) -> Result<Response<Body>, ServerError> {
+    tracing::warn!(function = ?function_name, %reason, "function URL request rejected");
+
+    let body = Body::from(serde_json::json!({ "Message": "Forbidden" }).to_string());
+    Response::builder()
+        .status(StatusCode::FORBIDDEN)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .map_err(ServerError::ResponseBuild)
+}
+
+/// Lambda rejects requests larger than the payload limit before invoking the function.
+/// Function URLs use their own error format.
+fn respond_with_request_too_large(function_url: bool) -> Result<Response<Body>, ServerError> {
+    let message = format!(
+        "Request must be smaller than {MAX_REQUEST_PAYLOAD_SIZE} bytes for the InvokeFunction operation"
+    );
+    tracing::error!(
+        limit = MAX_REQUEST_PAYLOAD_SIZE,
+        "request payload too large, use `--disable-payload-limits` to allow it"
+    );
+
+    let body = if function_url {
+        serde_json::json!({ "Message": message })
+    } else {
+        serde_json::json!({ "Type": "User", "message": message })
+    };
+    Response::builder()
+        .status(StatusCode::PAYLOAD_TOO_LARGE)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("x-amzn-ErrorType", "RequestEntityTooLargeException")
+        .body(Body::from(body.to_string()))
+        .map_err(ServerError::ResponseBuild)
+}
+
+/// Lambda throttles invocations when the function can't take more requests.
+/// Function URLs use their own error format.
+fn respond_with_too_many_requests(function_url: bool) -> Result<Response<Body>, ServerError> {
+    let message = "Rate Exceeded.";
+    let body = if function_url {
+        serde_json::json!({ "Message": message })
+    } else {
+        serde_json::json!({ "Type": "User", "message": message })
+    };
+    Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("x-amzn-ErrorType", "TooManyRequestsException")
+        .body(Body::from(body.to_string()))
+        .map_err(ServerError::ResponseBuild)
+}
+
+/// Lambda replaces responses larger than the payload limit with an error.
+fn respond_with_response_too_large(function_url: bool) -> Result<Response<Body>, ServerError> {
+    let message = format!(
+        "Response payload size exceeded maximum allowed payload size ({MAX_RESPONSE_PAYLOAD_SIZE} bytes)."
+    );
+    tracing::error!(
+        limit = MAX_RESPONSE_PAYLOAD_SIZE,
+        "response payload too large, use `--disable-payload-limits` to allow it"
+    );
+
+    let builder = Response::builder().header(header::CONTENT_TYPE, "application/json");
+    let (builder, body) = if function_url {
+        let body = serde_json::json!({ "Message": message });
+        (builder.status(StatusCode::BAD_GATEWAY), body)
+    } else {
+        let body = serde_json::json!({
+            "errorType": "Function.ResponseSizeTooLarge",
+            "errorMessage": message,
+        });
+        let builder = builder
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("x-amz-function-error", "Unhandled");
+        (builder, body)
+    };
+    builder
+        .body(Body::from(body.to_string()))
+        .map_err(ServerError::ResponseBuild)
+}
+
+fn respond_with_missing_invocation(request_id: &str) -> Result<Response<Body>, ServerError> {
+    let body = Body::from(
+        serde_json::json!({
+            "title": "Missing invocation",
+            "detail": format!("the invocation {request_id} is not in the history"),
+        })
+        .to_string(),
+    );
+    Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .body(body)
+        .map_err(ServerError::ResponseBuild)
+}
+
 fn respond_with_missing_function(
+    function_name: &str,
     binaries: &HashSet<String>,
 ) -> Result<Response<Body>, ServerError> {
-    let detail = "that function doesn't exist as a binary in your project";
-    tracing::error!(available_functions = ?binaries, detail);
+    let mut available_functions = binaries.iter().collect::<Vec<_>>();
+    available_functions.sort();
+
+    let suggestion = closest_function(function_name, binaries);
+    let detail = match suggestion {
+        Some(suggestion) => format!("the function `{function_name}` doesn't exist as a binary in your project, did you mean `{suggestion}`?"),
+        None => format!("the function `{function_name}` doesn't exist as a binary in your project"),
+    };
+    tracing::error!(?available_functions, detail);
 
     let body = Body::from(
         serde_json::json!({
             "title": "Missing function",
-            "detail": format!("{}. Available functions: {:?}", detail, binaries),
+            "detail": detail,
+            "available_functions": available_functions,
+            "suggestion": suggestion,
         })
         .to_string(),
     );
@@ -449,6 +911,20 @@ fn respond_with_missing_function(
         .map_err(ServerError::ResponseBuild)
 }
 
+/// Binary with the closest name to a function name that doesn't exist, to suggest it when the name has a typo.
+/// Names that are too different from every binary don't have a suggestion.
+pub(crate) fn closest_function<'a>(
+    function_name: &str,
+    binaries: &'a HashSet<String>,
+) -> Option<&'a str> {
+    binaries
+        .iter()
+        .map(|binary| (strsim::levenshtein(function_name, binary), binary.as_str()))
+        .filter(|(distance, binary)| *distance <= function_name.len().max(binary.len()).div_ceil(3))
+        .min_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)))
+        .map(|(_, binary)| binary)
+}
+
 #[cfg(test)]
 mod test {
     use std::{
@@ -460,12 +936,57 @@ mod test {
 
     use crate::RuntimeState;
 
-    use super::extract_path_parameters;
+    use super::{
+        closest_function, extract_path_parameters, function_url_response, is_text_content_type,
+        read_body, respond_with_missing_function, MAX_REQUEST_PAYLOAD_SIZE,
+    };
+    use aws_lambda_events::encodings::Body as LambdaBody;
+    use axum::body::Body;
     use cargo_lambda_metadata::{
         cargo::watch::{FunctionRouter, FunctionRoutes},
         DEFAULT_PACKAGE_FUNCTION,
     };
-    use http::Method;
+    use http::{header, HeaderMap, HeaderValue, Method};
+
+    #[test]
+    fn test_closest_function() {
+        let binaries = HashSet::from([
+            "orders".to_string(),
+            "order-events".to_string(),
+            "payments".to_string(),
+        ]);
+
+        assert_eq!(Some("orders"), closest_function("oders", &binaries));
+        assert_eq!(Some("payments"), closest_function("payment", &binaries));
+        assert_eq!(
+            Some("order-events"),
+            closest_function("order_events", &binaries)
+        );
+        assert_eq!(None, closest_function("inventory", &binaries));
+        assert_eq!(None, closest_function("orders", &HashSet::new()));
+    }
+
+    #[tokio::test]
+    async fn test_respond_with_missing_function() {
+        let binaries = HashSet::from(["payments".to_string(), "orders".to_string()]);
+        let response = respond_with_missing_function("oders", &binaries).unwrap();
+        assert_eq!(http::StatusCode::NOT_FOUND, response.status());
+
+        let body = read_body(response.into_body(), None)
+            .await
+            .unwrap()
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            serde_json::json!(["orders", "payments"]),
+            body["available_functions"]
+        );
+        assert_eq!("orders", body["suggestion"]);
+        assert_eq!(
+            "the function `oders` doesn't exist as a binary in your project, did you mean `orders`?",
+            body["detail"]
+        );
+    }
 
     #[test]
     fn test_extract_path_parameters() {
@@ -527,4 +1048,86 @@ mod test {
         assert_eq!("bar", func);
         assert_eq!("/foo", path);
     }
+
+    #[test]
+    fn test_is_text_content_type() {
+        let mut headers = HeaderMap::new();
+        assert!(is_text_content_type(&headers));
+
+        for (content_type, expected) in [
+            ("text/html; charset=utf-8", true),
+            ("application/json", true),
+            ("Application/JSON", true),
+            ("application/vnd.api+json", true),
+            ("application/x-www-form-urlencoded", true),
+            ("application/octet-stream", false),
+            ("image/png", false),
+        ] {
+            headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+            assert_eq!(expected, is_text_content_type(&headers), "{content_type}");
+        }
+    }
+
+    #[test]
+    fn test_function_url_response() {
+        let resp = function_url_response(
+            br#"{"statusCode": 201, "body": "aGVsbG8=", "isBase64Encoded": true}"#,
+        )
+        .unwrap();
+        assert_eq!(201, resp.status_code);
+        assert!(resp.is_base64_encoded);
+
+        let resp = function_url_response(br#"{"message": "hello"}"#).unwrap();
+        assert_eq!(200, resp.status_code);
+        assert_eq!("application/json", resp.headers[header::CONTENT_TYPE]);
+        assert_eq!(
+            Some(LambdaBody::Text(r#"{"message":"hello"}"#.into())),
+            resp.body
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_body() {
+        let body = read_body(Body::from("hello"), None).await.unwrap();
+        assert_eq!(Some("hello".into()), body);
+
+        let body = read_body(Body::from("hello"), Some(MAX_REQUEST_PAYLOAD_SIZE))
+            .await
+            .unwrap();
+        assert_eq!(Some("hello".into()), body);
+
+        let large = vec![b'a'; MAX_REQUEST_PAYLOAD_SIZE + 1];
+        let body = read_body(Body::from(large.clone()), Some(MAX_REQUEST_PAYLOAD_SIZE))
+            .await
+            .unwrap();
+        assert!(body.is_none());
+
+        let body = read_body(Body::from(large), None).await.unwrap();
+        assert_eq!(Some(MAX_REQUEST_PAYLOAD_SIZE + 1), body.map(|b| b.len()));
+    }
+
+    #[tokio::test]
+    async fn test_respond_with_too_many_requests() {
+        let response = respond_with_too_many_requests(false).unwrap();
+        assert_eq!(http::StatusCode::TOO_MANY_REQUESTS, response.status());
+        assert_eq!(
+            "TooManyRequestsException",
+            response.headers()["x-amzn-ErrorType"]
+        );
+
+        let body = read_body(response.into_body(), None)
+            .await
+            .unwrap()
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!("Rate Exceeded.", body["message"]);
+
+        let response = respond_with_too_many_requests(true).unwrap();
+        let body = read_body(response.into_body(), None)
+            .await
+            .unwrap()
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!("Rate Exceeded.", body["Message"]);
+    }
 }
@@ -0,0 +1,531 @@
+use crate::{error::ServerError, requests::Action};
+use aws_sdk_dynamodbstreams::{
+    types::{AttributeValue, Record, Shard, ShardIteratorType},
+    Client,
+};
+use base64::{engine::general_purpose as b64, Engine as _};
+use cargo_lambda_remote::RemoteConfig;
+use serde_json::{json, Map, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::Duration,
+};
+use tokio::sync::mpsc::Sender;
+use tokio_graceful_shutdown::SubsystemHandle;
+use tracing::{debug, error, info};
+
+use super::invoke_function;
+
+/// How often the shards are read when they don't have new records.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How often the list of shards is refreshed to find new shards.
+const SHARDS_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+/// Longest time to wait before reading a shard again after consecutive errors.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+/// Maximum number of records that DynamoDB Streams returns in each request.
+pub(super) const MAX_BATCH_SIZE: u16 = 1000;
+
+struct ShardReader {
+    parent_shard_id: Option<String>,
+    /// Iterator to read the next records, `None` when it must be requested again.
+    iterator: Option<String>,
+    /// Whether the shard is read from the latest record when there is no checkpoint.
+    latest: bool,
+    /// Consecutive errors reading the shard.
+    errors: u32,
+    retry_at: Option<tokio::time::Instant>,
+}
+
+impl ShardReader {
+    fn failed(&mut self) {
+        self.errors += 1;
+        self.retry_at = Some(tokio::time::Instant::now() + retry_delay(self.errors));
+    }
+}
+
+/// Read records from a DynamoDB stream, and invoke the function with them like an event source mapping.
+/// The sequence number of the last record processed in each shard is saved in a checkpoint file,
+/// so the emulator continues from the same position when it starts again.
+pub(super) async fn poll_stream(
+    subsys: SubsystemHandle,
+    function_name: String,
+    stream_arn: String,
+    remote_config: RemoteConfig,
+    batch_size: u16,
+    cmd_tx: Sender<Action>,
+) -> Result<(), ServerError> {
+    let config = RemoteConfig {
+        region: stream_region(&stream_arn).or(remote_config.region),
+        ..remote_config
+    };
+    let sdk_config = config.sdk_config(None).await?;
+    let client = Client::new(&sdk_config);
+
+    let mut checkpoints = Checkpoints::load(&stream_arn);
+    let mut readers: HashMap<String, ShardReader> = HashMap::new();
+    let mut finished: HashSet<String> = HashSet::new();
+    let mut initial_shards = true;
+    let mut last_refresh: Option<tokio::time::Instant> = None;
+
+    info!(stream = %stream_arn, function = %function_name, "reading records from DynamoDB stream");
+
+    loop {
+        if last_refresh.map_or(true, |t| t.elapsed() >= SHARDS_REFRESH_INTERVAL) {
+            match list_shards(&client, &stream_arn).await {
+                Ok(shards) => {
+                    for shard in shards {
+                        let Some(shard_id) = shard.shard_id() else {
+                            continue;
+                        };
+                        if readers.contains_key(shard_id) || finished.contains(shard_id) {
+                            continue;
+                        }
+
+                        // Shards that exist when the emulator starts are read from the latest record,
+                        // new shards are read from the beginning so no records are lost.
+                        readers.insert(
+                            shard_id.to_string(),
+                            ShardReader {
+                                parent_shard_id: shard.parent_shard_id().map(String::from),
+                                iterator: None,
+                                latest: initial_shards,
+                                errors: 0,
+                                retry_at: None,
+                            },
+                        );
+                    }
+                    initial_shards = false;
+                }
+                Err(error) => {
+                    error!(stream = %stream_arn, %error, "failed to list the shards of the DynamoDB stream")
+                }
+            }
+            last_refresh = Some(tokio::time::Instant::now());
+        }
+
+        let mut processed = 0;
+        let shard_ids = readers.keys().cloned().collect::<Vec<_>>();
+
+        for shard_id in shard_ids {
+            let Some(reader) = readers.get(&shard_id) else {
+                continue;
+            };
+
+            // Records in child shards are processed after the records in their parent shards.
+            let parent_pending = reader
+                .parent_shard_id
+                .as_ref()
+                .is_some_and(|parent| readers.contains_key(parent));
+            let backing_off = reader
+                .retry_at
+                .is_some_and(|t| t > tokio::time::Instant::now());
+            if parent_pending || backing_off {
+                continue;
+            }
+
+            let iterator = match reader.iterator.clone() {
+                Some(iterator) => iterator,
+                None => {
+                    let latest = reader.latest;
+                    let iterator = shard_iterator(
+                        &client,
+                        &stream_arn,
+                        &shard_id,
+                        checkpoints.get(&shard_id),
+                        latest,
+                    )
+                    .await;
+
+                    match iterator {
+                        Ok(Some(iterator)) => iterator,
+                        // The shard is closed, and its records were already trimmed.
+                        Ok(None) => {
+                            debug!(stream = %stream_arn, %shard_id, "DynamoDB stream shard closed");
+                            readers.remove(&shard_id);
+                            finished.insert(shard_id);
+                            continue;
+                        }
+                        Err(error) => {
+                            error!(stream = %stream_arn, %shard_id, %error, "failed to get the DynamoDB stream shard iterator");
+                            if let Some(reader) = readers.get_mut(&shard_id) {
+                                reader.failed();
+                            }
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            let output = client
+                .get_records()
+                .shard_iterator(&iterator)
+                .limit(batch_size as i32)
+                .send()
+                .await;
+
+            let output = match output {
+                Ok(output) => output,
+                Err(error) => {
+                    error!(stream = %stream_arn, %shard_id, error = %aws_sdk_dynamodbstreams::error::DisplayErrorContext(&error), "failed to read records from DynamoDB stream");
+                    // Expired iterators are requested again, from the last checkpoint.
+                    let expired = error
+                        .as_service_error()
+                        .is_some_and(|e| e.is_expired_iterator_exception());
+                    if let Some(reader) = readers.get_mut(&shard_id) {
+                        reader.iterator = if expired { None } else { Some(iterator) };
+                        reader.failed();
+                    }
+                    continue;
+                }
+            };
+
+            if let Some(reader) = readers.get_mut(&shard_id) {
+                reader.iterator = Some(iterator);
+                reader.errors = 0;
+                reader.retry_at = None;
+            }
+
+            let records = output.records();
+            if !records.is_empty() {
+                debug!(stream = %stream_arn, %shard_id, records = records.len(), "invoking function with DynamoDB records");
+                let event = dynamodb_event(records, &stream_arn);
+
+                match invoke_function(&cmd_tx, &function_name, &event).await {
+                    Ok((status, _)) if status.is_success() => {}
+                    Ok((status, _)) => {
+                        error!(stream = %stream_arn, %status, "the function failed to process the DynamoDB records, retrying the batch");
+                        continue;
+                    }
+                    Err(error) => {
+                        error!(stream = %stream_arn, ?error, "failed to invoke the function with DynamoDB records");
+                        continue;
+                    }
+                }
+
+                let last = records
+                    .last()
+                    .and_then(|r| r.dynamodb())
+                    .and_then(|r| r.sequence_number());
+                if let Some(sequence_number) = last {
+                    checkpoints.save(&shard_id, sequence_number);
+                }
+                processed += records.len();
+            }
+
+            match output.next_shard_iterator() {
+                Some(next) => {
+                    if let Some(reader) = readers.get_mut(&shard_id) {
+                        reader.iterator = Some(next.to_string());
+                    }
+                }
+                None => {
+                    debug!(stream = %stream_arn, %shard_id, "DynamoDB stream shard closed");
+                    readers.remove(&shard_id);
+                    finished.insert(shard_id);
+                }
+            }
+        }
+
+        if processed == 0 {
+            tokio::select! {
+                _ = tokio::time::sleep(POLL_INTERVAL) => {},
+                _ = subsys.on_shutdown_requested() => return Ok(()),
+            }
+        } else if subsys.is_shutdown_requested() {
+            return Ok(());
+        }
+    }
+}
+
+async fn list_shards(client: &Client, stream_arn: &str) -> Result<Vec<Shard>, String> {
+    let mut shards = Vec::new();
+    let mut start_shard_id: Option<String> = None;
+
+    loop {
+        let output = client
+            .describe_stream()
+            .stream_arn(stream_arn)
+            .set_exclusive_start_shard_id(start_shard_id.clone())
+            .send()
+            .await
+            .map_err(|e| aws_sdk_dynamodbstreams::error::DisplayErrorContext(e).to_string())?;
+
+        let Some(description) = output.stream_description() else {
+            return Ok(shards);
+        };
+
+        shards.extend(description.shards().iter().cloned());
+
+        match description.last_evaluated_shard_id() {
+            Some(id) => start_shard_id = Some(id.to_string()),
+            None => return Ok(shards),
+        }
+    }
+}
+
+async fn shard_iterator(
+    client: &Client,
+    stream_arn: &str,
+    shard_id: &str,
+    checkpoint: Option<String>,
+    latest: bool,
+) -> Result<Option<String>, String> {
+    let iterator_type = match (&checkpoint, latest) {
+        (Some(_), _) => ShardIteratorType::AfterSequenceNumber,
+        (None, true) => ShardIteratorType::Latest,
+        (None, false) => ShardIteratorType::TrimHorizon,
+    };
+
+    let output = client
+        .get_shard_iterator()
+        .stream_arn(stream_arn)
+        .shard_id(shard_id)
+        .shard_iterator_type(iterator_type)
+        .set_sequence_number(checkpoint)
+        .send()
+        .await;
+
+    output
+        .map(|output| output.shard_iterator().map(String::from))
+        .map_err(|e| aws_sdk_dynamodbstreams::error::DisplayErrorContext(e).to_string())
+}
+
+/// Time to wait before reading a shard again, doubling with each consecutive error.
+fn retry_delay(errors: u32) -> Duration {
+    let factor = 2u32.saturating_pow(errors.saturating_sub(1));
+    POLL_INTERVAL.saturating_mul(factor).min(MAX_RETRY_DELAY)
+}
+
+/// Stream ARNs have the format `arn:aws:dynamodb:<region>:<account>:table/<table>/stream/<label>`.
+fn stream_region(stream_arn: &str) -> Option<String> {
+    stream_arn
+        .split(':')
+        .nth(3)
+        .filter(|r| !r.is_empty())
+        .map(String::from)
+}
+
+/// Sequence numbers of the last records processed in each shard of a stream.
+struct Checkpoints {
+    path: Option<PathBuf>,
+    sequence_numbers: HashMap<String, String>,
+}
+
+impl Checkpoints {
+    fn load(stream_arn: &str) -> Self {
+        let path = dirs::cache_dir().map(|p| {
+            p.join("cargo-lambda")
+                .join("stream-checkpoints")
+                .join(format!("{}.json", checkpoint_name(stream_arn)))
+        });
+
+        Self::load_from(path)
+    }
+
+    fn load_from(path: Option<PathBuf>) -> Self {
+        let sequence_numbers = path
+            .as_ref()
+            .and_then(|p| std::fs::read(p).ok())
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default();
+
+        Checkpoints {
+            path,
+            sequence_numbers,
+        }
+    }
+
+    fn get(&self, shard_id: &str) -> Option<String> {
+        self.sequence_numbers.get(shard_id).cloned()
+    }
+
+    fn save(&mut self, shard_id: &str, sequence_number: &str) {
+        self.sequence_numbers
+            .insert(shard_id.to_string(), sequence_number.to_string());
+
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| {
+                let content = serde_json::to_vec(&self.sequence_numbers).unwrap_or_default();
+                std::fs::write(path, content)
+            });
+
+        if let Err(error) = result {
+            error!(?path, %error, "failed to save the DynamoDB stream checkpoint");
+        }
+    }
+}
+
+fn checkpoint_name(stream_arn: &str) -> String {
+    stream_arn
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn dynamodb_event(records: &[Record], stream_arn: &str) -> Value {
+    let records = records
+        .iter()
+        .map(|record| {
+            let stream_record = record.dynamodb().map(|r| {
+                let mut value = Map::new();
+                if let Some(date) = r.approximate_creation_date_time() {
+                    value.insert("ApproximateCreationDateTime".into(), json!(date.secs()));
+                }
+                if let Some(keys) = r.keys() {
+                    value.insert("Keys".into(), attribute_map(keys));
+                }
+                if let Some(image) = r.new_image() {
+                    value.insert("NewImage".into(), attribute_map(image));
+                }
+                if let Some(image) = r.old_image() {
+                    value.insert("OldImage".into(), attribute_map(image));
+                }
+                if let Some(sequence_number) = r.sequence_number() {
+                    value.insert("SequenceNumber".into(), json!(sequence_number));
+                }
+                if let Some(size) = r.size_bytes() {
+                    value.insert("SizeBytes".into(), json!(size));
+                }
+                if let Some(view_type) = r.stream_view_type() {
+                    value.insert("StreamViewType".into(), json!(view_type.as_str()));
+                }
+                Value::Object(value)
+            });
+
+            json!({
+                "eventID": record.event_id(),
+                "eventName": record.event_name().map(|n| n.as_str()),
+                "eventVersion": record.event_version(),
+                "eventSource": "aws:dynamodb",
+                "awsRegion": record.aws_region(),
+                "dynamodb": stream_record,
+                "eventSourceARN": stream_arn,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({ "Records": records })
+}
+
+fn attribute_map(attributes: &HashMap<String, AttributeValue>) -> Value {
+    let map = attributes
+        .iter()
+        .map(|(k, v)| (k.clone(), attribute_value(v)))
+        .collect::<Map<_, _>>();
+    Value::Object(map)
+}
+
+/// Attribute values in the same JSON format as DynamoDB events in Lambda, like `{"S": "value"}`.
+fn attribute_value(value: &AttributeValue) -> Value {
+    let encode = |b: &aws_sdk_dynamodbstreams::primitives::Blob| b64::STANDARD.encode(b.as_ref());
+
+    match value {
+        AttributeValue::S(s) => json!({ "S": s }),
+        AttributeValue::N(n) => json!({ "N": n }),
+        AttributeValue::B(b) => json!({ "B": encode(b) }),
+        AttributeValue::Ss(list) => json!({ "SS": list }),
+        AttributeValue::Ns(list) => json!({ "NS": list }),
+        AttributeValue::Bs(list) => json!({ "BS": list.iter().map(encode).collect::<Vec<_>>() }),
+        AttributeValue::M(map) => json!({ "M": attribute_map(map) }),
+        AttributeValue::L(list) => {
+            json!({ "L": list.iter().map(attribute_value).collect::<Vec<_>>() })
+        }
+        AttributeValue::Null(null) => json!({ "NULL": null }),
+        AttributeValue::Bool(b) => json!({ "BOOL": b }),
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_dynamodbstreams::types::{OperationType, StreamRecord};
+
+    #[test]
+    fn test_stream_region() {
+        assert_eq!(
+            Some("eu-west-1".into()),
+            stream_region("arn:aws:dynamodb:eu-west-1:123456789012:table/users/stream/label")
+        );
+        assert_eq!(None, stream_region("arn:aws:dynamodb::123:table/users"));
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(Duration::from_secs(1), retry_delay(1));
+        assert_eq!(Duration::from_secs(2), retry_delay(2));
+        assert_eq!(Duration::from_secs(32), retry_delay(6));
+        assert_eq!(MAX_RETRY_DELAY, retry_delay(7));
+        assert_eq!(MAX_RETRY_DELAY, retry_delay(100));
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoints").join("stream.json");
+
+        let mut checkpoints = Checkpoints::load_from(Some(path.clone()));
+        assert_eq!(None, checkpoints.get("shard-1"));
+        checkpoints.save("shard-1", "100");
+
+        let checkpoints = Checkpoints::load_from(Some(path));
+        assert_eq!(Some("100".into()), checkpoints.get("shard-1"));
+    }
+
+    #[test]
+    fn test_checkpoint_name() {
+        assert_eq!(
+            "arn_aws_dynamodb_us-east-1_123_table_users_stream_2024",
+            checkpoint_name("arn:aws:dynamodb:us-east-1:123:table/users/stream/2024")
+        );
+    }
+
+    #[test]
+    fn test_dynamodb_event() {
+        let record = Record::builder()
+            .event_id("event-1")
+            .event_name(OperationType::Insert)
+            .aws_region("us-east-1")
+            .dynamodb(
+                StreamRecord::builder()
+                    .keys("id", AttributeValue::S("user-1".into()))
+                    .new_image("id", AttributeValue::S("user-1".into()))
+                    .new_image("age", AttributeValue::N("42".into()))
+                    .new_image(
+                        "tags",
+                        AttributeValue::L(vec![
+                            AttributeValue::Bool(true),
+                            AttributeValue::Null(true),
+                        ]),
+                    )
+                    .sequence_number("100")
+                    .build(),
+            )
+            .build();
+
+        let event = dynamodb_event(&[record], "arn:stream");
+        let record = &event["Records"][0];
+        assert_eq!("event-1", record["eventID"]);
+        assert_eq!("INSERT", record["eventName"]);
+        assert_eq!("aws:dynamodb", record["eventSource"]);
+        assert_eq!("arn:stream", record["eventSourceARN"]);
+        assert_eq!("user-1", record["dynamodb"]["Keys"]["id"]["S"]);
+        assert_eq!("42", record["dynamodb"]["NewImage"]["age"]["N"]);
+        assert_eq!(true, record["dynamodb"]["NewImage"]["tags"]["L"][0]["BOOL"]);
+        assert_eq!(true, record["dynamodb"]["NewImage"]["tags"]["L"][1]["NULL"]);
+        assert_eq!("100", record["dynamodb"]["SequenceNumber"]);
+    }
+}
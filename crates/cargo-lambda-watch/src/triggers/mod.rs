@@ -0,0 +1,234 @@
+use crate::{
+    error::ServerError,
+    requests::Action,
+    state::RuntimeState,
+    trigger_router::{closest_function, schedule_invocation},
+};
+use axum::{body::Body, http::Request};
+use bytes::Bytes;
+use cargo_lambda_metadata::{
+    cargo::watch::{Trigger, TriggerSource, Watch},
+    DEFAULT_PACKAGE_FUNCTION,
+};
+use cargo_lambda_remote::RemoteConfig;
+use http_body_util::BodyExt;
+use hyper::StatusCode;
+use serde_json::Value;
+use tokio::sync::mpsc::Sender;
+use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
+
+mod dynamodb;
+mod s3;
+mod schedule;
+use schedule::Schedule;
+mod sqs;
+
+/// Default number of records sent to the function in each invocation.
+const DEFAULT_BATCH_SIZE: u16 = 10;
+
+/// Event sources that invoke functions locally, and their options.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TriggerConfig {
+    pub triggers: Vec<Trigger>,
+    pub batch_size: Option<u16>,
+    pub s3_bucket: Option<String>,
+    /// AWS configuration of the queues and streams that the triggers poll.
+    pub remote_config: RemoteConfig,
+}
+
+impl TriggerConfig {
+    pub(crate) fn new(config: &Watch, remote_config: &RemoteConfig) -> Self {
+        let mut remote_config = remote_config.clone();
+        if let Some(endpoint_url) = &config.trigger_endpoint_url {
+            remote_config.endpoint_url = Some(endpoint_url.clone());
+        }
+
+        TriggerConfig {
+            triggers: config.triggers.clone(),
+            batch_size: config.batch_size,
+            s3_bucket: config.s3_bucket.clone(),
+            remote_config,
+        }
+    }
+}
+
+/// Start a subsystem for each event source that invokes functions locally.
+/// Triggers for functions that don't exist fail before they start,
+/// because their events would wait forever for a binary that is never built.
+pub(crate) fn start_triggers(
+    subsys: &SubsystemHandle,
+    state: &RuntimeState,
+    config: TriggerConfig,
+    cmd_tx: Sender<Action>,
+) -> Result<(), ServerError> {
+    let batch_size = config.batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
+
+    for trigger in config.triggers {
+        let function_name = trigger
+            .function_name
+            .clone()
+            .unwrap_or_else(|| DEFAULT_PACKAGE_FUNCTION.into());
+
+        if function_name != DEFAULT_PACKAGE_FUNCTION {
+            if let Err(binaries) = state.is_function_available(&function_name) {
+                let help = closest_function(&function_name, &binaries)
+                    .map(|suggestion| format!("did you mean `{suggestion}`?"));
+                return Err(ServerError::MissingTriggerFunction(
+                    trigger.to_string(),
+                    function_name,
+                    help,
+                ));
+            }
+        }
+
+        if let Some(max) = max_batch_size(&trigger.source) {
+            if batch_size > max {
+                return Err(ServerError::InvalidBatchSize(
+                    trigger.to_string(),
+                    max,
+                    batch_size,
+                ));
+            }
+        }
+
+        let cmd_tx = cmd_tx.clone();
+        let name = format!("{trigger} trigger");
+
+        match trigger.source {
+            TriggerSource::Sqs { queue_url } => {
+                let remote_config = config.remote_config.clone();
+                subsys.start(SubsystemBuilder::new(name, move |s| {
+                    sqs::poll_queue(
+                        s,
+                        function_name,
+                        queue_url,
+                        remote_config,
+                        batch_size,
+                        cmd_tx,
+                    )
+                }));
+            }
+            TriggerSource::Schedule { expression } => {
+                let schedule = expression.parse::<Schedule>()?;
+                subsys.start(SubsystemBuilder::new(name, move |s| {
+                    schedule::run_schedule(s, function_name, schedule, cmd_tx)
+                }));
+            }
+            TriggerSource::S3 { dir } => {
+                let bucket = config.s3_bucket.clone();
+                subsys.start(SubsystemBuilder::new(name, move |s| {
+                    s3::watch_directory(s, function_name, dir, bucket, cmd_tx)
+                }));
+            }
+            TriggerSource::DynamoDb { stream_arn } => {
+                let remote_config = config.remote_config.clone();
+                subsys.start(SubsystemBuilder::new(name, move |s| {
+                    dynamodb::poll_stream(
+                        s,
+                        function_name,
+                        stream_arn,
+                        remote_config,
+                        batch_size,
+                        cmd_tx,
+                    )
+                }));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Maximum number of records that an event source can send in each invocation,
+/// for the event sources that send records in batches.
+fn max_batch_size(source: &TriggerSource) -> Option<u16> {
+    match source {
+        TriggerSource::Sqs { .. } => Some(sqs::MAX_BATCH_SIZE),
+        TriggerSource::DynamoDb { .. } => Some(dynamodb::MAX_BATCH_SIZE),
+        TriggerSource::Schedule { .. } | TriggerSource::S3 { .. } => None,
+    }
+}
+
+/// Invoke a function with an event from an event source,
+/// and return the status code and the body of the function response.
+async fn invoke_function(
+    cmd_tx: &Sender<Action>,
+    function_name: &str,
+    event: &Value,
+) -> Result<(StatusCode, Bytes), ServerError> {
+    let body = serde_json::to_vec(event)?;
+    let req = Request::builder()
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(body))?;
+
+    let resp = schedule_invocation(cmd_tx, function_name.to_string(), req).await?;
+    let status = resp
+        .extensions()
+        .get::<StatusCode>()
+        .cloned()
+        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+    let body = resp
+        .into_body()
+        .collect()
+        .await
+        .map_err(ServerError::DataDeserialization)?
+        .to_bytes();
+
+    Ok((status, body))
+}
+
+/// Identifiers of the records that the function reported as failed
+/// in a partial batch response: `{"batchItemFailures": [{"itemIdentifier": "id"}]}`.
+fn batch_item_failures(body: &[u8]) -> Vec<String> {
+    let Ok(response) = serde_json::from_slice::<Value>(body) else {
+        return Vec::new();
+    };
+
+    response
+        .get("batchItemFailures")
+        .and_then(Value::as_array)
+        .map(|failures| {
+            failures
+                .iter()
+                .filter_map(|f| f.get("itemIdentifier").and_then(Value::as_str))
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_batch_size() {
+        let sqs = TriggerSource::Sqs {
+            queue_url: "https://sqs.us-east-1.amazonaws.com/123456789012/orders".into(),
+        };
+        assert_eq!(Some(10), max_batch_size(&sqs));
+
+        let dynamodb = TriggerSource::DynamoDb {
+            stream_arn: "arn:aws:dynamodb:us-east-1:123:table/users/stream/2024".into(),
+        };
+        assert_eq!(Some(1000), max_batch_size(&dynamodb));
+
+        let schedule = TriggerSource::Schedule {
+            expression: "rate(1 minute)".into(),
+        };
+        assert_eq!(None, max_batch_size(&schedule));
+    }
+
+    #[test]
+    fn test_batch_item_failures() {
+        let body =
+            br#"{"batchItemFailures":[{"itemIdentifier":"id-1"},{"itemIdentifier":"id-2"}]}"#;
+        assert_eq!(vec!["id-1", "id-2"], batch_item_failures(body));
+
+        assert!(batch_item_failures(br#"{"batchItemFailures":[]}"#).is_empty());
+        assert!(batch_item_failures(b"null").is_empty());
+        assert!(batch_item_failures(b"").is_empty());
+    }
+}
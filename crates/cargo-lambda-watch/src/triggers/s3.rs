@@ -0,0 +1,258 @@
+use crate::{error::ServerError, requests::Action};
+use cargo_lambda_metadata::DEFAULT_REGION;
+use chrono::{SecondsFormat, Utc};
+use md5::{Digest, Md5};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use tokio::sync::mpsc::Sender;
+use tokio_graceful_shutdown::SubsystemHandle;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use super::invoke_function;
+
+const DEFAULT_BUCKET: &str = "local-bucket";
+/// How often the directory is scanned for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, PartialEq)]
+struct FileState {
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Change {
+    Created {
+        key: String,
+        path: PathBuf,
+        size: u64,
+    },
+    Removed {
+        key: String,
+    },
+}
+
+/// Watch a local directory, and invoke the function with an S3 event
+/// every time a file is created, updated, or removed.
+pub(super) async fn watch_directory(
+    subsys: SubsystemHandle,
+    function_name: String,
+    dir: PathBuf,
+    bucket: Option<String>,
+    cmd_tx: Sender<Action>,
+) -> Result<(), ServerError> {
+    let bucket = bucket.unwrap_or_else(|| default_bucket(&dir));
+    info!(dir = ?dir, %bucket, function = %function_name, "sending S3 events for changes in directory");
+
+    let mut snapshot = scan(&dir);
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {},
+            _ = subsys.on_shutdown_requested() => return Ok(()),
+        }
+
+        let current = scan(&dir);
+        let changes = diff(&dir, &snapshot, &current);
+        snapshot = current;
+
+        for change in changes {
+            debug!(?change, "invoking function with S3 event");
+            let event = s3_event(&bucket, &change);
+
+            match invoke_function(&cmd_tx, &function_name, &event).await {
+                Ok((status, _)) if status.is_success() => {}
+                Ok((status, _)) => {
+                    error!(%bucket, %status, "the function failed to process the S3 event")
+                }
+                Err(error) => {
+                    error!(%bucket, ?error, "failed to invoke the function with the S3 event")
+                }
+            }
+        }
+    }
+}
+
+fn default_bucket(dir: &Path) -> String {
+    dunce::canonicalize(dir)
+        .ok()
+        .and_then(|d| d.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| DEFAULT_BUCKET.into())
+}
+
+fn scan(dir: &Path) -> HashMap<PathBuf, FileState> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let state = FileState {
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+            };
+            Some((entry.into_path(), state))
+        })
+        .collect()
+}
+
+fn diff(
+    dir: &Path,
+    previous: &HashMap<PathBuf, FileState>,
+    current: &HashMap<PathBuf, FileState>,
+) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for (path, state) in current {
+        if previous.get(path) != Some(state) {
+            changes.push(Change::Created {
+                key: object_key(dir, path),
+                path: path.clone(),
+                size: state.size,
+            });
+        }
+    }
+
+    for path in previous.keys() {
+        if !current.contains_key(path) {
+            changes.push(Change::Removed {
+                key: object_key(dir, path),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Object key for a file, relative to the watched directory,
+/// and URL encoded like in S3 event notifications.
+fn object_key(dir: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(dir).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| form_urlencoded::byte_serialize(c.as_os_str().as_encoded_bytes()).collect())
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+fn etag(path: &Path) -> String {
+    match std::fs::read(path) {
+        Ok(content) => format!("{:x}", Md5::digest(content)),
+        Err(_) => String::new(),
+    }
+}
+
+fn s3_event(bucket: &str, change: &Change) -> Value {
+    let (event_name, object) = match change {
+        Change::Created { key, path, size } => (
+            "ObjectCreated:Put",
+            json!({
+                "key": key,
+                "size": size,
+                "eTag": etag(path),
+                "sequencer": sequencer(),
+            }),
+        ),
+        Change::Removed { key } => (
+            "ObjectRemoved:Delete",
+            json!({ "key": key, "sequencer": sequencer() }),
+        ),
+    };
+
+    json!({
+        "Records": [{
+            "eventVersion": "2.1",
+            "eventSource": "aws:s3",
+            "awsRegion": DEFAULT_REGION,
+            "eventTime": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            "eventName": event_name,
+            "userIdentity": { "principalId": "EXAMPLE" },
+            "requestParameters": { "sourceIPAddress": "127.0.0.1" },
+            "responseElements": {
+                "x-amz-request-id": Uuid::new_v4().simple().to_string().to_uppercase(),
+                "x-amz-id-2": Uuid::new_v4().to_string(),
+            },
+            "s3": {
+                "s3SchemaVersion": "1.0",
+                "configurationId": "cargo-lambda",
+                "bucket": {
+                    "name": bucket,
+                    "ownerIdentity": { "principalId": "EXAMPLE" },
+                    "arn": format!("arn:aws:s3:::{bucket}"),
+                },
+                "object": object,
+            },
+        }]
+    })
+}
+
+fn sequencer() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!("{nanos:X}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_key() {
+        let dir = Path::new("uploads");
+        assert_eq!(
+            "images/my+photo.jpg",
+            object_key(dir, &dir.join("images").join("my photo.jpg"))
+        );
+        assert_eq!("a%2Bb.txt", object_key(dir, &dir.join("a+b.txt")));
+    }
+
+    #[test]
+    fn test_diff() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        std::fs::write(&path, "hello").unwrap();
+
+        let previous = HashMap::new();
+        let current = scan(dir.path());
+        assert_eq!(
+            vec![Change::Created {
+                key: "file.txt".into(),
+                path: path.clone(),
+                size: 5
+            }],
+            diff(dir.path(), &previous, &current)
+        );
+
+        assert!(diff(dir.path(), &current, &current).is_empty());
+
+        assert_eq!(
+            vec![Change::Removed {
+                key: "file.txt".into()
+            }],
+            diff(dir.path(), &current, &HashMap::new())
+        );
+    }
+
+    #[test]
+    fn test_s3_event() {
+        let change = Change::Removed {
+            key: "file.txt".into(),
+        };
+        let event = s3_event("my-bucket", &change);
+        let record = &event["Records"][0];
+        assert_eq!("aws:s3", record["eventSource"]);
+        assert_eq!("ObjectRemoved:Delete", record["eventName"]);
+        assert_eq!("my-bucket", record["s3"]["bucket"]["name"]);
+        assert_eq!("arn:aws:s3:::my-bucket", record["s3"]["bucket"]["arn"]);
+        assert_eq!("file.txt", record["s3"]["object"]["key"]);
+    }
+}
@@ -0,0 +1,373 @@
+use crate::{error::ServerError, requests::Action};
+use cargo_lambda_metadata::{DEFAULT_ACCOUNT_ID, DEFAULT_REGION};
+use chrono::{DateTime, Datelike, Days, SecondsFormat, TimeZone, Timelike, Utc};
+use serde_json::{json, Value};
+use std::{collections::BTreeSet, str::FromStr, time::Duration};
+use tokio::sync::mpsc::Sender;
+use tokio_graceful_shutdown::SubsystemHandle;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use super::invoke_function;
+
+const MAX_YEAR: i32 = 2199;
+
+const MONTH_NAMES: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const DAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// Invoke the function every time the schedule fires, with a synthesized EventBridge scheduled event.
+pub(super) async fn run_schedule(
+    subsys: SubsystemHandle,
+    function_name: String,
+    schedule: Schedule,
+    cmd_tx: Sender<Action>,
+) -> Result<(), ServerError> {
+    info!(%schedule, function = %function_name, "invoking function on schedule");
+
+    loop {
+        let now = Utc::now();
+        let Some(next) = schedule.next_after(now) else {
+            info!(%schedule, "the schedule doesn't fire anymore");
+            subsys.on_shutdown_requested().await;
+            return Ok(());
+        };
+
+        debug!(%schedule, %next, "waiting for the next scheduled invocation");
+        let wait = (next - now).to_std().unwrap_or_default();
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {},
+            _ = subsys.on_shutdown_requested() => return Ok(()),
+        }
+
+        let event = scheduled_event(&function_name, next);
+        match invoke_function(&cmd_tx, &function_name, &event).await {
+            Ok((status, _)) if status.is_success() => {}
+            Ok((status, _)) => {
+                error!(%schedule, %status, "the function failed to process the scheduled event")
+            }
+            Err(error) => {
+                error!(%schedule, ?error, "failed to invoke the function with the scheduled event")
+            }
+        }
+    }
+}
+
+fn scheduled_event(function_name: &str, time: DateTime<Utc>) -> Value {
+    json!({
+        "version": "0",
+        "id": Uuid::new_v4().to_string(),
+        "detail-type": "Scheduled Event",
+        "source": "aws.events",
+        "account": DEFAULT_ACCOUNT_ID,
+        "time": time.to_rfc3339_opts(SecondsFormat::Secs, true),
+        "region": DEFAULT_REGION,
+        "resources": [
+            format!("arn:aws:events:{DEFAULT_REGION}:{DEFAULT_ACCOUNT_ID}:rule/{function_name}-schedule")
+        ],
+        "detail": {},
+    })
+}
+
+/// EventBridge schedule expression: `rate(value unit)` or `cron(fields)`.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Schedule {
+    Rate { raw: String, period: Duration },
+    Cron { raw: String, cron: Cron },
+}
+
+impl Schedule {
+    /// Time when the schedule fires next, after the given time.
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Rate { period, .. } => {
+                Some(after + chrono::Duration::from_std(*period).ok()?)
+            }
+            Schedule::Cron { cron, .. } => cron.next_after(after),
+        }
+    }
+}
+
+impl std::fmt::Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Schedule::Rate { raw, .. } | Schedule::Cron { raw, .. } => write!(f, "{raw}"),
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = ServerError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| ServerError::InvalidSchedule(expression.into(), reason.into());
+        let raw = expression.to_string();
+
+        if let Some(rate) = expression
+            .strip_prefix("rate(")
+            .and_then(|e| e.strip_suffix(')'))
+        {
+            let period = parse_rate(rate).map_err(|e| invalid(&e))?;
+            return Ok(Schedule::Rate { raw, period });
+        }
+
+        if let Some(cron) = expression
+            .strip_prefix("cron(")
+            .and_then(|e| e.strip_suffix(')'))
+        {
+            let cron = Cron::from_str(cron).map_err(|e| invalid(&e))?;
+            return Ok(Schedule::Cron { raw, cron });
+        }
+
+        Err(invalid("use `rate(value unit)` or `cron(fields)`"))
+    }
+}
+
+fn parse_rate(rate: &str) -> Result<Duration, String> {
+    let (value, unit) = rate
+        .trim()
+        .split_once(' ')
+        .ok_or_else(|| "the rate must include a value and a unit".to_string())?;
+
+    let value: u64 = value
+        .parse()
+        .ok()
+        .filter(|v| *v > 0)
+        .ok_or_else(|| format!("invalid rate value `{value}`"))?;
+
+    let seconds = match unit.trim() {
+        "minute" | "minutes" => 60,
+        "hour" | "hours" => 3_600,
+        "day" | "days" => 86_400,
+        unit => {
+            return Err(format!(
+                "invalid rate unit `{unit}`, use minutes, hours, or days"
+            ))
+        }
+    };
+
+    Ok(Duration::from_secs(value * seconds))
+}
+
+/// Cron expression with the six fields that EventBridge supports:
+/// minutes, hours, day of month, month, day of week, and year.
+/// Fields with `None` match any value.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Cron {
+    minutes: Option<BTreeSet<u32>>,
+    hours: Option<BTreeSet<u32>>,
+    days_of_month: Option<BTreeSet<u32>>,
+    months: Option<BTreeSet<u32>>,
+    days_of_week: Option<BTreeSet<u32>>,
+    years: Option<BTreeSet<u32>>,
+}
+
+impl FromStr for Cron {
+    type Err = String;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let fields = expression.split_whitespace().collect::<Vec<_>>();
+        let [minutes, hours, days_of_month, months, days_of_week, years] = fields[..] else {
+            return Err(format!(
+                "cron expressions must have 6 fields, found {}",
+                fields.len()
+            ));
+        };
+
+        if (days_of_month == "?") == (days_of_week == "?") {
+            return Err(
+                "use `?` in either the day-of-month or the day-of-week field, but not both".into(),
+            );
+        }
+
+        Ok(Cron {
+            minutes: parse_field(minutes, 0, 59, &[])?,
+            hours: parse_field(hours, 0, 23, &[])?,
+            days_of_month: parse_field(days_of_month, 1, 31, &[])?,
+            months: parse_field(months, 1, 12, &MONTH_NAMES)?,
+            days_of_week: parse_field(days_of_week, 1, 7, &DAY_NAMES)?,
+            years: parse_field(years, 1970, MAX_YEAR as u32, &[])?,
+        })
+    }
+}
+
+impl Cron {
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut t = after.with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+
+        while t.year() <= MAX_YEAR {
+            if !matches(&self.years, t.year() as u32) {
+                t = Utc.with_ymd_and_hms(t.year() + 1, 1, 1, 0, 0, 0).single()?;
+            } else if !matches(&self.months, t.month()) {
+                let (year, month) = if t.month() == 12 {
+                    (t.year() + 1, 1)
+                } else {
+                    (t.year(), t.month() + 1)
+                };
+                t = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()?;
+            } else if !matches(&self.days_of_month, t.day())
+                || !matches(&self.days_of_week, t.weekday().number_from_sunday())
+            {
+                let next_day = t.date_naive().checked_add_days(Days::new(1))?;
+                t = next_day.and_hms_opt(0, 0, 0)?.and_utc();
+            } else if !matches(&self.hours, t.hour()) {
+                t = t.with_minute(0)? + chrono::Duration::hours(1);
+            } else if !matches(&self.minutes, t.minute()) {
+                t += chrono::Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+
+        None
+    }
+}
+
+fn matches(field: &Option<BTreeSet<u32>>, value: u32) -> bool {
+    field
+        .as_ref()
+        .map_or(true, |values| values.contains(&value))
+}
+
+/// Parse a cron field with lists, ranges, and increments, like `1,15`, `MON-FRI`, or `0/15`.
+fn parse_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+) -> Result<Option<BTreeSet<u32>>, String> {
+    if field == "*" || field == "?" {
+        return Ok(None);
+    }
+
+    let value = |v: &str| -> Result<u32, String> {
+        let position = names.iter().position(|n| n.eq_ignore_ascii_case(v));
+        let value = match position {
+            Some(p) => p as u32 + min,
+            None => v
+                .parse()
+                .map_err(|_| format!("invalid value `{v}` in field `{field}`"))?,
+        };
+
+        if value < min || value > max {
+            return Err(format!(
+                "value `{v}` out of range in field `{field}`, use values between {min} and {max}"
+            ));
+        }
+        Ok(value)
+    };
+
+    let mut values = BTreeSet::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("invalid increment `{step}` in field `{field}`"))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (value(start)?, value(end)?),
+            None if step > 1 => (value(range)?, max),
+            None => {
+                let v = value(range)?;
+                (v, v)
+            }
+        };
+
+        if start > end {
+            return Err(format!("invalid range `{range}` in field `{field}`"));
+        }
+        values.extend((start..=end).step_by(step as usize));
+    }
+
+    Ok(Some(values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_rate() {
+        let schedule = Schedule::from_str("rate(5 minutes)").unwrap();
+        let Schedule::Rate { period, .. } = schedule else {
+            panic!("expected a rate schedule");
+        };
+        assert_eq!(Duration::from_secs(300), period);
+
+        assert!(Schedule::from_str("rate(1 day)").is_ok());
+        assert!(Schedule::from_str("rate(0 minutes)").is_err());
+        assert!(Schedule::from_str("rate(5 weeks)").is_err());
+        assert!(Schedule::from_str("rate(minutes)").is_err());
+    }
+
+    #[test]
+    fn test_parse_cron() {
+        assert!(Schedule::from_str("cron(0 12 * * ? *)").is_ok());
+        assert!(Schedule::from_str("cron(0/15 8-17 ? * MON-FRI *)").is_ok());
+        assert!(Schedule::from_str("cron(0 12 * * *)").is_err());
+        assert!(Schedule::from_str("cron(0 12 * * * *)").is_err());
+        assert!(Schedule::from_str("cron(60 12 * * ? *)").is_err());
+        assert!(Schedule::from_str("cron(0 12 ? FOO MON *)").is_err());
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let schedule = Schedule::from_str("cron(0 12 * * ? *)").unwrap();
+        assert_eq!(
+            Some(time("2024-03-01T12:00:00Z")),
+            schedule.next_after(time("2024-03-01T10:30:15Z"))
+        );
+        assert_eq!(
+            Some(time("2024-03-02T12:00:00Z")),
+            schedule.next_after(time("2024-03-01T12:00:00Z"))
+        );
+
+        // 2024-03-01 is a Friday.
+        let schedule = Schedule::from_str("cron(0/15 8-17 ? * MON-FRI *)").unwrap();
+        assert_eq!(
+            Some(time("2024-03-04T08:00:00Z")),
+            schedule.next_after(time("2024-03-01T17:50:00Z"))
+        );
+        assert_eq!(
+            Some(time("2024-03-01T10:15:00Z")),
+            schedule.next_after(time("2024-03-01T10:07:00Z"))
+        );
+
+        let schedule = Schedule::from_str("cron(0 0 1 JAN ? 2020)").unwrap();
+        assert_eq!(None, schedule.next_after(time("2024-03-01T00:00:00Z")));
+    }
+
+    #[test]
+    fn test_rate_next_after() {
+        let schedule = Schedule::from_str("rate(1 hour)").unwrap();
+        assert_eq!(
+            Some(time("2024-03-01T11:30:00Z")),
+            schedule.next_after(time("2024-03-01T10:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn test_scheduled_event() {
+        let event = scheduled_event("my-function", time("2024-03-01T12:00:00Z"));
+        assert_eq!("Scheduled Event", event["detail-type"]);
+        assert_eq!("aws.events", event["source"]);
+        assert_eq!("2024-03-01T12:00:00Z", event["time"]);
+        assert_eq!(
+            "arn:aws:events:us-east-1:123456789012:rule/my-function-schedule",
+            event["resources"][0]
+        );
+    }
+}
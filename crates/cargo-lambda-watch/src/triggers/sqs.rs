@@ -0,0 +1,356 @@
+use crate::{error::ServerError, requests::Action};
+use aws_sdk_sqs::{
+    types::{
+        DeleteMessageBatchRequestEntry, Message, MessageAttributeValue, MessageSystemAttributeName,
+        QueueAttributeName,
+    },
+    Client,
+};
+use base64::{engine::general_purpose as b64, Engine as _};
+use cargo_lambda_remote::{Partition, RemoteConfig};
+use reqwest::Url;
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
+use tokio_graceful_shutdown::SubsystemHandle;
+use tracing::{debug, error, info};
+
+use super::{batch_item_failures, invoke_function};
+
+/// Maximum number of messages that SQS returns in each receive request.
+pub(super) const MAX_BATCH_SIZE: u16 = 10;
+/// Maximum time that SQS keeps the receive request open waiting for messages.
+const WAIT_TIME_SECONDS: i32 = 20;
+/// Time to wait before polling the queue again after an error.
+const ERROR_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Poll messages from an SQS queue, and invoke the function with them like an event source mapping.
+/// Messages are deleted from the queue when the function processes them successfully.
+pub(super) async fn poll_queue(
+    subsys: SubsystemHandle,
+    function_name: String,
+    queue_url: String,
+    remote_config: RemoteConfig,
+    batch_size: u16,
+    cmd_tx: Sender<Action>,
+) -> Result<(), ServerError> {
+    // The queue URL has the queue's region, and the endpoint of local services like LocalStack.
+    let endpoint = QueueEndpoint::new(&queue_url);
+    let config = RemoteConfig {
+        region: endpoint.region.or(remote_config.region),
+        endpoint_url: remote_config.endpoint_url.or(endpoint.endpoint_url),
+        ..remote_config
+    };
+    let sdk_config = config.sdk_config(None).await?;
+    let client = Client::new(&sdk_config);
+
+    let region = sdk_config
+        .region()
+        .map(|r| r.to_string())
+        .unwrap_or_default();
+    let queue_arn = queue_arn(&client, &queue_url, &region).await;
+
+    info!(queue = %queue_url, function = %function_name, "polling messages from SQS queue");
+
+    loop {
+        let received = tokio::select! {
+            res = client
+                .receive_message()
+                .queue_url(&queue_url)
+                .max_number_of_messages(batch_size as i32)
+                .wait_time_seconds(WAIT_TIME_SECONDS)
+                .message_system_attribute_names(MessageSystemAttributeName::All)
+                .message_attribute_names("All")
+                .send() => res,
+            _ = subsys.on_shutdown_requested() => return Ok(()),
+        };
+
+        let messages = match received {
+            Ok(output) => output.messages.unwrap_or_default(),
+            Err(error) => {
+                error!(queue = %queue_url, error = %aws_sdk_sqs::error::DisplayErrorContext(&error), "failed to receive messages from SQS queue");
+                tokio::select! {
+                    _ = tokio::time::sleep(ERROR_BACKOFF) => continue,
+                    _ = subsys.on_shutdown_requested() => return Ok(()),
+                }
+            }
+        };
+
+        if messages.is_empty() {
+            continue;
+        }
+
+        debug!(queue = %queue_url, messages = messages.len(), "invoking function with SQS messages");
+        let event = sqs_event(&messages, &queue_arn, &region);
+
+        let failures = match invoke_function(&cmd_tx, &function_name, &event).await {
+            Ok((status, body)) if status.is_success() => batch_item_failures(&body),
+            Ok((status, _)) => {
+                error!(queue = %queue_url, %status, "the function failed to process the SQS messages, they'll be available again after the visibility timeout");
+                continue;
+            }
+            Err(error) => {
+                error!(queue = %queue_url, ?error, "failed to invoke the function with SQS messages");
+                continue;
+            }
+        };
+
+        delete_messages(&client, &queue_url, &messages, &failures).await;
+    }
+}
+
+/// Delete the messages that the function processed successfully,
+/// the failed ones become visible again after the visibility timeout, like in Lambda.
+async fn delete_messages(
+    client: &Client,
+    queue_url: &str,
+    messages: &[Message],
+    failures: &[String],
+) {
+    let mut entries = Vec::with_capacity(messages.len());
+
+    for (i, message) in messages.iter().enumerate() {
+        let (Some(id), Some(receipt_handle)) = (message.message_id(), message.receipt_handle())
+        else {
+            continue;
+        };
+
+        if failures.iter().any(|f| f == id) {
+            continue;
+        }
+
+        let entry = DeleteMessageBatchRequestEntry::builder()
+            .id(i.to_string())
+            .receipt_handle(receipt_handle)
+            .build();
+
+        match entry {
+            Ok(entry) => entries.push(entry),
+            Err(error) => error!(%error, "failed to build request to delete SQS message"),
+        }
+    }
+
+    if entries.is_empty() {
+        return;
+    }
+
+    let result = client
+        .delete_message_batch()
+        .queue_url(queue_url)
+        .set_entries(Some(entries))
+        .send()
+        .await;
+
+    match result {
+        Ok(output) if !output.failed().is_empty() => {
+            error!(queue = %queue_url, failed = ?output.failed(), "failed to delete some SQS messages")
+        }
+        Ok(_) => {}
+        Err(error) => {
+            error!(queue = %queue_url, error = %aws_sdk_sqs::error::DisplayErrorContext(&error), "failed to delete SQS messages")
+        }
+    }
+}
+
+async fn queue_arn(client: &Client, queue_url: &str, region: &str) -> String {
+    let attributes = client
+        .get_queue_attributes()
+        .queue_url(queue_url)
+        .attribute_names(QueueAttributeName::QueueArn)
+        .send()
+        .await;
+
+    let arn = attributes.ok().and_then(|output| {
+        output
+            .attributes()
+            .and_then(|attrs| attrs.get(&QueueAttributeName::QueueArn))
+            .cloned()
+    });
+
+    arn.unwrap_or_else(|| default_queue_arn(queue_url, region))
+}
+
+/// Build the queue ARN from its URL: `https://sqs.<region>.amazonaws.com/<account>/<name>`.
+fn default_queue_arn(queue_url: &str, region: &str) -> String {
+    let mut segments = queue_url.trim_end_matches('/').rsplit('/');
+    let name = segments.next().unwrap_or_default();
+    let account = segments.next().unwrap_or_default();
+    let partition = Partition::from_region(region);
+    format!("arn:{partition}:sqs:{region}:{account}:{name}")
+}
+
+/// AWS configuration derived from the queue URL.
+/// Queues in AWS include the region in the hostname,
+/// any other hostname is a custom endpoint, like LocalStack.
+#[derive(Debug, Default, PartialEq)]
+struct QueueEndpoint {
+    region: Option<String>,
+    endpoint_url: Option<String>,
+}
+
+impl QueueEndpoint {
+    fn new(queue_url: &str) -> Self {
+        let Ok(url) = Url::parse(queue_url) else {
+            return QueueEndpoint::default();
+        };
+        let Some(host) = url.host_str() else {
+            return QueueEndpoint::default();
+        };
+
+        let domain = host
+            .strip_suffix(".amazonaws.com")
+            .or_else(|| host.strip_suffix(".amazonaws.com.cn"));
+        if let Some(domain) = domain {
+            let region = domain
+                .strip_prefix("sqs.")
+                .or_else(|| domain.strip_suffix(".queue"))
+                .map(String::from);
+            return QueueEndpoint {
+                region,
+                endpoint_url: None,
+            };
+        }
+
+        QueueEndpoint {
+            region: None,
+            endpoint_url: Some(url.origin().ascii_serialization()),
+        }
+    }
+}
+
+fn sqs_event(messages: &[Message], queue_arn: &str, region: &str) -> Value {
+    let records = messages
+        .iter()
+        .map(|message| {
+            let attributes = message
+                .attributes()
+                .map(|attrs| {
+                    attrs
+                        .iter()
+                        .map(|(k, v)| (k.as_str().to_string(), json!(v)))
+                        .collect::<Map<_, _>>()
+                })
+                .unwrap_or_default();
+
+            let message_attributes = message
+                .message_attributes()
+                .map(|attrs| {
+                    attrs
+                        .iter()
+                        .map(|(k, v)| (k.clone(), message_attribute(v)))
+                        .collect::<Map<_, _>>()
+                })
+                .unwrap_or_default();
+
+            json!({
+                "messageId": message.message_id(),
+                "receiptHandle": message.receipt_handle(),
+                "body": message.body(),
+                "attributes": attributes,
+                "messageAttributes": message_attributes,
+                "md5OfBody": message.md5_of_body(),
+                "eventSource": "aws:sqs",
+                "eventSourceARN": queue_arn,
+                "awsRegion": region,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({ "Records": records })
+}
+
+fn message_attribute(value: &MessageAttributeValue) -> Value {
+    let mut attribute = json!({
+        "stringListValues": [],
+        "binaryListValues": [],
+        "dataType": value.data_type(),
+    });
+
+    if let Some(s) = value.string_value() {
+        attribute["stringValue"] = json!(s);
+    }
+    if let Some(b) = value.binary_value() {
+        attribute["binaryValue"] = json!(b64::STANDARD.encode(b.as_ref()));
+    }
+
+    attribute
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_endpoint() {
+        let endpoint =
+            QueueEndpoint::new("https://sqs.eu-west-1.amazonaws.com/123456789012/my-queue");
+        assert_eq!(Some("eu-west-1".into()), endpoint.region);
+        assert_eq!(None, endpoint.endpoint_url);
+
+        let endpoint = QueueEndpoint::new("https://us-east-2.queue.amazonaws.com/123456789012/q");
+        assert_eq!(Some("us-east-2".into()), endpoint.region);
+
+        let endpoint =
+            QueueEndpoint::new("https://sqs.cn-north-1.amazonaws.com.cn/123456789012/my-queue");
+        assert_eq!(Some("cn-north-1".into()), endpoint.region);
+        assert_eq!(None, endpoint.endpoint_url);
+
+        let endpoint = QueueEndpoint::new("http://localhost:4566/000000000000/my-queue");
+        assert_eq!(None, endpoint.region);
+        assert_eq!(Some("http://localhost:4566".into()), endpoint.endpoint_url);
+
+        assert_eq!(QueueEndpoint::default(), QueueEndpoint::new("my-queue"));
+    }
+
+    #[test]
+    fn test_default_queue_arn() {
+        assert_eq!(
+            "arn:aws:sqs:us-east-1:123456789012:my-queue",
+            default_queue_arn(
+                "https://sqs.us-east-1.amazonaws.com/123456789012/my-queue",
+                "us-east-1"
+            )
+        );
+        assert_eq!(
+            "arn:aws-us-gov:sqs:us-gov-west-1:123456789012:my-queue",
+            default_queue_arn(
+                "https://sqs.us-gov-west-1.amazonaws.com/123456789012/my-queue",
+                "us-gov-west-1"
+            )
+        );
+        assert_eq!(
+            "arn:aws-cn:sqs:cn-north-1:123456789012:my-queue",
+            default_queue_arn(
+                "https://sqs.cn-north-1.amazonaws.com.cn/123456789012/my-queue",
+                "cn-north-1"
+            )
+        );
+    }
+
+    #[test]
+    fn test_sqs_event() {
+        let message = Message::builder()
+            .message_id("id-1")
+            .receipt_handle("handle-1")
+            .body("hello")
+            .message_attributes(
+                "foo",
+                MessageAttributeValue::builder()
+                    .data_type("String")
+                    .string_value("bar")
+                    .build()
+                    .unwrap(),
+            )
+            .build();
+
+        let event = sqs_event(&[message], "arn:aws:sqs:us-east-1:123:q", "us-east-1");
+        let record = &event["Records"][0];
+        assert_eq!("id-1", record["messageId"]);
+        assert_eq!("handle-1", record["receiptHandle"]);
+        assert_eq!("hello", record["body"]);
+        assert_eq!("aws:sqs", record["eventSource"]);
+        assert_eq!("arn:aws:sqs:us-east-1:123:q", record["eventSourceARN"]);
+        assert_eq!("bar", record["messageAttributes"]["foo"]["stringValue"]);
+        assert_eq!("String", record["messageAttributes"]["foo"]["dataType"]);
+    }
+}
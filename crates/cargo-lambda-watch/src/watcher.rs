@@ -1,19 +1,33 @@
-use crate::{error::ServerError, requests::NextEvent, state::ExtensionCache};
+use crate::{
+    debugger::DebugConfig,
+    error::ServerError,
+    logs::{capture_output, function_prefix, OutputOptions, OutputTail},
+    requests::{NextEvent, ProcessStatus},
+    state::{crash_backoff, RuntimeState},
+};
 use cargo_lambda_metadata::{
     cargo::load_metadata,
     config::{load_config_without_cli_flags, ConfigOptions},
+    env::EnvOptions,
 };
 // use cargo_lambda_metadata::cargo::function_environment_metadata;
-use ignore::create_filter;
+use ignore::{create_filter, WatchedFilesFilterer};
 use ignore_files::IgnoreFile;
-use std::{collections::HashMap, convert::Infallible, path::PathBuf, sync::Arc, time::Duration};
-use tracing::{debug, error, trace};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    fs::{File, OpenOptions},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tracing::{debug, error, info, trace, warn};
 use watchexec::{
-    action::{Action, Outcome, PreSpawn},
+    action::{Action, Outcome, PostSpawn, PreSpawn},
     command::Command,
     config::{InitConfig, RuntimeConfig},
     error::RuntimeError,
-    event::{Event, Priority, ProcessEnd},
+    event::{Event, Priority, ProcessEnd, Tag},
     handler::SyncFnHandler,
     signal::source::MainSignal,
     ErrorHook, Watchexec,
@@ -21,6 +35,9 @@ use watchexec::{
 
 pub(crate) mod ignore;
 
+/// How long a reload waits for the invocations in flight to complete before stopping the function.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Clone, Debug, Default)]
 pub(crate) struct WatcherConfig {
     pub runtime_api: String,
@@ -29,10 +46,30 @@ pub(crate) struct WatcherConfig {
     pub base: PathBuf,
     pub manifest_path: PathBuf,
     pub ignore_files: Vec<IgnoreFile>,
+    pub ignore_globs: Vec<String>,
+    pub watch_paths: Vec<PathBuf>,
     pub ignore_changes: bool,
     pub only_lambda_apis: bool,
     pub env: HashMap<String, String>,
+    pub env_options: EnvOptions,
+    pub env_files: Vec<PathBuf>,
     pub wait: bool,
+    pub build_command: Option<Command>,
+    pub extensions: Vec<PathBuf>,
+    pub debug: Option<DebugConfig>,
+    pub prefix_output: bool,
+    pub color: bool,
+    pub logs_dir: Option<PathBuf>,
+    /// Directory with the binaries built by `cargo lambda build`, to run instead of `cargo run`.
+    pub artifact_dir: Option<PathBuf>,
+    /// Binary of the function that's running, when it runs from the artifact directory.
+    pub artifact: Option<PathBuf>,
+    /// Command template to start the functions instead of `cargo run`.
+    pub runner: Option<String>,
+    /// Start every function when the emulator starts, instead of only the default function.
+    pub init_all: bool,
+    /// Maximum number of functions that `init_all` compiles at the same time.
+    pub init_concurrency: usize,
 }
 
 impl WatcherConfig {
@@ -48,10 +85,10 @@ impl WatcherConfig {
 pub(crate) async fn new(
     cmd: Command,
     wc: WatcherConfig,
-    ext_cache: ExtensionCache,
+    state: RuntimeState,
 ) -> Result<Arc<Watchexec>, ServerError> {
     let init = crate::watcher::init();
-    let runtime = crate::watcher::runtime(cmd, wc, ext_cache).await?;
+    let runtime = crate::watcher::runtime(cmd, wc, state).await?;
 
     let wx = Watchexec::new(init, runtime).map_err(ServerError::WatcherError)?;
     wx.send_event(Event::default(), Priority::Urgent)
@@ -61,6 +98,25 @@ pub(crate) async fn new(
     Ok(wx)
 }
 
+/// Ask watchexec to start the function process again, after it exited.
+pub(crate) async fn restart(wx: &Watchexec) -> Result<(), ServerError> {
+    wx.send_event(Event::default(), Priority::Urgent)
+        .await
+        .map_err(ServerError::WatcherError)
+}
+
+/// Ask watchexec to stop the function process and exit.
+pub(crate) async fn terminate(wx: &Watchexec) -> Result<(), ServerError> {
+    let event = Event {
+        tags: vec![Tag::Signal(MainSignal::Terminate)],
+        metadata: Default::default(),
+    };
+
+    wx.send_event(event, Priority::Urgent)
+        .await
+        .map_err(ServerError::WatcherError)
+}
+
 fn init() -> InitConfig {
     let mut config = InitConfig::default();
     config.on_error(SyncFnHandler::from(
@@ -90,25 +146,89 @@ fn init() -> InitConfig {
 async fn runtime(
     cmd: Command,
     wc: WatcherConfig,
-    ext_cache: ExtensionCache,
+    state: RuntimeState,
 ) -> Result<RuntimeConfig, ServerError> {
     let mut config = RuntimeConfig::default();
-
-    config.pathset([wc.base.clone()]);
+    let ext_cache = state.ext_cache.clone();
+
+    let mut watched_files = wc.env_files.clone();
+
+    let (pathset, ignore_changes) = match &wc.artifact {
+        // Prebuilt binaries don't change with the source code, only when they are built again.
+        Some(artifact) => {
+            watched_files.push(artifact.clone());
+            let dir = artifact.parent().unwrap_or(artifact).to_path_buf();
+            let paths = std::iter::once(dir)
+                .chain(wc.env_files.iter().cloned())
+                .collect::<Vec<_>>();
+            (paths, true)
+        }
+        // Environment files outside the project are watched too, so changes in them reload the function.
+        None => {
+            let env_files = wc
+                .env_files
+                .iter()
+                .filter(|f| !f.starts_with(&wc.base))
+                .cloned();
+            let paths = std::iter::once(wc.base.clone())
+                .chain(wc.watch_paths.iter().cloned())
+                .chain(env_files)
+                .collect::<Vec<_>>();
+            (paths, wc.ignore_changes)
+        }
+    };
+    config.pathset(pathset);
     config.commands(vec![cmd]);
 
-    config.filterer(create_filter(&wc.base, &wc.ignore_files, wc.ignore_changes).await?);
+    let filter =
+        create_filter(&wc.base, &wc.ignore_files, &wc.ignore_globs, ignore_changes).await?;
+    if wc.ignore_changes || watched_files.is_empty() {
+        config.filterer(filter);
+    } else {
+        config.filterer(Arc::new(WatchedFilesFilterer {
+            files: watched_files,
+            inner: filter,
+        }));
+    }
 
     config.action_throttle(Duration::from_secs(3));
 
+    if state.lifecycle.is_enabled() {
+        // Run the function in its own process group, so it can be frozen between invocations.
+        config.command_grouped(true);
+
+        let lifecycle = state.lifecycle.clone();
+        let name = wc.name.clone();
+        config.on_post_spawn(move |postspawn: PostSpawn| {
+            let lifecycle = lifecycle.clone();
+            let name = name.clone();
+            async move {
+                lifecycle.spawned(&name, postspawn.id).await;
+                Ok::<(), Infallible>(())
+            }
+        });
+    }
+
+    let lifecycle = state.lifecycle.clone();
+    let drains = state.drains.clone();
+    let processes = state.processes.clone();
+    let crashes = state.crashes.clone();
+    let req_cache = state.req_cache.clone();
+    let notifications = state.notifications.clone();
+    let stderr_tail = OutputTail::default();
+    let action_stderr_tail = stderr_tail.clone();
+    let name = wc.name.clone();
+    let watched_env_files = wc.env_files.clone();
+    let artifact = wc.artifact.clone();
     config.on_action(move |action: Action| {
         let signals: Vec<MainSignal> = action.events.iter().flat_map(|e| e.signals()).collect();
-        let has_paths = action
+        let paths: Vec<PathBuf> = action
             .events
             .iter()
             .flat_map(|e| e.paths())
-            .next()
-            .is_some();
+            .map(|(path, _)| path.to_path_buf())
+            .collect();
+        let has_paths = !paths.is_empty();
 
         let empty_event = action
             .events
@@ -126,7 +246,24 @@ async fn runtime(
         );
 
         let ext_cache = ext_cache.clone();
+        let lifecycle = lifecycle.clone();
+        let drains = drains.clone();
+        let processes = processes.clone();
+        let crashes = crashes.clone();
+        let req_cache = req_cache.clone();
+        let notifications = notifications.clone();
+        let stderr_tail = action_stderr_tail.clone();
+        let name = name.clone();
+        let reload_reason = reload_reason(&paths, &watched_env_files).or_else(|| {
+            let artifact = artifact.as_ref()?;
+            paths
+                .contains(artifact)
+                .then(|| format!("function binary {} changed", artifact.display()))
+        });
         async move {
+            // Frozen processes can't handle signals, thaw them before watchexec sends any.
+            lifecycle.thaw(&name).await;
+
             if signals.contains(&MainSignal::Terminate) {
                 action.outcome(Outcome::both(Outcome::Stop, Outcome::Exit));
                 return Ok(());
@@ -150,6 +287,16 @@ async fn runtime(
 
                 let completion = action.events.iter().flat_map(|e| e.completions()).next();
                 if let Some(status) = completion {
+                    // The process exited with an error before it asked for its first invocation.
+                    let init_failed = matches!(status, Some(ProcessEnd::ExitError(_)))
+                        && processes.get(&name).await == ProcessStatus::Compiling;
+
+                    let process_status = match status {
+                        Some(ProcessEnd::Success) | None => ProcessStatus::NotStarted,
+                        _ => ProcessStatus::Crashed,
+                    };
+                    processes.set(&name, process_status).await;
+
                     match status {
                         Some(ProcessEnd::ExitError(sig)) => {
                             error!(code = ?sig, "command exited");
@@ -166,11 +313,37 @@ async fn runtime(
                         _ => {}
                     };
 
+                    if process_status == ProcessStatus::Crashed && !init_failed {
+                        notifications.error(&name, None, "function process exited with an error");
+                    }
+
+                    if init_failed {
+                        let failure = crashes.failed(&name, stderr_tail.lines()).await;
+                        error!(function = ?name, failures = failure.failures, backoff = ?crash_backoff(failure.failures), "function failed to initialize, it will start again with the next invocation");
+                        notifications.error(&name, None, "function failed to initialize");
+
+                        for req in req_cache.take_pending(&name).await {
+                            failure.respond(req);
+                        }
+                    }
+
                     action.outcome(Outcome::DoNothing);
                     return Ok(());
                 }
             }
 
+            if let Some(reason) = reload_reason {
+                info!(function = ?name, "{reason}, reloading function");
+            }
+
+            // Let the current process finish the invocations that it's handling before replacing it.
+            if has_paths {
+                let pending = drains.drain(&name, DRAIN_TIMEOUT).await;
+                if pending > 0 {
+                    warn!(function = ?name, pending, timeout = ?DRAIN_TIMEOUT, "invocations didn't complete before the reload, they will fail");
+                }
+            }
+
             if !empty_event {
                 let event = NextEvent::shutdown("recompiling function");
                 ext_cache.send_event(event).await?;
@@ -182,26 +355,88 @@ async fn runtime(
         }
     });
 
+    // Environment of the last process started, to explain what changed in the next reload.
+    let last_env: Arc<std::sync::Mutex<Option<HashMap<String, String>>>> = Arc::default();
     config.on_pre_spawn(move |prespawn: PreSpawn| {
         let name = wc.name.clone();
         let runtime_api = wc.runtime_api.clone();
         let manifest_path = wc.manifest_path.clone();
         let bin_name = wc.bin_name.clone();
         let base_env = wc.env.clone();
+        let env_options = wc.env_options.clone();
+        let last_env = last_env.clone();
+        let build_command = wc.build_command.clone();
+        let prefix = wc
+            .prefix_output
+            .then(|| function_prefix(&wc.name, wc.color));
+        let logs_dir = wc.logs_dir.clone();
+        let color = wc.color;
+        let stderr_tail = stderr_tail.clone();
+        let state = state.clone();
 
         async move {
+            state.cold_starts.reset(&name).await;
+            state.reloads.reset(&name).await;
+            state.drains.resume(&name).await;
+            state.processes.set(&name, ProcessStatus::Compiling).await;
+            state.notifications.building(&name);
+            state.crashes.starting(&name).await;
+            stderr_tail.clear();
+
+            if let Some(build_command) = build_command {
+                build_function(&name, &build_command).await;
+            }
+            state.telemetry.init_started(&name).await;
+
             trace!("loading watch environment metadata");
 
-            let new_env = reload_env(&manifest_path, &bin_name);
+            let package_env = reload_env(&manifest_path, &bin_name).unwrap_or(base_env);
+            let env = match env_options.lambda_environment(&package_env) {
+                Ok(env) => env,
+                Err(e) => {
+                    error!("failed to reload environment file: {}", e);
+                    package_env
+                }
+            };
+
+            if let Ok(mut last_env) = last_env.lock() {
+                if let Some(previous) = last_env.as_ref() {
+                    let changed = changed_variables(previous, &env);
+                    if !changed.is_empty() {
+                        info!(function = ?name, variables = ?changed, "starting function with a new environment");
+                    }
+                }
+                *last_env = Some(env.clone());
+            }
+
+            // The environment of the function takes precedence over the credentials injected with `--aws-env`.
+            let aws_env = state.aws_env.env().await;
 
             if let Some(mut command) = prespawn.command().await {
                 command
                     .env("AWS_LAMBDA_FUNCTION_VERSION", "1")
                     .env("AWS_LAMBDA_FUNCTION_MEMORY_SIZE", "4096")
-                    .envs(base_env)
-                    .envs(new_env)
+                    .envs(aws_env)
+                    .envs(env)
                     .env("AWS_LAMBDA_RUNTIME_API", &runtime_api)
                     .env("AWS_LAMBDA_FUNCTION_NAME", &name);
+
+                let mut options = OutputOptions {
+                    prefix,
+                    log_file: logs_dir.and_then(|dir| open_log_file(&dir, &name)),
+                    ..Default::default()
+                };
+                if state.logs.has_subscribers().await {
+                    options.caches.push(state.logs.clone());
+                }
+                if state.telemetry.events.has_subscribers().await {
+                    options.caches.push(state.telemetry.events.clone());
+                }
+                // Cargo doesn't print colors when its output is piped, unless it's forced.
+                if color && std::env::var_os("CARGO_TERM_COLOR").is_none() {
+                    command.env("CARGO_TERM_COLOR", "always");
+                }
+                capture_function_output(&mut command, options, stderr_tail);
             }
 
             Ok::<(), Infallible>(())
@@ -211,12 +446,106 @@ async fn runtime(
     Ok(config)
 }
 
-fn reload_env(manifest_path: &PathBuf, bin_name: &Option<String>) -> HashMap<String, String> {
+/// Pipe the function's stdout and stderr through the emulator,
+/// to add prefixes to the output, write it to a log file, and
+/// send it to the extensions subscribed to the Logs and Telemetry APIs.
+/// Only processes started after an extension subscribes are captured.
+/// The last lines of stderr are kept in `stderr_tail`, to report why the process failed to start.
+fn capture_function_output(
+    command: &mut tokio::process::Command,
+    options: OutputOptions,
+    stderr_tail: OutputTail,
+) {
+    let pipes = os_pipe::pipe().and_then(|(out_reader, out_writer)| {
+        let (err_reader, err_writer) = os_pipe::pipe()?;
+        Ok((out_reader, out_writer, err_reader, err_writer))
+    });
+
+    match pipes {
+        Ok((out_reader, out_writer, err_reader, err_writer)) => {
+            command.stdout(out_writer).stderr(err_writer);
+            capture_output(out_reader, std::io::stdout(), options.clone());
+            let options = OutputOptions {
+                tail: Some(stderr_tail),
+                ..options
+            };
+            capture_output(err_reader, std::io::stderr(), options);
+        }
+        Err(error) => error!(?error, "failed to capture the function output"),
+    }
+}
+
+/// Open the log file of a function in append mode, so the output of every reload is kept.
+fn open_log_file(dir: &Path, name: &str) -> Option<Arc<std::sync::Mutex<File>>> {
+    let path = dir.join(format!("{name}.log"));
+    let file = std::fs::create_dir_all(dir)
+        .and_then(|_| OpenOptions::new().create(true).append(true).open(&path));
+
+    match file {
+        Ok(file) => Some(Arc::new(std::sync::Mutex::new(file))),
+        Err(error) => {
+            error!(?path, ?error, "failed to open the function log file");
+            None
+        }
+    }
+}
+
+/// Compile the function before it starts, so the init phase only measures the function's initialization.
+/// Compilation errors are ignored here, `cargo run` reports them again when it starts.
+async fn build_function(name: &str, build_command: &Command) {
+    let Command::Exec { prog, args } = build_command else {
+        return;
+    };
+
+    debug!(function = ?name, ?build_command, "compiling function before starting it");
+    let status = tokio::process::Command::new(prog).args(args).status().await;
+
+    if let Err(error) = status {
+        error!(?error, function = ?name, "failed to compile function");
+    }
+}
+
+/// Explain why the function reloads when the changes include environment files or manifests.
+fn reload_reason(paths: &[PathBuf], env_files: &[PathBuf]) -> Option<String> {
+    if let Some(path) = paths.iter().find(|p| env_files.contains(p)) {
+        return Some(format!("environment file {} changed", path.display()));
+    }
+
+    paths
+        .iter()
+        .find(|p| p.file_name().is_some_and(|n| n == "Cargo.toml"))
+        .map(|path| format!("manifest {} changed", path.display()))
+}
+
+/// Names of the variables that were added, removed, or modified between two environments.
+fn changed_variables(
+    previous: &HashMap<String, String>,
+    current: &HashMap<String, String>,
+) -> Vec<String> {
+    let mut changed: Vec<String> = current
+        .iter()
+        .filter(|(k, v)| previous.get(*k) != Some(*v))
+        .map(|(k, _)| k.clone())
+        .chain(
+            previous
+                .keys()
+                .filter(|k| !current.contains_key(*k))
+                .cloned(),
+        )
+        .collect();
+    changed.sort();
+    changed
+}
+
+fn reload_env(
+    manifest_path: &PathBuf,
+    bin_name: &Option<String>,
+) -> Option<HashMap<String, String>> {
     let metadata = match load_metadata(manifest_path) {
         Ok(metadata) => metadata,
         Err(e) => {
             error!("failed to reload metadata: {}", e);
-            return HashMap::new();
+            return None;
         }
     };
 
@@ -228,15 +557,59 @@ fn reload_env(manifest_path: &PathBuf, bin_name: &Option<String>) -> HashMap<Str
         Ok(config) => config,
         Err(e) => {
             error!("failed to reload config: {}", e);
-            return HashMap::new();
+            return None;
         }
     };
 
     match config.watch.lambda_environment(&config.env) {
-        Ok(env) => env,
+        Ok(env) => Some(env),
         Err(e) => {
             error!("failed to reload environment: {}", e);
-            HashMap::new()
+            None
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reload_reason() {
+        let env_files = vec![PathBuf::from("/project/.env")];
+
+        let reason = reload_reason(&[PathBuf::from("/project/.env")], &env_files);
+        assert_eq!(
+            Some("environment file /project/.env changed".into()),
+            reason
+        );
+
+        let reason = reload_reason(&[PathBuf::from("/project/Cargo.toml")], &env_files);
+        assert_eq!(Some("manifest /project/Cargo.toml changed".into()), reason);
+
+        assert_eq!(
+            None,
+            reload_reason(&[PathBuf::from("/project/src/main.rs")], &env_files)
+        );
+    }
+
+    #[test]
+    fn test_changed_variables() {
+        let previous = HashMap::from([
+            ("FOO".to_string(), "1".to_string()),
+            ("BAR".to_string(), "1".to_string()),
+            ("BAZ".to_string(), "1".to_string()),
+        ]);
+        let current = HashMap::from([
+            ("FOO".to_string(), "1".to_string()),
+            ("BAR".to_string(), "2".to_string()),
+            ("QUX".to_string(), "1".to_string()),
+        ]);
+
+        assert_eq!(
+            vec!["BAR", "BAZ", "QUX"],
+            changed_variables(&previous, &current)
+        );
+        assert!(changed_variables(&previous, &previous).is_empty());
+    }
+}
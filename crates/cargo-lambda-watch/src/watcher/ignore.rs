@@ -1,4 +1,8 @@
-use std::{collections::HashSet, path::Path, sync::Arc};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use ignore::Match;
 use ignore_files::{IgnoreFile, IgnoreFilter};
@@ -51,6 +55,7 @@ pub(crate) async fn discover_files(base: &Path) -> Vec<ignore_files::IgnoreFile>
 pub(crate) async fn create_filter(
     base: &Path,
     files: &[IgnoreFile],
+    ignore_globs: &[String],
     ignore_changes: bool,
 ) -> Result<Arc<IgnoreFilterer>, ServerError> {
     trace!(?files, "creating watcher ignore filterer");
@@ -72,6 +77,17 @@ pub(crate) async fn create_filter(
         .map_err(ServerError::InvalidIgnoreFiles)?;
     filters.push(filter);
 
+    // Globs from the watch configuration apply to every watched path,
+    // including paths outside the project's directory.
+    if !ignore_globs.is_empty() {
+        let globs = ignore_globs.iter().map(String::as_str).collect::<Vec<_>>();
+        let mut filter = IgnoreFilter::empty(base);
+        filter
+            .add_globs(&globs, None)
+            .map_err(ServerError::InvalidIgnoreFiles)?;
+        filters.push(filter);
+    }
+
     for file in files {
         let base = file
             .applies_in
@@ -151,6 +167,30 @@ impl Filterer for IgnoreFilterer {
     }
 }
 
+/// A Watchexec [`Filterer`] that always accepts changes in specific files,
+/// even if they are ignored by the ignore files, like `.env` files usually are,
+/// or binaries in the target directory.
+/// Any other event is checked with the inner filterer.
+#[derive(Clone, Debug)]
+pub struct WatchedFilesFilterer {
+    pub files: Vec<PathBuf>,
+    pub inner: Arc<IgnoreFilterer>,
+}
+
+impl Filterer for WatchedFilesFilterer {
+    fn check_event(&self, event: &Event, priority: Priority) -> Result<bool, RuntimeError> {
+        if event
+            .paths()
+            .any(|(path, _)| self.files.iter().any(|f| f == path))
+        {
+            trace!("watched file changed (pass)");
+            return Ok(true);
+        }
+
+        self.inner.check_event(event, priority)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{io::Write, path::PathBuf};
@@ -211,9 +251,37 @@ mod tests {
         assert!(!filterer.check_event(&event, Priority::Normal).unwrap());
     }
 
+    #[test]
+    fn test_watched_files_filterer() {
+        let mut filter = IgnoreFilter::empty(Path::new("."));
+        filter
+            .add_globs(&["**/*"], Some(&PathBuf::from(".")))
+            .unwrap();
+        let filterer = WatchedFilesFilterer {
+            files: vec![PathBuf::from("./.env")],
+            inner: Arc::new(IgnoreFilterer(vec![filter])),
+        };
+
+        let event = |path: &str| Event {
+            tags: vec![Tag::Path {
+                path: path.into(),
+                file_type: Some(FileType::File),
+            }],
+            ..Default::default()
+        };
+        assert!(filterer
+            .check_event(&event("./.env"), Priority::Normal)
+            .unwrap());
+        assert!(!filterer
+            .check_event(&event("./src/main.rs"), Priority::Normal)
+            .unwrap());
+    }
+
     #[tokio::test]
     async fn test_create_filter_with_default_target_dir() {
-        let filter = create_filter(Path::new("."), &[], false).await.unwrap();
+        let filter = create_filter(Path::new("."), &[], &[], false)
+            .await
+            .unwrap();
         assert_eq!(filter.0.len(), 1);
 
         let event = Event {
@@ -226,6 +294,32 @@ mod tests {
         assert!(!filter.check_event(&event, Priority::Normal).unwrap());
     }
 
+    #[tokio::test]
+    async fn test_create_filter_with_ignore_globs() {
+        let globs = vec!["*.md".to_string()];
+        let filter = create_filter(Path::new("."), &[], &globs, false)
+            .await
+            .unwrap();
+        assert_eq!(filter.0.len(), 2);
+
+        let event = |path: &str| Event {
+            tags: vec![Tag::Path {
+                path: path.into(),
+                file_type: Some(FileType::File),
+            }],
+            ..Default::default()
+        };
+        assert!(!filter
+            .check_event(&event("./README.md"), Priority::Normal)
+            .unwrap());
+        assert!(!filter
+            .check_event(&event("../shared-lib/CHANGELOG.md"), Priority::Normal)
+            .unwrap());
+        assert!(filter
+            .check_event(&event("./templates/index.html"), Priority::Normal)
+            .unwrap());
+    }
+
     #[tokio::test]
     async fn test_create_filter_with_ignore_files() {
         let mut tempfile = tempfile::NamedTempFile::new().unwrap();
@@ -237,7 +331,7 @@ mod tests {
             applies_to: None,
         };
 
-        let filter = create_filter(Path::new("."), &[ignore_file], false)
+        let filter = create_filter(Path::new("."), &[ignore_file], &[], false)
             .await
             .unwrap();
         assert_eq!(filter.0.len(), 2);
@@ -69,12 +69,29 @@ function sidebar() {
             collapsible: true,
             items: [
                 { text: 'Supported commands', link: '/commands/introduction' },
+                { text: 'cargo lambda bench', link: '/commands/bench' },
                 { text: 'cargo lambda build', link: '/commands/build' },
+                { text: 'cargo lambda completions', link: '/commands/completions' },
+                { text: 'cargo lambda config', link: '/commands/config' },
+                { text: 'cargo lambda delete', link: '/commands/delete' },
                 { text: 'cargo lambda deploy', link: '/commands/deploy' },
+                { text: 'cargo lambda doctor', link: '/commands/doctor' },
+                { text: 'cargo lambda env', link: '/commands/env' },
+                { text: 'cargo lambda info', link: '/commands/info' },
                 { text: 'cargo lambda init', link: '/commands/init' },
                 { text: 'cargo lambda invoke', link: '/commands/invoke' },
+                { text: 'cargo lambda layers', link: '/commands/layers' },
+                { text: 'cargo lambda list', link: '/commands/list' },
+                { text: 'cargo lambda logs', link: '/commands/logs' },
+                { text: 'cargo lambda manpages', link: '/commands/manpages' },
                 { text: 'cargo lambda new', link: '/commands/new' },
+                { text: 'cargo lambda promote', link: '/commands/promote' },
+                { text: 'cargo lambda role', link: '/commands/role' },
+                { text: 'cargo lambda rollback', link: '/commands/rollback' },
+                { text: 'cargo lambda self', link: '/commands/self' },
                 { text: 'cargo lambda system', link: '/commands/system' },
+                { text: 'cargo lambda template', link: '/commands/template' },
+                { text: 'cargo lambda test', link: '/commands/test' },
                 { text: 'cargo lambda watch', link: '/commands/watch' },
             ]
         }